@@ -37,7 +37,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let config = ReplicateConfig::new(source, destination)
         .with_chunk_size(10 * 1024 * 1024) // 10MB chunks
-        .with_max_retries(3);
+        .with_max_retries(3)
+        .with_verification(true); // Check checksums and size after transfer
 
     let replicate = CloudReplicate::new(config);
     match replicate.execute().await {
@@ -47,6 +48,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("  - Chunks: {}", stats.chunks_transferred);
             println!("  - Speed: {:.2} MB/s", stats.speed_mbps());
             println!("  - Duration: {:.2}s", stats.elapsed().as_secs_f64());
+            if let Some(report) = &stats.verification {
+                println!(
+                    "  - Verified: {} bytes, {} parts checksummed, {} retried",
+                    report.destination_size, report.parts_verified, report.parts_retried
+                );
+            }
         }
         Err(e) => println!("✗ Replicate failed: {}", e),
     }
@@ -196,7 +203,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             endpoint_url: Some("http://backup-minio:9000".to_string()),
         };
 
-        let config = ReplicateConfig::new(source, destination).with_chunk_size(5 * 1024 * 1024);
+        // Different endpoints stream through memory; move 4 parts at a time
+        let config = ReplicateConfig::new(source, destination)
+            .with_chunk_size(5 * 1024 * 1024)
+            .with_concurrency(4);
 
         // Use builder with custom clients (different secrets)
         let replicate = CloudReplicateBuilder::new()
@@ -251,5 +261,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(e) => println!("✗ DigitalOcean Spaces replicate failed: {}", e),
     }
 
+    // Example 6: Nightly backup of a whole folder
+    println!("\n=== Example 6: Prefix Replicate (whole folder) ===");
+    let source = CloudSource {
+        provider: CloudProvider::S3,
+        bucket: "my-source-bucket".to_string(),
+        key: "exports/2024-12/".to_string(),
+        region: Some("us-east-1".to_string()),
+        endpoint_url: None,
+    };
+
+    let destination = CloudDestination {
+        provider: CloudProvider::S3,
+        bucket: "my-backup-bucket".to_string(),
+        key: "nightly/exports/2024-12/".to_string(),
+        region: Some("us-east-1".to_string()),
+        endpoint_url: None,
+    };
+
+    let config = ReplicateConfig::new(source, destination)
+        .with_include("**/*.xlsx")
+        .with_exclude("tmp/**");
+
+    let replicate = CloudReplicate::new(config);
+    match replicate.execute_prefix().await {
+        Ok(manifest) => {
+            println!("✓ Prefix replicate finished!");
+            println!(
+                "  - Copied: {}, skipped: {}, failed: {}",
+                manifest.copied(),
+                manifest.skipped(),
+                manifest.failed()
+            );
+            println!("  - Transferred: {} bytes", manifest.bytes_transferred());
+            print!("{}", manifest.to_csv());
+        }
+        Err(e) => println!("✗ Prefix replicate failed: {}", e),
+    }
+
     Ok(())
 }
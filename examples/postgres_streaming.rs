@@ -12,6 +12,7 @@
 
 use excelstream::types::CellValue;
 use excelstream::writer::ExcelWriter;
+use excelstream::FlushPolicy;
 use postgres::{Client, NoTls};
 use std::time::{Duration, Instant};
 
@@ -78,8 +79,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut writer = ExcelWriter::new(output_file)?;
 
     // Configure for optimal memory usage
-    writer.set_flush_interval(500); // Flush every 500 rows (lower = more frequent flushing = lower peak memory)
-    writer.set_max_buffer_size(512 * 1024); // Reduced from 1MB to 512KB to force more frequent flushes
+    writer.set_flush_policy(FlushPolicy::new(512 * 1024)); // Flush every 512KB of rows (lower = lower peak memory)
 
     // Write header for e_invoice data
     writer.write_header([
@@ -12,6 +12,7 @@
 use deadpool_postgres::{Config, Pool, Runtime};
 use excelstream::types::CellValue;
 use excelstream::writer::ExcelWriter;
+use excelstream::FlushPolicy;
 use std::time::Instant;
 use tokio_postgres::NoTls;
 
@@ -90,8 +91,7 @@ async fn export_table(
     let mut writer = ExcelWriter::new(output_file)?;
 
     // Configure for optimal performance
-    writer.set_flush_interval(1000);
-    writer.set_max_buffer_size(1024 * 1024);
+    writer.set_flush_policy(FlushPolicy::new(1024 * 1024));
 
     if rows.is_empty() {
         writer.save()?;
@@ -144,8 +144,7 @@ async fn export_multiple_tables(pool: &Pool) -> Result<(), Box<dyn std::error::E
     let mut writer = ExcelWriter::new(output_file)?;
 
     // Configure for optimal performance
-    writer.set_flush_interval(1000);
-    writer.set_max_buffer_size(1024 * 1024);
+    writer.set_flush_policy(FlushPolicy::new(1024 * 1024));
 
     // Define queries for different sheets
     let queries = [
@@ -0,0 +1,26 @@
+//! Feed arbitrary bytes to the hardened reader entry point
+//!
+//! Run with `cargo +nightly fuzz run parse_untrusted` from the repository
+//! root. Any panic is a bug: `parse_untrusted` must turn every malformed
+//! file into an error. Small limits keep each run fast.
+#![no_main]
+
+use excelstream::streaming_reader::ReadLimits;
+use excelstream::untrusted::{parse_untrusted, UntrustedLimits};
+use libfuzzer_sys::fuzz_target;
+use std::io::Write;
+
+fuzz_target!(|data: &[u8]| {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(data).unwrap();
+    let limits = UntrustedLimits {
+        read: ReadLimits {
+            max_entry_size: 16 * 1024 * 1024,
+            max_row_bytes: 1024 * 1024,
+            ..ReadLimits::default()
+        },
+        max_sheets: 16,
+        max_cells: 100_000,
+    };
+    let _ = parse_untrusted(file.path(), limits);
+});
@@ -0,0 +1,628 @@
+//! DEFLATE decoder that reports where each block starts
+//!
+//! General-purpose decoders hide block boundaries. The splice path needs
+//! them: any block can be cut off and replaced by new blocks without touching
+//! the bits before it, so appending rows only rewrites the sheet's last
+//! blocks instead of recompressing the whole sheet. Decoding can also start
+//! at a known block boundary, given the window of output before it, which is
+//! how [`tail`](crate::tail) reads only what was appended.
+
+use crate::error::{ExcelError, Result};
+use std::io::Read;
+
+/// Receives the decoded stream
+pub(crate) trait InflateSink {
+    /// A block starts at `bit_offset` in the compressed stream; all output
+    /// of earlier blocks has been passed to `data` already
+    fn block_start(&mut self, bit_offset: u64) -> Result<()>;
+
+    /// The block that just started is a stored block
+    fn stored_block(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Next piece of decoded output
+    fn data(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+/// Most output a back-reference can reach
+pub(crate) const WINDOW: usize = 32 * 1024;
+const FAST_BITS: u32 = 10;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn corrupt(what: &str) -> ExcelError {
+    ExcelError::ZipError(format!("Corrupt deflate stream: {}", what))
+}
+
+/// Decode a raw DEFLATE stream, feeding output and block starts to `sink`
+///
+/// Returns the number of decoded bytes.
+pub(super) fn inflate<R: Read, S: InflateSink>(input: R, sink: &mut S) -> Result<u64> {
+    inflate_from(input, 0, &[], sink)
+}
+
+/// Decode a raw DEFLATE stream from a block that starts `skip_bits` (0-7)
+/// into `input`, where `window` is the output before it (up to [`WINDOW`]
+/// bytes, not passed to `sink` again)
+///
+/// Bit offsets given to `sink` count from the start of `input`. Returns the
+/// number of bytes decoded after the window.
+pub(crate) fn inflate_from<R: Read, S: InflateSink>(
+    input: R,
+    skip_bits: u32,
+    window: &[u8],
+    sink: &mut S,
+) -> Result<u64> {
+    let window = &window[window.len().saturating_sub(WINDOW)..];
+    let mut out = Vec::with_capacity(4 * WINDOW);
+    out.extend_from_slice(window);
+    let mut inflater = Inflater {
+        bits: BitReader::new(input),
+        flushed: out.len(),
+        out,
+        total: 0,
+    };
+    inflater.bits.take(skip_bits)?;
+    inflater.run(sink)?;
+    Ok(inflater.total)
+}
+
+struct Inflater<R> {
+    bits: BitReader<R>,
+    out: Vec<u8>,   // Recent output: the back-reference window plus unflushed bytes
+    flushed: usize, // out[..flushed] has been passed to the sink
+    total: u64,
+}
+
+impl<R: Read> Inflater<R> {
+    fn run<S: InflateSink>(&mut self, sink: &mut S) -> Result<()> {
+        loop {
+            self.flush(sink)?;
+            sink.block_start(self.bits.bit_offset())?;
+
+            let last = self.bits.take(1)? == 1;
+            match self.bits.take(2)? {
+                0 => {
+                    sink.stored_block()?;
+                    self.stored_block(sink)?
+                }
+                1 => {
+                    let (lit, dist) = fixed_tables();
+                    self.huffman_block(sink, &lit, &dist)?
+                }
+                2 => {
+                    let (lit, dist) = self.dynamic_tables()?;
+                    self.huffman_block(sink, &lit, &dist)?
+                }
+                _ => return Err(corrupt("invalid block type")),
+            }
+
+            if last {
+                return self.flush(sink);
+            }
+        }
+    }
+
+    // Pass unflushed output to the sink, keeping one window for back-references
+    fn flush<S: InflateSink>(&mut self, sink: &mut S) -> Result<()> {
+        if self.flushed < self.out.len() {
+            sink.data(&self.out[self.flushed..])?;
+            self.total += (self.out.len() - self.flushed) as u64;
+            self.flushed = self.out.len();
+        }
+        if self.out.len() > 3 * WINDOW {
+            self.out.drain(..self.out.len() - WINDOW);
+            self.flushed = self.out.len();
+        }
+        Ok(())
+    }
+
+    fn stored_block<S: InflateSink>(&mut self, sink: &mut S) -> Result<()> {
+        self.bits.align_to_byte();
+        let len = self.bits.take(16)? as usize;
+        let nlen = self.bits.take(16)? as usize;
+        if len != !nlen & 0xFFFF {
+            return Err(corrupt("stored block length mismatch"));
+        }
+        let start = self.out.len();
+        self.out.resize(start + len, 0);
+        self.bits.read_bytes(&mut self.out[start..])?;
+        self.flush(sink)
+    }
+
+    fn huffman_block<S: InflateSink>(
+        &mut self,
+        sink: &mut S,
+        lit: &Huffman,
+        dist: &Huffman,
+    ) -> Result<()> {
+        loop {
+            let symbol = lit.decode(&mut self.bits)? as usize;
+            match symbol {
+                0..=255 => self.out.push(symbol as u8),
+                256 => return Ok(()),
+                257..=285 => {
+                    let i = symbol - 257;
+                    let len =
+                        LENGTH_BASE[i] as usize + self.bits.take(LENGTH_EXTRA[i] as u32)? as usize;
+                    let d = dist.decode(&mut self.bits)? as usize;
+                    if d >= 30 {
+                        return Err(corrupt("invalid distance code"));
+                    }
+                    let distance =
+                        DIST_BASE[d] as usize + self.bits.take(DIST_EXTRA[d] as u32)? as usize;
+                    if distance > self.out.len() {
+                        return Err(corrupt("distance before start of output"));
+                    }
+                    let from = self.out.len() - distance;
+                    if distance >= len {
+                        self.out.extend_from_within(from..from + len);
+                    } else {
+                        for k in 0..len {
+                            let byte = self.out[from + k];
+                            self.out.push(byte);
+                        }
+                    }
+                }
+                _ => return Err(corrupt("invalid literal/length code")),
+            }
+            if self.out.len() - self.flushed >= 2 * WINDOW {
+                self.flush(sink)?;
+            }
+        }
+    }
+
+    fn dynamic_tables(&mut self) -> Result<(Huffman, Huffman)> {
+        let hlit = self.bits.take(5)? as usize + 257;
+        let hdist = self.bits.take(5)? as usize + 1;
+        let hclen = self.bits.take(4)? as usize + 4;
+
+        let mut code_lengths = [0u8; 19];
+        for &index in &CODE_LENGTH_ORDER[..hclen] {
+            code_lengths[index] = self.bits.take(3)? as u8;
+        }
+        let code_table = Huffman::new(&code_lengths)?;
+
+        let mut lengths = vec![0u8; hlit + hdist];
+        let mut i = 0;
+        while i < lengths.len() {
+            let symbol = code_table.decode(&mut self.bits)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *lengths[..i]
+                        .last()
+                        .ok_or_else(|| corrupt("repeat with no previous length"))?;
+                    (previous, 3 + self.bits.take(2)? as usize)
+                }
+                17 => (0, 3 + self.bits.take(3)? as usize),
+                18 => (0, 11 + self.bits.take(7)? as usize),
+                _ => return Err(corrupt("invalid code length code")),
+            };
+            if i + repeat > lengths.len() {
+                return Err(corrupt("code lengths overflow"));
+            }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err(corrupt("missing end-of-block code"));
+        }
+
+        Ok((
+            Huffman::new(&lengths[..hlit])?,
+            Huffman::new(&lengths[hlit..])?,
+        ))
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lit = [0u8; 288];
+    lit[..144].fill(8);
+    lit[144..256].fill(9);
+    lit[256..280].fill(7);
+    lit[280..].fill(8);
+    // Both tables are complete, so construction can't fail
+    (
+        Huffman::new(&lit).expect("fixed literal table"),
+        Huffman::new(&[5u8; 30]).expect("fixed distance table"),
+    )
+}
+
+// Canonical Huffman decoder: a lookup table for codes up to FAST_BITS long,
+// then the bit-at-a-time canonical walk for longer ones
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+    fast: Vec<u32>, // (symbol << 4) | length, 0 when the code is longer
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(corrupt("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        let mut fast = vec![0u32; 1 << FAST_BITS];
+        let mut code: u32 = 0;
+        let mut index = 0;
+        for len in 1..=FAST_BITS {
+            for _ in 0..counts[len as usize] {
+                let reversed = code.reverse_bits() >> (32 - len);
+                let entry = ((symbols[index] as u32) << 4) | len;
+                let mut fill = reversed;
+                while fill < (1 << FAST_BITS) {
+                    fast[fill as usize] = entry;
+                    fill += 1 << len;
+                }
+                code += 1;
+                index += 1;
+            }
+            code <<= 1;
+        }
+
+        Ok(Huffman {
+            counts,
+            symbols,
+            fast,
+        })
+    }
+
+    fn decode<R: Read>(&self, bits: &mut BitReader<R>) -> Result<u16> {
+        bits.refill()?;
+        let entry = self.fast[(bits.peek() & ((1 << FAST_BITS) - 1)) as usize];
+        if entry != 0 {
+            bits.consume(entry & 15)?;
+            return Ok((entry >> 4) as u16);
+        }
+
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.take(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("invalid Huffman code"))
+    }
+}
+
+// LSB-first bit reader that tracks its position in the stream
+struct BitReader<R> {
+    input: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    bytes_read: u64, // Bytes moved from `input` into `buf` so far
+    acc: u64,
+    count: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    fn new(input: R) -> Self {
+        BitReader {
+            input,
+            buf: vec![0; 64 * 1024].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            bytes_read: 0,
+            acc: 0,
+            count: 0,
+        }
+    }
+
+    fn bit_offset(&self) -> u64 {
+        (self.bytes_read - (self.len - self.pos) as u64) * 8 - self.count as u64
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        if self.pos == self.len {
+            self.len = self.input.read(&mut self.buf)?;
+            self.pos = 0;
+            self.bytes_read += self.len as u64;
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+        self.pos += 1;
+        Ok(Some(self.buf[self.pos - 1]))
+    }
+
+    // Load as many whole bytes as fit; fewer near the end of the stream
+    fn refill(&mut self) -> Result<()> {
+        while self.count <= 56 {
+            match self.next_byte()? {
+                Some(byte) => {
+                    self.acc |= (byte as u64) << self.count;
+                    self.count += 8;
+                }
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> u64 {
+        self.acc
+    }
+
+    fn consume(&mut self, n: u32) -> Result<()> {
+        if n > self.count {
+            return Err(corrupt("unexpected end of stream"));
+        }
+        self.acc >>= n;
+        self.count -= n;
+        Ok(())
+    }
+
+    fn take(&mut self, n: u32) -> Result<u64> {
+        if n == 0 {
+            return Ok(0);
+        }
+        if self.count < n {
+            self.refill()?;
+        }
+        let value = self.acc & ((1u64 << n) - 1);
+        self.consume(n)?;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        let skip = self.count % 8;
+        self.acc >>= skip;
+        self.count -= skip;
+    }
+
+    // Copy whole bytes after align_to_byte
+    fn read_bytes(&mut self, dest: &mut [u8]) -> Result<()> {
+        let mut filled = 0;
+        while filled < dest.len() && self.count >= 8 {
+            dest[filled] = self.acc as u8;
+            self.acc >>= 8;
+            self.count -= 8;
+            filled += 1;
+        }
+        while filled < dest.len() {
+            if self.pos == self.len {
+                if self.next_byte()?.is_none() {
+                    return Err(corrupt("unexpected end of stream"));
+                }
+                self.pos -= 1;
+            }
+            let n = (self.len - self.pos).min(dest.len() - filled);
+            dest[filled..filled + n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            filled += n;
+        }
+        Ok(())
+    }
+}
+
+/// LSB-first bit writer for emitting stored blocks after a cut point
+pub(super) struct StoredBlockWriter {
+    out: Vec<u8>,
+    acc: u32,
+    count: u32,
+}
+
+impl StoredBlockWriter {
+    /// Continue a stream whose last byte holds `count` valid low bits of `partial`
+    pub(super) fn new(partial: u8, count: u32) -> Self {
+        StoredBlockWriter {
+            out: Vec::new(),
+            acc: (partial as u32) & ((1 << count) - 1),
+            count,
+        }
+    }
+
+    fn put_bits(&mut self, value: u32, n: u32) {
+        self.acc |= value << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write `data` as stored blocks, the last one marked final
+    pub(super) fn finish(mut self, data: &[u8]) -> Vec<u8> {
+        let mut chunks = data.chunks(0xFFFF).peekable();
+        if chunks.peek().is_none() {
+            // Empty final fixed-Huffman block: BFINAL, BTYPE=01, end-of-block
+            self.put_bits(0b011, 3);
+            self.put_bits(0, 7);
+        }
+        while let Some(chunk) = chunks.next() {
+            let last = chunks.peek().is_none();
+            self.put_bits(last as u32, 1);
+            self.put_bits(0, 2);
+            if self.count > 0 {
+                self.put_bits(0, 8 - self.count);
+            }
+            let len = chunk.len() as u32;
+            self.put_bits(len, 16);
+            self.put_bits(!len & 0xFFFF, 16);
+            self.out.extend_from_slice(chunk);
+        }
+        if self.count > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+/// `data` as non-final stored blocks, starting and ending on a byte boundary
+pub(super) fn stored_blocks(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 0xFFFF * 5 + 5);
+    for chunk in data.chunks(0xFFFF) {
+        let len = chunk.len() as u16;
+        out.push(0); // BFINAL = 0, BTYPE = 00, padding
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Collect {
+        output: Vec<u8>,
+        blocks: Vec<(u64, usize)>,
+    }
+
+    impl InflateSink for Collect {
+        fn block_start(&mut self, bit_offset: u64) -> Result<()> {
+            self.blocks.push((bit_offset, self.output.len()));
+            Ok(())
+        }
+
+        fn data(&mut self, bytes: &[u8]) -> Result<()> {
+            self.output.extend_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    fn inflate_all(data: &[u8]) -> Collect {
+        let mut sink = Collect::default();
+        let total = inflate(data, &mut sink).unwrap();
+        assert_eq!(total as usize, sink.output.len());
+        sink
+    }
+
+    // Non-final fixed-Huffman block "abcabcabc": three literals plus a
+    // length-6 distance-3 match. Ends 46 bits into the stream.
+    fn fixed_block(writer: &mut StoredBlockWriter) {
+        let mut put_code = |code: u32, len: u32| {
+            writer.put_bits(code.reverse_bits() >> (32 - len), len);
+        };
+        put_code(0, 1); // BFINAL
+        put_code(0b10, 2); // BTYPE = 01
+        for byte in b"abc" {
+            put_code(0x30 + *byte as u32, 8);
+        }
+        put_code(260 - 256, 7); // length 6
+        put_code(2, 5); // distance 3
+        put_code(0, 7); // end of block
+    }
+
+    #[test]
+    fn test_stored_blocks_round_trip() {
+        let data: Vec<u8> = (0..150_000u32).map(|i| (i % 251) as u8).collect();
+        let stream = StoredBlockWriter::new(0, 0).finish(&data);
+        let sink = inflate_all(&stream);
+        assert_eq!(sink.output, data);
+        assert_eq!(
+            sink.blocks,
+            vec![
+                (0, 0),
+                (8 * (5 + 0xFFFF), 0xFFFF),
+                (16 * (5 + 0xFFFF), 0x1FFFE)
+            ]
+        );
+
+        assert_eq!(
+            inflate_all(&StoredBlockWriter::new(0, 0).finish(b"")).output,
+            b""
+        );
+    }
+
+    #[test]
+    fn test_stored_blocks_after_cut() {
+        let mut writer = StoredBlockWriter::new(0, 0);
+        fixed_block(&mut writer);
+        assert_eq!((writer.out.len(), writer.count), (5, 6));
+        let mut stream = writer.out.clone();
+
+        // Continue mid-byte with stored blocks, as the splice path does
+        stream.extend(StoredBlockWriter::new(writer.acc as u8, writer.count).finish(b"-tail"));
+        let sink = inflate_all(&stream);
+        assert_eq!(sink.output, b"abcabcabc-tail");
+        assert_eq!(sink.blocks, vec![(0, 0), (46, 9)]);
+    }
+
+    #[test]
+    fn test_inflate_from_block() {
+        let mut writer = StoredBlockWriter::new(0, 0);
+        fixed_block(&mut writer);
+        let mut stream = writer.out.clone();
+        stream.extend(StoredBlockWriter::new(writer.acc as u8, writer.count).finish(b"-tail"));
+
+        // Start at the second block, which needs no window
+        let mut sink = Collect::default();
+        let total = inflate_from(&stream[5..], 6, b"abcabcabc", &mut sink).unwrap();
+        assert_eq!((total, &sink.output[..]), (5, &b"-tail"[..]));
+        assert_eq!(sink.blocks, vec![(6, 0)]);
+
+        // A final fixed block copying 3 bytes from distance 3 of the window
+        let mut writer = StoredBlockWriter::new(0, 0);
+        let mut put_code = |code: u32, len: u32| {
+            writer.put_bits(code.reverse_bits() >> (32 - len), len);
+        };
+        put_code(1, 1); // BFINAL
+        put_code(0b10, 2); // BTYPE = 01
+        put_code(257 - 256, 7); // length 3
+        put_code(2, 5); // distance 3
+        put_code(0, 7); // end of block
+        let mut stream = writer.out.clone();
+        stream.push(writer.acc as u8);
+        let mut sink = Collect::default();
+        inflate_from(&stream[..], 0, b"xyz", &mut sink).unwrap();
+        assert_eq!(sink.output, b"xyz");
+    }
+
+    #[test]
+    fn test_rejects_corrupt_stream() {
+        let mut sink = Collect::default();
+        assert!(inflate(&[0b111u8][..], &mut sink).is_err());
+        assert!(inflate(&[0x01, 0x05, 0x00, 0x00, 0x00][..], &mut sink).is_err());
+    }
+}
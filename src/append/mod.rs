@@ -6,9 +6,30 @@
 //! # How It Works
 //!
 //! 1. Parse ZIP central directory to locate sheet XML
-//! 2. Extract last row number from sheet.xml
-//! 3. Append new rows to sheet.xml (streaming)
-//! 4. Update ZIP central directory with modified sheet
+//! 2. Stream sheet.xml once to find the last row number
+//! 3. Decode the sheet up to the DEFLATE block holding `</sheetData>`
+//! 4. Copy every other entry and the sheet's earlier blocks byte-for-byte,
+//!    then write that last block with the new rows as stored blocks
+//! 5. Patch offsets, sizes and CRC in the central directory (ZIP64 included)
+//!
+//! Nothing is recompressed and memory use doesn't grow with the file, so
+//! multi-GB workbooks append as fast as the disk can copy them. Archives
+//! that can't be spliced (a stored or encrypted sheet) are rewritten entry
+//! by entry instead.
+//!
+//! A `<dimension ref>` declared by the sheet is grown to cover the new rows,
+//! so readers relying on it (Excel's used range,
+//! [`row_count_fast`](crate::streaming_reader::StreamingReader::row_count_fast))
+//! stay correct. The blocks holding it are rewritten as stored blocks too.
+//!
+//! Durations are styled `[h]:mm:ss` and percentages `0%` with a cell format
+//! from the workbook's styles part. If it has none yet, one is added, and since that changes a
+//! second entry the workbook is rewritten entry by entry for that save.
+//!
+//! The updated archive is staged in a temp file (next to the workbook, or in
+//! the directory given to [`AppendableExcelWriter::with_temp_dir`]) and only
+//! replaces the original once it is complete, so a failed or interrupted
+//! save never leaves a corrupt workbook or a stray temp file behind.
 //!
 //! # Example
 //!
@@ -36,19 +57,34 @@
 //! - **Append mode**: 0.5-2 seconds (10-100x faster!)
 
 use crate::error::{ExcelError, Result};
-use crate::fast_writer::StreamingZipReader;
-use crate::types::CellValue;
+use crate::fast_writer::styles::{ELAPSED_TIME_NUM_FMT_ID, PERCENT_NUM_FMT_ID};
+use crate::fast_writer::{StreamingZipReader, StreamingZipWriter};
+use crate::staging::{self, StagedFile};
+use crate::streaming_reader::{decode_xml_entities, resolve_part_path, xml_attr, xml_elements};
+use crate::types::{duration_serial, Cell, CellRange, CellValue, FloatFormat, TimeZonePolicy};
+use splice::{MARKERS, MARKER_LEN};
+use std::io::Read;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+pub(crate) mod inflate;
+pub(crate) mod splice;
+
 /// Appendable Excel writer for incremental updates
 ///
 /// This writer modifies existing Excel files by appending new rows
 /// without reading or rewriting the entire file.
 pub struct AppendableExcelWriter {
     file_path: PathBuf,
+    temp_dir: Option<PathBuf>,
     selected_sheet: Option<String>,
+    sheet_xml_path: String,
+    dimension: Option<String>, // The selected sheet's <dimension ref>, if declared
+    cell_formats: CellFormats,
     last_row_number: u32,
-    new_rows: Vec<Vec<String>>,
+    new_rows: Vec<Vec<CellValue>>,
+    float_format: FloatFormat,
+    time_zone: TimeZonePolicy,
 }
 
 impl AppendableExcelWriter {
@@ -76,12 +112,50 @@ impl AppendableExcelWriter {
 
         Ok(Self {
             file_path,
+            temp_dir: None,
             selected_sheet: None,
+            sheet_xml_path: String::new(),
+            dimension: None,
+            cell_formats: CellFormats::default(),
             last_row_number: 0,
             new_rows: Vec::new(),
+            float_format: FloatFormat::default(),
+            time_zone: TimeZonePolicy::default(),
         })
     }
 
+    /// Stage the updated workbook in `dir` instead of next to the original
+    ///
+    /// The staged copy is as large as the whole workbook, so point this at a
+    /// volume with enough room. It is deleted if `save` fails or panics.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::append::AppendableExcelWriter;
+    ///
+    /// let mut writer = AppendableExcelWriter::open("data.xlsx")?.with_temp_dir("/scratch");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Control how appended float cells are written, see
+    /// [`ExcelWriter::set_float_format`](crate::ExcelWriter::set_float_format)
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    /// Control how appended cells with a UTC offset are written, see
+    /// [`ExcelWriter::set_time_zone_policy`](crate::ExcelWriter::set_time_zone_policy)
+    pub fn with_time_zone_policy(mut self, policy: TimeZonePolicy) -> Self {
+        self.time_zone = policy;
+        self
+    }
+
     /// Select which sheet to append to
     ///
     /// # Arguments
@@ -102,21 +176,52 @@ impl AppendableExcelWriter {
         // Open ZIP using internal streaming reader
         let mut reader = StreamingZipReader::open(&self.file_path)?;
 
-        // Find sheet index by reading workbook.xml
+        // Resolve the sheet's part via workbook.xml and its relationships
         let workbook_xml_bytes = reader.read_entry_by_name("xl/workbook.xml")?;
         let workbook_xml = String::from_utf8(workbook_xml_bytes).map_err(|e| {
             ExcelError::InvalidState(format!("Invalid UTF-8 in workbook.xml: {}", e))
         })?;
-        let sheet_id = self.find_sheet_id(&workbook_xml, &sheet_name)?;
+        let rels_xml_bytes = reader.read_entry_by_name("xl/_rels/workbook.xml.rels")?;
+        let rels_xml = String::from_utf8(rels_xml_bytes).map_err(|e| {
+            ExcelError::InvalidState(format!("Invalid UTF-8 in workbook.xml.rels: {}", e))
+        })?;
+        let sheet_xml_path = self.find_sheet_path(&workbook_xml, &rels_xml, &sheet_name)?;
+        let cell_formats = CellFormats::read(&mut reader, &rels_xml);
 
-        // Read sheet XML to find last row number
-        let sheet_xml_path = format!("xl/worksheets/sheet{}.xml", sheet_id);
-        let sheet_xml_bytes = reader.read_entry_by_name(&sheet_xml_path)?;
-        let sheet_xml = String::from_utf8(sheet_xml_bytes)
-            .map_err(|e| ExcelError::InvalidState(format!("Invalid UTF-8 in sheet XML: {}", e)))?;
-        let last_row = self.find_last_row_number(&sheet_xml)?;
+        // Stream the sheet XML to find its dimension and last row number;
+        // each chunk is scanned up to its last '<' so no tag is split
+        // between two scans
+        let mut entry = reader.read_entry_streaming_by_name(&sheet_xml_path)?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut pending = Vec::new();
+        let mut dimension = None;
+        let mut in_head = true; // Before <sheetData>
+        let mut last_row = 0;
+        loop {
+            let n = entry.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            pending.extend_from_slice(&buffer[..n]);
+            let end = memchr::memrchr(b'<', &pending).unwrap_or(pending.len());
+            let xml = String::from_utf8_lossy(&pending[..end]);
+            if in_head {
+                in_head = scan_head(&xml, &mut dimension);
+            }
+            last_row = find_last_row_number(&xml, last_row);
+            pending.drain(..end);
+        }
+        let xml = String::from_utf8_lossy(&pending);
+        if in_head {
+            scan_head(&xml, &mut dimension);
+        }
+        let last_row = find_last_row_number(&xml, last_row);
+        drop(entry);
 
         self.selected_sheet = Some(sheet_name);
+        self.sheet_xml_path = sheet_xml_path;
+        self.dimension = dimension;
+        self.cell_formats = cell_formats;
         self.last_row_number = last_row;
 
         Ok(())
@@ -149,7 +254,10 @@ impl AppendableExcelWriter {
             ));
         }
 
-        let row_values: Vec<String> = row.into_iter().map(|s| s.as_ref().to_string()).collect();
+        let row_values: Vec<CellValue> = row
+            .into_iter()
+            .map(|s| CellValue::String(s.as_ref().to_string()))
+            .collect();
         self.new_rows.push(row_values);
         self.last_row_number += 1;
 
@@ -164,20 +272,7 @@ impl AppendableExcelWriter {
             ));
         }
 
-        let row_values: Vec<String> = cells
-            .iter()
-            .map(|cell| match cell {
-                CellValue::String(s) => s.clone(),
-                CellValue::Int(i) => i.to_string(),
-                CellValue::Float(f) => f.to_string(),
-                CellValue::Bool(b) => b.to_string(),
-                CellValue::Empty => String::new(),
-                CellValue::Formula(f) => f.clone(),
-                _ => String::new(),
-            })
-            .collect();
-
-        self.new_rows.push(row_values);
+        self.new_rows.push(cells.to_vec());
         self.last_row_number += 1;
 
         Ok(())
@@ -192,65 +287,510 @@ impl AppendableExcelWriter {
             return Ok(()); // Nothing to save
         }
 
-        // TODO: Implement ZIP modification
-        // 1. Extract all files from original ZIP
-        // 2. Modify the selected sheet XML
-        // 3. Recreate ZIP with modified sheet
+        let mut added_formats = Vec::new();
+        let rows_xml = self.rows_xml(&mut added_formats);
+        let dimension = self.dimension_ref();
+        if !added_formats.is_empty() {
+            // The styles part changes too, which splicing one entry can't do
+            return self.save_with(|staged| {
+                self.write_rewritten(staged, &rows_xml, dimension.as_deref(), &added_formats)
+            });
+        }
+        let spliced = self.save_with(|staged| {
+            splice::splice_sheet(
+                &self.file_path,
+                &self.sheet_xml_path,
+                rows_xml.as_bytes(),
+                dimension.as_deref(),
+                staged.file()?,
+            )
+        });
+        match spliced {
+            Err(ExcelError::NotSupported(_)) => self.save_with(|staged| {
+                self.write_rewritten(staged, &rows_xml, dimension.as_deref(), &[])
+            }),
+            result => result,
+        }
+    }
 
-        // For now, return an error indicating this is not yet implemented
-        Err(ExcelError::InvalidState(
-            "Incremental append is not yet fully implemented. \
-             This is a complex feature requiring ZIP entry modification.\
-             For now, please use the standard ExcelWriter to recreate the file."
-                .to_string(),
-        ))
+    /// The sheet's `<dimension>` grown to cover the pending rows, or None
+    /// when the sheet declares none or it already covers them
+    fn dimension_ref(&self) -> Option<String> {
+        let declared = self.dimension.as_deref()?;
+        let first_row = self.last_row_number - self.new_rows.len() as u32;
+        let mut used: Option<CellRange> = None;
+        for (offset, row) in self.new_rows.iter().enumerate() {
+            let row_index = first_row + offset as u32;
+            let first = row.iter().position(|v| self.is_written(v));
+            let last = row.iter().rposition(|v| self.is_written(v));
+            if let (Some(first), Some(last)) = (first, last) {
+                let cells = CellRange::new(row_index, first as u32, row_index, last as u32);
+                used = Some(used.map_or(cells, |used| used.union(&cells)));
+            }
+        }
+        let declared = declared.parse::<CellRange>().ok();
+        let grown = match declared {
+            Some(range) => range.union(&used?),
+            None => used?,
+        };
+        (declared != Some(grown)).then(|| grown.to_string())
     }
 
-    // Helper methods
+    /// Write the updated workbook into a fresh staged file and commit it
+    fn save_with(&self, write: impl FnOnce(&StagedFile) -> Result<()>) -> Result<()> {
+        let staged = StagedFile::new(&self.file_path, self.temp_dir.as_deref())?;
+        if let Err(err) = write(&staged) {
+            // Out of space becomes ExcelError::DiskFull; the temp file is removed either way
+            return Err(staging::discard_partial(&self.file_path, Some(staged), err));
+        }
+        staged.commit()
+    }
 
-    fn find_sheet_id(&self, workbook_xml: &str, sheet_name: &str) -> Result<usize> {
-        // Simple XML parsing to find sheet ID
-        // Format: <sheet name="SheetName" sheetId="1" r:id="rId1"/>
-
-        for line in workbook_xml.lines() {
-            if line.contains("<sheet ") && line.contains(&format!("name=\"{}\"", sheet_name)) {
-                // Extract sheetId
-                if let Some(start) = line.find("sheetId=\"") {
-                    let start = start + 9;
-                    if let Some(end) = line[start..].find('"') {
-                        if let Ok(id) = line[start..start + end].parse::<usize>() {
-                            return Ok(id);
-                        }
+    /// Rewrite every entry of the workbook into `staged`, with the new rows
+    /// inserted into the sheet, its dimension set to `dimension` and cell
+    /// formats for `added_formats` added to the styles part
+    fn write_rewritten(
+        &self,
+        staged: &StagedFile,
+        rows_xml: &str,
+        dimension: Option<&str>,
+        added_formats: &[u32],
+    ) -> Result<()> {
+        let mut reader = StreamingZipReader::open(&self.file_path)?;
+        let names: Vec<String> = reader.entries().iter().map(|e| e.name.clone()).collect();
+        let mut zip = StreamingZipWriter::from_writer(staged.file()?)?;
+
+        for name in &names {
+            zip.start_entry(name)?;
+            if *name == self.sheet_xml_path {
+                let entry = reader.read_entry_streaming_by_name(name)?;
+                rewrite_sheet(entry, rows_xml.as_bytes(), dimension, |bytes| {
+                    Ok(zip.write_data(bytes)?)
+                })?;
+            } else if !added_formats.is_empty() && self.cell_formats.part.as_ref() == Some(name) {
+                let styles_xml = reader.read_entry_by_name(name)?;
+                let styles_xml = String::from_utf8(styles_xml).map_err(|e| {
+                    ExcelError::InvalidState(format!("Invalid UTF-8 in {}: {}", name, e))
+                })?;
+                let styles_xml = add_cell_formats(&styles_xml, added_formats)?;
+                zip.write_data(styles_xml.as_bytes())?;
+            } else {
+                // Copy untouched entries through a fixed-size buffer
+                let mut entry = reader.read_entry_streaming_by_name(name)?;
+                let mut buffer = vec![0u8; 64 * 1024];
+                loop {
+                    let n = entry.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
                     }
+                    zip.write_data(&buffer[..n])?;
                 }
             }
         }
+        zip.finish()?;
+        Ok(())
+    }
+
+    // Helper methods
 
-        Err(ExcelError::InvalidState(format!(
-            "Sheet '{}' not found in workbook",
-            sheet_name
-        )))
+    // ZIP path of a sheet's worksheet part. Sheets are matched by name and
+    // followed through their r:id, since sheetId and the file name diverge
+    // once sheets are deleted or reordered.
+    fn find_sheet_path(
+        &self,
+        workbook_xml: &str,
+        rels_xml: &str,
+        sheet_name: &str,
+    ) -> Result<String> {
+        let sheets = xml_elements(workbook_xml, "sheet");
+        let names: Vec<String> = sheets
+            .iter()
+            .filter_map(|el| xml_attr(el, "name").map(decode_xml_entities))
+            .collect();
+
+        let Some(sheet) = sheets.iter().find(|el| {
+            xml_attr(el, "name").map(decode_xml_entities).as_deref() == Some(sheet_name)
+        }) else {
+            return Err(ExcelError::SheetNotFound {
+                sheet: sheet_name.to_string(),
+                available: names.join(", "),
+            });
+        };
+
+        let rid = xml_attr(sheet, "r:id").ok_or_else(|| {
+            ExcelError::InvalidFormat(format!("Sheet '{}' has no r:id", sheet_name))
+        })?;
+        xml_elements(rels_xml, "Relationship")
+            .into_iter()
+            .find(|el| xml_attr(el, "Id") == Some(rid))
+            .and_then(|el| xml_attr(el, "Target"))
+            .map(|target| resolve_part_path("xl", &decode_xml_entities(target)))
+            .ok_or_else(|| {
+                ExcelError::InvalidFormat(format!(
+                    "No relationship '{}' for sheet '{}' in workbook.xml.rels",
+                    rid, sheet_name
+                ))
+            })
     }
 
-    fn find_last_row_number(&self, sheet_xml: &str) -> Result<u32> {
-        let mut last_row = 0u32;
+    /// Whether `value` produces a cell rather than being left blank
+    fn is_written(&self, value: &CellValue) -> bool {
+        !value.is_empty() && self.float_format.replace_non_finite(value) != Some(CellValue::Empty)
+    }
+
+    // ` s="N"` for a cell in number format `num_fmt_id`, empty if the
+    // workbook has no styles part to hold it
+    fn style_attr(&self, num_fmt_id: u32, added_formats: &mut Vec<u32>) -> String {
+        self.cell_formats
+            .index(num_fmt_id, added_formats)
+            .map(|index| format!(" s=\"{}\"", index))
+            .unwrap_or_default()
+    }
 
-        // Find all <row r="N"> tags and get the maximum row number
-        for line in sheet_xml.lines() {
-            if line.contains("<row ") {
-                if let Some(start) = line.find("r=\"") {
-                    let start = start + 3;
-                    if let Some(end) = line[start..].find('"') {
-                        if let Ok(row_num) = line[start..start + end].parse::<u32>() {
-                            last_row = last_row.max(row_num);
+    /// XML for the pending rows, numbered on from the sheet's last row
+    ///
+    /// Number formats the styles part lacks are pushed to `added_formats`.
+    fn rows_xml(&self, added_formats: &mut Vec<u32>) -> String {
+        let first_row = self.last_row_number + 1 - self.new_rows.len() as u32;
+        let mut rows_xml = String::new();
+        for (offset, row) in self.new_rows.iter().enumerate() {
+            let row_num = first_row + offset as u32;
+            rows_xml.push_str(&format!("<row r=\"{}\"", row_num));
+            let used = |v: &CellValue| self.is_written(v);
+            if let (Some(first), Some(last)) =
+                (row.iter().position(used), row.iter().rposition(used))
+            {
+                rows_xml.push_str(&format!(" spans=\"{}:{}\"", first + 1, last + 1));
+            }
+            rows_xml.push('>');
+            for (col, value) in row.iter().enumerate() {
+                let reference = Cell::new(row_num - 1, col as u32, CellValue::Empty).reference();
+                let replaced = self
+                    .time_zone
+                    .replace(value)
+                    .or_else(|| self.float_format.replace_non_finite(value));
+                match replaced.as_ref().unwrap_or(value) {
+                    // Offset dates are replaced per the time zone policy above
+                    CellValue::Empty | CellValue::DateTimeTz(_) => {}
+                    CellValue::Int(i) => {
+                        rows_xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, i))
+                    }
+                    CellValue::Float(f) => rows_xml.push_str(&format!(
+                        "<c r=\"{}\"><v>{}</v></c>",
+                        reference,
+                        self.float_format.format(*f)
+                    )),
+                    CellValue::DateTime(f) => {
+                        rows_xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, f))
+                    }
+                    CellValue::Percent(p) => rows_xml.push_str(&format!(
+                        "<c r=\"{}\"{}><v>{}</v></c>",
+                        reference,
+                        self.style_attr(PERCENT_NUM_FMT_ID, added_formats),
+                        self.float_format.format(p / 100.0)
+                    )),
+                    CellValue::Duration(d) => rows_xml.push_str(&format!(
+                        "<c r=\"{}\"{}><v>{}</v></c>",
+                        reference,
+                        self.style_attr(ELAPSED_TIME_NUM_FMT_ID, added_formats),
+                        self.float_format.format(duration_serial(d))
+                    )),
+                    CellValue::Decimal(d) => {
+                        rows_xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, d))
+                    }
+                    CellValue::Bool(b) => rows_xml.push_str(&format!(
+                        "<c r=\"{}\" t=\"b\"><v>{}</v></c>",
+                        reference,
+                        u8::from(*b)
+                    )),
+                    CellValue::Formula(f) => rows_xml.push_str(&format!(
+                        "<c r=\"{}\"><f>{}</f></c>",
+                        reference,
+                        escape_xml(f.strip_prefix('=').unwrap_or(f))
+                    )),
+                    CellValue::Error(e) if CellValue::error_code(e).is_some() => {
+                        rows_xml.push_str(&format!(
+                            "<c r=\"{}\" t=\"e\"><v>{}</v></c>",
+                            reference,
+                            CellValue::error_code(e).unwrap_or_default()
+                        ))
+                    }
+                    CellValue::String(s) | CellValue::Error(s) => rows_xml.push_str(&format!(
+                        "<c r=\"{}\" t=\"inlineStr\"><is><t>{}</t></is></c>",
+                        reference,
+                        escape_xml(s)
+                    )),
+                    CellValue::RichText(text) => {
+                        rows_xml.push_str(&format!("<c r=\"{}\" t=\"inlineStr\"><is>", reference));
+                        for run in text.runs() {
+                            let mut properties = Vec::new();
+                            run.push_properties(&mut properties);
+                            rows_xml.push_str(&format!(
+                                "<r>{}<t xml:space=\"preserve\">{}</t></r>",
+                                String::from_utf8_lossy(&properties),
+                                escape_xml(&run.text)
+                            ));
                         }
+                        rows_xml.push_str("</is></c>");
                     }
                 }
             }
+            rows_xml.push_str("</row>");
+        }
+
+        rows_xml
+    }
+}
+
+/// Cell formats (`<cellXfs>`) of a workbook's styles part
+#[derive(Debug, Clone, Default)]
+struct CellFormats {
+    part: Option<String>, // ZIP path of the styles part
+    // numFmtId of each cell format, None for those that also set a font,
+    // fill or border
+    num_fmt_ids: Vec<Option<u32>>,
+}
+
+impl CellFormats {
+    /// Read the styles part named in workbook.xml.rels; a workbook without
+    /// one (or with an unreadable one) gets no styled cells
+    fn read(reader: &mut StreamingZipReader, rels_xml: &str) -> Self {
+        let Some(part) = xml_elements(rels_xml, "Relationship")
+            .into_iter()
+            .find(|el| xml_attr(el, "Type").is_some_and(|t| t.ends_with("/styles")))
+            .and_then(|el| xml_attr(el, "Target"))
+            .map(|target| resolve_part_path("xl", &decode_xml_entities(target)))
+        else {
+            return CellFormats::default();
+        };
+        let Ok(styles_xml) = reader.read_entry_by_name(&part) else {
+            return CellFormats::default();
+        };
+        let styles_xml = String::from_utf8_lossy(&styles_xml);
+        let plain = |xf: &str, attr| xml_attr(xf, attr).is_none_or(|id| id == "0");
+        let num_fmt_ids = xml_elements(&styles_xml, "cellXfs")
+            .first()
+            .map(|xfs| {
+                xml_elements(xfs, "xf")
+                    .into_iter()
+                    .map(|xf| {
+                        (plain(xf, "fontId") && plain(xf, "fillId") && plain(xf, "borderId"))
+                            .then(|| xml_attr(xf, "numFmtId").and_then(|id| id.parse().ok()))
+                            .flatten()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        CellFormats {
+            part: Some(part),
+            num_fmt_ids,
         }
+    }
 
-        Ok(last_row)
+    /// Index of a plain cell format in number format `num_fmt_id`, pushing
+    /// it to `added` when the styles part has none; None without a styles
+    /// part to add it to
+    fn index(&self, num_fmt_id: u32, added: &mut Vec<u32>) -> Option<u32> {
+        if self.num_fmt_ids.is_empty() {
+            return None;
+        }
+        if let Some(index) = self
+            .num_fmt_ids
+            .iter()
+            .position(|&id| id == Some(num_fmt_id))
+        {
+            return Some(index as u32);
+        }
+        let pos = match added.iter().position(|&id| id == num_fmt_id) {
+            Some(pos) => pos,
+            None => {
+                added.push(num_fmt_id);
+                added.len() - 1
+            }
+        };
+        Some((self.num_fmt_ids.len() + pos) as u32)
+    }
+}
+
+/// `styles_xml` with plain cell formats in `num_fmt_ids` added to the end
+/// of its `<cellXfs>`
+fn add_cell_formats(styles_xml: &str, num_fmt_ids: &[u32]) -> Result<String> {
+    let missing = || ExcelError::InvalidFormat("Styles part has no <cellXfs>".to_string());
+    let end = styles_xml.find("</cellXfs>").ok_or_else(missing)?;
+    let start = styles_xml[..end].rfind("<cellXfs").ok_or_else(missing)?;
+    let tag_end = start + styles_xml[start..end].find('>').ok_or_else(missing)? + 1;
+    let tag = &styles_xml[start..tag_end];
+    let tag = match xml_attr(tag, "count").and_then(|c| c.parse::<usize>().ok()) {
+        Some(count) => tag.replacen(
+            &format!("count=\"{}\"", count),
+            &format!("count=\"{}\"", count + num_fmt_ids.len()),
+            1,
+        ),
+        None => tag.to_string(),
+    };
+
+    let mut out = String::with_capacity(styles_xml.len() + 96 * num_fmt_ids.len());
+    out.push_str(&styles_xml[..start]);
+    out.push_str(&tag);
+    out.push_str(&styles_xml[tag_end..end]);
+    for id in num_fmt_ids {
+        out.push_str(&format!(
+            "<xf numFmtId=\"{}\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\"/>",
+            id
+        ));
     }
+    out.push_str(&styles_xml[end..]);
+    Ok(out)
+}
+
+/// Insert `rows_xml` at the end of the `<sheetData>` in `sheet_xml`
+fn insert_rows(sheet_xml: &[u8], rows_xml: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(sheet_xml.len() + rows_xml.len() + 12);
+    if let Some(pos) = memchr::memmem::rfind(sheet_xml, b"</sheetData>") {
+        out.extend_from_slice(&sheet_xml[..pos]);
+        out.extend_from_slice(rows_xml);
+        out.extend_from_slice(&sheet_xml[pos..]);
+    } else if let Some(pos) = memchr::memmem::find(sheet_xml, b"<sheetData/>") {
+        out.extend_from_slice(&sheet_xml[..pos]);
+        out.extend_from_slice(b"<sheetData>");
+        out.extend_from_slice(rows_xml);
+        out.extend_from_slice(b"</sheetData>");
+        out.extend_from_slice(&sheet_xml[pos + b"<sheetData/>".len()..]);
+    } else {
+        return Err(ExcelError::InvalidState(
+            "Sheet XML has no <sheetData> element".to_string(),
+        ));
+    }
+    Ok(out)
+}
+
+/// Stream `sheet` to `write` with `rows_xml` inserted at the end of its
+/// `<sheetData>` and its `<dimension>` (if any) set to `dimension`
+///
+/// Only the sheet up to its `<dimension>` element and a marker's length of
+/// lookahead are held in memory.
+fn rewrite_sheet<R: Read>(
+    mut sheet: R,
+    rows_xml: &[u8],
+    dimension: Option<&str>,
+    mut write: impl FnMut(&[u8]) -> Result<()>,
+) -> Result<()> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut pending = Vec::new();
+    let mut dimension = dimension;
+    let mut inserted = false;
+    loop {
+        let n = sheet.read(&mut buffer)?;
+        pending.extend_from_slice(&buffer[..n]);
+        if let Some(reference) = dimension {
+            let head_done = memchr::memmem::find(&pending, b"<sheetData").is_some();
+            if !head_done && dimension_range(&pending).is_none() && n > 0 {
+                continue; // The element may still be incomplete
+            }
+            set_dimension(&mut pending, reference);
+            dimension = None;
+        }
+        if !inserted {
+            let marker = MARKERS
+                .iter()
+                .filter_map(|m| memchr::memmem::find(&pending, m).map(|pos| (pos, *m)))
+                .min();
+            if let Some((pos, marker)) = marker {
+                write(&pending[..pos])?;
+                if marker == b"<sheetData/>" {
+                    write(b"<sheetData>")?;
+                }
+                write(rows_xml)?;
+                write(b"</sheetData>")?;
+                pending.drain(..pos + MARKER_LEN);
+                inserted = true;
+            }
+        }
+        if n == 0 {
+            break;
+        }
+        // Hold back what could be the start of a marker
+        let keep = if inserted { 0 } else { MARKER_LEN - 1 };
+        let flush = pending.len().saturating_sub(keep);
+        write(&pending[..flush])?;
+        pending.drain(..flush);
+    }
+    if !inserted {
+        return Err(ExcelError::InvalidState(
+            "Sheet XML has no <sheetData> element".to_string(),
+        ));
+    }
+    write(&pending)
+}
+
+/// Pick up the `<dimension ref>` from sheet XML before `<sheetData>`;
+/// returns whether `<sheetData>` is still to come
+fn scan_head(xml: &str, dimension: &mut Option<String>) -> bool {
+    let data = xml.find("<sheetData");
+    let head = &xml[..data.unwrap_or(xml.len())];
+    if let Some(reference) = xml_elements(head, "dimension")
+        .first()
+        .and_then(|el| xml_attr(el, "ref"))
+    {
+        *dimension = Some(reference.to_string());
+    }
+    data.is_none()
+}
+
+/// Byte range of the `<dimension>` element before `<sheetData>`, if any
+fn dimension_range(xml: &[u8]) -> Option<Range<usize>> {
+    let head = &xml[..memchr::memmem::find(xml, b"<sheetData").unwrap_or(xml.len())];
+    let mut pos = 0;
+    loop {
+        let start = pos + memchr::memmem::find(&head[pos..], b"<dimension")?;
+        pos = start + b"<dimension".len();
+        if !head
+            .get(pos)
+            .is_some_and(|&b| b == b'/' || b == b'>' || b.is_ascii_whitespace())
+        {
+            continue; // A longer name sharing the prefix
+        }
+        let tag_end = pos + memchr::memchr(b'>', &head[pos..])? + 1;
+        if head[tag_end - 2] == b'/' {
+            return Some(start..tag_end);
+        }
+        let close = memchr::memmem::find(&head[tag_end..], b"</dimension>")?;
+        return Some(start..tag_end + close + b"</dimension>".len());
+    }
+}
+
+/// Point the `<dimension>` element of `xml` at `reference`
+fn set_dimension(xml: &mut Vec<u8>, reference: &str) {
+    if let Some(range) = dimension_range(xml) {
+        let element = format!("<dimension ref=\"{}\"/>", reference);
+        xml.splice(range, element.into_bytes());
+    }
+}
+
+/// Highest row number in `sheet_xml`, continuing from `last_row`
+///
+/// Rows without an `r` attribute follow the previous one.
+fn find_last_row_number(sheet_xml: &str, mut last_row: u32) -> u32 {
+    let mut pos = 0;
+    while let Some(start) = sheet_xml[pos..].find("<row") {
+        let start = pos + start;
+        pos = start + 4;
+        if !matches!(sheet_xml.as_bytes().get(pos), Some(b' ' | b'>' | b'/')) {
+            continue; // <rowBreaks> etc.
+        }
+        let row_num = xml_attr(&sheet_xml[start..], "r")
+            .and_then(|r| r.parse::<u32>().ok())
+            .unwrap_or(last_row + 1);
+        last_row = last_row.max(row_num);
+    }
+    last_row
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 #[cfg(test)]
@@ -259,13 +799,6 @@ mod tests {
 
     #[test]
     fn test_find_last_row_number() {
-        let writer = AppendableExcelWriter {
-            file_path: PathBuf::new(),
-            selected_sheet: None,
-            last_row_number: 0,
-            new_rows: Vec::new(),
-        };
-
         let xml = r#"
             <sheetData>
                 <row r="1"><c r="A1"><v>Header</v></c></row>
@@ -274,28 +807,284 @@ mod tests {
             </sheetData>
         "#;
 
-        let last_row = writer.find_last_row_number(xml).unwrap();
+        let last_row = find_last_row_number(xml, 0);
         assert_eq!(last_row, 5);
     }
 
     #[test]
-    fn test_find_sheet_id() {
+    fn test_find_last_row_number_single_line() {
+        let xml = r#"<sheetData><row r="1" spans="1:2"><c r="A1"/></row><row r="9"><c r="A9"/></row><row><c/></row></sheetData>"#;
+        assert_eq!(find_last_row_number(xml, 0), 10);
+
+        // Chunks scanned in turn carry the row count over
+        assert_eq!(find_last_row_number("<row><c/></row>", 10), 11);
+    }
+
+    #[test]
+    fn test_find_sheet_path() {
         let writer = AppendableExcelWriter {
             file_path: PathBuf::new(),
+            temp_dir: None,
             selected_sheet: None,
+            sheet_xml_path: String::new(),
+            dimension: None,
+            cell_formats: CellFormats::default(),
             last_row_number: 0,
             new_rows: Vec::new(),
+            float_format: FloatFormat::default(),
+            time_zone: TimeZonePolicy::default(),
         };
 
-        let xml = r#"
+        // Sheets reordered and one deleted: sheetId, r:id and file name all differ
+        let workbook = r#"
             <sheets>
-                <sheet name="Sheet1" sheetId="1" r:id="rId1"/>
-                <sheet name="Sales" sheetId="2" r:id="rId2"/>
-                <sheet name="Data" sheetId="3" r:id="rId3"/>
+                <sheet name="Data" sheetId="3" r:id="rId2"/>
+                <sheet name="R&amp;D" sheetId="1" r:id="rId7"/>
+                <sheet name="Sales" sheetId="2" r:id="rId1"/>
             </sheets>
         "#;
+        let rels = r#"
+            <Relationships>
+                <Relationship Id="rId1" Type="worksheet" Target="worksheets/sheet3.xml"/>
+                <Relationship Id="rId2" Type="worksheet" Target="/xl/worksheets/sheet1.xml"/>
+                <Relationship Id="rId7" Type="worksheet" Target="worksheets/sheet2.xml"/>
+            </Relationships>
+        "#;
+
+        let path = |name| writer.find_sheet_path(workbook, rels, name);
+        assert_eq!(path("Sales").unwrap(), "xl/worksheets/sheet3.xml");
+        assert_eq!(path("Data").unwrap(), "xl/worksheets/sheet1.xml");
+        assert_eq!(path("R&D").unwrap(), "xl/worksheets/sheet2.xml");
+        assert!(matches!(
+            path("Missing"),
+            Err(ExcelError::SheetNotFound { available, .. }) if available == "Data, R&D, Sales"
+        ));
+    }
+
+    #[test]
+    fn test_append_to_reordered_sheet() {
+        let out = tempfile::tempdir().unwrap();
+        let path = out.path().join("reordered.xlsx");
+        {
+            let mut zip = StreamingZipWriter::new(&path).unwrap();
+            let entries = [
+                (
+                    "xl/workbook.xml",
+                    r#"<workbook><sheets><sheet name="Summary" sheetId="4" r:id="rId2"/><sheet name="Log" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+                ),
+                (
+                    "xl/_rels/workbook.xml.rels",
+                    r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet2.xml"/><Relationship Id="rId2" Target="worksheets/sheet1.xml"/></Relationships>"#,
+                ),
+                (
+                    "xl/worksheets/sheet1.xml",
+                    r#"<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData></worksheet>"#,
+                ),
+                (
+                    "xl/worksheets/sheet2.xml",
+                    r#"<worksheet><sheetData><row r="1"><c r="A1"><v>10</v></c></row><row r="2"><c r="A2"><v>20</v></c></row></sheetData></worksheet>"#,
+                ),
+            ];
+            for (name, data) in entries {
+                zip.start_entry(name).unwrap();
+                zip.write_data(data.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        let mut writer = AppendableExcelWriter::open(&path).unwrap();
+        writer.select_sheet("Log").unwrap();
+        writer.append_row_typed(&[CellValue::Int(30)]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        let log: Vec<_> = reader
+            .rows("Log")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .map(|row| (row.index, row.cells))
+            .collect();
+        assert_eq!(
+            log,
+            vec![
+                (0, vec![CellValue::Int(10)]),
+                (1, vec![CellValue::Int(20)]),
+                (2, vec![CellValue::Int(30)]),
+            ]
+        );
+        assert_eq!(reader.rows("Summary").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_save_appends_rows_via_temp_dir() {
+        let out = tempfile::tempdir().unwrap();
+        let scratch = tempfile::tempdir().unwrap();
+        let path = out.path().join("log.xlsx");
+        {
+            let mut writer = crate::writer::ExcelWriter::new(&path).unwrap();
+            writer.write_row(["date", "entry"]).unwrap();
+            writer.write_row(["2024-12-09", "first"]).unwrap();
+            writer.save().unwrap();
+        }
+
+        let mut writer = AppendableExcelWriter::open(&path)
+            .unwrap()
+            .with_temp_dir(scratch.path());
+        writer.select_sheet("Sheet1").unwrap();
+        writer.append_row(["2024-12-10", "a < b"]).unwrap();
+        writer
+            .append_row_typed(&[CellValue::Int(7), CellValue::Bool(true)])
+            .unwrap();
+        writer.save().unwrap();
+        assert_eq!(std::fs::read_dir(scratch.path()).unwrap().count(), 0);
+        assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), 1);
+
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[2][1], CellValue::String("a < b".to_string()));
+        assert_eq!(rows[3], vec![CellValue::Int(7), CellValue::Bool(true)]);
+    }
+
+    #[test]
+    fn test_rewrite_sheet_streams_across_reads() {
+        // One byte per read, so every marker straddles reads
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.0.len().min(buf.len()).min(1);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let rewrite = |sheet: &[u8], dimension| {
+            let mut out = Vec::new();
+            rewrite_sheet(Trickle(sheet), b"<row r=\"3\"/>", dimension, |bytes| {
+                out.extend_from_slice(bytes);
+                Ok(())
+            })
+            .map(|_| String::from_utf8(out).unwrap())
+        };
+        assert_eq!(
+            rewrite(
+                br#"<worksheet><dimension ref="A1:B2"></dimension><sheetData><row r="2"/></sheetData></worksheet>"#,
+                Some("A1:B3")
+            )
+            .unwrap(),
+            r#"<worksheet><dimension ref="A1:B3"/><sheetData><row r="2"/><row r="3"/></sheetData></worksheet>"#
+        );
+        assert_eq!(
+            rewrite(b"<worksheet><sheetData/><cols/></worksheet>", Some("A1")).unwrap(),
+            r#"<worksheet><sheetData><row r="3"/></sheetData><cols/></worksheet>"#
+        );
+        assert!(matches!(
+            rewrite(b"<worksheet/>", None),
+            Err(ExcelError::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn test_append_grows_dimension() {
+        let out = tempfile::tempdir().unwrap();
+        // Deflated sheets are spliced, stored ones rewritten
+        for (name, method) in [
+            ("deflated", s_zip::CompressionMethod::Deflate),
+            ("stored", s_zip::CompressionMethod::Stored),
+        ] {
+            let path = out.path().join(format!("{}.xlsx", name));
+            {
+                let mut zip = StreamingZipWriter::with_method(&path, method, 6).unwrap();
+                let entries = [
+                    (
+                        "xl/workbook.xml",
+                        r#"<workbook><sheets><sheet name="Log" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+                    ),
+                    (
+                        "xl/_rels/workbook.xml.rels",
+                        r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#,
+                    ),
+                    (
+                        "xl/worksheets/sheet1.xml",
+                        r#"<worksheet><dimension ref="A1:B2"/><sheetData><row r="1"><c r="A1"><v>1</v></c></row><row r="2"><c r="B2"><v>2</v></c></row></sheetData></worksheet>"#,
+                    ),
+                ];
+                for (name, data) in entries {
+                    zip.start_entry(name).unwrap();
+                    zip.write_data(data.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+
+            let mut writer = AppendableExcelWriter::open(&path).unwrap();
+            writer.select_sheet("Log").unwrap();
+            writer
+                .append_row_typed(&[CellValue::Empty, CellValue::Int(3), CellValue::Int(4)])
+                .unwrap();
+            writer.save().unwrap();
+
+            let mut reader = StreamingZipReader::open(&path).unwrap();
+            let sheet = reader
+                .read_entry_by_name("xl/worksheets/sheet1.xml")
+                .unwrap();
+            let sheet = String::from_utf8(sheet).unwrap();
+            assert!(sheet.starts_with(r#"<worksheet><dimension ref="A1:C3"/><sheetData>"#));
+
+            // Only cells that get written count towards the range
+            let mut writer = AppendableExcelWriter::open(&path).unwrap();
+            writer.select_sheet("Log").unwrap();
+            writer.append_row_typed(&[CellValue::Int(5)]).unwrap();
+            assert_eq!(writer.dimension_ref(), Some("A1:C4".to_string()));
+            writer.new_rows[0] = vec![CellValue::Empty];
+            assert_eq!(writer.dimension_ref(), None);
+        }
+    }
+
+    #[test]
+    fn test_append_styles_durations_and_percentages() {
+        let out = tempfile::tempdir().unwrap();
+        let path = out.path().join("times.xlsx");
+        {
+            let mut writer = crate::writer::ExcelWriter::new(&path).unwrap();
+            writer.write_row(["task", "time"]).unwrap();
+            writer.save().unwrap();
+        }
+        let read = |name| {
+            let mut reader = StreamingZipReader::open(&path).unwrap();
+            String::from_utf8(reader.read_entry_by_name(name).unwrap()).unwrap()
+        };
+        let xfs = xml_elements(&read("xl/styles.xml"), "xf").len();
+        let percent = crate::types::CellStyle::NumberPercentage.index();
+
+        // The first duration adds a cell format, later ones reuse it
+        for _ in 0..2 {
+            let mut writer = AppendableExcelWriter::open(&path).unwrap();
+            writer.select_sheet("Sheet1").unwrap();
+            writer
+                .append_row_typed(&[
+                    CellValue::String("build".to_string()),
+                    CellValue::Duration(std::time::Duration::from_secs(90 * 60)),
+                    CellValue::Percent(25.0),
+                ])
+                .unwrap();
+            writer.save().unwrap();
+        }
 
-        let id = writer.find_sheet_id(xml, "Sales").unwrap();
-        assert_eq!(id, 2);
+        let styles = read("xl/styles.xml");
+        assert_eq!(xml_elements(&styles, "xf").len(), xfs + 1);
+        assert!(styles.contains(&format!("<cellXfs count=\"{}\">", xfs + 1)));
+        assert!(styles.contains("<xf numFmtId=\"46\""));
+        let sheet = read("xl/worksheets/sheet1.xml");
+        let cell = format!("<c r=\"B2\" s=\"{}\"><v>0.0625</v></c>", xfs);
+        assert!(sheet.contains(&cell));
+        assert!(sheet.contains(&cell.replace("B2", "B3")));
+        // Percentages use the workbook's existing 0% format
+        let cell = format!("<c r=\"C3\" s=\"{}\"><v>0.25</v></c>", percent);
+        assert!(sheet.contains(&cell));
     }
 }
@@ -0,0 +1,964 @@
+//! Append by splicing the archive instead of rebuilding it
+//!
+//! Every entry except the modified sheet is copied byte-for-byte, compressed
+//! data included. The sheet's DEFLATE stream is decoded once to find the
+//! block holding `</sheetData>`; the bits before that block are kept as they
+//! are, and everything from it onward (the block, the new rows and the rest
+//! of the sheet) is written as stored blocks. Appending 100 rows to a 2 GB
+//! workbook therefore costs one decode of the sheet and a file copy, with no
+//! recompression.
+//!
+//! When the sheet's `<dimension>` has to grow, the decoded output up to a
+//! block boundary at least one DEFLATE window past it is written out again
+//! as stored blocks with the new element; later blocks never reach back
+//! across the change, so their bits are kept, shifted to a byte boundary.
+//!
+//! Archive layouts this can't patch in place (stored sheets, encryption,
+//! sizes that outgrow their 32-bit fields) return `ExcelError::NotSupported`
+//! so the caller can fall back to a full rewrite.
+//!
+//! [`relayout`] uses the same byte-for-byte copy to reorder a finished
+//! archive's entries and align their data.
+
+use super::inflate::{inflate, stored_blocks, InflateSink, StoredBlockWriter, WINDOW};
+use super::{dimension_range, insert_rows, set_dimension};
+use crate::error::{ExcelError, Result};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const LOCAL_HEADER_SIG: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIG: u32 = 0x0201_4b50;
+const EOCD_SIG: u32 = 0x0605_4b50;
+const ZIP64_EOCD_SIG: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIG: u32 = 0x0706_4b50;
+
+// Both ways a worksheet can end its cell data; equal length by design
+pub(crate) const MARKERS: [&[u8]; 2] = [b"</sheetData>", b"<sheetData/>"];
+pub(crate) const MARKER_LEN: usize = 12;
+
+// Most decoded output held to rewrite the sheet's <dimension>
+const HEAD_LIMIT: usize = 4 * 1024 * 1024;
+
+fn u16_at(buf: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([buf[pos], buf[pos + 1]])
+}
+
+fn u32_at(buf: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(buf[pos..pos + 4].try_into().unwrap())
+}
+
+fn u64_at(buf: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap())
+}
+
+fn invalid(what: &str) -> ExcelError {
+    ExcelError::ZipError(format!("Invalid ZIP archive: {}", what))
+}
+
+// Where a size or offset lives in a central directory record: the 32-bit
+// field itself, or the ZIP64 extra field when the 32-bit one is 0xFFFFFFFF
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Short(usize),
+    Long(usize),
+}
+
+impl Slot {
+    fn set(self, record: &mut [u8], value: u64) -> Result<()> {
+        match self {
+            Slot::Short(pos) => {
+                let value = u32::try_from(value)
+                    .ok()
+                    .filter(|v| *v != u32::MAX)
+                    .ok_or_else(|| {
+                        ExcelError::NotSupported("splice needs ZIP64 fields".to_string())
+                    })?;
+                record[pos..pos + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            Slot::Long(pos) => record[pos..pos + 8].copy_from_slice(&value.to_le_bytes()),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct CentralEntry {
+    record: Vec<u8>,
+    name: String,
+    flags: u16,
+    method: u16,
+    compressed_size: u64,
+    local_offset: u64,
+    uncompressed_slot: Slot,
+    compressed_slot: Slot,
+    offset_slot: Slot,
+}
+
+#[derive(Debug)]
+struct Layout {
+    entries: Vec<CentralEntry>,
+    cd_offset: u64,
+    eocd: Vec<u8>,               // End of central directory record, comment included
+    zip64_eocd: Option<Vec<u8>>, // ZIP64 end of central directory record
+}
+
+fn read_exact_at(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_layout(file: &mut File) -> Result<Layout> {
+    let file_len = file.metadata()?.len();
+    let tail_len = file_len.min(22 + 0xFFFF);
+    let tail = read_exact_at(file, file_len - tail_len, tail_len as usize)?;
+
+    // The EOCD record is the last one whose comment runs to the end of file
+    let eocd_pos = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&p| {
+            u32_at(&tail, p) == EOCD_SIG && p + 22 + u16_at(&tail, p + 20) as usize == tail.len()
+        })
+        .ok_or_else(|| invalid("end of central directory not found"))?;
+    let eocd = tail[eocd_pos..].to_vec();
+    let eocd_offset = file_len - tail_len + eocd_pos as u64;
+
+    let mut cd_offset = u32_at(&eocd, 16) as u64;
+    let mut cd_size = u32_at(&eocd, 12) as u64;
+    let mut zip64_eocd = None;
+    if eocd_offset >= 20 {
+        let locator = read_exact_at(file, eocd_offset - 20, 20)?;
+        if u32_at(&locator, 0) == ZIP64_LOCATOR_SIG {
+            let record_offset = u64_at(&locator, 8);
+            if record_offset.saturating_add(56) > file_len {
+                return Err(invalid("ZIP64 end of central directory out of bounds"));
+            }
+            let head = read_exact_at(file, record_offset, 56)?;
+            if u32_at(&head, 0) != ZIP64_EOCD_SIG {
+                return Err(invalid("bad ZIP64 end of central directory"));
+            }
+            // Sizes come from the file; check them before allocating
+            let record_len = u64_at(&head, 4).saturating_add(12);
+            if record_len < 56 || record_offset.saturating_add(record_len) > file_len {
+                return Err(invalid("bad ZIP64 end of central directory size"));
+            }
+            let record = read_exact_at(file, record_offset, record_len as usize)?;
+            cd_size = u64_at(&record, 40);
+            cd_offset = u64_at(&record, 48);
+            zip64_eocd = Some(record);
+        }
+    }
+
+    if cd_offset.saturating_add(cd_size) > file_len {
+        return Err(invalid("central directory out of bounds"));
+    }
+    let cd = read_exact_at(file, cd_offset, cd_size as usize)?;
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while pos + 46 <= cd.len() && u32_at(&cd, pos) == CENTRAL_HEADER_SIG {
+        let name_len = u16_at(&cd, pos + 28) as usize;
+        let extra_len = u16_at(&cd, pos + 30) as usize;
+        let comment_len = u16_at(&cd, pos + 32) as usize;
+        let end = pos + 46 + name_len + extra_len + comment_len;
+        if end > cd.len() {
+            return Err(invalid("truncated central directory"));
+        }
+        let record = cd[pos..end].to_vec();
+        entries.push(parse_central_entry(record, name_len, extra_len)?);
+        pos = end;
+    }
+
+    Ok(Layout {
+        entries,
+        cd_offset,
+        eocd,
+        zip64_eocd,
+    })
+}
+
+fn parse_central_entry(record: Vec<u8>, name_len: usize, extra_len: usize) -> Result<CentralEntry> {
+    // ZIP64 extra field values, in order, for each 32-bit field set to 0xFFFFFFFF
+    let mut zip64_pos = None;
+    let mut pos = 46 + name_len;
+    let extra_end = pos + extra_len;
+    while pos + 4 <= extra_end {
+        let size = u16_at(&record, pos + 2) as usize;
+        if u16_at(&record, pos) == 0x0001 {
+            zip64_pos = Some(pos + 4);
+        }
+        pos += 4 + size;
+    }
+
+    let mut next_long = zip64_pos;
+    let mut slot = |short_pos: usize| -> Result<Slot> {
+        if u32_at(&record, short_pos) != u32::MAX {
+            return Ok(Slot::Short(short_pos));
+        }
+        let long = next_long.ok_or_else(|| invalid("missing ZIP64 extra field"))?;
+        next_long = Some(long + 8);
+        Ok(Slot::Long(long))
+    };
+    let uncompressed_slot = slot(24)?;
+    let compressed_slot = slot(20)?;
+    let offset_slot = slot(42)?;
+
+    let read = |slot: Slot| match slot {
+        Slot::Short(p) => u32_at(&record, p) as u64,
+        Slot::Long(p) => u64_at(&record, p),
+    };
+    Ok(CentralEntry {
+        name: String::from_utf8_lossy(&record[46..46 + name_len]).into_owned(),
+        flags: u16_at(&record, 8),
+        method: u16_at(&record, 10),
+        compressed_size: read(compressed_slot),
+        local_offset: read(offset_slot),
+        uncompressed_slot,
+        compressed_slot,
+        offset_slot,
+        record,
+    })
+}
+
+// An entry's local header up to its name, and where its data starts
+fn read_local_header(file: &mut File, entry: &CentralEntry) -> Result<(Vec<u8>, u64)> {
+    let local = read_exact_at(file, entry.local_offset, 30)?;
+    if u32_at(&local, 0) != LOCAL_HEADER_SIG {
+        return Err(invalid("bad local file header"));
+    }
+    let name_len = u16_at(&local, 26) as usize;
+    let data_start = entry.local_offset + 30 + name_len as u64 + u16_at(&local, 28) as u64;
+    Ok((
+        read_exact_at(file, entry.local_offset, 30 + name_len)?,
+        data_start,
+    ))
+}
+
+/// Where an entry's compressed data is in an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EntryData {
+    pub(crate) offset: u64,
+    pub(crate) compressed_size: u64,
+    pub(crate) deflated: bool, // Deflated and not encrypted
+}
+
+/// Locate the data of entry `name`
+pub(crate) fn entry_data(file: &mut File, name: &str) -> Result<EntryData> {
+    let layout = read_layout(file)?;
+    let entry = layout
+        .entries
+        .iter()
+        .find(|e| e.name == name)
+        .ok_or_else(|| ExcelError::ReadError(format!("ZIP entry not found: {}", name)))?;
+    let (_, offset) = read_local_header(file, entry)?;
+    Ok(EntryData {
+        offset,
+        compressed_size: entry.compressed_size,
+        deflated: entry.method == 8 && entry.flags & 0x0001 == 0,
+    })
+}
+
+// A run of decoded output starting at a block boundary
+struct Block {
+    bit_offset: u64,
+    crc: crc32fast::Hasher, // CRC of all output before the block
+    data: Vec<u8>,
+}
+
+/// Finds the last block boundary before the end of `<sheetData>` and keeps
+/// the decoded output from there on
+///
+/// When tracking the dimension, it also keeps the output from the start of
+/// the sheet up to the first block boundary a full window past the
+/// `<dimension>` element; the CRC restarts at that boundary.
+struct TailFinder {
+    crc: crc32fast::Hasher,
+    total: u64,
+    prev: Option<Block>,
+    cur: Option<Block>,
+    found: bool,
+    collecting: bool,             // Still appending decoded output to `head`
+    head: Vec<u8>,                // Decoded output from the start of the sheet
+    dimension_end: Option<usize>, // End of the <dimension> element in `head`
+    head_cut: Option<(u64, u64)>, // Bit offset and output length where `head` stops
+    head_overflow: bool,          // `head` outgrew HEAD_LIMIT
+    stored: Vec<u64>,             // Stored blocks after `head_cut`, by bit offset
+}
+
+impl TailFinder {
+    fn new(track_dimension: bool) -> Self {
+        TailFinder {
+            crc: crc32fast::Hasher::new(),
+            total: 0,
+            prev: None,
+            cur: None,
+            found: false,
+            collecting: track_dimension,
+            head: Vec::new(),
+            dimension_end: None,
+            head_cut: None,
+            head_overflow: false,
+            stored: Vec::new(),
+        }
+    }
+
+    fn stop_collecting(&mut self, keep: bool) {
+        self.collecting = false;
+        if !keep {
+            self.head = Vec::new();
+        }
+    }
+
+    fn contains_marker(&self) -> bool {
+        let Some(cur) = &self.cur else {
+            return false;
+        };
+        let in_cur = |marker: &[u8]| memchr::memmem::find(&cur.data, marker).is_some();
+        if MARKERS.iter().any(|m| in_cur(m)) {
+            return true;
+        }
+        // A marker straddling the two blocks
+        let Some(prev) = &self.prev else {
+            return false;
+        };
+        let mut seam = prev.data[prev.data.len().saturating_sub(MARKER_LEN - 1)..].to_vec();
+        seam.extend_from_slice(&cur.data[..cur.data.len().min(MARKER_LEN - 1)]);
+        MARKERS
+            .iter()
+            .any(|m| memchr::memmem::find(&seam, m).is_some())
+    }
+
+    // The cut point, its CRC state and everything decoded after it. The
+    // cut is at the previous block only when the marker straddles the two.
+    fn finish(self) -> Result<(u64, crc32fast::Hasher, Vec<u8>)> {
+        if !self.found && !self.contains_marker() {
+            return Err(ExcelError::InvalidState(
+                "Sheet XML has no <sheetData> element".to_string(),
+            ));
+        }
+        let cur = self.cur.expect("stream has at least one block");
+        let in_cur = MARKERS
+            .iter()
+            .any(|m| memchr::memmem::find(&cur.data, m).is_some());
+        Ok(match self.prev {
+            Some(mut prev) if !in_cur => {
+                prev.data.extend_from_slice(&cur.data);
+                (prev.bit_offset, prev.crc, prev.data)
+            }
+            _ => (cur.bit_offset, cur.crc, cur.data),
+        })
+    }
+}
+
+impl InflateSink for TailFinder {
+    fn block_start(&mut self, bit_offset: u64) -> Result<()> {
+        if self.found {
+            return Ok(());
+        }
+        if self.contains_marker() {
+            self.found = true;
+            self.stop_collecting(true);
+            return Ok(());
+        }
+        if let Some(end) = self.dimension_end {
+            if self.collecting && self.total >= (end + WINDOW) as u64 {
+                self.head_cut = Some((bit_offset, self.total));
+                self.stop_collecting(true);
+                self.crc = crc32fast::Hasher::new();
+            }
+        }
+
+        // Keep the finished block as `prev` so markers across the boundary
+        // are still seen; tiny blocks are folded into the one before
+        match (self.cur.take(), &mut self.prev) {
+            (Some(cur), Some(prev)) if cur.data.len() < MARKER_LEN => {
+                prev.data.extend_from_slice(&cur.data)
+            }
+            (Some(cur), _) => self.prev = Some(cur),
+            (None, _) => {}
+        }
+        self.cur = Some(Block {
+            bit_offset,
+            crc: self.crc.clone(),
+            data: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn stored_block(&mut self) -> Result<()> {
+        if let (false, Some(_), Some(cur)) = (self.found, self.head_cut, &self.cur) {
+            self.stored.push(cur.bit_offset);
+        }
+        Ok(())
+    }
+
+    fn data(&mut self, bytes: &[u8]) -> Result<()> {
+        self.crc.update(bytes);
+        self.total += bytes.len() as u64;
+        if let Some(cur) = &mut self.cur {
+            cur.data.extend_from_slice(bytes);
+        }
+        if self.collecting {
+            let from = self.head.len().saturating_sub(b"<sheetData".len() - 1);
+            self.head.extend_from_slice(bytes);
+            if self.head.len() > HEAD_LIMIT {
+                self.head_overflow = true;
+                self.stop_collecting(false);
+            } else if self.dimension_end.is_none()
+                && memchr::memmem::find(&self.head[from..], b"<sheetData").is_some()
+            {
+                match dimension_range(&self.head) {
+                    Some(range) => self.dimension_end = Some(range.end),
+                    None => self.stop_collecting(false), // Nothing to rewrite
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `source` to `out` with `rows_xml` inserted at the end of the
+/// `<sheetData>` of entry `sheet_path`, and its `<dimension>` (if it has
+/// one) set to `dimension`
+pub(super) fn splice_sheet(
+    source: &Path,
+    sheet_path: &str,
+    rows_xml: &[u8],
+    dimension: Option<&str>,
+    out: File,
+) -> Result<()> {
+    let mut file = File::open(source)?;
+    let layout = read_layout(&mut file)?;
+
+    let sheet_index = layout
+        .entries
+        .iter()
+        .position(|e| e.name == sheet_path)
+        .ok_or_else(|| ExcelError::ReadError(format!("ZIP entry not found: {}", sheet_path)))?;
+    let sheet = &layout.entries[sheet_index];
+    if sheet.method != 8 || sheet.flags & 0x0001 != 0 {
+        return Err(ExcelError::NotSupported(
+            "splice needs an unencrypted deflated sheet".to_string(),
+        ));
+    }
+
+    let (local_head, data_start) = read_local_header(&mut file, sheet)?;
+
+    // Decode the sheet once to find the cut point
+    file.seek(SeekFrom::Start(data_start))?;
+    let mut finder = TailFinder::new(dimension.is_some());
+    inflate((&mut file).take(sheet.compressed_size), &mut finder)?;
+    if dimension.is_some() && finder.head_overflow {
+        return Err(ExcelError::NotSupported(
+            "splice can't reach the sheet's <dimension>".to_string(),
+        ));
+    }
+    let dimension = dimension.filter(|_| finder.dimension_end.is_some());
+    let head = std::mem::take(&mut finder.head);
+    let stored = std::mem::take(&mut finder.stored);
+    let head_cut = finder.head_cut;
+    let kept_bytes = finder.total;
+    let (cut_bit, crc, tail) = finder.finish()?;
+    let kept_bytes = kept_bytes - tail.len() as u64;
+
+    // What replaces the output before the kept bits: nothing, the head with
+    // the new dimension, or (when the cut comes before the head ends) the
+    // whole sheet, moved into the tail
+    let (new_head, head_bit, head_len, mut crc, tail) = match (dimension, head_cut) {
+        (None, _) => (Vec::new(), 0, 0, crc, tail),
+        (Some(reference), Some((head_bit, head_len))) if head_bit <= cut_bit => {
+            // Stored blocks pad to a byte boundary, so they can't be shifted
+            let shifted = |&bit: &u64| head_bit % 8 != 0 && (head_bit..cut_bit).contains(&bit);
+            if stored.iter().any(shifted) {
+                return Err(ExcelError::NotSupported(
+                    "splice can't realign stored blocks".to_string(),
+                ));
+            }
+            let mut head = head;
+            set_dimension(&mut head, reference);
+            let mut full = crc32fast::Hasher::new();
+            full.update(&head);
+            full.combine(&crc);
+            (head, head_bit, head_len, full, tail)
+        }
+        (Some(reference), _) => {
+            let mut full = head[..kept_bytes as usize].to_vec();
+            full.extend_from_slice(&tail);
+            set_dimension(&mut full, reference);
+            (
+                Vec::new(),
+                cut_bit,
+                kept_bytes,
+                crc32fast::Hasher::new(),
+                full,
+            )
+        }
+    };
+    let head_blocks = stored_blocks(&new_head);
+
+    let tail = insert_rows(&tail, rows_xml)?;
+    crc.update(&tail);
+    let crc = crc.finalize();
+    let uncompressed_size = new_head.len() as u64 + (kept_bytes - head_len) + tail.len() as u64;
+
+    // The kept bits start at a byte boundary in the new stream
+    let kept_bits = cut_bit - head_bit;
+    let spare = (kept_bits % 8) as u32;
+    let partial = read_bits(&mut file, data_start * 8 + cut_bit - spare as u64, spare)?;
+    let blocks = StoredBlockWriter::new(partial, spare).finish(&tail);
+    let compressed_size = head_blocks.len() as u64 + kept_bits / 8 + blocks.len() as u64;
+
+    // Copy entries in file order, rewriting only the sheet
+    let mut order: Vec<usize> = (0..layout.entries.len()).collect();
+    order.sort_by_key(|&i| layout.entries[i].local_offset);
+    let mut new_offsets = vec![0u64; layout.entries.len()];
+    let mut out = BufWriter::with_capacity(256 * 1024, out);
+    let mut pos = 0u64;
+
+    for (n, &i) in order.iter().enumerate() {
+        let entry = &layout.entries[i];
+        new_offsets[i] = pos;
+        if i == sheet_index {
+            let header = local_header(&local_head, crc, compressed_size, uncompressed_size);
+            out.write_all(&header)?;
+            out.write_all(&head_blocks)?;
+            copy_bits(
+                &mut file,
+                &mut out,
+                data_start * 8 + head_bit,
+                kept_bits / 8,
+            )?;
+            out.write_all(&blocks)?;
+            pos += header.len() as u64 + compressed_size;
+        } else {
+            let end = order
+                .get(n + 1)
+                .map_or(layout.cd_offset, |&next| layout.entries[next].local_offset);
+            file.seek(SeekFrom::Start(entry.local_offset))?;
+            copy_exact(&mut file, &mut out, end - entry.local_offset)?;
+            pos += end - entry.local_offset;
+        }
+    }
+
+    let cd_offset = pos;
+    for (i, entry) in layout.entries.iter().enumerate() {
+        let mut record = entry.record.clone();
+        entry.offset_slot.set(&mut record, new_offsets[i])?;
+        if i == sheet_index {
+            let flags = entry.flags & !0x0008; // No data descriptor any more
+            record[8..10].copy_from_slice(&flags.to_le_bytes());
+            record[16..20].copy_from_slice(&crc.to_le_bytes());
+            entry.compressed_slot.set(&mut record, compressed_size)?;
+            entry
+                .uncompressed_slot
+                .set(&mut record, uncompressed_size)?;
+        }
+        out.write_all(&record)?;
+        pos += record.len() as u64;
+    }
+
+    write_end_records(&mut out, layout, cd_offset, pos)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write `source` to `out` with the entries named in `first` moved to the
+/// front, in that order, and the rest in their original order
+///
+/// With `align` above 1, each entry's data starts at a multiple of `align`
+/// bytes, padded with an alignment extra field (0xD935) in its local
+/// header. Compressed data and data descriptors are copied as they are.
+/// Returns the size of the new archive.
+pub(crate) fn relayout(source: &Path, first: &[&str], align: u16, out: File) -> Result<u64> {
+    let mut file = File::open(source)?;
+    let layout = read_layout(&mut file)?;
+    let entries = &layout.entries;
+
+    // Each entry runs up to the next one in file order (or the directory)
+    let mut by_offset: Vec<usize> = (0..entries.len()).collect();
+    by_offset.sort_by_key(|&i| entries[i].local_offset);
+    let mut ends = vec![0u64; entries.len()];
+    for (n, &i) in by_offset.iter().enumerate() {
+        ends[i] = by_offset
+            .get(n + 1)
+            .map_or(layout.cd_offset, |&next| entries[next].local_offset);
+    }
+    let mut order: Vec<usize> = first
+        .iter()
+        .filter_map(|name| entries.iter().position(|e| e.name == *name))
+        .collect();
+    let rest: Vec<usize> = by_offset
+        .into_iter()
+        .filter(|i| !order.contains(i))
+        .collect();
+    order.extend(rest);
+
+    let mut new_offsets = vec![0u64; entries.len()];
+    let mut out = BufWriter::with_capacity(256 * 1024, out);
+    let mut pos = 0u64;
+    for &i in &order {
+        let entry = &entries[i];
+        new_offsets[i] = pos;
+        let (mut header, data_start) = read_local_header(&mut file, entry)?;
+        let extra_start = entry.local_offset + header.len() as u64;
+        let extra = read_exact_at(&mut file, extra_start, (data_start - extra_start) as usize)?;
+        let mut extra = without_alignment(&extra);
+        if align > 1 {
+            let unpadded = pos + (header.len() + extra.len()) as u64 + 6;
+            let padding = (align as u64 - unpadded % align as u64) % align as u64;
+            extra.extend_from_slice(&ALIGNMENT_EXTRA_ID.to_le_bytes());
+            let size =
+                u16::try_from(2 + padding).map_err(|_| invalid("alignment padding too long"))?;
+            extra.extend_from_slice(&size.to_le_bytes());
+            extra.extend_from_slice(&align.to_le_bytes());
+            extra.resize(extra.len() + padding as usize, 0);
+        }
+        let extra_len =
+            u16::try_from(extra.len()).map_err(|_| invalid("local header extra field too long"))?;
+        header[28..30].copy_from_slice(&extra_len.to_le_bytes());
+        out.write_all(&header)?;
+        out.write_all(&extra)?;
+        pos += (header.len() + extra.len()) as u64;
+
+        file.seek(SeekFrom::Start(data_start))?;
+        copy_exact(&mut file, &mut out, ends[i] - data_start)?;
+        pos += ends[i] - data_start;
+    }
+
+    let cd_offset = pos;
+    for &i in &order {
+        let mut record = entries[i].record.clone();
+        entries[i].offset_slot.set(&mut record, new_offsets[i])?;
+        out.write_all(&record)?;
+        pos += record.len() as u64;
+    }
+
+    let eocd_len = layout.eocd.len() as u64;
+    let zip64_len = layout
+        .zip64_eocd
+        .as_ref()
+        .map_or(0, |r| r.len() as u64 + 20);
+    write_end_records(&mut out, layout, cd_offset, pos)?;
+    out.flush()?;
+    Ok(pos + zip64_len + eocd_len)
+}
+
+// Extra field id of the padding that aligns entry data (as zipalign writes)
+const ALIGNMENT_EXTRA_ID: u16 = 0xD935;
+
+// A local header extra field without its alignment padding
+fn without_alignment(extra: &[u8]) -> Vec<u8> {
+    let mut kept = Vec::with_capacity(extra.len());
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let end = (pos + 4 + u16_at(extra, pos + 2) as usize).min(extra.len());
+        if u16_at(extra, pos) != ALIGNMENT_EXTRA_ID {
+            kept.extend_from_slice(&extra[pos..end]);
+        }
+        pos = end;
+    }
+    kept
+}
+
+// The (ZIP64) end of central directory records for a directory moved to
+// `cd_offset`, written at `pos`
+fn write_end_records<W: Write>(
+    out: &mut W,
+    layout: Layout,
+    cd_offset: u64,
+    pos: u64,
+) -> Result<()> {
+    let mut eocd = layout.eocd;
+    let has_zip64 = layout.zip64_eocd.is_some();
+    if let Some(mut record) = layout.zip64_eocd {
+        record[48..56].copy_from_slice(&cd_offset.to_le_bytes());
+        let record_offset = pos;
+        out.write_all(&record)?;
+        let mut locator = Vec::with_capacity(20);
+        locator.extend_from_slice(&ZIP64_LOCATOR_SIG.to_le_bytes());
+        locator.extend_from_slice(&0u32.to_le_bytes());
+        locator.extend_from_slice(&record_offset.to_le_bytes());
+        locator.extend_from_slice(&1u32.to_le_bytes());
+        out.write_all(&locator)?;
+    }
+    if has_zip64 {
+        // The ZIP64 record holds the real offset; saturate the short one
+        let short = u32::try_from(cd_offset).unwrap_or(u32::MAX);
+        eocd[16..20].copy_from_slice(&short.to_le_bytes());
+    } else {
+        Slot::Short(16).set(&mut eocd, cd_offset)?;
+    }
+    out.write_all(&eocd)?;
+    Ok(())
+}
+
+// Local header for the rewritten sheet: sizes up front, no data descriptor,
+// ZIP64 extra field only when the sizes need it
+fn local_header(original: &[u8], crc: u32, compressed: u64, uncompressed: u64) -> Vec<u8> {
+    let zip64 = compressed >= u32::MAX as u64 || uncompressed >= u32::MAX as u64;
+    let mut header = original.to_vec();
+    let flags = u16_at(&header, 6) & !0x0008;
+    header[6..8].copy_from_slice(&flags.to_le_bytes());
+    header[14..18].copy_from_slice(&crc.to_le_bytes());
+    if zip64 {
+        header[4..6].copy_from_slice(&45u16.to_le_bytes());
+        header[18..26].copy_from_slice(&[0xFF; 8]);
+        header[28..30].copy_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(&uncompressed.to_le_bytes());
+        header.extend_from_slice(&compressed.to_le_bytes());
+    } else {
+        header[18..22].copy_from_slice(&(compressed as u32).to_le_bytes());
+        header[22..26].copy_from_slice(&(uncompressed as u32).to_le_bytes());
+        header[28..30].copy_from_slice(&0u16.to_le_bytes());
+    }
+    header
+}
+
+fn copy_exact<W: Write>(file: &mut File, out: &mut W, len: u64) -> Result<()> {
+    let copied = std::io::copy(&mut file.take(len), out)?;
+    if copied != len {
+        return Err(invalid("unexpected end of file"));
+    }
+    Ok(())
+}
+
+// The `count` bits of an entry's data starting at absolute bit `bit`
+fn read_bits(file: &mut File, bit: u64, count: u32) -> Result<u8> {
+    if count == 0 {
+        return Ok(0);
+    }
+    let shift = (bit % 8) as u32;
+    let bytes = read_exact_at(file, bit / 8, (shift + count).div_ceil(8) as usize)?;
+    let word = u16::from_le_bytes([bytes[0], bytes.get(1).copied().unwrap_or(0)]);
+    Ok((word >> shift) as u8)
+}
+
+// Copy `len` bytes' worth of bits starting at absolute bit `bit`, shifted
+// down to a byte boundary
+fn copy_bits<W: Write>(file: &mut File, out: &mut W, bit: u64, len: u64) -> Result<()> {
+    file.seek(SeekFrom::Start(bit / 8))?;
+    let shift = bit % 8;
+    if shift == 0 {
+        return copy_exact(file, out, len);
+    }
+    // Each output byte takes the high bits of one input byte and the low
+    // bits of the next, so one input byte more than the output is read
+    let mut input = file.take(len + 1);
+    let mut buf = vec![0u8; 64 * 1024 + 1];
+    let mut shifted = Vec::with_capacity(64 * 1024);
+    let mut filled = 0;
+    let mut written = 0;
+    while written < len {
+        let n = input.read(&mut buf[filled..])?;
+        if n == 0 {
+            return Err(invalid("unexpected end of file"));
+        }
+        filled += n;
+        shifted.clear();
+        shifted.extend(
+            buf[..filled]
+                .windows(2)
+                .map(|pair| (u16::from_le_bytes([pair[0], pair[1]]) >> shift) as u8),
+        );
+        out.write_all(&shifted)?;
+        written += shifted.len() as u64;
+        buf[0] = buf[filled - 1];
+        filled = 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fast_writer::{StreamingZipReader, StreamingZipWriter};
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = StreamingZipWriter::new(path).unwrap();
+        for (name, data) in entries {
+            zip.start_entry(name).unwrap();
+            zip.write_data(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_splice_keeps_other_entries_byte_for_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("in.xlsx");
+        let spliced = dir.path().join("out.xlsx");
+
+        // Enough rows for the sheet to span many deflate blocks
+        let mut sheet = b"<worksheet><sheetData>".to_vec();
+        for i in 1..=20_000u32 {
+            sheet.extend_from_slice(
+                format!(
+                    "<row r=\"{0}\"><c r=\"A{0}\"><v>{1}</v></c></row>",
+                    i,
+                    i * 7919 % 104729
+                )
+                .as_bytes(),
+            );
+        }
+        sheet.extend_from_slice(b"</sheetData><mergeCells count=\"0\"/></worksheet>");
+        let other: Vec<u8> = (0..50_000u32).map(|i| (i * 31 % 253) as u8).collect();
+        write_zip(
+            &source,
+            &[
+                ("a.bin", &other),
+                ("xl/worksheets/sheet1.xml", &sheet),
+                ("z.xml", b"<z/>"),
+            ],
+        );
+
+        let new_row = b"<row r=\"20001\"><c r=\"A20001\"><v>1</v></c></row>";
+        splice_sheet(
+            &source,
+            "xl/worksheets/sheet1.xml",
+            new_row,
+            None,
+            File::create(&spliced).unwrap(),
+        )
+        .unwrap();
+
+        let mut reader = StreamingZipReader::open(&spliced).unwrap();
+        let expected = insert_rows(&sheet, new_row).unwrap();
+        assert_eq!(
+            reader
+                .read_entry_by_name("xl/worksheets/sheet1.xml")
+                .unwrap(),
+            expected
+        );
+        assert_eq!(reader.read_entry_by_name("a.bin").unwrap(), other);
+        assert_eq!(reader.read_entry_by_name("z.xml").unwrap(), b"<z/>");
+
+        // Untouched entries keep their compressed bytes
+        let mut src = File::open(&source).unwrap();
+        let mut dst = File::open(&spliced).unwrap();
+        let (src_layout, dst_layout) = (
+            read_layout(&mut src).unwrap(),
+            read_layout(&mut dst).unwrap(),
+        );
+        let a = &src_layout.entries[0];
+        assert_eq!(
+            read_exact_at(&mut src, a.local_offset, a.compressed_size as usize + 35).unwrap(),
+            read_exact_at(
+                &mut dst,
+                dst_layout.entries[0].local_offset,
+                a.compressed_size as usize + 35
+            )
+            .unwrap()
+        );
+
+        // Only the tail of the sheet was rewritten
+        let sheet_before = &src_layout.entries[1];
+        let sheet_after = &dst_layout.entries[1];
+        assert!(sheet_after.compressed_size < sheet_before.compressed_size + 64 * 1024);
+
+        // Splicing the spliced file again works on its stored tail
+        let twice = dir.path().join("twice.xlsx");
+        let row2 = b"<row r=\"20002\"/>";
+        splice_sheet(
+            &spliced,
+            "xl/worksheets/sheet1.xml",
+            row2,
+            None,
+            File::create(&twice).unwrap(),
+        )
+        .unwrap();
+        let mut reader = StreamingZipReader::open(&twice).unwrap();
+        assert_eq!(
+            reader
+                .read_entry_by_name("xl/worksheets/sheet1.xml")
+                .unwrap(),
+            insert_rows(&expected, row2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_splice_grows_dimension() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sheet = b"<worksheet><dimension ref=\"A1:B20000\"/><sheetData>".to_vec();
+        for i in 1..=20_000u32 {
+            sheet.extend_from_slice(
+                format!(
+                    "<row r=\"{0}\"><c r=\"A{0}\"><v>{1}</v></c><c r=\"B{0}\"><v>{0}</v></c></row>",
+                    i,
+                    i * 7919 % 104729
+                )
+                .as_bytes(),
+            );
+        }
+        sheet.extend_from_slice(b"</sheetData></worksheet>");
+        let small = b"<worksheet><dimension ref=\"A1\"></dimension><sheetData/></worksheet>";
+
+        for (name, sheet) in [("big", &sheet[..]), ("small", &small[..])] {
+            let source = dir.path().join(format!("{}.xlsx", name));
+            write_zip(&source, &[("sheet.xml", sheet), ("z.xml", b"<z/>")]);
+
+            // The big sheet keeps most of its compressed bits
+            let mut file = File::open(&source).unwrap();
+            let data = entry_data(&mut file, "sheet.xml").unwrap();
+            file.seek(SeekFrom::Start(data.offset)).unwrap();
+            let mut finder = TailFinder::new(true);
+            inflate((&mut file).take(data.compressed_size), &mut finder).unwrap();
+            assert_eq!(finder.head_cut.is_some(), name == "big");
+
+            let spliced = dir.path().join(format!("{}-out.xlsx", name));
+            let new_row = b"<row r=\"20001\"><c r=\"C20001\"><v>1</v></c></row>";
+            splice_sheet(
+                &source,
+                "sheet.xml",
+                new_row,
+                Some("A1:C20001"),
+                File::create(&spliced).unwrap(),
+            )
+            .unwrap();
+
+            let mut expected = insert_rows(sheet, new_row).unwrap();
+            set_dimension(&mut expected, "A1:C20001");
+            let mut reader = StreamingZipReader::open(&spliced).unwrap();
+            assert_eq!(reader.read_entry_by_name("sheet.xml").unwrap(), expected);
+            assert_eq!(reader.read_entry_by_name("z.xml").unwrap(), b"<z/>");
+        }
+    }
+
+    #[test]
+    fn test_read_layout_rejects_oversized_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.xlsx");
+        write_zip(&path, &[("a.xml", b"<a/>")]);
+
+        // Claim a central directory far larger than the file
+        let mut bytes = std::fs::read(&path).unwrap();
+        let eocd = bytes.len() - 22;
+        bytes[eocd + 12..eocd + 16].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        assert!(matches!(
+            read_layout(&mut file),
+            Err(ExcelError::ZipError(msg)) if msg.contains("out of bounds")
+        ));
+    }
+
+    #[test]
+    fn test_splice_rejects_stored_sheet() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("stored.xlsx");
+        let mut zip =
+            StreamingZipWriter::with_method(&source, s_zip::CompressionMethod::Stored, 0).unwrap();
+        zip.start_entry("sheet.xml").unwrap();
+        zip.write_data(b"<sheetData/>").unwrap();
+        zip.finish().unwrap();
+
+        let out = File::create(dir.path().join("out.xlsx")).unwrap();
+        assert!(matches!(
+            splice_sheet(&source, "sheet.xml", b"<row/>", None, out),
+            Err(ExcelError::NotSupported(_))
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+//! Cell reference helpers: column names, A1 and R1C1 references, ranges
+//!
+//! Rows and columns are 0-based throughout, like everywhere else in the
+//! crate; only the text forms are 1-based. Parsing rejects references
+//! outside Excel's grid (1,048,576 rows by 16,384 columns, `XFD`).
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::cellref;
+//!
+//! assert_eq!(cellref::col_to_name(27), "AB");
+//! assert_eq!(cellref::name_to_col("ab"), Some(27));
+//! assert_eq!(cellref::parse_a1("$C$10"), Some((9, 2)));
+//!
+//! // Build a formula over the data rows of column C
+//! let range = cellref::parse_range("C2:C100").unwrap();
+//! assert_eq!(format!("=SUM({})", range), "=SUM(C2:C100)");
+//!
+//! assert_eq!(cellref::to_r1c1(9, 2), "R10C3");
+//! assert_eq!(cellref::r1c1_to_a1("R[-1]C", (9, 2)).as_deref(), Some("C9"));
+//! ```
+
+pub use crate::types::CellRange;
+
+/// Number of rows in a worksheet
+pub const MAX_ROWS: u32 = 1_048_576;
+
+/// Number of columns in a worksheet
+pub const MAX_COLS: u32 = 16_384;
+
+/// Column name for a 0-based column index (0 -> "A", 26 -> "AA")
+pub fn col_to_name(col: u32) -> String {
+    let mut name = Vec::with_capacity(3);
+    push_col_name(&mut name, col);
+    // Only ASCII letters are pushed
+    String::from_utf8(name).unwrap_or_default()
+}
+
+// Append the column name of a 0-based index to an XML buffer
+pub(crate) fn push_col_name(buffer: &mut Vec<u8>, col: u32) {
+    let mut letters = [0u8; 7];
+    let mut len = 0;
+    let mut n = col as u64 + 1;
+    while n > 0 {
+        letters[len] = b'A' + ((n - 1) % 26) as u8;
+        len += 1;
+        n = (n - 1) / 26;
+    }
+    buffer.extend(letters[..len].iter().rev());
+}
+
+/// 0-based column index for a column name ("A" -> 0, "xfd" -> 16383)
+pub fn name_to_col(name: &str) -> Option<u32> {
+    if name.is_empty() {
+        return None;
+    }
+    let mut col = 0u32;
+    for b in name.bytes() {
+        if !b.is_ascii_alphabetic() {
+            return None;
+        }
+        col = col * 26 + (b.to_ascii_uppercase() - b'A') as u32 + 1;
+        if col > MAX_COLS {
+            return None;
+        }
+    }
+    Some(col - 1)
+}
+
+// Column of a reference read from a file ("AB12" -> 27), ignoring the row
+pub(crate) fn col_of(cell_ref: &str) -> Option<u32> {
+    let letters = cell_ref
+        .bytes()
+        .position(|b| !b.is_ascii_alphabetic())
+        .unwrap_or(cell_ref.len());
+    name_to_col(&cell_ref[..letters])
+}
+
+/// A1 reference for a 0-based cell ("B3" for row 2, column 1)
+pub fn to_a1(row: u32, col: u32) -> String {
+    format!("{}{}", col_to_name(col), row as u64 + 1)
+}
+
+/// Absolute A1 reference ("$B$3")
+pub fn to_a1_absolute(row: u32, col: u32) -> String {
+    format!("${}${}", col_to_name(col), row as u64 + 1)
+}
+
+/// Parse an A1 reference ("B3", "$B$3", "b3") into 0-based (row, col)
+pub fn parse_a1(reference: &str) -> Option<(u32, u32)> {
+    let reference = reference.strip_prefix('$').unwrap_or(reference);
+    let split = reference.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (letters, rest) = reference.split_at(split);
+    let digits = rest.strip_prefix('$').unwrap_or(rest);
+    let col = name_to_col(letters)?;
+    let row = parse_row(digits)?;
+    Some((row, col))
+}
+
+/// Parse "A1:C3" into a range; a single reference is a one-cell range and
+/// reversed corners ("C3:A1") are normalized
+pub fn parse_range(range: &str) -> Option<CellRange> {
+    let (start, end) = range.split_once(':').unwrap_or((range, range));
+    let (first_row, first_col) = parse_a1(start)?;
+    let (last_row, last_col) = parse_a1(end)?;
+    Some(CellRange::new(
+        first_row.min(last_row),
+        first_col.min(last_col),
+        first_row.max(last_row),
+        first_col.max(last_col),
+    ))
+}
+
+/// Absolute R1C1 reference for a 0-based cell ("R3C2")
+pub fn to_r1c1(row: u32, col: u32) -> String {
+    format!("R{}C{}", row as u64 + 1, col as u64 + 1)
+}
+
+/// Parse an R1C1 reference into 0-based (row, col)
+///
+/// Bracketed parts are relative to `origin`, the 0-based (row, col) of the
+/// cell holding the reference: `R[-1]C` is the cell above it, `RC[2]` two
+/// to the right.
+pub fn parse_r1c1(reference: &str, origin: (u32, u32)) -> Option<(u32, u32)> {
+    let rest = reference.strip_prefix(['R', 'r'])?;
+    let c_at = rest.find(['C', 'c'])?;
+    let row = r1c1_part(&rest[..c_at], origin.0, MAX_ROWS)?;
+    let col = r1c1_part(&rest[c_at + 1..], origin.1, MAX_COLS)?;
+    Some((row, col))
+}
+
+/// Convert an R1C1 reference to A1, see [`parse_r1c1`]
+pub fn r1c1_to_a1(reference: &str, origin: (u32, u32)) -> Option<String> {
+    parse_r1c1(reference, origin).map(|(row, col)| to_a1(row, col))
+}
+
+/// Convert an A1 reference to absolute R1C1
+pub fn a1_to_r1c1(reference: &str) -> Option<String> {
+    parse_a1(reference).map(|(row, col)| to_r1c1(row, col))
+}
+
+/// Sheet name as written before `!` in a reference, quoted when it isn't a
+/// plain identifier
+///
+/// Names with spaces or punctuation, names starting with a digit and names
+/// that read as a cell reference (`Q1`, `R2C3`) are quoted, with embedded
+/// quotes doubled.
+///
+/// ```
+/// use excelstream::cellref;
+///
+/// assert_eq!(cellref::quote_sheet_name("Data"), "Data");
+/// assert_eq!(cellref::quote_sheet_name("Q1 Sales"), "'Q1 Sales'");
+/// assert_eq!(cellref::quote_sheet_name("Bob's"), "'Bob''s'");
+/// assert_eq!(cellref::quote_sheet_name("Q1"), "'Q1'");
+/// assert_eq!(format!("{}!B2", cellref::quote_sheet_name("2024")), "'2024'!B2");
+/// ```
+pub fn quote_sheet_name(sheet: &str) -> String {
+    let plain = sheet
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && sheet
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && parse_a1(sheet).is_none()
+        && parse_r1c1(sheet, (0, 0)).is_none();
+    if plain {
+        sheet.to_string()
+    } else {
+        format!("'{}'", sheet.replace('\'', "''"))
+    }
+}
+
+/// External workbook references in formula text, as the byte range of
+/// each `[n]` marker and the 1-based external link number `n`
+///
+/// Excel stores references to other workbooks as `[1]Sheet1!A1` or
+/// `'[2]Q1 Sales'!B2`, numbering the workbooks in `xl/externalLinks`, see
+/// [`StreamingReader::external_links`](crate::streaming_reader::StreamingReader::external_links).
+/// Brackets in string literals, structured table references and R1C1
+/// offsets are not markers.
+///
+/// ```
+/// use excelstream::cellref;
+///
+/// let refs = cellref::external_refs("=[1]Rates!B2*Table1[[#This Row],[2]]");
+/// assert_eq!(refs, vec![(1..4, 1)]);
+/// ```
+pub fn external_refs(formula: &str) -> Vec<(std::ops::Range<usize>, u32)> {
+    let bytes = formula.as_bytes();
+    let mut refs = Vec::new();
+    let mut in_string = false;
+    let mut in_quoted = false; // Within a quoted sheet name
+    let mut depth = 0u32; // Open structured reference brackets
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' if !in_quoted => in_string = !in_string,
+            b'\'' if !in_string => in_quoted = !in_quoted,
+            b'[' if !in_string => {
+                let prev = i.checked_sub(1).map(|p| bytes[p]);
+                let starts_token = match prev {
+                    None => true,
+                    Some(b'\'') => true,
+                    Some(p) => !(in_quoted || p.is_ascii_alphanumeric() || b"_.@[]".contains(&p)),
+                };
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                let close = i + 1 + digits;
+                if depth == 0 && starts_token && digits > 0 && bytes.get(close) == Some(&b']') {
+                    if let Ok(n) = formula[i + 1..close].parse() {
+                        refs.push((i..close + 1, n));
+                    }
+                    i = close + 1;
+                    continue;
+                }
+                if !in_quoted {
+                    depth += 1;
+                }
+            }
+            b']' if !in_string && !in_quoted => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        i += 1;
+    }
+    refs
+}
+
+// 1-based row number -> 0-based row
+fn parse_row(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let row: u32 = digits.parse().ok()?;
+    (1..=MAX_ROWS).contains(&row).then(|| row - 1)
+}
+
+// One half of an R1C1 reference: "" (same as origin), "5" or "[-2]"
+fn r1c1_part(part: &str, origin: u32, max: u32) -> Option<u32> {
+    let index = if part.is_empty() {
+        origin as i64
+    } else if let Some(offset) = part.strip_prefix('[').and_then(|p| p.strip_suffix(']')) {
+        origin as i64 + offset.parse::<i64>().ok()?
+    } else {
+        if !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        part.parse::<i64>().ok()? - 1
+    };
+    (0..max as i64).contains(&index).then_some(index as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_names() {
+        for (col, name) in [(0, "A"), (25, "Z"), (26, "AA"), (701, "ZZ"), (702, "AAA")] {
+            assert_eq!(col_to_name(col), name);
+            assert_eq!(name_to_col(name), Some(col));
+        }
+        assert_eq!(name_to_col("XFD"), Some(MAX_COLS - 1));
+        assert_eq!(name_to_col("XFE"), None);
+        assert_eq!(name_to_col("A1"), None);
+        assert_eq!(name_to_col(""), None);
+        assert_eq!(col_to_name(u32::MAX), "MWLQKWV");
+        assert_eq!(col_of("AB12"), Some(27));
+    }
+
+    #[test]
+    fn test_a1_references() {
+        assert_eq!(parse_a1("A1"), Some((0, 0)));
+        assert_eq!(parse_a1("$b$3"), Some((2, 1)));
+        assert_eq!(parse_a1("B$3"), Some((2, 1)));
+        assert_eq!(parse_a1("XFD1048576"), Some((MAX_ROWS - 1, MAX_COLS - 1)));
+        for bad in ["A0", "A", "1", "A1B", "A-1", "A1048577", "$$A1", ""] {
+            assert_eq!(parse_a1(bad), None, "{}", bad);
+        }
+        assert_eq!(to_a1(2, 1), "B3");
+        assert_eq!(to_a1_absolute(2, 1), "$B$3");
+
+        assert_eq!(parse_range("C3:A1"), Some(CellRange::new(0, 0, 2, 2)));
+        assert_eq!(parse_range("B2"), Some(CellRange::new(1, 1, 1, 1)));
+        assert_eq!(parse_range("A1:"), None);
+    }
+
+    #[test]
+    fn test_r1c1_references() {
+        assert_eq!(to_r1c1(0, 0), "R1C1");
+        assert_eq!(parse_r1c1("R10C3", (0, 0)), Some((9, 2)));
+        assert_eq!(parse_r1c1("RC", (4, 4)), Some((4, 4)));
+        assert_eq!(parse_r1c1("R[-1]C[2]", (4, 4)), Some((3, 6)));
+        assert_eq!(parse_r1c1("R[-1]C", (0, 0)), None);
+        assert_eq!(parse_r1c1("R0C1", (0, 0)), None);
+        assert_eq!(a1_to_r1c1("$D$5").as_deref(), Some("R5C4"));
+        assert_eq!(r1c1_to_a1("R5C4", (0, 0)).as_deref(), Some("D5"));
+    }
+
+    #[test]
+    fn test_external_refs() {
+        assert_eq!(external_refs("=[1]Sheet1!A1"), vec![(1..4, 1)]);
+        let formula = "=SUM('[12]Q1 Sales'!B2:B9)+[3]!Rate";
+        assert_eq!(external_refs(formula), vec![(6..10, 12), (27..30, 3)]);
+        for none in [
+            "=A1+1",
+            r#"="[1]"&A1"#,
+            "='Odd [1] name'!A1",
+            "=Table1[[#This Row],[2]]",
+            "=Table1[@[1]]",
+            "=R[-1]C[2]",
+        ] {
+            assert!(external_refs(none).is_empty(), "{}", none);
+        }
+    }
+}
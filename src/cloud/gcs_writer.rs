@@ -3,8 +3,14 @@
 //! This module provides streaming Excel generation directly to Google Cloud Storage
 //! using s-zip's cloud support. NO local disk space required!
 
+use super::multipart::{MultipartUpload, PART_SIZE};
+use super::workbook_core::WorkbookCore;
+use super::CloudStorage;
 use crate::error::{ExcelError, Result};
-use crate::types::{CellStyle, CellValue};
+use crate::types::{CellStyle, CellValue, FloatFormat, StyledCell, TimeZonePolicy};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncWrite, DuplexStream};
 
 #[cfg(feature = "cloud-gcs")]
 use s_zip::cloud::GCSZipWriter;
@@ -36,13 +42,43 @@ use s_zip::AsyncStreamingZipWriter;
 /// }
 /// ```
 pub struct GCSExcelWriter {
-    zip_writer: Option<AsyncStreamingZipWriter<GCSZipWriter>>,
-    current_row: u32,
-    max_col: u32,
-    xml_buffer: Vec<u8>,
-    worksheet_count: u32,
-    worksheets: Vec<String>,
-    in_worksheet: bool,
+    zip_writer: Option<AsyncStreamingZipWriter<GCSSink>>,
+    upload: Option<MultipartUpload>, // Set when uploading through a CloudStorage
+    core: WorkbookCore,
+}
+
+/// Where the ZIP stream goes: s-zip's GCS writer, or our own multipart
+/// upload through a custom backend
+enum GCSSink {
+    Zip(GCSZipWriter),
+    Upload(DuplexStream),
+}
+
+impl AsyncWrite for GCSSink {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            GCSSink::Zip(w) => Pin::new(w).poll_write(cx, buf),
+            GCSSink::Upload(w) => Pin::new(w).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            GCSSink::Zip(w) => Pin::new(w).poll_flush(cx),
+            GCSSink::Upload(w) => Pin::new(w).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            GCSSink::Zip(w) => Pin::new(w).poll_shutdown(cx),
+            GCSSink::Upload(w) => Pin::new(w).poll_shutdown(cx),
+        }
+    }
 }
 
 impl GCSExcelWriter {
@@ -51,66 +87,104 @@ impl GCSExcelWriter {
         GCSExcelWriterBuilder::default()
     }
 
-    async fn ensure_worksheet(&mut self) -> Result<()> {
-        if !self.in_worksheet {
-            self.add_worksheet("Sheet1").await?;
+    /// Create GCSExcelWriter uploading through a [`CloudStorage`] backend
+    ///
+    /// The workbook is streamed to the backend in 5 MB parts. The upload is
+    /// completed by [`save`](Self::save); if a part fails, or the writer is
+    /// dropped before saving, it is aborted instead.
+    pub async fn from_storage<S: CloudStorage + 'static>(storage: S) -> Result<Self> {
+        let (pipe, upload) = MultipartUpload::start(storage, PART_SIZE).await?;
+        Ok(Self {
+            zip_writer: Some(AsyncStreamingZipWriter::from_writer(GCSSink::Upload(pipe))),
+            upload: Some(upload),
+            core: WorkbookCore::new(),
+        })
+    }
+
+    /// Start a new worksheet; following rows are written to it
+    ///
+    /// A "Sheet1" worksheet is created automatically if rows are written
+    /// before any sheet is added.
+    pub async fn add_sheet(&mut self, name: &str) -> Result<()> {
+        let zip_writer = self
+            .zip_writer
+            .as_mut()
+            .ok_or_else(|| ExcelError::InvalidState("Writer not initialized".to_string()))?;
+
+        loop {
+            let end = self.core.finish_sheet()?;
+            if end.is_empty() {
+                break;
+            }
+            zip_writer.write_data(end).await.map_err(zip_error)?;
         }
+        let entry_name = self.core.start_sheet(name)?;
+        zip_writer
+            .start_entry(&entry_name)
+            .await
+            .map_err(zip_error)?;
         Ok(())
     }
 
-    /// Add a new worksheet
-    async fn add_worksheet(&mut self, name: &str) -> Result<()> {
-        // Finish previous worksheet if any
-        if self.in_worksheet {
-            self.finish_current_worksheet().await?;
+    async fn ensure_worksheet(&mut self) -> Result<()> {
+        if !self.core.in_worksheet() {
+            self.add_sheet("Sheet1").await?;
         }
+        Ok(())
+    }
 
-        self.worksheet_count += 1;
-        self.worksheets.push(name.to_string());
-        self.current_row = 0;
-        self.max_col = 0;
+    /// Set column width for the current worksheet (0-based column)
+    ///
+    /// Must be called before the sheet's first row, like
+    /// [`ExcelWriter::set_column_width`](crate::ExcelWriter::set_column_width).
+    pub fn set_column_width(&mut self, col: u32, width: f64) -> Result<()> {
+        self.core.set_column_width(col, width)
+    }
 
-        // Start new worksheet entry in ZIP
-        let entry_name = format!("xl/worksheets/sheet{}.xml", self.worksheet_count);
-        self.zip_writer
-            .as_mut()
-            .ok_or_else(|| ExcelError::InvalidState("Writer not initialized".to_string()))?
-            .start_entry(&entry_name)
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+    /// Set height in points for the next row to be written
+    pub fn set_next_row_height(&mut self, height: f64) -> Result<()> {
+        self.core.set_next_row_height(height);
+        Ok(())
+    }
 
-        // Write worksheet XML header
-        let header = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
-<sheetData>"#;
+    /// Batch rows before they are uploaded, see
+    /// [`ExcelWriter::set_flush_policy`](crate::ExcelWriter::set_flush_policy)
+    pub fn set_flush_policy(&mut self, policy: crate::fast_writer::FlushPolicy) {
+        self.core.set_flush_policy(policy);
+    }
 
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(header.as_bytes())
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+    /// Write each sheet's `<dimension>` with its exact used range, see
+    /// [`ExcelWriterBuilder::with_exact_dimensions`](crate::writer::ExcelWriterBuilder::with_exact_dimensions)
+    ///
+    /// A sheet's rows are spooled to a temp file (in `dir`, or the system
+    /// temp dir) and uploaded when the sheet is finished. Applies to sheets
+    /// added afterwards, including the automatic "Sheet1".
+    pub fn set_exact_dimensions(&mut self, enabled: bool, dir: Option<&std::path::Path>) {
+        self.core.set_exact_dimensions(enabled, dir);
+    }
 
-        self.in_worksheet = true;
-        Ok(())
+    /// Cap the rate rows are uploaded at, see
+    /// [`throttle`](crate::throttle); the row call that gets ahead of the
+    /// rate awaits until it is back on schedule
+    pub fn set_throttle(&mut self, throttle: Option<crate::throttle::Throttle>) {
+        self.core.set_throttle(throttle);
     }
 
-    async fn finish_current_worksheet(&mut self) -> Result<()> {
-        if !self.in_worksheet {
-            return Ok(());
-        }
+    /// Control how float cells are written, see
+    /// [`ExcelWriter::set_float_format`](crate::ExcelWriter::set_float_format)
+    pub fn set_float_format(&mut self, format: FloatFormat) {
+        self.core.set_float_format(format);
+    }
 
-        // Close sheetData and worksheet tags
-        let footer = "</sheetData></worksheet>";
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(footer.as_bytes())
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+    /// Control how cells with a UTC offset are written, see
+    /// [`ExcelWriter::set_time_zone_policy`](crate::ExcelWriter::set_time_zone_policy)
+    pub fn set_time_zone_policy(&mut self, policy: TimeZonePolicy) {
+        self.core.set_time_zone_policy(policy);
+    }
 
-        self.in_worksheet = false;
-        Ok(())
+    /// Register a custom number format and return a style that uses it
+    pub fn register_number_format(&mut self, code: &str) -> CellStyle {
+        self.core.register_number_format(code)
     }
 
     /// Write a header row with bold formatting
@@ -119,19 +193,26 @@ impl GCSExcelWriter {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.ensure_worksheet().await?;
-
         let cells: Vec<_> = headers
             .into_iter()
             .map(|h| {
-                crate::types::StyledCell::new(
+                StyledCell::new(
                     CellValue::String(h.as_ref().to_string()),
                     CellStyle::HeaderBold,
                 )
             })
             .collect();
 
-        self.write_row_styled(&cells).await
+        self.write_cells(&cells).await
+    }
+
+    /// Write a header row without formatting
+    pub async fn write_header<I, S>(&mut self, headers: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.write_row(headers).await
     }
 
     /// Write a data row (strings)
@@ -142,43 +223,16 @@ impl GCSExcelWriter {
     {
         self.ensure_worksheet().await?;
 
-        self.current_row += 1;
-        let values: Vec<String> = row.into_iter().map(|s| s.as_ref().to_string()).collect();
-        self.max_col = self.max_col.max(values.len() as u32);
-
-        // Build row XML in buffer
-        self.xml_buffer.clear();
-        self.xml_buffer.extend_from_slice(b"<row r=\"");
-        self.xml_buffer
-            .extend_from_slice(self.current_row.to_string().as_bytes());
-        self.xml_buffer.extend_from_slice(b"\">");
-
-        for (col_idx, value) in values.iter().enumerate() {
-            let col_letter = Self::column_letter(col_idx as u32 + 1);
-            self.xml_buffer.extend_from_slice(b"<c r=\"");
-            self.xml_buffer.extend_from_slice(col_letter.as_bytes());
-            self.xml_buffer
-                .extend_from_slice(self.current_row.to_string().as_bytes());
-
-            if value.is_empty() {
-                self.xml_buffer.extend_from_slice(b"\"/>");
-            } else {
-                self.xml_buffer
-                    .extend_from_slice(b"\" t=\"inlineStr\"><is><t>");
-                Self::write_escaped(&mut self.xml_buffer, value.as_str());
-                self.xml_buffer.extend_from_slice(b"</t></is></c>");
-            }
-        }
-
-        self.xml_buffer.extend_from_slice(b"</row>");
-
-        // Stream to GCS immediately
+        let xml = self.core.text_row(row)?;
         self.zip_writer
             .as_mut()
             .unwrap()
-            .write_data(&self.xml_buffer)
+            .write_data(xml)
             .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+            .map_err(zip_error)?;
+        if let Some(wait) = self.core.take_wait() {
+            tokio::time::sleep(wait).await;
+        }
 
         Ok(())
     }
@@ -187,317 +241,98 @@ impl GCSExcelWriter {
     pub async fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
         let styled_cells: Vec<_> = cells
             .iter()
-            .map(|v| crate::types::StyledCell::new(v.clone(), CellStyle::Default))
+            .map(|v| StyledCell::new(v.clone(), CellStyle::Default))
             .collect();
 
-        self.write_row_styled(&styled_cells).await
+        self.write_cells(&styled_cells).await
     }
 
-    /// Write a row with styled cells
-    async fn write_row_styled(&mut self, cells: &[crate::types::StyledCell]) -> Result<()> {
-        self.ensure_worksheet().await?;
-
-        self.current_row += 1;
-        self.max_col = self.max_col.max(cells.len() as u32);
-
-        // Build row XML in buffer
-        self.xml_buffer.clear();
-        self.xml_buffer.extend_from_slice(b"<row r=\"");
-        self.xml_buffer
-            .extend_from_slice(self.current_row.to_string().as_bytes());
-        self.xml_buffer.extend_from_slice(b"\">");
-
-        for (col_idx, styled_cell) in cells.iter().enumerate() {
-            let col_letter = Self::column_letter(col_idx as u32 + 1);
-            let value = &styled_cell.value;
-            let style_id = styled_cell.style.index();
-
-            self.xml_buffer.extend_from_slice(b"<c r=\"");
-            self.xml_buffer.extend_from_slice(col_letter.as_bytes());
-            self.xml_buffer
-                .extend_from_slice(self.current_row.to_string().as_bytes());
-            self.xml_buffer.extend_from_slice(b"\"");
-
-            // Add style attribute if not default
-            if style_id > 0 {
-                self.xml_buffer.extend_from_slice(b" s=\"");
-                self.xml_buffer
-                    .extend_from_slice(style_id.to_string().as_bytes());
-                self.xml_buffer.extend_from_slice(b"\"");
-            }
-
-            // Write cell value based on type
-            match value {
-                CellValue::Empty => {
-                    self.xml_buffer.extend_from_slice(b"/>");
-                }
-                CellValue::Int(i) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(i.to_string().as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::Float(f) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(f.to_string().as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::Bool(b) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"b\"><v>");
-                    self.xml_buffer
-                        .extend_from_slice(if *b { b"1" } else { b"0" });
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::String(s) => {
-                    self.xml_buffer
-                        .extend_from_slice(b" t=\"inlineStr\"><is><t>");
-                    Self::write_escaped(&mut self.xml_buffer, s);
-                    self.xml_buffer.extend_from_slice(b"</t></is></c>");
-                }
-                CellValue::Formula(f) => {
-                    self.xml_buffer.extend_from_slice(b"><f>");
-                    Self::write_escaped(&mut self.xml_buffer, f);
-                    self.xml_buffer.extend_from_slice(b"</f></c>");
-                }
-                CellValue::DateTime(dt) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(dt.to_string().as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::Error(e) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"e\"><v>");
-                    Self::write_escaped(&mut self.xml_buffer, e);
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-            }
-        }
-
-        self.xml_buffer.extend_from_slice(b"</row>");
-
-        // Stream to GCS immediately
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(&self.xml_buffer)
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+    /// Write a row with a style per cell
+    pub async fn write_row_styled(&mut self, cells: &[(CellValue, CellStyle)]) -> Result<()> {
+        let styled_cells: Vec<_> = cells
+            .iter()
+            .map(|(value, style)| StyledCell::new(value.clone(), *style))
+            .collect();
 
-        Ok(())
+        self.write_cells(&styled_cells).await
     }
 
-    /// Save and upload Excel file to GCS (streaming, no temp files!)
-    pub async fn save(mut self) -> Result<()> {
-        // Finish current worksheet
-        self.finish_current_worksheet().await?;
-
-        // Write all required Excel files
-        self.write_content_types().await?;
-        self.write_rels().await?;
-        self.write_workbook().await?;
-        self.write_workbook_rels().await?;
-        self.write_styles().await?;
-
-        // Finish ZIP - this completes the GCS upload
-        let zip_writer = self
-            .zip_writer
-            .take()
-            .ok_or_else(|| ExcelError::InvalidState("Writer not initialized".to_string()))?;
-
-        zip_writer
-            .finish()
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        Ok(())
+    /// Write a row with the same style for every cell
+    pub async fn write_row_with_style(
+        &mut self,
+        values: &[CellValue],
+        style: CellStyle,
+    ) -> Result<()> {
+        let cells: Vec<_> = values.iter().map(|v| (v.clone(), style)).collect();
+        self.write_row_styled(&cells).await
     }
 
-    async fn write_content_types(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("[Content_Types].xml")
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        let mut xml = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
-<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
-<Default Extension="xml" ContentType="application/xml"/>
-<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
-<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
-        );
-
-        for i in 1..=self.worksheet_count {
-            xml.push_str(&format!(
-                r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
-                i
-            ));
-        }
-
-        xml.push_str("</Types>");
+    async fn write_cells(&mut self, cells: &[StyledCell]) -> Result<()> {
+        self.ensure_worksheet().await?;
 
+        let xml = self.core.styled_row(cells)?;
         self.zip_writer
             .as_mut()
             .unwrap()
-            .write_data(xml.as_bytes())
+            .write_data(xml)
             .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+            .map_err(zip_error)?;
+        if let Some(wait) = self.core.take_wait() {
+            tokio::time::sleep(wait).await;
+        }
 
         Ok(())
     }
 
-    async fn write_rels(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("_rels/.rels")
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>
-</Relationships>"#;
-
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        Ok(())
+    /// Number of rows written to the current worksheet
+    pub fn current_row(&self) -> u32 {
+        self.core.current_row()
     }
 
-    async fn write_workbook(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/workbook.xml")
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        let mut xml = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
-<sheets>"#,
-        );
-
-        for (idx, name) in self.worksheets.iter().enumerate() {
-            let sheet_id = idx + 1;
-            xml.push_str(&format!(
-                r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
-                name, sheet_id, sheet_id
-            ));
+    /// Save and upload Excel file to GCS (streaming, no temp files!)
+    pub async fn save(mut self) -> Result<()> {
+        let written = self.finish_package().await;
+        // Close the pipe so our own upload sees the end of the stream
+        self.zip_writer = None;
+        match self.upload.take() {
+            Some(upload) => upload.finish(written).await,
+            None => written,
         }
-
-        xml.push_str("</sheets></workbook>");
-
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        Ok(())
     }
 
-    async fn write_workbook_rels(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/_rels/workbook.xml.rels")
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        let mut xml = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
-        );
-
-        for i in 1..=self.worksheet_count {
-            xml.push_str(&format!(
-                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
-                i, i
-            ));
+    async fn finish_package(&mut self) -> Result<()> {
+        if self.core.worksheets().is_empty() {
+            self.add_sheet("Sheet1").await?;
         }
 
-        xml.push_str(&format!(
-            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
-            self.worksheet_count + 1
-        ));
-
-        xml.push_str("</Relationships>");
-
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        Ok(())
-    }
-
-    async fn write_styles(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/styles.xml")
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-<fonts count="2">
-<font><sz val="11"/><name val="Calibri"/></font>
-<font><b/><sz val="11"/><name val="Calibri"/></font>
-</fonts>
-<fills count="1"><fill><patternFill patternType="none"/></fill></fills>
-<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>
-<cellXfs count="2">
-<xf numFmtId="0" fontId="0" fillId="0" borderId="0"/>
-<xf numFmtId="0" fontId="1" fillId="0" borderId="0" applyFont="1"/>
-</cellXfs>
-</styleSheet>"#;
+        let mut zip_writer = self
+            .zip_writer
+            .take()
+            .ok_or_else(|| ExcelError::InvalidState("Writer not initialized".to_string()))?;
 
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())
-            .await
-            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+        loop {
+            let end = self.core.finish_sheet()?;
+            if end.is_empty() {
+                break;
+            }
+            zip_writer.write_data(end).await.map_err(zip_error)?;
+        }
+        for (name, xml) in self.core.package_parts() {
+            zip_writer.start_entry(name).await.map_err(zip_error)?;
+            zip_writer
+                .write_data(xml.as_bytes())
+                .await
+                .map_err(zip_error)?;
+        }
 
+        // Dropping the finished stream closes the pipe to our own upload
+        drop(zip_writer.finish().await.map_err(zip_error)?);
         Ok(())
     }
+}
 
-    fn column_letter(col: u32) -> String {
-        let mut result = String::new();
-        let mut n = col;
-        while n > 0 {
-            n -= 1;
-            result.insert(0, (b'A' + (n % 26) as u8) as char);
-            n /= 26;
-        }
-        result
-    }
-
-    fn write_escaped(buffer: &mut Vec<u8>, text: &str) {
-        for ch in text.chars() {
-            match ch {
-                '<' => buffer.extend_from_slice(b"&lt;"),
-                '>' => buffer.extend_from_slice(b"&gt;"),
-                '&' => buffer.extend_from_slice(b"&amp;"),
-                '"' => buffer.extend_from_slice(b"&quot;"),
-                '\'' => buffer.extend_from_slice(b"&apos;"),
-                _ => {
-                    let mut buf = [0; 4];
-                    buffer.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
-                }
-            }
-        }
-    }
+fn zip_error(e: impl std::fmt::Display) -> ExcelError {
+    ExcelError::IoError(std::io::Error::other(e.to_string()))
 }
 
 /// Builder for GCSExcelWriter
@@ -545,16 +380,12 @@ impl GCSExcelWriterBuilder {
             .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
 
         // Wrap in AsyncStreamingZipWriter
-        let zip_writer = AsyncStreamingZipWriter::from_writer(gcs_writer);
+        let zip_writer = AsyncStreamingZipWriter::from_writer(GCSSink::Zip(gcs_writer));
 
         Ok(GCSExcelWriter {
             zip_writer: Some(zip_writer),
-            current_row: 0,
-            max_col: 0,
-            xml_buffer: Vec::with_capacity(4096),
-            worksheet_count: 0,
-            worksheets: Vec::new(),
-            in_worksheet: false,
+            upload: None,
+            core: WorkbookCore::new(),
         })
     }
 
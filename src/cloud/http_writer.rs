@@ -9,6 +9,8 @@
 //! - No temporary files required
 //! - Constant memory usage
 //! - Works with any async web framework (Axum, Actix-web, Warp, etc.)
+//! - Optional gzip/zstd `Content-Encoding` of the body (`http-compression`
+//!   feature), see [`HttpExcelWriter::with_content_encoding`]
 //!
 //! # Example with Axum
 //!
@@ -38,8 +40,51 @@
 //! }
 //! ```
 
+use super::workbook_core::WorkbookCore;
 use crate::error::{ExcelError, Result};
-use crate::types::CellValue;
+use crate::types::{CellStyle, CellValue, FloatFormat, StyledCell, TimeZonePolicy};
+use std::io::{Seek, Write};
+
+/// `Content-Encoding` of the body returned by
+/// [`HttpExcelWriter::finish_encoded`]
+///
+/// gzip and zstd need the `http-compression` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentEncoding {
+    /// The xlsx as is, its parts deflated inside the ZIP (default)
+    #[default]
+    Identity,
+    /// gzip at a level from 0 to 9
+    Gzip(u32),
+    /// zstd at a level from 1 to 22
+    Zstd(i32),
+}
+
+impl ContentEncoding {
+    /// Value for the `Content-Encoding` header, None for identity
+    pub fn header_value(&self) -> Option<&'static str> {
+        match self {
+            ContentEncoding::Identity => None,
+            ContentEncoding::Gzip(_) => Some("gzip"),
+            ContentEncoding::Zstd(_) => Some("zstd"),
+        }
+    }
+
+    // Whether this build can write the encoding
+    fn is_supported(&self) -> bool {
+        *self == ContentEncoding::Identity || cfg!(feature = "http-compression")
+    }
+}
+
+/// Finished file with the encoding it was sent with
+#[derive(Debug, Clone)]
+pub struct EncodedBody {
+    /// Body bytes
+    pub bytes: Vec<u8>,
+    /// Value for the `Content-Encoding` header, None if the body is the
+    /// plain xlsx
+    pub content_encoding: Option<&'static str>,
+}
 
 /// In-memory buffer that implements Write + Seek traits
 struct MemoryBuffer {
@@ -82,6 +127,100 @@ impl std::io::Write for MemoryBuffer {
     }
 }
 
+/// Where the ZIP goes: the body itself, or an encoder compressing the body
+/// as it is written
+///
+/// The ZIP writer streams its entries with data descriptors and never seeks
+/// back, so an encoder only has to report its position.
+enum BodySink {
+    Plain(MemoryBuffer),
+    #[cfg(feature = "http-compression")]
+    Gzip(flate2::write::GzEncoder<Vec<u8>>, u64),
+    #[cfg(feature = "http-compression")]
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>, u64),
+}
+
+impl BodySink {
+    /// Encoder for `encoding`; None for identity, or without the
+    /// `http-compression` feature
+    fn encoder(encoding: ContentEncoding) -> Option<Self> {
+        match encoding {
+            ContentEncoding::Identity => None,
+            #[cfg(feature = "http-compression")]
+            ContentEncoding::Gzip(level) => Some(BodySink::Gzip(
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level.min(9))),
+                0,
+            )),
+            #[cfg(feature = "http-compression")]
+            ContentEncoding::Zstd(level) => Some(BodySink::Zstd(
+                zstd::stream::write::Encoder::new(Vec::new(), level.clamp(1, 22))
+                    .expect("Failed to create zstd encoder"),
+                0,
+            )),
+            #[cfg(not(feature = "http-compression"))]
+            ContentEncoding::Gzip(_) | ContentEncoding::Zstd(_) => None,
+        }
+    }
+
+    /// The finished body
+    fn into_body(self) -> Result<Vec<u8>> {
+        match self {
+            BodySink::Plain(buffer) => Ok(buffer.into_inner()),
+            #[cfg(feature = "http-compression")]
+            BodySink::Gzip(encoder, _) => Ok(encoder.finish()?),
+            #[cfg(feature = "http-compression")]
+            BodySink::Zstd(encoder, _) => Ok(encoder.finish()?),
+        }
+    }
+}
+
+impl std::io::Write for BodySink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            BodySink::Plain(buffer) => buffer.write(buf),
+            #[cfg(feature = "http-compression")]
+            BodySink::Gzip(encoder, position) => {
+                let n = encoder.write(buf)?;
+                *position += n as u64;
+                Ok(n)
+            }
+            #[cfg(feature = "http-compression")]
+            BodySink::Zstd(encoder, position) => {
+                let n = encoder.write(buf)?;
+                *position += n as u64;
+                Ok(n)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            BodySink::Plain(buffer) => buffer.flush(),
+            #[cfg(feature = "http-compression")]
+            BodySink::Gzip(encoder, _) => encoder.flush(),
+            #[cfg(feature = "http-compression")]
+            BodySink::Zstd(encoder, _) => encoder.flush(),
+        }
+    }
+}
+
+impl std::io::Seek for BodySink {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            BodySink::Plain(buffer) => buffer.seek(pos),
+            #[cfg(feature = "http-compression")]
+            BodySink::Gzip(_, position) | BodySink::Zstd(_, position) => match pos {
+                std::io::SeekFrom::Current(0) | std::io::SeekFrom::End(0) => Ok(*position),
+                std::io::SeekFrom::Start(offset) if offset == *position => Ok(offset),
+                _ => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "An encoded body can't seek",
+                )),
+            },
+        }
+    }
+}
+
 impl std::io::Seek for MemoryBuffer {
     fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
         let new_pos = match pos {
@@ -124,16 +263,13 @@ impl std::io::Seek for MemoryBuffer {
 pub struct HttpExcelWriter {
     workbook: Option<InMemoryWorkbook>,
     finished: bool,
+    encoding: ContentEncoding,
 }
 
 /// Internal workbook that writes to memory
 struct InMemoryWorkbook {
-    zip_writer: Option<s_zip::StreamingZipWriter<MemoryBuffer>>,
-    worksheets: Vec<String>,
-    worksheet_count: u32,
-    current_row: u32,
-    xml_buffer: Vec<u8>,
-    in_worksheet: bool,
+    zip_writer: s_zip::StreamingZipWriter<BodySink>,
+    core: WorkbookCore,
 }
 
 impl HttpExcelWriter {
@@ -151,11 +287,130 @@ impl HttpExcelWriter {
     ///   - 6: Balanced (recommended)
     ///   - 9: Maximum compression (slowest)
     pub fn with_compression(compression_level: u32) -> Self {
-        let workbook = InMemoryWorkbook::new(compression_level.min(9));
+        let workbook = InMemoryWorkbook::new(
+            compression_level.min(9),
+            BodySink::Plain(MemoryBuffer::new()),
+        );
 
         Self {
             workbook: Some(workbook),
             finished: false,
+            encoding: ContentEncoding::Identity,
+        }
+    }
+
+    /// Create a writer whose body is compressed as a whole with a
+    /// `Content-Encoding`, for servers and clients that accept it
+    ///
+    /// The ZIP parts are stored rather than deflated, so the XML is
+    /// compressed once, by the encoding, instead of twice; zstd over the
+    /// whole body is typically about half the size of the deflated xlsx.
+    /// Rows are encoded as they are written, so only the encoded body is
+    /// held in memory. With [`ContentEncoding::Identity`] this is
+    /// [`new`](Self::new).
+    ///
+    /// gzip and zstd need the `http-compression` feature; without it,
+    /// [`finish_encoded`](Self::finish_encoded) fails with
+    /// [`ExcelError::NotSupported`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::cloud::{ContentEncoding, HttpExcelWriter};
+    ///
+    /// let mut writer = HttpExcelWriter::with_content_encoding(ContentEncoding::Zstd(3));
+    /// writer.write_row(&["1", "Alice"])?;
+    /// let body = writer.finish_encoded()?;
+    /// // Send body.bytes with `Content-Encoding: zstd` (body.content_encoding)
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_content_encoding(encoding: ContentEncoding) -> Self {
+        match BodySink::encoder(encoding) {
+            Some(sink) => Self {
+                workbook: Some(InMemoryWorkbook::new(0, sink)),
+                finished: false,
+                encoding,
+            },
+            None => Self {
+                encoding,
+                ..Self::new()
+            },
+        }
+    }
+
+    /// Encoding applied by [`finish_encoded`](Self::finish_encoded)
+    pub fn content_encoding(&self) -> ContentEncoding {
+        self.encoding
+    }
+
+    /// Start a new worksheet; following rows are written to it
+    ///
+    /// A "Sheet1" worksheet is created automatically if rows are written
+    /// before any sheet is added.
+    pub fn add_sheet(&mut self, name: &str) -> Result<()> {
+        self.workbook()?.add_sheet(name)
+    }
+
+    /// Add a new worksheet (same as [`add_sheet`](Self::add_sheet))
+    pub fn add_worksheet(&mut self, name: &str) -> Result<()> {
+        self.add_sheet(name)
+    }
+
+    /// Set column width for the current worksheet (0-based column)
+    ///
+    /// Must be called before the sheet's first row, like
+    /// [`ExcelWriter::set_column_width`](crate::ExcelWriter::set_column_width).
+    pub fn set_column_width(&mut self, col: u32, width: f64) -> Result<()> {
+        self.workbook()?.core.set_column_width(col, width)
+    }
+
+    /// Set height in points for the next row to be written
+    pub fn set_next_row_height(&mut self, height: f64) -> Result<()> {
+        self.workbook()?.core.set_next_row_height(height);
+        Ok(())
+    }
+
+    /// Batch rows before they are compressed, see
+    /// [`ExcelWriter::set_flush_policy`](crate::ExcelWriter::set_flush_policy)
+    pub fn set_flush_policy(&mut self, policy: crate::fast_writer::FlushPolicy) {
+        if let Some(workbook) = self.workbook.as_mut() {
+            workbook.core.set_flush_policy(policy);
+        }
+    }
+
+    /// Write each sheet's `<dimension>` with its exact used range, see
+    /// [`ExcelWriterBuilder::with_exact_dimensions`](crate::writer::ExcelWriterBuilder::with_exact_dimensions)
+    ///
+    /// A sheet's rows are spooled to a temp file (in `dir`, or the system
+    /// temp dir) until the sheet is finished. Applies to sheets added
+    /// afterwards, including the automatic "Sheet1".
+    pub fn set_exact_dimensions(&mut self, enabled: bool, dir: Option<&std::path::Path>) {
+        if let Some(workbook) = self.workbook.as_mut() {
+            workbook.core.set_exact_dimensions(enabled, dir);
+        }
+    }
+
+    /// Control how float cells are written, see
+    /// [`ExcelWriter::set_float_format`](crate::ExcelWriter::set_float_format)
+    pub fn set_float_format(&mut self, format: FloatFormat) {
+        if let Some(workbook) = self.workbook.as_mut() {
+            workbook.core.set_float_format(format);
+        }
+    }
+
+    /// Control how cells with a UTC offset are written, see
+    /// [`ExcelWriter::set_time_zone_policy`](crate::ExcelWriter::set_time_zone_policy)
+    pub fn set_time_zone_policy(&mut self, policy: TimeZonePolicy) {
+        if let Some(workbook) = self.workbook.as_mut() {
+            workbook.core.set_time_zone_policy(policy);
+        }
+    }
+
+    /// Register a custom number format and return a style that uses it
+    pub fn register_number_format(&mut self, code: &str) -> CellStyle {
+        match self.workbook.as_mut() {
+            Some(workbook) => workbook.core.register_number_format(code),
+            None => CellStyle::Default, // Only after finish(), which consumes the writer
         }
     }
 
@@ -165,23 +420,26 @@ impl HttpExcelWriter {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.check_not_finished()?;
-
-        let workbook = self
-            .workbook
-            .as_mut()
-            .ok_or_else(|| ExcelError::InvalidState("Workbook not initialized".to_string()))?;
-
-        if workbook.worksheet_count == 0 {
-            workbook.add_worksheet("Sheet1")?;
-        }
-
-        let headers: Vec<String> = headers
+        let cells: Vec<_> = headers
             .into_iter()
-            .map(|s| s.as_ref().to_string())
+            .map(|h| {
+                StyledCell::new(
+                    CellValue::String(h.as_ref().to_string()),
+                    CellStyle::HeaderBold,
+                )
+            })
             .collect();
 
-        workbook.write_row(&headers.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+        self.workbook()?.write_cells(&cells)
+    }
+
+    /// Write a header row without formatting
+    pub fn write_header<I, S>(&mut self, headers: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.write_row(headers)
     }
 
     /// Write a data row (strings)
@@ -190,54 +448,49 @@ impl HttpExcelWriter {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.check_not_finished()?;
-
-        let workbook = self
-            .workbook
-            .as_mut()
-            .ok_or_else(|| ExcelError::InvalidState("Workbook not initialized".to_string()))?;
-
-        if workbook.worksheet_count == 0 {
-            workbook.add_worksheet("Sheet1")?;
-        }
-
-        let row: Vec<String> = row.into_iter().map(|s| s.as_ref().to_string()).collect();
-
-        workbook.write_row(&row.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+        self.workbook()?.write_row(row)
     }
 
     /// Write a data row with typed values
     pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
-        self.check_not_finished()?;
+        let styled_cells: Vec<_> = cells
+            .iter()
+            .map(|v| StyledCell::new(v.clone(), CellStyle::Default))
+            .collect();
 
-        let workbook = self
-            .workbook
-            .as_mut()
-            .ok_or_else(|| ExcelError::InvalidState("Workbook not initialized".to_string()))?;
+        self.workbook()?.write_cells(&styled_cells)
+    }
 
-        if workbook.worksheet_count == 0 {
-            workbook.add_worksheet("Sheet1")?;
-        }
+    /// Write a row with a style per cell
+    pub fn write_row_styled(&mut self, cells: &[(CellValue, CellStyle)]) -> Result<()> {
+        let styled_cells: Vec<_> = cells
+            .iter()
+            .map(|(value, style)| StyledCell::new(value.clone(), *style))
+            .collect();
 
-        workbook.write_row_typed(cells)
+        self.workbook()?.write_cells(&styled_cells)
     }
 
-    /// Add a new worksheet
-    pub fn add_worksheet(&mut self, name: &str) -> Result<()> {
-        self.check_not_finished()?;
-
-        let workbook = self
-            .workbook
-            .as_mut()
-            .ok_or_else(|| ExcelError::InvalidState("Workbook not initialized".to_string()))?;
+    /// Write a row with the same style for every cell
+    pub fn write_row_with_style(&mut self, values: &[CellValue], style: CellStyle) -> Result<()> {
+        let cells: Vec<_> = values.iter().map(|v| (v.clone(), style)).collect();
+        self.write_row_styled(&cells)
+    }
 
-        workbook.add_worksheet(name)
+    /// Number of rows written to the current worksheet
+    pub fn current_row(&self) -> u32 {
+        self.workbook
+            .as_ref()
+            .map_or(0, |workbook| workbook.core.current_row())
     }
 
     /// Finish writing and return the Excel file as bytes
     ///
     /// This consumes the writer and returns the complete Excel file
-    /// as a Vec<u8> that can be sent as an HTTP response.
+    /// as a Vec<u8> that can be sent as an HTTP response. A writer made with
+    /// [`with_content_encoding`](Self::with_content_encoding) returns the
+    /// encoded body; [`finish_encoded`](Self::finish_encoded) also gives its
+    /// header value.
     pub fn finish(mut self) -> Result<Vec<u8>> {
         if self.finished {
             return Err(ExcelError::InvalidState("Already finished".to_string()));
@@ -254,14 +507,33 @@ impl HttpExcelWriter {
         Ok(bytes)
     }
 
-    fn check_not_finished(&self) -> Result<()> {
+    /// Finish writing and return the body with the writer's
+    /// [`ContentEncoding`] applied
+    ///
+    /// Fails with [`ExcelError::NotSupported`] for gzip or zstd without the
+    /// `http-compression` feature.
+    pub fn finish_encoded(self) -> Result<EncodedBody> {
+        let encoding = self.encoding;
+        if !encoding.is_supported() {
+            return Err(ExcelError::NotSupported(
+                "http-compression feature not enabled".to_string(),
+            ));
+        }
+        Ok(EncodedBody {
+            bytes: self.finish()?,
+            content_encoding: encoding.header_value(),
+        })
+    }
+
+    fn workbook(&mut self) -> Result<&mut InMemoryWorkbook> {
         if self.finished {
-            Err(ExcelError::InvalidState(
+            return Err(ExcelError::InvalidState(
                 "Writer already finished".to_string(),
-            ))
-        } else {
-            Ok(())
+            ));
         }
+        self.workbook
+            .as_mut()
+            .ok_or_else(|| ExcelError::InvalidState("Workbook not initialized".to_string()))
     }
 }
 
@@ -272,419 +544,168 @@ impl Default for HttpExcelWriter {
 }
 
 impl InMemoryWorkbook {
-    fn new(compression_level: u32) -> Self {
-        let buffer = MemoryBuffer::new();
-        let zip_writer = s_zip::StreamingZipWriter::from_writer_with_compression(
-            buffer,
-            compression_level.min(9),
-        )
-        .expect("Failed to create ZIP writer");
+    fn new(compression_level: u32, sink: BodySink) -> Self {
+        let zip_writer =
+            s_zip::StreamingZipWriter::from_writer_with_compression(sink, compression_level.min(9))
+                .expect("Failed to create ZIP writer");
 
         Self {
-            zip_writer: Some(zip_writer),
-            worksheets: Vec::new(),
-            worksheet_count: 0,
-            current_row: 0,
-            xml_buffer: Vec::with_capacity(4096),
-            in_worksheet: false,
+            zip_writer,
+            core: WorkbookCore::new(),
         }
     }
 
-    fn add_worksheet(&mut self, name: &str) -> Result<()> {
-        // Finish previous worksheet if any
-        self.finish_current_worksheet()?;
-
-        self.worksheet_count += 1;
-        self.worksheets.push(name.to_string());
-        self.current_row = 0;
-
-        // Start new worksheet entry in ZIP
-        let entry_name = format!("xl/worksheets/sheet{}.xml", self.worksheet_count);
-        self.zip_writer.as_mut().unwrap().start_entry(&entry_name)?;
-
-        // Write worksheet XML header
-        let header = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
-<sheetData>"#;
-
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(header.as_bytes())?;
-        self.in_worksheet = true;
-
-        Ok(())
-    }
-
-    fn write_row(&mut self, values: &[&str]) -> Result<()> {
-        if !self.in_worksheet {
-            return Err(ExcelError::WriteError("No worksheet started".to_string()));
-        }
-
-        self.current_row += 1;
-
-        // Build row XML in buffer
-        self.xml_buffer.clear();
-        self.xml_buffer.extend_from_slice(b"<row r=\"");
-        self.xml_buffer
-            .extend_from_slice(self.current_row.to_string().as_bytes());
-        self.xml_buffer.extend_from_slice(b"\">");
-
-        for (col_idx, value) in values.iter().enumerate() {
-            let col_letter = Self::column_letter(col_idx as u32 + 1);
-            self.xml_buffer.extend_from_slice(b"<c r=\"");
-            self.xml_buffer.extend_from_slice(col_letter.as_bytes());
-            self.xml_buffer
-                .extend_from_slice(self.current_row.to_string().as_bytes());
-
-            if value.is_empty() {
-                self.xml_buffer.extend_from_slice(b"\"/>");
-            } else {
-                self.xml_buffer
-                    .extend_from_slice(b"\" t=\"inlineStr\"><is><t>");
-                Self::write_escaped(&mut self.xml_buffer, value);
-                self.xml_buffer.extend_from_slice(b"</t></is></c>");
-            }
-        }
-
-        self.xml_buffer.extend_from_slice(b"</row>");
-
-        // Stream to compressor immediately
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(&self.xml_buffer)?;
-
+    fn add_sheet(&mut self, name: &str) -> Result<()> {
+        self.finish_sheet()?;
+        let entry_name = self.core.start_sheet(name)?;
+        self.zip_writer.start_entry(&entry_name)?;
         Ok(())
     }
 
-    fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
-        if !self.in_worksheet {
-            return Err(ExcelError::WriteError("No worksheet started".to_string()));
-        }
-
-        self.current_row += 1;
-
-        // Build row XML in buffer
-        self.xml_buffer.clear();
-        self.xml_buffer.extend_from_slice(b"<row r=\"");
-        self.xml_buffer
-            .extend_from_slice(self.current_row.to_string().as_bytes());
-        self.xml_buffer.extend_from_slice(b"\">");
-
-        for (col_idx, value) in cells.iter().enumerate() {
-            let col_letter = Self::column_letter(col_idx as u32 + 1);
-
-            self.xml_buffer.extend_from_slice(b"<c r=\"");
-            self.xml_buffer.extend_from_slice(col_letter.as_bytes());
-            self.xml_buffer
-                .extend_from_slice(self.current_row.to_string().as_bytes());
-            self.xml_buffer.extend_from_slice(b"\"");
-
-            // Write cell value based on type
-            match value {
-                CellValue::Empty => {
-                    self.xml_buffer.extend_from_slice(b"/>");
-                }
-                CellValue::Int(i) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(i.to_string().as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::Float(f) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(f.to_string().as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::Bool(b) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"b\"><v>");
-                    self.xml_buffer
-                        .extend_from_slice(if *b { b"1" } else { b"0" });
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::String(s) => {
-                    self.xml_buffer
-                        .extend_from_slice(b" t=\"inlineStr\"><is><t>");
-                    Self::write_escaped(&mut self.xml_buffer, s);
-                    self.xml_buffer.extend_from_slice(b"</t></is></c>");
-                }
-                CellValue::Formula(f) => {
-                    self.xml_buffer.extend_from_slice(b"><f>");
-                    Self::write_escaped(&mut self.xml_buffer, f);
-                    self.xml_buffer.extend_from_slice(b"</f></c>");
-                }
-                CellValue::DateTime(dt) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(dt.to_string().as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                CellValue::Error(e) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"e\"><v>");
-                    Self::write_escaped(&mut self.xml_buffer, e);
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
+    fn finish_sheet(&mut self) -> Result<()> {
+        loop {
+            let end = self.core.finish_sheet()?;
+            if end.is_empty() {
+                return Ok(());
             }
+            self.zip_writer.write_data(end)?;
         }
-
-        self.xml_buffer.extend_from_slice(b"</row>");
-
-        // Stream to compressor immediately
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(&self.xml_buffer)?;
-
-        Ok(())
     }
 
-    fn finish_current_worksheet(&mut self) -> Result<()> {
-        if self.in_worksheet {
-            self.zip_writer
-                .as_mut()
-                .unwrap()
-                .write_data(b"</sheetData></worksheet>")?;
-            self.in_worksheet = false;
+    fn ensure_worksheet(&mut self) -> Result<()> {
+        if !self.core.in_worksheet() {
+            self.add_sheet("Sheet1")?;
         }
         Ok(())
     }
 
-    fn close(mut self) -> Result<Vec<u8>> {
-        // Finish current worksheet
-        self.finish_current_worksheet()?;
-
-        // Write all other required ZIP entries
-        self.write_content_types()?;
-        self.write_rels()?;
-        self.write_workbook()?;
-        self.write_workbook_rels()?;
-        self.write_styles()?;
-        self.write_shared_strings()?;
-        self.write_app_props()?;
-        self.write_core_props()?;
-
-        // Finish ZIP and get buffer
-        let zip_writer = self.zip_writer.take().unwrap();
-        let buffer = zip_writer.finish()?;
-
-        Ok(buffer.into_inner())
-    }
-
-    fn write_content_types(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("[Content_Types].xml")?;
-        let mut xml = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
-<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
-<Default Extension="xml" ContentType="application/xml"/>
-<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
-<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>
-<Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/>
-<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
-<Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/>"#,
-        );
-
-        for i in 1..=self.worksheet_count {
-            xml.push_str(&format!(
-                r#"
-<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
-                i
-            ));
-        }
-
-        xml.push_str("\n</Types>");
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+    fn write_row<I, S>(&mut self, row: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ensure_worksheet()?;
+        let xml = self.core.text_row(row)?;
+        self.zip_writer.write_data(xml)?;
         Ok(())
     }
 
-    fn write_rels(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("_rels/.rels")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>
-<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
-<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/>
-</Relationships>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+    fn write_cells(&mut self, cells: &[StyledCell]) -> Result<()> {
+        self.ensure_worksheet()?;
+        let xml = self.core.styled_row(cells)?;
+        self.zip_writer.write_data(xml)?;
         Ok(())
     }
 
-    fn write_workbook(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/workbook.xml")?;
-        let mut xml = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
-<sheets>"#,
-        );
-
-        for (i, name) in self.worksheets.iter().enumerate() {
-            xml.push_str(&format!(
-                r#"
-<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
-                name,
-                i + 1,
-                i + 1
-            ));
+    fn close(mut self) -> Result<Vec<u8>> {
+        if self.core.worksheets().is_empty() {
+            self.add_sheet("Sheet1")?;
         }
 
-        xml.push_str("\n</sheets>\n</workbook>");
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
-        Ok(())
-    }
-
-    fn write_workbook_rels(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/_rels/workbook.xml.rels")?;
-        let mut xml = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
-        );
-
-        for i in 1..=self.worksheet_count {
-            xml.push_str(&format!(
-                r#"
-<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
-                i, i
-            ));
+        self.finish_sheet()?;
+        for (name, xml) in self.core.package_parts() {
+            self.zip_writer.start_entry(name)?;
+            self.zip_writer.write_data(xml.as_bytes())?;
         }
 
-        xml.push_str(&format!(
-            r#"
-<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
-<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/>
-</Relationships>"#,
-            self.worksheet_count + 1,
-            self.worksheet_count + 2
-        ));
-
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
-        Ok(())
+        self.zip_writer.finish()?.into_body()
     }
+}
 
-    fn write_styles(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_styled_multi_sheet() {
+        let mut writer = HttpExcelWriter::new();
+        writer.set_column_width(0, 18.0).unwrap();
+        writer.write_header_bold(["Item", "Price"]).unwrap();
+        let money = writer.register_number_format("#,##0.00");
+        writer
+            .write_row_styled(&[
+                (CellValue::String("Widget".to_string()), CellStyle::Default),
+                (CellValue::Float(9.5), money),
+            ])
+            .unwrap();
+        writer.add_sheet("Flags").unwrap();
+        writer
+            .write_row_typed(&[CellValue::Bool(true), CellValue::Int(3)])
+            .unwrap();
+        assert_eq!(writer.current_row(), 1);
+
+        let bytes = writer.finish().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("http.xlsx");
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        assert_eq!(reader.sheet_names(), vec!["Sheet1", "Flags"]);
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
             .unwrap()
-            .start_entry("xl/styles.xml")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-<numFmts count="0"/>
-<fonts count="2">
-<font><sz val="11"/><name val="Calibri"/></font>
-<font><b/><sz val="11"/><name val="Calibri"/></font>
-</fonts>
-<fills count="2">
-<fill><patternFill patternType="none"/></fill>
-<fill><patternFill patternType="gray125"/></fill>
-</fills>
-<borders count="1">
-<border><left/><right/><top/><bottom/><diagonal/></border>
-</borders>
-<cellXfs count="2">
-<xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>
-<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>
-</cellXfs>
-</styleSheet>"#;
-        self.zip_writer
-            .as_mut()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(rows[1][1], CellValue::Float(9.5));
+        let flags: Vec<_> = reader
+            .rows("Flags")
             .unwrap()
-            .write_data(xml.as_bytes())?;
-        Ok(())
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(flags, vec![vec![CellValue::Bool(true), CellValue::Int(3)]]);
     }
 
-    fn write_shared_strings(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/sharedStrings.xml")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="0" uniqueCount="0"/>
-"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
-        Ok(())
+    #[test]
+    fn test_identity_encoding() {
+        let mut writer = HttpExcelWriter::with_content_encoding(ContentEncoding::Identity);
+        writer.write_row(["a"]).unwrap();
+        let body = writer.finish_encoded().unwrap();
+        assert_eq!(body.content_encoding, None);
+        assert!(body.bytes.starts_with(b"PK"));
+        assert_eq!(ContentEncoding::Zstd(3).header_value(), Some("zstd"));
     }
 
-    fn write_app_props(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("docProps/app.xml")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties">
-<Application>ExcelStream HTTP</Application>
-</Properties>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
-        Ok(())
-    }
+    #[cfg(feature = "http-compression")]
+    #[test]
+    fn test_encoded_bodies_decode_to_xlsx() {
+        use std::io::Read;
+
+        let encoded = |encoding| {
+            let mut writer = HttpExcelWriter::with_content_encoding(encoding);
+            for i in 0..1000 {
+                writer
+                    .write_row([i.to_string(), "North".to_string()])
+                    .unwrap();
+            }
+            writer.finish_encoded().unwrap()
+        };
 
-    fn write_core_props(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("docProps/core.xml")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-<dc:creator>ExcelStream HTTP</dc:creator>
-</cp:coreProperties>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
-        Ok(())
+        let zstd_body = encoded(ContentEncoding::Zstd(3));
+        assert_eq!(zstd_body.content_encoding, Some("zstd"));
+        let xlsx = zstd::decode_all(zstd_body.bytes.as_slice()).unwrap();
+        assert!(xlsx.starts_with(b"PK"));
+
+        let gzip_body = encoded(ContentEncoding::Gzip(6));
+        assert_eq!(gzip_body.content_encoding, Some("gzip"));
+        let mut xlsx = Vec::new();
+        flate2::read::GzDecoder::new(gzip_body.bytes.as_slice())
+            .read_to_end(&mut xlsx)
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gzip.xlsx");
+        std::fs::write(&path, xlsx).unwrap();
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        assert_eq!(reader.rows("Sheet1").unwrap().count(), 1000);
     }
 
-    fn column_letter(n: u32) -> String {
-        let mut result = String::new();
-        let mut n = n;
-        while n > 0 {
-            let rem = (n - 1) % 26;
-            result.insert(0, (b'A' + rem as u8) as char);
-            n = (n - 1) / 26;
-        }
-        result
-    }
-
-    fn write_escaped(buffer: &mut Vec<u8>, s: &str) {
-        for c in s.chars() {
-            match c {
-                '&' => buffer.extend_from_slice(b"&amp;"),
-                '<' => buffer.extend_from_slice(b"&lt;"),
-                '>' => buffer.extend_from_slice(b"&gt;"),
-                '"' => buffer.extend_from_slice(b"&quot;"),
-                '\'' => buffer.extend_from_slice(b"&apos;"),
-                _ => {
-                    let mut buf = [0; 4];
-                    buffer.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
-                }
-            }
-        }
+    #[cfg(not(feature = "http-compression"))]
+    #[test]
+    fn test_encoding_needs_feature() {
+        let writer = HttpExcelWriter::with_content_encoding(ContentEncoding::Gzip(6));
+        assert!(matches!(
+            writer.finish_encoded(),
+            Err(ExcelError::NotSupported(_))
+        ));
     }
 }
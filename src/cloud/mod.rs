@@ -9,6 +9,16 @@
 //! - Multipart upload for large files
 //! - Constant memory usage (~30-35 MB)
 //! - No temporary files
+//! - Same row API on every writer as the local `ExcelWriter`: typed cells,
+//!   styles and number formats, multiple sheets, column widths, row heights
+//! - S3 object options: server-side encryption (SSE-S3/SSE-KMS), ACL,
+//!   storage class, cache control, metadata and tags
+//!
+//! Rows go out as they are written, so sheets carry no `<dimension>` by
+//! default. `set_exact_dimensions` on a writer spools each sheet's rows to a
+//! temp file until its used range is known, like
+//! [`ExcelWriterBuilder::with_exact_dimensions`](crate::writer::ExcelWriterBuilder::with_exact_dimensions)
+//! does for local files.
 //!
 //! # S3 Example
 //!
@@ -67,6 +77,17 @@ pub mod http_writer;
 
 pub mod replicate;
 
+mod object_options;
+
+#[cfg(any(feature = "cloud-s3", feature = "cloud-gcs"))]
+mod multipart;
+
+#[cfg(feature = "cloud-s3")]
+mod s3_upload;
+
+#[cfg(any(feature = "cloud-s3", feature = "cloud-gcs", feature = "cloud-http"))]
+mod workbook_core;
+
 #[cfg(feature = "cloud-s3")]
 pub use s3_writer::S3ExcelWriter;
 
@@ -77,7 +98,9 @@ pub use s3_reader::S3ExcelReader;
 pub use gcs_writer::GCSExcelWriter;
 
 #[cfg(feature = "cloud-http")]
-pub use http_writer::HttpExcelWriter;
+pub use http_writer::{ContentEncoding, EncodedBody, HttpExcelWriter};
+
+pub use object_options::{ObjectOptions, ServerSideEncryption};
 
 use crate::error::Result;
 use std::io::Write;
@@ -85,8 +108,10 @@ use std::io::Write;
 /// Trait for cloud storage backends
 ///
 /// This trait abstracts different cloud storage providers (S3, GCS, Azure)
-/// to provide a unified interface for streaming Excel files.
-pub trait CloudStorage: Write + Send {
+/// to provide a unified interface for streaming Excel files. The S3 and GCS
+/// writers upload through any backend with `from_storage`; an upload that
+/// fails partway through is aborted rather than completed.
+pub trait CloudStorage: Send {
     /// Start a multipart upload session
     fn start_upload(&mut self) -> impl std::future::Future<Output = Result<String>> + Send;
 
@@ -180,3 +205,108 @@ impl Write for CloudBuffer {
         Ok(())
     }
 }
+
+/// A [`CloudStorage`] backend that fails chosen multipart calls
+///
+/// Wraps a real or in-memory backend to test what happens when the network
+/// drops partway through an upload: [`fail_part`](Self::fail_part) fails the
+/// given part numbers (once each, so a retry goes through) and
+/// [`fail_complete`](Self::fail_complete) fails completing the upload. Writes
+/// pass through to the wrapped backend; see
+/// [`FaultySink`](crate::sink::FaultySink) for faults on the byte stream.
+pub struct FaultyStorage<S> {
+    inner: S,
+    failing_parts: Vec<u32>,
+    fail_complete: bool,
+    kind: std::io::ErrorKind,
+    aborted: Vec<String>,
+}
+
+impl<S: CloudStorage> FaultyStorage<S> {
+    /// Wrap `inner` without any faults
+    pub fn new(inner: S) -> Self {
+        FaultyStorage {
+            inner,
+            failing_parts: Vec::new(),
+            fail_complete: false,
+            kind: std::io::ErrorKind::ConnectionReset,
+            aborted: Vec::new(),
+        }
+    }
+
+    /// Fail the first attempt to upload part `part_number`
+    pub fn fail_part(mut self, part_number: u32) -> Self {
+        self.failing_parts.push(part_number);
+        self
+    }
+
+    /// Fail completing the upload, after all parts went through
+    pub fn fail_complete(mut self) -> Self {
+        self.fail_complete = true;
+        self
+    }
+
+    /// Error kind of the injected failures (default `ConnectionReset`)
+    pub fn with_error_kind(mut self, kind: std::io::ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Upload IDs passed to `abort_upload`, in order
+    pub fn aborted(&self) -> &[String] {
+        &self.aborted
+    }
+
+    /// The wrapped backend
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn fault(&self, what: String) -> crate::error::ExcelError {
+        crate::error::ExcelError::IoError(std::io::Error::new(
+            self.kind,
+            format!("injected fault: {}", what),
+        ))
+    }
+}
+
+impl<S: CloudStorage + Write> Write for FaultyStorage<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: CloudStorage> CloudStorage for FaultyStorage<S> {
+    async fn start_upload(&mut self) -> Result<String> {
+        self.inner.start_upload().await
+    }
+
+    async fn upload_part(
+        &mut self,
+        upload_id: &str,
+        part_number: u32,
+        data: &[u8],
+    ) -> Result<String> {
+        if let Some(i) = self.failing_parts.iter().position(|&p| p == part_number) {
+            self.failing_parts.swap_remove(i);
+            return Err(self.fault(format!("upload of part {}", part_number)));
+        }
+        self.inner.upload_part(upload_id, part_number, data).await
+    }
+
+    async fn complete_upload(&mut self, upload_id: &str, parts: Vec<(u32, String)>) -> Result<()> {
+        if self.fail_complete {
+            return Err(self.fault("completing the upload".to_string()));
+        }
+        self.inner.complete_upload(upload_id, parts).await
+    }
+
+    async fn abort_upload(&mut self, upload_id: &str) -> Result<()> {
+        self.aborted.push(upload_id.to_string());
+        self.inner.abort_upload(upload_id).await
+    }
+}
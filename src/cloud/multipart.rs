@@ -0,0 +1,109 @@
+//! Multipart upload through any [`CloudStorage`] backend
+//!
+//! The writer side is one end of an in-memory pipe; a background task reads
+//! the other end in part-sized chunks and uploads them. The upload is only
+//! completed when [`MultipartUpload::finish`] is called after a successful
+//! write, so a writer dropped or failing halfway through aborts it instead of
+//! leaving a truncated object behind.
+
+use super::CloudStorage;
+use crate::error::{ExcelError, Result};
+use tokio::io::{AsyncReadExt, DuplexStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Part size for uploads (the S3 minimum for all but the last part)
+pub(crate) const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Pipe size between the writer and the upload task
+const PIPE_SIZE: usize = 256 * 1024;
+
+/// A running multipart upload
+pub(crate) struct MultipartUpload {
+    commit: oneshot::Sender<()>,
+    // Ok(false) when the writer went away without committing
+    task: JoinHandle<Result<bool>>,
+}
+
+impl MultipartUpload {
+    /// Start the upload and return the pipe to write the object into
+    ///
+    /// The upload is created before this returns, so a backend that rejects
+    /// it (an unknown KMS key, a denied ACL) fails here.
+    pub(crate) async fn start<S: CloudStorage + 'static>(
+        mut storage: S,
+        part_size: usize,
+    ) -> Result<(DuplexStream, MultipartUpload)> {
+        let upload_id = storage.start_upload().await?;
+
+        let (writer, reader) = tokio::io::duplex(PIPE_SIZE);
+        let (commit, committed) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let result = upload_parts(&mut storage, &upload_id, part_size, reader, committed).await;
+            if !matches!(result, Ok(true)) {
+                let _ = storage.abort_upload(&upload_id).await;
+            }
+            result
+        });
+
+        Ok((writer, MultipartUpload { commit, task }))
+    }
+
+    /// Complete the upload once the writer end has been dropped, or abort
+    /// it if writing the object failed
+    ///
+    /// A failed part also breaks the pipe under the writer, so its error is
+    /// reported in place of `written`.
+    pub(crate) async fn finish(self, written: Result<()>) -> Result<()> {
+        let MultipartUpload { commit, task } = self;
+        if written.is_ok() {
+            let _ = commit.send(());
+        } else {
+            drop(commit);
+        }
+        task.await
+            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))??;
+        written
+    }
+}
+
+async fn upload_parts<S: CloudStorage>(
+    storage: &mut S,
+    upload_id: &str,
+    part_size: usize,
+    mut reader: DuplexStream,
+    committed: oneshot::Receiver<()>,
+) -> Result<bool> {
+    let mut parts = Vec::new();
+    let mut buffer = Vec::with_capacity(part_size);
+    loop {
+        // Fill a whole part unless the writer is done
+        buffer.clear();
+        while buffer.len() < part_size {
+            let n = (&mut reader)
+                .take((part_size - buffer.len()) as u64)
+                .read_to_end(&mut buffer)
+                .await?;
+            if n == 0 {
+                break;
+            }
+        }
+        let last = buffer.len() < part_size;
+        if !buffer.is_empty() || parts.is_empty() {
+            let part_number = parts.len() as u32 + 1;
+            let etag = storage.upload_part(upload_id, part_number, &buffer).await?;
+            parts.push((part_number, etag));
+        }
+        if last {
+            break;
+        }
+    }
+
+    // The pipe also closes when the writer is dropped without saving
+    if committed.await.is_err() {
+        return Ok(false);
+    }
+
+    storage.complete_upload(upload_id, parts).await?;
+    Ok(true)
+}
@@ -0,0 +1,201 @@
+//! Object-level settings for uploads: encryption, ACL, storage class,
+//! caching and metadata
+//!
+//! [`ObjectOptions`] is applied when the upload is created, so the object is
+//! encrypted and tagged from its first byte. No copy after the upload is
+//! needed.
+
+use indexmap::IndexMap;
+
+/// Server-side encryption for an uploaded object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerSideEncryption {
+    /// SSE-S3: keys managed by S3 (`AES256`)
+    S3,
+    /// SSE-KMS with the given key id or ARN, or the account's default
+    /// `aws/s3` key when None
+    Kms { key_id: Option<String> },
+}
+
+/// Settings applied to an object when it is created
+///
+/// # Example
+///
+/// ```
+/// use excelstream::cloud::ObjectOptions;
+///
+/// let options = ObjectOptions::new()
+///     .sse_kms("arn:aws:kms:us-east-1:123456789012:key/abcd")
+///     .storage_class("STANDARD_IA")
+///     .cache_control("no-store")
+///     .metadata("department", "finance")
+///     .tag("retention", "7y");
+/// assert!(!options.is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectOptions {
+    pub server_side_encryption: Option<ServerSideEncryption>,
+    /// Canned ACL, e.g. "private" or "bucket-owner-full-control"
+    pub acl: Option<String>,
+    /// Storage class, e.g. "STANDARD_IA" or "GLACIER_IR"
+    pub storage_class: Option<String>,
+    pub cache_control: Option<String>,
+    /// User metadata, sent as `x-amz-meta-*` headers
+    pub metadata: IndexMap<String, String>,
+    pub tags: IndexMap<String, String>,
+}
+
+impl ObjectOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encrypt with S3-managed keys (SSE-S3)
+    pub fn sse_s3(mut self) -> Self {
+        self.server_side_encryption = Some(ServerSideEncryption::S3);
+        self
+    }
+
+    /// Encrypt with a KMS key (SSE-KMS)
+    pub fn sse_kms(mut self, key_id: impl Into<String>) -> Self {
+        self.server_side_encryption = Some(ServerSideEncryption::Kms {
+            key_id: Some(key_id.into()),
+        });
+        self
+    }
+
+    /// Set the canned ACL
+    pub fn acl(mut self, acl: impl Into<String>) -> Self {
+        self.acl = Some(acl.into());
+        self
+    }
+
+    /// Set the storage class
+    pub fn storage_class(mut self, class: impl Into<String>) -> Self {
+        self.storage_class = Some(class.into());
+        self
+    }
+
+    /// Set the Cache-Control header stored with the object
+    pub fn cache_control(mut self, value: impl Into<String>) -> Self {
+        self.cache_control = Some(value.into());
+        self
+    }
+
+    /// Add a user metadata entry
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add an object tag
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Whether no option is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Tags in the URL-encoded form of the `x-amz-tagging` header
+    pub(crate) fn tagging(&self) -> Option<String> {
+        if self.tags.is_empty() {
+            return None;
+        }
+        let pairs: Vec<String> = self
+            .tags
+            .iter()
+            .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+            .collect();
+        Some(pairs.join("&"))
+    }
+}
+
+/// Apply [`ObjectOptions`] to an S3 request builder that creates an object
+/// (`create_multipart_upload`, `put_object` or `copy_object`)
+#[cfg(feature = "cloud-s3")]
+macro_rules! apply_object_options {
+    ($builder:expr, $options:expr) => {{
+        use aws_sdk_s3::types::{ObjectCannedAcl, ServerSideEncryption as Sse, StorageClass};
+        let options: &$crate::cloud::ObjectOptions = $options;
+        let mut builder = $builder;
+        match &options.server_side_encryption {
+            Some($crate::cloud::ServerSideEncryption::S3) => {
+                builder = builder.server_side_encryption(Sse::Aes256);
+            }
+            Some($crate::cloud::ServerSideEncryption::Kms { key_id }) => {
+                builder = builder.server_side_encryption(Sse::AwsKms);
+                if let Some(key_id) = key_id {
+                    builder = builder.ssekms_key_id(key_id);
+                }
+            }
+            None => {}
+        }
+        if let Some(acl) = &options.acl {
+            builder = builder.acl(ObjectCannedAcl::from(acl.as_str()));
+        }
+        if let Some(class) = &options.storage_class {
+            builder = builder.storage_class(StorageClass::from(class.as_str()));
+        }
+        if let Some(cache_control) = &options.cache_control {
+            builder = builder.cache_control(cache_control);
+        }
+        for (key, value) in &options.metadata {
+            builder = builder.metadata(key, value);
+        }
+        if let Some(tagging) = options.tagging() {
+            builder = builder.tagging(tagging);
+        }
+        builder
+    }};
+}
+
+#[cfg(feature = "cloud-s3")]
+pub(crate) use apply_object_options;
+
+// Percent-encode everything but RFC 3986 unreserved characters
+fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_builder_and_tagging() {
+        assert!(ObjectOptions::new().is_empty());
+        assert_eq!(ObjectOptions::new().tagging(), None);
+
+        let options = ObjectOptions::new()
+            .sse_kms("alias/reports")
+            .acl("bucket-owner-full-control")
+            .tag("team", "data & ml")
+            .tag("env", "prod");
+        assert_eq!(
+            options.server_side_encryption,
+            Some(ServerSideEncryption::Kms {
+                key_id: Some("alias/reports".to_string())
+            })
+        );
+        assert_eq!(
+            options.tagging().as_deref(),
+            Some("team=data%20%26%20ml&env=prod")
+        );
+        assert_eq!(
+            ObjectOptions::new().sse_s3().server_side_encryption,
+            Some(ServerSideEncryption::S3)
+        );
+    }
+}
@@ -3,9 +3,12 @@
 //! Transfer files between different cloud storage services (S3, GCS, etc.)
 //! without downloading to local disk. Perfect for replicate and disaster recovery.
 
+use super::ObjectOptions;
 use crate::error::{ExcelError, Result};
 use std::sync::Arc;
 
+#[cfg(feature = "cloud-s3")]
+use super::object_options::apply_object_options;
 #[cfg(feature = "cloud-s3")]
 use aws_sdk_s3::Client as S3Client;
 
@@ -39,12 +42,20 @@ pub struct CloudDestination {
 }
 
 /// Cloud-to-Cloud replicate configuration
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReplicateConfig {
     pub source: CloudSource,
     pub destination: CloudDestination,
     pub chunk_size: usize, // Default: 5MB
     pub max_retries: u32,
+    /// Encryption, ACL, storage class, metadata and tags for the copy
+    pub destination_options: ObjectOptions,
+    pub verify: bool,       // Default: false
+    pub concurrency: usize, // Default: 1 (sequential)
+    /// Globs a key must match to be copied by `execute_prefix` (empty: all)
+    pub include: Vec<String>,
+    /// Globs that exclude a key from `execute_prefix`
+    pub exclude: Vec<String>,
 }
 
 impl ReplicateConfig {
@@ -55,6 +66,11 @@ impl ReplicateConfig {
             destination,
             chunk_size: 5 * 1024 * 1024, // 5MB
             max_retries: 3,
+            destination_options: ObjectOptions::default(),
+            verify: false,
+            concurrency: 1,
+            include: Vec::new(),
+            exclude: Vec::new(),
         }
     }
 
@@ -69,6 +85,165 @@ impl ReplicateConfig {
         self.max_retries = retries;
         self
     }
+
+    /// Set object options for the destination
+    ///
+    /// Metadata and tags given here replace the source object's. Without
+    /// them, a server-side copy keeps the source's and a streamed copy has
+    /// none.
+    pub fn with_destination_options(mut self, options: ObjectOptions) -> Self {
+        self.destination_options = options;
+        self
+    }
+
+    /// Verify the destination after transfer
+    ///
+    /// Streamed parts are sent with a CRC32 checksum that S3 checks on
+    /// arrival, and a part that fails it is retried like any other failed
+    /// part. Once the copy completes, the destination's size is compared with
+    /// the source's; a mismatch fails with
+    /// [`ExcelError::IntegrityMismatch`]. The result is in
+    /// [`ReplicateStats::verification`].
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Set how many parts a streamed copy transfers at once
+    ///
+    /// Each part in flight holds one chunk in memory, so memory use grows to
+    /// about `concurrency * chunk_size`. Failed parts are retried on their
+    /// own without holding up the others. Values below 1 are treated as 1.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Only copy keys matching this glob in `execute_prefix`
+    ///
+    /// Globs match the key relative to the source prefix. `*` and `?` stay
+    /// within one path segment, `**` spans segments. Can be called more than
+    /// once; a key is copied if it matches any include.
+    pub fn with_include(mut self, glob: impl Into<String>) -> Self {
+        self.include.push(glob.into());
+        self
+    }
+
+    /// Skip keys matching this glob in `execute_prefix`
+    ///
+    /// Excludes win over includes.
+    pub fn with_exclude(mut self, glob: impl Into<String>) -> Self {
+        self.exclude.push(glob.into());
+        self
+    }
+
+    /// Whether `execute_prefix` copies a key, given relative to the source
+    /// prefix
+    pub fn selects(&self, relative_key: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|g| glob_match(g, relative_key)))
+            && !self.exclude.iter().any(|g| glob_match(g, relative_key))
+    }
+}
+
+/// Report of a prefix replicate: one entry per object under the prefix
+#[derive(Debug, Clone)]
+pub struct ReplicateManifest {
+    pub source_prefix: String,
+    pub destination_prefix: String,
+    pub entries: Vec<ManifestEntry>,
+    pub start_time: std::time::Instant,
+}
+
+/// One object of a prefix replicate
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub source_key: String,
+    pub destination_key: String,
+    /// Source size from the listing
+    pub size: u64,
+    pub status: ManifestStatus,
+}
+
+/// What happened to one object of a prefix replicate
+#[derive(Debug, Clone)]
+pub enum ManifestStatus {
+    Copied(ReplicateStats),
+    /// Filtered out by the include/exclude globs
+    Skipped,
+    /// The copy failed; the batch carried on with the next object
+    Failed(String),
+}
+
+impl ReplicateManifest {
+    /// Number of objects copied
+    pub fn copied(&self) -> usize {
+        self.count(|status| matches!(status, ManifestStatus::Copied(_)))
+    }
+
+    /// Number of objects skipped by the filters
+    pub fn skipped(&self) -> usize {
+        self.count(|status| matches!(status, ManifestStatus::Skipped))
+    }
+
+    /// Number of objects whose copy failed
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, ManifestStatus::Failed(_)))
+    }
+
+    /// Whether every selected object was copied
+    pub fn is_complete(&self) -> bool {
+        self.failed() == 0
+    }
+
+    /// Bytes transferred over all copied objects
+    pub fn bytes_transferred(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(|entry| match &entry.status {
+                ManifestStatus::Copied(stats) => stats.bytes_transferred,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Get elapsed time
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.start_time.elapsed()
+    }
+
+    /// The manifest as CSV: `status,source_key,destination_key,size,detail`
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("status,source_key,destination_key,size,detail\n");
+        for entry in &self.entries {
+            let (status, detail) = match &entry.status {
+                ManifestStatus::Copied(stats) => match &stats.verification {
+                    Some(report) => (
+                        "copied",
+                        format!("verified {} bytes", report.destination_size),
+                    ),
+                    None => ("copied", String::new()),
+                },
+                ManifestStatus::Skipped => ("skipped", String::new()),
+                ManifestStatus::Failed(error) => ("failed", error.clone()),
+            };
+            out.push_str(&format!(
+                "{},{},{},{},{}\n",
+                status,
+                csv_field(&entry.source_key),
+                csv_field(&entry.destination_key),
+                entry.size,
+                csv_field(&detail)
+            ));
+        }
+        out
+    }
+
+    fn count(&self, pred: impl Fn(&ManifestStatus) -> bool) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| pred(&entry.status))
+            .count()
+    }
 }
 
 /// Replicate status and statistics
@@ -78,6 +253,20 @@ pub struct ReplicateStats {
     pub chunks_transferred: u32,
     pub start_time: std::time::Instant,
     pub errors: Vec<String>,
+    /// Set when the transfer was verified (see `with_verification`)
+    pub verification: Option<VerificationReport>,
+}
+
+/// What was checked after a verified transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationReport {
+    pub source_size: u64,
+    pub destination_size: u64,
+    /// Parts whose CRC32 checksum was confirmed (0 for a server-side copy)
+    pub parts_verified: u32,
+    /// Part uploads repeated after a failure or a checksum mismatch
+    pub parts_retried: u32,
+    pub destination_etag: Option<String>,
 }
 
 impl ReplicateStats {
@@ -118,6 +307,7 @@ impl CloudReplicate {
                 chunks_transferred: 0,
                 start_time: std::time::Instant::now(),
                 errors: Vec::new(),
+                verification: None,
             })),
             #[cfg(feature = "cloud-s3")]
             source_client: None,
@@ -145,6 +335,7 @@ impl CloudReplicate {
                 chunks_transferred: 0,
                 start_time: std::time::Instant::now(),
                 errors: Vec::new(),
+                verification: None,
             })),
             source_client: Some(Arc::new(source_client)),
             dest_client: Some(Arc::new(dest_client)),
@@ -161,6 +352,7 @@ impl CloudReplicate {
                 chunks_transferred: 0,
                 start_time: std::time::Instant::now(),
                 errors: Vec::new(),
+                verification: None,
             })),
             source_client: Some(Arc::new(source_client)),
             dest_client: None,
@@ -177,6 +369,7 @@ impl CloudReplicate {
                 chunks_transferred: 0,
                 start_time: std::time::Instant::now(),
                 errors: Vec::new(),
+                verification: None,
             })),
             source_client: None,
             dest_client: Some(Arc::new(dest_client)),
@@ -240,8 +433,134 @@ impl CloudReplicate {
         }
     }
 
+    /// Copy every object under the source key, used as a prefix
+    ///
+    /// Each object's key relative to `source.key` is appended to
+    /// `destination.key`, so `exports/2024-12/` to `backup/2024-12/` copies
+    /// `exports/2024-12/a.xlsx` to `backup/2024-12/a.xlsx`. Objects are
+    /// filtered with the include/exclude globs and copied one after another,
+    /// each with the rest of the configuration (verification, concurrency,
+    /// object options). A failed object is recorded in the manifest and does
+    /// not stop the batch; only a failed listing returns an error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::cloud::replicate::{CloudReplicate, ReplicateConfig, CloudSource, CloudDestination, CloudProvider};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let source = CloudSource {
+    ///         provider: CloudProvider::S3,
+    ///         bucket: "reports".to_string(),
+    ///         key: "exports/2024-12/".to_string(),
+    ///         region: Some("us-east-1".to_string()),
+    ///         endpoint_url: None,
+    ///     };
+    ///
+    ///     let destination = CloudDestination {
+    ///         provider: CloudProvider::S3,
+    ///         bucket: "reports-backup".to_string(),
+    ///         key: "nightly/2024-12/".to_string(),
+    ///         region: Some("us-west-2".to_string()),
+    ///         endpoint_url: None,
+    ///     };
+    ///
+    ///     let config = ReplicateConfig::new(source, destination)
+    ///         .with_include("**/*.xlsx")
+    ///         .with_exclude("tmp/**");
+    ///
+    ///     let manifest = CloudReplicate::new(config).execute_prefix().await?;
+    ///     println!("Copied {} objects, {} failed", manifest.copied(), manifest.failed());
+    ///     std::fs::write("manifest.csv", manifest.to_csv())?;
+    ///     Ok(())
+    /// }
+    /// ```
     #[cfg(feature = "cloud-s3")]
-    async fn s3_to_s3(&self) -> Result<ReplicateStats> {
+    pub async fn execute_prefix(&self) -> Result<ReplicateManifest> {
+        if !matches!(
+            (
+                &self.config.source.provider,
+                &self.config.destination.provider
+            ),
+            (CloudProvider::S3, CloudProvider::S3)
+        ) {
+            return Err(ExcelError::InvalidState(
+                "Prefix replicate is only supported from S3 to S3".to_string(),
+            ));
+        }
+
+        let start_time = std::time::Instant::now();
+        let (source_client, dest_client) = self.s3_clients().await;
+        let source_prefix = &self.config.source.key;
+        let destination_prefix = &self.config.destination.key;
+
+        let mut entries = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let page = source_client
+                .list_objects_v2()
+                .bucket(&self.config.source.bucket)
+                .prefix(source_prefix)
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+
+            for object in page.contents() {
+                let Some(source_key) = object.key() else {
+                    continue;
+                };
+                let relative_key = &source_key[source_prefix.len().min(source_key.len())..];
+                if relative_key.is_empty() {
+                    continue; // The prefix's own folder marker
+                }
+                let destination_key = format!("{}{}", destination_prefix, relative_key);
+
+                let status = if !self.config.selects(relative_key) {
+                    ManifestStatus::Skipped
+                } else {
+                    let mut config = self.config.clone();
+                    config.source.key = source_key.to_string();
+                    config.destination.key = destination_key.clone();
+                    let replicate = CloudReplicate::with_clients(
+                        config,
+                        source_client.clone(),
+                        dest_client.clone(),
+                    );
+                    match replicate.execute().await {
+                        Ok(stats) => ManifestStatus::Copied(stats),
+                        Err(e) => ManifestStatus::Failed(e.to_string()),
+                    }
+                };
+
+                entries.push(ManifestEntry {
+                    source_key: source_key.to_string(),
+                    destination_key,
+                    size: object.size().unwrap_or(0) as u64,
+                    status,
+                });
+            }
+
+            match page.next_continuation_token() {
+                Some(token) if page.is_truncated() == Some(true) => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(ReplicateManifest {
+            source_prefix: source_prefix.clone(),
+            destination_prefix: destination_prefix.clone(),
+            entries,
+            start_time,
+        })
+    }
+
+    /// Given clients, or clients for the configured regions and endpoints
+    #[cfg(feature = "cloud-s3")]
+    async fn s3_clients(&self) -> (S3Client, S3Client) {
         let source_region = self
             .config
             .source
@@ -260,7 +579,7 @@ impl CloudReplicate {
             client.as_ref().clone()
         } else {
             let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-                .region(aws_sdk_s3::config::Region::new(source_region.clone()))
+                .region(aws_sdk_s3::config::Region::new(source_region))
                 .load()
                 .await;
             let mut builder = aws_sdk_s3::config::Builder::from(&config);
@@ -277,7 +596,7 @@ impl CloudReplicate {
             client.as_ref().clone()
         } else {
             let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-                .region(aws_sdk_s3::config::Region::new(dest_region.clone()))
+                .region(aws_sdk_s3::config::Region::new(dest_region))
                 .load()
                 .await;
             let mut builder = aws_sdk_s3::config::Builder::from(&config);
@@ -289,6 +608,20 @@ impl CloudReplicate {
             S3Client::from_conf(builder.build())
         };
 
+        (source_client, dest_client)
+    }
+
+    #[cfg(feature = "cloud-s3")]
+    async fn s3_to_s3(&self) -> Result<ReplicateStats> {
+        let (source_client, dest_client) = self.s3_clients().await;
+        let source_region = self.config.source.region.as_deref().unwrap_or("us-east-1");
+        let dest_region = self
+            .config
+            .destination
+            .region
+            .as_deref()
+            .unwrap_or("us-east-1");
+
         // Check if same region - can use native copy_object (zero memory!)
         if source_region == dest_region
             && self.config.source.endpoint_url == self.config.destination.endpoint_url
@@ -307,11 +640,35 @@ impl CloudReplicate {
 
         let start = std::time::Instant::now();
 
-        client
-            .copy_object()
-            .copy_source(copy_source)
-            .bucket(&self.config.destination.bucket)
-            .key(&self.config.destination.key)
+        let source_size = if self.config.verify {
+            let head = client
+                .head_object()
+                .bucket(&self.config.source.bucket)
+                .key(&self.config.source.key)
+                .send()
+                .await
+                .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+            Some(head.content_length().unwrap_or(0) as u64)
+        } else {
+            None
+        };
+
+        let options = &self.config.destination_options;
+        let mut request = apply_object_options!(
+            client
+                .copy_object()
+                .copy_source(copy_source)
+                .bucket(&self.config.destination.bucket)
+                .key(&self.config.destination.key),
+            options
+        );
+        if !options.metadata.is_empty() {
+            request = request.metadata_directive(aws_sdk_s3::types::MetadataDirective::Replace);
+        }
+        if !options.tags.is_empty() {
+            request = request.tagging_directive(aws_sdk_s3::types::TaggingDirective::Replace);
+        }
+        request
             .send()
             .await
             .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
@@ -327,11 +684,26 @@ impl CloudReplicate {
 
         let actual_size = head.content_length().unwrap_or(0) as u64;
 
+        let verification = match source_size {
+            Some(source_size) => {
+                check_size(&self.destination_name(), source_size, actual_size)?;
+                Some(VerificationReport {
+                    source_size,
+                    destination_size: actual_size,
+                    parts_verified: 0,
+                    parts_retried: 0,
+                    destination_etag: head.e_tag().map(str::to_string),
+                })
+            }
+            None => None,
+        };
+
         Ok(ReplicateStats {
             bytes_transferred: actual_size,
             chunks_transferred: 1,
             start_time: start,
             errors: vec![],
+            verification,
         })
     }
 
@@ -353,10 +725,17 @@ impl CloudReplicate {
         let file_size = head_response.content_length().unwrap_or(0) as u64;
 
         // Initiate multipart upload
-        let multipart = dest_client
-            .create_multipart_upload()
-            .bucket(&self.config.destination.bucket)
-            .key(&self.config.destination.key)
+        let mut request = apply_object_options!(
+            dest_client
+                .create_multipart_upload()
+                .bucket(&self.config.destination.bucket)
+                .key(&self.config.destination.key),
+            &self.config.destination_options
+        );
+        if self.config.verify {
+            request = request.checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32);
+        }
+        let multipart = request
             .send()
             .await
             .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
@@ -366,70 +745,112 @@ impl CloudReplicate {
             .ok_or_else(|| ExcelError::InvalidState("No upload ID".to_string()))?
             .to_string();
 
-        let mut parts = Vec::new();
-        let mut offset = 0;
-        let mut part_number = 1u32;
-
-        // Stream chunks without collecting entire buffer into memory
-        while offset < file_size {
-            let chunk_size = (self.config.chunk_size as u64).min(file_size - offset);
-            let range = format!("bytes={}-{}", offset, offset + chunk_size - 1);
-
-            // Get chunk stream from source
-            let response = source_client
-                .get_object()
-                .bucket(&self.config.source.bucket)
-                .key(&self.config.source.key)
-                .range(&range)
-                .send()
-                .await
-                .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
-
-            // Collect ByteStream into Vec<u8> for proper checksum calculation
-            // This is necessary for S3-compatible services (MinIO, FPT Cloud, etc.)
-            // that strictly validate x-amz-content-sha256 header
-            let byte_stream = response.body;
-            let chunk_bytes = byte_stream
-                .collect()
-                .await
-                .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?
-                .into_bytes();
+        let result = self
+            .s3_stream_parts(source_client, dest_client, upload_id.clone(), file_size)
+            .await;
+        let parts_retried = match result {
+            Ok(parts_retried) => parts_retried,
+            Err(e) => {
+                // Don't leave the parts behind as billed storage
+                let _ = dest_client
+                    .abort_multipart_upload()
+                    .bucket(&self.config.destination.bucket)
+                    .key(&self.config.destination.key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await;
+                return Err(e);
+            }
+        };
 
-            // Upload chunk - SDK can now calculate checksum properly
-            let part_response = dest_client
-                .upload_part()
+        let verification = if self.config.verify {
+            let head = dest_client
+                .head_object()
                 .bucket(&self.config.destination.bucket)
                 .key(&self.config.destination.key)
-                .upload_id(&upload_id)
-                .part_number(part_number as i32)
-                .body(chunk_bytes.to_vec().into())
                 .send()
                 .await
                 .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+            let destination_size = head.content_length().unwrap_or(0) as u64;
+            check_size(&self.destination_name(), file_size, destination_size)?;
+
+            let stats = self.stats.lock().await;
+            Some(VerificationReport {
+                source_size: file_size,
+                destination_size,
+                parts_verified: stats.chunks_transferred,
+                parts_retried,
+                destination_etag: head.e_tag().map(str::to_string),
+            })
+        } else {
+            None
+        };
 
-            if let Some(etag) = part_response.e_tag() {
-                parts.push(
-                    aws_sdk_s3::types::CompletedPart::builder()
-                        .e_tag(etag)
-                        .part_number(part_number as i32)
-                        .build(),
-                );
-            }
+        let mut stats = self.stats.lock().await;
+        stats.verification = verification;
+        Ok(stats.clone())
+    }
+
+    /// Copy every part and complete the upload, returning how many part
+    /// uploads were retried
+    #[cfg(feature = "cloud-s3")]
+    async fn s3_stream_parts(
+        &self,
+        source_client: &S3Client,
+        dest_client: &S3Client,
+        upload_id: String,
+        file_size: u64,
+    ) -> Result<u32> {
+        let copy = Arc::new(PartCopy {
+            source_client: source_client.clone(),
+            dest_client: dest_client.clone(),
+            source_bucket: self.config.source.bucket.clone(),
+            source_key: self.config.source.key.clone(),
+            dest_bucket: self.config.destination.bucket.clone(),
+            dest_key: self.config.destination.key.clone(),
+            upload_id,
+            verify: self.config.verify,
+            max_retries: self.config.max_retries,
+            stats: Arc::clone(&self.stats),
+        });
+        let concurrency = self.config.concurrency.max(1);
+
+        // Dropping the set on error aborts the parts still in flight
+        let mut in_flight = tokio::task::JoinSet::new();
+        let mut parts = Vec::new();
+        let mut parts_retried = 0;
+        let mut offset = 0;
+        let mut part_number = 1u32;
 
-            let mut stats = self.stats.lock().await;
-            stats.bytes_transferred += chunk_size;
-            stats.chunks_transferred += 1;
+        // Stream chunks without collecting entire buffer into memory
+        while offset < file_size || !in_flight.is_empty() {
+            if offset < file_size && in_flight.len() < concurrency {
+                let chunk_size = (self.config.chunk_size as u64).min(file_size - offset);
+                let copy = Arc::clone(&copy);
+                in_flight.spawn(async move { copy.run(part_number, offset, chunk_size).await });
+
+                offset += chunk_size;
+                part_number += 1;
+                continue;
+            }
 
-            offset += chunk_size;
-            part_number += 1;
+            if let Some(joined) = in_flight.join_next().await {
+                let (part, retried) = joined
+                    .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))??;
+                parts.push(part);
+                parts_retried += retried;
+            }
         }
 
+        // Parts finish in any order, S3 wants them ascending
+        parts.sort_by_key(|part| part.part_number());
+
         // Complete multipart upload
         dest_client
             .complete_multipart_upload()
             .bucket(&self.config.destination.bucket)
             .key(&self.config.destination.key)
-            .upload_id(&upload_id)
+            .upload_id(&copy.upload_id)
             .multipart_upload(
                 aws_sdk_s3::types::CompletedMultipartUpload::builder()
                     .set_parts(Some(parts))
@@ -439,13 +860,16 @@ impl CloudReplicate {
             .await
             .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
 
-        let stats = self.stats.lock().await;
-        Ok(ReplicateStats {
-            bytes_transferred: stats.bytes_transferred,
-            chunks_transferred: stats.chunks_transferred,
-            start_time: stats.start_time,
-            errors: stats.errors.clone(),
-        })
+        Ok(parts_retried)
+    }
+
+    /// `s3://bucket/key` of the destination, for error messages
+    #[cfg(feature = "cloud-s3")]
+    fn destination_name(&self) -> String {
+        object_name(
+            &self.config.destination.bucket,
+            &self.config.destination.key,
+        )
     }
 
     #[cfg(not(feature = "cloud-s3"))]
@@ -455,6 +879,13 @@ impl CloudReplicate {
         ))
     }
 
+    #[cfg(not(feature = "cloud-s3"))]
+    pub async fn execute_prefix(&self) -> Result<ReplicateManifest> {
+        Err(ExcelError::InvalidState(
+            "cloud-s3 feature not enabled".to_string(),
+        ))
+    }
+
     #[cfg(feature = "cloud-gcs")]
     async fn s3_to_gcs(&self) -> Result<ReplicateStats> {
         Err(ExcelError::InvalidState(
@@ -477,6 +908,130 @@ impl CloudReplicate {
     }
 }
 
+/// One streamed copy's upload, shared by its part tasks
+#[cfg(feature = "cloud-s3")]
+struct PartCopy {
+    source_client: S3Client,
+    dest_client: S3Client,
+    source_bucket: String,
+    source_key: String,
+    dest_bucket: String,
+    dest_key: String,
+    upload_id: String,
+    verify: bool,
+    max_retries: u32,
+    stats: Arc<tokio::sync::Mutex<ReplicateStats>>,
+}
+
+#[cfg(feature = "cloud-s3")]
+impl PartCopy {
+    /// Copy one part, retrying up to `max_retries` times; returns the part
+    /// and how many retries it took
+    async fn run(
+        &self,
+        part_number: u32,
+        offset: u64,
+        chunk_size: u64,
+    ) -> Result<(aws_sdk_s3::types::CompletedPart, u32)> {
+        let mut attempt = 0;
+        loop {
+            match self.copy(part_number, offset, chunk_size).await {
+                Ok(part) => {
+                    let mut stats = self.stats.lock().await;
+                    stats.bytes_transferred += chunk_size;
+                    stats.chunks_transferred += 1;
+                    return Ok((part, attempt));
+                }
+                Err(e) if attempt < self.max_retries => {
+                    attempt += 1;
+                    self.stats
+                        .lock()
+                        .await
+                        .errors
+                        .push(format!("part {} (attempt {}): {}", part_number, attempt, e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Copy one byte range of the source as one part of the upload
+    async fn copy(
+        &self,
+        part_number: u32,
+        offset: u64,
+        chunk_size: u64,
+    ) -> Result<aws_sdk_s3::types::CompletedPart> {
+        let range = format!("bytes={}-{}", offset, offset + chunk_size - 1);
+        let part_name = format!(
+            "{} part {}",
+            object_name(&self.dest_bucket, &self.dest_key),
+            part_number
+        );
+
+        // Get chunk stream from source
+        let response = self
+            .source_client
+            .get_object()
+            .bucket(&self.source_bucket)
+            .key(&self.source_key)
+            .range(&range)
+            .send()
+            .await
+            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+
+        // Collect ByteStream into Vec<u8> for proper checksum calculation
+        // This is necessary for S3-compatible services (MinIO, FPT Cloud, etc.)
+        // that strictly validate x-amz-content-sha256 header
+        let byte_stream = response.body;
+        let chunk_bytes = byte_stream
+            .collect()
+            .await
+            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?
+            .into_bytes();
+
+        // A short read would otherwise end up as a silently truncated copy
+        check_size(&part_name, chunk_size, chunk_bytes.len() as u64)?;
+
+        let checksum = self.verify.then(|| crc32_base64(&chunk_bytes));
+
+        // Upload chunk - SDK can now calculate checksum properly
+        let part_response = self
+            .dest_client
+            .upload_part()
+            .bucket(&self.dest_bucket)
+            .key(&self.dest_key)
+            .upload_id(&self.upload_id)
+            .part_number(part_number as i32)
+            .set_checksum_crc32(checksum.clone())
+            .body(chunk_bytes.to_vec().into())
+            .send()
+            .await
+            .map_err(|e| ExcelError::IoError(std::io::Error::other(e.to_string())))?;
+
+        // S3 rejects a part whose body doesn't match the checksum; compare
+        // again for stores that accept the header without checking it
+        if let (Some(sent), Some(stored)) = (&checksum, part_response.checksum_crc32()) {
+            if sent != stored {
+                return Err(ExcelError::IntegrityMismatch {
+                    object: part_name,
+                    reason: format!("CRC32 {} sent, {} stored", sent, stored),
+                });
+            }
+        }
+
+        let etag = part_response.e_tag().ok_or_else(|| {
+            ExcelError::InvalidState(format!("No ETag returned for part {}", part_number))
+        })?;
+
+        Ok(aws_sdk_s3::types::CompletedPart::builder()
+            .e_tag(etag)
+            .part_number(part_number as i32)
+            .set_checksum_crc32(checksum)
+            .build())
+    }
+}
+
 /// Builder for CloudReplicate with custom client support
 #[derive(Default)]
 pub struct CloudReplicateBuilder {
@@ -534,6 +1089,72 @@ impl CloudReplicateBuilder {
     }
 }
 
+/// Match `text` against a glob where `*` and `?` don't cross `/` and `**`
+/// does
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(p: &[u8], t: &[u8]) -> bool {
+        match p {
+            [] => t.is_empty(),
+            [b'*', b'*', rest @ ..] => {
+                // "**/" also matches no directory at all
+                let rest_after_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+                (0..=t.len()).any(|i| matches(rest, &t[i..])) || matches(rest_after_slash, t)
+            }
+            [b'*', rest @ ..] => {
+                let segment = t.iter().position(|&c| c == b'/').unwrap_or(t.len());
+                (0..=segment).any(|i| matches(rest, &t[i..]))
+            }
+            [b'?', rest @ ..] => {
+                matches!(t.first(), Some(&c) if c != b'/') && matches(rest, &t[1..])
+            }
+            [c, rest @ ..] => t.first() == Some(c) && matches(rest, &t[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), text.as_bytes())
+}
+
+/// Quote a CSV field if it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `s3://bucket/key`, for error messages
+#[cfg(feature = "cloud-s3")]
+fn object_name(bucket: &str, key: &str) -> String {
+    format!("s3://{}/{}", bucket, key)
+}
+
+/// Fail with [`ExcelError::IntegrityMismatch`] unless `actual == expected`
+#[cfg(feature = "cloud-s3")]
+fn check_size(object: &str, expected: u64, actual: u64) -> Result<()> {
+    if actual == expected {
+        return Ok(());
+    }
+    Err(ExcelError::IntegrityMismatch {
+        object: object.to_string(),
+        reason: format!("expected {} bytes, found {}", expected, actual),
+    })
+}
+
+/// CRC32 of `data` in the base64 form S3 uses for `x-amz-checksum-crc32`
+#[cfg(feature = "cloud-s3")]
+fn crc32_base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bits = crc32fast::hash(data);
+    // 4 bytes encode as 5 full sextets and a 2-bit tail, padded with "=="
+    let mut out = String::with_capacity(8);
+    for shift in [26, 20, 14, 8, 2] {
+        out.push(ALPHABET[((bits >> shift) & 0x3f) as usize] as char);
+    }
+    out.push(ALPHABET[((bits & 0x3) << 4) as usize] as char);
+    out.push_str("==");
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -571,6 +1192,7 @@ mod tests {
             chunks_transferred: 1,
             start_time: start,
             errors: vec![],
+            verification: None,
         };
 
         let speed = stats.speed_mbps();
@@ -598,10 +1220,114 @@ mod tests {
 
         let config = ReplicateConfig::new(source, dest)
             .with_chunk_size(10 * 1024 * 1024)
-            .with_max_retries(5);
+            .with_max_retries(5)
+            .with_destination_options(ObjectOptions::new().sse_s3().storage_class("GLACIER_IR"));
 
         assert_eq!(config.chunk_size, 10 * 1024 * 1024);
         assert_eq!(config.max_retries, 5);
+        assert_eq!(
+            config.destination_options.storage_class.as_deref(),
+            Some("GLACIER_IR")
+        );
+        assert!(!config.verify);
+        assert_eq!(config.concurrency, 1);
+        let config = config.with_verification(true).with_concurrency(8);
+        assert!(config.verify);
+        assert_eq!(config.concurrency, 8);
+        assert_eq!(config.with_concurrency(0).concurrency, 1);
+    }
+
+    #[test]
+    fn test_prefix_filters() {
+        assert!(glob_match("*.xlsx", "a.xlsx"));
+        assert!(!glob_match("*.xlsx", "daily/a.xlsx"));
+        assert!(glob_match("**/*.xlsx", "a.xlsx"));
+        assert!(glob_match("**/*.xlsx", "daily/2024/a.xlsx"));
+        assert!(glob_match("tmp/**", "tmp/x/y.csv"));
+        assert!(glob_match("report-??.csv", "report-01.csv"));
+        assert!(!glob_match("report-??.csv", "report-1.csv"));
+
+        let source = CloudSource {
+            provider: CloudProvider::S3,
+            bucket: "source".to_string(),
+            key: "exports/2024-12/".to_string(),
+            region: None,
+            endpoint_url: None,
+        };
+        let dest = CloudDestination {
+            provider: CloudProvider::S3,
+            bucket: "dest".to_string(),
+            key: "backup/".to_string(),
+            region: None,
+            endpoint_url: None,
+        };
+        let config = ReplicateConfig::new(source, dest);
+        assert!(config.selects("anything.bin"));
+
+        let config = config.with_include("**/*.xlsx").with_exclude("tmp/**");
+        assert!(config.selects("a.xlsx"));
+        assert!(config.selects("daily/a.xlsx"));
+        assert!(!config.selects("a.csv"));
+        assert!(!config.selects("tmp/a.xlsx"));
+    }
+
+    #[test]
+    fn test_manifest_report() {
+        let stats = ReplicateStats {
+            bytes_transferred: 10,
+            chunks_transferred: 1,
+            start_time: std::time::Instant::now(),
+            errors: vec![],
+            verification: None,
+        };
+        let entry = |key: &str, status| ManifestEntry {
+            source_key: format!("in/{}", key),
+            destination_key: format!("out/{}", key),
+            size: 10,
+            status,
+        };
+        let manifest = ReplicateManifest {
+            source_prefix: "in/".to_string(),
+            destination_prefix: "out/".to_string(),
+            entries: vec![
+                entry("a.xlsx", ManifestStatus::Copied(stats)),
+                entry("b.csv", ManifestStatus::Skipped),
+                entry(
+                    "c,d.xlsx",
+                    ManifestStatus::Failed("Access \"denied\"".to_string()),
+                ),
+            ],
+            start_time: std::time::Instant::now(),
+        };
+
+        assert_eq!(
+            (manifest.copied(), manifest.skipped(), manifest.failed()),
+            (1, 1, 1)
+        );
+        assert!(!manifest.is_complete());
+        assert_eq!(manifest.bytes_transferred(), 10);
+        assert_eq!(
+            manifest.to_csv(),
+            "status,source_key,destination_key,size,detail\n\
+             copied,in/a.xlsx,out/a.xlsx,10,\n\
+             skipped,in/b.csv,out/b.csv,10,\n\
+             failed,\"in/c,d.xlsx\",\"out/c,d.xlsx\",10,\"Access \"\"denied\"\"\"\n"
+        );
+    }
+
+    #[cfg(feature = "cloud-s3")]
+    #[test]
+    fn test_verification_helpers() {
+        assert_eq!(crc32_base64(b""), "AAAAAA==");
+        assert_eq!(crc32_base64(b"hello world"), "DUoRhQ==");
+
+        assert!(check_size("s3://b/k", 10, 10).is_ok());
+        let err = check_size("s3://b/k", 10, 7).unwrap_err();
+        assert!(matches!(err, ExcelError::IntegrityMismatch { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Integrity check failed for s3://b/k: expected 10 bytes, found 7"
+        );
     }
 
     #[test]
@@ -21,7 +21,9 @@ use tokio::io::AsyncReadExt;
 ///
 /// Downloads file from S3 to a temporary file, then uses StreamingReader
 /// for efficient row-by-row processing. Temp file is automatically cleaned
-/// up when S3ExcelReader is dropped.
+/// up when S3ExcelReader is dropped. Use
+/// [`S3ExcelReaderBuilder::temp_dir`] to download somewhere other than the
+/// system temp directory.
 ///
 /// # Memory Usage
 ///
@@ -345,6 +347,7 @@ pub struct S3ExcelReaderBuilder {
     region: Option<String>,
     endpoint_url: Option<String>,
     force_path_style: bool,
+    temp_dir: Option<std::path::PathBuf>,
 }
 
 impl Default for S3ExcelReaderBuilder {
@@ -355,6 +358,7 @@ impl Default for S3ExcelReaderBuilder {
             region: Some("us-east-1".to_string()),
             endpoint_url: None,
             force_path_style: false,
+            temp_dir: None,
         }
     }
 }
@@ -412,6 +416,16 @@ impl S3ExcelReaderBuilder {
         self
     }
 
+    /// Directory for the downloaded copy of the object (defaults to the
+    /// system temp directory)
+    ///
+    /// The copy is as large as the object and is deleted when the reader is
+    /// dropped, including when `build` fails part-way.
+    pub fn temp_dir(mut self, dir: impl AsRef<std::path::Path>) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
     /// Build the S3ExcelReader
     ///
     /// # Process
@@ -480,7 +494,7 @@ impl S3ExcelReaderBuilder {
 
         let s3_client = Client::from_conf(s3_config_builder.build());
 
-        Self::build_reader_from_client(s3_client, bucket, key, region_str).await
+        Self::build_reader_from_client(s3_client, bucket, key, region_str, self.temp_dir).await
     }
 
     #[cfg(not(feature = "cloud-s3"))]
@@ -503,7 +517,7 @@ impl S3ExcelReaderBuilder {
 
         let region_str = self.region.unwrap_or_else(|| "us-east-1".to_string());
 
-        Self::build_reader_from_client(s3_client, bucket, key, region_str).await
+        Self::build_reader_from_client(s3_client, bucket, key, region_str, self.temp_dir).await
     }
 
     #[cfg(not(feature = "cloud-s3"))]
@@ -549,6 +563,7 @@ impl S3ExcelReaderBuilder {
     #[cfg(feature = "cloud-s3")]
     async fn create_reader_from_s3_response(
         get_object_output: aws_sdk_s3::operation::get_object::GetObjectOutput,
+        temp_dir: Option<&std::path::Path>,
     ) -> Result<(tempfile::NamedTempFile, StreamingReader)> {
         let mut body = get_object_output.body.into_async_read();
         let mut buffer = Vec::new();
@@ -557,7 +572,11 @@ impl S3ExcelReaderBuilder {
             .await
             .map_err(ExcelError::IoError)?;
 
-        let mut temp_file = tempfile::NamedTempFile::new().map_err(ExcelError::IoError)?;
+        let mut temp_file = match temp_dir {
+            Some(dir) => tempfile::NamedTempFile::new_in(dir),
+            None => tempfile::NamedTempFile::new(),
+        }
+        .map_err(ExcelError::IoError)?;
 
         temp_file.write_all(&buffer).map_err(ExcelError::IoError)?;
         temp_file.flush().map_err(ExcelError::IoError)?;
@@ -574,10 +593,11 @@ impl S3ExcelReaderBuilder {
         bucket: String,
         key: String,
         region_str: String,
+        temp_dir: Option<std::path::PathBuf>,
     ) -> Result<S3ExcelReader> {
         let get_object_output = Self::download_from_s3(&s3_client, &bucket, &key).await?;
         let (temp_file, streaming_reader) =
-            Self::create_reader_from_s3_response(get_object_output).await?;
+            Self::create_reader_from_s3_response(get_object_output, temp_dir.as_deref()).await?;
 
         Ok(S3ExcelReader {
             bucket,
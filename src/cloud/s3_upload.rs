@@ -0,0 +1,111 @@
+//! S3 multipart upload backend with full control over object options
+//!
+//! [`S3Storage`] implements [`CloudStorage`] on the AWS SDK; the S3 writer
+//! streams into it through a [`MultipartUpload`](super::multipart::MultipartUpload)
+//! when object options are set.
+
+use super::object_options::apply_object_options;
+use super::{CloudStorage, ObjectOptions};
+use crate::error::{ExcelError, Result};
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+
+/// Multipart uploads of one S3 object
+pub(crate) struct S3Storage {
+    client: Client,
+    bucket: String,
+    key: String,
+    options: ObjectOptions,
+}
+
+impl S3Storage {
+    pub(crate) fn new(client: Client, bucket: String, key: String, options: ObjectOptions) -> Self {
+        S3Storage {
+            client,
+            bucket,
+            key,
+            options,
+        }
+    }
+}
+
+impl CloudStorage for S3Storage {
+    async fn start_upload(&mut self) -> Result<String> {
+        let created = apply_object_options!(
+            self.client
+                .create_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&self.key),
+            &self.options
+        )
+        .send()
+        .await
+        .map_err(sdk_error)?;
+        created
+            .upload_id()
+            .map(str::to_string)
+            .ok_or_else(|| ExcelError::InvalidState("No upload ID".to_string()))
+    }
+
+    async fn upload_part(
+        &mut self,
+        upload_id: &str,
+        part_number: u32,
+        data: &[u8],
+    ) -> Result<String> {
+        let response = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(upload_id)
+            .part_number(part_number as i32)
+            .body(data.to_vec().into())
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        Ok(response.e_tag().unwrap_or_default().to_string())
+    }
+
+    async fn complete_upload(&mut self, upload_id: &str, parts: Vec<(u32, String)>) -> Result<()> {
+        let parts = parts
+            .into_iter()
+            .map(|(part_number, etag)| {
+                CompletedPart::builder()
+                    .e_tag(etag)
+                    .part_number(part_number as i32)
+                    .build()
+            })
+            .collect();
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        Ok(())
+    }
+
+    async fn abort_upload(&mut self, upload_id: &str) -> Result<()> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        Ok(())
+    }
+}
+
+fn sdk_error(e: impl std::fmt::Display) -> ExcelError {
+    ExcelError::IoError(std::io::Error::other(e.to_string()))
+}
@@ -0,0 +1,642 @@
+//! XLSX generation shared by the cloud writers
+//!
+//! [`WorkbookCore`] produces the XML of every workbook part; the S3, GCS and
+//! HTTP writers only move those bytes into their ZIP stream. Anything added
+//! here (cell types, styles, sheet options) reaches all backends at once.
+
+use crate::cellref;
+use crate::error::{ExcelError, Result};
+use crate::fast_writer::cell_xml::{write_escaped, CellWriter};
+use crate::fast_writer::{FlushPolicy, StyleRegistry};
+use crate::throttle::{Throttle, Throttler};
+use crate::types::{CellStyle, FloatFormat, StyledCell, TimeZonePolicy};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Size of the chunks a spooled sheet is copied out in
+const SPOOL_CHUNK: usize = 64 * 1024;
+
+/// Sheet and row state of a workbook being streamed to a cloud backend
+///
+/// Methods that produce worksheet content return the bytes to append to the
+/// current ZIP entry. They borrow an internal buffer, which is reused for the
+/// next call. With a batching [`FlushPolicy`], rows are held back until a
+/// batch is complete, so a row call may return nothing. With exact
+/// dimensions, rows are spooled until the sheet is finished and no row call
+/// returns anything.
+pub(crate) struct WorkbookCore {
+    worksheets: Vec<String>,
+    styles: StyleRegistry,
+    current_row: u32,
+    max_col: u32,
+    exact_dimensions: bool,
+    spool_dir: Option<PathBuf>,
+    spool: Option<Spool>, // Rows of the current sheet when writing exact dimensions
+    column_widths: Vec<(u32, f64)>, // Written with the first row of the sheet
+    next_row_height: Option<f64>,
+    cells: CellWriter,
+    in_worksheet: bool,
+    in_sheet_data: bool, // `<sheetData>` opened for the current sheet
+    xml: Vec<u8>,
+    flushed: usize, // Bytes at the front of `xml` already returned
+    flush_policy: FlushPolicy,
+    throttler: Option<Throttler>,
+    wait: Option<Duration>, // Owed to the throttle by the last row
+}
+
+// Temp file holding a sheet's rows, and whether it is being copied out
+struct Spool {
+    file: File,
+    reading: bool,
+}
+
+impl WorkbookCore {
+    pub(crate) fn new() -> Self {
+        Self {
+            worksheets: Vec::new(),
+            styles: StyleRegistry::new(),
+            current_row: 0,
+            max_col: 0,
+            exact_dimensions: false,
+            spool_dir: None,
+            spool: None,
+            column_widths: Vec::new(),
+            next_row_height: None,
+            cells: CellWriter::default(),
+            in_worksheet: false,
+            in_sheet_data: false,
+            xml: Vec::with_capacity(4096),
+            flushed: 0,
+            flush_policy: FlushPolicy::STREAMING,
+            throttler: None,
+            wait: None,
+        }
+    }
+
+    pub(crate) fn in_worksheet(&self) -> bool {
+        self.in_worksheet
+    }
+
+    pub(crate) fn current_row(&self) -> u32 {
+        self.current_row
+    }
+
+    pub(crate) fn worksheets(&self) -> &[String] {
+        &self.worksheets
+    }
+
+    /// Write each sheet's `<dimension>` with its exact used range, see
+    /// [`ZeroTempWorkbook::set_exact_dimensions`](crate::fast_writer::ZeroTempWorkbook::set_exact_dimensions)
+    ///
+    /// Rows are spooled to an anonymous temp file (in `dir`, or the system
+    /// temp dir) and come out of [`finish_sheet`](Self::finish_sheet).
+    /// Applies to sheets started afterwards.
+    pub(crate) fn set_exact_dimensions(&mut self, enabled: bool, dir: Option<&Path>) {
+        self.exact_dimensions = enabled;
+        self.spool_dir = dir.map(Path::to_path_buf);
+    }
+
+    /// Start a new worksheet and return its ZIP entry name
+    ///
+    /// The previous sheet must have been closed with [`finish_sheet`](Self::finish_sheet).
+    pub(crate) fn start_sheet(&mut self, name: &str) -> Result<String> {
+        debug_assert!(!self.in_worksheet, "finish_sheet() not called");
+        if self.exact_dimensions {
+            let file = match &self.spool_dir {
+                Some(dir) => tempfile::tempfile_in(dir)?,
+                None => tempfile::tempfile()?,
+            };
+            self.spool = Some(Spool {
+                file,
+                reading: false,
+            });
+        }
+        self.worksheets.push(name.to_string());
+        self.current_row = 0;
+        self.max_col = 0;
+        self.in_worksheet = true;
+        Ok(format!("xl/worksheets/sheet{}.xml", self.worksheets.len()))
+    }
+
+    /// Next bytes that close the current worksheet; call until it returns
+    /// nothing (at once when no sheet is open)
+    ///
+    /// A spooled sheet comes out as its start with the `<dimension>`, then
+    /// its rows a chunk at a time, then its end.
+    pub(crate) fn finish_sheet(&mut self) -> Result<&[u8]> {
+        self.xml.drain(..self.flushed);
+        self.flushed = 0;
+        if let Some(spool) = self.spool.as_mut() {
+            if !spool.reading {
+                // Rows held back by the flush policy go after the spooled ones
+                spool.file.write_all(&self.xml)?;
+                spool.file.seek(SeekFrom::Start(0))?;
+                spool.reading = true;
+                self.xml.clear();
+                let dimension = if self.current_row == 0 || self.max_col == 0 {
+                    "A1".to_string()
+                } else {
+                    format!(
+                        "A1:{}",
+                        cellref::to_a1(self.current_row - 1, self.max_col - 1)
+                    )
+                };
+                self.open_sheet_data(Some(&dimension));
+                self.flushed = self.xml.len();
+                return Ok(&self.xml);
+            }
+            self.xml.resize(SPOOL_CHUNK, 0);
+            let n = spool.file.read(&mut self.xml)?;
+            self.xml.truncate(n);
+            if n > 0 {
+                self.flushed = n;
+                return Ok(&self.xml);
+            }
+            self.spool = None;
+        }
+        if self.in_worksheet {
+            if !self.in_sheet_data {
+                self.open_sheet_data(None);
+            }
+            self.xml.extend_from_slice(b"</sheetData></worksheet>");
+            self.in_worksheet = false;
+            self.in_sheet_data = false;
+            self.column_widths.clear();
+        }
+        self.flushed = self.xml.len();
+        Ok(&self.xml)
+    }
+
+    /// Set a column width (0-based column) for the current or next worksheet
+    pub(crate) fn set_column_width(&mut self, col: u32, width: f64) -> Result<()> {
+        crate::types::check_column_width(col, width)?;
+        if self.in_sheet_data {
+            return Err(ExcelError::InvalidState(
+                "Column widths must be set before writing rows".to_string(),
+            ));
+        }
+        self.column_widths.retain(|(c, _)| *c != col);
+        self.column_widths.push((col, width));
+        Ok(())
+    }
+
+    /// Set the height in points of the next row written
+    pub(crate) fn set_next_row_height(&mut self, height: f64) {
+        self.next_row_height = Some(height);
+    }
+
+    pub(crate) fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.flush_policy = policy;
+    }
+
+    pub(crate) fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.throttler = throttle.map(Throttler::new);
+    }
+
+    /// How long to wait before the next row to stay within the throttle
+    pub(crate) fn take_wait(&mut self) -> Option<Duration> {
+        self.wait.take()
+    }
+
+    pub(crate) fn set_float_format(&mut self, format: FloatFormat) {
+        self.cells.float_format = format;
+    }
+
+    pub(crate) fn set_time_zone_policy(&mut self, policy: TimeZonePolicy) {
+        self.cells.time_zone = policy;
+    }
+
+    pub(crate) fn register_number_format(&mut self, code: &str) -> CellStyle {
+        CellStyle::NumberFormat(self.styles.register_num_fmt(code))
+    }
+
+    /// XML of a row of inline string cells; empty strings leave the cell blank
+    pub(crate) fn text_row<I, S>(&mut self, values: I) -> Result<&[u8]>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let (row_start, height) = self.begin_row();
+        let cells = self.cells.text_row(
+            &mut self.xml,
+            &mut self.styles,
+            self.current_row,
+            height,
+            values,
+        );
+        self.end_row(row_start, height, cells)
+    }
+
+    /// XML of a row of typed, styled cells
+    pub(crate) fn styled_row(&mut self, cells: &[StyledCell]) -> Result<&[u8]> {
+        let (row_start, height) = self.begin_row();
+        let cells = self.cells.styled_row(
+            &mut self.xml,
+            &mut self.styles,
+            self.current_row,
+            height,
+            cells.iter().map(|cell| (&cell.value, cell.style)),
+        );
+        self.end_row(row_start, height, cells)
+    }
+
+    /// The non-worksheet parts as (entry name, content), in write order
+    pub(crate) fn package_parts(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("[Content_Types].xml", self.content_types_xml()),
+            ("_rels/.rels", RELS_XML.to_string()),
+            ("xl/workbook.xml", self.workbook_xml()),
+            ("xl/_rels/workbook.xml.rels", self.workbook_rels_xml()),
+            ("xl/styles.xml", self.styles.to_xml()),
+            ("xl/sharedStrings.xml", SHARED_STRINGS_XML.to_string()),
+            ("docProps/app.xml", APP_XML.to_string()),
+            ("docProps/core.xml", CORE_XML.to_string()),
+        ]
+    }
+
+    // Drop what was returned and start the next row, emitting the sheet
+    // start first if this is the sheet's first row; returns where the row
+    // starts in the buffer and its height
+    fn begin_row(&mut self) -> (usize, Option<f64>) {
+        self.xml.drain(..self.flushed);
+        self.flushed = 0;
+        let row_start = self.xml.len();
+        if !self.in_sheet_data {
+            if self.spool.is_some() {
+                // The sheet start is written with the dimension on finish
+                self.in_sheet_data = true;
+            } else {
+                self.open_sheet_data(None);
+            }
+        }
+        self.current_row += 1;
+        (row_start, self.next_row_height.take())
+    }
+
+    // The batch to write after a row; a row over one of Excel's limits
+    // was left out of the buffer and is not counted
+    fn end_row(
+        &mut self,
+        row_start: usize,
+        height: Option<f64>,
+        cells: Result<u32>,
+    ) -> Result<&[u8]> {
+        let cells = match cells {
+            Ok(cells) => cells,
+            Err(err) => {
+                self.current_row -= 1;
+                self.next_row_height = height;
+                return Err(err);
+            }
+        };
+        self.max_col = self.max_col.max(cells);
+        self.take_batch(row_start)
+    }
+
+    // The buffered rows to write now, per the flush policy, given that the
+    // row just built starts at `row_start`; also charges the row to the
+    // throttle. A spooled sheet takes the batch itself and returns nothing.
+    fn take_batch(&mut self, row_start: usize) -> Result<&[u8]> {
+        if let Some(throttler) = self.throttler.as_mut() {
+            self.wait = throttler.record(self.xml.len() - row_start);
+        }
+        self.flushed = self
+            .flush_policy
+            .flush_len(row_start, self.xml.len() - row_start);
+        if let Some(spool) = self.spool.as_mut() {
+            spool.file.write_all(&self.xml[..self.flushed])?;
+            self.xml.drain(..self.flushed);
+            self.flushed = 0;
+        }
+        Ok(&self.xml[..self.flushed])
+    }
+
+    fn open_sheet_data(&mut self, dimension: Option<&str>) {
+        self.xml.extend_from_slice(
+            br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+"#,
+        );
+        if let Some(dimension) = dimension {
+            self.xml
+                .extend_from_slice(format!("<dimension ref=\"{}\"/>", dimension).as_bytes());
+        }
+        if !self.column_widths.is_empty() {
+            self.column_widths.sort_by_key(|(col, _)| *col);
+            self.xml.extend_from_slice(b"<cols>");
+            for (col, width) in &self.column_widths {
+                self.xml.extend_from_slice(
+                    format!(
+                        "<col min=\"{0}\" max=\"{0}\" width=\"{1}\" customWidth=\"1\"/>",
+                        col + 1,
+                        width
+                    )
+                    .as_bytes(),
+                );
+            }
+            self.xml.extend_from_slice(b"</cols>");
+        }
+        self.xml.extend_from_slice(b"<sheetData>");
+        self.in_sheet_data = true;
+    }
+
+    fn content_types_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
+<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>
+<Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+<Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/>"#,
+        );
+        for i in 1..=self.worksheets.len() {
+            xml.push_str(&format!(
+                r#"
+<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                i
+            ));
+        }
+        xml.push_str("\n</Types>");
+        xml
+    }
+
+    fn workbook_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<sheets>"#,
+        );
+        for (i, name) in self.worksheets.iter().enumerate() {
+            let mut escaped = Vec::new();
+            write_escaped(&mut escaped, name);
+            xml.push_str(&format!(
+                r#"
+<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+                String::from_utf8_lossy(&escaped),
+                i + 1,
+                i + 1
+            ));
+        }
+        xml.push_str("\n</sheets>\n</workbook>");
+        xml
+    }
+
+    fn workbook_rels_xml(&self) -> String {
+        let count = self.worksheets.len();
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        );
+        for i in 1..=count {
+            xml.push_str(&format!(
+                r#"
+<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+                i, i
+            ));
+        }
+        xml.push_str(&format!(
+            r#"
+<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/>
+</Relationships>"#,
+            count + 1,
+            count + 2
+        ));
+        xml
+    }
+}
+
+const RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/>
+</Relationships>"#;
+
+const SHARED_STRINGS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="0" uniqueCount="0"/>
+"#;
+
+const APP_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties">
+<Application>ExcelStream</Application>
+</Properties>"#;
+
+const CORE_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+<dc:creator>ExcelStream</dc:creator>
+</cp:coreProperties>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fast_writer::StreamingZipWriter;
+    use crate::types::{CellValue, MAX_FORMULA_LEN};
+
+    // Everything that closes the current sheet
+    fn finish(core: &mut WorkbookCore) -> Vec<u8> {
+        let mut end = Vec::new();
+        loop {
+            let chunk = core.finish_sheet().unwrap();
+            if chunk.is_empty() {
+                return end;
+            }
+            end.extend_from_slice(chunk);
+        }
+    }
+
+    // Assemble a workbook the way the cloud writers do, into a local file
+    fn write_workbook(
+        path: &std::path::Path,
+        core: &mut WorkbookCore,
+        build: impl FnOnce(&mut WorkbookCore, &mut Vec<(String, Vec<u8>)>),
+    ) {
+        let mut sheets = Vec::new();
+        build(core, &mut sheets);
+        if let Some((_, data)) = sheets.last_mut() {
+            data.extend_from_slice(&finish(core));
+        }
+
+        let mut zip = StreamingZipWriter::new(path).unwrap();
+        for (name, data) in &sheets {
+            zip.start_entry(name).unwrap();
+            zip.write_data(data).unwrap();
+        }
+        for (name, xml) in core.package_parts() {
+            zip.start_entry(name).unwrap();
+            zip.write_data(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_styles_widths_and_sheets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.xlsx");
+        write_workbook(&path, &mut WorkbookCore::new(), |core, sheets| {
+            core.set_column_width(1, 25.0).unwrap();
+            assert!(core.set_column_width(0, f64::NAN).is_err());
+            assert!(core.set_column_width(0, 256.0).is_err());
+            sheets.push((core.start_sheet("Sales").unwrap(), Vec::new()));
+            let header = core
+                .styled_row(&[
+                    StyledCell::new(CellValue::String("Item".into()), CellStyle::HeaderBold),
+                    StyledCell::new(CellValue::String("Total".into()), CellStyle::HeaderBold),
+                ])
+                .unwrap();
+            sheets[0].1.extend_from_slice(header);
+            assert!(core.set_column_width(2, 10.0).is_err());
+
+            let money = core.register_number_format("#,##0.00");
+            core.set_next_row_height(30.0);
+            let row = core
+                .styled_row(&[
+                    StyledCell::new(CellValue::String("A & B".into()), CellStyle::Default),
+                    StyledCell::new(CellValue::Float(12.5), money),
+                ])
+                .unwrap();
+            sheets[0].1.extend_from_slice(row);
+
+            let end = finish(core);
+            sheets[0].1.extend_from_slice(&end);
+            sheets.push((core.start_sheet("Notes").unwrap(), Vec::new()));
+            let row = core.text_row(["note", ""]).unwrap().to_vec();
+            sheets[1].1.extend_from_slice(&row);
+        });
+
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        assert_eq!(reader.sheet_names(), vec!["Sales", "Notes"]);
+        let sales: Vec<_> = reader
+            .rows("Sales")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(sales[1][1], CellValue::Float(12.5));
+        assert_eq!(reader.rows("Notes").unwrap().count(), 1);
+
+        let mut zip = crate::fast_writer::StreamingZipReader::open(&path).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(
+            r#"<cols><col min="2" max="2" width="25" customWidth="1"/></cols><sheetData>"#
+        ));
+        assert!(sheet.contains(r#"<row r="2" spans="1:2" ht="30" customHeight="1">"#));
+        assert!(sheet.contains(r#"<c r="A1" s="1" t="inlineStr">"#));
+        let styles = String::from_utf8(zip.read_entry_by_name("xl/styles.xml").unwrap()).unwrap();
+        assert!(styles.contains("#,##0.00"));
+    }
+
+    #[test]
+    fn test_exact_dimensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.xlsx");
+        let mut core = WorkbookCore::new();
+        core.set_exact_dimensions(true, Some(dir.path()));
+        core.set_flush_policy(FlushPolicy::new(500).with_low_watermark(300));
+        write_workbook(&path, &mut core, |core, sheets| {
+            sheets.push((core.start_sheet("Data").unwrap(), Vec::new()));
+            core.set_column_width(0, 12.0).unwrap();
+            for i in 0..1000 {
+                let row = core.text_row([i.to_string(), "x".repeat(i % 4)]).unwrap();
+                assert!(row.is_empty());
+            }
+            let end = finish(core);
+            sheets[0].1.extend_from_slice(&end);
+            sheets.push((core.start_sheet("Empty").unwrap(), Vec::new()));
+        });
+
+        let mut zip = crate::fast_writer::StreamingZipReader::open(&path).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(r#"<dimension ref="A1:B1000"/><cols>"#));
+        assert!(sheet.ends_with("</sheetData></worksheet>"));
+        let empty =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet2.xml").unwrap()).unwrap();
+        assert!(empty.contains(r#"<dimension ref="A1"/><sheetData></sheetData>"#));
+
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        let rows: Vec<_> = reader.rows("Data").unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 1000);
+        assert_eq!(rows[999].cells[0], CellValue::String("999".to_string()));
+    }
+
+    #[test]
+    fn test_batched_rows() {
+        let rows = |core: &mut WorkbookCore| {
+            let mut chunks = Vec::new();
+            core.start_sheet("Data").unwrap();
+            for i in 0..100 {
+                chunks.push(
+                    core.text_row([i.to_string(), "x".repeat(i % 7 * 10)])
+                        .unwrap()
+                        .to_vec(),
+                );
+            }
+            chunks.push(finish(core));
+            chunks
+        };
+        let streamed = rows(&mut WorkbookCore::new());
+        assert!(streamed.iter().all(|chunk| !chunk.is_empty()));
+
+        let mut core = WorkbookCore::new();
+        core.set_flush_policy(FlushPolicy::new(500).with_low_watermark(300));
+        let batched = rows(&mut core);
+        assert_eq!(batched.concat(), streamed.concat());
+        let written: Vec<_> = batched[..100].iter().filter(|c| !c.is_empty()).collect();
+        assert!(written.len() < 40);
+        assert!(written.iter().all(|c| (300..=500).contains(&c.len())));
+    }
+
+    #[test]
+    fn test_throttle_wait() {
+        let mut core = WorkbookCore::new();
+        core.start_sheet("Sheet1").unwrap();
+        core.text_row(["a"]).unwrap();
+        assert_eq!(core.take_wait(), None);
+
+        core.set_throttle(Some(crate::throttle::Throttle::RowsPerSec(10)));
+        core.text_row(["b"]).unwrap();
+        assert!(core.take_wait().unwrap() > std::time::Duration::from_millis(50));
+        assert_eq!(core.take_wait(), None);
+    }
+
+    #[test]
+    fn test_empty_sheet_is_well_formed() {
+        let mut core = WorkbookCore::new();
+        assert!(core.finish_sheet().unwrap().is_empty());
+        assert_eq!(
+            core.start_sheet("Empty").unwrap(),
+            "xl/worksheets/sheet1.xml"
+        );
+        let xml = String::from_utf8(finish(&mut core)).unwrap();
+        assert!(xml.ends_with("<sheetData></sheetData></worksheet>"));
+        assert!(!core.in_worksheet());
+    }
+
+    #[test]
+    fn test_rows_over_limits_are_dropped() {
+        let mut core = WorkbookCore::new();
+        core.start_sheet("Sheet1").unwrap();
+        core.text_row(["first"]).unwrap();
+        let formula = format!("={}", "1+".repeat(MAX_FORMULA_LEN / 2) + "1");
+        let err = core
+            .styled_row(&[StyledCell::new(
+                CellValue::Formula(formula),
+                CellStyle::Default,
+            )])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExcelError::LimitExceeded { ref what, .. } if what == "Cell A2 formula length"
+        ));
+        assert_eq!(core.current_row(), 1);
+
+        let row = String::from_utf8(core.text_row(["second"]).unwrap().to_vec()).unwrap();
+        assert!(row.starts_with(r#"<row r="2""#));
+    }
+}
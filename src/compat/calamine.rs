@@ -0,0 +1,604 @@
+//! calamine-compatible reading API backed by [`StreamingReader`]
+//!
+//! Code written against calamine's `open_workbook` / `Reader` /
+//! `Range<Data>` API usually only needs its imports changed:
+//!
+//! ```no_run
+//! // use calamine::{open_workbook, Data, DataType, Reader, Xlsx};
+//! use excelstream::compat::calamine::{open_workbook, Data, DataType, Reader, Xlsx};
+//!
+//! let mut workbook: Xlsx<_> = open_workbook("orders.xlsx")?;
+//! if let Ok(range) = workbook.worksheet_range("Orders") {
+//!     for row in range.rows() {
+//!         let total = row.get(3).and_then(|c| c.as_f64()).unwrap_or(0.0);
+//!         println!("{} {}", row[0], total);
+//!     }
+//! }
+//! # Ok::<(), excelstream::ExcelError>(())
+//! ```
+//!
+//! Like calamine, a [`Range`] holds the whole sheet in memory. Large sheets
+//! can be streamed row by row instead with [`Xlsx::worksheet_rows`], or
+//! through the full [`StreamingReader`] API via [`Xlsx::inner_mut`].
+//!
+//! Differences from calamine:
+//! - Only `.xlsx` / `.xlsm` files are read
+//! - Errors are [`ExcelError`]s ([`Error`] and [`XlsxError`] are aliases)
+//! - [`Data::DateTime`] holds the Excel serial number rather than an
+//!   `ExcelDateTime`; [`DataType::as_f64`] works on both
+//! - [`Data::Error`] holds the error literal (`"#N/A"`) rather than a
+//!   `CellErrorType`
+
+use crate::error::{ExcelError, Result};
+use crate::streaming_reader::{ReaderOptions, StreamingReader};
+use crate::types::CellValue;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::marker::PhantomData;
+use std::ops::Index;
+use std::path::Path;
+
+/// calamine's `Error`
+pub type Error = ExcelError;
+/// calamine's `XlsxError`
+pub type XlsxError = ExcelError;
+/// calamine's `Sheets`, returned by [`open_workbook_auto`]
+pub type Sheets<RS> = Xlsx<RS>;
+
+/// A cell value, like calamine's `Data`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Data {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    /// Excel serial date number
+    DateTime(f64),
+    /// Error literal, e.g. `#DIV/0!`
+    Error(String),
+    #[default]
+    Empty,
+}
+
+impl From<CellValue> for Data {
+    fn from(value: CellValue) -> Self {
+        match value {
+            CellValue::Empty => Data::Empty,
+            CellValue::String(s) | CellValue::Formula(s) => Data::String(s),
+            CellValue::Int(i) => Data::Int(i),
+            CellValue::Float(f) => Data::Float(f),
+            CellValue::Percent(p) => Data::Float(p / 100.0),
+            CellValue::Decimal(d) => Data::Float(d.to_f64()),
+            CellValue::Bool(b) => Data::Bool(b),
+            CellValue::DateTime(d) => Data::DateTime(d),
+            CellValue::DateTimeTz(dt) => Data::String(dt.to_rfc3339()),
+            CellValue::RichText(text) => Data::String(text.text()),
+            CellValue::Duration(d) => Data::DateTime(d.as_secs_f64() / 86_400.0),
+            CellValue::Error(e) => Data::Error(e),
+        }
+    }
+}
+
+impl fmt::Display for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Data::Int(i) => write!(f, "{}", i),
+            Data::Float(v) | Data::DateTime(v) => write!(f, "{}", v),
+            Data::String(s) | Data::Error(s) => write!(f, "{}", s),
+            Data::Bool(b) => write!(f, "{}", b),
+            Data::Empty => Ok(()),
+        }
+    }
+}
+
+/// Typed accessors of a cell value, like calamine's `DataType`
+pub trait DataType {
+    fn is_empty(&self) -> bool;
+    fn is_int(&self) -> bool;
+    fn is_float(&self) -> bool;
+    fn is_bool(&self) -> bool;
+    fn is_string(&self) -> bool;
+    fn is_error(&self) -> bool;
+    fn is_datetime(&self) -> bool;
+    fn get_int(&self) -> Option<i64>;
+    fn get_float(&self) -> Option<f64>;
+    fn get_bool(&self) -> Option<bool>;
+    fn get_string(&self) -> Option<&str>;
+    /// Text of strings and numbers
+    fn as_string(&self) -> Option<String>;
+    /// Integer value of numbers, or of text that parses as one
+    fn as_i64(&self) -> Option<i64>;
+    /// Float value of numbers and dates, or of text that parses as one
+    fn as_f64(&self) -> Option<f64>;
+}
+
+impl DataType for Data {
+    fn is_empty(&self) -> bool {
+        matches!(self, Data::Empty)
+    }
+
+    fn is_int(&self) -> bool {
+        matches!(self, Data::Int(_))
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, Data::Float(_))
+    }
+
+    fn is_bool(&self) -> bool {
+        matches!(self, Data::Bool(_))
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(self, Data::String(_))
+    }
+
+    fn is_error(&self) -> bool {
+        matches!(self, Data::Error(_))
+    }
+
+    fn is_datetime(&self) -> bool {
+        matches!(self, Data::DateTime(_))
+    }
+
+    fn get_int(&self) -> Option<i64> {
+        match self {
+            Data::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn get_float(&self) -> Option<f64> {
+        match self {
+            Data::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn get_bool(&self) -> Option<bool> {
+        match self {
+            Data::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn get_string(&self) -> Option<&str> {
+        match self {
+            Data::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_string(&self) -> Option<String> {
+        match self {
+            Data::String(s) => Some(s.clone()),
+            Data::Int(_) | Data::Float(_) => Some(self.to_string()),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Data::Int(i) => Some(*i),
+            Data::Float(f) => Some(*f as i64),
+            Data::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Data::Int(i) => Some(*i as f64),
+            Data::Float(f) | Data::DateTime(f) => Some(*f),
+            Data::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// A rectangle of cells, like calamine's `Range`
+///
+/// Positions passed to [`get_value`](Self::get_value) are absolute 0-based
+/// (row, column); those passed to [`get`](Self::get) and yielded by
+/// [`cells`](Self::cells) are relative to [`start`](Self::start).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Range<T> {
+    start: (u32, u32),
+    end: (u32, u32),
+    inner: Vec<T>,
+}
+
+impl<T: Default + Clone> Range<T> {
+    /// Range from `start` to `end` (inclusive), filled with default values
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> Self {
+        let height = end.0.saturating_sub(start.0) as usize + 1;
+        let width = end.1.saturating_sub(start.1) as usize + 1;
+        Range {
+            start,
+            end,
+            inner: vec![T::default(); height * width],
+        }
+    }
+
+    /// Range without cells
+    pub fn empty() -> Self {
+        Range {
+            start: (0, 0),
+            end: (0, 0),
+            inner: Vec::new(),
+        }
+    }
+
+    /// Smallest range holding every (row, column, value)
+    pub fn from_sparse(cells: Vec<(u32, u32, T)>) -> Self {
+        let Some(first) = cells.first() else {
+            return Self::empty();
+        };
+        let (mut start, mut end) = ((first.0, first.1), (first.0, first.1));
+        for (row, col, _) in &cells {
+            start = (start.0.min(*row), start.1.min(*col));
+            end = (end.0.max(*row), end.1.max(*col));
+        }
+        let mut range = Self::new(start, end);
+        for (row, col, value) in cells {
+            range.set_value((row, col), value);
+        }
+        range
+    }
+
+    /// Set the value at an absolute position inside the range
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside the range.
+    pub fn set_value(&mut self, absolute_position: (u32, u32), value: T) {
+        let index = self
+            .index_of(absolute_position)
+            .expect("position outside of the range");
+        self.inner[index] = value;
+    }
+}
+
+impl<T> Range<T> {
+    fn index_of(&self, (row, col): (u32, u32)) -> Option<usize> {
+        if self.inner.is_empty()
+            || row < self.start.0
+            || row > self.end.0
+            || col < self.start.1
+            || col > self.end.1
+        {
+            return None;
+        }
+        Some((row - self.start.0) as usize * self.width() + (col - self.start.1) as usize)
+    }
+
+    /// Absolute position of the top-left cell
+    pub fn start(&self) -> Option<(u32, u32)> {
+        (!self.inner.is_empty()).then_some(self.start)
+    }
+
+    /// Absolute position of the bottom-right cell
+    pub fn end(&self) -> Option<(u32, u32)> {
+        (!self.inner.is_empty()).then_some(self.end)
+    }
+
+    pub fn width(&self) -> usize {
+        if self.inner.is_empty() {
+            0
+        } else {
+            (self.end.1 - self.start.1) as usize + 1
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.inner.is_empty() {
+            0
+        } else {
+            (self.end.0 - self.start.0) as usize + 1
+        }
+    }
+
+    /// (height, width)
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.height(), self.width())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Value at an absolute (row, column)
+    pub fn get_value(&self, absolute_position: (u32, u32)) -> Option<&T> {
+        self.index_of(absolute_position).map(|i| &self.inner[i])
+    }
+
+    /// Value at a (row, column) relative to the start
+    pub fn get(&self, relative_position: (usize, usize)) -> Option<&T> {
+        let (row, col) = relative_position;
+        (row < self.height() && col < self.width()).then(|| &self.inner[row * self.width() + col])
+    }
+
+    /// Rows as slices
+    pub fn rows(&self) -> std::slice::Chunks<'_, T> {
+        self.inner.chunks(self.width().max(1))
+    }
+
+    /// Every cell as (relative row, relative column, value)
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let width = self.width().max(1);
+        self.inner
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| (i / width, i % width, value))
+    }
+}
+
+impl<T: Default + PartialEq> Range<T> {
+    /// Non-empty cells as (relative row, relative column, value)
+    pub fn used_cells(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let empty = T::default();
+        self.cells().filter(move |(_, _, value)| **value != empty)
+    }
+}
+
+impl<T: fmt::Display> Range<T> {
+    /// Text of the first row, e.g. column headers
+    pub fn headers(&self) -> Option<Vec<String>> {
+        self.rows()
+            .next()
+            .map(|row| row.iter().map(|value| value.to_string()).collect())
+    }
+}
+
+impl<T> Index<usize> for Range<T> {
+    type Output = [T];
+
+    /// Row at a relative index
+    fn index(&self, row: usize) -> &[T] {
+        let width = self.width();
+        &self.inner[row * width..(row + 1) * width]
+    }
+}
+
+impl<T> Index<(usize, usize)> for Range<T> {
+    type Output = T;
+
+    /// Value at a relative (row, column)
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(col < self.width(), "column out of range");
+        &self.inner[row * self.width() + col]
+    }
+}
+
+/// Workbook reader, like calamine's `Reader`
+pub trait Reader<RS>: Sized {
+    type Error;
+
+    /// Open the workbook at `path` (used by [`open_workbook`])
+    fn open_path(path: &Path) -> std::result::Result<Self, Self::Error>;
+
+    /// Sheet names, in workbook order
+    fn sheet_names(&self) -> Vec<String>;
+
+    /// All cell values of a sheet
+    fn worksheet_range(&mut self, name: &str) -> std::result::Result<Range<Data>, Self::Error>;
+
+    /// All cell values of the `n`th sheet
+    fn worksheet_range_at(
+        &mut self,
+        n: usize,
+    ) -> Option<std::result::Result<Range<Data>, Self::Error>> {
+        let name = self.sheet_names().into_iter().nth(n)?;
+        Some(self.worksheet_range(&name))
+    }
+
+    /// Formulas of a sheet, without the leading `=`
+    fn worksheet_formula(&mut self, name: &str) -> std::result::Result<Range<String>, Self::Error>;
+
+    /// Every sheet with its values; sheets that fail to read are left out
+    fn worksheets(&mut self) -> Vec<(String, Range<Data>)> {
+        self.sheet_names()
+            .into_iter()
+            .filter_map(|name| {
+                let range = self.worksheet_range(&name).ok()?;
+                Some((name, range))
+            })
+            .collect()
+    }
+}
+
+/// An xlsx workbook, like calamine's `Xlsx`
+///
+/// `RS` only mirrors calamine's reader type parameter, so that
+/// annotations such as `Xlsx<_>` keep compiling.
+pub struct Xlsx<RS = BufReader<File>> {
+    inner: StreamingReader,
+    _reader: PhantomData<RS>,
+}
+
+impl<RS> Xlsx<RS> {
+    /// The underlying [`StreamingReader`]
+    pub fn inner_mut(&mut self) -> &mut StreamingReader {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> StreamingReader {
+        self.inner
+    }
+
+    /// Stream a sheet row by row instead of loading a [`Range`]
+    ///
+    /// Rows are dense from column A, and blank rows are skipped as usual
+    /// for [`StreamingReader`].
+    pub fn worksheet_rows(
+        &mut self,
+        name: &str,
+    ) -> Result<impl Iterator<Item = Result<Vec<Data>>> + '_> {
+        Ok(self
+            .inner
+            .stream_rows(name)?
+            .map(|row| row.map(|cells| cells.into_iter().map(Data::from).collect())))
+    }
+
+    // (row, column, cell) of every cell `pick` keeps
+    fn sparse<T>(
+        &mut self,
+        name: &str,
+        mut pick: impl FnMut(crate::types::Cell) -> Option<T>,
+    ) -> Result<Vec<(u32, u32, T)>> {
+        let mut cells = Vec::new();
+        for row in self.inner.cells(name)? {
+            for cell in row? {
+                let (row, col) = (cell.row, cell.col);
+                if let Some(value) = pick(cell) {
+                    cells.push((row, col, value));
+                }
+            }
+        }
+        Ok(cells)
+    }
+}
+
+impl Reader<BufReader<File>> for Xlsx<BufReader<File>> {
+    type Error = ExcelError;
+
+    fn open_path(path: &Path) -> Result<Self> {
+        Ok(Xlsx {
+            // Formula cells without a cached value are empty; keep them when
+            // trailing so worksheet_formula sees them
+            inner: StreamingReader::open_with_options(
+                path,
+                ReaderOptions::new().with_keep_trailing_empty(true),
+            )?,
+            _reader: PhantomData,
+        })
+    }
+
+    fn sheet_names(&self) -> Vec<String> {
+        self.inner.sheet_names()
+    }
+
+    fn worksheet_range(&mut self, name: &str) -> Result<Range<Data>> {
+        let cells = self.sparse(name, |cell| match cell.value {
+            CellValue::Empty => None,
+            value => Some(Data::from(value)),
+        })?;
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula(&mut self, name: &str) -> Result<Range<String>> {
+        let cells = self.sparse(name, |cell| {
+            cell.formula
+                .map(|f| f.strip_prefix('=').map_or(f.clone(), str::to_string))
+        })?;
+        Ok(Range::from_sparse(cells))
+    }
+}
+
+/// Open a workbook, like calamine's `open_workbook`
+pub fn open_workbook<R, P>(path: P) -> std::result::Result<R, R::Error>
+where
+    R: Reader<BufReader<File>>,
+    P: AsRef<Path>,
+{
+    R::open_path(path.as_ref())
+}
+
+/// Open a workbook, checking its extension, like calamine's
+/// `open_workbook_auto`
+///
+/// Fails with [`ExcelError::NotSupported`] for formats other than xlsx/xlsm.
+pub fn open_workbook_auto<P: AsRef<Path>>(path: P) -> Result<Sheets<BufReader<File>>> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "xlsx" | "xlsm" => Xlsx::open_path(path),
+        _ => Err(ExcelError::NotSupported(format!(
+            "Cannot open {}: only .xlsx and .xlsm files are supported",
+            path.display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::ExcelWriter;
+
+    #[test]
+    fn test_calamine_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compat.xlsx");
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        writer.write_row(["name", "qty", "price"]).unwrap();
+        writer
+            .write_row_typed(&[
+                CellValue::String("apple".into()),
+                CellValue::Int(3),
+                CellValue::Float(1.25),
+            ])
+            .unwrap();
+        writer
+            .write_row_typed(&[
+                CellValue::String("total".into()),
+                CellValue::Empty,
+                CellValue::Formula("=B2*C2".into()),
+            ])
+            .unwrap();
+        writer.save().unwrap();
+
+        let mut workbook: Xlsx<_> = open_workbook(&path).unwrap();
+        assert_eq!(workbook.sheet_names(), vec!["Sheet1"]);
+        let range = workbook.worksheet_range("Sheet1").unwrap();
+        assert_eq!(range.get_size(), (3, 3));
+        assert_eq!(range.start(), Some((0, 0)));
+        assert_eq!(range.headers().unwrap(), vec!["name", "qty", "price"]);
+        assert_eq!(range.get_value((1, 1)).and_then(|c| c.get_int()), Some(3));
+        assert_eq!(range[(1, 2)].as_f64(), Some(1.25));
+        assert_eq!(range[1][0].get_string(), Some("apple"));
+        assert!(range.get((2, 1)).unwrap().is_empty());
+        assert_eq!(range.rows().count(), 3);
+        // The formula has no cached value, so only worksheet_formula sees it
+        assert_eq!(range.used_cells().count(), 7);
+
+        let formulas = workbook.worksheet_formula("Sheet1").unwrap();
+        assert_eq!(formulas.start(), Some((2, 2)));
+        assert_eq!(
+            formulas.get_value((2, 2)).map(String::as_str),
+            Some("B2*C2")
+        );
+
+        let rows: Vec<Vec<Data>> = workbook
+            .worksheet_rows("Sheet1")
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(rows[1][1], Data::Int(3));
+        assert!(workbook.worksheet_range("Missing").is_err());
+        assert_eq!(workbook.worksheets().len(), 1);
+
+        assert!(open_workbook_auto(&path).is_ok());
+        assert!(matches!(
+            open_workbook_auto(dir.path().join("old.xls")),
+            Err(ExcelError::NotSupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_range_from_sparse() {
+        let range = Range::from_sparse(vec![(2, 3, Data::Int(1)), (4, 1, Data::Bool(true))]);
+        assert_eq!(range.start(), Some((2, 1)));
+        assert_eq!(range.end(), Some((4, 3)));
+        assert_eq!(range.get_size(), (3, 3));
+        assert_eq!(range.get_value((4, 1)), Some(&Data::Bool(true)));
+        assert_eq!(range.get_value((0, 0)), None);
+        assert_eq!(range.get((0, 2)), Some(&Data::Int(1)));
+        assert!(Range::<Data>::empty().is_empty());
+        assert_eq!(Range::<Data>::empty().rows().count(), 0);
+    }
+}
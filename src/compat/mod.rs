@@ -0,0 +1,4 @@
+//! Drop-in APIs of other spreadsheet crates, for migrating to excelstream
+//!
+//! - [`calamine`]: `open_workbook`, `Reader`, `Range` and `Data`
+pub mod calamine;
@@ -3,6 +3,7 @@
 use crate::csv::{CompressionMethod, CsvEncoder};
 use crate::error::{ExcelError, Result};
 use crate::fast_writer::StreamingZipWriter;
+use crate::numfmt::Locale;
 use crate::types::CellValue;
 use std::fs::File;
 use std::io::{BufWriter, Write};
@@ -58,6 +59,7 @@ pub struct CsvWriter {
     delimiter: u8,
     quote_char: u8,
     line_ending: &'static [u8],
+    locale: Option<Locale>,
 }
 
 impl CsvWriter {
@@ -103,6 +105,7 @@ impl CsvWriter {
                 delimiter: b',',
                 quote_char: b'"',
                 line_ending: b"\n",
+                locale: None,
             })
         }
     }
@@ -165,6 +168,7 @@ impl CsvWriter {
             delimiter: b',',
             quote_char: b'"',
             line_ending: b"\n",
+            locale: None,
         })
     }
 
@@ -190,6 +194,32 @@ impl CsvWriter {
         self
     }
 
+    /// Write typed numbers and dates the way a locale does (builder pattern)
+    ///
+    /// Numbers get the locale's decimal separator (no grouping) and dates its
+    /// day/month/year order. Locales with a decimal comma usually pair with
+    /// `;` as the delimiter.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::csv_writer::CsvWriter;
+    /// use excelstream::numfmt::Locale;
+    /// use excelstream::types::CellValue;
+    ///
+    /// let mut writer = CsvWriter::new("data.csv")
+    ///     .unwrap()
+    ///     .delimiter(b';')
+    ///     .locale(Locale::DE_DE);
+    /// writer
+    ///     .write_row_typed(&[CellValue::Float(1234.5), CellValue::DateTime(45322.0)])
+    ///     .unwrap(); // 1234,5;31.01.2024
+    /// ```
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+
     /// Write a row of strings
     ///
     /// # Examples
@@ -250,7 +280,10 @@ impl CsvWriter {
     /// ]).unwrap();
     /// ```
     pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
-        let strings: Vec<String> = cells.iter().map(|c| c.as_string()).collect();
+        let strings: Vec<String> = match &self.locale {
+            Some(locale) => cells.iter().map(|c| localized_text(locale, c)).collect(),
+            None => cells.iter().map(|c| c.as_string()).collect(),
+        };
         let refs: Vec<&str> = strings.iter().map(|s| s.as_str()).collect();
         self.write_row(refs)
     }
@@ -313,6 +346,31 @@ impl CsvWriter {
     }
 }
 
+// Cell text with a locale's decimal separator and date order
+fn localized_text(locale: &Locale, cell: &CellValue) -> String {
+    match cell {
+        CellValue::Float(_) | CellValue::Decimal(_) | CellValue::Percent(_) => {
+            locale.localize_number(&cell.as_string())
+        }
+        CellValue::DateTime(serial) => {
+            let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .expect("valid epoch");
+            let millis = (serial * 86_400_000.0).round() as i64;
+            let Some(datetime) = epoch.checked_add_signed(chrono::Duration::milliseconds(millis))
+            else {
+                return cell.as_string();
+            };
+            let mut format = locale.chrono_date_format();
+            if serial.fract() != 0.0 {
+                format.push_str(" %H:%M:%S");
+            }
+            datetime.format(&format).to_string()
+        }
+        _ => cell.as_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -381,4 +439,25 @@ mod tests {
         std::fs::remove_file(path).ok();
         Ok(())
     }
+
+    #[test]
+    fn test_locale_values() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("de.csv");
+        {
+            let mut writer = CsvWriter::new(&path)?.delimiter(b';').locale(Locale::DE_DE);
+            writer.write_row_typed(&[
+                CellValue::Float(1234.5),
+                CellValue::Int(7),
+                CellValue::DateTime(45322.0),
+                CellValue::DateTime(45322.5),
+                CellValue::String("a.b".to_string()),
+            ])?;
+            writer.save()?;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        assert_eq!(content, "1234,5;7;31.01.2024;31.01.2024 12:00:00;a.b\n");
+        Ok(())
+    }
 }
@@ -0,0 +1,197 @@
+//! Drop duplicate rows on write by key columns
+//!
+//! [`ExcelWriter::dedupe_by`](crate::ExcelWriter::dedupe_by) keeps one row
+//! per distinct projection of the key columns, for feeds that occasionally
+//! send a record twice. Keys are held as 128-bit digests, so memory grows by
+//! a few dozen bytes per distinct key rather than by the rows themselves.
+//!
+//! With [`Keep::First`] a row is written as soon as its key is new and later
+//! repeats are dropped on the spot. With [`Keep::Last`] the rows are spooled
+//! to a temporary file until the sheet is finished, then each key's last row
+//! is written, in the order the rows were written. The number of rows
+//! dropped is reported in [`WriteReport::duplicates_dropped`](crate::types::WriteReport::duplicates_dropped).
+//!
+//! Keys match exactly: text is case-sensitive, and `Int(1)` differs from
+//! `Float(1.0)`. A missing cell matches an empty one.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::dedupe::Keep;
+//! use excelstream::types::CellValue;
+//! use excelstream::ExcelWriter;
+//!
+//! let mut writer = ExcelWriter::new("orders.xlsx")?;
+//! writer.write_header_bold(["Order", "Status"])?;
+//! writer.dedupe_by([0], Keep::Last)?;
+//! writer.write_row_typed(&[CellValue::Int(7), "pending".into()])?;
+//! writer.write_row_typed(&[CellValue::Int(7), "shipped".into()])?;
+//! let report = writer.save()?;
+//! assert_eq!(report.duplicates_dropped, 1); // Order 7 is "shipped"
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::error::Result;
+use crate::sort::{encode_row, next_row};
+use crate::transpose::{encode, ColumnCursor, SpooledColumn};
+use crate::types::{CellValue, StyledCell};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufWriter;
+
+// Read buffer for the spool while the kept rows are written
+const READ_BUFFER: usize = 64 * 1024;
+
+/// Which row of a set of duplicates is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Keep {
+    /// The first row with the key; repeats are dropped as they come
+    #[default]
+    First,
+    /// The last row with the key, at its own position; rows are held back
+    /// until the sheet is finished
+    Last,
+}
+
+/// What happens to a row handed to a [`Deduper`]
+pub(crate) enum Verdict {
+    /// New key: write it now
+    Write,
+    /// Spooled until [`Deduper::drain`]
+    Hold,
+    /// Repeat of a key already written
+    Drop,
+}
+
+type KeyDigest = [u8; 16];
+
+/// Key columns seen by a deduplicating writer
+pub(crate) struct Deduper {
+    columns: Vec<usize>,
+    keep: Keep,
+    scratch: Vec<u8>,              // Encoded key projection, reused between rows
+    seen: HashSet<KeyDigest>,      // Keep::First
+    last: HashMap<KeyDigest, u64>, // Keep::Last: key -> index of its last row
+    spool: Option<BufWriter<File>>,
+    spooled: u64, // Bytes written to the spool
+    values: u64,  // Values in the spool
+    rows: u64,    // Rows in the spool
+}
+
+impl Deduper {
+    pub(crate) fn new(columns: Vec<usize>, keep: Keep) -> Self {
+        Deduper {
+            columns,
+            keep,
+            scratch: Vec::new(),
+            seen: HashSet::new(),
+            last: HashMap::new(),
+            spool: None,
+            spooled: 0,
+            values: 0,
+            rows: 0,
+        }
+    }
+
+    /// Decide on a row; with [`Keep::Last`] it is spooled
+    pub(crate) fn push(&mut self, row: &[StyledCell]) -> Result<Verdict> {
+        let key = self.key(row)?;
+        match self.keep {
+            Keep::First if self.seen.insert(key) => Ok(Verdict::Write),
+            Keep::First => Ok(Verdict::Drop),
+            Keep::Last => {
+                let spool = match &mut self.spool {
+                    Some(spool) => spool,
+                    None => self
+                        .spool
+                        .insert(BufWriter::with_capacity(64 * 1024, tempfile::tempfile()?)),
+                };
+                let (bytes, values) = encode_row(spool, row)?;
+                self.spooled += bytes;
+                self.values += values;
+                self.last.insert(key, self.rows);
+                self.rows += 1;
+                Ok(Verdict::Hold)
+            }
+        }
+    }
+
+    /// Hand each key's last held row to `emit`, in write order. Returns the
+    /// number of held rows dropped
+    pub(crate) fn drain<F>(mut self, mut emit: F) -> Result<u64>
+    where
+        F: FnMut(&[StyledCell]) -> Result<()>,
+    {
+        let Some(spool) = self.spool.take() else {
+            return Ok(0);
+        };
+        let mut file = spool.into_inner().map_err(|e| e.into_error())?;
+        let run = SpooledColumn {
+            offset: 0,
+            len: self.spooled,
+            rows: self.values,
+        };
+        let mut cursor = ColumnCursor::new(&run, READ_BUFFER);
+        let mut left = self.values;
+        let mut index = 0u64;
+        while let Some(row) = next_row(&mut cursor, &mut left, &mut file)? {
+            let key = self.key(&row)?;
+            if self.last.get(&key) == Some(&index) {
+                emit(&row)?;
+            }
+            index += 1;
+        }
+        Ok(self.rows - self.last.len() as u64)
+    }
+
+    // Digest of the row's key columns
+    fn key(&mut self, row: &[StyledCell]) -> Result<KeyDigest> {
+        self.scratch.clear();
+        for &col in &self.columns {
+            let value = row.get(col).map_or(&CellValue::Empty, |c| &c.value);
+            encode(&mut self.scratch, value)?;
+        }
+        let digest = Sha256::digest(&self.scratch);
+        let mut key = [0u8; 16];
+        key.copy_from_slice(&digest[..16]);
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CellStyle;
+
+    fn row(values: &[CellValue]) -> Vec<StyledCell> {
+        values
+            .iter()
+            .map(|v| StyledCell::new(v.clone(), CellStyle::Default))
+            .collect()
+    }
+
+    #[test]
+    fn test_keep_last_writes_last_rows_in_order() {
+        let mut deduper = Deduper::new(vec![0, 2], Keep::Last);
+        let rows = [
+            row(&[CellValue::Int(1), "a".into(), "x".into()]),
+            row(&[CellValue::Int(2), "b".into()]),
+            row(&[CellValue::Int(1), "c".into(), "x".into()]),
+            row(&[CellValue::Int(2), "d".into(), CellValue::Empty]),
+            row(&[CellValue::Float(1.0), "e".into(), "x".into()]),
+        ];
+        for r in &rows {
+            assert!(matches!(deduper.push(r).unwrap(), Verdict::Hold));
+        }
+        let mut emitted = Vec::new();
+        let dropped = deduper
+            .drain(|r| {
+                emitted.push(r.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(dropped, 2);
+        assert_eq!(emitted, [rows[2].clone(), rows[3].clone(), rows[4].clone()]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Write key-value records whose columns are discovered on the fly
+//!
+//! [`DynamicWriter`] wraps an [`ExcelWriter`] and takes records keyed by
+//! column name, such as semi-structured JSON events, without knowing all
+//! keys up front. A key seen for the first time becomes a new column at the
+//! right. Records stream into a temporary spool file as they arrive;
+//! [`finish`](DynamicWriter::finish) writes the final header row, then reads
+//! the records back and writes them below it, leaving cells blank where a
+//! record lacks a key. Memory stays bounded by the spool buffers and the
+//! header set, however many records there are.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::dynamic::DynamicWriter;
+//! use excelstream::types::CellValue;
+//! use excelstream::ExcelWriter;
+//! use std::collections::HashMap;
+//!
+//! let mut writer = DynamicWriter::new(ExcelWriter::new("events.xlsx")?).with_columns(["id"]);
+//! writer.write_record([("id", CellValue::Int(1)), ("user", "alice".into())])?;
+//!
+//! let mut event = HashMap::new();
+//! event.insert("id".to_string(), CellValue::Int(2));
+//! event.insert("status".to_string(), CellValue::Int(404));
+//! writer.write_map(&event)?;
+//!
+//! writer.finish()?; // id, user, status
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::cellref::{MAX_COLS, MAX_ROWS};
+use crate::error::{ExcelError, Result};
+use crate::transpose::{encode, ColumnCursor, SpooledColumn};
+use crate::types::{CellValue, WriteReport};
+use crate::writer::ExcelWriter;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+// Read buffer for the spool while `finish` writes the rows
+const READ_BUFFER: usize = 64 * 1024;
+
+/// Writer taking records with any keys, see [module docs](self)
+pub struct DynamicWriter {
+    writer: ExcelWriter,
+    temp_dir: Option<PathBuf>,
+    headers: Vec<String>,
+    columns: HashMap<String, usize>, // Header name -> column
+    spool: Option<BufWriter<File>>,
+    spooled: u64, // Bytes written to the spool
+    values: u64,  // Values in the spool, each record's length included
+    records: u32,
+    row: Vec<CellValue>, // The record being spooled, in column order
+}
+
+impl DynamicWriter {
+    /// Wrap a writer; the header and records go to its current sheet on
+    /// [`finish`](Self::finish)
+    pub fn new(writer: ExcelWriter) -> Self {
+        DynamicWriter {
+            writer,
+            temp_dir: None,
+            headers: Vec::new(),
+            columns: HashMap::new(),
+            spool: None,
+            spooled: 0,
+            values: 0,
+            records: 0,
+            row: Vec::new(),
+        }
+    }
+
+    /// Directory for the spool file (default: the system temp directory)
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Start with these columns, in this order, before any discovered ones
+    pub fn with_columns<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for name in names {
+            if !self.columns.contains_key(name.as_ref()) && self.headers.len() < MAX_COLS as usize {
+                self.add_column(name.as_ref());
+            }
+        }
+        self
+    }
+
+    /// Column names so far, in column order
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Number of records written so far
+    pub fn record_count(&self) -> u32 {
+        self.records
+    }
+
+    /// Append a record of (column name, value) pairs
+    ///
+    /// New names become columns in the order the record yields them. When a
+    /// name repeats within a record, the last value wins. Fails with
+    /// [`ExcelError::LimitExceeded`] past 16,384 columns or 1,048,575
+    /// records; the record is then dropped (its new columns are kept) and
+    /// the writer stays usable.
+    pub fn write_record<I, K, V>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<CellValue>,
+    {
+        if self.records == MAX_ROWS - 1 {
+            return Err(ExcelError::LimitExceeded {
+                what: "Record count".to_string(),
+                limit: (MAX_ROWS - 1) as u64,
+            });
+        }
+        self.row.clear();
+        for (name, value) in record {
+            let col = match self.columns.get(name.as_ref()) {
+                Some(&col) => col,
+                None if self.headers.len() == MAX_COLS as usize => {
+                    return Err(ExcelError::LimitExceeded {
+                        what: format!("Column count at key '{}'", name.as_ref()),
+                        limit: MAX_COLS as u64,
+                    });
+                }
+                None => self.add_column(name.as_ref()),
+            };
+            if self.row.len() <= col {
+                self.row.resize(col + 1, CellValue::Empty);
+            }
+            self.row[col] = value.into();
+        }
+        self.spool_row()
+    }
+
+    /// Append a record given as a map
+    ///
+    /// Map order is arbitrary, so keys new in this record become columns in
+    /// sorted order, keeping the layout the same from run to run.
+    pub fn write_map(&mut self, record: &HashMap<String, CellValue>) -> Result<()> {
+        let mut new_keys: Vec<&String> = record
+            .keys()
+            .filter(|key| !self.columns.contains_key(key.as_str()))
+            .collect();
+        new_keys.sort_unstable();
+        let known = record
+            .iter()
+            .filter(|(key, _)| self.columns.contains_key(key.as_str()));
+        let fresh = new_keys.into_iter().map(|key| (key, &record[key]));
+        let pairs: Vec<(&String, CellValue)> = known
+            .chain(fresh)
+            .map(|(key, value)| (key, value.clone()))
+            .collect();
+        self.write_record(pairs)
+    }
+
+    // Register a new column at the right, returning its index
+    fn add_column(&mut self, name: &str) -> usize {
+        let col = self.headers.len();
+        self.headers.push(name.to_string());
+        self.columns.insert(name.to_string(), col);
+        col
+    }
+
+    // Append the record in `row` to the spool: its length, then its cells
+    fn spool_row(&mut self) -> Result<()> {
+        let spool = match &mut self.spool {
+            Some(spool) => spool,
+            None => {
+                let file = match &self.temp_dir {
+                    Some(dir) => tempfile::tempfile_in(dir)?,
+                    None => tempfile::tempfile()?,
+                };
+                self.spool.insert(BufWriter::with_capacity(64 * 1024, file))
+            }
+        };
+        self.spooled += encode(spool, &CellValue::Int(self.row.len() as i64))?;
+        for value in &self.row {
+            self.spooled += encode(spool, value)?;
+        }
+        self.values += 1 + self.row.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Write the header row and the records, and save the workbook
+    pub fn finish(mut self) -> Result<WriteReport> {
+        if !self.headers.is_empty() {
+            self.writer.write_header_bold(&self.headers)?;
+        }
+        if let Some(spool) = self.spool.take() {
+            let mut file = spool.into_inner().map_err(|e| e.into_error())?;
+            let mut cursor = ColumnCursor::new(
+                &SpooledColumn {
+                    offset: 0,
+                    len: self.spooled,
+                    rows: self.values,
+                },
+                READ_BUFFER,
+            );
+            for _ in 0..self.records {
+                let len = match cursor.next_value(&mut file)? {
+                    CellValue::Int(len) => len as usize,
+                    _ => return Err(corrupt_spool()),
+                };
+                self.row.clear();
+                for _ in 0..len {
+                    self.row.push(cursor.next_value(&mut file)?);
+                }
+                self.writer.write_row_typed(&self.row)?;
+            }
+        }
+        self.writer.save()
+    }
+}
+
+fn corrupt_spool() -> ExcelError {
+    ExcelError::InvalidState("Dynamic writer spool file is corrupt".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming_reader::StreamingReader;
+
+    #[test]
+    fn test_dynamic_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.xlsx");
+        let mut writer = DynamicWriter::new(ExcelWriter::new(&path).unwrap())
+            .with_temp_dir(dir.path())
+            .with_columns(["id", "id"]);
+        writer
+            .write_record([("id", CellValue::Int(1)), ("user", "alice".into())])
+            .unwrap();
+        let mut event = HashMap::new();
+        event.insert("status".to_string(), CellValue::Int(404));
+        event.insert("id".to_string(), CellValue::Int(2));
+        event.insert("path".to_string(), CellValue::from("/a"));
+        writer.write_map(&event).unwrap();
+        writer
+            .write_record([("user", CellValue::from("bob")), ("user", "carol".into())])
+            .unwrap();
+        writer
+            .write_record(Vec::<(&str, CellValue)>::new())
+            .unwrap();
+        assert_eq!(writer.headers(), ["id", "user", "path", "status"]);
+        assert_eq!(writer.record_count(), 4);
+        writer.finish().unwrap();
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        let rows: Vec<Vec<CellValue>> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(
+            rows[0],
+            ["id", "user", "path", "status"].map(CellValue::from)
+        );
+        assert_eq!(rows[1], [CellValue::Int(1), CellValue::from("alice")]);
+        assert_eq!(
+            rows[2],
+            [
+                CellValue::Int(2),
+                CellValue::Empty,
+                CellValue::from("/a"),
+                CellValue::Int(404)
+            ]
+        );
+        assert_eq!(rows[3], [CellValue::Empty, CellValue::from("carol")]);
+        assert_eq!(rows.len(), 5);
+        assert!(rows[4].iter().all(CellValue::is_empty));
+    }
+}
@@ -60,6 +60,27 @@ pub enum ExcelError {
     /// ZIP error
     #[error("ZIP error: {0}")]
     ZipError(String),
+
+    /// Invalid UTF-8 byte sequence in a workbook part
+    #[error("Invalid UTF-8 in '{part}' at byte offset {offset}")]
+    InvalidUtf8 { part: String, offset: u64 },
+
+    /// A configured read limit was exceeded (see `ReadLimits`)
+    #[error("Limit exceeded: {what} is larger than {limit}")]
+    LimitExceeded { what: String, limit: u64 },
+
+    /// Not enough free disk space for the output
+    ///
+    /// Returned by the space preflight, where `needed` is the requested
+    /// amount, and when the disk fills up mid-write, where `needed` is the
+    /// size the partial output had reached (a lower bound). The partial
+    /// output is removed before this is returned.
+    #[error("Disk full: needed at least {needed} bytes, {available} available")]
+    DiskFull { needed: u64, available: u64 },
+
+    /// A copied object didn't match its source (size or checksum)
+    #[error("Integrity check failed for {object}: {reason}")]
+    IntegrityMismatch { object: String, reason: String },
 }
 
 // Convert s-zip errors to ExcelError for backward compatibility
@@ -0,0 +1,211 @@
+//! Single-call export for serverless handlers
+//!
+//! [`export_to_bytes`] turns an iterator of rows into a finished xlsx in
+//! memory: no temp files, no runtime, nothing to configure beyond
+//! [`ExportOptions`]. Rows are streamed into the ZIP compressor as they come,
+//! so besides the compressed output only one row is held at a time. This is
+//! the recommended way to answer an AWS Lambda (or any serverless) request
+//! with a spreadsheet; with the `cloud-s3` feature,
+//! [`export_to_s3`] uploads the result instead of returning it.
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::export::{export_to_bytes, ExportOptions};
+//! use excelstream::types::CellValue;
+//!
+//! let rows = (1..=3).map(|i| vec![CellValue::Int(i), CellValue::String(format!("Item {}", i))]);
+//! let options = ExportOptions::new().with_header(["ID", "Name"]);
+//! let bytes = export_to_bytes(rows, &options)?;
+//! assert!(bytes.starts_with(b"PK"));
+//! // Lambda: return base64(bytes) with isBase64Encoded = true and
+//! // Content-Type: application/vnd.openxmlformats-officedocument.spreadsheetml.sheet
+//! # Ok::<(), excelstream::ExcelError>(())
+//! ```
+
+use crate::error::Result;
+use crate::fast_writer::ZeroTempWorkbook;
+use crate::types::{CellStyle, CellValue, FloatFormat, StyledCell};
+use std::io::Cursor;
+
+/// `Content-Type` of an xlsx file
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Options for [`export_to_bytes`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    /// Name of the single sheet (default: "Sheet1")
+    pub sheet_name: String,
+    /// Bold header row written before the data (default: none)
+    pub header: Option<Vec<String>>,
+    /// ZIP compression level from 0 to 9 (default: 6); lower levels spend
+    /// less CPU for a larger file
+    pub compression_level: u32,
+    /// How float cells are written (default: shortest round-trip form)
+    pub float_format: FloatFormat,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            sheet_name: "Sheet1".to_string(),
+            header: None,
+            compression_level: 6,
+            float_format: FloatFormat::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Create options with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the sheet name
+    pub fn with_sheet_name(mut self, name: &str) -> Self {
+        self.sheet_name = name.to_string();
+        self
+    }
+
+    /// Write a bold header row first
+    pub fn with_header<I, S>(mut self, header: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.header = Some(header.into_iter().map(|h| h.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Set the compression level (clamped to 9)
+    pub fn with_compression_level(mut self, level: u32) -> Self {
+        self.compression_level = level.min(9);
+        self
+    }
+
+    /// Set how float cells are written
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+}
+
+/// Write rows to a one-sheet xlsx file in memory and return its bytes
+pub fn export_to_bytes<I>(rows: I, options: &ExportOptions) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = Vec<CellValue>>,
+{
+    let mut workbook =
+        ZeroTempWorkbook::from_writer(Cursor::new(Vec::new()), options.compression_level)?;
+    workbook.set_float_format(options.float_format);
+    workbook.add_worksheet(&options.sheet_name)?;
+
+    if let Some(header) = &options.header {
+        let cells: Vec<StyledCell> = header
+            .iter()
+            .map(|h| StyledCell::new(CellValue::String(h.clone()), CellStyle::HeaderBold))
+            .collect();
+        workbook.write_row_styled(&cells)?;
+    }
+
+    let mut cells = Vec::new();
+    for row in rows {
+        cells.clear();
+        cells.extend(
+            row.into_iter()
+                .map(|value| StyledCell::new(value, CellStyle::Default)),
+        );
+        workbook.write_row_styled(&cells)?;
+    }
+
+    let (output, _) = workbook.close_into_inner()?;
+    Ok(output.into_inner())
+}
+
+/// Export rows like [`export_to_bytes`] and upload the file to S3 with a
+/// single `PutObject`
+///
+/// Requires the `cloud-s3` feature. Meant for small and medium exports from
+/// short-lived functions; use [`S3ExcelWriter`](crate::cloud::S3ExcelWriter)
+/// for files too large to hold in memory.
+///
+/// # Example
+///
+/// ```no_run
+/// use excelstream::export::{export_to_s3, ExportOptions};
+/// use excelstream::types::CellValue;
+///
+/// # async fn handler(client: aws_sdk_s3::Client) -> excelstream::Result<()> {
+/// let rows = vec![vec![CellValue::Int(1), CellValue::String("Alice".to_string())]];
+/// export_to_s3(&client, "reports", "daily.xlsx", rows, &ExportOptions::new()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "cloud-s3")]
+pub async fn export_to_s3<I>(
+    client: &aws_sdk_s3::Client,
+    bucket: &str,
+    key: &str,
+    rows: I,
+    options: &ExportOptions,
+) -> Result<()>
+where
+    I: IntoIterator<Item = Vec<CellValue>>,
+{
+    use aws_sdk_s3::error::ProvideErrorMetadata;
+    use aws_sdk_s3::primitives::ByteStream;
+
+    let bytes = export_to_bytes(rows, options)?;
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .content_type(XLSX_CONTENT_TYPE)
+        .body(ByteStream::from(bytes))
+        .send()
+        .await
+        .map_err(|e| {
+            crate::error::ExcelError::WriteError(format!(
+                "S3 upload of s3://{}/{} failed: {}",
+                bucket,
+                key,
+                e.message().unwrap_or("Unknown error")
+            ))
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_to_bytes() {
+        let rows = (1..=100).map(|i| vec![CellValue::Int(i), CellValue::Float(i as f64 + 0.5)]);
+        let options = ExportOptions::new()
+            .with_sheet_name("Data")
+            .with_header(["ID", "Value"])
+            .with_compression_level(12);
+        assert_eq!(options.compression_level, 9);
+        let bytes = export_to_bytes(rows, &options).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.xlsx");
+        std::fs::write(&path, &bytes).unwrap();
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        assert_eq!(reader.sheet_names(), vec!["Data"]);
+        let rows: Vec<_> = reader
+            .rows("Data")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(rows.len(), 101);
+        assert_eq!(rows[0][1], CellValue::String("Value".to_string()));
+        assert_eq!(
+            rows[100],
+            vec![CellValue::Int(100), CellValue::Float(100.5)]
+        );
+    }
+}
@@ -0,0 +1,419 @@
+//! Row and cell XML shared by the workbook writers
+//!
+//! [`ZeroTempWorkbook`](super::ZeroTempWorkbook) and the cloud writers'
+//! `WorkbookCore` both render rows through [`CellWriter`], so formula
+//! sanitization, float and time zone handling and Excel's text, formula,
+//! style and hyperlink limits hold whichever backend a workbook goes to.
+
+use super::styles::StyleRegistry;
+use crate::cellref;
+use crate::error::{ExcelError, Result};
+use crate::types::{
+    duration_serial, CellStyle, CellValue, FloatFormat, LimitPolicy, RichText, StyledCell,
+    TimeZonePolicy, MAX_CELL_STYLES, MAX_FORMULA_LEN, MAX_HYPERLINKS, MAX_TEXT_LEN,
+};
+
+/// Whether text would be interpreted as a formula when opened in a
+/// spreadsheet app or exported to CSV (`=`, `+`, `-`, `@`, tab, CR)
+///
+/// Plain numbers such as "-5" or "+1.5" are not considered formulas.
+pub(crate) fn is_formula_like(s: &str) -> bool {
+    match s.chars().next() {
+        Some('=' | '@' | '\t' | '\r') => true,
+        Some('+' | '-') => s.parse::<f64>().is_err(),
+        _ => false,
+    }
+}
+
+/// Append `s` to `buffer` with XML special characters escaped
+pub(crate) fn write_escaped(buffer: &mut Vec<u8>, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => buffer.extend_from_slice(b"&amp;"),
+            '<' => buffer.extend_from_slice(b"&lt;"),
+            '>' => buffer.extend_from_slice(b"&gt;"),
+            '"' => buffer.extend_from_slice(b"&quot;"),
+            '\'' => buffer.extend_from_slice(b"&apos;"),
+            _ => {
+                let mut buf = [0; 4];
+                buffer.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+}
+
+/// Start a `<row>` tag, with `spans` when the row has cells and a custom
+/// height when given
+fn push_row_tag(buf: &mut Vec<u8>, row: u32, cells: u32, height: Option<f64>) {
+    let mut num_buffer = itoa::Buffer::new();
+    buf.extend_from_slice(b"<row r=\"");
+    buf.extend_from_slice(num_buffer.format(row).as_bytes());
+    if cells > 0 {
+        buf.extend_from_slice(b"\" spans=\"1:");
+        buf.extend_from_slice(num_buffer.format(cells).as_bytes());
+    }
+    buf.extend_from_slice(b"\"");
+    if let Some(height) = height {
+        buf.extend_from_slice(format!(" ht=\"{}\" customHeight=\"1\"", height).as_bytes());
+    }
+    buf.extend_from_slice(b">");
+}
+
+fn limit_exceeded(what: String, limit: usize) -> ExcelError {
+    ExcelError::LimitExceeded {
+        what,
+        limit: limit as u64,
+    }
+}
+
+/// Cell options of the sheet being written and the rendering that uses them
+#[derive(Debug, Default)]
+pub(crate) struct CellWriter {
+    pub(crate) formula_prefix: Option<char>,
+    pub(crate) float_format: FloatFormat,
+    pub(crate) time_zone: TimeZonePolicy,
+    pub(crate) limit_policy: LimitPolicy,
+    text_columns: Vec<u32>, // Columns of the current sheet written as text
+    column_styles: Vec<(u32, CellStyle)>, // Default styles of columns of the current sheet
+}
+
+impl CellWriter {
+    /// Forget the column defaults of the previous sheet
+    pub(crate) fn start_sheet(&mut self) {
+        self.text_columns.clear();
+        self.column_styles.clear();
+    }
+
+    pub(crate) fn set_text_column(&mut self, col: u32) {
+        if !self.text_columns.contains(&col) {
+            self.text_columns.push(col);
+        }
+    }
+
+    pub(crate) fn set_column_style(&mut self, col: u32, style: CellStyle) {
+        self.column_styles.retain(|(c, _)| *c != col);
+        if style != CellStyle::Default {
+            self.column_styles.push((col, style));
+        }
+    }
+
+    // Style of a cell written with `style`: text columns, then the value's
+    // own format (durations, percentages), then the column's default
+    fn cell_style(&self, col: u32, value: &CellValue, style: CellStyle) -> CellStyle {
+        match (style, value) {
+            (CellStyle::Default, _) if self.text_columns.contains(&col) => CellStyle::TextFormat,
+            (CellStyle::Default, CellValue::Duration(_)) => CellStyle::Duration,
+            (CellStyle::Default, CellValue::Percent(_)) => CellStyle::NumberPercentage,
+            (CellStyle::Default, _) => self
+                .column_styles
+                .iter()
+                .find(|(c, _)| *c == col)
+                .map_or(CellStyle::Default, |(_, style)| *style),
+            (style, _) => style,
+        }
+    }
+
+    /// Whether another hyperlink fits in a sheet holding `count`; past
+    /// [`MAX_HYPERLINKS`] this fails, or says no under
+    /// [`LimitPolicy::Truncate`]
+    pub(crate) fn hyperlink_fits(&self, count: usize, row: u32, col: u32) -> Result<bool> {
+        if count < MAX_HYPERLINKS {
+            return Ok(true);
+        }
+        match self.limit_policy {
+            LimitPolicy::Error => Err(limit_exceeded(
+                format!("Hyperlink count at {}", cellref::to_a1(row, col)),
+                MAX_HYPERLINKS,
+            )),
+            LimitPolicy::Truncate => Ok(false),
+        }
+    }
+
+    /// Append row `row` (1-based) of inline string cells to `buf`; empty
+    /// strings leave the cell blank. Returns the number of cells.
+    ///
+    /// On error nothing is appended.
+    pub(crate) fn text_row<I, S>(
+        &self,
+        buf: &mut Vec<u8>,
+        styles: &mut StyleRegistry,
+        row: u32,
+        height: Option<f64>,
+        values: I,
+    ) -> Result<u32>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let start = buf.len();
+        let result = self.push_row(buf, row, height, |buf| {
+            let mut cells = 0;
+            for (col, value) in values.into_iter().enumerate() {
+                let col = self.check_col(row, col)?;
+                let value = value.as_ref();
+                let style = self.cell_style(col, &CellValue::Empty, CellStyle::Default);
+                self.push_cell_start(buf, styles, row, col, style)?;
+                if value.is_empty() {
+                    buf.extend_from_slice(b"/>");
+                } else {
+                    buf.extend_from_slice(b" t=\"inlineStr\"><is><t>");
+                    self.push_text(buf, value, row, col)?;
+                    buf.extend_from_slice(b"</t></is></c>");
+                }
+                cells += 1;
+            }
+            Ok(cells)
+        });
+        if result.is_err() {
+            buf.truncate(start);
+        }
+        result
+    }
+
+    /// Append row `row` (1-based) of typed cells to `buf`. Returns the
+    /// number of cells.
+    ///
+    /// On error nothing is appended.
+    pub(crate) fn styled_row<'c, I>(
+        &self,
+        buf: &mut Vec<u8>,
+        styles: &mut StyleRegistry,
+        row: u32,
+        height: Option<f64>,
+        cells: I,
+    ) -> Result<u32>
+    where
+        I: IntoIterator<Item = (&'c CellValue, CellStyle)>,
+    {
+        let start = buf.len();
+        let result = self.push_row(buf, row, height, |buf| {
+            let mut count = 0;
+            for (col, (value, style)) in cells.into_iter().enumerate() {
+                let col = self.check_col(row, col)?;
+                self.push_cell(buf, styles, row, col, value, style)?;
+                count += 1;
+            }
+            Ok(count)
+        });
+        if result.is_err() {
+            buf.truncate(start);
+        }
+        result
+    }
+
+    // Wrap the cells `push_cells` writes in a row tag; the tag goes in
+    // front once the span is known
+    fn push_row(
+        &self,
+        buf: &mut Vec<u8>,
+        row: u32,
+        height: Option<f64>,
+        push_cells: impl FnOnce(&mut Vec<u8>) -> Result<u32>,
+    ) -> Result<u32> {
+        let start = buf.len();
+        let cells = push_cells(buf)?;
+        let mut row_tag = Vec::with_capacity(48);
+        push_row_tag(&mut row_tag, row, cells, height);
+        buf.splice(start..start, row_tag);
+        buf.extend_from_slice(b"</row>");
+        Ok(cells)
+    }
+
+    // The column index, or an error once a row has more cells than a sheet
+    // has columns
+    fn check_col(&self, row: u32, col: usize) -> Result<u32> {
+        if col >= cellref::MAX_COLS as usize {
+            return Err(limit_exceeded(
+                format!("Row {} column count", row),
+                cellref::MAX_COLS as usize,
+            ));
+        }
+        Ok(col as u32)
+    }
+
+    // `<c r="A1"` with the style attribute, tag left open
+    fn push_cell_start(
+        &self,
+        buf: &mut Vec<u8>,
+        styles: &mut StyleRegistry,
+        row: u32,
+        col: u32,
+        style: CellStyle,
+    ) -> Result<()> {
+        let mut style_id = styles.style_index(&style);
+        if style_id >= MAX_CELL_STYLES {
+            match self.limit_policy {
+                LimitPolicy::Error => {
+                    return Err(limit_exceeded(
+                        format!("Cell {} style count", cellref::to_a1(row - 1, col)),
+                        MAX_CELL_STYLES as usize,
+                    ))
+                }
+                LimitPolicy::Truncate => style_id = 0,
+            }
+        }
+
+        let mut num_buffer = itoa::Buffer::new();
+        buf.extend_from_slice(b"<c r=\"");
+        cellref::push_col_name(buf, col);
+        buf.extend_from_slice(num_buffer.format(row).as_bytes());
+        buf.extend_from_slice(b"\"");
+        if style_id > 0 {
+            buf.extend_from_slice(b" s=\"");
+            buf.extend_from_slice(num_buffer.format(style_id).as_bytes());
+            buf.extend_from_slice(b"\"");
+        }
+        Ok(())
+    }
+
+    // One typed cell
+    fn push_cell(
+        &self,
+        buf: &mut Vec<u8>,
+        styles: &mut StyleRegistry,
+        row: u32,
+        col: u32,
+        value: &CellValue,
+        style: CellStyle,
+    ) -> Result<()> {
+        let style = self.cell_style(col, value, style);
+        let stored = StyledCell::stored(value, &style);
+        let replaced = self
+            .time_zone
+            .replace(&stored)
+            .or_else(|| self.float_format.replace_non_finite(&stored));
+        let value = replaced.as_ref().unwrap_or(&stored);
+        self.push_cell_start(buf, styles, row, col, style)?;
+
+        let mut num_buffer = itoa::Buffer::new();
+        match value {
+            // Offset dates are replaced per the time zone policy above
+            CellValue::Empty | CellValue::DateTimeTz(_) => buf.extend_from_slice(b"/>"),
+            CellValue::Int(i) => {
+                buf.extend_from_slice(b" t=\"n\"><v>");
+                buf.extend_from_slice(num_buffer.format(*i).as_bytes());
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::Float(f) => {
+                buf.extend_from_slice(b" t=\"n\"><v>");
+                self.float_format.write(*f, buf);
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::Percent(p) => {
+                buf.extend_from_slice(b" t=\"n\"><v>");
+                self.float_format.write(p / 100.0, buf);
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::Duration(d) => {
+                buf.extend_from_slice(b" t=\"n\"><v>");
+                self.float_format.write(duration_serial(d), buf);
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::Decimal(d) => {
+                // Exact decimal text, no f64 round-trip
+                buf.extend_from_slice(b" t=\"n\"><v>");
+                buf.extend_from_slice(d.to_string().as_bytes());
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::Bool(b) => {
+                buf.extend_from_slice(b" t=\"b\"><v>");
+                buf.extend_from_slice(if *b { b"1" } else { b"0" });
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::String(s) => {
+                buf.extend_from_slice(b" t=\"inlineStr\"><is><t>");
+                self.push_text(buf, s, row, col)?;
+                buf.extend_from_slice(b"</t></is></c>");
+            }
+            CellValue::RichText(text) => {
+                buf.extend_from_slice(b" t=\"inlineStr\"><is>");
+                self.push_rich_text(buf, text, row, col)?;
+                buf.extend_from_slice(b"</is></c>");
+            }
+            CellValue::Formula(f) => {
+                if f.len() > MAX_FORMULA_LEN && f.chars().count() > MAX_FORMULA_LEN {
+                    return Err(limit_exceeded(
+                        format!("Cell {} formula length", cellref::to_a1(row - 1, col)),
+                        MAX_FORMULA_LEN,
+                    ));
+                }
+                buf.extend_from_slice(b"><f>");
+                write_escaped(buf, f);
+                buf.extend_from_slice(b"</f></c>");
+            }
+            CellValue::DateTime(dt) => {
+                // Excel date serial number
+                buf.extend_from_slice(b" t=\"n\"><v>");
+                buf.extend_from_slice(dt.to_string().as_bytes());
+                buf.extend_from_slice(b"</v></c>");
+            }
+            CellValue::Error(e) => match CellValue::error_code(e) {
+                Some(code) => {
+                    buf.extend_from_slice(b" t=\"e\"><v>");
+                    buf.extend_from_slice(code.as_bytes());
+                    buf.extend_from_slice(b"</v></c>");
+                }
+                None => {
+                    buf.extend_from_slice(b" t=\"inlineStr\"><is><t>");
+                    self.push_text(buf, e, row, col)?;
+                    buf.extend_from_slice(b"</t></is></c>");
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // Text cell content, neutralizing formula-like values if enabled and
+    // holding it to Excel's length limit
+    fn push_text(&self, buf: &mut Vec<u8>, s: &str, row: u32, col: u32) -> Result<()> {
+        let mut max = MAX_TEXT_LEN;
+        if let Some(prefix) = self.formula_prefix {
+            if is_formula_like(s) {
+                let mut utf8 = [0u8; 4];
+                buf.extend_from_slice(prefix.encode_utf8(&mut utf8).as_bytes());
+                max -= 1;
+            }
+        }
+        let mut s = s;
+        if s.len() > max {
+            if let Some((cut, _)) = s.char_indices().nth(max) {
+                match self.limit_policy {
+                    LimitPolicy::Error => {
+                        return Err(limit_exceeded(
+                            format!("Cell {} text length", cellref::to_a1(row - 1, col)),
+                            MAX_TEXT_LEN,
+                        ))
+                    }
+                    LimitPolicy::Truncate => s = &s[..cut],
+                }
+            }
+        }
+        write_escaped(buf, s);
+        Ok(())
+    }
+
+    // Runs of a rich text cell, cut at the cell text limit like plain text
+    fn push_rich_text(&self, buf: &mut Vec<u8>, text: &RichText, row: u32, col: u32) -> Result<()> {
+        let len: usize = text.runs().iter().map(|run| run.text.chars().count()).sum();
+        if len > MAX_TEXT_LEN && self.limit_policy == LimitPolicy::Error {
+            return Err(limit_exceeded(
+                format!("Cell {} text length", cellref::to_a1(row - 1, col)),
+                MAX_TEXT_LEN,
+            ));
+        }
+        let mut left = MAX_TEXT_LEN;
+        for run in text.runs() {
+            let s = match run.text.char_indices().nth(left) {
+                Some((cut, _)) => &run.text[..cut],
+                None => run.text.as_str(),
+            };
+            left -= s.chars().count();
+            buf.extend_from_slice(b"<r>");
+            run.push_properties(buf);
+            buf.extend_from_slice(b"<t xml:space=\"preserve\">");
+            write_escaped(buf, s);
+            buf.extend_from_slice(b"</t></r>");
+        }
+        Ok(())
+    }
+}
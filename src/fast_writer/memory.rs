@@ -2,8 +2,149 @@
 
 use crate::error::Result;
 use crate::fast_writer::UltraLowMemoryWorkbook;
+use crate::types::MemoryReport;
 use std::path::Path;
 
+/// When batched rows are handed to the compressor, by batch size
+///
+/// Sizes are bytes of row XML, so wide rows fill a batch sooner than narrow
+/// ones and memory stays bounded whatever the column count. A batch is
+/// flushed once it reaches `high_watermark`; a batch already holding
+/// `low_watermark` bytes is flushed before a row that would take it past
+/// `high_watermark`, so batches mostly land between the two marks. A single
+/// row of `high_watermark` bytes or more goes to the compressor directly.
+///
+/// The default (`high_watermark` 0) streams every row as it is written.
+///
+/// # Examples
+///
+/// ```no_run
+/// use excelstream::fast_writer::FlushPolicy;
+/// use excelstream::ExcelWriterBuilder;
+///
+/// let writer = ExcelWriterBuilder::new("wide.xlsx")
+///     .with_flush_policy(FlushPolicy::new(512 * 1024).with_low_watermark(384 * 1024))
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FlushPolicy {
+    /// Batch size in bytes that triggers a flush (0 = stream every row)
+    pub high_watermark: usize,
+    /// Batch size in bytes above which the batch is flushed before a row
+    /// that would overshoot `high_watermark`
+    pub low_watermark: usize,
+}
+
+impl FlushPolicy {
+    /// Stream every row to the compressor as it is written (default)
+    pub const STREAMING: FlushPolicy = FlushPolicy {
+        high_watermark: 0,
+        low_watermark: 0,
+    };
+
+    /// Batches of 256 KiB, used when batching is turned on without a size
+    pub const BATCHED: FlushPolicy = FlushPolicy {
+        high_watermark: 256 * 1024,
+        low_watermark: 192 * 1024,
+    };
+
+    /// Batch rows up to `high_watermark` bytes, with the low watermark at
+    /// three quarters of it
+    pub fn new(high_watermark: usize) -> Self {
+        FlushPolicy {
+            high_watermark,
+            low_watermark: high_watermark / 4 * 3,
+        }
+    }
+
+    /// Set the low watermark (capped at the high watermark)
+    pub fn with_low_watermark(mut self, low_watermark: usize) -> Self {
+        self.low_watermark = low_watermark.min(self.high_watermark);
+        self
+    }
+
+    /// Whether rows are batched at all
+    pub fn is_streaming(&self) -> bool {
+        self.high_watermark == 0
+    }
+
+    /// How much of a batch holding `pending` bytes followed by a
+    /// `row`-byte row to flush now: nothing, the batch before the row, or
+    /// everything
+    pub(crate) fn flush_len(&self, pending: usize, row: usize) -> usize {
+        let total = pending + row;
+        if pending > 0
+            && pending >= self.low_watermark
+            && total > self.high_watermark
+            && row < self.high_watermark
+        {
+            pending
+        } else if total >= self.high_watermark {
+            total
+        } else {
+            0
+        }
+    }
+}
+
+// Central directory record the ZIP writer keeps per entry until close:
+// the fixed part, plus the entry name
+const DIRECTORY_RECORD: usize = 46;
+
+/// High-water marks of a workbook's own buffers as it is written
+#[derive(Debug, Default)]
+pub(crate) struct MemoryTracker {
+    report: MemoryReport,
+    entry_bytes: u64, // Uncompressed bytes of the entry being written
+}
+
+impl MemoryTracker {
+    /// Note the capacity of the row buffer and of the batch waiting for
+    /// the compressor
+    pub(crate) fn observe_buffers(&mut self, row: usize, batch: usize) {
+        let report = &mut self.report;
+        report.peak_row_buffer = report.peak_row_buffer.max(row);
+        report.peak_batch_buffer = report.peak_batch_buffer.max(batch);
+        report.peak_buffers = report.peak_buffers.max(row + batch);
+    }
+
+    pub(crate) fn start_entry(&mut self, name: &str) {
+        self.report.zip_entries += 1;
+        self.report.zip_directory_bytes += DIRECTORY_RECORD + name.len();
+        self.entry_bytes = 0;
+    }
+
+    /// Count bytes written to the current entry
+    pub(crate) fn entry_data(&mut self, len: usize) {
+        self.entry_bytes += len as u64;
+        self.report.largest_entry_bytes = self.report.largest_entry_bytes.max(self.entry_bytes);
+    }
+
+    pub(crate) fn finish(&self, shared_strings_bytes: usize, styles: u32) -> MemoryReport {
+        MemoryReport {
+            shared_strings_bytes,
+            styles,
+            peak_rss_bytes: peak_rss(),
+            ..self.report
+        }
+    }
+}
+
+// Peak resident set size of the process (`VmHWM`), where /proc has it
+fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
 /// Memory profile cho các loại pods khác nhau
 #[derive(Debug, Clone, Copy)]
 pub enum MemoryProfile {
@@ -41,24 +182,40 @@ impl MemoryProfile {
             .unwrap_or(MemoryProfile::High)
     }
 
-    fn apply(&self, _workbook: &mut UltraLowMemoryWorkbook) {
-        // UltraLowMemoryWorkbook doesn't have flush_interval or max_buffer_size
-        // These settings are no-ops for compatibility
+    /// Rows batched before they are handed to the compressor
+    ///
+    /// Kept for reference only: batches are sized in bytes, see
+    /// [`flush_policy`](Self::flush_policy).
+    pub fn flush_interval(&self) -> u32 {
         match self {
-            MemoryProfile::Low => {
-                // Low memory mode - already optimized
-            }
-            MemoryProfile::Medium => {
-                // Medium memory mode - already optimized
-            }
-            MemoryProfile::High => {
-                // High memory mode - already optimized
-            }
-            MemoryProfile::Custom { .. } => {
-                // Custom settings - already optimized
-            }
+            MemoryProfile::Low => 100,
+            MemoryProfile::Medium => 500,
+            MemoryProfile::High => 1000,
+            MemoryProfile::Custom { flush_interval, .. } => *flush_interval,
         }
     }
+
+    /// Batch size in bytes that triggers a flush, the high watermark of
+    /// [`flush_policy`](Self::flush_policy)
+    pub fn max_buffer_size(&self) -> usize {
+        match self {
+            MemoryProfile::Low => 256 * 1024,
+            MemoryProfile::Medium => 512 * 1024,
+            MemoryProfile::High => 1024 * 1024,
+            MemoryProfile::Custom {
+                max_buffer_size, ..
+            } => *max_buffer_size,
+        }
+    }
+
+    /// How rows are batched under this profile
+    pub fn flush_policy(&self) -> FlushPolicy {
+        FlushPolicy::new(self.max_buffer_size())
+    }
+
+    pub(crate) fn apply(&self, workbook: &mut UltraLowMemoryWorkbook) {
+        workbook.set_flush_policy(self.flush_policy());
+    }
 }
 
 /// Tạo UltraLowMemoryWorkbook với memory profile tự động
@@ -96,4 +253,76 @@ mod tests {
             MemoryProfile::High
         ));
     }
+
+    #[test]
+    fn test_memory_profile_batching() {
+        assert_eq!(MemoryProfile::Low.flush_interval(), 100);
+        assert_eq!(MemoryProfile::High.max_buffer_size(), 1024 * 1024);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let profile = MemoryProfile::Custom {
+            flush_interval: 7,
+            max_buffer_size: 64,
+        };
+        let mut workbook = create_workbook_with_profile(file.path(), profile).unwrap();
+        workbook.add_worksheet("Sheet1").unwrap();
+        for i in 0..50 {
+            workbook
+                .write_row([i.to_string(), "batched".to_string()])
+                .unwrap();
+        }
+        let report = workbook.close().unwrap();
+        assert_eq!(report.total_rows(), 50);
+
+        let mut reader = crate::ExcelReader::open(file.path()).unwrap();
+        assert_eq!(reader.rows("Sheet1").unwrap().count(), 50);
+    }
+
+    #[test]
+    fn test_memory_tracker() {
+        let mut tracker = MemoryTracker::default();
+        tracker.observe_buffers(100, 0);
+        tracker.observe_buffers(40, 300);
+        tracker.start_entry("xl/worksheets/sheet1.xml");
+        tracker.entry_data(500);
+        tracker.entry_data(700);
+        tracker.start_entry("xl/styles.xml");
+        tracker.entry_data(900);
+        let report = tracker.finish(64, 3);
+        assert_eq!(report.peak_row_buffer, 100);
+        assert_eq!(report.peak_batch_buffer, 300);
+        assert_eq!(report.peak_buffers, 340);
+        assert_eq!(report.zip_entries, 2);
+        assert_eq!(report.zip_directory_bytes, 2 * 46 + 24 + 13);
+        assert_eq!(report.largest_entry_bytes, 1200);
+        assert_eq!(report.shared_strings_bytes, 64);
+        assert_eq!(report.styles, 3);
+        if cfg!(target_os = "linux") {
+            assert!(report.peak_rss_bytes.unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        assert_eq!(
+            MemoryProfile::Low.flush_policy(),
+            FlushPolicy::new(256 * 1024)
+        );
+        let policy = FlushPolicy::new(100).with_low_watermark(60);
+        assert_eq!(policy.flush_len(0, 30), 0);
+        assert_eq!(policy.flush_len(50, 30), 0);
+        assert_eq!(policy.flush_len(80, 20), 100);
+        // Flushed ahead of a row that would overshoot
+        assert_eq!(policy.flush_len(70, 40), 70);
+        // Too small to flush on its own: the row goes along
+        assert_eq!(policy.flush_len(40, 70), 110);
+        // Oversized rows go straight through
+        assert_eq!(policy.flush_len(0, 500), 500);
+        assert_eq!(policy.flush_len(70, 500), 570);
+        assert_eq!(FlushPolicy::STREAMING.flush_len(0, 10), 10);
+        assert_eq!(
+            FlushPolicy::new(100).with_low_watermark(500).low_watermark,
+            100
+        );
+    }
 }
@@ -6,8 +6,10 @@
 //! - Optimized ZIP compression (using s-zip library)
 //! - Streaming-first design
 
+pub(crate) mod cell_xml;
 pub mod memory;
 pub mod shared_strings;
+pub mod styles;
 pub mod ultra_low_memory;
 pub mod worksheet;
 pub mod xml_writer;
@@ -19,7 +21,9 @@ pub use s_zip::{StreamingZipReader, StreamingZipWriter, ZipEntry};
 use crate::error::Result;
 use std::path::Path;
 
-pub use memory::{create_workbook_auto, create_workbook_with_profile, MemoryProfile};
+pub use memory::{create_workbook_auto, create_workbook_with_profile, FlushPolicy, MemoryProfile};
+pub use shared_strings::{SharedStrings, SharedStringsSnapshot, SharedStringsStats};
+pub use styles::StyleRegistry;
 pub use ultra_low_memory::UltraLowMemoryWorkbook;
 pub use worksheet::FastWorksheet;
 pub use zero_temp_workbook::ZeroTempWorkbook;
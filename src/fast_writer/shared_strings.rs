@@ -1,9 +1,137 @@
 //! Shared strings table for string deduplication
+//!
+//! An export resumed in a later process, or split into shards that are
+//! merged afterwards, can carry the table over with
+//! [`SharedStrings::snapshot`] and [`SharedStrings::from_snapshot`]: strings
+//! keep their indices, so `<c t="s">` cells written by different runs point
+//! into one table and sheets can be concatenated as they are.
+//!
+//! Strings registered up front get the lowest indices, so the `<v>`
+//! references to them in every cell are the shortest and compress best.
+//! [`SharedStrings::from_frequencies`] does this from a sample of the data
+//! (a first pass, or rows held back while buffering), and
+//! [`SharedStrings::stats`] reports how often lookups hit an existing entry.
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::fast_writer::shared_strings::{SharedStrings, SharedStringsSnapshot};
+//!
+//! let mut first_run = SharedStrings::new();
+//! first_run.add_string("North");
+//! let mut saved = Vec::new();
+//! first_run.snapshot().write_to(&mut saved)?;
+//!
+//! // Next run
+//! let snapshot = SharedStringsSnapshot::read_from(&mut saved.as_slice())?;
+//! let mut second_run = SharedStrings::from_snapshot(snapshot);
+//! assert_eq!(second_run.add_string("South"), 1);
+//! assert_eq!(second_run.add_string("North"), 0);
+//! # Ok::<(), excelstream::ExcelError>(())
+//! ```
 
 use super::xml_writer::XmlWriter;
-use crate::error::Result;
+use crate::error::{ExcelError, Result};
 use indexmap::IndexMap;
-use std::io::Write;
+use std::io::{Read, Write};
+
+/// Header of a serialized [`SharedStringsSnapshot`]
+const SNAPSHOT_MAGIC: &[u8; 8] = b"EXSST\x00\x00\x01";
+
+/// Saved state of a [`SharedStrings`] table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SharedStringsSnapshot {
+    /// Strings in index order
+    pub strings: Vec<String>,
+    /// String cell references written so far (the `count` attribute)
+    pub total_count: u32,
+    /// Limit of deduplicated strings the table was created with
+    pub max_unique_strings: usize,
+}
+
+impl SharedStringsSnapshot {
+    /// Serialize to a writer (little-endian, length-prefixed strings)
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(SNAPSHOT_MAGIC)?;
+        writer.write_all(&self.total_count.to_le_bytes())?;
+        writer.write_all(&(self.max_unique_strings as u64).to_le_bytes())?;
+        writer.write_all(&(self.strings.len() as u64).to_le_bytes())?;
+        for s in &self.strings {
+            writer.write_all(&(s.len() as u32).to_le_bytes())?;
+            writer.write_all(s.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read a snapshot written by [`write_to`](Self::write_to)
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != SNAPSHOT_MAGIC {
+            return Err(ExcelError::InvalidFormat(
+                "Not a shared strings snapshot".to_string(),
+            ));
+        }
+        let total_count = u32::from_le_bytes(read_array(reader)?);
+        let max_unique_strings = u64::from_le_bytes(read_array(reader)?) as usize;
+        let len = u64::from_le_bytes(read_array(reader)?);
+
+        let mut strings = Vec::new();
+        for _ in 0..len {
+            let size = u32::from_le_bytes(read_array(reader)?) as u64;
+            let mut bytes = Vec::new();
+            reader.take(size).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 != size {
+                return Err(ExcelError::InvalidFormat(
+                    "Truncated shared strings snapshot".to_string(),
+                ));
+            }
+            let s = String::from_utf8(bytes).map_err(|_| {
+                ExcelError::InvalidFormat("Invalid UTF-8 in shared strings snapshot".to_string())
+            })?;
+            strings.push(s);
+        }
+
+        Ok(SharedStringsSnapshot {
+            strings,
+            total_count,
+            max_unique_strings,
+        })
+    }
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Deduplication counters of a [`SharedStrings`] table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SharedStringsStats {
+    /// String references added
+    pub references: u64,
+    /// References that found their string already in the table
+    pub hits: u64,
+    /// Strings in the table
+    pub unique: usize,
+    /// Strings stored without deduplication because the table was full
+    pub overflow: u64,
+}
+
+impl SharedStringsStats {
+    /// Share of references that reused an entry (0.0 with no references)
+    ///
+    /// A low rate means most strings are distinct, so inline strings would
+    /// cost less memory for about the same file size.
+    pub fn hit_rate(&self) -> f64 {
+        if self.references == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.references as f64
+        }
+    }
+}
 
 /// Shared strings table that deduplicates strings across the workbook
 pub struct SharedStrings {
@@ -11,6 +139,8 @@ pub struct SharedStrings {
     string_map: IndexMap<String, u32>,
     max_unique_strings: usize, // Giới hạn số string unique để tiết kiệm memory
     total_count: u32,          // Track total number of string references (for count attribute)
+    hits: u64,
+    overflow: u64,
 }
 
 impl SharedStrings {
@@ -20,6 +150,8 @@ impl SharedStrings {
             string_map: IndexMap::with_capacity(1000),
             max_unique_strings: 100_000, // Giới hạn 100K unique strings
             total_count: 0,
+            hits: 0,
+            overflow: 0,
         }
     }
 
@@ -30,6 +162,113 @@ impl SharedStrings {
             string_map: IndexMap::with_capacity(capacity),
             max_unique_strings: max_unique,
             total_count: 0,
+            hits: 0,
+            overflow: 0,
+        }
+    }
+
+    /// Continue a table from a snapshot; existing strings keep their indices
+    pub fn from_snapshot(snapshot: SharedStringsSnapshot) -> Self {
+        let mut string_map = IndexMap::with_capacity(snapshot.strings.len());
+        // Strings past the limit were never deduplicated, keep it that way
+        for (index, s) in snapshot
+            .strings
+            .iter()
+            .take(snapshot.max_unique_strings)
+            .enumerate()
+        {
+            string_map.entry(s.clone()).or_insert(index as u32);
+        }
+        SharedStrings {
+            strings: snapshot.strings,
+            string_map,
+            max_unique_strings: snapshot.max_unique_strings,
+            total_count: snapshot.total_count,
+            hits: 0,
+            overflow: 0,
+        }
+    }
+
+    /// Table with the most frequent strings of `sample` registered first,
+    /// most frequent at index 0 (ties keep first-seen order)
+    ///
+    /// At most `limit` strings are registered, and strings seen only once
+    /// are left out: they gain nothing from a low index. The sample only
+    /// ranks strings; its references are not counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use excelstream::fast_writer::SharedStrings;
+    ///
+    /// let sample = ["Open", "Closed", "Open", "Pending", "Open", "Closed"];
+    /// let mut sst = SharedStrings::from_frequencies(sample, 10);
+    /// assert_eq!(sst.add_string("Open"), 0);
+    /// assert_eq!(sst.add_string("Closed"), 1);
+    /// assert_eq!(sst.add_string("Pending"), 2);
+    /// ```
+    pub fn from_frequencies<I, S>(sample: I, limit: usize) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut counts: IndexMap<String, u64> = IndexMap::new();
+        for s in sample {
+            let s = s.as_ref();
+            match counts.get_mut(s) {
+                Some(count) => *count += 1,
+                None => {
+                    counts.insert(s.to_string(), 1);
+                }
+            }
+        }
+        // Stable sort keeps first-seen order among equal counts
+        let mut ranked: Vec<(String, u64)> =
+            counts.into_iter().filter(|(_, count)| *count > 1).collect();
+        ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let mut table = Self::new();
+        table.preregister(ranked.into_iter().take(limit).map(|(s, _)| s));
+        table
+    }
+
+    /// Register strings without referencing them, so they get the next
+    /// indices in order; strings already in the table keep theirs
+    ///
+    /// Call before writing cells to give frequent strings the lowest
+    /// indices. Registered strings count toward the unique string limit.
+    pub fn preregister<I, S>(&mut self, strings: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for s in strings {
+            let s = s.as_ref();
+            if self.string_map.contains_key(s) || self.strings.len() >= self.max_unique_strings {
+                continue;
+            }
+            let index = self.strings.len() as u32;
+            self.strings.push(s.to_string());
+            self.string_map.insert(s.to_string(), index);
+        }
+    }
+
+    /// Deduplication counters since the table was created
+    pub fn stats(&self) -> SharedStringsStats {
+        SharedStringsStats {
+            references: self.total_count as u64,
+            hits: self.hits,
+            unique: self.strings.len(),
+            overflow: self.overflow,
+        }
+    }
+
+    /// Current state, to be restored with [`from_snapshot`](Self::from_snapshot)
+    pub fn snapshot(&self) -> SharedStringsSnapshot {
+        SharedStringsSnapshot {
+            strings: self.strings.clone(),
+            total_count: self.total_count,
+            max_unique_strings: self.max_unique_strings,
         }
     }
 
@@ -39,12 +278,14 @@ impl SharedStrings {
         self.total_count += 1;
 
         if let Some(&index) = self.string_map.get(s) {
+            self.hits += 1;
             return index;
         }
 
         // Nếu đã đạt giới hạn, không lưu vào map nữa (tránh memory leak)
         // Nhưng vẫn lưu string để đảm bảo tính đúng
         if self.strings.len() >= self.max_unique_strings {
+            self.overflow += 1;
             let index = self.strings.len() as u32;
             self.strings.push(s.to_string());
             return index;
@@ -61,6 +302,15 @@ impl SharedStrings {
         self.strings.len()
     }
 
+    /// Heap held by the table: each string is kept twice, in order and as
+    /// a lookup key
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let text: usize = self.strings.iter().map(String::capacity).sum();
+        let slots = self.strings.capacity() * std::mem::size_of::<String>()
+            + self.string_map.capacity() * std::mem::size_of::<(String, u32, u64)>();
+        2 * text + slots
+    }
+
     /// Write shared strings XML
     pub fn write_xml<W: Write>(&self, writer: &mut XmlWriter<W>) -> Result<()> {
         // XML declaration
@@ -119,4 +369,50 @@ mod tests {
         assert_eq!(idx3, 0); // Should return same index
         assert_eq!(ss.count(), 2);
     }
+
+    #[test]
+    fn test_frequencies_and_stats() {
+        let sample = ["b", "a", "c", "a", "b", "a", "d"];
+        let mut ss = SharedStrings::from_frequencies(sample, 10);
+        assert_eq!(ss.count(), 2); // "c" and "d" appear once
+        assert_eq!(ss.stats().references, 0);
+        assert_eq!(ss.add_string("a"), 0);
+        assert_eq!(ss.add_string("b"), 1);
+        assert_eq!(ss.add_string("c"), 2);
+        assert_eq!(ss.add_string("a"), 0);
+        let stats = ss.stats();
+        assert_eq!((stats.references, stats.hits, stats.unique), (4, 3, 3));
+        assert_eq!(stats.hit_rate(), 0.75);
+
+        let ss = SharedStrings::from_frequencies(sample, 1);
+        assert_eq!(ss.count(), 1);
+
+        let mut ss = SharedStrings::with_capacity(2, 2);
+        ss.preregister(["x", "y", "z"]);
+        assert_eq!(ss.count(), 2);
+        assert_eq!(ss.add_string("z"), 2);
+        assert_eq!(ss.add_string("z"), 3);
+        assert_eq!(ss.stats().overflow, 2);
+        assert_eq!(SharedStringsStats::default().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut ss = SharedStrings::with_capacity(4, 2);
+        for s in ["a", "b\nline", "c", "a"] {
+            ss.add_string(s);
+        }
+        let mut bytes = Vec::new();
+        ss.snapshot().write_to(&mut bytes).unwrap();
+
+        let snapshot = SharedStringsSnapshot::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(snapshot, ss.snapshot());
+        let mut restored = SharedStrings::from_snapshot(snapshot);
+        assert_eq!(restored.add_string("b\nline"), 1);
+        assert_eq!(restored.add_string("d"), 3);
+        assert_eq!(restored.snapshot().total_count, 6);
+
+        assert!(SharedStringsSnapshot::read_from(&mut &b"garbage!"[..]).is_err());
+        assert!(SharedStringsSnapshot::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
 }
@@ -0,0 +1,350 @@
+//! Style table (styles.xml) with on-demand number format registration
+
+use crate::types::{CellStyle, MAX_CELL_STYLES};
+
+/// First id available for custom number formats (ids below 164 are built-in)
+const FIRST_CUSTOM_NUM_FMT_ID: u32 = 164;
+
+/// Built-in number format `[h]:mm:ss`
+pub(crate) const ELAPSED_TIME_NUM_FMT_ID: u32 = 46;
+
+/// Built-in number format `0%`, used by [`CellStyle::NumberPercentage`]
+pub(crate) const PERCENT_NUM_FMT_ID: u32 = 9;
+
+/// Cell format (`xf`) attributes: number format, font, fill, border and
+/// whether the cell is locked when its sheet is protected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Xf {
+    num_fmt_id: u32,
+    font_id: u32,
+    fill_id: u32,
+    border_id: u32,
+    locked: bool,
+}
+
+impl Xf {
+    const fn new(num_fmt_id: u32, font_id: u32, fill_id: u32, border_id: u32) -> Self {
+        Xf {
+            num_fmt_id,
+            font_id,
+            fill_id,
+            border_id,
+            locked: true,
+        }
+    }
+
+    fn to_xml(self) -> String {
+        let mut xml = format!(
+            "<xf numFmtId=\"{}\" fontId=\"{}\" fillId=\"{}\" borderId=\"{}\" xfId=\"0\"",
+            self.num_fmt_id, self.font_id, self.fill_id, self.border_id
+        );
+        for (used, attr) in [
+            (self.num_fmt_id != 0, " applyNumberFormat=\"1\""),
+            (self.font_id != 0, " applyFont=\"1\""),
+            (self.fill_id != 0, " applyFill=\"1\""),
+            (self.border_id != 0, " applyBorder=\"1\""),
+        ] {
+            if used {
+                xml.push_str(attr);
+            }
+        }
+        if self.locked {
+            xml.push_str("/>");
+        } else {
+            xml.push_str(" applyProtection=\"1\"><protection locked=\"0\"/></xf>");
+        }
+        xml
+    }
+}
+
+/// Cell formats of the [`CellStyle`] presets, in index order
+const PRESET_XFS: [Xf; CellStyle::BUILTIN_COUNT as usize] = [
+    Xf::new(0, 0, 0, 0),                  // Default
+    Xf::new(0, 1, 0, 0),                  // HeaderBold
+    Xf::new(3, 0, 0, 0),                  // NumberInteger
+    Xf::new(4, 0, 0, 0),                  // NumberDecimal
+    Xf::new(5, 0, 0, 0),                  // NumberCurrency
+    Xf::new(PERCENT_NUM_FMT_ID, 0, 0, 0), // NumberPercentage
+    Xf::new(14, 0, 0, 0),                 // DateDefault
+    Xf::new(22, 0, 0, 0),                 // DateTimestamp
+    Xf::new(0, 1, 0, 0),                  // TextBold
+    Xf::new(0, 2, 0, 0),                  // TextItalic
+    Xf::new(0, 0, 2, 0),                  // HighlightYellow
+    Xf::new(0, 0, 3, 0),                  // HighlightGreen
+    Xf::new(0, 0, 4, 0),                  // HighlightRed
+    Xf::new(0, 0, 0, 1),                  // BorderThin
+    Xf::new(49, 0, 0, 0),                 // TextFormat
+    Xf {
+        locked: false,
+        ..Xf::new(0, 0, 0, 0)
+    }, // Unlocked
+];
+
+/// Workbook style registry
+///
+/// Holds the preset styles from [`CellStyle`] plus any cell formats
+/// registered while writing (currency and duration styles, user format
+/// codes, unlocked copies of other styles). Each registered format gets its own cell format
+/// (`xf`) index after the presets.
+///
+/// The registry holds at most [`MAX_CELL_STYLES`] cell formats. Once full,
+/// registering a new format adds nothing and returns `MAX_CELL_STYLES`, an
+/// index writers reject or write unstyled per their
+/// [`LimitPolicy`](crate::types::LimitPolicy).
+#[derive(Debug, Clone, Default)]
+pub struct StyleRegistry {
+    num_fmts: Vec<String>,
+    xfs: Vec<Xf>,
+}
+
+impl StyleRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a number format code and return its cell style index
+    ///
+    /// Registering the same code twice returns the same index.
+    pub fn register_num_fmt(&mut self, code: &str) -> u32 {
+        match self.num_fmt_id(code) {
+            Some(num_fmt_id) => self.register_xf(Xf::new(num_fmt_id, 0, 0, 0)),
+            None => MAX_CELL_STYLES,
+        }
+    }
+
+    /// Register a copy of a style with another number format code and
+    /// return its cell style index
+    ///
+    /// Font, fill, border and protection are kept from `style`.
+    pub fn register_with_num_fmt(&mut self, style: &CellStyle, code: &str) -> u32 {
+        let index = self.style_index(style);
+        let xf = self.xf(index).unwrap_or(PRESET_XFS[0]);
+        match self.num_fmt_id(code) {
+            Some(num_fmt_id) => self.register_xf(Xf { num_fmt_id, ..xf }),
+            None => MAX_CELL_STYLES,
+        }
+    }
+
+    // Id of a custom number format code, added if new and a cell format
+    // using it still fits
+    fn num_fmt_id(&mut self, code: &str) -> Option<u32> {
+        let pos = match self.num_fmts.iter().position(|c| c == code) {
+            Some(pos) => pos,
+            None if self.is_full() => return None,
+            None => {
+                self.num_fmts.push(code.to_string());
+                self.num_fmts.len() - 1
+            }
+        };
+        Some(FIRST_CUSTOM_NUM_FMT_ID + pos as u32)
+    }
+
+    /// Register a copy of a style that stays editable on protected sheets
+    /// and return its cell style index
+    pub fn register_unlocked(&mut self, style: &CellStyle) -> u32 {
+        let index = self.style_index(style);
+        if index >= MAX_CELL_STYLES {
+            return index;
+        }
+        let xf = self.xf(index).unwrap_or(PRESET_XFS[0]);
+        if !xf.locked {
+            return index;
+        }
+        self.register_xf(Xf {
+            locked: false,
+            ..xf
+        })
+    }
+
+    fn xf(&self, index: u32) -> Option<Xf> {
+        match index.checked_sub(CellStyle::BUILTIN_COUNT) {
+            None => PRESET_XFS.get(index as usize).copied(),
+            Some(pos) => self.xfs.get(pos as usize).copied(),
+        }
+    }
+
+    fn register_xf(&mut self, xf: Xf) -> u32 {
+        if let Some(preset) = PRESET_XFS.iter().position(|x| *x == xf) {
+            return preset as u32;
+        }
+        let pos = match self.xfs.iter().position(|x| *x == xf) {
+            Some(pos) => pos,
+            None if self.is_full() => return MAX_CELL_STYLES,
+            None => {
+                self.xfs.push(xf);
+                self.xfs.len() - 1
+            }
+        };
+        CellStyle::BUILTIN_COUNT + pos as u32
+    }
+
+    /// Resolve the cell style index for a style, registering it if needed
+    pub fn style_index(&mut self, style: &CellStyle) -> u32 {
+        match style {
+            CellStyle::Currency(currency) => self.register_num_fmt(&currency.format_code()),
+            CellStyle::Duration => self.register_xf(Xf::new(ELAPSED_TIME_NUM_FMT_ID, 0, 0, 0)),
+            other => other.index(),
+        }
+    }
+
+    /// Number of cell formats (presets + registered)
+    pub fn xf_count(&self) -> u32 {
+        CellStyle::BUILTIN_COUNT + self.xfs.len() as u32
+    }
+
+    // No room for another cell format
+    fn is_full(&self) -> bool {
+        self.xf_count() >= MAX_CELL_STYLES
+    }
+
+    /// Generate styles.xml
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+"#,
+        );
+
+        if self.num_fmts.is_empty() {
+            xml.push_str("<numFmts count=\"0\"/>\n");
+        } else {
+            xml.push_str(&format!("<numFmts count=\"{}\">\n", self.num_fmts.len()));
+            for (i, code) in self.num_fmts.iter().enumerate() {
+                xml.push_str(&format!(
+                    "<numFmt numFmtId=\"{}\" formatCode=\"{}\"/>\n",
+                    FIRST_CUSTOM_NUM_FMT_ID + i as u32,
+                    escape_attr(code)
+                ));
+            }
+            xml.push_str("</numFmts>\n");
+        }
+
+        xml.push_str(
+            r#"<fonts count="3">
+<font><sz val="11"/><name val="Calibri"/></font>
+<font><b/><sz val="11"/><name val="Calibri"/></font>
+<font><i/><sz val="11"/><name val="Calibri"/></font>
+</fonts>
+<fills count="5">
+<fill><patternFill patternType="none"/></fill>
+<fill><patternFill patternType="gray125"/></fill>
+<fill><patternFill patternType="solid"><fgColor rgb="FFFFFF00"/></patternFill></fill>
+<fill><patternFill patternType="solid"><fgColor rgb="FF00FF00"/></patternFill></fill>
+<fill><patternFill patternType="solid"><fgColor rgb="FFFF0000"/></patternFill></fill>
+</fills>
+<borders count="2">
+<border><left/><right/><top/><bottom/><diagonal/></border>
+<border><left style="thin"/><right style="thin"/><top style="thin"/><bottom style="thin"/></border>
+</borders>
+"#,
+        );
+
+        xml.push_str(&format!("<cellXfs count=\"{}\">\n", self.xf_count()));
+        for xf in PRESET_XFS.iter().chain(&self.xfs) {
+            xml.push_str(&xf.to_xml());
+            xml.push('\n');
+        }
+        xml.push_str("</cellXfs>\n</styleSheet>");
+        xml
+    }
+}
+
+fn escape_attr(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numfmt::Currency;
+
+    #[test]
+    fn test_register_currency_styles() {
+        let mut styles = StyleRegistry::new();
+        assert_eq!(styles.style_index(&CellStyle::HeaderBold), 1);
+
+        let vnd = styles.style_index(&CellStyle::Currency(Currency::VND));
+        let eur = styles.style_index(&CellStyle::Currency(Currency::EUR));
+        assert_eq!(vnd, CellStyle::BUILTIN_COUNT);
+        assert_eq!(eur, CellStyle::BUILTIN_COUNT + 1);
+        assert_eq!(styles.style_index(&CellStyle::Currency(Currency::VND)), vnd);
+
+        let xml = styles.to_xml();
+        assert!(xml.contains("<numFmts count=\"2\">"));
+        assert!(xml.contains("formatCode=\"#,##0 [$₫-42A]\""));
+        assert!(xml.contains("<cellXfs count=\"18\">"));
+    }
+
+    #[test]
+    fn test_register_unlocked_styles() {
+        let mut styles = StyleRegistry::new();
+        assert_eq!(styles.register_unlocked(&CellStyle::Default), 15);
+        assert_eq!(styles.register_unlocked(&CellStyle::Unlocked), 15);
+
+        let bold = styles.register_unlocked(&CellStyle::HeaderBold);
+        let money = styles.register_num_fmt("#,##0.00");
+        let money_unlocked = styles.register_unlocked(&CellStyle::NumberFormat(money));
+        assert_eq!(
+            (bold, money, money_unlocked),
+            (CellStyle::BUILTIN_COUNT, bold + 1, bold + 2)
+        );
+        assert_eq!(styles.register_unlocked(&CellStyle::HeaderBold), bold);
+
+        let xml = styles.to_xml();
+        assert!(xml.contains(
+            "<xf numFmtId=\"0\" fontId=\"1\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyFont=\"1\" applyProtection=\"1\"><protection locked=\"0\"/></xf>"
+        ));
+        assert!(xml.contains(
+            "<xf numFmtId=\"164\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\" applyProtection=\"1\">"
+        ));
+    }
+
+    #[test]
+    fn test_register_with_num_fmt() {
+        let mut styles = StyleRegistry::new();
+        let bold = styles.register_with_num_fmt(&CellStyle::HeaderBold, "#,##0.00");
+        assert_eq!(bold, CellStyle::BUILTIN_COUNT);
+        assert_eq!(styles.register_num_fmt("#,##0.00"), bold + 1);
+        assert_eq!(
+            styles.register_with_num_fmt(&CellStyle::NumberCurrency, "#,##0.00"),
+            bold + 1
+        );
+        assert!(styles.to_xml().contains(
+            "<xf numFmtId=\"164\" fontId=\"1\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\" applyFont=\"1\"/>"
+        ));
+    }
+
+    #[test]
+    fn test_registry_stops_at_max_cell_styles() {
+        let mut styles = StyleRegistry::new();
+        let zero = styles.register_num_fmt("0.0");
+        // Fill the rest with distinct formats without registering each one
+        let rest = MAX_CELL_STYLES - styles.xf_count();
+        styles
+            .xfs
+            .extend((0..rest).map(|i| Xf::new(FIRST_CUSTOM_NUM_FMT_ID, 0, 0, i + 2)));
+        assert_eq!(styles.xf_count(), MAX_CELL_STYLES);
+
+        assert_eq!(styles.register_num_fmt("0.000 \"new\""), MAX_CELL_STYLES);
+        assert_eq!(styles.style_index(&CellStyle::Duration), MAX_CELL_STYLES);
+        assert_eq!(
+            styles.register_unlocked(&CellStyle::NumberFormat(MAX_CELL_STYLES)),
+            MAX_CELL_STYLES
+        );
+        assert_eq!(styles.register_num_fmt("0.0"), zero);
+        assert_eq!(styles.xf_count(), MAX_CELL_STYLES);
+        assert!(!styles.to_xml().contains("new"));
+    }
+
+    #[test]
+    fn test_escape_format_code() {
+        let mut styles = StyleRegistry::new();
+        styles.register_num_fmt("0.00 \"pcs\"");
+        assert!(styles
+            .to_xml()
+            .contains("formatCode=\"0.00 &quot;pcs&quot;\""));
+    }
+}
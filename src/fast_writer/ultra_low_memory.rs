@@ -1,29 +1,101 @@
 //! Ultra-low memory workbook - wrapper around ZeroTempWorkbook
 
 use super::zero_temp_workbook::ZeroTempWorkbook;
-use crate::error::Result;
-use crate::types::{CellValue, ProtectionOptions};
-use std::path::Path;
+use crate::error::{ExcelError, Result};
+use crate::sink::{Sink, WrapSink};
+use crate::staging::{self, StagedFile};
+use crate::types::{CellValue, ProtectionOptions, StyledCell, WriteReport, ZipLayout};
+use crate::writer::SpreadsheetWriter;
+use std::path::{Path, PathBuf};
 
 pub struct UltraLowMemoryWorkbook {
-    inner: ZeroTempWorkbook,
+    inner: ZeroTempWorkbook<Box<dyn Sink>>,
     compression_level: u32,
+    path: PathBuf,
+    staged: Option<StagedFile>,
+    disk_full: Option<(u64, u64)>, // (needed, available) once the disk filled up
+    zip_layout: ZipLayout,
 }
 
+// Entries moved in front of the worksheets by `ZipLayout::metadata_first`,
+// in the order a streaming reader needs them
+const METADATA_ENTRIES: &[&str] = &[
+    "[Content_Types].xml",
+    "_rels/.rels",
+    "xl/workbook.xml",
+    "xl/_rels/workbook.xml.rels",
+    "xl/styles.xml",
+    "xl/sharedStrings.xml",
+];
+
 impl UltraLowMemoryWorkbook {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         Self::with_compression(path, 6)
     }
 
     pub fn with_compression<P: AsRef<Path>>(path: P, compression_level: u32) -> Result<Self> {
-        let inner = ZeroTempWorkbook::new(
-            path.as_ref().to_str().unwrap_or("output.xlsx"),
-            compression_level.min(9),
-        )?;
+        Self::create(path.as_ref(), compression_level, None, None)
+    }
+
+    /// Write into a temp file in `temp_dir` and move it to `path` on close
+    ///
+    /// Nothing appears at `path` until [`close`](Self::close) succeeds. If the
+    /// workbook is dropped before that (error, early return or panic), the
+    /// temp file is deleted.
+    pub fn with_temp_dir<P: AsRef<Path>, D: AsRef<Path>>(path: P, temp_dir: D) -> Result<Self> {
+        Self::create(path.as_ref(), 6, Some(temp_dir.as_ref()), None)
+    }
+
+    pub(crate) fn create(
+        path: &Path,
+        compression_level: u32,
+        temp_dir: Option<&Path>,
+        wrap: Option<WrapSink>,
+    ) -> Result<Self> {
+        let compression_level = compression_level.min(9);
+        let (file, staged) = match temp_dir {
+            Some(dir) => {
+                let staged = StagedFile::new(path, Some(dir))?;
+                (staged.file()?, Some(staged))
+            }
+            None => (std::fs::File::create(path)?, None),
+        };
+        let sink = match wrap {
+            Some(wrap) => wrap(file),
+            None => Box::new(file),
+        };
+        let inner = ZeroTempWorkbook::from_writer(sink, compression_level)?;
 
         Ok(UltraLowMemoryWorkbook {
             inner,
-            compression_level: compression_level.min(9),
+            compression_level,
+            path: path.to_path_buf(),
+            staged,
+            disk_full: None,
+            zip_layout: ZipLayout::default(),
+        })
+    }
+
+    /// Run a write against the inner workbook, handling a full disk
+    ///
+    /// Running out of space removes the partial output and returns
+    /// [`ExcelError::DiskFull`], from then on for every write.
+    fn guarded<T>(
+        &mut self,
+        op: impl FnOnce(&mut ZeroTempWorkbook<Box<dyn Sink>>) -> Result<T>,
+    ) -> Result<T> {
+        if let Some((needed, available)) = self.disk_full {
+            return Err(ExcelError::DiskFull { needed, available });
+        }
+        op(&mut self.inner).map_err(|err| {
+            if !staging::is_disk_full(&err) {
+                return err;
+            }
+            let err = staging::discard_partial(&self.path, self.staged.take(), err);
+            if let ExcelError::DiskFull { needed, available } = err {
+                self.disk_full = Some((needed, available));
+            }
+            err
         })
     }
 
@@ -31,8 +103,24 @@ impl UltraLowMemoryWorkbook {
         self.inner.protect_sheet(options)
     }
 
+    pub fn register_number_format(&mut self, code: &str) -> u32 {
+        self.inner.register_number_format(code)
+    }
+
+    pub fn register_style_format(&mut self, style: &crate::types::CellStyle, code: &str) -> u32 {
+        self.inner.register_style_format(style, code)
+    }
+
+    pub fn register_unlocked_style(&mut self, style: &crate::types::CellStyle) -> u32 {
+        self.inner.register_unlocked_style(style)
+    }
+
+    pub fn sheet_names(&self) -> &[String] {
+        self.inner.sheet_names()
+    }
+
     pub fn add_worksheet(&mut self, name: &str) -> Result<()> {
-        self.inner.add_worksheet(name)
+        self.guarded(|inner| inner.add_worksheet(name))
     }
 
     pub fn write_row<I, S>(&mut self, values: I) -> Result<()>
@@ -40,7 +128,7 @@ impl UltraLowMemoryWorkbook {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.inner.write_row(values)
+        self.guarded(|inner| inner.write_row(values))
     }
 
     pub fn write_row_typed(&mut self, values: &[CellValue]) -> Result<()> {
@@ -50,12 +138,99 @@ impl UltraLowMemoryWorkbook {
             .map(|v| crate::types::StyledCell::new(v.clone(), crate::types::CellStyle::Default))
             .collect();
 
-        self.inner.write_row_styled(&styled_cells)
+        self.guarded(|inner| inner.write_row_styled(&styled_cells))
     }
 
     pub fn write_row_styled(&mut self, values: &[crate::types::StyledCell]) -> Result<()> {
         // Delegate to ZeroTempWorkbook which now supports styling
-        self.inner.write_row_styled(values)
+        self.guarded(|inner| inner.write_row_styled(values))
+    }
+
+    pub fn write_row_styled_ref<'c, I>(&mut self, cells: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (&'c CellValue, crate::types::CellStyle)>,
+    {
+        self.guarded(|inner| inner.write_row_styled_ref(cells))
+    }
+
+    pub fn set_formula_sanitization(&mut self, prefix: Option<char>) {
+        self.inner.set_formula_sanitization(prefix);
+    }
+
+    pub fn add_internal_hyperlink(&mut self, row: u32, col: u32, location: &str) -> Result<()> {
+        self.inner.add_internal_hyperlink(row, col, location)
+    }
+
+    pub fn set_limit_policy(&mut self, policy: crate::types::LimitPolicy) {
+        self.inner.set_limit_policy(policy);
+    }
+
+    pub fn set_throttle(&mut self, throttle: Option<crate::throttle::Throttle>) {
+        self.inner.set_throttle(throttle);
+    }
+
+    pub fn set_audit(&mut self, options: Option<crate::types::AuditOptions>) {
+        self.inner.set_audit(options);
+    }
+
+    pub fn set_auto_filter(&mut self, range: Option<&str>) {
+        self.inner.set_auto_filter(range);
+    }
+
+    pub fn sheet_extent(&self) -> (u32, u32) {
+        self.inner.sheet_extent()
+    }
+
+    pub fn set_index_sheet(&mut self, options: Option<crate::types::IndexSheetOptions>) {
+        self.inner.set_index_sheet(options);
+    }
+
+    pub fn set_header_footer(&mut self, header_footer: crate::types::HeaderFooter) -> Result<()> {
+        self.inner.set_header_footer(header_footer)
+    }
+
+    pub fn set_calc_settings(&mut self, settings: Option<crate::types::CalcSettings>) {
+        self.inner.set_calc_settings(settings);
+    }
+
+    pub fn set_sheet_visibility(
+        &mut self,
+        name: &str,
+        visibility: crate::types::SheetVisibility,
+    ) -> Result<()> {
+        self.inner.set_sheet_visibility(name, visibility)
+    }
+
+    pub fn set_active_sheet(&mut self, name: &str) -> Result<()> {
+        self.inner.set_active_sheet(name)
+    }
+
+    pub fn set_sheet_order(&mut self, names: &[&str]) -> Result<()> {
+        self.inner.set_sheet_order(names)
+    }
+
+    pub fn set_exact_dimensions(&mut self, enabled: bool, dir: Option<&Path>) {
+        self.inner.set_exact_dimensions(enabled, dir);
+    }
+
+    pub fn set_strict(&mut self, enabled: bool) {
+        self.inner.set_strict(enabled);
+    }
+
+    pub fn set_text_column(&mut self, col: u32) {
+        self.inner.set_text_column(col);
+    }
+
+    pub fn set_column_style(&mut self, col: u32, style: crate::types::CellStyle) {
+        self.inner.set_column_style(col, style);
+    }
+
+    pub fn set_float_format(&mut self, format: crate::types::FloatFormat) {
+        self.inner.set_float_format(format);
+    }
+
+    pub fn set_time_zone_policy(&mut self, policy: crate::types::TimeZonePolicy) {
+        self.inner.set_time_zone_policy(policy);
     }
 
     pub fn set_compression_level(&mut self, level: u32) {
@@ -66,26 +241,111 @@ impl UltraLowMemoryWorkbook {
         self.compression_level
     }
 
-    pub fn close(self) -> Result<()> {
-        self.inner.close()
+    /// Path the workbook is saved to
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 
-    // Stub methods for API compatibility
-    pub fn set_column_width(&mut self, _col: u32, _width: f64) -> Result<()> {
-        // TODO: Implement in ZeroTempWorkbook
-        Ok(())
+    pub fn set_embedded_manifest(&mut self, enabled: bool) {
+        self.inner.set_embedded_manifest(enabled);
+    }
+
+    pub fn set_memory_instrumentation(&mut self, enabled: bool) {
+        self.inner.set_memory_instrumentation(enabled);
+    }
+
+    pub fn set_vba_project(&mut self, project: Option<Vec<u8>>) -> Result<()> {
+        self.inner.set_vba_project(project)
+    }
+
+    pub fn set_signer(&mut self, signer: Option<Box<dyn crate::signature::Signer>>) -> Result<()> {
+        self.inner.set_signer(signer)
+    }
+
+    pub fn close(self) -> Result<WriteReport> {
+        if let Some((needed, available)) = self.disk_full {
+            return Err(ExcelError::DiskFull { needed, available });
+        }
+        match self.inner.close() {
+            Ok(mut report) => {
+                if !self.zip_layout.is_streamed() {
+                    // Rewrite next to the target; the first copy goes away
+                    let source = self
+                        .staged
+                        .as_ref()
+                        .map_or(self.path.as_path(), |s| s.path());
+                    let relaid = StagedFile::new(&self.path, None)?;
+                    let first = if self.zip_layout.metadata_first {
+                        METADATA_ENTRIES
+                    } else {
+                        &[]
+                    };
+                    report.compressed_bytes = crate::append::splice::relayout(
+                        source,
+                        first,
+                        self.zip_layout.alignment,
+                        relaid.file()?,
+                    )?;
+                    relaid.commit()?;
+                } else if let Some(staged) = self.staged {
+                    staged.commit()?;
+                }
+                Ok(report)
+            }
+            Err(err) => Err(staging::discard_partial(&self.path, self.staged, err)),
+        }
     }
 
+    /// Reorder or align the ZIP entries on close, see [`ZipLayout`]
+    pub fn set_zip_layout(&mut self, layout: ZipLayout) {
+        self.zip_layout = layout;
+    }
+
+    pub fn set_column_width(&mut self, col: u32, width: f64) -> Result<()> {
+        self.inner.set_column_width(col, width)
+    }
+
+    // Stub methods for API compatibility
     pub fn set_next_row_height(&mut self, _height: f64) -> Result<()> {
         // TODO: Implement in ZeroTempWorkbook
         Ok(())
     }
 
-    pub fn set_flush_interval(&mut self, _interval: u32) {
-        // Not applicable for ZeroTempWorkbook (always streaming)
+    pub fn set_flush_policy(&mut self, policy: super::FlushPolicy) {
+        self.inner.set_flush_policy(policy);
+    }
+
+    pub fn set_flush_interval(&mut self, interval: u32) {
+        self.inner.set_flush_interval(interval);
+    }
+
+    pub fn set_max_buffer_size(&mut self, size: usize) {
+        self.inner.set_max_buffer_size(size);
+    }
+}
+
+impl SpreadsheetWriter for UltraLowMemoryWorkbook {
+    fn add_sheet(&mut self, name: &str) -> Result<()> {
+        self.add_worksheet(name)
+    }
+
+    fn write_row<I, S>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        UltraLowMemoryWorkbook::write_row(self, values)
+    }
+
+    fn write_row_typed(&mut self, values: &[CellValue]) -> Result<()> {
+        UltraLowMemoryWorkbook::write_row_typed(self, values)
+    }
+
+    fn write_row_styled(&mut self, cells: &[StyledCell]) -> Result<()> {
+        UltraLowMemoryWorkbook::write_row_styled(self, cells)
     }
 
-    pub fn set_max_buffer_size(&mut self, _size: usize) {
-        // Not applicable for ZeroTempWorkbook (uses fixed 4KB buffer)
+    fn close(self) -> Result<WriteReport> {
+        UltraLowMemoryWorkbook::close(self)
     }
 }
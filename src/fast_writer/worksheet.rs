@@ -1,8 +1,10 @@
 //! Fast worksheet implementation with optimized row writing
 
 use super::shared_strings::SharedStrings;
+use super::styles::StyleRegistry;
 use super::xml_writer::XmlWriter;
 use crate::error::Result;
+use crate::types::{duration_serial, FloatFormat};
 use std::io::Write;
 
 /// Cell reference generator
@@ -27,14 +29,8 @@ impl CellRef {
     }
 
     fn to_cell_ref(&self, row: u32, col: u32) -> String {
-        let mut col_str = String::new();
-        let mut n = col;
-        while n > 0 {
-            let rem = (n - 1) % 26;
-            col_str.insert(0, (b'A' + rem as u8) as char);
-            n = (n - 1) / 26;
-        }
-        format!("{}{}", col_str, row)
+        // Both 1-based here
+        crate::cellref::to_a1(row - 1, col - 1)
     }
 }
 
@@ -44,6 +40,8 @@ pub struct FastWorksheet<W: Write> {
     shared_strings: SharedStrings,
     cell_ref: CellRef,
     row_count: u32,
+    float_format: FloatFormat,
+    styles: StyleRegistry,
 }
 
 impl<W: Write> FastWorksheet<W> {
@@ -74,9 +72,28 @@ impl<W: Write> FastWorksheet<W> {
             shared_strings,
             cell_ref: CellRef::new(),
             row_count: 0,
+            float_format: FloatFormat::default(),
+            styles: StyleRegistry::new(),
         })
     }
 
+    /// How float cells are written (significant digits, scientific notation)
+    pub fn set_float_format(&mut self, format: FloatFormat) {
+        self.float_format = format;
+    }
+
+    /// Continue the style registry of an earlier sheet of the workbook
+    pub fn set_styles(&mut self, styles: StyleRegistry) {
+        self.styles = styles;
+    }
+
+    /// Styles the sheet's cells refer to, for the workbook's styles.xml
+    ///
+    /// Durations register the `[h]:mm:ss` format on first use.
+    pub fn styles(&self) -> &StyleRegistry {
+        &self.styles
+    }
+
     /// Write a row of string data
     pub fn write_row(&mut self, values: &[&str]) -> Result<()> {
         self.cell_ref.next_row();
@@ -85,6 +102,10 @@ impl<W: Write> FastWorksheet<W> {
         // Start row element
         self.xml_writer.start_element("row")?;
         self.xml_writer.attribute_int("r", self.row_count as i64)?;
+        if !values.is_empty() {
+            self.xml_writer
+                .attribute("spans", &format!("1:{}", values.len()))?;
+        }
         self.xml_writer.close_start_tag()?;
 
         // Write cells
@@ -125,7 +146,7 @@ impl<W: Write> FastWorksheet<W> {
 
     /// Write a row of styled cells
     pub fn write_row_styled(&mut self, cells: &[crate::types::StyledCell]) -> Result<()> {
-        use crate::types::CellValue;
+        use crate::types::{CellStyle, CellValue};
 
         self.cell_ref.next_row();
         self.row_count += 1;
@@ -133,14 +154,24 @@ impl<W: Write> FastWorksheet<W> {
         // Start row element
         self.xml_writer.start_element("row")?;
         self.xml_writer.attribute_int("r", self.row_count as i64)?;
+        if !cells.is_empty() {
+            self.xml_writer
+                .attribute("spans", &format!("1:{}", cells.len()))?;
+        }
         self.xml_writer.close_start_tag()?;
 
         // Write cells
         for cell in cells {
             let cell_ref = self.cell_ref.next_cell();
-            let style_index = cell.style.index();
-
-            match &cell.value {
+            let style = match (cell.style, &cell.value) {
+                (CellStyle::Default, CellValue::Duration(_)) => CellStyle::Duration,
+                (CellStyle::Default, CellValue::Percent(_)) => CellStyle::NumberPercentage,
+                (style, _) => style,
+            };
+            let style_index = self.styles.style_index(&style);
+
+            let replaced = self.float_format.replace_non_finite(&cell.value);
+            match replaced.as_ref().unwrap_or(&cell.value) {
                 CellValue::Empty => {
                     // Skip empty cells
                 }
@@ -178,6 +209,22 @@ impl<W: Write> FastWorksheet<W> {
 
                     self.xml_writer.end_element("c")?;
                 }
+                CellValue::Decimal(d) => {
+                    self.xml_writer.start_element("c")?;
+                    self.xml_writer.attribute("r", &cell_ref)?;
+                    if style_index > 0 {
+                        self.xml_writer.attribute_int("s", style_index as i64)?;
+                    }
+                    self.xml_writer.attribute("t", "n")?; // Number type
+                    self.xml_writer.close_start_tag()?;
+
+                    self.xml_writer.start_element("v")?;
+                    self.xml_writer.close_start_tag()?;
+                    self.xml_writer.write_str(&d.to_string())?;
+                    self.xml_writer.end_element("v")?;
+
+                    self.xml_writer.end_element("c")?;
+                }
                 CellValue::Float(f) => {
                     self.xml_writer.start_element("c")?;
                     self.xml_writer.attribute("r", &cell_ref)?;
@@ -189,7 +236,38 @@ impl<W: Write> FastWorksheet<W> {
 
                     self.xml_writer.start_element("v")?;
                     self.xml_writer.close_start_tag()?;
-                    self.xml_writer.write_str(&f.to_string())?;
+                    self.xml_writer.write_float(*f, &self.float_format)?;
+                    self.xml_writer.end_element("v")?;
+
+                    self.xml_writer.end_element("c")?;
+                }
+                CellValue::Duration(d) => {
+                    self.xml_writer.start_element("c")?;
+                    self.xml_writer.attribute("r", &cell_ref)?;
+                    self.xml_writer.attribute_int("s", style_index as i64)?;
+                    self.xml_writer.attribute("t", "n")?; // Number type
+                    self.xml_writer.close_start_tag()?;
+
+                    self.xml_writer.start_element("v")?;
+                    self.xml_writer.close_start_tag()?;
+                    self.xml_writer
+                        .write_float(duration_serial(d), &self.float_format)?;
+                    self.xml_writer.end_element("v")?;
+
+                    self.xml_writer.end_element("c")?;
+                }
+                CellValue::Percent(p) => {
+                    self.xml_writer.start_element("c")?;
+                    self.xml_writer.attribute("r", &cell_ref)?;
+                    if style_index > 0 {
+                        self.xml_writer.attribute_int("s", style_index as i64)?;
+                    }
+                    self.xml_writer.attribute("t", "n")?; // Number type
+                    self.xml_writer.close_start_tag()?;
+
+                    self.xml_writer.start_element("v")?;
+                    self.xml_writer.close_start_tag()?;
+                    self.xml_writer.write_float(p / 100.0, &self.float_format)?;
                     self.xml_writer.end_element("v")?;
 
                     self.xml_writer.end_element("c")?;
@@ -226,9 +304,33 @@ impl<W: Write> FastWorksheet<W> {
 
                     self.xml_writer.end_element("c")?;
                 }
-                CellValue::DateTime(_) | CellValue::Error(_) => {
-                    // For DateTime and Error, convert to string
-                    let s = format!("{:?}", cell.value);
+                CellValue::Error(e) if CellValue::error_code(e).is_some() => {
+                    self.xml_writer.start_element("c")?;
+                    self.xml_writer.attribute("r", &cell_ref)?;
+                    if style_index > 0 {
+                        self.xml_writer.attribute_int("s", style_index as i64)?;
+                    }
+                    self.xml_writer.attribute("t", "e")?;
+                    self.xml_writer.close_start_tag()?;
+
+                    self.xml_writer.start_element("v")?;
+                    self.xml_writer.close_start_tag()?;
+                    self.xml_writer
+                        .write_str(CellValue::error_code(e).unwrap_or_default())?;
+                    self.xml_writer.end_element("v")?;
+
+                    self.xml_writer.end_element("c")?;
+                }
+                CellValue::DateTime(_)
+                | CellValue::DateTimeTz(_)
+                | CellValue::RichText(_)
+                | CellValue::Error(_) => {
+                    // For DateTime, rich text and unknown errors, convert to string
+                    let s = match &cell.value {
+                        CellValue::Error(e) => e.clone(),
+                        CellValue::DateTimeTz(_) | CellValue::RichText(_) => cell.value.as_string(),
+                        value => format!("{:?}", value),
+                    };
                     let string_index = self.shared_strings.add_string(&s);
 
                     self.xml_writer.start_element("c")?;
@@ -296,8 +398,32 @@ mod tests {
         let ss = ws.finish().unwrap();
 
         let xml = String::from_utf8(output).unwrap();
-        assert!(xml.contains("<row r=\"1\">"));
-        assert!(xml.contains("<row r=\"2\">"));
+        assert!(xml.contains("<row r=\"1\" spans=\"1:2\">"));
+        assert!(xml.contains("<row r=\"2\" spans=\"1:2\">"));
         assert_eq!(ss.count(), 4); // Name, Age, Alice, 30
     }
+
+    #[test]
+    fn test_duration_and_percent_cells_are_styled() {
+        use crate::types::CellValue;
+        use std::time::Duration;
+
+        let mut output = Vec::new();
+        let mut ws = FastWorksheet::new(&mut output, SharedStrings::new()).unwrap();
+        ws.write_row_typed(&[
+            CellValue::Duration(Duration::from_secs(36 * 3600)),
+            CellValue::Percent(12.5),
+        ])
+        .unwrap();
+        let duration = ws.styles().xf_count() - 1;
+        assert!(ws.styles().to_xml().contains("<xf numFmtId=\"46\""));
+        ws.finish().unwrap();
+
+        let xml = String::from_utf8(output).unwrap();
+        assert!(xml.contains(&format!(
+            "<c r=\"A1\" s=\"{}\" t=\"n\"><v>1.5</v></c>",
+            duration
+        )));
+        assert!(xml.contains("<c r=\"B1\" s=\"5\" t=\"n\"><v>0.125</v></c>"));
+    }
 }
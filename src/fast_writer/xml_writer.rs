@@ -1,6 +1,7 @@
 //! Optimized XML writer with minimal allocations
 
 use crate::error::Result;
+use crate::types::FloatFormat;
 use std::io::Write;
 
 /// Fast XML writer that writes directly to output without intermediate buffers
@@ -45,6 +46,13 @@ impl<W: Write> XmlWriter<W> {
         self.write_raw(s.as_bytes())
     }
 
+    /// Write a float as [`FloatFormat`] formats it
+    #[inline]
+    pub(crate) fn write_float(&mut self, value: f64, format: &FloatFormat) -> Result<()> {
+        format.write(value, &mut self.buffer);
+        self.auto_flush()
+    }
+
     /// Write XML element start tag
     #[inline]
     pub fn start_element(&mut self, name: &str) -> Result<()> {
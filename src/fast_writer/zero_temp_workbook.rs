@@ -2,42 +2,169 @@
 //!
 //! Expected memory: 8-12 MB (vs 17MB with temp files)
 
+use super::cell_xml::{write_escaped, CellWriter};
+use super::memory::{FlushPolicy, MemoryTracker};
 use super::shared_strings::SharedStrings;
+use super::styles::StyleRegistry;
 use super::StreamingZipWriter;
+use crate::cellref;
 use crate::error::Result;
-use crate::types::ProtectionOptions;
-use itoa;
+use crate::ooxml;
+use crate::signature::{self, Signer};
+use crate::throttle::{Throttle, Throttler};
+use crate::types::{
+    AuditOptions, CalcSettings, CellStyle, CellValue, FloatFormat, HeaderFooter, IndexSheetOptions,
+    LimitPolicy, ProtectionOptions, SheetVisibility, StyledCell, TimeZonePolicy, WriteReport,
+};
+use crate::writer::SpreadsheetWriter;
+use sha2::{Digest, Sha256};
+use std::io::{Seek, SeekFrom, Write};
+use std::time::Instant;
 
 /// Workbook that streams XML directly into compressor (no temp files)
-pub struct ZeroTempWorkbook {
-    zip_writer: Option<StreamingZipWriter<std::fs::File>>,
+///
+/// Writes to a file by default; [`from_writer`](Self::from_writer) takes any
+/// seekable output, e.g. a `Cursor<Vec<u8>>` to build the file in memory.
+pub struct ZeroTempWorkbook<W: Write + Seek = std::fs::File> {
+    zip_writer: Option<StreamingZipWriter<W>>,
     worksheets: Vec<String>,
+    visibility: Vec<SheetVisibility>, // Per worksheet, in write order
+    sheet_order: Vec<usize>,          // Worksheets listed first, in this order
+    active_sheet: Option<usize>,
     worksheet_count: u32,
     current_row: u32,
     max_col: u32,
     xml_buffer: Vec<u8>,
+    pending: Vec<u8>,          // Rows batched before they go to the compressor
+    flush_policy: FlushPolicy, // When `pending` is flushed
     #[allow(dead_code)]
     shared_strings: SharedStrings,
     #[allow(dead_code)]
     protection: Option<ProtectionOptions>,
+    styles: StyleRegistry,
+    cells: CellWriter, // Cell options of the current sheet
+    throttler: Option<Throttler>,
+    column_widths: Vec<(u32, f64)>, // Written with the first row of the current sheet
+    header_pending: bool,           // Current sheet's entry not started until its first row
+    hyperlinks: Vec<(u32, u32, String)>, // (row, col, location) of the current sheet
+    auto_filter: Option<String>,    // Autofilter range of the current sheet
+    index_sheet: Option<IndexSheetOptions>,
+    index_first: bool, // Last sheet file is the index, listed first
+    audit: Option<AuditOptions>,
+    audit_sheet: Option<String>, // Name the audit sheet was written under
+    calc_settings: Option<CalcSettings>,
+    custom_properties: Vec<(String, String)>, // docProps/custom.xml text properties
+    embed_manifest: bool,
+    signer: Option<Box<dyn Signer>>,
+    vba_project: Option<Vec<u8>>, // xl/vbaProject.bin, makes the workbook macro-enabled
+    strict: bool,                 // Write strict OOXML namespaces
+    part_hasher: Option<(String, Sha256)>, // Digest of the entry being written when signing
+    part_digests: Vec<(String, [u8; 32])>,
+    header_footer: Option<HeaderFooter>, // Page header/footer of the current sheet
+    vml_count: u32,                      // Header/footer picture drawings written
+    media_count: u32,
+    media_types: Vec<(String, &'static str)>, // (extension, content type) of media written
+    exact_dimensions: bool,
+    spool_dir: Option<std::path::PathBuf>,
+    spool: Option<std::fs::File>, // Rows of the current sheet when writing exact dimensions
     in_worksheet: bool,
+    per_sheet_rows: Vec<(String, u32)>,
+    sheet_stats: Vec<(u64, Option<[u8; 32]>)>, // (cells, first row digest) per sheet, for the audit
+    sheet_cells: u64,
+    schema_hash: Option<[u8; 32]>, // Digest of the current sheet's first row
+    total_cells: u64,
+    uncompressed_bytes: u64,
+    memory: Option<MemoryTracker>, // Buffer high-water marks, when instrumented
+    started_at: Instant,
+}
+
+/// Name of the machine the process runs on, for audit sheets
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
 }
 
 impl ZeroTempWorkbook {
     pub fn new(path: &str, compression_level: u32) -> Result<Self> {
         let zip_writer = StreamingZipWriter::with_compression(path, compression_level)?;
+        Ok(Self::from_zip_writer(zip_writer))
+    }
+
+    /// Stream the workbook into an already opened file
+    pub fn from_file(file: std::fs::File, compression_level: u32) -> Result<Self> {
+        Self::from_writer(file, compression_level)
+    }
+}
 
-        Ok(Self {
+impl<W: Write + Seek> ZeroTempWorkbook<W> {
+    /// Stream the workbook into any seekable output
+    pub fn from_writer(writer: W, compression_level: u32) -> Result<Self> {
+        let zip_writer =
+            StreamingZipWriter::from_writer_with_compression(writer, compression_level)?;
+        Ok(Self::from_zip_writer(zip_writer))
+    }
+
+    fn from_zip_writer(zip_writer: StreamingZipWriter<W>) -> Self {
+        Self {
             zip_writer: Some(zip_writer),
             worksheets: Vec::new(),
+            visibility: Vec::new(),
+            sheet_order: Vec::new(),
+            active_sheet: None,
             worksheet_count: 0,
             current_row: 0,
             max_col: 0,
             xml_buffer: Vec::with_capacity(4096),
+            pending: Vec::new(),
+            flush_policy: FlushPolicy::STREAMING,
             shared_strings: SharedStrings::new(),
             protection: None,
+            styles: StyleRegistry::new(),
+            cells: CellWriter::default(),
+            throttler: None,
+            column_widths: Vec::new(),
+            header_pending: false,
+            hyperlinks: Vec::new(),
+            auto_filter: None,
+            index_sheet: None,
+            index_first: false,
+            audit: None,
+            audit_sheet: None,
+            calc_settings: None,
+            custom_properties: Vec::new(),
+            embed_manifest: false,
+            signer: None,
+            vba_project: None,
+            strict: false,
+            part_hasher: None,
+            part_digests: Vec::new(),
+            header_footer: None,
+            vml_count: 0,
+            media_count: 0,
+            media_types: Vec::new(),
+            exact_dimensions: false,
+            spool_dir: None,
+            spool: None,
             in_worksheet: false,
-        })
+            per_sheet_rows: Vec::new(),
+            sheet_stats: Vec::new(),
+            sheet_cells: 0,
+            schema_hash: None,
+            total_cells: 0,
+            uncompressed_bytes: 0,
+            memory: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Names of the worksheets added so far
+    pub fn sheet_names(&self) -> &[String] {
+        &self.worksheets
     }
 
     pub fn add_worksheet(&mut self, name: &str) -> Result<()> {
@@ -46,27 +173,89 @@ impl ZeroTempWorkbook {
 
         self.worksheet_count += 1;
         self.worksheets.push(name.to_string());
+        self.visibility.push(SheetVisibility::Visible);
         self.current_row = 0;
         self.max_col = 0;
-        // Reset protection for new worksheet
+        self.sheet_cells = 0;
+        self.schema_hash = None;
+        // Reset protection and text columns for new worksheet
         self.protection = None;
+        self.cells.start_sheet();
+        self.column_widths.clear();
+        self.hyperlinks.clear();
+        self.auto_filter = None;
+        self.header_footer = None;
 
-        // Start new worksheet entry in ZIP
+        if self.exact_dimensions {
+            // Rows go to a spool; the entry is written once the used range is known
+            self.spool = Some(match &self.spool_dir {
+                Some(dir) => tempfile::tempfile_in(dir)?,
+                None => tempfile::tempfile()?,
+            });
+        } else {
+            // Started with the first row, once the column widths are known
+            self.header_pending = true;
+        }
+        self.in_worksheet = true;
+
+        Ok(())
+    }
+
+    // Start the current sheet's ZIP entry and write the XML up to <sheetData>
+    fn start_worksheet_entry(&mut self, dimension: Option<&str>) -> Result<()> {
         let entry_name = format!("xl/worksheets/sheet{}.xml", self.worksheet_count);
-        self.zip_writer.as_mut().unwrap().start_entry(&entry_name)?;
+        self.start_entry(&entry_name)?;
+        self.column_widths.sort_by_key(|(col, _)| *col);
+        let header = Self::worksheet_header(dimension, &self.column_widths);
+        self.write_markup(&header)
+    }
 
-        // Write worksheet XML header
-        let header = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    fn worksheet_header(dimension: Option<&str>, column_widths: &[(u32, f64)]) -> String {
+        let mut header = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
-<sheetData>"#;
+"#,
+        );
+        if let Some(dimension) = dimension {
+            header.push_str(&format!("<dimension ref=\"{}\"/>", dimension));
+        }
+        if !column_widths.is_empty() {
+            header.push_str("<cols>");
+            for (col, width) in column_widths {
+                header.push_str(&format!(
+                    "<col min=\"{0}\" max=\"{0}\" width=\"{1}\" customWidth=\"1\"/>",
+                    col + 1,
+                    width
+                ));
+            }
+            header.push_str("</cols>");
+        }
+        header.push_str("<sheetData>");
+        header
+    }
 
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(header.as_bytes())?;
-        self.in_worksheet = true;
+    /// Write "Strict Open XML" markup instead of transitional
+    ///
+    /// Parts use the strict (`purl.oclc.org`) namespaces and relationship
+    /// types, and the workbook declares `conformance="strict"`. Applies to
+    /// parts started afterwards, so set it before adding the first sheet.
+    /// Header/footer pictures are VML, which strict conformance excludes.
+    pub fn set_strict(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
 
-        Ok(())
+    /// Write each sheet's `<dimension>` with its exact used range
+    ///
+    /// The used range is only known once a sheet is finished, so its rows are
+    /// spooled to an anonymous temp file (in `dir`, or the system temp dir)
+    /// and copied into the ZIP entry behind the header. Costs one extra write
+    /// and read of the sheet XML. Applies to sheets added afterwards.
+    ///
+    /// Off by default: sheets are then written without a `<dimension>`,
+    /// which Excel and LibreOffice accept and readers compute by scanning.
+    pub fn set_exact_dimensions(&mut self, enabled: bool, dir: Option<&std::path::Path>) {
+        self.exact_dimensions = enabled;
+        self.spool_dir = dir.map(|d| d.to_path_buf());
     }
 
     pub fn protect_sheet(&mut self, options: ProtectionOptions) -> Result<()> {
@@ -74,6 +263,104 @@ impl ZeroTempWorkbook {
         Ok(())
     }
 
+    /// Prefix text cells that look like formulas with `prefix` (None disables)
+    pub fn set_formula_sanitization(&mut self, prefix: Option<char>) {
+        self.cells.formula_prefix = prefix;
+    }
+
+    /// How float cells are written (significant digits, scientific notation)
+    pub fn set_float_format(&mut self, format: FloatFormat) {
+        self.cells.float_format = format;
+    }
+
+    /// How cells with a UTC offset are written
+    pub fn set_time_zone_policy(&mut self, policy: TimeZonePolicy) {
+        self.cells.time_zone = policy;
+    }
+
+    /// What to do with cells over Excel's limits (default
+    /// [`LimitPolicy::Error`])
+    pub fn set_limit_policy(&mut self, policy: LimitPolicy) {
+        self.cells.limit_policy = policy;
+    }
+
+    /// Cap the rate rows are written at; `None` removes the cap
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.throttler = throttle.map(Throttler::new);
+    }
+
+    /// Write a column (0-based) of the current worksheet as text
+    ///
+    /// Cells without an explicit style get [`CellStyle::TextFormat`], so
+    /// leading zeros and phone numbers stay as written.
+    pub fn set_text_column(&mut self, col: u32) {
+        self.cells.set_text_column(col);
+    }
+
+    /// Style cells of a column (0-based) of the current worksheet that are
+    /// written without an explicit style
+    pub fn set_column_style(&mut self, col: u32, style: CellStyle) {
+        self.cells.set_column_style(col, style);
+    }
+
+    /// Set a column width (0-based column) of the current worksheet
+    ///
+    /// Fails for widths that aren't a number from 0 to
+    /// [`MAX_COLUMN_WIDTH`](crate::types::MAX_COLUMN_WIDTH).
+    pub fn set_column_width(&mut self, col: u32, width: f64) -> Result<()> {
+        crate::types::check_column_width(col, width)?;
+        if self.current_row > 0 {
+            return Err(crate::ExcelError::InvalidState(
+                "Column widths must be set before writing rows".to_string(),
+            ));
+        }
+        self.column_widths.retain(|(c, _)| *c != col);
+        self.column_widths.push((col, width));
+        Ok(())
+    }
+
+    /// Batch rows before handing them to the compressor, see [`FlushPolicy`]
+    ///
+    /// Larger batches mean fewer, bigger compressor writes at the cost of
+    /// holding the batch in memory. Rows are streamed one by one by default.
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.flush_policy = policy;
+    }
+
+    /// Turn row batching on (`interval` above 1) or off
+    ///
+    /// Batches are sized in bytes, not rows: turning batching on uses
+    /// [`FlushPolicy::BATCHED`] unless a batch size is already set.
+    pub fn set_flush_interval(&mut self, interval: u32) {
+        if interval <= 1 {
+            self.flush_policy = FlushPolicy::STREAMING;
+        } else if self.flush_policy.is_streaming() {
+            self.flush_policy = FlushPolicy::BATCHED;
+        }
+    }
+
+    /// Batch rows up to `size` bytes (0 = stream every row), short for
+    /// `set_flush_policy(FlushPolicy::new(size))`
+    pub fn set_max_buffer_size(&mut self, size: usize) {
+        self.flush_policy = FlushPolicy::new(size);
+    }
+
+    /// Register a custom number format code and return its style index
+    pub fn register_number_format(&mut self, code: &str) -> u32 {
+        self.styles.register_num_fmt(code)
+    }
+
+    /// Register a copy of a style with another number format code and
+    /// return its style index
+    pub fn register_style_format(&mut self, style: &CellStyle, code: &str) -> u32 {
+        self.styles.register_with_num_fmt(style, code)
+    }
+
+    /// Register an unlocked copy of a style and return its style index
+    pub fn register_unlocked_style(&mut self, style: &CellStyle) -> u32 {
+        self.styles.register_unlocked(style)
+    }
+
     pub fn write_row<I, S>(&mut self, values: I) -> Result<()>
     where
         I: IntoIterator<Item = S>,
@@ -86,51 +373,27 @@ impl ZeroTempWorkbook {
         }
 
         self.current_row += 1;
-
-        // Build row XML in buffer
         self.xml_buffer.clear();
-        self.xml_buffer.extend_from_slice(b"<row r=\"");
-
-        let mut num_buffer = itoa::Buffer::new();
-        self.xml_buffer
-            .extend_from_slice(num_buffer.format(self.current_row).as_bytes());
-
-        self.xml_buffer.extend_from_slice(b"\">");
-
-        let mut col_count = 0;
-        for (col_idx, value) in values.into_iter().enumerate() {
-            col_count += 1;
-
-            self.xml_buffer.extend_from_slice(b"<c r=\"");
-            Self::push_column_letter(&mut self.xml_buffer, col_idx as u32 + 1);
-            self.xml_buffer
-                .extend_from_slice(num_buffer.format(self.current_row).as_bytes());
-
-            let v = value.as_ref();
-            if v.is_empty() {
-                self.xml_buffer.extend_from_slice(b"\"/>");
-            } else {
-                self.xml_buffer
-                    .extend_from_slice(b"\" t=\"inlineStr\"><is><t>");
-                Self::write_escaped(&mut self.xml_buffer, v);
-                self.xml_buffer.extend_from_slice(b"</t></is></c>");
-            }
-        }
-        self.max_col = self.max_col.max(col_count);
-
-        self.xml_buffer.extend_from_slice(b"</row>");
-
-        // Stream to compressor immediately
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(&self.xml_buffer)?;
-
-        Ok(())
+        let cells = self.cells.text_row(
+            &mut self.xml_buffer,
+            &mut self.styles,
+            self.current_row,
+            None,
+            values,
+        );
+        self.finish_row(cells)
     }
 
     /// Write a row with cell styling
     pub fn write_row_styled(&mut self, cells: &[crate::types::StyledCell]) -> Result<()> {
+        self.write_row_styled_ref(cells.iter().map(|cell| (&cell.value, cell.style)))
+    }
+
+    /// Write a row of borrowed values with their styles
+    pub fn write_row_styled_ref<'c, I>(&mut self, cells: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (&'c CellValue, CellStyle)>,
+    {
         if !self.in_worksheet {
             return Err(crate::error::ExcelError::WriteError(
                 "No worksheet started".to_string(),
@@ -138,100 +401,144 @@ impl ZeroTempWorkbook {
         }
 
         self.current_row += 1;
-        self.max_col = self.max_col.max(cells.len() as u32);
-
-        // Build row XML in buffer
         self.xml_buffer.clear();
-        self.xml_buffer.extend_from_slice(b"<row r=\"");
-
-        let mut num_buffer = itoa::Buffer::new();
-        self.xml_buffer
-            .extend_from_slice(num_buffer.format(self.current_row).as_bytes());
-        self.xml_buffer.extend_from_slice(b"\">");
-
-        for (col_idx, styled_cell) in cells.iter().enumerate() {
-            let value = &styled_cell.value;
-            let style_id = styled_cell.style.index();
-
-            self.xml_buffer.extend_from_slice(b"<c r=\"");
-            Self::push_column_letter(&mut self.xml_buffer, col_idx as u32 + 1);
-            self.xml_buffer
-                .extend_from_slice(num_buffer.format(self.current_row).as_bytes());
-            self.xml_buffer.extend_from_slice(b"\"");
-
-            // Add style attribute if not default
-            if style_id > 0 {
-                self.xml_buffer.extend_from_slice(b" s=\"");
-                self.xml_buffer
-                    .extend_from_slice(num_buffer.format(style_id).as_bytes());
-                self.xml_buffer.extend_from_slice(b"\"");
-            }
+        let cells = self.cells.styled_row(
+            &mut self.xml_buffer,
+            &mut self.styles,
+            self.current_row,
+            None,
+            cells,
+        );
+        self.finish_row(cells)
+    }
 
-            // Write cell value based on type
-            match value {
-                crate::types::CellValue::Empty => {
-                    self.xml_buffer.extend_from_slice(b"/>");
-                }
-                crate::types::CellValue::Int(i) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer
-                        .extend_from_slice(num_buffer.format(*i).as_bytes());
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                crate::types::CellValue::Float(f) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(f.to_string().as_bytes()); // Float doesn't use itoa
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                crate::types::CellValue::Bool(b) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"b\"><v>");
-                    self.xml_buffer
-                        .extend_from_slice(if *b { b"1" } else { b"0" });
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                crate::types::CellValue::String(s) => {
-                    self.xml_buffer
-                        .extend_from_slice(b" t=\"inlineStr\"><is><t>");
-                    Self::write_escaped(&mut self.xml_buffer, s);
-                    self.xml_buffer.extend_from_slice(b"</t></is></c>");
-                }
-                crate::types::CellValue::Formula(f) => {
-                    self.xml_buffer.extend_from_slice(b"><f>");
-                    Self::write_escaped(&mut self.xml_buffer, f);
-                    self.xml_buffer.extend_from_slice(b"</f></c>");
-                }
-                crate::types::CellValue::DateTime(dt) => {
-                    // Excel date serial number
-                    self.xml_buffer.extend_from_slice(b" t=\"n\"><v>");
-                    self.xml_buffer.extend_from_slice(dt.to_string().as_bytes()); // Keep as is for now
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
-                crate::types::CellValue::Error(e) => {
-                    self.xml_buffer.extend_from_slice(b" t=\"e\"><v>");
-                    Self::write_escaped(&mut self.xml_buffer, e);
-                    self.xml_buffer.extend_from_slice(b"</v></c>");
-                }
+    // Count the row built in `xml_buffer` and emit it; a row over one of
+    // Excel's limits is dropped so it never reaches the output
+    fn finish_row(&mut self, cells: Result<u32>) -> Result<()> {
+        let col_count = match cells {
+            Ok(count) => count,
+            Err(err) => {
+                self.current_row -= 1;
+                return Err(err);
             }
+        };
+        self.max_col = self.max_col.max(col_count);
+        self.total_cells += col_count as u64;
+        self.sheet_cells += col_count as u64;
+        self.emit_row()
+    }
+
+    // Hand the row in `xml_buffer` to the compressor, batching rows per
+    // the flush policy
+    fn emit_row(&mut self) -> Result<()> {
+        if let Some(memory) = self.memory.as_mut() {
+            memory.observe_buffers(self.xml_buffer.capacity(), self.pending.capacity());
+        }
+        if self.current_row == 1 && self.audit.is_some() {
+            self.schema_hash = Some(Sha256::digest(&self.xml_buffer).into());
+        }
+        if let Some(wait) = self
+            .throttler
+            .as_mut()
+            .and_then(|t| t.record(self.xml_buffer.len()))
+        {
+            std::thread::sleep(wait);
+        }
+        let pending = self.pending.len();
+        let flush = self.flush_policy.flush_len(pending, self.xml_buffer.len());
+        if flush == 0 {
+            self.pending.extend_from_slice(&self.xml_buffer);
+            return Ok(());
         }
 
-        self.xml_buffer.extend_from_slice(b"</row>");
+        self.flush_pending()?;
+        if flush == pending {
+            self.pending.extend_from_slice(&self.xml_buffer);
+        } else {
+            // The row completes the batch: pass it on without copying
+            let row = std::mem::take(&mut self.xml_buffer);
+            let result = self.write_sheet_data(&row);
+            self.xml_buffer = row;
+            result?;
+        }
+        Ok(())
+    }
 
-        // Stream to compressor immediately
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(&self.xml_buffer)?;
+    fn flush_pending(&mut self) -> Result<()> {
+        if !self.pending.is_empty() {
+            if let Some(memory) = self.memory.as_mut() {
+                memory.observe_buffers(self.xml_buffer.capacity(), self.pending.capacity());
+            }
+            let mut pending = std::mem::take(&mut self.pending);
+            let result = self.write_sheet_data(&pending);
+            pending.clear();
+            self.pending = pending;
+            result?;
+        }
+        Ok(())
+    }
+
+    // Append row XML to the current sheet (or its spool)
+    fn write_sheet_data(&mut self, data: &[u8]) -> Result<()> {
+        self.start_pending_worksheet()?;
+        self.uncompressed_bytes += data.len() as u64;
+        if let Some(spool) = self.spool.as_mut() {
+            spool.write_all(data)?;
+        } else {
+            if let Some((_, hasher)) = self.part_hasher.as_mut() {
+                hasher.update(data);
+            }
+            if let Some(memory) = self.memory.as_mut() {
+                memory.entry_data(data.len());
+            }
+            self.zip_writer.as_mut().unwrap().write_data(data)?;
+        }
+        Ok(())
+    }
 
+    // Start the current sheet's entry if no row has done so yet
+    fn start_pending_worksheet(&mut self) -> Result<()> {
+        if self.header_pending {
+            self.header_pending = false;
+            self.start_worksheet_entry(None)?;
+        }
         Ok(())
     }
 
     fn finish_current_worksheet(&mut self) -> Result<()> {
         if self.in_worksheet {
+            self.flush_pending()?;
+            self.start_pending_worksheet()?;
+            if let Some(mut spool) = self.spool.take() {
+                let dimension = if self.current_row == 0 || self.max_col == 0 {
+                    "A1".to_string()
+                } else {
+                    format!(
+                        "A1:{}",
+                        cellref::to_a1(self.current_row - 1, self.max_col - 1)
+                    )
+                };
+                self.start_worksheet_entry(Some(&dimension))?;
+                // Rows were counted in the uncompressed size when spooled
+                spool.seek(SeekFrom::Start(0))?;
+                let zip = self.zip_writer.as_mut().unwrap();
+                let mut chunk = vec![0u8; 64 * 1024];
+                loop {
+                    let n = std::io::Read::read(&mut spool, &mut chunk)?;
+                    if n == 0 {
+                        break;
+                    }
+                    if let Some((_, hasher)) = self.part_hasher.as_mut() {
+                        hasher.update(&chunk[..n]);
+                    }
+                    if let Some(memory) = self.memory.as_mut() {
+                        memory.entry_data(n);
+                    }
+                    zip.write_data(&chunk[..n])?;
+                }
+            }
             // Close sheetData
-            self.zip_writer
-                .as_mut()
-                .unwrap()
-                .write_data(b"</sheetData>")?;
+            self.write_data(b"</sheetData>")?;
 
             // Add sheetProtection if present
             if let Some(ref prot) = self.protection {
@@ -282,53 +589,546 @@ impl ZeroTempWorkbook {
 
                 protection_xml.push_str("/>");
 
-                self.zip_writer
-                    .as_mut()
-                    .unwrap()
-                    .write_data(protection_xml.as_bytes())?;
+                self.write_data(protection_xml.as_bytes())?;
+            }
+
+            if let Some(range) = self.auto_filter.take() {
+                let xml = format!("<autoFilter ref=\"{}\"/>", range);
+                self.write_data(xml.as_bytes())?;
+            }
+
+            if !self.hyperlinks.is_empty() {
+                let mut xml = b"<hyperlinks>".to_vec();
+                for (row, col, location) in &self.hyperlinks {
+                    xml.extend_from_slice(b"<hyperlink ref=\"");
+                    cellref::push_col_name(&mut xml, *col);
+                    xml.extend_from_slice((row + 1).to_string().as_bytes());
+                    xml.extend_from_slice(b"\" location=\"");
+                    write_escaped(&mut xml, location);
+                    xml.extend_from_slice(b"\"/>");
+                }
+                xml.extend_from_slice(b"</hyperlinks>");
+                self.write_data(&xml)?;
+                self.hyperlinks.clear();
+            }
+
+            let header_footer = self.header_footer.take();
+            if let Some(header_footer) = &header_footer {
+                let mut xml = b"<headerFooter>".to_vec();
+                if !header_footer.header.is_empty() {
+                    xml.extend_from_slice(b"<oddHeader>");
+                    write_escaped(&mut xml, &header_footer.header);
+                    xml.extend_from_slice(b"</oddHeader>");
+                }
+                if !header_footer.footer.is_empty() {
+                    xml.extend_from_slice(b"<oddFooter>");
+                    write_escaped(&mut xml, &header_footer.footer);
+                    xml.extend_from_slice(b"</oddFooter>");
+                }
+                xml.extend_from_slice(b"</headerFooter>");
+                if !header_footer.images.is_empty() {
+                    xml.extend_from_slice(b"<legacyDrawingHF r:id=\"rId1\"/>");
+                }
+                self.write_data(&xml)?;
             }
 
             // Close worksheet
-            self.zip_writer
-                .as_mut()
-                .unwrap()
-                .write_data(b"</worksheet>")?;
+            self.write_data(b"</worksheet>")?;
             self.in_worksheet = false;
+
+            if let Some(header_footer) = header_footer.filter(|hf| !hf.images.is_empty()) {
+                self.write_header_footer_drawing(&header_footer)?;
+            }
+
+            let name = self.worksheets.last().cloned().unwrap_or_default();
+            self.per_sheet_rows.push((name, self.current_row));
+            self.sheet_stats
+                .push((self.sheet_cells, self.schema_hash.take()));
+        }
+        Ok(())
+    }
+
+    /// Link a cell of the current sheet to a place in the workbook
+    /// (`location` like `'Data'!A1`)
+    ///
+    /// Past [`MAX_HYPERLINKS`](crate::types::MAX_HYPERLINKS) links in a sheet this fails, or drops the
+    /// link under [`LimitPolicy::Truncate`].
+    pub fn add_internal_hyperlink(&mut self, row: u32, col: u32, location: &str) -> Result<()> {
+        if self.cells.hyperlink_fits(self.hyperlinks.len(), row, col)? {
+            self.hyperlinks.push((row, col, location.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Put an autofilter on `range` (like "A1:D120") of the current sheet,
+    /// written when the sheet is finished; None removes it
+    pub fn set_auto_filter(&mut self, range: Option<&str>) {
+        self.auto_filter = range.map(str::to_string);
+    }
+
+    /// Rows written to the current sheet and its widest row's cell count
+    pub fn sheet_extent(&self) -> (u32, u32) {
+        (self.current_row, self.max_col)
+    }
+
+    /// Write a sheet linking to all others at close, placed first
+    pub fn set_index_sheet(&mut self, options: Option<IndexSheetOptions>) {
+        self.index_sheet = options;
+    }
+
+    /// Add a provenance sheet after the data sheets at close, see
+    /// [`AuditOptions`]
+    pub fn set_audit(&mut self, options: Option<AuditOptions>) {
+        self.audit = options;
+    }
+
+    /// Print a page header and footer, with pictures, on the current sheet
+    pub fn set_header_footer(&mut self, header_footer: HeaderFooter) -> Result<()> {
+        header_footer.validate()?;
+        self.header_footer = Some(header_footer);
+        Ok(())
+    }
+
+    fn sheet_index(&self, name: &str) -> Result<usize> {
+        self.worksheets
+            .iter()
+            .position(|sheet| sheet == name)
+            .ok_or_else(|| crate::error::ExcelError::SheetNotFound {
+                sheet: name.to_string(),
+                available: self.worksheets.join(", "),
+            })
+    }
+
+    /// Show or hide a sheet's tab
+    ///
+    /// The active sheet and the last visible sheet can't be hidden.
+    pub fn set_sheet_visibility(&mut self, name: &str, visibility: SheetVisibility) -> Result<()> {
+        let idx = self.sheet_index(name)?;
+        if visibility != SheetVisibility::Visible {
+            if self.active_sheet == Some(idx) {
+                return Err(crate::error::ExcelError::InvalidState(format!(
+                    "Sheet '{}' is the active sheet and can't be hidden",
+                    name
+                )));
+            }
+            let others_visible = self
+                .visibility
+                .iter()
+                .enumerate()
+                .any(|(i, v)| i != idx && *v == SheetVisibility::Visible);
+            if !others_visible {
+                return Err(crate::error::ExcelError::InvalidState(format!(
+                    "Sheet '{}' is the last visible sheet and can't be hidden",
+                    name
+                )));
+            }
+        }
+        self.visibility[idx] = visibility;
+        Ok(())
+    }
+
+    /// Select the tab shown when the file is opened (default: the first
+    /// visible sheet)
+    pub fn set_active_sheet(&mut self, name: &str) -> Result<()> {
+        let idx = self.sheet_index(name)?;
+        if self.visibility[idx] != SheetVisibility::Visible {
+            return Err(crate::error::ExcelError::InvalidState(format!(
+                "Hidden sheet '{}' can't be the active sheet",
+                name
+            )));
+        }
+        self.active_sheet = Some(idx);
+        Ok(())
+    }
+
+    /// Order the tabs: `names` first, in this order, then the sheets not
+    /// listed in the order they were added
+    pub fn set_sheet_order(&mut self, names: &[&str]) -> Result<()> {
+        let mut order = Vec::with_capacity(names.len());
+        for name in names {
+            let idx = self.sheet_index(name)?;
+            if order.contains(&idx) {
+                return Err(crate::error::ExcelError::InvalidState(format!(
+                    "Sheet '{}' is listed twice in the sheet order",
+                    name
+                )));
+            }
+            order.push(idx);
+        }
+        self.sheet_order = order;
+        Ok(())
+    }
+
+    /// Write `<calcPr>` into workbook.xml
+    pub fn set_calc_settings(&mut self, settings: Option<CalcSettings>) {
+        self.calc_settings = settings;
+    }
+
+    /// Set a text custom document property (File > Properties > Custom)
+    pub fn set_custom_property(&mut self, name: &str, value: &str) {
+        match self.custom_properties.iter_mut().find(|(n, _)| n == name) {
+            Some(property) => property.1 = value.to_string(),
+            None => self
+                .custom_properties
+                .push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Sign the workbook at close with an OOXML package signature, see
+    /// [`signature`](crate::signature)
+    ///
+    /// Parts are hashed as they are written, so this fails with
+    /// [`ExcelError::InvalidState`](crate::ExcelError::InvalidState) once
+    /// rows have been written or a sheet finished.
+    pub fn set_signer(&mut self, signer: Option<Box<dyn Signer>>) -> Result<()> {
+        if self.worksheet_count > 1 || self.current_row > 0 || !self.pending.is_empty() {
+            return Err(crate::ExcelError::InvalidState(
+                "Set the signer before writing rows".to_string(),
+            ));
+        }
+        // Nothing of the first sheet is out yet: its entry starts with the
+        // first row
+        self.part_hasher = None;
+        self.signer = signer;
+        Ok(())
+    }
+
+    /// Attach a VBA project (`vbaProject.bin` from an `.xlsm` file), making
+    /// the workbook macro-enabled
+    ///
+    /// The file must then be saved as `.xlsm`: Excel refuses macro-enabled
+    /// content in an `.xlsx`. Fails with
+    /// [`ExcelError::InvalidFormat`](crate::ExcelError::InvalidFormat) if
+    /// `project` isn't an OLE compound file.
+    pub fn set_vba_project(&mut self, project: Option<Vec<u8>>) -> Result<()> {
+        const OLE_MAGIC: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        if let Some(project) = &project {
+            if !project.starts_with(&OLE_MAGIC) {
+                return Err(crate::ExcelError::InvalidFormat(
+                    "VBA project is not an OLE compound file (vbaProject.bin)".to_string(),
+                ));
+            }
         }
+        self.vba_project = project;
         Ok(())
     }
 
-    pub fn close(mut self) -> Result<()> {
+    /// Embed a [`Manifest`](crate::manifest::Manifest) of the sheets and
+    /// their row counts as a custom document property at close
+    pub fn set_embedded_manifest(&mut self, enabled: bool) {
+        self.embed_manifest = enabled;
+    }
+
+    /// Report buffer high-water marks in
+    /// [`WriteReport::memory`](crate::WriteReport::memory) at close
+    ///
+    /// The marks are only kept while this is enabled, so a workbook
+    /// without it pays nothing per row. Enable it before the first row:
+    /// buffers and entries written earlier are not counted.
+    pub fn set_memory_instrumentation(&mut self, enabled: bool) {
+        if !enabled {
+            self.memory = None;
+        } else if self.memory.is_none() {
+            self.memory = Some(MemoryTracker::default());
+        }
+    }
+
+    pub fn close(self) -> Result<WriteReport> {
+        self.close_into_inner().map(|(_, report)| report)
+    }
+
+    /// Finish the workbook and hand back the output it was written to
+    pub fn close_into_inner(mut self) -> Result<(W, WriteReport)> {
         // Finish current worksheet
         self.finish_current_worksheet()?;
+        if let Some(options) = self.audit.take() {
+            self.write_audit_sheet(&options)?;
+        }
+        if let Some(options) = self.index_sheet.take() {
+            self.write_index_sheet(&options)?;
+        }
+        if self.embed_manifest {
+            let manifest = crate::manifest::Manifest::new(self.per_sheet_rows.clone());
+            self.set_custom_property(crate::manifest::EMBEDDED_PROPERTY, &manifest.to_json());
+        }
 
         // Write all other required ZIP entries
         self.write_content_types()?;
         self.write_rels()?;
         self.write_workbook()?;
         self.write_workbook_rels()?;
+        self.write_vba_project()?;
         self.write_styles()?;
         self.write_shared_strings()?;
         self.write_app_props()?;
         self.write_core_props()?;
+        self.write_custom_props()?;
+        self.write_signature()?;
 
         // Finish ZIP
-        self.zip_writer.take().unwrap().finish()?;
+        let mut output = self.zip_writer.take().unwrap().finish()?;
+        let compressed_bytes = output.seek(SeekFrom::End(0))?;
+
+        let report = WriteReport {
+            per_sheet_rows: std::mem::take(&mut self.per_sheet_rows),
+            total_cells: self.total_cells,
+            compressed_bytes,
+            uncompressed_bytes: self.uncompressed_bytes,
+            duration: self.started_at.elapsed(),
+            masked_cells: Vec::new(),
+            manifest: None,
+            duplicates_dropped: 0,
+            memory: self.memory.as_ref().map(|memory| {
+                memory.finish(self.shared_strings.heap_bytes(), self.styles.xf_count())
+            }),
+        };
+        Ok((output, report))
+    }
+
+    // VML drawing with the current sheet's header/footer pictures, its media
+    // and the relationships linking sheet -> drawing -> media
+    fn write_header_footer_drawing(&mut self, header_footer: &HeaderFooter) -> Result<()> {
+        self.vml_count += 1;
+        let vml = self.vml_count;
+        self.start_entry(&format!(
+            "xl/worksheets/_rels/sheet{}.xml.rels",
+            self.worksheet_count
+        ))?;
+        let rels = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/vmlDrawing" Target="../drawings/vmlDrawing{}.vml"/></Relationships>"#,
+            vml
+        );
+        self.write_markup(&rels)?;
+
+        let mut shapes = String::new();
+        let mut image_rels = String::new();
+        for (i, image) in header_footer.images.iter().enumerate() {
+            self.media_count += 1;
+            let media = format!("image{}.{}", self.media_count, image.extension);
+            shapes.push_str(&format!(
+                r##"<v:shape id="{}" o:spid="_x0000_s{}" type="#_x0000_t75" style="position:absolute;margin-left:0;margin-top:0;width:{}pt;height:{}pt;z-index:{}"><v:imagedata o:relid="rId{}" o:title="image{}"/><o:lock v:ext="edit" rotation="t"/></v:shape>"##,
+                image.position.shape_id(),
+                vml * 1024 + i as u32 + 1,
+                image.width,
+                image.height,
+                i + 1,
+                i + 1,
+                self.media_count
+            ));
+            image_rels.push_str(&format!(
+                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/{}"/>"#,
+                i + 1,
+                media
+            ));
+
+            self.start_entry(&format!("xl/media/{}", media))?;
+            self.write_data(&image.data)?;
+            if !self
+                .media_types
+                .iter()
+                .any(|(ext, _)| *ext == image.extension)
+            {
+                self.media_types
+                    .push((image.extension.clone(), image.content_type()));
+            }
+        }
+
+        self.start_entry(&format!("xl/drawings/vmlDrawing{}.vml", vml))?;
+        let drawing = format!(
+            r#"<xml xmlns:v="urn:schemas-microsoft-com:vml" xmlns:o="urn:schemas-microsoft-com:office:office" xmlns:x="urn:schemas-microsoft-com:office:excel"><o:shapelayout v:ext="edit"><o:idmap v:ext="edit" data="{}"/></o:shapelayout><v:shapetype id="_x0000_t75" coordsize="21600,21600" o:spt="75" o:preferrelative="t" path="m@4@5l@4@11@9@11@9@5xe" filled="f" stroked="f"><v:stroke joinstyle="miter"/><v:formulas><v:f eqn="if lineDrawn pixelLineWidth 0"/><v:f eqn="sum @0 1 0"/><v:f eqn="sum 0 0 @1"/><v:f eqn="prod @2 1 2"/><v:f eqn="prod @3 21600 pixelWidth"/><v:f eqn="prod @3 21600 pixelHeight"/><v:f eqn="sum @0 0 1"/><v:f eqn="prod @6 1 2"/><v:f eqn="prod @7 21600 pixelWidth"/><v:f eqn="sum @8 21600 0"/><v:f eqn="prod @7 21600 pixelHeight"/><v:f eqn="sum @10 21600 0"/></v:formulas><v:path o:extrusionok="f" gradientshapeok="t" o:connecttype="rect"/><o:lock v:ext="edit" aspectratio="t"/></v:shapetype>{}</xml>"#,
+            vml, shapes
+        );
+        self.write_data(drawing.as_bytes())?;
+
+        self.start_entry(&format!("xl/drawings/_rels/vmlDrawing{}.vml.rels", vml))?;
+        let rels = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+            image_rels
+        );
+        self.write_markup(&rels)?;
+        Ok(())
+    }
+
+    // Table of contents as the last sheet file, listed first by
+    // `write_workbook`
+    fn write_index_sheet(&mut self, options: &IndexSheetOptions) -> Result<()> {
+        let sheets: Vec<_> = self
+            .per_sheet_rows
+            .iter()
+            .filter(|(sheet, _)| Some(sheet) != self.audit_sheet.as_ref())
+            .cloned()
+            .collect();
+        let name = crate::merge::unique_sheet_name(
+            &options.sheet_name,
+            self.worksheets.iter().map(|s| s.as_str()),
+        );
+        self.add_worksheet(&name)?;
+
+        let mut header = vec![StyledCell::new(
+            CellValue::String("Sheet".to_string()),
+            CellStyle::HeaderBold,
+        )];
+        if options.row_counts {
+            header.push(StyledCell::new(
+                CellValue::String("Rows".to_string()),
+                CellStyle::HeaderBold,
+            ));
+        }
+        self.write_row_styled(&header)?;
+
+        for (row, (sheet, rows)) in sheets.iter().enumerate() {
+            let mut cells = vec![StyledCell::new(
+                CellValue::String(sheet.clone()),
+                CellStyle::Default,
+            )];
+            if options.row_counts {
+                let count = rows.saturating_sub(options.header_rows);
+                cells.push(StyledCell::new(
+                    CellValue::Int(count as i64),
+                    CellStyle::Default,
+                ));
+            }
+            self.write_row_styled(&cells)?;
+            let location = format!("'{}'!A1", sheet.replace('\'', "''"));
+            self.add_internal_hyperlink(row as u32 + 1, 0, &location)?;
+        }
+        self.finish_current_worksheet()?;
 
+        // Report the index first, like its tab
+        if let Some(index) = self.per_sheet_rows.pop() {
+            self.per_sheet_rows.insert(0, index);
+        }
+        self.index_first = true;
+        Ok(())
+    }
+
+    // Provenance sheet after the data sheets, left out of the index
+    fn write_audit_sheet(&mut self, options: &AuditOptions) -> Result<()> {
+        let sheets: Vec<_> = self
+            .per_sheet_rows
+            .iter()
+            .cloned()
+            .zip(self.sheet_stats.clone())
+            .collect();
+        let duration = self.started_at.elapsed();
+        let name = crate::merge::unique_sheet_name(
+            &options.sheet_name,
+            self.worksheets.iter().map(|s| s.as_str()),
+        );
+        self.add_worksheet(&name)?;
+
+        let bold = |text: &str| {
+            StyledCell::new(CellValue::String(text.to_string()), CellStyle::HeaderBold)
+        };
+        let plain = |value: CellValue| StyledCell::new(value, CellStyle::Default);
+        self.write_row_styled(&[
+            bold("Sheet"),
+            bold("Rows"),
+            bold("Cells"),
+            bold("Schema hash"),
+        ])?;
+        for ((sheet, rows), (cells, schema_hash)) in sheets {
+            let schema_hash = schema_hash.map_or(CellValue::Empty, |digest| {
+                CellValue::String(digest.iter().map(|b| format!("{:02x}", b)).collect())
+            });
+            self.write_row_styled(&[
+                plain(CellValue::String(sheet)),
+                plain(CellValue::Int(rows as i64)),
+                plain(CellValue::Int(cells as i64)),
+                plain(schema_hash),
+            ])?;
+        }
+        self.write_row_styled(&[])?;
+
+        let host = options.host.clone().unwrap_or_else(host_name);
+        let generated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        for (label, value) in [
+            ("Generated at", CellValue::String(generated_at)),
+            ("Duration (ms)", CellValue::Int(duration.as_millis() as i64)),
+            ("Host", CellValue::String(host)),
+            (
+                "Library version",
+                CellValue::String(format!("excelstream {}", env!("CARGO_PKG_VERSION"))),
+            ),
+        ] {
+            self.write_row_styled(&[bold(label), plain(value)])?;
+        }
+        self.finish_current_worksheet()?;
+
+        // Stays visible rather than fail when it is the only sheet
+        let others_visible =
+            self.visibility[..self.visibility.len() - 1].contains(&SheetVisibility::Visible);
+        if others_visible {
+            self.set_sheet_visibility(&name, options.visibility)?;
+        }
+        self.audit_sheet = Some(name);
+        Ok(())
+    }
+
+    /// Start a ZIP entry, hashing it when the workbook is signed
+    fn start_entry(&mut self, name: &str) -> Result<()> {
+        self.finish_part_digest();
+        if self.signer.is_some() {
+            self.part_hasher = Some((name.to_string(), Sha256::new()));
+        }
+        if let Some(memory) = self.memory.as_mut() {
+            memory.start_entry(name);
+        }
+        self.zip_writer.as_mut().unwrap().start_entry(name)?;
+        Ok(())
+    }
+
+    fn finish_part_digest(&mut self) {
+        if let Some((name, hasher)) = self.part_hasher.take() {
+            self.part_digests.push((name, hasher.finalize().into()));
+        }
+    }
+
+    /// Write part markup, in strict OOXML namespaces when enabled
+    fn write_markup(&mut self, xml: &str) -> Result<()> {
+        if self.strict {
+            self.write_data(ooxml::to_strict(xml).as_bytes())
+        } else {
+            self.write_data(xml.as_bytes())
+        }
+    }
+
+    /// Write bytes to the current ZIP entry, tracking uncompressed size
+    fn write_data(&mut self, data: &[u8]) -> Result<()> {
+        self.uncompressed_bytes += data.len() as u64;
+        if let Some((_, hasher)) = self.part_hasher.as_mut() {
+            hasher.update(data);
+        }
+        if let Some(memory) = self.memory.as_mut() {
+            memory.entry_data(data.len());
+        }
+        self.zip_writer.as_mut().unwrap().write_data(data)?;
         Ok(())
     }
 
     fn write_content_types(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("[Content_Types].xml")?;
+        self.start_entry("[Content_Types].xml")?;
         let mut xml = String::from(
             r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
 <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
-<Default Extension="xml" ContentType="application/xml"/>
-<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>"#,
+        );
+        xml.push_str(&format!(
+            r#"
+<Override PartName="/xl/workbook.xml" ContentType="{}"/>"#,
+            self.content_type("xl/workbook.xml")
+        ));
+        if self.vba_project.is_some() {
+            xml.push_str(
+                r#"
+<Default Extension="bin" ContentType="application/vnd.ms-office.vbaProject"/>"#,
+            );
+        }
+        xml.push_str(
+            r#"
 <Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>
 <Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/>
 <Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
@@ -343,66 +1143,148 @@ impl ZeroTempWorkbook {
             ));
         }
 
+        if !self.custom_properties.is_empty() {
+            xml.push_str(
+                r#"
+<Override PartName="/docProps/custom.xml" ContentType="application/vnd.openxmlformats-officedocument.custom-properties+xml"/>"#,
+            );
+        }
+
+        if self.signer.is_some() {
+            xml.push_str(&format!(
+                r#"
+<Default Extension="sigs" ContentType="{}"/>
+<Override PartName="/{}" ContentType="{}"/>"#,
+                signature::ORIGIN_CONTENT_TYPE,
+                signature::SIGNATURE_PART,
+                signature::SIGNATURE_CONTENT_TYPE
+            ));
+        }
+
+        if self.vml_count > 0 {
+            xml.push_str(
+                r#"
+<Default Extension="vml" ContentType="application/vnd.openxmlformats-officedocument.vmlDrawing"/>"#,
+            );
+        }
+        for (extension, content_type) in &self.media_types {
+            xml.push_str(&format!(
+                r#"
+<Default Extension="{}" ContentType="{}"/>"#,
+                extension, content_type
+            ));
+        }
+
         xml.push_str("\n</Types>");
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        self.write_data(xml.as_bytes())?;
         Ok(())
     }
 
     fn write_rels(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("_rels/.rels")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+        self.start_entry("_rels/.rels")?;
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
 <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>
 <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
-<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/>
-</Relationships>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/>"#,
+        );
+        if !self.custom_properties.is_empty() {
+            xml.push_str(
+                r#"
+<Relationship Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/custom-properties" Target="docProps/custom.xml"/>"#,
+            );
+        }
+        if self.signer.is_some() {
+            xml.push_str(&format!(
+                r#"
+<Relationship Id="rId5" Type="{}" Target="{}"/>"#,
+                signature::ORIGIN_RELATIONSHIP,
+                signature::ORIGIN_PART
+            ));
+        }
+        xml.push_str("\n</Relationships>");
+        self.write_markup(&xml)?;
         Ok(())
     }
 
     fn write_workbook(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/workbook.xml")?;
+        self.start_entry("xl/workbook.xml")?;
         let mut xml = String::from(
             r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
-<sheets>"#,
+"#,
         );
+        if self.strict {
+            xml = xml.replacen("<workbook ", r#"<workbook conformance="strict" "#, 1);
+        }
+        if self.vba_project.is_some() {
+            // Code name of the project's workbook module
+            xml.push_str(r#"<workbookPr codeName="ThisWorkbook"/>"#);
+        }
 
-        for (i, name) in self.worksheets.iter().enumerate() {
+        // Sheet files are numbered in write order; an index sheet is written
+        // last but shown first
+        let count = self.worksheets.len();
+        let mut order: Vec<usize> = (0..count).collect();
+        if self.index_first && count > 0 {
+            order.rotate_right(1);
+        }
+        if !self.sheet_order.is_empty() {
+            order.retain(|i| !self.sheet_order.contains(i));
+            order.splice(0..0, self.sheet_order.iter().copied());
+        }
+
+        // The active tab must be visible; default to the first visible one
+        let first_visible = order
+            .iter()
+            .position(|&i| self.visibility[i] == SheetVisibility::Visible)
+            .unwrap_or(0);
+        let active_tab = self
+            .active_sheet
+            .and_then(|active| order.iter().position(|&i| i == active))
+            .unwrap_or(first_visible);
+        if active_tab > 0 || first_visible > 0 {
+            xml.push_str(&format!(
+                "<bookViews><workbookView activeTab=\"{}\"",
+                active_tab
+            ));
+            if first_visible > 0 {
+                xml.push_str(&format!(" firstSheet=\"{}\"", first_visible));
+            }
+            xml.push_str("/></bookViews>\n");
+        }
+        xml.push_str("<sheets>");
+
+        for i in order {
+            let mut escaped = Vec::new();
+            write_escaped(&mut escaped, &self.worksheets[i]);
+            let state = match self.visibility[i] {
+                SheetVisibility::Visible => String::new(),
+                hidden => format!(r#" state="{}""#, hidden.as_str()),
+            };
             xml.push_str(&format!(
                 r#"
-<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
-                name,
+<sheet name="{}" sheetId="{}"{} r:id="rId{}"/>"#,
+                String::from_utf8_lossy(&escaped),
                 i + 1,
+                state,
                 i + 1
             ));
         }
 
-        xml.push_str("\n</sheets>\n</workbook>");
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        xml.push_str("\n</sheets>");
+        if let Some(settings) = &self.calc_settings {
+            xml.push('\n');
+            xml.push_str(&settings.to_xml());
+        }
+        xml.push_str("\n</workbook>");
+        self.write_markup(&xml)?;
         Ok(())
     }
 
     fn write_workbook_rels(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/_rels/workbook.xml.rels")?;
+        self.start_entry("xl/_rels/workbook.xml.rels")?;
         let mut xml = String::from(
             r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
@@ -419,144 +1301,192 @@ impl ZeroTempWorkbook {
         xml.push_str(&format!(
             r#"
 <Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
-<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/>
-</Relationships>"#,
+<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/>"#,
             self.worksheet_count + 1,
             self.worksheet_count + 2
         ));
+        if self.vba_project.is_some() {
+            xml.push_str(&format!(
+                r#"
+<Relationship Id="rId{}" Type="http://schemas.microsoft.com/office/2006/relationships/vbaProject" Target="vbaProject.bin"/>"#,
+                self.worksheet_count + 3
+            ));
+        }
+        xml.push_str("\n</Relationships>");
 
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        self.write_markup(&xml)?;
+        Ok(())
+    }
+
+    fn write_vba_project(&mut self) -> Result<()> {
+        let Some(project) = self.vba_project.take() else {
+            return Ok(());
+        };
+        self.start_entry("xl/vbaProject.bin")?;
+        self.write_data(&project)?;
+        // Still needed for content types of a signature
+        self.vba_project = Some(project);
         Ok(())
     }
 
     fn write_styles(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/styles.xml")?;
-        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-<numFmts count="0"/>
-<fonts count="3">
-<font><sz val="11"/><name val="Calibri"/></font>
-<font><b/><sz val="11"/><name val="Calibri"/></font>
-<font><i/><sz val="11"/><name val="Calibri"/></font>
-</fonts>
-<fills count="5">
-<fill><patternFill patternType="none"/></fill>
-<fill><patternFill patternType="gray125"/></fill>
-<fill><patternFill patternType="solid"><fgColor rgb="FFFFFF00"/></patternFill></fill>
-<fill><patternFill patternType="solid"><fgColor rgb="FF00FF00"/></patternFill></fill>
-<fill><patternFill patternType="solid"><fgColor rgb="FFFF0000"/></patternFill></fill>
-</fills>
-<borders count="2">
-<border><left/><right/><top/><bottom/><diagonal/></border>
-<border><left style="thin"/><right style="thin"/><top style="thin"/><bottom style="thin"/></border>
-</borders>
-<cellXfs count="14">
-<xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>
-<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>
-<xf numFmtId="3" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
-<xf numFmtId="4" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
-<xf numFmtId="5" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
-<xf numFmtId="9" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
-<xf numFmtId="14" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
-<xf numFmtId="22" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
-<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>
-<xf numFmtId="0" fontId="2" fillId="0" borderId="0" xfId="0" applyFont="1"/>
-<xf numFmtId="0" fontId="0" fillId="2" borderId="0" xfId="0" applyFill="1"/>
-<xf numFmtId="0" fontId="0" fillId="3" borderId="0" xfId="0" applyFill="1"/>
-<xf numFmtId="0" fontId="0" fillId="4" borderId="0" xfId="0" applyFill="1"/>
-<xf numFmtId="0" fontId="0" fillId="0" borderId="1" xfId="0" applyBorder="1"/>
-</cellXfs>
-</styleSheet>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        self.start_entry("xl/styles.xml")?;
+        let xml = self.styles.to_xml();
+        self.write_markup(&xml)?;
         Ok(())
     }
 
     fn write_shared_strings(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("xl/sharedStrings.xml")?;
+        self.start_entry("xl/sharedStrings.xml")?;
         let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="0" uniqueCount="0"/>
 "#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        self.write_markup(xml)?;
         Ok(())
     }
 
     fn write_app_props(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("docProps/app.xml")?;
+        self.start_entry("docProps/app.xml")?;
         let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties">
 <Application>ExcelStream</Application>
 </Properties>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        self.write_markup(xml)?;
         Ok(())
     }
 
     fn write_core_props(&mut self) -> Result<()> {
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .start_entry("docProps/core.xml")?;
+        self.start_entry("docProps/core.xml")?;
         let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
 <dc:creator>ExcelStream</dc:creator>
 </cp:coreProperties>"#;
-        self.zip_writer
-            .as_mut()
-            .unwrap()
-            .write_data(xml.as_bytes())?;
+        self.write_data(xml.as_bytes())?;
         Ok(())
     }
 
-    fn push_column_letter(buffer: &mut Vec<u8>, mut n: u32) {
-        if n == 0 {
-            return;
+    fn write_custom_props(&mut self) -> Result<()> {
+        if self.custom_properties.is_empty() {
+            return Ok(());
         }
-        let mut tmp = [0u8; 10];
-        let mut len = 0;
-        while n > 0 {
-            let rem = (n - 1) % 26;
-            tmp[len] = b'A' + rem as u8;
-            len += 1;
-            n = (n - 1) / 26;
-        }
-        for i in (0..len).rev() {
-            buffer.push(tmp[i]);
+        self.start_entry("docProps/custom.xml")?;
+        let mut xml = Vec::from(
+            &br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/custom-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">"#[..],
+        );
+        // Property ids start at 2 by convention
+        for (pid, (name, value)) in self.custom_properties.iter().enumerate() {
+            xml.extend_from_slice(
+                format!(
+                    "\n<property fmtid=\"{{D5CDD505-2E9C-101B-9397-08002B2CF9AE}}\" pid=\"{}\" name=\"",
+                    pid + 2
+                )
+                .as_bytes(),
+            );
+            write_escaped(&mut xml, name);
+            xml.extend_from_slice(b"\"><vt:lpwstr>");
+            write_escaped(&mut xml, value);
+            xml.extend_from_slice(b"</vt:lpwstr></property>");
         }
+        xml.extend_from_slice(b"\n</Properties>");
+        self.write_markup(&String::from_utf8_lossy(&xml))
     }
 
-    fn write_escaped(buffer: &mut Vec<u8>, s: &str) {
-        for c in s.chars() {
-            match c {
-                '&' => buffer.extend_from_slice(b"&amp;"),
-                '<' => buffer.extend_from_slice(b"&lt;"),
-                '>' => buffer.extend_from_slice(b"&gt;"),
-                '"' => buffer.extend_from_slice(b"&quot;"),
-                '\'' => buffer.extend_from_slice(b"&apos;"),
-                _ => {
-                    let mut buf = [0; 4];
-                    buffer.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
-                }
+    // Signature origin, its relationship to the signature and the signature
+    // over every part written except relationships and [Content_Types].xml
+    fn write_signature(&mut self) -> Result<()> {
+        self.finish_part_digest();
+        let Some(signer) = self.signer.take() else {
+            return Ok(());
+        };
+        let parts: Vec<(String, String, [u8; 32])> = self
+            .part_digests
+            .iter()
+            .filter(|(name, _)| !name.ends_with(".rels") && name != "[Content_Types].xml")
+            .map(|(name, digest)| (name.clone(), self.content_type(name).to_string(), *digest))
+            .collect();
+        let signing_time = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let xml = signature::signature_xml(&parts, &signing_time, signer.as_ref())?;
+
+        self.start_entry(signature::ORIGIN_PART)?;
+        self.start_entry("_xmlsignatures/_rels/origin.sigs.rels")?;
+        let rels = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="{}" Target="sig1.xml"/></Relationships>"#,
+            signature::SIGNATURE_RELATIONSHIP
+        );
+        self.write_data(rels.as_bytes())?;
+        self.start_entry(signature::SIGNATURE_PART)?;
+        self.write_data(xml.as_bytes())
+    }
+
+    // Content type of a part written by this workbook
+    fn content_type(&self, part: &str) -> &'static str {
+        match part {
+            "xl/workbook.xml" if self.vba_project.is_some() => {
+                "application/vnd.ms-excel.sheet.macroEnabled.main+xml"
+            }
+            "xl/workbook.xml" => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"
+            }
+            "xl/vbaProject.bin" => "application/vnd.ms-office.vbaProject",
+            "xl/styles.xml" => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"
+            }
+            "xl/sharedStrings.xml" => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"
+            }
+            "docProps/core.xml" => "application/vnd.openxmlformats-package.core-properties+xml",
+            "docProps/app.xml" => {
+                "application/vnd.openxmlformats-officedocument.extended-properties+xml"
+            }
+            "docProps/custom.xml" => {
+                "application/vnd.openxmlformats-officedocument.custom-properties+xml"
+            }
+            _ if part.starts_with("xl/worksheets/") => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"
+            }
+            _ if part.ends_with(".vml") => {
+                "application/vnd.openxmlformats-officedocument.vmlDrawing"
+            }
+            _ => {
+                let extension = part.rsplit('.').next().unwrap_or("");
+                self.media_types
+                    .iter()
+                    .find(|(ext, _)| ext == extension)
+                    .map(|(_, content_type)| *content_type)
+                    .unwrap_or("application/xml")
             }
         }
     }
 }
+
+impl<W: Write + Seek> SpreadsheetWriter for ZeroTempWorkbook<W> {
+    fn add_sheet(&mut self, name: &str) -> Result<()> {
+        self.add_worksheet(name)
+    }
+
+    fn write_row<I, S>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        ZeroTempWorkbook::write_row(self, values)
+    }
+
+    fn write_row_typed(&mut self, values: &[CellValue]) -> Result<()> {
+        let cells: Vec<StyledCell> = values
+            .iter()
+            .map(|v| StyledCell::new(v.clone(), CellStyle::Default))
+            .collect();
+        ZeroTempWorkbook::write_row_styled(self, &cells)
+    }
+
+    fn write_row_styled(&mut self, cells: &[StyledCell]) -> Result<()> {
+        ZeroTempWorkbook::write_row_styled(self, cells)
+    }
+
+    fn close(self) -> Result<WriteReport> {
+        ZeroTempWorkbook::close(self)
+    }
+}
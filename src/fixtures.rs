@@ -0,0 +1,710 @@
+//! Generated workbooks for reader and round-trip tests
+//!
+//! Fixtures are assembled from raw OOXML parts instead of going through
+//! [`ExcelWriter`], so they can hold what our own writer never produces:
+//! shared strings with rich text and phonetic runs, merged cells, sheet names
+//! that need escaping, rows 16,384 columns wide. Each fixture also knows the
+//! rows a reader should return, which makes reader → writer → reader checks
+//! a few lines:
+//!
+//! ```
+//! use excelstream::fixtures;
+//!
+//! # fn main() -> excelstream::Result<()> {
+//! let fixture = fixtures::rich_text();
+//! let original = fixture.write_temp()?;
+//! assert_eq!(fixtures::read_workbook(original.path())?, fixture.expected());
+//!
+//! let copy = tempfile::NamedTempFile::new()?;
+//! fixtures::rewrite(original.path(), copy.path())?;
+//! assert_eq!(fixtures::read_workbook(copy.path())?, fixture.expected());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::fast_writer::StreamingZipWriter;
+use crate::reader::ExcelReader;
+use crate::types::{Cell, CellRange, CellValue};
+use crate::writer::{ExcelWriter, ExcelWriterBuilder};
+use indexmap::IndexSet;
+use std::path::Path;
+
+/// Sheet names and rows, in sheet order, as [`read_workbook`] returns them
+pub type WorkbookRows = Vec<(String, Vec<Vec<CellValue>>)>;
+
+/// One cell of a fixture sheet
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixtureCell {
+    /// A number or boolean, or a string stored in the shared string table.
+    /// `Empty` leaves the cell out.
+    Value(CellValue),
+    /// A string stored inline (`t="inlineStr"`)
+    Inline(String),
+    /// A shared string made of runs, every other one bold; reads as the runs
+    /// joined
+    RichText(Vec<String>),
+    /// A shared string with a phonetic reading (`<rPh>`) that readers must
+    /// leave out
+    Phonetic { text: String, reading: String },
+}
+
+impl FixtureCell {
+    /// The value a reader should return for this cell
+    pub fn expected(&self) -> CellValue {
+        match self {
+            FixtureCell::Value(value) => value.clone(),
+            FixtureCell::Inline(text) | FixtureCell::Phonetic { text, .. } => {
+                CellValue::String(text.clone())
+            }
+            FixtureCell::RichText(runs) => CellValue::String(runs.concat()),
+        }
+    }
+}
+
+impl From<CellValue> for FixtureCell {
+    fn from(value: CellValue) -> Self {
+        FixtureCell::Value(value)
+    }
+}
+
+impl From<&str> for FixtureCell {
+    fn from(text: &str) -> Self {
+        FixtureCell::Value(CellValue::String(text.to_string()))
+    }
+}
+
+impl From<i64> for FixtureCell {
+    fn from(value: i64) -> Self {
+        FixtureCell::Value(CellValue::Int(value))
+    }
+}
+
+/// How a [`WorkbookFixture`] spells its XML, to mimic producers other than
+/// Excel (Google Sheets, Numbers, other libraries)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Markup {
+    /// Leave out the `r` attribute of rows and cells, so positions are
+    /// implicit; empty cells are then written as `<c/>` to hold their place
+    pub implicit_positions: bool,
+    /// Write empty cells as `<c/>` without a type instead of leaving them out
+    pub empty_cells: bool,
+    /// Bind the main namespace to this prefix (e.g. `"x"`) and prefix every
+    /// element with it
+    pub prefix: Option<String>,
+    /// Write relationship targets as absolute part names
+    /// (`/xl/worksheets/sheet1.xml`)
+    pub absolute_targets: bool,
+}
+
+/// A worksheet of a [`WorkbookFixture`]
+#[derive(Debug, Clone)]
+pub struct SheetFixture {
+    name: String,
+    rows: Vec<Vec<FixtureCell>>,
+    merges: Vec<CellRange>,
+}
+
+impl SheetFixture {
+    pub fn new(name: &str) -> Self {
+        SheetFixture {
+            name: name.to_string(),
+            rows: Vec::new(),
+            merges: Vec::new(),
+        }
+    }
+
+    /// Add a row
+    pub fn row<I, C>(mut self, cells: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<FixtureCell>,
+    {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Merge a range
+    ///
+    /// Only the top-left cell should hold a value; the others read as empty.
+    pub fn merge(mut self, range: CellRange) -> Self {
+        self.merges.push(range);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The rows a reader should return, with trailing empty cells dropped
+    pub fn expected_rows(&self) -> Vec<Vec<CellValue>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut values: Vec<CellValue> = row.iter().map(FixtureCell::expected).collect();
+                while values.last() == Some(&CellValue::Empty) {
+                    values.pop();
+                }
+                values
+            })
+            .collect()
+    }
+
+    fn to_xml(&self, strings: &mut SharedStringTable, markup: &Markup) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheetData>"#,
+        );
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            if markup.implicit_positions {
+                xml.push_str("<row>");
+            } else {
+                xml.push_str(&format!(r#"<row r="{}">"#, row_idx + 1));
+            }
+            for (col_idx, cell) in row.iter().enumerate() {
+                let reference = if markup.implicit_positions {
+                    String::new()
+                } else {
+                    let cell = Cell::new(row_idx as u32, col_idx as u32, CellValue::Empty);
+                    format!(r#" r="{}""#, cell.reference())
+                };
+                let (cell_type, body) = match cell {
+                    FixtureCell::Value(CellValue::Empty) => {
+                        if markup.implicit_positions || markup.empty_cells {
+                            xml.push_str(&format!("<c{}/>", reference));
+                        }
+                        continue;
+                    }
+                    FixtureCell::Value(CellValue::Bool(b)) => ("b", format!("<v>{}</v>", *b as u8)),
+                    FixtureCell::Value(CellValue::Int(i)) => ("n", format!("<v>{}</v>", i)),
+                    FixtureCell::Value(CellValue::Float(f)) => ("n", format!("<v>{}</v>", f)),
+                    FixtureCell::Value(value) => {
+                        let item = format!("<si>{}</si>", text_run(&value.as_string()));
+                        ("s", format!("<v>{}</v>", strings.add(item)))
+                    }
+                    FixtureCell::Inline(text) => {
+                        ("inlineStr", format!("<is>{}</is>", text_run(text)))
+                    }
+                    FixtureCell::RichText(runs) => {
+                        let mut item = String::from("<si>");
+                        for (i, run) in runs.iter().enumerate() {
+                            let props = if i % 2 == 0 { "<rPr><b/></rPr>" } else { "" };
+                            item.push_str(&format!("<r>{}{}</r>", props, text_run(run)));
+                        }
+                        item.push_str("</si>");
+                        ("s", format!("<v>{}</v>", strings.add(item)))
+                    }
+                    FixtureCell::Phonetic { text, reading } => {
+                        let item = format!(
+                            r#"<si>{}<rPh sb="0" eb="{}">{}</rPh><phoneticPr fontId="0" type="noConversion"/></si>"#,
+                            text_run(text),
+                            text.chars().count(),
+                            text_run(reading)
+                        );
+                        ("s", format!("<v>{}</v>", strings.add(item)))
+                    }
+                };
+                xml.push_str(&format!(
+                    r#"<c{} t="{}">{}</c>"#,
+                    reference, cell_type, body
+                ));
+            }
+            xml.push_str("</row>");
+        }
+        xml.push_str("</sheetData>");
+        if !self.merges.is_empty() {
+            xml.push_str(&format!(r#"<mergeCells count="{}">"#, self.merges.len()));
+            for range in &self.merges {
+                xml.push_str(&format!(r#"<mergeCell ref="{}"/>"#, range));
+            }
+            xml.push_str("</mergeCells>");
+        }
+        xml.push_str("</worksheet>");
+        xml
+    }
+}
+
+/// A workbook built from raw parts, with the rows a reader should return
+#[derive(Debug, Clone)]
+pub struct WorkbookFixture {
+    name: String,
+    sheets: Vec<SheetFixture>,
+    markup: Markup,
+}
+
+impl WorkbookFixture {
+    pub fn new(name: &str) -> Self {
+        WorkbookFixture {
+            name: name.to_string(),
+            sheets: Vec::new(),
+            markup: Markup::default(),
+        }
+    }
+
+    /// Spell the XML differently from Excel
+    pub fn markup(mut self, markup: Markup) -> Self {
+        self.markup = markup;
+        self
+    }
+
+    /// Add a sheet
+    pub fn sheet(mut self, sheet: SheetFixture) -> Self {
+        self.sheets.push(sheet);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn sheets(&self) -> &[SheetFixture] {
+        &self.sheets
+    }
+
+    /// Sheet names and rows a reader should return
+    pub fn expected(&self) -> WorkbookRows {
+        self.sheets
+            .iter()
+            .map(|sheet| (sheet.name.clone(), sheet.expected_rows()))
+            .collect()
+    }
+
+    /// Write the workbook to `path`
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut strings = SharedStringTable::default();
+        let sheet_xml: Vec<String> = self
+            .sheets
+            .iter()
+            .map(|sheet| sheet.to_xml(&mut strings, &self.markup))
+            .collect();
+
+        let mut zip = StreamingZipWriter::new(path.as_ref())?;
+        let mut put = |name: &str, data: &str| -> Result<()> {
+            zip.start_entry(name)?;
+            zip.write_data(data.as_bytes())?;
+            Ok(())
+        };
+        // Parts in the SpreadsheetML namespace
+        let main = |xml: &str| match &self.markup.prefix {
+            Some(prefix) => prefix_elements(xml, prefix),
+            None => xml.to_string(),
+        };
+        put("[Content_Types].xml", &self.content_types())?;
+        put("_rels/.rels", ROOT_RELS)?;
+        put("xl/workbook.xml", &main(&self.workbook_xml()))?;
+        put("xl/_rels/workbook.xml.rels", &self.workbook_rels())?;
+        put("xl/styles.xml", &main(STYLES))?;
+        put("xl/sharedStrings.xml", &main(&strings.to_xml()))?;
+        for (i, xml) in sheet_xml.iter().enumerate() {
+            put(&format!("xl/worksheets/sheet{}.xml", i + 1), &main(xml))?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Write the workbook to a temporary file, removed when dropped
+    pub fn write_temp(&self) -> Result<tempfile::NamedTempFile> {
+        let file = tempfile::NamedTempFile::new()?;
+        self.write(file.path())?;
+        Ok(file)
+    }
+
+    fn content_types(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/><Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/>"#,
+        );
+        for i in 1..=self.sheets.len() {
+            xml.push_str(&format!(
+                r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                i
+            ));
+        }
+        xml.push_str("</Types>");
+        xml
+    }
+
+    fn workbook_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+        );
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            xml.push_str(&format!(
+                r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+                escape_xml(&sheet.name),
+                i + 1,
+                i + 1
+            ));
+        }
+        xml.push_str("</sheets></workbook>");
+        xml
+    }
+
+    fn workbook_rels(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        );
+        let base = if self.markup.absolute_targets {
+            "/xl/"
+        } else {
+            ""
+        };
+        let n = self.sheets.len();
+        for i in 1..=n {
+            xml.push_str(&format!(
+                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="{}worksheets/sheet{}.xml"/>"#,
+                i, base, i
+            ));
+        }
+        xml.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="{base}styles.xml"/><Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="{base}sharedStrings.xml"/></Relationships>"#,
+            n + 1,
+            n + 2
+        ));
+        xml
+    }
+}
+
+/// Unicode, emoji and characters that need escaping in sheet names, and
+/// cell text with entities, surrounding spaces and newlines
+pub fn unicode_sheet_names() -> WorkbookFixture {
+    let names = [
+        "Données",
+        "数据 2024",
+        "R&D <draft>",
+        "📊 Report",
+        "O'Brien \"Q1\"",
+    ];
+    let mut fixture = WorkbookFixture::new("unicode_sheet_names");
+    for name in names {
+        fixture = fixture.sheet(SheetFixture::new(name).row(["Sheet", name]).row([
+            "a &lt; b",
+            "  padded  ",
+            "line 1\nline 2",
+            "tab\there",
+        ]));
+    }
+    fixture
+}
+
+/// A full row across all 16,384 columns (A to XFD), and one with only the
+/// first and last cells set
+pub fn wide_row() -> WorkbookFixture {
+    const MAX_COLUMNS: usize = 16_384;
+    let mut sparse = vec![FixtureCell::Value(CellValue::Empty); MAX_COLUMNS];
+    sparse[0] = "first".into();
+    sparse[MAX_COLUMNS - 1] = "XFD".into();
+    WorkbookFixture::new("wide_row").sheet(
+        SheetFixture::new("Wide")
+            .row((0..MAX_COLUMNS as i64).map(|col| col * 3))
+            .row(sparse),
+    )
+}
+
+/// Rich text, phonetic runs and inline strings
+pub fn rich_text() -> WorkbookFixture {
+    WorkbookFixture::new("rich_text").sheet(
+        SheetFixture::new("Rich")
+            .row([
+                FixtureCell::RichText(vec!["Bold".to_string(), " and plain".to_string()]),
+                FixtureCell::Phonetic {
+                    text: "東京".to_string(),
+                    reading: "トウキョウ".to_string(),
+                },
+                FixtureCell::Inline("inline & <escaped>".to_string()),
+            ])
+            .row([
+                FixtureCell::RichText(vec!["a".to_string(), " ".to_string(), "b".to_string()]),
+                "after rich text".into(),
+                FixtureCell::Value(CellValue::Bool(true)),
+                FixtureCell::Value(CellValue::Float(2.5)),
+            ]),
+    )
+}
+
+/// Merged ranges with values only in their top-left cells
+pub fn merged_cells() -> WorkbookFixture {
+    let range = |r: &str| r.parse::<CellRange>().expect("valid range");
+    let empty = || FixtureCell::Value(CellValue::Empty);
+    WorkbookFixture::new("merged_cells").sheet(
+        SheetFixture::new("Merged")
+            .row(["Region".into(), empty(), FixtureCell::from(7)])
+            .row([empty(), empty(), FixtureCell::from(8)])
+            .row(["Total".into(), empty(), empty()])
+            .merge(range("A1:B2"))
+            .merge(range("A3:C3")),
+    )
+}
+
+/// `count` distinct shared strings, ten to a row
+pub fn large_shared_strings(count: usize) -> WorkbookFixture {
+    let mut sheet = SheetFixture::new("Strings");
+    for start in (0..count).step_by(10) {
+        let row: Vec<String> = (start..count.min(start + 10))
+            .map(|i| format!("string {:07}", i))
+            .collect();
+        sheet = sheet.row(row.iter().map(String::as_str));
+    }
+    WorkbookFixture::new("large_shared_strings").sheet(sheet)
+}
+
+/// Gaps, blank rows and phonetic runs in markup Excel never writes but
+/// other producers do, one fixture per quirk and one with all of them
+pub fn quirks() -> Vec<WorkbookFixture> {
+    let empty = || FixtureCell::Value(CellValue::Empty);
+    let sheet = SheetFixture::new("Quirks")
+        .row(["id".into(), empty(), FixtureCell::from("name")])
+        .row([
+            FixtureCell::from(1),
+            empty(),
+            FixtureCell::Phonetic {
+                text: "大阪".to_string(),
+                reading: "オオサカ".to_string(),
+            },
+            empty(),
+        ])
+        .row([empty(), empty()])
+        .row([
+            empty(),
+            FixtureCell::Value(CellValue::Float(0.5)),
+            FixtureCell::Inline("inline".to_string()),
+            FixtureCell::Value(CellValue::Bool(false)),
+        ]);
+    let fixture = |name: &str, markup: Markup| {
+        WorkbookFixture::new(name)
+            .sheet(sheet.clone())
+            .markup(markup)
+    };
+    let prefix = || Some("x".to_string());
+    vec![
+        fixture(
+            "implicit_positions",
+            Markup {
+                implicit_positions: true,
+                ..Markup::default()
+            },
+        ),
+        fixture(
+            "empty_cells",
+            Markup {
+                empty_cells: true,
+                ..Markup::default()
+            },
+        ),
+        fixture(
+            "prefixed_elements",
+            Markup {
+                prefix: prefix(),
+                ..Markup::default()
+            },
+        ),
+        fixture(
+            "absolute_targets",
+            Markup {
+                absolute_targets: true,
+                ..Markup::default()
+            },
+        ),
+        fixture(
+            "all_quirks",
+            Markup {
+                implicit_positions: true,
+                empty_cells: true,
+                prefix: prefix(),
+                absolute_targets: true,
+            },
+        ),
+    ]
+}
+
+/// Every fixture, with a 20,000-string shared string table, and the
+/// [`quirks`] fixtures
+pub fn corpus() -> Vec<WorkbookFixture> {
+    let mut corpus = vec![
+        unicode_sheet_names(),
+        wide_row(),
+        rich_text(),
+        merged_cells(),
+        large_shared_strings(20_000),
+    ];
+    corpus.extend(quirks());
+    corpus
+}
+
+/// Read every sheet of a workbook with [`ExcelReader`]
+pub fn read_workbook<P: AsRef<Path>>(path: P) -> Result<WorkbookRows> {
+    let mut reader = ExcelReader::open(path)?;
+    let mut sheets = Vec::new();
+    for name in reader.sheet_names() {
+        let rows = reader
+            .rows(&name)?
+            .map(|row| row.map(|row| row.cells))
+            .collect::<Result<Vec<_>>>()?;
+        sheets.push((name, rows));
+    }
+    Ok(sheets)
+}
+
+/// Copy a workbook's sheets and values with [`ExcelWriter`]
+pub fn rewrite<P: AsRef<Path>, Q: AsRef<Path>>(source: P, destination: Q) -> Result<()> {
+    let sheets = read_workbook(source)?;
+    let first = sheets.first().map_or("Sheet1", |(name, _)| name.as_str());
+    let mut writer: ExcelWriter = ExcelWriterBuilder::new(destination)
+        .with_sheet_name(first)
+        .build()?;
+    for (i, (name, rows)) in sheets.iter().enumerate() {
+        if i > 0 {
+            writer.add_sheet(name)?;
+        }
+        for row in rows {
+            writer.write_row_typed(row)?;
+        }
+    }
+    writer.save()?;
+    Ok(())
+}
+
+// Shared string items in index order; plain strings are deduplicated
+#[derive(Default)]
+struct SharedStringTable {
+    items: IndexSet<String>,
+}
+
+impl SharedStringTable {
+    fn add(&mut self, item: String) -> usize {
+        self.items.insert_full(item).0
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="{0}" uniqueCount="{0}">"#,
+            self.items.len()
+        );
+        for item in &self.items {
+            xml.push_str(item);
+        }
+        xml.push_str("</sst>");
+        xml
+    }
+}
+
+// A <t> element, preserving surrounding whitespace when there is any
+fn text_run(text: &str) -> String {
+    if text.trim() != text {
+        format!(r#"<t xml:space="preserve">{}</t>"#, escape_xml(text))
+    } else {
+        format!("<t>{}</t>", escape_xml(text))
+    }
+}
+
+// Bind the SpreadsheetML namespace to `prefix` and prefix every element
+fn prefix_elements(xml: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(xml.len() + xml.len() / 4);
+    let mut rest = xml;
+    while let Some(i) = rest.find('<') {
+        out.push_str(&rest[..=i]);
+        rest = &rest[i + 1..];
+        if let Some(after) = rest.strip_prefix('/') {
+            out.push('/');
+            rest = after;
+        }
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            out.push_str(prefix);
+            out.push(':');
+        }
+    }
+    out.push_str(rest);
+    out.replacen(
+        r#"xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main""#,
+        &format!(
+            r#"xmlns:{}="http://schemas.openxmlformats.org/spreadsheetml/2006/main""#,
+            prefix
+        ),
+        1,
+    )
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="1"><font><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border/></borders><cellStyleXfs count="1"><xf/></cellStyleXfs><cellXfs count="1"><xf/></cellXfs></styleSheet>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_xml() {
+        let mut strings = SharedStringTable::default();
+        let sheet = SheetFixture::new("S")
+            .row([FixtureCell::from("x"), CellValue::Empty.into(), "x".into()])
+            .row([FixtureCell::RichText(vec!["a".into(), " b".into()])])
+            .merge("A1:B1".parse().unwrap());
+        let xml = sheet.to_xml(&mut strings, &Markup::default());
+
+        assert!(xml.contains(r#"<c r="A1" t="s"><v>0</v></c><c r="C1" t="s"><v>0</v></c>"#));
+        assert!(xml.contains(r#"<mergeCell ref="A1:B1"/>"#));
+        assert_eq!(strings.items.len(), 2);
+        assert_eq!(
+            strings.items[1],
+            r#"<si><r><rPr><b/></rPr><t>a</t></r><r><t xml:space="preserve"> b</t></r></si>"#
+        );
+        assert_eq!(
+            sheet.expected_rows(),
+            vec![
+                vec![
+                    CellValue::String("x".into()),
+                    CellValue::Empty,
+                    CellValue::String("x".into())
+                ],
+                vec![CellValue::String("a b".into())],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quirk_markup() {
+        let mut strings = SharedStringTable::default();
+        let sheet =
+            SheetFixture::new("S").row([FixtureCell::from(1), CellValue::Empty.into(), 2.into()]);
+        let implicit = Markup {
+            implicit_positions: true,
+            ..Markup::default()
+        };
+        let xml = sheet.to_xml(&mut strings, &implicit);
+        assert!(xml.contains(r#"<row><c t="n"><v>1</v></c><c/><c t="n"><v>2</v></c></row>"#));
+
+        let empty_cells = Markup {
+            empty_cells: true,
+            ..Markup::default()
+        };
+        let xml = sheet.to_xml(&mut strings, &empty_cells);
+        assert!(xml.contains(r#"<c r="A1" t="n"><v>1</v></c><c r="B1"/>"#));
+
+        let xml = prefix_elements(&xml, "x");
+        assert!(xml.starts_with(r#"<?xml version="1.0""#));
+        assert!(xml.contains(
+            r#"<x:worksheet xmlns:x="http://schemas.openxmlformats.org/spreadsheetml/2006/main""#
+        ));
+        assert!(xml.contains(r#"<x:row r="1"><x:c r="A1" t="n"><x:v>1</x:v></x:c><x:c r="B1"/>"#));
+        assert!(xml.ends_with("</x:sheetData></x:worksheet>"));
+    }
+
+    #[test]
+    fn test_merged_cells_read_back() {
+        let fixture = merged_cells();
+        let file = fixture.write_temp().unwrap();
+        assert_eq!(read_workbook(file.path()).unwrap(), fixture.expected());
+    }
+}
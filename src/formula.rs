@@ -0,0 +1,677 @@
+//! Typed formula builder
+//!
+//! Builds formula text from ranges, values and functions instead of string
+//! concatenation: sheet names are quoted, text literals escaped, nested
+//! operations parenthesized, and references checked against the grid.
+//! [`ExcelWriter::formula`](crate::ExcelWriter::formula) also checks that
+//! referenced sheets have been written.
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::formula::{Formula, Range};
+//!
+//! let total = Formula::sum(Range::col("B", 2, 1001));
+//! assert_eq!(total.to_string(), "=SUM(B2:B1001)");
+//!
+//! let grade = Formula::ifs([
+//!     (Formula::from(Range::parse("C2")).ge(90), Formula::from("A")),
+//!     (Formula::from(Range::parse("C2")).ge(75), Formula::from("B")),
+//!     (Formula::from(true), Formula::from("C")),
+//! ]);
+//! assert_eq!(grade.to_string(), r#"=IFS(C2>=90,"A",C2>=75,"B",TRUE,"C")"#);
+//!
+//! let other = Range::parse("A1:A10").on_sheet("Q1 Sales");
+//! assert_eq!(Formula::max(other).to_string(), "=MAX('Q1 Sales'!A1:A10)");
+//! ```
+
+use crate::cellref;
+use crate::error::{ExcelError, Result};
+use crate::numfmt::Locale;
+use crate::types::{CellRange, CellValue};
+use std::fmt;
+
+/// A cell or range reference, optionally on another sheet
+///
+/// Invalid references (outside the grid, bad column names) don't fail at
+/// construction; they render as `#REF!` and are reported by
+/// [`Formula::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    sheet: Option<String>,
+    area: std::result::Result<CellRange, String>,
+    absolute: bool,
+}
+
+impl Range {
+    /// Wrap a [`CellRange`]
+    pub fn new(range: CellRange) -> Self {
+        Range {
+            sheet: None,
+            area: Ok(range),
+            absolute: false,
+        }
+    }
+
+    /// Parse "B2", "B2:D10" or "'Other sheet'!B2:D10"
+    pub fn parse(text: &str) -> Self {
+        let (sheet, reference) = match text.rsplit_once('!') {
+            Some((sheet, reference)) => (Some(unquote_sheet(sheet)), reference),
+            None => (None, text),
+        };
+        Range {
+            sheet,
+            area: cellref::parse_range(reference).ok_or_else(|| text.to_string()),
+            absolute: false,
+        }
+    }
+
+    /// Rows `first_row..=last_row` of a column, with rows numbered from 1
+    /// as Excel shows them: `Range::col("B", 2, 1001)` is `B2:B1001`
+    pub fn col(name: &str, first_row: u32, last_row: u32) -> Self {
+        Self::parse(&format!("{}{}:{}{}", name, first_row, name, last_row))
+    }
+
+    /// Columns `first_col..=last_col` of a row numbered from 1:
+    /// `Range::row(5, "A", "F")` is `A5:F5`
+    pub fn row(row: u32, first_col: &str, last_col: &str) -> Self {
+        Self::parse(&format!("{}{}:{}{}", first_col, row, last_col, row))
+    }
+
+    /// Refer to the range on another sheet
+    pub fn on_sheet(mut self, sheet: &str) -> Self {
+        self.sheet = Some(sheet.to_string());
+        self
+    }
+
+    /// Render as an absolute reference (`$B$2:$B$1001`)
+    pub fn absolute(mut self) -> Self {
+        self.absolute = true;
+        self
+    }
+
+    /// Sheet the range is on, None for the formula's own sheet
+    pub fn sheet(&self) -> Option<&str> {
+        self.sheet.as_deref()
+    }
+
+    /// The cells referred to, or an error for an invalid reference
+    pub fn area(&self) -> Result<CellRange> {
+        self.area.clone().map_err(ExcelError::InvalidCell)
+    }
+}
+
+impl From<CellRange> for Range {
+    fn from(range: CellRange) -> Self {
+        Range::new(range)
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Ok(area) = &self.area else {
+            return f.write_str("#REF!");
+        };
+        if let Some(sheet) = &self.sheet {
+            f.write_str(&cellref::quote_sheet_name(sheet))?;
+            f.write_str("!")?;
+        }
+        let single = area.rows() == 1 && area.cols() == 1;
+        let corner = |row, col| {
+            if self.absolute {
+                cellref::to_a1_absolute(row, col)
+            } else {
+                cellref::to_a1(row, col)
+            }
+        };
+        f.write_str(&corner(area.first_row, area.first_col))?;
+        if !single {
+            write!(f, ":{}", corner(area.last_row, area.last_col))?;
+        }
+        Ok(())
+    }
+}
+
+/// Functions added to Excel after 2007, which files must store with the
+/// `_xlfn.` prefix; without it Excel shows `#NAME?` until the cell is
+/// re-entered
+const FUTURE_FUNCTIONS: &[&str] = &[
+    "CONCAT",
+    "DAYS",
+    "IFNA",
+    "IFS",
+    "ISOWEEKNUM",
+    "MAXIFS",
+    "MINIFS",
+    "SWITCH",
+    "TEXTJOIN",
+    "XLOOKUP",
+    "XMATCH",
+    "XOR",
+];
+
+/// A formula expression
+///
+/// Displays with a leading `=` as it is typed in Excel; [`to_cell`](Self::to_cell)
+/// gives the form files store, with newer functions prefixed `_xlfn.`.
+/// Combine expressions with `+ - * /` and the comparison methods.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula(Node);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Int(i64),
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Ref(Range),
+    Call(String, Vec<Node>),
+    Binary(Box<Node>, &'static str, Box<Node>),
+}
+
+impl Formula {
+    /// Call any worksheet function, e.g. `Formula::call("ROUND", [x, 2.into()])`
+    pub fn call<I, A>(function: &str, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<Formula>,
+    {
+        Formula(Node::Call(
+            function.to_ascii_uppercase(),
+            args.into_iter().map(|a| a.into().0).collect(),
+        ))
+    }
+
+    /// `SUM(range)`
+    pub fn sum(range: impl Into<Formula>) -> Self {
+        Self::call("SUM", [range])
+    }
+
+    /// `AVERAGE(range)`
+    pub fn average(range: impl Into<Formula>) -> Self {
+        Self::call("AVERAGE", [range])
+    }
+
+    /// `COUNT(range)`: number of numeric cells
+    pub fn count(range: impl Into<Formula>) -> Self {
+        Self::call("COUNT", [range])
+    }
+
+    /// `COUNTA(range)`: number of non-empty cells
+    pub fn counta(range: impl Into<Formula>) -> Self {
+        Self::call("COUNTA", [range])
+    }
+
+    /// `MIN(range)`
+    pub fn min(range: impl Into<Formula>) -> Self {
+        Self::call("MIN", [range])
+    }
+
+    /// `MAX(range)`
+    pub fn max(range: impl Into<Formula>) -> Self {
+        Self::call("MAX", [range])
+    }
+
+    /// `SUMIF(range, criteria, sum_range)`
+    pub fn sumif(
+        range: impl Into<Formula>,
+        criteria: impl Into<Formula>,
+        sum_range: impl Into<Formula>,
+    ) -> Self {
+        Self::call("SUMIF", [range.into(), criteria.into(), sum_range.into()])
+    }
+
+    /// `COUNTIF(range, criteria)`
+    pub fn countif(range: impl Into<Formula>, criteria: impl Into<Formula>) -> Self {
+        Self::call("COUNTIF", [range.into(), criteria.into()])
+    }
+
+    /// `IF(condition, then, otherwise)`
+    pub fn if_(
+        condition: impl Into<Formula>,
+        then: impl Into<Formula>,
+        otherwise: impl Into<Formula>,
+    ) -> Self {
+        Self::call("IF", [condition.into(), then.into(), otherwise.into()])
+    }
+
+    /// `IFS(condition1, value1, condition2, value2, ...)`
+    pub fn ifs<I>(cases: I) -> Self
+    where
+        I: IntoIterator<Item = (Formula, Formula)>,
+    {
+        Self::call("IFS", cases.into_iter().flat_map(|(c, v)| [c, v]))
+    }
+
+    /// `IFERROR(value, fallback)`
+    pub fn iferror(value: impl Into<Formula>, fallback: impl Into<Formula>) -> Self {
+        Self::call("IFERROR", [value.into(), fallback.into()])
+    }
+
+    /// `CONCAT(parts...)`
+    pub fn concat<I, A>(parts: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<Formula>,
+    {
+        Self::call("CONCAT", parts)
+    }
+
+    /// `self > other`
+    pub fn gt(self, other: impl Into<Formula>) -> Self {
+        self.binary(">", other)
+    }
+
+    /// `self >= other`
+    pub fn ge(self, other: impl Into<Formula>) -> Self {
+        self.binary(">=", other)
+    }
+
+    /// `self < other`
+    pub fn lt(self, other: impl Into<Formula>) -> Self {
+        self.binary("<", other)
+    }
+
+    /// `self <= other`
+    pub fn le(self, other: impl Into<Formula>) -> Self {
+        self.binary("<=", other)
+    }
+
+    /// `self = other`
+    pub fn equals(self, other: impl Into<Formula>) -> Self {
+        self.binary("=", other)
+    }
+
+    /// `self <> other`
+    pub fn not_equals(self, other: impl Into<Formula>) -> Self {
+        self.binary("<>", other)
+    }
+
+    /// `self & other` (text concatenation)
+    pub fn join(self, other: impl Into<Formula>) -> Self {
+        self.binary("&", other)
+    }
+
+    fn binary(self, op: &'static str, other: impl Into<Formula>) -> Self {
+        Formula(Node::Binary(Box::new(self.0), op, Box::new(other.into().0)))
+    }
+
+    /// Ranges the formula refers to
+    pub fn references(&self) -> Vec<&Range> {
+        let mut refs = Vec::new();
+        self.0.collect_refs(&mut refs);
+        refs
+    }
+
+    /// Check the references: every range must be valid and every sheet it
+    /// names must be one of `sheets` (compared ignoring case, like Excel)
+    pub fn validate<S: AsRef<str>>(&self, sheets: &[S]) -> Result<()> {
+        match self.missing_sheets(sheets)?.first() {
+            Some(sheet) => Err(sheet_not_found(sheet, sheets)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the ranges and list the sheets referenced that are not in
+    /// `sheets`, each once
+    pub(crate) fn missing_sheets<S: AsRef<str>>(&self, sheets: &[S]) -> Result<Vec<&str>> {
+        let mut missing: Vec<&str> = Vec::new();
+        for range in self.references() {
+            range.area()?;
+            if let Some(sheet) = range.sheet() {
+                let known = sheets
+                    .iter()
+                    .map(|s| s.as_ref())
+                    .chain(missing.iter().copied())
+                    .any(|s| s.eq_ignore_ascii_case(sheet));
+                if !known {
+                    missing.push(sheet);
+                }
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Formula text for a locale's formula bar, without the leading `=`
+    ///
+    /// Locales with a decimal comma separate arguments with `;`. Files
+    /// always store the en-US form, so use this only for display or text
+    /// exports.
+    pub fn to_localized(&self, locale: &Locale) -> String {
+        let separator = if locale.decimal_separator == ',' {
+            ';'
+        } else {
+            ','
+        };
+        let mut out = String::new();
+        self.0
+            .render(&mut out, separator, locale.decimal_separator, false);
+        out
+    }
+
+    /// The formula as a cell value, in the stored form: functions newer
+    /// than Excel 2007 such as `IFS` and `CONCAT` get the `_xlfn.` prefix
+    pub fn to_cell(&self) -> CellValue {
+        let mut out = String::from("=");
+        self.0.render(&mut out, ',', '.', true);
+        CellValue::Formula(out)
+    }
+}
+
+impl Node {
+    // `stored` renders function names as files store them
+    fn render(&self, out: &mut String, separator: char, decimal: char, stored: bool) {
+        use std::fmt::Write;
+        match self {
+            Node::Int(i) => {
+                let _ = write!(out, "{}", i);
+            }
+            Node::Number(n) if n.is_finite() => {
+                let text = n.to_string();
+                if decimal == '.' {
+                    out.push_str(&text);
+                } else {
+                    out.push_str(&text.replace('.', &decimal.to_string()));
+                }
+            }
+            Node::Number(_) => out.push_str("#NUM!"),
+            Node::Text(s) => {
+                out.push('"');
+                out.push_str(&s.replace('"', "\"\""));
+                out.push('"');
+            }
+            Node::Bool(b) => out.push_str(if *b { "TRUE" } else { "FALSE" }),
+            Node::Ref(range) => {
+                let _ = write!(out, "{}", range);
+            }
+            Node::Call(function, args) => {
+                if stored && FUTURE_FUNCTIONS.contains(&function.as_str()) {
+                    out.push_str("_xlfn.");
+                }
+                out.push_str(function);
+                out.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        out.push(separator);
+                    }
+                    arg.render(out, separator, decimal, stored);
+                }
+                out.push(')');
+            }
+            Node::Binary(left, op, right) => {
+                for (i, side) in [left, right].into_iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(op);
+                    }
+                    if matches!(**side, Node::Binary(..)) {
+                        out.push('(');
+                        side.render(out, separator, decimal, stored);
+                        out.push(')');
+                    } else {
+                        side.render(out, separator, decimal, stored);
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_refs<'a>(&'a self, refs: &mut Vec<&'a Range>) {
+        match self {
+            Node::Ref(range) => refs.push(range),
+            Node::Call(_, args) => args.iter().for_each(|a| a.collect_refs(refs)),
+            Node::Binary(left, _, right) => {
+                left.collect_refs(refs);
+                right.collect_refs(refs);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::from("=");
+        self.0.render(&mut out, ',', '.', false);
+        f.write_str(&out)
+    }
+}
+
+impl From<Formula> for CellValue {
+    fn from(formula: Formula) -> Self {
+        formula.to_cell()
+    }
+}
+
+impl From<Range> for Formula {
+    fn from(range: Range) -> Self {
+        Formula(Node::Ref(range))
+    }
+}
+
+impl From<CellRange> for Formula {
+    fn from(range: CellRange) -> Self {
+        Formula(Node::Ref(Range::new(range)))
+    }
+}
+
+impl From<i64> for Formula {
+    fn from(value: i64) -> Self {
+        Formula(Node::Int(value))
+    }
+}
+
+impl From<i32> for Formula {
+    fn from(value: i32) -> Self {
+        Formula(Node::Int(value as i64))
+    }
+}
+
+impl From<f64> for Formula {
+    fn from(value: f64) -> Self {
+        Formula(Node::Number(value))
+    }
+}
+
+impl From<bool> for Formula {
+    fn from(value: bool) -> Self {
+        Formula(Node::Bool(value))
+    }
+}
+
+/// A text literal (quotes are escaped)
+impl From<&str> for Formula {
+    fn from(value: &str) -> Self {
+        Formula(Node::Text(value.to_string()))
+    }
+}
+
+macro_rules! arithmetic {
+    ($trait:ident, $method:ident, $op:literal) => {
+        impl<T: Into<Formula>> std::ops::$trait<T> for Formula {
+            type Output = Formula;
+
+            fn $method(self, other: T) -> Formula {
+                self.binary($op, other)
+            }
+        }
+    };
+}
+
+arithmetic!(Add, add, "+");
+arithmetic!(Sub, sub, "-");
+arithmetic!(Mul, mul, "*");
+arithmetic!(Div, div, "/");
+
+pub(crate) fn sheet_not_found<S: AsRef<str>>(sheet: &str, sheets: &[S]) -> ExcelError {
+    ExcelError::SheetNotFound {
+        sheet: sheet.to_string(),
+        available: sheets
+            .iter()
+            .map(|s| s.as_ref())
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// A formula's reference to a sheet that had not been added when the
+/// formula was built, see [`ExcelWriter::formula_deferred`](crate::ExcelWriter::formula_deferred)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferredRef {
+    /// Sheet referenced
+    pub sheet: String,
+    /// Sheet the formula was built for
+    pub from_sheet: String,
+    /// 0-based row the formula was built for (the next row of `from_sheet`)
+    pub row: u32,
+}
+
+fn unquote_sheet(sheet: &str) -> String {
+    match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        Some(inner) => inner.replace("''", "'"),
+        None => sheet.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let price = Formula::from(Range::parse("B2"));
+        let qty = Formula::from(Range::parse("C2"));
+        assert_eq!((price * qty + 1).to_string(), "=(B2*C2)+1");
+
+        let f = Formula::if_(
+            Formula::from(Range::parse("A1")).equals(""),
+            "say \"hi\"",
+            Formula::average(Range::col("D", 2, 10).absolute()),
+        );
+        assert_eq!(
+            f.to_string(),
+            r#"=IF(A1="","say ""hi""",AVERAGE($D$2:$D$10))"#
+        );
+        assert_eq!(
+            Formula::call("round", [Formula::from(Range::parse("A1")), 2.into()]).to_string(),
+            "=ROUND(A1,2)"
+        );
+        assert_eq!(
+            Formula::sum(Range::row(5, "A", "F").on_sheet("It's")).to_string(),
+            "=SUM('It''s'!A5:F5)"
+        );
+        assert_eq!(
+            Formula::sum(Range::parse("A1").on_sheet("R1C1")).to_string(),
+            "=SUM('R1C1'!A1)"
+        );
+
+        let f = Formula::iferror(Formula::from(Range::parse("A1")) / 2.5, 0);
+        assert_eq!(f.to_localized(&Locale::DE_DE), "IFERROR(A1/2,5;0)");
+        assert_eq!(
+            f.to_cell(),
+            CellValue::Formula("=IFERROR(A1/2.5,0)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let f =
+            Formula::sum(Range::parse("'Q1 Sales'!B2:B9")) + Formula::max(Range::col("B", 2, 9));
+        assert_eq!(f.references()[0].sheet(), Some("Q1 Sales"));
+        assert!(f.validate(&["Summary", "q1 sales"]).is_ok());
+        assert!(matches!(
+            f.validate(&["Summary"]),
+            Err(ExcelError::SheetNotFound { .. })
+        ));
+
+        let bad = Formula::sum(Range::col("B", 0, 10));
+        assert_eq!(bad.to_string(), "=SUM(#REF!)");
+        assert!(matches!(
+            bad.validate::<&str>(&[]),
+            Err(ExcelError::InvalidCell(_))
+        ));
+    }
+
+    #[test]
+    fn test_newer_functions_are_prefixed() {
+        let a1 = || Formula::from(Range::parse("A1"));
+        let grade = Formula::ifs([(a1().ge(90), Formula::from(1)), (true.into(), 0.into())]);
+        let label = Formula::concat([a1(), Formula::from(Range::parse("B1"))]);
+        assert_eq!(grade.to_string(), "=IFS(A1>=90,1,TRUE,0)");
+        assert_eq!(
+            grade.to_cell(),
+            CellValue::Formula("=_xlfn.IFS(A1>=90,1,TRUE,0)".to_string())
+        );
+        assert_eq!(
+            Formula::iferror(label.clone(), "").to_cell(),
+            CellValue::Formula(r#"=IFERROR(_xlfn.CONCAT(A1,B1),"")"#.to_string())
+        );
+
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer
+            .write_row_typed(&[grade.to_cell(), label.to_cell()])
+            .unwrap();
+        writer.save().unwrap();
+        let mut zip = crate::fast_writer::StreamingZipReader::open(temp.path()).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains("_xlfn.IFS(A1&gt;=90,1,TRUE,0)</f>"));
+        assert!(sheet.contains("_xlfn.CONCAT(A1,B1)</f>"));
+    }
+
+    #[test]
+    fn test_writer_checks_sheets() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        let summary = Formula::sum(Range::col("A", 1, 3).on_sheet("Summary"));
+        assert!(writer.formula(&summary).is_err());
+        writer.add_sheet("Summary").unwrap();
+        assert_eq!(
+            writer.formula(&summary).unwrap(),
+            CellValue::Formula("=SUM(Summary!A1:A3)".to_string())
+        );
+        writer.save().unwrap();
+    }
+
+    #[test]
+    fn test_deferred_refs() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer.write_row(["Total"]).unwrap();
+        let total = Formula::sum(Range::col("B", 2, 10).on_sheet("Q1 Detail"));
+        assert_eq!(
+            writer.formula_deferred(&total).unwrap(),
+            CellValue::Formula("=SUM('Q1 Detail'!B2:B10)".to_string())
+        );
+        writer.formula_deferred(&total).unwrap();
+        assert_eq!(
+            writer.deferred_refs(),
+            [DeferredRef {
+                sheet: "Q1 Detail".to_string(),
+                from_sheet: "Sheet1".to_string(),
+                row: 1,
+            }]
+        );
+        writer.add_sheet("q1 detail").unwrap();
+        assert!(writer.deferred_refs().is_empty());
+        writer.save().unwrap();
+
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer.formula_deferred(&total).unwrap();
+        assert!(matches!(
+            writer.save(),
+            Err(ExcelError::SheetNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_quote_sheet_name() {
+        use crate::cellref::quote_sheet_name;
+        assert_eq!(quote_sheet_name("Data"), "Data");
+        assert_eq!(quote_sheet_name("Q1 Detail"), "'Q1 Detail'");
+        assert_eq!(quote_sheet_name("Bob's"), "'Bob''s'");
+        assert_eq!(quote_sheet_name("2024"), "'2024'");
+        assert_eq!(quote_sheet_name("AB12"), "'AB12'");
+    }
+}
@@ -0,0 +1,346 @@
+//! Type inference for text-only sheets
+//!
+//! Sheets written as plain strings (CSV conversions, the crate's own
+//! `write_row`) read back as [`CellValue::String`] everywhere. With
+//! [`ReaderOptions::with_infer_types`](crate::ReaderOptions::with_infer_types)
+//! the reader parses such text into `Int`, `Float`, `Bool` and `DateTime`
+//! values, following the number and date conventions of a [`TypeInference`].
+//! ISO 8601 date-times with a UTC offset (`2024-01-31T09:00:00+07:00`) become
+//! [`CellValue::DateTimeTz`], keeping the offset.
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::infer::TypeInference;
+//! use excelstream::types::CellValue;
+//!
+//! let german = TypeInference::decimal_comma();
+//! assert_eq!(german.infer("1.234,5"), CellValue::Float(1234.5));
+//! assert_eq!(german.infer("wahr"), CellValue::String("wahr".to_string()));
+//!
+//! let default = TypeInference::new();
+//! assert_eq!(default.infer("42"), CellValue::Int(42));
+//! assert_eq!(default.infer("007"), CellValue::String("007".to_string()));
+//! ```
+
+use crate::types::CellValue;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// Rules for turning text cells into typed values
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeInference {
+    /// Character between the integer and fractional part (default: `.`)
+    pub decimal_separator: char,
+    /// Digit grouping character accepted in numbers, e.g. `,` in `1,234.5`
+    /// (default: none)
+    pub thousands_separator: Option<char>,
+    /// Text read as `true`, compared ignoring case (default: `true`)
+    pub true_values: Vec<String>,
+    /// Text read as `false`, compared ignoring case (default: `false`)
+    pub false_values: Vec<String>,
+    /// `chrono` formats tried for dates and date-times, first match wins
+    /// (default: ISO dates and date-times)
+    pub date_formats: Vec<String>,
+    /// Leave integers with leading zeros (`007`, zip codes) as text
+    /// (default: true)
+    pub keep_leading_zeros: bool,
+}
+
+impl Default for TypeInference {
+    fn default() -> Self {
+        TypeInference {
+            decimal_separator: '.',
+            thousands_separator: None,
+            true_values: vec!["true".to_string()],
+            false_values: vec!["false".to_string()],
+            date_formats: vec![
+                "%Y-%m-%d".to_string(),
+                "%Y-%m-%d %H:%M:%S".to_string(),
+                "%Y-%m-%dT%H:%M:%S".to_string(),
+            ],
+            keep_leading_zeros: true,
+        }
+    }
+}
+
+impl TypeInference {
+    /// Decimal point, ISO dates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decimal comma with `.` grouping (`1.234,5`) and day-first dates
+    /// (`31.01.2024`, `31/01/2024`), as in most of continental Europe
+    pub fn decimal_comma() -> Self {
+        let mut rules = TypeInference {
+            decimal_separator: ',',
+            thousands_separator: Some('.'),
+            ..Self::default()
+        };
+        rules
+            .date_formats
+            .extend(["%d.%m.%Y", "%d/%m/%Y", "%d.%m.%Y %H:%M:%S"].map(String::from));
+        rules
+    }
+
+    /// Set the decimal separator
+    pub fn with_decimal_separator(mut self, separator: char) -> Self {
+        self.decimal_separator = separator;
+        self
+    }
+
+    /// Set the digit grouping character, or `None` to reject grouped numbers
+    pub fn with_thousands_separator(mut self, separator: Option<char>) -> Self {
+        self.thousands_separator = separator;
+        self
+    }
+
+    /// Set the words read as booleans
+    pub fn with_bool_values<I, J, S, T>(mut self, true_values: I, false_values: J) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        J: IntoIterator<Item = T>,
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        self.true_values = true_values
+            .into_iter()
+            .map(|v| v.as_ref().to_string())
+            .collect();
+        self.false_values = false_values
+            .into_iter()
+            .map(|v| v.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Replace the date formats tried
+    pub fn with_date_formats<I, S>(mut self, formats: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.date_formats = formats
+            .into_iter()
+            .map(|f| f.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Keep or drop leading zeros (see [`keep_leading_zeros`](Self::keep_leading_zeros))
+    pub fn with_keep_leading_zeros(mut self, keep: bool) -> Self {
+        self.keep_leading_zeros = keep;
+        self
+    }
+
+    /// Typed value for `text`, or the text itself when nothing matches
+    pub fn infer(&self, text: &str) -> CellValue {
+        self.try_infer(text)
+            .unwrap_or_else(|| CellValue::String(text.to_string()))
+    }
+
+    /// Replace the text cells of a row with their typed values
+    pub fn apply(&self, row: &mut [CellValue]) {
+        for cell in row.iter_mut() {
+            if let CellValue::String(text) = cell {
+                if let Some(value) = self.try_infer(text) {
+                    *cell = value;
+                }
+            }
+        }
+    }
+
+    fn try_infer(&self, text: &str) -> Option<CellValue> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        if self
+            .true_values
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(text))
+        {
+            return Some(CellValue::Bool(true));
+        }
+        if self
+            .false_values
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(text))
+        {
+            return Some(CellValue::Bool(false));
+        }
+        self.parse_number(text).or_else(|| self.parse_date(text))
+    }
+
+    fn parse_number(&self, text: &str) -> Option<CellValue> {
+        let (sign, unsigned) = match text.as_bytes()[0] {
+            b'-' => ("-", &text[1..]),
+            b'+' => ("", &text[1..]),
+            _ => ("", text),
+        };
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(pos) => (&unsigned[..pos], Some(&unsigned[pos + 1..])),
+            None => (unsigned, None),
+        };
+        let (int_part, frac_part) = match mantissa.split_once(self.decimal_separator) {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (mantissa, None),
+        };
+
+        let digits = self.ungroup(int_part)?;
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if digits.is_empty() && frac_part.is_none_or(|f| f.is_empty()) {
+            return None;
+        }
+        if !all_digits(&digits) || !frac_part.is_none_or(all_digits) {
+            return None;
+        }
+        if let Some(exponent) = exponent {
+            let exp_digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if exp_digits.is_empty() || !all_digits(exp_digits) {
+                return None;
+            }
+        }
+
+        if frac_part.is_none() && exponent.is_none() {
+            if self.keep_leading_zeros && digits.len() > 1 && digits.starts_with('0') {
+                return None;
+            }
+            if let Ok(value) = format!("{}{}", sign, digits).parse::<i64>() {
+                return Some(CellValue::Int(value));
+            }
+        }
+
+        let mut normalized = format!("{}{}", sign, digits);
+        if let Some(frac_part) = frac_part {
+            normalized.push('.');
+            normalized.push_str(frac_part);
+        }
+        if let Some(exponent) = exponent {
+            normalized.push('e');
+            normalized.push_str(exponent);
+        }
+        normalized.parse::<f64>().ok().map(CellValue::Float)
+    }
+
+    // Integer digits with grouping removed, if the groups are well formed
+    fn ungroup(&self, int_part: &str) -> Option<String> {
+        let Some(separator) = self.thousands_separator else {
+            return Some(int_part.to_string());
+        };
+        if !int_part.contains(separator) {
+            return Some(int_part.to_string());
+        }
+        let mut groups = int_part.split(separator);
+        let first = groups.next()?;
+        if first.is_empty() || first.len() > 3 {
+            return None;
+        }
+        let mut digits = first.to_string();
+        for group in groups {
+            if group.len() != 3 {
+                return None;
+            }
+            digits.push_str(group);
+        }
+        Some(digits)
+    }
+
+    fn parse_date(&self, text: &str) -> Option<CellValue> {
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+            return Some(CellValue::DateTimeTz(datetime));
+        }
+        let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+        self.date_formats.iter().find_map(|format| {
+            let datetime = NaiveDateTime::parse_from_str(text, format)
+                .ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(text, format)
+                        .ok()
+                        .and_then(|d| d.and_hms_opt(0, 0, 0))
+                })?;
+            let seconds = (datetime - epoch).num_seconds();
+            Some(CellValue::DateTime(seconds as f64 / 86_400.0))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_default_rules() {
+        let rules = TypeInference::new();
+        assert_eq!(rules.infer("42"), CellValue::Int(42));
+        assert_eq!(rules.infer("-7"), CellValue::Int(-7));
+        assert_eq!(rules.infer("3.5"), CellValue::Float(3.5));
+        assert_eq!(rules.infer(".5"), CellValue::Float(0.5));
+        assert_eq!(rules.infer("1e3"), CellValue::Float(1000.0));
+        assert_eq!(rules.infer("TRUE"), CellValue::Bool(true));
+        assert_eq!(rules.infer("2023-10-18"), CellValue::DateTime(45217.0));
+        assert_eq!(
+            rules.infer("2023-10-18 12:00:00"),
+            CellValue::DateTime(45217.5)
+        );
+        assert_eq!(rules.infer("99999999999999999999"), CellValue::Float(1e20));
+        let offset = rules.infer("2023-10-18T12:00:00+07:00");
+        assert!(matches!(offset, CellValue::DateTimeTz(_)));
+        assert_eq!(offset.as_string(), "2023-10-18T12:00:00+07:00");
+
+        for text in ["007", "1,234", "inf", "NaN", "1.2.3", "-", "abc", ""] {
+            assert_eq!(rules.infer(text), CellValue::String(text.to_string()));
+        }
+        assert_eq!(
+            rules.with_keep_leading_zeros(false).infer("007"),
+            CellValue::Int(7)
+        );
+    }
+
+    #[test]
+    fn test_infer_decimal_comma() {
+        let rules = TypeInference::decimal_comma().with_bool_values(["ja"], ["nein"]);
+        assert_eq!(rules.infer("1.234.567"), CellValue::Int(1234567));
+        assert_eq!(rules.infer("-1.234,5"), CellValue::Float(-1234.5));
+        assert_eq!(rules.infer("0,25"), CellValue::Float(0.25));
+        assert_eq!(rules.infer("Ja"), CellValue::Bool(true));
+        assert_eq!(rules.infer("18.10.2023"), CellValue::DateTime(45217.0));
+        assert_eq!(rules.infer("1.5"), CellValue::String("1.5".to_string()));
+        assert_eq!(
+            rules.infer("1.2345"),
+            CellValue::String("1.2345".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reader_infers_types() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer.write_row(["id", "price", "paid", "due"]).unwrap();
+        writer
+            .write_row(["0042", "1.299,90", "true", "31.01.2024"])
+            .unwrap();
+        writer.save().unwrap();
+
+        let options =
+            crate::ReaderOptions::new().with_type_inference(TypeInference::decimal_comma());
+        let mut reader =
+            crate::streaming_reader::StreamingReader::open_with_options(temp.path(), options)
+                .unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(rows[0][0], CellValue::String("id".to_string()));
+        assert_eq!(
+            rows[1],
+            vec![
+                CellValue::String("0042".to_string()),
+                CellValue::Float(1299.9),
+                CellValue::Bool(true),
+                CellValue::DateTime(45322.0),
+            ]
+        );
+    }
+}
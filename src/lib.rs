@@ -18,10 +18,10 @@
 //! ### Reading Excel Files (Streaming)
 //!
 //! ```rust,no_run
-//! use excelstream::streaming_reader::StreamingReader;
+//! use excelstream::ExcelReader;
 //!
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
-//! let mut reader = StreamingReader::open("data.xlsx")?;
+//! let mut reader = ExcelReader::open("data.xlsx")?;
 //!
 //! for row_result in reader.rows("Sheet1")? {
 //!     let row = row_result?;
@@ -61,11 +61,50 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ### Serverless (AWS Lambda)
+//!
+//! ```rust
+//! use excelstream::types::CellValue;
+//! use excelstream::{export_to_bytes, ExportOptions};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let rows = vec![vec![CellValue::String("Alice".to_string()), CellValue::Int(30)]];
+//! let body = export_to_bytes(rows, &ExportOptions::new().with_header(["Name", "Age"]))?;
+//! # Ok(())
+//! # }
+//! ```
 
+pub mod cellref;
+pub mod compat;
+pub mod dedupe;
+pub mod dynamic;
 pub mod error;
+pub mod export;
 pub mod fast_writer;
+pub mod fixtures;
+pub mod formula;
+pub mod infer;
+pub mod manifest;
+pub mod merge;
+pub mod middleware;
+pub mod numfmt;
+mod ooxml;
+pub mod preview;
+pub mod profile;
+pub mod reader;
+pub mod sharded;
+pub mod signature;
+pub mod sink;
+pub mod sort;
+mod staging;
 pub mod streaming_reader;
+pub mod summary;
+pub mod throttle;
+pub mod transpose;
 pub mod types;
+pub mod untrusted;
+pub mod workbook;
 pub mod writer;
 
 // CSV support
@@ -89,11 +128,38 @@ pub mod parquet;
 
 // Incremental append mode
 pub mod append;
+pub mod tail;
+
+// Database export (optional)
+#[cfg(any(feature = "db-postgres", feature = "db-mysql"))]
+pub mod db;
+
+// Async row streams (optional)
+#[cfg(feature = "async")]
+pub mod row_stream;
 
+pub use dynamic::DynamicWriter;
 pub use error::{ExcelError, Result};
-pub use streaming_reader::StreamingReader as ExcelReader; // Re-export for backward compatibility
-pub use types::{Cell, CellStyle, CellValue, ProtectionOptions, Row, StyledCell};
-pub use writer::ExcelWriter;
+pub use export::{export_to_bytes, ExportOptions};
+pub use fast_writer::{FlushPolicy, MemoryProfile};
+pub use reader::ExcelReader;
+pub use sharded::ShardedWriter;
+pub use streaming_reader::{
+    BlankRows, DuplicateHeaders, ExternalValues, MissingCells, ReadLimits, ReaderOptions,
+};
+pub use throttle::Throttle;
+pub use transpose::TransposedWriter;
+pub use types::{
+    AuditOptions, BorrowedCell, BorrowedRow, CalcMode, CalcSettings, Cell, CellFormat, CellRange,
+    CellStyle, CellValue, Column, Decimal, DefinedName, ExternalLink, FloatFormat, FormattedCell,
+    HeaderFooter, HeaderFooterImage, HeaderFooterPosition, Hyperlink, IndexSheetOptions,
+    LimitPolicy, MemoryReport, NonFinite, ProtectionOptions, Record, RichText, Row, SheetImage,
+    SheetVisibility, StyledCell, TableInfo, TextRun, TimeZonePolicy, WorkbookProperties,
+    WorkbookStats, WriteReport, ZipLayout,
+};
+pub use untrusted::{parse_untrusted, UntrustedLimits};
+pub use workbook::Workbook;
+pub use writer::{ExcelWriter, ExcelWriterBuilder, SheetContext, SpreadsheetWriter};
 
 // CSV exports
 pub use csv::CompressionMethod;
@@ -0,0 +1,290 @@
+//! Delivery manifests for written files
+//!
+//! A [`Manifest`] records what a delivery should contain: the file's SHA-256
+//! and size, rows per sheet, when it was generated and by which library
+//! version. Enable it with
+//! [`ExcelWriterBuilder::with_manifest`](crate::ExcelWriterBuilder::with_manifest);
+//! after `save()` the manifest is written as a JSON sidecar next to the file
+//! and returned in [`WriteReport::manifest`](crate::WriteReport::manifest).
+//! Ingestion jobs check a delivery with [`Manifest::verify`].
+//!
+//! The manifest can also be embedded in the workbook as the custom document
+//! property [`EMBEDDED_PROPERTY`] (File > Properties in Excel). A file can't
+//! contain its own checksum, so the embedded copy has no `sha256` or `size`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::manifest::ManifestOptions;
+//! use excelstream::ExcelWriterBuilder;
+//!
+//! let mut writer = ExcelWriterBuilder::new("delivery.xlsx")
+//!     .with_manifest(ManifestOptions::new().with_embedded(true))
+//!     .build()?;
+//! writer.write_row(["id", "amount"])?;
+//! let report = writer.save()?;
+//! // delivery.xlsx.manifest.json:
+//! // {"file":"delivery.xlsx","sha256":"…","size":…,"sheets":[{"name":"Sheet1","rows":1}],…}
+//! report.manifest.unwrap().verify("delivery.xlsx")?;
+//! # Ok::<(), excelstream::ExcelError>(())
+//! ```
+
+use crate::error::{ExcelError, Result};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Name of the custom document property holding an embedded manifest
+pub const EMBEDDED_PROPERTY: &str = "excelstream.manifest";
+
+/// Where a manifest is written, see
+/// [`ExcelWriterBuilder::with_manifest`](crate::ExcelWriterBuilder::with_manifest)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestOptions {
+    /// Write a JSON sidecar file (default: true)
+    pub sidecar: bool,
+    /// Sidecar path (default: the file's path plus `.manifest.json`)
+    pub sidecar_path: Option<PathBuf>,
+    /// Embed the manifest, without checksum, as a custom document property
+    /// (default: false)
+    pub embedded: bool,
+}
+
+impl Default for ManifestOptions {
+    fn default() -> Self {
+        ManifestOptions {
+            sidecar: true,
+            sidecar_path: None,
+            embedded: false,
+        }
+    }
+}
+
+impl ManifestOptions {
+    /// Write a sidecar next to the file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the sidecar or not
+    pub fn with_sidecar(mut self, sidecar: bool) -> Self {
+        self.sidecar = sidecar;
+        self
+    }
+
+    /// Write the sidecar to `path`
+    pub fn with_sidecar_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.sidecar_path = Some(path.as_ref().to_path_buf());
+        self.sidecar = true;
+        self
+    }
+
+    /// Embed the manifest in the workbook or not
+    pub fn with_embedded(mut self, embedded: bool) -> Self {
+        self.embedded = embedded;
+        self
+    }
+
+    /// Sidecar path for a file at `path`
+    pub fn sidecar_path_for(&self, path: &Path) -> PathBuf {
+        self.sidecar_path.clone().unwrap_or_else(|| {
+            let mut name = path.as_os_str().to_os_string();
+            name.push(".manifest.json");
+            PathBuf::from(name)
+        })
+    }
+}
+
+/// Contents of a delivered file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// File name, without directories (None when embedded)
+    pub file: Option<String>,
+    /// Lowercase hex SHA-256 of the file (None when embedded)
+    pub sha256: Option<String>,
+    /// File size in bytes (None when embedded)
+    pub size: Option<u64>,
+    /// Rows written to each sheet, in sheet order
+    pub sheets: Vec<(String, u32)>,
+    /// When the file was generated (RFC 3339, UTC)
+    pub generated_at: String,
+    /// Version of excelstream that wrote the file
+    pub library_version: String,
+}
+
+impl Manifest {
+    /// Manifest without file checksum, e.g. to embed in the workbook
+    pub fn new(sheets: Vec<(String, u32)>) -> Self {
+        Manifest {
+            file: None,
+            sha256: None,
+            size: None,
+            sheets,
+            generated_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            library_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Manifest of a finished file: hashes it and records its size
+    pub fn for_file<P: AsRef<Path>>(path: P, sheets: Vec<(String, u32)>) -> Result<Self> {
+        let path = path.as_ref();
+        let (sha256, size) = sha256_file(path)?;
+        Ok(Manifest {
+            file: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            sha256: Some(sha256),
+            size: Some(size),
+            ..Self::new(sheets)
+        })
+    }
+
+    /// Total rows across all sheets
+    pub fn total_rows(&self) -> u64 {
+        self.sheets.iter().map(|(_, rows)| *rows as u64).sum()
+    }
+
+    /// Check that the file at `path` is the one described
+    ///
+    /// Fails with [`ExcelError::IntegrityMismatch`] when its size or SHA-256
+    /// differ, and with [`ExcelError::InvalidState`] for a manifest without
+    /// checksum.
+    pub fn verify<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let Some(expected) = &self.sha256 else {
+            return Err(ExcelError::InvalidState(
+                "Manifest has no checksum to verify against".to_string(),
+            ));
+        };
+        let (actual, size) = sha256_file(path)?;
+        let mismatch = |reason: String| ExcelError::IntegrityMismatch {
+            object: path.display().to_string(),
+            reason,
+        };
+        if let Some(expected_size) = self.size {
+            if size != expected_size {
+                return Err(mismatch(format!(
+                    "expected {} bytes, found {}",
+                    expected_size, size
+                )));
+            }
+        }
+        if &actual != expected {
+            return Err(mismatch(format!(
+                "expected SHA-256 {}, found {}",
+                expected, actual
+            )));
+        }
+        Ok(())
+    }
+
+    /// Manifest as a JSON object
+    ///
+    /// Keys: `file`, `sha256`, `size` (omitted when embedded), `sheets` (a
+    /// list of `{"name", "rows"}`), `total_rows`, `generated_at`, `library`
+    /// and `library_version`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        if let Some(file) = &self.file {
+            json.push_str(&format!("\"file\":{},", json_string(file)));
+        }
+        if let Some(sha256) = &self.sha256 {
+            json.push_str(&format!("\"sha256\":{},", json_string(sha256)));
+        }
+        if let Some(size) = self.size {
+            json.push_str(&format!("\"size\":{},", size));
+        }
+        json.push_str("\"sheets\":[");
+        for (i, (name, rows)) in self.sheets.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!(
+                "{{\"name\":{},\"rows\":{}}}",
+                json_string(name),
+                rows
+            ));
+        }
+        json.push_str(&format!(
+            "],\"total_rows\":{},\"generated_at\":{},\"library\":\"excelstream\",\"library_version\":{}}}",
+            self.total_rows(),
+            json_string(&self.generated_at),
+            json_string(&self.library_version)
+        ));
+        json
+    }
+}
+
+/// Lowercase hex SHA-256 and size of a file, read in chunks
+pub fn sha256_file<P: AsRef<Path>>(path: P) -> Result<(String, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        size += n as u64;
+        hasher.update(&chunk[..n]);
+    }
+    let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok((hex, size))
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_json_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.xlsx");
+        std::fs::write(&path, b"abc").unwrap();
+
+        let manifest = Manifest::for_file(&path, vec![("Q\"1".to_string(), 3)]).unwrap();
+        assert_eq!(
+            manifest.sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        let json = manifest.to_json();
+        assert!(json.starts_with(
+            r#"{"file":"abc.xlsx","sha256":"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad","size":3,"sheets":[{"name":"Q\"1","rows":3}],"total_rows":3,"generated_at":""#
+        ));
+        manifest.verify(&path).unwrap();
+
+        std::fs::write(&path, b"abd").unwrap();
+        assert!(matches!(
+            manifest.verify(&path),
+            Err(ExcelError::IntegrityMismatch { .. })
+        ));
+        assert!(Manifest::new(Vec::new()).verify(&path).is_err());
+        assert_eq!(
+            ManifestOptions::new().sidecar_path_for(&path),
+            dir.path().join("abc.xlsx.manifest.json")
+        );
+    }
+}
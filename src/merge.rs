@@ -0,0 +1,520 @@
+//! Combine several workbooks into one
+//!
+//! [`concat`] streams the rows of every input into a single output workbook,
+//! one row at a time, so memory stays flat however large the inputs are.
+//! Values keep their number formats (dates, currencies, percentages); fonts
+//! and fills are not carried over.
+//!
+//! [`copy_rows`] streams one sheet into a sheet of an open writer, with a
+//! [`StyleMap`] deciding which style each source format becomes, e.g. to
+//! move a workbook to a new house style.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::merge::{self, MergeOptions};
+//!
+//! // jan.xlsx, feb.xlsx, mar.xlsx each have a "Sales" sheet with a header row
+//! let report = merge::concat(
+//!     &["jan.xlsx", "feb.xlsx", "mar.xlsx"],
+//!     "q1.xlsx",
+//!     MergeOptions::by_sheet_name().dedupe_headers(true),
+//! )?;
+//! println!("{} rows", report.total_rows());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::error::{ExcelError, Result};
+use crate::streaming_reader::StreamingReader;
+use crate::types::{CellFormat, CellStyle, CellValue, WriteReport};
+use crate::writer::{ExcelWriter, ExcelWriterBuilder};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Longest sheet name Excel accepts
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// How input sheets map to output sheets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeMode {
+    /// Sheets with the same name are appended into one output sheet, in
+    /// input order; names are compared ignoring case, as Excel does, and
+    /// the output sheet takes the first spelling
+    #[default]
+    BySheetName,
+    /// Every input sheet becomes its own output sheet, named after the file
+    /// (`jan`) or file and sheet (`jan Sales`) when the file has several
+    AsSeparateSheets,
+}
+
+/// Options for [`concat`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeOptions {
+    pub mode: MergeMode,
+    /// In [`MergeMode::BySheetName`], drop the first row of a sheet when it
+    /// repeats the header already written for that sheet
+    pub dedupe_headers: bool,
+}
+
+impl MergeOptions {
+    /// Append sheets with the same name
+    pub fn by_sheet_name() -> Self {
+        MergeOptions {
+            mode: MergeMode::BySheetName,
+            dedupe_headers: false,
+        }
+    }
+
+    /// Copy every input sheet to a sheet of its own
+    pub fn as_separate_sheets() -> Self {
+        MergeOptions {
+            mode: MergeMode::AsSeparateSheets,
+            dedupe_headers: false,
+        }
+    }
+
+    /// Drop header rows repeated by later inputs
+    pub fn dedupe_headers(mut self, dedupe: bool) -> Self {
+        self.dedupe_headers = dedupe;
+        self
+    }
+}
+
+/// Merge `inputs` into a new workbook at `output`
+///
+/// Output sheets are written in the order they first appear in the inputs.
+/// Returns the writer's report, whose `per_sheet_rows` counts the rows of
+/// every output sheet.
+pub fn concat<P, Q>(inputs: &[P], output: Q, options: MergeOptions) -> Result<WriteReport>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    // (output sheet, [(input, input sheet)])
+    let mut plan: Vec<(String, Vec<(usize, String)>)> = Vec::new();
+    for (input, path) in inputs.iter().enumerate() {
+        let names = StreamingReader::sheet_names_of(path)?;
+        for sheet in &names {
+            match options.mode {
+                MergeMode::BySheetName => match plan
+                    .iter_mut()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(sheet))
+                {
+                    Some((_, sources)) => sources.push((input, sheet.clone())),
+                    None => plan.push((sheet.clone(), vec![(input, sheet.clone())])),
+                },
+                MergeMode::AsSeparateSheets => {
+                    let stem = inputs[input]
+                        .as_ref()
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| format!("Input{}", input + 1));
+                    let name = if names.len() == 1 {
+                        stem
+                    } else {
+                        format!("{} {}", stem, sheet)
+                    };
+                    let name = unique_sheet_name(&name, plan.iter().map(|(n, _)| n.as_str()));
+                    plan.push((name, vec![(input, sheet.clone())]));
+                }
+            }
+        }
+    }
+    if plan.is_empty() {
+        return Err(ExcelError::InvalidState("No sheets to merge".to_string()));
+    }
+
+    let mut writer = ExcelWriterBuilder::new(output)
+        .with_sheet_name(&plan[0].0)
+        .build()?;
+
+    // Inputs are opened one at a time, when their rows are due
+    let mut open: Option<(usize, StreamingReader)> = None;
+    for (index, (name, sources)) in plan.iter().enumerate() {
+        if index > 0 {
+            writer.add_sheet(name)?;
+        }
+        let mut header: Option<Vec<CellValue>> = None;
+        for (input, sheet) in sources {
+            let reader = match open {
+                Some((current, ref mut reader)) if current == *input => reader,
+                _ => {
+                    open = None;
+                    &mut open
+                        .insert((*input, StreamingReader::open(&inputs[*input])?))
+                        .1
+                }
+            };
+            let mut first = true;
+            for row in reader.rows_with_style(sheet)? {
+                let row = row?;
+                let values: Vec<CellValue> = row.iter().map(|c| c.value.clone()).collect();
+                if first {
+                    first = false;
+                    if options.dedupe_headers && options.mode == MergeMode::BySheetName {
+                        match &header {
+                            Some(seen) if *seen == values => continue,
+                            Some(_) => {}
+                            None => header = Some(values.clone()),
+                        }
+                    }
+                }
+
+                let cells: Vec<(CellValue, CellStyle)> = row
+                    .into_iter()
+                    .map(|cell| {
+                        let style = StyleMap::keep_number_format(&cell.format, &mut writer);
+                        (cell.value, style)
+                    })
+                    .collect();
+                writer.write_row_styled(&cells)?;
+            }
+        }
+    }
+
+    writer.save()
+}
+
+type StyleRule = dyn Fn(&CellFormat) -> Option<CellStyle> + Send + Sync;
+
+/// Which output style each source cell format becomes, for [`copy_rows`]
+///
+/// A cell is looked up by its source
+/// [`style_index`](CellFormat::style_index) first, then by the rules in
+/// the order they were added. Cells nothing matches keep their number
+/// format and lose fonts and fills, as in [`concat`].
+///
+/// # Example
+///
+/// ```
+/// use excelstream::merge::StyleMap;
+/// use excelstream::types::CellStyle;
+///
+/// // Old brand: red fills and a bold header style at index 1
+/// let styles = StyleMap::new()
+///     .map_index(1, CellStyle::HeaderBold)
+///     .map_with(|format| {
+///         (format.fill_color.as_deref() == Some("FFFF0000")).then_some(CellStyle::HighlightGreen)
+///     });
+/// ```
+#[derive(Clone, Default)]
+pub struct StyleMap {
+    indices: HashMap<u32, CellStyle>,
+    rules: Vec<Arc<StyleRule>>,
+}
+
+impl std::fmt::Debug for StyleMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StyleMap")
+            .field("indices", &self.indices)
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+impl StyleMap {
+    /// Map keeping number formats only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give cells with source style `index` the style `style`
+    pub fn map_index(mut self, index: u32, style: CellStyle) -> Self {
+        self.indices.insert(index, style);
+        self
+    }
+
+    /// Add a rule choosing a style from a cell's source format; returning
+    /// None leaves the cell to the next rule
+    pub fn map_with<F>(mut self, rule: F) -> Self
+    where
+        F: Fn(&CellFormat) -> Option<CellStyle> + Send + Sync + 'static,
+    {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    // Output style for a cell with source format `format`
+    fn resolve(&self, format: &CellFormat, writer: &mut ExcelWriter) -> CellStyle {
+        if let Some(style) = self.indices.get(&format.style_index) {
+            return *style;
+        }
+        self.rules
+            .iter()
+            .find_map(|rule| rule(format))
+            .unwrap_or_else(|| Self::keep_number_format(format, writer))
+    }
+
+    // The source number format registered in `writer`, or the default style
+    fn keep_number_format(format: &CellFormat, writer: &mut ExcelWriter) -> CellStyle {
+        match format.num_fmt.as_deref() {
+            // Registering a code again returns the same style
+            Some(code) if format.num_fmt_id != 0 => writer.register_number_format(code),
+            _ => CellStyle::Default,
+        }
+    }
+}
+
+/// Stream the rows of `src_sheet` into sheet `dst_sheet` of `writer`,
+/// remapping styles with `styles`
+///
+/// Values are copied as read. `dst_sheet` is added to the writer unless it
+/// is already the current sheet, in which case the rows are appended.
+/// Each source style is resolved once. Returns the number of rows copied.
+///
+/// # Example
+///
+/// ```no_run
+/// use excelstream::merge::{copy_rows, StyleMap};
+/// use excelstream::streaming_reader::StreamingReader;
+/// use excelstream::types::CellStyle;
+/// use excelstream::ExcelWriter;
+///
+/// let mut reader = StreamingReader::open("old-brand.xlsx")?;
+/// let mut writer = ExcelWriter::new("new-brand.xlsx")?;
+/// let styles = StyleMap::new().map_with(|format| format.bold.then_some(CellStyle::HeaderBold));
+/// let names = reader.sheet_names();
+/// for (i, sheet) in names.iter().enumerate() {
+///     let dst = if i == 0 { "Sheet1" } else { sheet.as_str() };
+///     copy_rows(&mut reader, sheet, &mut writer, dst, &styles)?;
+/// }
+/// writer.save()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn copy_rows(
+    reader: &mut StreamingReader,
+    src_sheet: &str,
+    writer: &mut ExcelWriter,
+    dst_sheet: &str,
+    styles: &StyleMap,
+) -> Result<u32> {
+    if writer.current_sheet_name() != dst_sheet {
+        writer.add_sheet(dst_sheet)?;
+    }
+    let mut resolved: HashMap<u32, CellStyle> = HashMap::new();
+    let mut copied = 0;
+    for row in reader.rows_with_style(src_sheet)? {
+        let cells: Vec<(CellValue, CellStyle)> = row?
+            .into_iter()
+            .map(|cell| {
+                let style = *resolved
+                    .entry(cell.format.style_index)
+                    .or_insert_with(|| styles.resolve(&cell.format, writer));
+                (cell.value, style)
+            })
+            .collect();
+        writer.write_row_styled(&cells)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+// `name` cut to Excel's limit, with " (2)", " (3)"... added if already taken
+pub(crate) fn unique_sheet_name<'a>(
+    name: &str,
+    taken: impl Iterator<Item = &'a str> + Clone,
+) -> String {
+    let truncate = |s: &str, len: usize| s.chars().take(len).collect::<String>();
+    let mut candidate = truncate(name, MAX_SHEET_NAME_LEN);
+    let mut n = 2;
+    while taken.clone().any(|t| t.eq_ignore_ascii_case(&candidate)) {
+        let suffix = format!(" ({})", n);
+        candidate = truncate(name, MAX_SHEET_NAME_LEN - suffix.len()) + &suffix;
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExcelReader, ExcelWriter};
+
+    fn write_input(path: &Path, sheets: &[(&str, &[&[&str]])]) {
+        let mut writer = ExcelWriterBuilder::new(path)
+            .with_sheet_name(sheets[0].0)
+            .build()
+            .unwrap();
+        for (index, (name, rows)) in sheets.iter().enumerate() {
+            if index > 0 {
+                writer.add_sheet(name).unwrap();
+            }
+            for row in rows.iter() {
+                writer.write_row(row.iter()).unwrap();
+            }
+        }
+        writer.save().unwrap();
+    }
+
+    fn read_rows(path: &Path, sheet: &str) -> Vec<Vec<String>> {
+        let mut reader = ExcelReader::open(path).unwrap();
+        reader
+            .rows(sheet)
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect()
+    }
+
+    #[test]
+    fn test_unique_sheet_name() {
+        let taken = ["jan", "Sales (2)"];
+        assert_eq!(unique_sheet_name("feb", taken.iter().copied()), "feb");
+        assert_eq!(unique_sheet_name("JAN", taken.iter().copied()), "JAN (2)");
+        let long = "a".repeat(40);
+        assert_eq!(unique_sheet_name(&long, taken.iter().copied()).len(), 31);
+    }
+
+    #[test]
+    fn test_concat_by_sheet_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let jan = dir.path().join("jan.xlsx");
+        let feb = dir.path().join("feb.xlsx");
+        write_input(
+            &jan,
+            &[
+                ("Sales", &[&["Item", "Qty"], &["A", "1"]]),
+                ("Notes", &[&["n1"]]),
+            ],
+        );
+        write_input(
+            &feb,
+            &[("Sales", &[&["Item", "Qty"], &["B", "2"], &["C", "3"]])],
+        );
+
+        let output = dir.path().join("q1.xlsx");
+        let report = concat(
+            &[&jan, &feb],
+            &output,
+            MergeOptions::by_sheet_name().dedupe_headers(true),
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.per_sheet_rows,
+            vec![("Sales".to_string(), 4), ("Notes".to_string(), 1)]
+        );
+        assert_eq!(
+            read_rows(&output, "Sales"),
+            vec![
+                vec!["Item", "Qty"],
+                vec!["A", "1"],
+                vec!["B", "2"],
+                vec!["C", "3"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_concat_by_sheet_name_ignores_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let jan = dir.path().join("jan.xlsx");
+        let feb = dir.path().join("feb.xlsx");
+        write_input(&jan, &[("Sales", &[&["A"]])]);
+        write_input(&feb, &[("SALES", &[&["B"]])]);
+
+        let output = dir.path().join("q1.xlsx");
+        let report = concat(&[&jan, &feb], &output, MergeOptions::by_sheet_name()).unwrap();
+
+        assert_eq!(report.per_sheet_rows, vec![("Sales".to_string(), 2)]);
+        assert_eq!(read_rows(&output, "Sales"), vec![vec!["A"], vec!["B"]]);
+    }
+
+    #[test]
+    fn test_concat_as_separate_sheets() {
+        let dir = tempfile::tempdir().unwrap();
+        let jan = dir.path().join("jan.xlsx");
+        let feb = dir.path().join("feb.xlsx");
+        write_input(&jan, &[("Sales", &[&["A"]]), ("Notes", &[&["n1"]])]);
+        write_input(&feb, &[("Sales", &[&["B"]])]);
+
+        let output = dir.path().join("all.xlsx");
+        concat(&[&jan, &feb], &output, MergeOptions::as_separate_sheets()).unwrap();
+
+        let reader = ExcelReader::open(&output).unwrap();
+        assert_eq!(reader.sheet_names(), ["jan Sales", "jan Notes", "feb"]);
+        assert_eq!(read_rows(&output, "feb"), vec![vec!["B"]]);
+    }
+
+    #[test]
+    fn test_concat_keeps_number_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.xlsx");
+        let mut writer = ExcelWriter::new(&input).unwrap();
+        writer
+            .write_row_styled(&[(CellValue::Float(0.25), CellStyle::NumberPercentage)])
+            .unwrap();
+        writer.save().unwrap();
+
+        let output = dir.path().join("out.xlsx");
+        concat(&[&input], &output, MergeOptions::default()).unwrap();
+
+        let mut reader = StreamingReader::open(&output).unwrap();
+        let rows: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(rows[0][0].value, CellValue::Float(0.25));
+        assert_eq!(rows[0][0].format.num_fmt.as_deref(), Some("0%"));
+    }
+
+    #[test]
+    fn test_copy_rows_remaps_styles() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("old.xlsx");
+        let mut writer = ExcelWriter::new(&input).unwrap();
+        writer.write_header_bold(["Item", "Share"]).unwrap();
+        writer
+            .write_row_styled(&[
+                (CellValue::from("A"), CellStyle::HighlightRed),
+                (CellValue::Float(0.25), CellStyle::NumberPercentage),
+            ])
+            .unwrap();
+        writer.save().unwrap();
+
+        let output = dir.path().join("new.xlsx");
+        let mut reader = StreamingReader::open(&input).unwrap();
+        let mut writer = ExcelWriter::new(&output).unwrap();
+        writer.write_row(["Copied"]).unwrap();
+        let styles = StyleMap::new()
+            .map_index(CellStyle::HeaderBold.index(), CellStyle::TextItalic)
+            .map_with(|format| {
+                (format.fill_color.as_deref() == Some("FFFF0000"))
+                    .then_some(CellStyle::HighlightGreen)
+            });
+        let copied = copy_rows(&mut reader, "Sheet1", &mut writer, "Sheet1", &styles).unwrap();
+        assert_eq!(copied, 2);
+        copy_rows(
+            &mut reader,
+            "Sheet1",
+            &mut writer,
+            "Plain",
+            &StyleMap::new(),
+        )
+        .unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(&output).unwrap();
+        let rows: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1][0].value, CellValue::from("Item"));
+        assert!(rows[1][0].format.italic && !rows[1][0].format.bold);
+        assert_eq!(rows[2][0].format.fill_color.as_deref(), Some("FF00FF00"));
+        assert_eq!(rows[2][1].value, CellValue::Float(0.25));
+        assert_eq!(rows[2][1].format.num_fmt.as_deref(), Some("0%"));
+
+        let plain: Vec<_> = reader
+            .rows_with_style("Plain")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(!plain[0][0].format.bold);
+        assert_eq!(plain[1][0].format.fill_color, None);
+        assert_eq!(plain[1][1].format.num_fmt.as_deref(), Some("0%"));
+    }
+}
@@ -0,0 +1,745 @@
+//! Row transformations applied while writing
+//!
+//! A [`RowMiddleware`] sees every data row before it is written and can
+//! change cells in place or drop the row. Stack them on an
+//! [`ExcelWriter`](crate::ExcelWriter) with
+//! [`add_middleware`](crate::ExcelWriter::add_middleware); they run in the
+//! order they were added. Header rows written with `write_header_bold` are
+//! passed through unchanged.
+//!
+//! Built in: [`TrimWhitespace`], [`MaskColumns`], [`MaxLength`] and, with the
+//! `regex` feature, [`RedactPattern`]. Closures of the same signature work
+//! too. For PII, [`ExcelWriter::mask_column`](crate::ExcelWriter::mask_column)
+//! applies a [`Masker`] after all middleware and counts the masked cells in
+//! the [`WriteReport`](crate::WriteReport).
+//!
+//! The read side has [`ReadMiddleware`]: normalization steps set once on
+//! [`ReaderOptions`](crate::ReaderOptions) and applied to every row before a
+//! reader yields it, such as [`TrimWhitespace`], [`NullValues`],
+//! [`CoerceDates`] and [`RenameHeaders`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::middleware::{MaskColumns, TrimWhitespace};
+//! use excelstream::types::CellValue;
+//! use excelstream::ExcelWriter;
+//!
+//! let mut writer = ExcelWriter::new("customers.xlsx")?;
+//! writer.add_middleware(TrimWhitespace);
+//! writer.add_middleware(MaskColumns::new([2]).keep_last(4));
+//! // Skip test accounts
+//! writer.add_middleware(|row: &mut Vec<CellValue>| {
+//!     (row[0] != CellValue::String("test".to_string())).then_some(())
+//! });
+//!
+//! writer.write_header_bold(["Name", "Email", "Card"])?;
+//! writer.write_row(["  Alice ", "alice@example.com", "4111111111111111"])?;
+//! // -> "Alice", "alice@example.com", "************1111"
+//! writer.save()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::types::CellValue;
+use std::sync::Arc;
+
+/// Transformation applied to each row before it is written
+pub trait RowMiddleware: Send {
+    /// Change the row in place; return `None` to drop it
+    fn transform(&mut self, row: &mut Vec<CellValue>) -> Option<()>;
+}
+
+impl<F> RowMiddleware for F
+where
+    F: FnMut(&mut Vec<CellValue>) -> Option<()> + Send,
+{
+    fn transform(&mut self, row: &mut Vec<CellValue>) -> Option<()> {
+        self(row)
+    }
+}
+
+/// Normalization applied to each row a reader yields
+///
+/// Set on [`ReaderOptions`](crate::ReaderOptions) with
+/// [`with_middleware`](crate::ReaderOptions::with_middleware). Closures
+/// taking the 0-based row index and the row work too.
+pub trait ReadMiddleware: Send + Sync {
+    /// Change the row in place; return `None` to skip it
+    fn normalize(&self, row_index: u32, row: &mut Vec<CellValue>) -> Option<()>;
+}
+
+impl<F> ReadMiddleware for F
+where
+    F: Fn(u32, &mut Vec<CellValue>) -> Option<()> + Send + Sync,
+{
+    fn normalize(&self, row_index: u32, row: &mut Vec<CellValue>) -> Option<()> {
+        self(row_index, row)
+    }
+}
+
+/// Read middleware in the order it runs; cheap to clone
+#[derive(Clone, Default)]
+pub struct ReadMiddlewareStack(Vec<Arc<dyn ReadMiddleware>>);
+
+impl std::fmt::Debug for ReadMiddlewareStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReadMiddlewareStack({} steps)", self.0.len())
+    }
+}
+
+impl ReadMiddlewareStack {
+    /// Add a step at the end
+    pub fn push<M: ReadMiddleware + 'static>(&mut self, middleware: M) {
+        self.0.push(Arc::new(middleware));
+    }
+
+    /// Number of steps
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no steps
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn apply(&self, row_index: u32, row: &mut Vec<CellValue>) -> Option<()> {
+        for middleware in &self.0 {
+            middleware.normalize(row_index, row)?;
+        }
+        Some(())
+    }
+}
+
+/// Trim leading and trailing whitespace from text cells
+///
+/// Works on both the write and the read side.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimWhitespace;
+
+impl TrimWhitespace {
+    fn trim(row: &mut [CellValue]) {
+        for cell in row.iter_mut() {
+            if let CellValue::String(s) = cell {
+                let trimmed = s.trim();
+                if trimmed.len() != s.len() {
+                    *s = trimmed.to_string();
+                }
+            }
+        }
+    }
+}
+
+impl RowMiddleware for TrimWhitespace {
+    fn transform(&mut self, row: &mut Vec<CellValue>) -> Option<()> {
+        Self::trim(row);
+        Some(())
+    }
+}
+
+impl ReadMiddleware for TrimWhitespace {
+    fn normalize(&self, _row_index: u32, row: &mut Vec<CellValue>) -> Option<()> {
+        Self::trim(row);
+        Some(())
+    }
+}
+
+/// Read text cells that stand for "no value" (blank, `NULL`, `N/A`, ...) as
+/// empty cells
+#[derive(Debug, Clone)]
+pub struct NullValues {
+    markers: Vec<String>,
+}
+
+impl Default for NullValues {
+    /// Blank text plus `NULL`, `N/A`, `NA`, `#N/A`, `None`, `nil` and `-`
+    fn default() -> Self {
+        NullValues::new(["NULL", "N/A", "NA", "#N/A", "None", "nil", "-"])
+    }
+}
+
+impl NullValues {
+    /// Treat blank text and these markers (compared ignoring case and
+    /// surrounding whitespace) as empty
+    pub fn new<I, S>(markers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        NullValues {
+            markers: markers
+                .into_iter()
+                .map(|m| m.as_ref().trim().to_lowercase())
+                .collect(),
+        }
+    }
+}
+
+impl ReadMiddleware for NullValues {
+    fn normalize(&self, _row_index: u32, row: &mut Vec<CellValue>) -> Option<()> {
+        for cell in row.iter_mut() {
+            if let CellValue::String(s) = cell {
+                let text = s.trim();
+                if text.is_empty() || self.markers.iter().any(|m| m.eq_ignore_ascii_case(text)) {
+                    *cell = CellValue::Empty;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Rewrite dates in some columns as ISO text (`2024-01-31`,
+/// `2024-01-31 08:30:00`), the form the reader uses for date cells
+///
+/// Text is parsed with the given `chrono` formats, first match wins; numbers
+/// are taken as Excel date serials. Values that don't parse are left alone.
+#[derive(Debug, Clone)]
+pub struct CoerceDates {
+    columns: Vec<usize>,
+    formats: Vec<String>,
+}
+
+impl CoerceDates {
+    /// Coerce these columns (0-based), trying ISO dates and date-times,
+    /// `2024/01/31` and `31.01.2024`
+    pub fn new<I: IntoIterator<Item = usize>>(columns: I) -> Self {
+        CoerceDates {
+            columns: columns.into_iter().collect(),
+            formats: [
+                "%Y-%m-%d",
+                "%Y-%m-%d %H:%M:%S",
+                "%Y-%m-%dT%H:%M:%S",
+                "%Y/%m/%d",
+                "%d.%m.%Y",
+            ]
+            .iter()
+            .map(|f| f.to_string())
+            .collect(),
+        }
+    }
+
+    /// Replace the formats tried, e.g. `["%d/%m/%Y"]` for day-first dates
+    pub fn with_formats<I, S>(mut self, formats: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.formats = formats
+            .into_iter()
+            .map(|f| f.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    fn parse(&self, text: &str) -> Option<String> {
+        use chrono::{NaiveDate, NaiveDateTime};
+
+        let text = text.trim();
+        self.formats.iter().find_map(|format| {
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+                Some(datetime.format("%Y-%m-%d %H:%M:%S").to_string())
+            } else {
+                NaiveDate::parse_from_str(text, format)
+                    .ok()
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+            }
+        })
+    }
+}
+
+impl ReadMiddleware for CoerceDates {
+    fn normalize(&self, _row_index: u32, row: &mut Vec<CellValue>) -> Option<()> {
+        for &col in &self.columns {
+            let Some(cell) = row.get_mut(col) else {
+                continue;
+            };
+            let coerced = match cell {
+                CellValue::String(s) => self.parse(s),
+                CellValue::Float(serial) | CellValue::DateTime(serial) => {
+                    Some(crate::streaming_reader::parse_excel_date(*serial))
+                }
+                CellValue::Int(serial) => {
+                    Some(crate::streaming_reader::parse_excel_date(*serial as f64))
+                }
+                _ => None,
+            };
+            if let Some(text) = coerced {
+                *cell = CellValue::String(text);
+            }
+        }
+        Some(())
+    }
+}
+
+/// Rename columns in the header row, so sources with different headings
+/// read the same
+#[derive(Debug, Clone)]
+pub struct RenameHeaders {
+    header_row: u32,
+    names: Vec<(String, String)>,
+}
+
+impl RenameHeaders {
+    /// Rename header cells matching a `from` name (ignoring case and
+    /// surrounding whitespace) to `to`, in the first row
+    pub fn new<I, A, B>(names: I) -> Self
+    where
+        I: IntoIterator<Item = (A, B)>,
+        A: AsRef<str>,
+        B: AsRef<str>,
+    {
+        RenameHeaders {
+            header_row: 0,
+            names: names
+                .into_iter()
+                .map(|(from, to)| (from.as_ref().trim().to_string(), to.as_ref().to_string()))
+                .collect(),
+        }
+    }
+
+    /// Header row (0-based) when the sheet has a title above it
+    pub fn header_row(mut self, row: u32) -> Self {
+        self.header_row = row;
+        self
+    }
+}
+
+impl ReadMiddleware for RenameHeaders {
+    fn normalize(&self, row_index: u32, row: &mut Vec<CellValue>) -> Option<()> {
+        if row_index != self.header_row {
+            return Some(());
+        }
+        for cell in row.iter_mut() {
+            if let CellValue::String(s) = cell {
+                let name = s.trim();
+                if let Some((_, to)) = self
+                    .names
+                    .iter()
+                    .find(|(from, _)| from.eq_ignore_ascii_case(name))
+                {
+                    *s = to.clone();
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Replace the characters of some columns with a mask, e.g. for card numbers
+/// or national IDs
+///
+/// Non-empty cells of the columns become text; empty cells stay empty.
+#[derive(Debug, Clone)]
+pub struct MaskColumns {
+    columns: Vec<usize>,
+    mask: char,
+    keep_last: usize,
+}
+
+impl MaskColumns {
+    /// Mask every character of these columns (0-based) with `*`
+    pub fn new<I: IntoIterator<Item = usize>>(columns: I) -> Self {
+        MaskColumns {
+            columns: columns.into_iter().collect(),
+            mask: '*',
+            keep_last: 0,
+        }
+    }
+
+    /// Leave the last `n` characters readable
+    pub fn keep_last(mut self, n: usize) -> Self {
+        self.keep_last = n;
+        self
+    }
+
+    /// Use another mask character
+    pub fn with_mask(mut self, mask: char) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    fn mask(&self, text: &str) -> String {
+        let len = text.chars().count();
+        let hidden = len.saturating_sub(self.keep_last);
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| if i < hidden { self.mask } else { c })
+            .collect()
+    }
+}
+
+impl RowMiddleware for MaskColumns {
+    fn transform(&mut self, row: &mut Vec<CellValue>) -> Option<()> {
+        for &col in &self.columns {
+            if let Some(cell) = row.get_mut(col) {
+                if !cell.is_empty() {
+                    *cell = CellValue::String(self.mask(&cell.as_string()));
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Masking rule for a PII column, see
+/// [`ExcelWriter::mask_column`](crate::ExcelWriter::mask_column)
+///
+/// Separators (spaces, dashes, brackets) are kept so the masked value still
+/// looks like what it was.
+///
+/// ```
+/// use excelstream::middleware::Masker;
+///
+/// assert_eq!(Masker::Email.mask("alice@example.com"), "a****@example.com");
+/// assert_eq!(Masker::Phone.mask("+1 (555) 123-4567"), "+* (***) ***-4567");
+/// assert_eq!(Masker::TaxId.mask("123-45-6789"), "***-**-6789");
+/// assert_eq!(Masker::CreditCard.mask("4111 1111 1111 1111"), "**** **** **** 1111");
+/// assert_eq!(Masker::Full.mask("secret"), "******");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Masker {
+    /// Keep the first character of the local part and the domain
+    Email,
+    /// Keep the last 4 digits
+    Phone,
+    /// Keep the last 4 letters or digits (SSN, EIN, national IDs)
+    TaxId,
+    /// Keep the last 4 digits (PAN truncation)
+    CreditCard,
+    /// Mask every character
+    Full,
+}
+
+impl Masker {
+    /// Mask a value
+    pub fn mask(&self, text: &str) -> String {
+        match self {
+            Masker::Email => match text.split_once('@') {
+                Some((local, domain)) if !local.is_empty() => {
+                    let mut chars = local.chars();
+                    let first = chars.next().unwrap_or_default();
+                    let mut masked = String::with_capacity(text.len());
+                    masked.push(first);
+                    masked.extend(chars.map(|_| '*'));
+                    masked.push('@');
+                    masked.push_str(domain);
+                    masked
+                }
+                _ => Masker::Full.mask(text),
+            },
+            Masker::Phone | Masker::CreditCard => mask_keep_last(text, 4, |c| c.is_ascii_digit()),
+            Masker::TaxId => mask_keep_last(text, 4, char::is_alphanumeric),
+            Masker::Full => text.chars().map(|_| '*').collect(),
+        }
+    }
+}
+
+// Mask the characters matching `maskable`, except the last `keep` of them
+fn mask_keep_last(text: &str, keep: usize, maskable: impl Fn(char) -> bool) -> String {
+    let total = text.chars().filter(|&c| maskable(c)).count();
+    let mut seen = 0;
+    text.chars()
+        .map(|c| {
+            if !maskable(c) {
+                return c;
+            }
+            seen += 1;
+            if seen + keep > total {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+/// Cut text cells to at most `max` characters
+///
+/// Excel rejects cells over 32,767 characters; `MaxLength::default()` cuts
+/// at that limit.
+#[derive(Debug, Clone)]
+pub struct MaxLength {
+    max: usize,
+    suffix: String,
+}
+
+impl Default for MaxLength {
+    fn default() -> Self {
+        MaxLength::new(crate::types::MAX_TEXT_LEN)
+    }
+}
+
+impl MaxLength {
+    /// Cut text after `max` characters
+    pub fn new(max: usize) -> Self {
+        MaxLength {
+            max,
+            suffix: String::new(),
+        }
+    }
+
+    /// End cut text with `suffix` (e.g. "…"), counted within `max`
+    pub fn with_suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+}
+
+impl RowMiddleware for MaxLength {
+    fn transform(&mut self, row: &mut Vec<CellValue>) -> Option<()> {
+        for cell in row.iter_mut() {
+            if let CellValue::String(s) = cell {
+                if let Some((cut, _)) = s.char_indices().nth(self.max) {
+                    let keep = self.max.saturating_sub(self.suffix.chars().count());
+                    let end = s.char_indices().nth(keep).map_or(cut, |(i, _)| i);
+                    s.truncate(end);
+                    s.push_str(&self.suffix);
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Replace matches of a regular expression in text cells
+///
+/// Requires the `regex` feature.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct RedactPattern {
+    pattern: regex::Regex,
+    replacement: String,
+    columns: Option<Vec<usize>>,
+}
+
+#[cfg(feature = "regex")]
+impl RedactPattern {
+    /// Replace every match of `pattern` with `replacement` (`$1` style
+    /// references to capture groups work)
+    pub fn new(pattern: &str, replacement: &str) -> crate::error::Result<Self> {
+        let pattern = regex::Regex::new(pattern).map_err(|e| {
+            crate::error::ExcelError::InvalidFormat(format!("Invalid redact pattern: {}", e))
+        })?;
+        Ok(RedactPattern {
+            pattern,
+            replacement: replacement.to_string(),
+            columns: None,
+        })
+    }
+
+    /// Only look at these columns (0-based) instead of every text cell
+    pub fn in_columns<I: IntoIterator<Item = usize>>(mut self, columns: I) -> Self {
+        self.columns = Some(columns.into_iter().collect());
+        self
+    }
+}
+
+#[cfg(feature = "regex")]
+impl RowMiddleware for RedactPattern {
+    fn transform(&mut self, row: &mut Vec<CellValue>) -> Option<()> {
+        for (col, cell) in row.iter_mut().enumerate() {
+            if self.columns.as_ref().is_some_and(|c| !c.contains(&col)) {
+                continue;
+            }
+            if let CellValue::String(s) = cell {
+                // Borrowed means nothing matched
+                let redacted = match self.pattern.replace_all(s, self.replacement.as_str()) {
+                    std::borrow::Cow::Owned(redacted) => Some(redacted),
+                    std::borrow::Cow::Borrowed(_) => None,
+                };
+                if let Some(redacted) = redacted {
+                    *s = redacted;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<CellValue> {
+        values
+            .iter()
+            .map(|v| CellValue::String(v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_builtin_middleware() {
+        let mut row = strings(&[" a ", "b\t"]);
+        TrimWhitespace.transform(&mut row).unwrap();
+        assert_eq!(row, strings(&["a", "b"]));
+
+        let mut row = vec![
+            CellValue::Int(4111111111111111),
+            CellValue::String("Nguyễn".to_string()),
+            CellValue::Empty,
+        ];
+        MaskColumns::new([0, 1, 2])
+            .keep_last(4)
+            .transform(&mut row)
+            .unwrap();
+        assert_eq!(row[0], CellValue::String("************1111".to_string()));
+        assert_eq!(row[1], CellValue::String("**uyễn".to_string()));
+        assert_eq!(row[2], CellValue::Empty);
+
+        let mut row = strings(&["abcdefgh", "abc", "ééééé"]);
+        MaxLength::new(5)
+            .with_suffix("…")
+            .transform(&mut row)
+            .unwrap();
+        assert_eq!(row, strings(&["abcd…", "abc", "ééééé"]));
+        MaxLength::new(2).transform(&mut row).unwrap();
+        assert_eq!(row, strings(&["ab", "ab", "éé"]));
+    }
+
+    #[test]
+    fn test_read_middleware() {
+        let mut row = strings(&[" NULL ", "", "n/a", "x"]);
+        NullValues::default().normalize(1, &mut row).unwrap();
+        assert_eq!(
+            row,
+            vec![
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::String("x".to_string())
+            ]
+        );
+
+        let dates = CoerceDates::new([0, 1, 2, 3]).with_formats(["%d/%m/%Y", "%Y-%m-%d %H:%M"]);
+        let mut row = vec![
+            CellValue::String("31/01/2024".to_string()),
+            CellValue::String("2024-01-31 08:30".to_string()),
+            CellValue::Float(45217.5),
+            CellValue::String("soon".to_string()),
+        ];
+        dates.normalize(1, &mut row).unwrap();
+        assert_eq!(
+            row,
+            strings(&[
+                "2024-01-31",
+                "2024-01-31 08:30:00",
+                "2023-10-18 12:00:00",
+                "soon"
+            ])
+        );
+
+        let rename = RenameHeaders::new([("Cust Name", "customer"), ("E-mail", "email")]);
+        let mut header = strings(&["cust name ", "E-MAIL", "id"]);
+        rename.normalize(0, &mut header).unwrap();
+        assert_eq!(header, strings(&["customer", "email", "id"]));
+        let mut data = strings(&["Cust Name"]);
+        rename.normalize(1, &mut data).unwrap();
+        assert_eq!(data, strings(&["Cust Name"]));
+    }
+
+    #[test]
+    fn test_writer_applies_middleware() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer.add_middleware(TrimWhitespace);
+        writer.add_middleware(|row: &mut Vec<CellValue>| (!row[0].is_empty()).then_some(()));
+        writer.write_header_bold([" Name "]).unwrap();
+        writer.write_row([" Alice "]).unwrap();
+        writer.write_row_typed(&[CellValue::Empty]).unwrap();
+        writer.write_row_typed(&strings(&["Bob  "])).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(rows, vec![vec![" Name "], vec!["Alice"], vec!["Bob"]]);
+    }
+
+    #[test]
+    fn test_maskers() {
+        assert_eq!(Masker::Email.mask("bob@x.org"), "b**@x.org");
+        assert_eq!(Masker::Email.mask("no-at-sign"), "**********");
+        assert_eq!(Masker::Email.mask("@x.org"), "******");
+        assert_eq!(Masker::Phone.mask("0912 345 678"), "**** **5 678");
+        assert_eq!(Masker::TaxId.mask("AB12"), "AB12");
+        assert_eq!(
+            Masker::CreditCard.mask("4111111111111111"),
+            "************1111"
+        );
+    }
+
+    #[test]
+    fn test_writer_masks_columns() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer.add_middleware(TrimWhitespace);
+        writer.mask_column(1, Masker::Full);
+        writer.mask_column(1, Masker::Email);
+        writer.mask_column(0, Masker::TaxId);
+        writer.write_header(["Tax ID", "Email"]).unwrap();
+        writer
+            .write_row([" 123-45-6789 ", "ann@example.com"])
+            .unwrap();
+        writer
+            .write_row_typed(&[CellValue::Int(987654321), CellValue::Empty])
+            .unwrap();
+        let report = writer.save().unwrap();
+        assert_eq!(report.masked_cells, vec![(0, 2), (1, 1)]);
+        assert_eq!(report.total_masked_cells(), 3);
+
+        let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Tax ID", "Email"],
+                vec!["***-**-6789", "a**@example.com"],
+                vec!["*****4321"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reader_applies_middleware() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(temp.path()).unwrap();
+        writer.write_row(["Cust Name", "Joined"]).unwrap();
+        writer.write_row([" Alice ", "31/01/2024"]).unwrap();
+        writer.write_row(["skip", "N/A"]).unwrap();
+        writer.write_row(["Bob", "NULL"]).unwrap();
+        writer.save().unwrap();
+
+        let options = crate::ReaderOptions::new()
+            .with_middleware(TrimWhitespace)
+            .with_middleware(NullValues::default())
+            .with_middleware(CoerceDates::new([1]).with_formats(["%d/%m/%Y"]))
+            .with_middleware(RenameHeaders::new([("cust name", "customer")]))
+            .with_middleware(|_: u32, row: &mut Vec<CellValue>| {
+                (row[0] != CellValue::String("skip".to_string())).then_some(())
+            });
+        let mut reader =
+            crate::streaming_reader::StreamingReader::open_with_options(temp.path(), options)
+                .unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["customer", "Joined"],
+                vec!["Alice", "2024-01-31"],
+                vec!["Bob", ""]
+            ]
+        );
+    }
+}
@@ -0,0 +1,708 @@
+//! Number format code helpers
+//!
+//! Builds Excel number format codes for currencies, thousands separators and
+//! negative-number styles, so callers don't have to hand-write format strings.
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::numfmt::{Currency, NegativeStyle, NumberFormat};
+//!
+//! assert_eq!(Currency::VND.format_code(), "#,##0 [$₫-42A]");
+//!
+//! let code = NumberFormat::currency(Currency::USD)
+//!     .negative_style(NegativeStyle::RedParentheses)
+//!     .build();
+//! assert_eq!(code, "[$$-409]#,##0.00;[Red]([$$-409]#,##0.00)");
+//! ```
+
+use crate::types::CellValue;
+
+/// Currencies with built-in format codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Currency {
+    /// US Dollar ($1,234.56)
+    USD,
+    /// Euro (1.234,56 €)
+    EUR,
+    /// British Pound (£1,234.56)
+    GBP,
+    /// Japanese Yen (¥1,235)
+    JPY,
+    /// Chinese Yuan (¥1,234.56)
+    CNY,
+    /// Vietnamese Dong (1,235 ₫)
+    VND,
+}
+
+impl Currency {
+    /// All supported currencies
+    pub const ALL: [Currency; 6] = [
+        Currency::USD,
+        Currency::EUR,
+        Currency::GBP,
+        Currency::JPY,
+        Currency::CNY,
+        Currency::VND,
+    ];
+
+    /// Look up a currency by ISO 4217 code (case-insensitive)
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.code().eq_ignore_ascii_case(code))
+    }
+
+    /// ISO 4217 code (e.g. "USD")
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::USD => "USD",
+            Currency::EUR => "EUR",
+            Currency::GBP => "GBP",
+            Currency::JPY => "JPY",
+            Currency::CNY => "CNY",
+            Currency::VND => "VND",
+        }
+    }
+
+    /// Currency symbol (e.g. "$")
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::USD => "$",
+            Currency::EUR => "€",
+            Currency::GBP => "£",
+            Currency::JPY | Currency::CNY => "¥",
+            Currency::VND => "₫",
+        }
+    }
+
+    /// Number of minor-unit digits normally displayed
+    pub fn decimals(&self) -> u8 {
+        match self {
+            Currency::JPY | Currency::VND => 0,
+            _ => 2,
+        }
+    }
+
+    /// Default format code for this currency
+    pub fn format_code(&self) -> String {
+        NumberFormat::currency(*self).build()
+    }
+
+    // Windows locale id used in the `[$symbol-LCID]` tag so Excel renders
+    // the symbol the same way regardless of the viewer's regional settings
+    fn locale_id(&self) -> &'static str {
+        match self {
+            Currency::USD => "409",
+            Currency::EUR => "407",
+            Currency::GBP => "809",
+            Currency::JPY => "411",
+            Currency::CNY => "804",
+            Currency::VND => "42A",
+        }
+    }
+
+    fn symbol_first(&self) -> bool {
+        !matches!(self, Currency::EUR | Currency::VND)
+    }
+}
+
+/// How negative numbers are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NegativeStyle {
+    /// Leading minus sign: -1,234.56
+    #[default]
+    Minus,
+    /// Parentheses: (1,234.56)
+    Parentheses,
+    /// Red with minus sign
+    Red,
+    /// Red with parentheses
+    RedParentheses,
+}
+
+/// Builder for Excel number format codes
+#[derive(Debug, Clone)]
+pub struct NumberFormat {
+    decimals: u8,
+    thousands: bool,
+    negative: NegativeStyle,
+    currency: Option<Currency>,
+}
+
+impl NumberFormat {
+    /// Plain number with the given number of decimal places (e.g. "0.00")
+    pub fn number(decimals: u8) -> Self {
+        NumberFormat {
+            decimals,
+            thousands: false,
+            negative: NegativeStyle::Minus,
+            currency: None,
+        }
+    }
+
+    /// Currency format using the currency's usual decimals and a thousands separator
+    pub fn currency(currency: Currency) -> Self {
+        NumberFormat {
+            decimals: currency.decimals(),
+            thousands: true,
+            negative: NegativeStyle::Minus,
+            currency: Some(currency),
+        }
+    }
+
+    /// Set the number of decimal places
+    pub fn decimals(mut self, decimals: u8) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Enable or disable the thousands separator
+    pub fn thousands_separator(mut self, enabled: bool) -> Self {
+        self.thousands = enabled;
+        self
+    }
+
+    /// Set how negative numbers are displayed
+    pub fn negative_style(mut self, style: NegativeStyle) -> Self {
+        self.negative = style;
+        self
+    }
+
+    /// Build the format code string
+    pub fn build(&self) -> String {
+        let mut number = String::from(if self.thousands { "#,##0" } else { "0" });
+        if self.decimals > 0 {
+            number.push('.');
+            number.extend(std::iter::repeat_n('0', self.decimals as usize));
+        }
+
+        let positive = match self.currency {
+            Some(c) => {
+                let tag = format!("[${}-{}]", c.symbol(), c.locale_id());
+                if c.symbol_first() {
+                    format!("{}{}", tag, number)
+                } else {
+                    format!("{} {}", number, tag)
+                }
+            }
+            None => number,
+        };
+
+        match self.negative {
+            NegativeStyle::Minus => positive,
+            NegativeStyle::Parentheses => format!("{};({})", positive, positive),
+            NegativeStyle::Red => format!("{};[Red]-{}", positive, positive),
+            NegativeStyle::RedParentheses => format!("{};[Red]({})", positive, positive),
+        }
+    }
+}
+
+/// Order of day, month and year in written dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateOrder {
+    /// 31.01.2024
+    DayMonthYear,
+    /// 01/31/2024
+    MonthDayYear,
+    /// 2024-01-31
+    YearMonthDay,
+}
+
+/// Regional number and date conventions
+///
+/// Drives type inference when reading
+/// ([`ReaderOptions::with_locale`](crate::ReaderOptions::with_locale)), the
+/// date formats of written workbooks
+/// ([`ExcelWriter::set_locale`](crate::ExcelWriter::set_locale)) and number
+/// text in CSV output
+/// ([`CsvWriter::locale`](crate::csv_writer::CsvWriter::locale)).
+///
+/// Numbers in xlsx files are always stored with a decimal point; Excel shows
+/// them with the viewer's separators, so the separators here only matter for
+/// text (string-only sheets, CSV).
+///
+/// # Example
+///
+/// ```
+/// use excelstream::numfmt::Locale;
+///
+/// let de = Locale::from_tag("de-DE").unwrap();
+/// assert_eq!(de.date_format_code(), "dd.mm.yyyy");
+/// assert_eq!(de.format_number(1234.5), "1234,5");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Locale {
+    /// BCP 47 tag, e.g. "de-DE"
+    pub tag: &'static str,
+    pub decimal_separator: char,
+    pub thousands_separator: char,
+    pub date_order: DateOrder,
+    /// Character between day, month and year
+    pub date_separator: char,
+}
+
+impl Locale {
+    /// English (United States): 1,234.5 and 01/31/2024
+    pub const EN_US: Locale = Locale {
+        tag: "en-US",
+        decimal_separator: '.',
+        thousands_separator: ',',
+        date_order: DateOrder::MonthDayYear,
+        date_separator: '/',
+    };
+    /// English (United Kingdom): 1,234.5 and 31/01/2024
+    pub const EN_GB: Locale = Locale {
+        tag: "en-GB",
+        decimal_separator: '.',
+        thousands_separator: ',',
+        date_order: DateOrder::DayMonthYear,
+        date_separator: '/',
+    };
+    /// German (Germany): 1.234,5 and 31.01.2024
+    pub const DE_DE: Locale = Locale {
+        tag: "de-DE",
+        decimal_separator: ',',
+        thousands_separator: '.',
+        date_order: DateOrder::DayMonthYear,
+        date_separator: '.',
+    };
+    /// French (France): 1 234,5 and 31/01/2024
+    pub const FR_FR: Locale = Locale {
+        tag: "fr-FR",
+        decimal_separator: ',',
+        thousands_separator: '\u{a0}',
+        date_order: DateOrder::DayMonthYear,
+        date_separator: '/',
+    };
+    /// Vietnamese (Vietnam): 1.234,5 and 31/01/2024
+    pub const VI_VN: Locale = Locale {
+        tag: "vi-VN",
+        decimal_separator: ',',
+        thousands_separator: '.',
+        date_order: DateOrder::DayMonthYear,
+        date_separator: '/',
+    };
+
+    /// All built-in locales
+    pub const ALL: [Locale; 5] = [
+        Locale::EN_US,
+        Locale::EN_GB,
+        Locale::DE_DE,
+        Locale::FR_FR,
+        Locale::VI_VN,
+    ];
+
+    /// Look up a built-in locale by tag (case-insensitive, `_` or `-`)
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.replace('_', "-");
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|l| l.tag.eq_ignore_ascii_case(&tag))
+    }
+
+    /// Excel format code for dates, e.g. "dd.mm.yyyy"
+    pub fn date_format_code(&self) -> String {
+        self.date_pattern("dd", "mm", "yyyy")
+    }
+
+    /// Excel format code for date-times, e.g. "dd.mm.yyyy hh:mm:ss"
+    pub fn datetime_format_code(&self) -> String {
+        format!("{} hh:mm:ss", self.date_format_code())
+    }
+
+    /// `chrono` format for dates, e.g. "%d.%m.%Y"
+    pub fn chrono_date_format(&self) -> String {
+        self.date_pattern("%d", "%m", "%Y")
+    }
+
+    fn date_pattern(&self, day: &str, month: &str, year: &str) -> String {
+        let sep = self.date_separator;
+        match self.date_order {
+            DateOrder::DayMonthYear => format!("{day}{sep}{month}{sep}{year}"),
+            DateOrder::MonthDayYear => format!("{month}{sep}{day}{sep}{year}"),
+            DateOrder::YearMonthDay => format!("{year}{sep}{month}{sep}{day}"),
+        }
+    }
+
+    /// Number as text with this locale's decimal separator, without grouping
+    pub fn format_number(&self, value: f64) -> String {
+        self.localize_number(&value.to_string())
+    }
+
+    // Swap the decimal point of plain number text for the locale's
+    pub(crate) fn localize_number(&self, text: &str) -> String {
+        if self.decimal_separator == '.' {
+            text.to_string()
+        } else {
+            text.replace('.', self.decimal_separator.encode_utf8(&mut [0; 4]))
+        }
+    }
+
+    /// Type inference rules for text written in this locale: its separators,
+    /// its date order and ISO dates
+    pub fn type_inference(&self) -> crate::infer::TypeInference {
+        let date = self.chrono_date_format();
+        let mut formats = crate::infer::TypeInference::default().date_formats;
+        formats.push(format!("{} %H:%M:%S", date));
+        formats.push(format!("{} %H:%M", date));
+        formats.push(date);
+        crate::infer::TypeInference::new()
+            .with_decimal_separator(self.decimal_separator)
+            .with_thousands_separator(Some(self.thousands_separator))
+            .with_date_formats(formats)
+    }
+}
+
+/// Format code for a built-in number format id (ids Excel doesn't store in
+/// styles.xml), or None if the id is not built in
+pub fn builtin_format_code(id: u32) -> Option<&'static str> {
+    let code = match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "mm-dd-yy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yy h:mm",
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mmss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// What a formatted number measures, see [`NormalizedValue`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NumberUnit {
+    /// Plain number
+    #[default]
+    Number,
+    /// Share shown as a percentage (0.12 shown as 12%)
+    Percent,
+    /// Amount of money, in [`NormalizedValue::currency`] when known
+    Currency,
+}
+
+/// A number with the unit its format (or its text) gives it
+///
+/// Returned by [`normalize`] and
+/// [`FormattedCell::normalized`](crate::types::FormattedCell::normalized),
+/// so importers get `0.12` and [`NumberUnit::Percent`] for a cell showing
+/// "12%", whether it holds the number with a `0%` format or the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedValue {
+    /// The number, percentages as fractions (12% is 0.12)
+    pub value: f64,
+    /// What the number measures
+    pub unit: NumberUnit,
+    /// ISO 4217 code of a known currency (e.g. "USD"), otherwise the
+    /// symbol as written; None unless the unit is currency
+    pub currency: Option<String>,
+}
+
+/// Normalize a cell value shown with number format `code`
+///
+/// Numbers keep their value and take the unit of the format's first
+/// section: a `%` makes it a percentage, a currency symbol or `[$...]` tag
+/// an amount of money. Text such as "12%", "$1,234.50", "(1,234.50)" or
+/// "1,234 ₫" is parsed with a decimal point and comma grouping. Other
+/// values, including dates, give None.
+///
+/// # Example
+///
+/// ```
+/// use excelstream::numfmt::{normalize, NumberUnit};
+/// use excelstream::types::CellValue;
+///
+/// let pct = normalize(&CellValue::Float(0.12), Some("0%")).unwrap();
+/// assert_eq!((pct.value, pct.unit), (0.12, NumberUnit::Percent));
+///
+/// let usd = normalize(&CellValue::from("$1,234.50"), None).unwrap();
+/// assert_eq!(usd.value, 1234.5);
+/// assert_eq!(usd.currency.as_deref(), Some("USD"));
+/// ```
+pub fn normalize(value: &CellValue, code: Option<&str>) -> Option<NormalizedValue> {
+    match value {
+        CellValue::Int(_) | CellValue::Float(_) | CellValue::Decimal(_) => {
+            let (unit, currency) = code.map(format_unit).unwrap_or_default();
+            Some(NormalizedValue {
+                value: value.as_f64()?,
+                unit,
+                currency,
+            })
+        }
+        CellValue::String(text) => parse_formatted_number(text),
+        _ => None,
+    }
+}
+
+// Whether the first section of a format code has an elapsed time token:
+// `[h]`, `[mm]`, `[s]` and so on, outside quotes
+pub(crate) fn is_elapsed_time(code: &str) -> bool {
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            '"' => {
+                chars.by_ref().take_while(|&c| c != '"').for_each(drop);
+            }
+            '\\' => {
+                chars.next();
+            }
+            '[' => {
+                let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let mut letters = tag.chars().map(|c| c.to_ascii_lowercase());
+                if let Some(first @ ('h' | 'm' | 's')) = letters.next() {
+                    if letters.all(|c| c == first) {
+                        return true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+// Unit of the first section of a format code
+fn format_unit(code: &str) -> (NumberUnit, Option<String>) {
+    let mut percent = false;
+    let mut currency = None;
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            '%' => percent = true,
+            '"' => {
+                let quoted: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                currency = currency.or_else(|| currency_code(quoted.trim(), None));
+            }
+            '\\' => {
+                let escaped = chars.next().map(String::from).unwrap_or_default();
+                currency = currency.or_else(|| currency_code(&escaped, None));
+            }
+            '[' => {
+                let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if let Some(tag) = tag.strip_prefix('$') {
+                    let (symbol, lcid) = match tag.split_once('-') {
+                        Some((symbol, lcid)) => (symbol, Some(lcid)),
+                        None => (tag, None),
+                    };
+                    currency = currency
+                        .or_else(|| currency_code(symbol.trim(), lcid))
+                        .or_else(|| (!symbol.trim().is_empty()).then(|| symbol.trim().to_string()));
+                }
+            }
+            _ => currency = currency.or_else(|| currency_code(c.encode_utf8(&mut [0; 4]), None)),
+        }
+    }
+    if percent {
+        (NumberUnit::Percent, None)
+    } else if currency.is_some() {
+        (NumberUnit::Currency, currency)
+    } else {
+        (NumberUnit::Number, None)
+    }
+}
+
+// ISO code for a currency symbol or code; `lcid` tells ¥ apart
+fn currency_code(symbol: &str, lcid: Option<&str>) -> Option<String> {
+    if symbol == "¥" && lcid.is_some_and(|id| id.eq_ignore_ascii_case("804")) {
+        return Some(Currency::CNY.code().to_string());
+    }
+    Currency::ALL
+        .iter()
+        .find(|c| c.symbol() == symbol)
+        .copied()
+        .or_else(|| Currency::from_code(symbol))
+        .map(|c| c.code().to_string())
+}
+
+// "12%", "-$1,234.50", "(1,234.50)", "1,234 ₫", "USD 5"
+fn parse_formatted_number(text: &str) -> Option<NormalizedValue> {
+    let mut rest = text.trim();
+    let mut negative = false;
+    if let Some(inner) = rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+        negative = true;
+        rest = inner.trim();
+    }
+    if let Some(unsigned) = rest.strip_prefix('-') {
+        negative = !negative;
+        rest = unsigned.trim_start();
+    }
+
+    let mut unit = NumberUnit::Number;
+    let mut currency = None;
+    if let Some(number) = rest.strip_suffix('%') {
+        unit = NumberUnit::Percent;
+        rest = number.trim_end();
+    } else {
+        for c in Currency::ALL {
+            for symbol in [c.symbol(), c.code()] {
+                let number = rest
+                    .strip_prefix(symbol)
+                    .or_else(|| rest.strip_suffix(symbol));
+                if let Some(number) = number {
+                    unit = NumberUnit::Currency;
+                    currency = currency_code(symbol, None);
+                    rest = number.trim();
+                }
+            }
+            if currency.is_some() {
+                break;
+            }
+        }
+        // "$-5"
+        if let Some(unsigned) = rest.strip_prefix('-').filter(|_| currency.is_some()) {
+            negative = !negative;
+            rest = unsigned.trim_start();
+        }
+    }
+
+    let digits = rest.replace(',', "");
+    if !digits.bytes().any(|b| b.is_ascii_digit())
+        || !digits.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+    {
+        return None;
+    }
+    let mut value: f64 = digits.parse().ok()?;
+    if negative {
+        value = -value;
+    }
+    if unit == NumberUnit::Percent {
+        value /= 100.0;
+    }
+    Some(NormalizedValue {
+        value,
+        unit,
+        currency,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_formats() {
+        assert_eq!(NumberFormat::number(0).build(), "0");
+        assert_eq!(NumberFormat::number(2).build(), "0.00");
+        assert_eq!(
+            NumberFormat::number(1).thousands_separator(true).build(),
+            "#,##0.0"
+        );
+        assert_eq!(
+            NumberFormat::number(0)
+                .negative_style(NegativeStyle::Parentheses)
+                .build(),
+            "0;(0)"
+        );
+    }
+
+    #[test]
+    fn test_locales() {
+        assert_eq!(Locale::from_tag("vi_vn"), Some(Locale::VI_VN));
+        assert_eq!(Locale::from_tag("xx-XX"), None);
+        assert_eq!(Locale::EN_US.date_format_code(), "mm/dd/yyyy");
+        assert_eq!(Locale::VI_VN.datetime_format_code(), "dd/mm/yyyy hh:mm:ss");
+        assert_eq!(Locale::EN_US.format_number(-0.5), "-0.5");
+        assert_eq!(Locale::FR_FR.format_number(-0.5), "-0,5");
+
+        let us = Locale::EN_US.type_inference();
+        assert_eq!(us.infer("1,234.5"), CellValue::Float(1234.5));
+        assert_eq!(us.infer("01/31/2024"), CellValue::DateTime(45322.0));
+        let vi = Locale::VI_VN.type_inference();
+        assert_eq!(vi.infer("1.234,5"), CellValue::Float(1234.5));
+        assert_eq!(vi.infer("31/01/2024 12:00"), CellValue::DateTime(45322.5));
+        assert_eq!(
+            Locale::FR_FR.type_inference().infer("1\u{a0}234,5"),
+            CellValue::Float(1234.5)
+        );
+    }
+
+    #[test]
+    fn test_currency_formats() {
+        assert_eq!(Currency::USD.format_code(), "[$$-409]#,##0.00");
+        assert_eq!(Currency::EUR.format_code(), "#,##0.00 [$€-407]");
+        assert_eq!(Currency::VND.format_code(), "#,##0 [$₫-42A]");
+        assert_eq!(Currency::from_code("vnd"), Some(Currency::VND));
+        assert_eq!(Currency::from_code("XYZ"), None);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let unit = |value: CellValue, code: &str| {
+            let n = normalize(&value, Some(code)).unwrap();
+            (n.value, n.unit, n.currency)
+        };
+        assert_eq!(
+            unit(CellValue::Float(0.125), "0.0%"),
+            (0.125, NumberUnit::Percent, None)
+        );
+        assert_eq!(
+            unit(CellValue::Int(5), r#""%" 0"#),
+            (5.0, NumberUnit::Number, None)
+        );
+        let usd = Some("USD".to_string());
+        assert_eq!(
+            unit(CellValue::Float(1.5), &Currency::USD.format_code()),
+            (1.5, NumberUnit::Currency, usd.clone())
+        );
+        assert_eq!(
+            unit(CellValue::Int(-2), "$#,##0_);($#,##0)"),
+            (-2.0, NumberUnit::Currency, usd.clone())
+        );
+        assert_eq!(
+            unit(CellValue::Int(3), "#,##0.00 [$¥-804]").2.as_deref(),
+            Some("CNY")
+        );
+        assert_eq!(
+            unit(CellValue::Int(3), "[$CHF] #,##0.00").2.as_deref(),
+            Some("CHF")
+        );
+        assert_eq!(unit(CellValue::Int(3), "General").1, NumberUnit::Number);
+        assert_eq!(normalize(&CellValue::DateTime(45322.0), Some("0%")), None);
+
+        let text = |s: &str| {
+            let n = normalize(&CellValue::from(s), None)?;
+            Some((n.value, n.unit, n.currency))
+        };
+        assert_eq!(text("12%"), Some((0.12, NumberUnit::Percent, None)));
+        assert_eq!(
+            text(" $1,234.50 "),
+            Some((1234.5, NumberUnit::Currency, usd.clone()))
+        );
+        assert_eq!(
+            text("(1,234.50)"),
+            Some((-1234.5, NumberUnit::Number, None))
+        );
+        assert_eq!(text("$-5"), Some((-5.0, NumberUnit::Currency, usd)));
+        assert_eq!(text("1,234 ₫").unwrap().2.as_deref(), Some("VND"));
+        assert_eq!(text("EUR 7").unwrap().1, NumberUnit::Currency);
+        assert_eq!(text("12 apples"), None);
+        assert_eq!(text("inf"), None);
+        assert_eq!(text("%"), None);
+    }
+}
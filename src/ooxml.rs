@@ -0,0 +1,83 @@
+//! OOXML namespaces in their transitional and strict forms
+//!
+//! Workbooks saved as "Strict Open XML Spreadsheet" use `purl.oclc.org`
+//! namespaces and relationship types in place of the
+//! `schemas.openxmlformats.org` ones. Package-level namespaces (content
+//! types, package relationships, core properties) are shared by both.
+
+/// SpreadsheetML main namespace (transitional)
+pub(crate) const MAIN_NS: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+/// SpreadsheetML main namespace (strict)
+pub(crate) const STRICT_MAIN_NS: &str = "http://purl.oclc.org/ooxml/spreadsheetml/main";
+/// Office document relationships namespace, also the base of relationship
+/// types (transitional)
+pub(crate) const RELATIONSHIPS_NS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+/// Office document relationships namespace (strict)
+pub(crate) const STRICT_RELATIONSHIPS_NS: &str =
+    "http://purl.oclc.org/ooxml/officeDocument/relationships";
+
+// (transitional, strict) pairs, most specific first since relationship
+// types start with the relationships namespace
+const STRICT_EQUIVALENTS: &[(&str, &str)] = &[
+    (
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties",
+        "http://purl.oclc.org/ooxml/officeDocument/relationships/extendedProperties",
+    ),
+    (
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships/custom-properties",
+        "http://purl.oclc.org/ooxml/officeDocument/relationships/customProperties",
+    ),
+    (RELATIONSHIPS_NS, STRICT_RELATIONSHIPS_NS),
+    (MAIN_NS, STRICT_MAIN_NS),
+    (
+        "http://schemas.openxmlformats.org/officeDocument/2006/extended-properties",
+        "http://purl.oclc.org/ooxml/officeDocument/extendedProperties",
+    ),
+    (
+        "http://schemas.openxmlformats.org/officeDocument/2006/custom-properties",
+        "http://purl.oclc.org/ooxml/officeDocument/customProperties",
+    ),
+    (
+        "http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes",
+        "http://purl.oclc.org/ooxml/officeDocument/docPropsVTypes",
+    ),
+    (
+        "http://schemas.openxmlformats.org/drawingml/2006/main",
+        "http://purl.oclc.org/ooxml/drawingml/main",
+    ),
+    (
+        "http://schemas.openxmlformats.org/drawingml/2006/spreadsheetDrawing",
+        "http://purl.oclc.org/ooxml/drawingml/spreadsheetDrawing",
+    ),
+];
+
+/// Rewrite transitional namespaces and relationship types in markup to
+/// their strict equivalents
+pub(crate) fn to_strict(xml: &str) -> String {
+    let mut xml = xml.to_string();
+    for (transitional, strict) in STRICT_EQUIVALENTS {
+        if xml.contains(transitional) {
+            xml = xml.replace(transitional, strict);
+        }
+    }
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_strict() {
+        let rels = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/></Relationships>"#;
+        assert_eq!(
+            to_strict(rels),
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://purl.oclc.org/ooxml/officeDocument/relationships/officeDocument" Target="xl/workbook.xml"/><Relationship Id="rId3" Type="http://purl.oclc.org/ooxml/officeDocument/relationships/extendedProperties" Target="docProps/app.xml"/></Relationships>"#
+        );
+        assert_eq!(
+            to_strict(&format!(r#"<sst xmlns="{}"/>"#, MAIN_NS)),
+            format!(r#"<sst xmlns="{}"/>"#, STRICT_MAIN_NS)
+        );
+    }
+}
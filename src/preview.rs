@@ -0,0 +1,388 @@
+//! HTML previews of a sheet range
+//!
+//! [`to_html`] renders a bounded range of a sheet as an HTML `<table>`,
+//! streamed as it is read: the [`HtmlPreview`] it returns yields the table
+//! one row at a time, so a web handler can send each chunk as it comes and
+//! stop reading the sheet once the range is done. Bold, italic, font and
+//! fill colors are kept as inline styles.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::preview::{self, PreviewOptions};
+//! use excelstream::ExcelReader;
+//!
+//! let mut reader = ExcelReader::open("upload.xlsx")?;
+//! let options = PreviewOptions::new().with_header(true);
+//! let html = preview::to_html(&mut reader, "Sheet1", "A1:H50", &options)?;
+//! let mut out = Vec::new();
+//! html.write_to(&mut out)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::error::{ExcelError, Result};
+use crate::reader::ExcelReader;
+use crate::streaming_reader::FormattedRowIterator;
+use crate::types::{CellFormat, CellRange, CellValue, FormattedCell};
+use std::io::Write;
+
+/// Options for [`to_html`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewOptions {
+    /// Most rows rendered, whatever the range (default: 200)
+    pub max_rows: u32,
+    /// Most columns rendered, whatever the range (default: 50)
+    pub max_cols: u32,
+    /// Render the first row of the range as `<th>` cells in a `<thead>`
+    /// (default: false)
+    pub header: bool,
+    /// Keep bold, italic, colors and right-align numbers with inline
+    /// styles (default: true)
+    pub styles: bool,
+    /// `class` attribute of the table (default: none)
+    pub table_class: Option<String>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        PreviewOptions {
+            max_rows: 200,
+            max_cols: 50,
+            header: false,
+            styles: true,
+            table_class: None,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Create options with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cap the rows and columns rendered
+    pub fn with_max_size(mut self, rows: u32, cols: u32) -> Self {
+        self.max_rows = rows;
+        self.max_cols = cols;
+        self
+    }
+
+    /// Render the first row as a header
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Keep or drop inline styles
+    pub fn with_styles(mut self, styles: bool) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    /// Set the table's `class` attribute
+    pub fn with_table_class(mut self, class: &str) -> Self {
+        self.table_class = Some(class.to_string());
+        self
+    }
+}
+
+/// Render `range` (e.g. "A1:H50") of `sheet` as an HTML table
+///
+/// The range is cut to [`max_rows`](PreviewOptions::max_rows) by
+/// [`max_cols`](PreviewOptions::max_cols). Rows missing from the sheet
+/// inside the range are rendered empty; the table ends at the sheet's last
+/// row if that comes first. Fails with [`ExcelError::InvalidCell`] for a
+/// malformed range.
+pub fn to_html<'r>(
+    reader: &'r mut ExcelReader,
+    sheet: &str,
+    range: &str,
+    options: &PreviewOptions,
+) -> Result<HtmlPreview<'r>> {
+    let mut range: CellRange = range.parse()?;
+    if options.max_rows == 0 || options.max_cols == 0 {
+        return Err(ExcelError::InvalidState(
+            "Preview must allow at least one row and column".to_string(),
+        ));
+    }
+    range.last_row = range
+        .last_row
+        .min(range.first_row.saturating_add(options.max_rows - 1));
+    range.last_col = range
+        .last_col
+        .min(range.first_col.saturating_add(options.max_cols - 1));
+
+    Ok(HtmlPreview {
+        rows: reader.rows_with_style(sheet)?,
+        range,
+        options: options.clone(),
+        next_row: range.first_row,
+        state: State::Start,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Rows,
+    Done,
+}
+
+/// HTML table of a sheet range, yielded in chunks, see [`to_html`]
+///
+/// The first chunk opens the table, each following one holds a row (with
+/// any empty rows before it) and the last closes the table.
+pub struct HtmlPreview<'r> {
+    rows: FormattedRowIterator<'r>,
+    range: CellRange,
+    options: PreviewOptions,
+    next_row: u32, // Next row of the range to render
+    state: State,
+}
+
+impl HtmlPreview<'_> {
+    /// Write the whole table to `out`, returning the bytes written
+    pub fn write_to<W: Write>(self, mut out: W) -> Result<u64> {
+        let mut written = 0;
+        for chunk in self {
+            let chunk = chunk?;
+            out.write_all(chunk.as_bytes())?;
+            written += chunk.len() as u64;
+        }
+        Ok(written)
+    }
+
+    // The closing tags, ending the iteration
+    fn finish(&mut self) -> String {
+        self.state = State::Done;
+        let mut html = String::new();
+        if self.options.header && self.next_row == self.range.first_row {
+            html.push_str("<thead></thead><tbody>");
+        }
+        html.push_str("</tbody></table>");
+        html
+    }
+
+    // Append `cells` (or an empty row) as the next row of the table
+    fn push_row(&mut self, html: &mut String, cells: &[FormattedCell]) {
+        let header = self.options.header && self.next_row == self.range.first_row;
+        let tag = if header { "th" } else { "td" };
+        if header {
+            html.push_str("<thead>");
+        }
+        html.push_str("<tr>");
+        for col in self.range.first_col..=self.range.last_col {
+            match cells.get(col as usize) {
+                Some(cell) if !matches!(cell.value, CellValue::Empty) => {
+                    html.push('<');
+                    html.push_str(tag);
+                    if self.options.styles {
+                        push_style(html, &cell.value, &cell.format);
+                    }
+                    html.push('>');
+                    push_escaped(html, &cell.value.to_string());
+                    html.push_str("</");
+                    html.push_str(tag);
+                    html.push('>');
+                }
+                _ => {
+                    html.push('<');
+                    html.push_str(tag);
+                    html.push_str("></");
+                    html.push_str(tag);
+                    html.push('>');
+                }
+            }
+        }
+        html.push_str("</tr>");
+        if header {
+            html.push_str("</thead><tbody>");
+        }
+        self.next_row += 1;
+    }
+}
+
+impl Iterator for HtmlPreview<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state {
+            State::Done => return None,
+            State::Start => {
+                self.state = State::Rows;
+                let mut html = String::from("<table");
+                if let Some(class) = &self.options.table_class {
+                    html.push_str(" class=\"");
+                    push_escaped(&mut html, class);
+                    html.push('"');
+                }
+                html.push('>');
+                if !self.options.header {
+                    html.push_str("<tbody>");
+                }
+                return Some(Ok(html));
+            }
+            State::Rows => {}
+        }
+
+        loop {
+            if self.next_row > self.range.last_row {
+                return Some(Ok(self.finish()));
+            }
+            let cells = match self.rows.next() {
+                Some(Ok(cells)) => cells,
+                Some(Err(e)) => {
+                    self.state = State::Done;
+                    return Some(Err(e));
+                }
+                None => return Some(Ok(self.finish())),
+            };
+            let index = self.rows.row_index();
+            if index < self.range.first_row {
+                continue;
+            }
+
+            let mut html = String::new();
+            while self.next_row < index.min(self.range.last_row + 1) {
+                self.push_row(&mut html, &[]);
+            }
+            if index <= self.range.last_row {
+                self.push_row(&mut html, &cells);
+            }
+            return Some(Ok(html));
+        }
+    }
+}
+
+// Inline style for a cell's formatting, if it has any
+fn push_style(html: &mut String, value: &CellValue, format: &CellFormat) {
+    let mut style = String::new();
+    if format.bold {
+        style.push_str("font-weight:bold;");
+    }
+    if format.italic {
+        style.push_str("font-style:italic;");
+    }
+    if let Some(color) = format.font_color.as_deref().and_then(css_color) {
+        style.push_str("color:");
+        style.push_str(&color);
+        style.push(';');
+    }
+    if let Some(color) = format.fill_color.as_deref().and_then(css_color) {
+        style.push_str("background-color:");
+        style.push_str(&color);
+        style.push(';');
+    }
+    if matches!(
+        value,
+        CellValue::Int(_)
+            | CellValue::Float(_)
+            | CellValue::Decimal(_)
+            | CellValue::DateTime(_)
+            | CellValue::DateTimeTz(_)
+            | CellValue::Duration(_)
+            | CellValue::Percent(_)
+    ) {
+        style.push_str("text-align:right;");
+    }
+    if !style.is_empty() {
+        html.push_str(" style=\"");
+        html.push_str(&style);
+        html.push('"');
+    }
+}
+
+// "#RRGGBB" from an ARGB or RGB hex color
+fn css_color(argb: &str) -> Option<String> {
+    let rgb = argb.get(argb.len().checked_sub(6)?..)?;
+    rgb.bytes()
+        .all(|b| b.is_ascii_hexdigit())
+        .then(|| format!("#{}", rgb))
+}
+
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CellStyle;
+    use crate::ExcelWriter;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_to_html() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer
+            .write_header_bold(["Name", "Amount", "Note"])
+            .unwrap();
+        writer
+            .write_row_typed(&[
+                CellValue::String("<Alice>".to_string()),
+                CellValue::Int(30),
+                CellValue::String("a & b".to_string()),
+            ])
+            .unwrap();
+        writer.write_row(["", "", ""]).unwrap();
+        writer
+            .write_row_styled(&[
+                (CellValue::String("Bob".to_string()), CellStyle::TextItalic),
+                (CellValue::Float(2.5), CellStyle::Default),
+            ])
+            .unwrap();
+        writer.write_row(["past", "the", "range"]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = ExcelReader::open(temp.path()).unwrap();
+        let options = PreviewOptions::new()
+            .with_header(true)
+            .with_table_class("grid");
+        let chunks: Vec<String> = to_html(&mut reader, "Sheet1", "A1:B4", &options)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunks.first().unwrap(), "<table class=\"grid\">");
+        assert_eq!(chunks.last().unwrap(), "</tbody></table>");
+        let html = chunks.concat();
+        assert!(html.starts_with(
+            "<table class=\"grid\"><thead><tr><th style=\"font-weight:bold;\">Name</th>"
+        ));
+        assert!(
+            html.contains("<tr><td>&lt;Alice&gt;</td><td style=\"text-align:right;\">30</td></tr>")
+        );
+        assert!(html.contains("<tr><td></td><td></td></tr>"));
+        assert!(html.contains("<td style=\"font-style:italic;\">Bob</td>"));
+        assert!(!html.contains("a &amp; b") && !html.contains("past"));
+        assert_eq!(html.matches("<tr>").count(), 4);
+
+        // Capped at one row, no styles, written in one go
+        let options = PreviewOptions::new().with_max_size(1, 5).with_styles(false);
+        let mut out = Vec::new();
+        let written = to_html(&mut reader, "Sheet1", "A2:C9", &options)
+            .unwrap()
+            .write_to(&mut out)
+            .unwrap();
+        assert_eq!(written, out.len() as u64);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<table><tbody><tr><td>&lt;Alice&gt;</td><td>30</td><td>a &amp; b</td></tr></tbody></table>"
+        );
+
+        assert!(matches!(
+            to_html(&mut reader, "Sheet1", "A1:", &options),
+            Err(ExcelError::InvalidCell(_))
+        ));
+    }
+}
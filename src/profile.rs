@@ -0,0 +1,296 @@
+//! Column statistics for data-quality checks before an import
+//!
+//! [`Profiler`] collects per-column stats in a single pass over any row
+//! source; [`StreamingReader::profile`](crate::streaming_reader::StreamingReader::profile)
+//! runs it over a worksheet.
+//!
+//! # Example
+//!
+//! ```
+//! use excelstream::profile::Profiler;
+//! use excelstream::types::CellValue;
+//!
+//! let mut profiler = Profiler::new();
+//! profiler.add_row(&[CellValue::Int(1), CellValue::String("a".to_string())]);
+//! profiler.add_row(&[CellValue::Int(5), CellValue::Empty]);
+//!
+//! let profile = profiler.finish();
+//! assert_eq!(profile.columns[0].max, Some(5.0));
+//! assert_eq!(profile.columns[1].null_count, 1);
+//! ```
+
+use crate::types::CellValue;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Number of non-empty values of each type in a column
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeCounts {
+    pub string: u64,
+    pub int: u64,
+    pub float: u64,
+    pub percent: u64,
+    pub decimal: u64,
+    pub bool: u64,
+    pub datetime: u64,
+    pub duration: u64,
+    pub error: u64,
+    pub formula: u64,
+}
+
+impl TypeCounts {
+    fn add(&mut self, value: &CellValue) {
+        match value {
+            CellValue::Empty => {}
+            CellValue::String(_) | CellValue::RichText(_) => self.string += 1,
+            CellValue::Int(_) => self.int += 1,
+            CellValue::Float(_) => self.float += 1,
+            CellValue::Percent(_) => self.percent += 1,
+            CellValue::Decimal(_) => self.decimal += 1,
+            CellValue::Bool(_) => self.bool += 1,
+            CellValue::DateTime(_) | CellValue::DateTimeTz(_) => self.datetime += 1,
+            CellValue::Duration(_) => self.duration += 1,
+            CellValue::Error(_) => self.error += 1,
+            CellValue::Formula(_) => self.formula += 1,
+        }
+    }
+
+    /// Name of the most common type ("string", "int", ...), None if all empty
+    pub fn dominant(&self) -> Option<&'static str> {
+        [
+            ("string", self.string),
+            ("int", self.int),
+            ("float", self.float),
+            ("percent", self.percent),
+            ("decimal", self.decimal),
+            ("bool", self.bool),
+            ("datetime", self.datetime),
+            ("duration", self.duration),
+            ("error", self.error),
+            ("formula", self.formula),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map(|(name, _)| name)
+    }
+}
+
+/// Statistics for one column
+#[derive(Debug, Clone)]
+pub struct ColumnProfile {
+    /// 0-based column index
+    pub index: usize,
+    /// Header text, when profiled with a header row
+    pub name: Option<String>,
+    /// Non-empty values by type
+    pub types: TypeCounts,
+    /// Empty or missing cells
+    pub null_count: u64,
+    /// Smallest numeric value
+    pub min: Option<f64>,
+    /// Largest numeric value
+    pub max: Option<f64>,
+    /// Longest text value, in characters
+    pub max_text_len: usize,
+    /// Estimated number of distinct non-empty values (HyperLogLog, ~2% error)
+    pub distinct_estimate: u64,
+}
+
+/// Statistics for a whole sheet
+#[derive(Debug, Clone, Default)]
+pub struct SheetProfile {
+    /// Data rows profiled (excluding the header row)
+    pub rows: u64,
+    /// Per-column statistics, left to right
+    pub columns: Vec<ColumnProfile>,
+}
+
+/// Single-pass column profiler
+#[derive(Debug, Default)]
+pub struct Profiler {
+    rows: u64,
+    header: Option<Vec<String>>,
+    columns: Vec<ColumnState>,
+}
+
+#[derive(Debug)]
+struct ColumnState {
+    types: TypeCounts,
+    non_empty: u64,
+    min: Option<f64>,
+    max: Option<f64>,
+    max_text_len: usize,
+    distinct: HyperLogLog,
+}
+
+impl ColumnState {
+    fn new() -> Self {
+        ColumnState {
+            types: TypeCounts::default(),
+            non_empty: 0,
+            min: None,
+            max: None,
+            max_text_len: 0,
+            distinct: HyperLogLog::new(),
+        }
+    }
+}
+
+impl Profiler {
+    /// Create a profiler treating every row as data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given column names instead of index-only profiles
+    pub fn with_header(mut self, header: Vec<String>) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Add one data row
+    pub fn add_row(&mut self, cells: &[CellValue]) {
+        self.rows += 1;
+        if self.columns.len() < cells.len() {
+            self.columns.resize_with(cells.len(), ColumnState::new);
+        }
+
+        for (value, column) in cells.iter().zip(self.columns.iter_mut()) {
+            if value.is_empty() {
+                continue;
+            }
+            column.non_empty += 1;
+            column.types.add(value);
+
+            let number = match value {
+                CellValue::Int(i) => Some(*i as f64),
+                CellValue::Float(f) => Some(*f),
+                CellValue::Decimal(d) => Some(d.to_f64()),
+                _ => None,
+            };
+            if let Some(n) = number.filter(|n| !n.is_nan()) {
+                column.min = Some(column.min.map_or(n, |m| m.min(n)));
+                column.max = Some(column.max.map_or(n, |m| m.max(n)));
+            }
+            if let CellValue::String(s) = value {
+                column.max_text_len = column.max_text_len.max(s.chars().count());
+            }
+
+            let mut hasher = DefaultHasher::new();
+            std::mem::discriminant(value).hash(&mut hasher);
+            value.as_string().hash(&mut hasher);
+            column.distinct.insert(hasher.finish());
+        }
+    }
+
+    /// Finish profiling
+    pub fn finish(self) -> SheetProfile {
+        let rows = self.rows;
+        let header = self.header.unwrap_or_default();
+        let width = self.columns.len().max(header.len());
+        let mut columns = self.columns;
+        columns.resize_with(width, ColumnState::new);
+
+        SheetProfile {
+            rows,
+            columns: columns
+                .into_iter()
+                .enumerate()
+                .map(|(index, column)| ColumnProfile {
+                    index,
+                    name: header.get(index).cloned(),
+                    types: column.types,
+                    null_count: rows - column.non_empty,
+                    min: column.min,
+                    max: column.max,
+                    max_text_len: column.max_text_len,
+                    distinct_estimate: column.distinct.estimate(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// HyperLogLog cardinality sketch with 2^12 registers (4 KB per column)
+#[derive(Debug)]
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    const PRECISION: u32 = 12;
+
+    fn new() -> Self {
+        HyperLogLog {
+            registers: vec![0; 1 << Self::PRECISION],
+        }
+    }
+
+    fn insert(&mut self, hash: u64) {
+        let index = (hash >> (64 - Self::PRECISION)) as usize;
+        let rest = hash << Self::PRECISION;
+        let rank = (rest.leading_zeros() + 1).min(64 - Self::PRECISION + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_columns() {
+        let mut profiler = Profiler::new().with_header(vec!["id".into(), "name".into()]);
+        profiler.add_row(&[CellValue::Int(3), CellValue::String("Alice".into())]);
+        profiler.add_row(&[CellValue::Float(-1.5), CellValue::Empty]);
+        profiler.add_row(&[CellValue::Int(3)]);
+
+        let profile = profiler.finish();
+        assert_eq!(profile.rows, 3);
+
+        let id = &profile.columns[0];
+        assert_eq!(id.name.as_deref(), Some("id"));
+        assert_eq!(id.types.int, 2);
+        assert_eq!(id.types.dominant(), Some("int"));
+        assert_eq!((id.min, id.max), (Some(-1.5), Some(3.0)));
+        assert_eq!(id.null_count, 0);
+        assert_eq!(id.distinct_estimate, 2);
+
+        let name = &profile.columns[1];
+        assert_eq!(name.null_count, 2);
+        assert_eq!(name.max_text_len, 5);
+        assert_eq!(name.min, None);
+    }
+
+    #[test]
+    fn test_hyperloglog_estimate() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..100_000u64 {
+            let mut hasher = DefaultHasher::new();
+            (i % 20_000).hash(&mut hasher);
+            hll.insert(hasher.finish());
+        }
+        let estimate = hll.estimate() as f64;
+        assert!(
+            (estimate - 20_000.0).abs() / 20_000.0 < 0.05,
+            "{}",
+            estimate
+        );
+    }
+}
@@ -1,19 +1,40 @@
-//! Excel file reading with streaming support
-
-use crate::error::{ExcelError, Result};
-use crate::types::{CellValue, Row};
-use calamine::{open_workbook_auto, Data, Range, Reader, Sheets};
+//! Excel file reading
+//!
+//! [`ExcelReader`] is the recommended entry point for reading XLSX files. It is
+//! a thin facade over [`StreamingReader`]: every streaming method (`rows`,
+//! `cells`, `profile`, ...) is available through `Deref`, and reading is
+//! configured with [`ReaderOptions`].
+
+use crate::error::Result;
+use crate::streaming_reader::{
+    FormattedRowIterator, ReaderOptions, RowIterator, RowStructIterator, SheetReader,
+    StreamingReader,
+};
+use crate::types::CellValue;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
 
 /// Excel file reader with streaming capabilities
+///
+/// # Examples
+///
+/// ```no_run
+/// use excelstream::{ExcelReader, ReaderOptions};
+///
+/// let options = ReaderOptions::new().with_numbers_as_decimal(true);
+/// let mut reader = ExcelReader::open_with_options("data.xlsx", options)?;
+/// for row in reader.rows("Sheet1")? {
+///     let row = row?;
+///     println!("Row {}: {:?}", row.index, row.to_strings());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
 pub struct ExcelReader {
-    workbook: Sheets<std::io::BufReader<std::fs::File>>,
+    inner: StreamingReader,
 }
 
 impl ExcelReader {
-    /// Open an Excel file for reading
-    ///
-    /// Supports XLSX, XLS, and ODS formats. Format is auto-detected from file extension.
+    /// Open an XLSX file for reading with default options
     ///
     /// # Examples
     ///
@@ -23,79 +44,69 @@ impl ExcelReader {
     /// let reader = ExcelReader::open("data.xlsx").unwrap();
     /// ```
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let workbook =
-            open_workbook_auto(path).map_err(|e| ExcelError::ReadError(e.to_string()))?;
+        Self::open_with_options(path, ReaderOptions::default())
+    }
 
-        Ok(ExcelReader { workbook })
+    /// Open an XLSX file for reading with custom [`ReaderOptions`]
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: ReaderOptions) -> Result<Self> {
+        Ok(ExcelReader {
+            inner: StreamingReader::open_with_options(path, options)?,
+        })
     }
 
-    /// Get list of sheet names in the workbook
-    ///
-    /// # Examples
-    ///
-    /// ```no_run
-    /// use excelstream::reader::ExcelReader;
-    ///
-    /// let reader = ExcelReader::open("data.xlsx").unwrap();
-    /// let sheets = reader.sheet_names();
-    /// println!("Available sheets: {:?}", sheets);
-    /// ```
+    /// Names of all worksheets, in workbook order
     pub fn sheet_names(&self) -> Vec<String> {
-        self.workbook.sheet_names().to_vec()
+        self.inner.sheet_names()
     }
 
-    /// Get the number of sheets in the workbook
+    /// Number of worksheets
     pub fn sheet_count(&self) -> usize {
-        self.workbook.sheet_names().len()
+        self.inner.sheet_names().len()
     }
 
-    /// Read all rows from a specific sheet (streaming iterator)
-    ///
-    /// Returns an iterator that yields rows one at a time, minimizing memory usage.
-    ///
-    /// # Examples
-    ///
-    /// ```no_run
-    /// use excelstream::reader::ExcelReader;
-    ///
-    /// let mut reader = ExcelReader::open("data.xlsx").unwrap();
-    /// for row_result in reader.rows("Sheet1").unwrap() {
-    ///     let row = row_result.unwrap();
-    ///     println!("Row {}: {:?}", row.index, row.cells);
-    /// }
-    /// ```
-    pub fn rows(&mut self, sheet_name: &str) -> Result<RowIterator> {
-        let range = self.workbook.worksheet_range(sheet_name).map_err(|e| {
-            let error_str = e.to_string();
-            if error_str.contains("not found") {
-                let available = self.sheet_names().join(", ");
-                ExcelError::SheetNotFound {
-                    sheet: sheet_name.to_string(),
-                    available,
-                }
-            } else {
-                ExcelError::from(e)
-            }
-        })?;
+    /// Stream the rows of a sheet as [`Row`](crate::types::Row)s
+    pub fn rows(&mut self, sheet_name: &str) -> Result<RowStructIterator<'_>> {
+        self.inner.rows(sheet_name)
+    }
 
-        Ok(RowIterator::new(range))
+    /// Stream the rows of a sheet as a `futures::Stream` (feature `async`),
+    /// see [`StreamingReader::rows_stream`]
+    #[cfg(feature = "async")]
+    pub fn rows_stream(self, sheet_name: &str) -> Result<crate::row_stream::RowStream> {
+        self.inner.rows_stream(sheet_name)
     }
 
-    /// Read all rows from a sheet by index (0-based)
-    pub fn rows_by_index(&mut self, index: usize) -> Result<RowIterator> {
-        let sheet_names = self.sheet_names();
-        let sheet_name = sheet_names.get(index).ok_or_else(|| {
-            let available = sheet_names.join(", ");
-            ExcelError::SheetNotFound {
-                sheet: format!("index {}", index),
-                available,
-            }
-        })?;
+    /// Open one sheet with its own ZIP entry reader, so several sheets can
+    /// be read at once, see [`StreamingReader::sheet`]
+    pub fn sheet(&self, sheet_name: &str) -> Result<SheetReader<'_>> {
+        self.inner.sheet(sheet_name)
+    }
 
-        self.rows(sheet_name)
+    /// Stream the rows of a sheet by 0-based index
+    pub fn rows_by_index(&mut self, sheet_index: usize) -> Result<RowStructIterator<'_>> {
+        self.inner.rows_by_index(sheet_index)
     }
 
-    /// Read a specific cell value
+    /// Stream the rows of a sheet as plain cell value vectors
+    pub fn stream_rows(&mut self, sheet_name: &str) -> Result<RowIterator<'_>> {
+        self.inner.stream_rows(sheet_name)
+    }
+
+    /// Stream the rows of a sheet with each cell's formatting, see
+    /// [`StreamingReader::rows_with_style`]
+    pub fn rows_with_style(&mut self, sheet_name: &str) -> Result<FormattedRowIterator<'_>> {
+        self.inner.rows_with_style(sheet_name)
+    }
+
+    /// Sheet dimensions as (rows, columns)
+    pub fn dimensions(&mut self, sheet_name: &str) -> Result<(usize, usize)> {
+        self.inner.dimensions(sheet_name)
+    }
+
+    /// Read a single cell value (0-based row and column)
+    ///
+    /// This streams the sheet up to the requested row, so prefer `rows()`
+    /// when reading more than a handful of cells.
     ///
     /// # Examples
     ///
@@ -107,126 +118,90 @@ impl ExcelReader {
     /// println!("Cell A1: {}", value);
     /// ```
     pub fn read_cell(&mut self, sheet_name: &str, row: u32, col: u32) -> Result<CellValue> {
-        let range = self.workbook.worksheet_range(sheet_name).map_err(|e| {
-            let error_str = e.to_string();
-            if error_str.contains("not found") {
-                let available = self.sheet_names().join(", ");
-                ExcelError::SheetNotFound {
-                    sheet: sheet_name.to_string(),
-                    available,
-                }
-            } else {
-                ExcelError::from(e)
+        for result in self.inner.rows(sheet_name)? {
+            let current = result?;
+            if current.index > row {
+                break;
             }
-        })?;
-
-        let cell = range
-            .get_value((row, col))
-            .map(datatype_to_cellvalue)
-            .unwrap_or(CellValue::Empty);
-
-        Ok(cell)
-    }
-
-    /// Get the dimensions of a sheet (rows, cols)
-    pub fn dimensions(&mut self, sheet_name: &str) -> Result<(u32, u32)> {
-        let range = self.workbook.worksheet_range(sheet_name).map_err(|e| {
-            let error_str = e.to_string();
-            if error_str.contains("not found") {
-                let available = self.sheet_names().join(", ");
-                ExcelError::SheetNotFound {
-                    sheet: sheet_name.to_string(),
-                    available,
-                }
-            } else {
-                ExcelError::from(e)
+            if current.index == row {
+                return Ok(current
+                    .cells
+                    .into_iter()
+                    .nth(col as usize)
+                    .unwrap_or(CellValue::Empty));
             }
-        })?;
+        }
+        Ok(CellValue::Empty)
+    }
 
-        let (rows, cols) = range.get_size();
-        Ok((rows as u32, cols as u32))
+    /// Options this reader was opened with
+    pub fn options(&self) -> &ReaderOptions {
+        self.inner.options()
     }
-}
 
-/// Iterator over rows in an Excel sheet
-pub struct RowIterator {
-    range: Range<Data>,
-    current_row: u32,
-    max_row: u32,
+    /// Unwrap the underlying [`StreamingReader`]
+    pub fn into_inner(self) -> StreamingReader {
+        self.inner
+    }
 }
 
-impl RowIterator {
-    fn new(range: Range<Data>) -> Self {
-        let (rows, _) = range.get_size();
-        let start = range.start().map(|(r, _)| r).unwrap_or(0);
-
-        RowIterator {
-            range,
-            current_row: start,
-            max_row: start + rows as u32,
-        }
+impl From<StreamingReader> for ExcelReader {
+    fn from(inner: StreamingReader) -> Self {
+        ExcelReader { inner }
     }
 }
 
-impl Iterator for RowIterator {
-    type Item = Result<Row>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_row >= self.max_row {
-            return None;
-        }
-
-        let row_idx = self.current_row;
-        self.current_row += 1;
-
-        let (_, cols) = self.range.get_size();
-        let mut cells = Vec::with_capacity(cols);
-
-        // Optimize: iterate directly without intermediate conversions
-        let start_col = self.range.start().map(|(_, c)| c).unwrap_or(0);
-        for col in start_col..start_col + cols as u32 {
-            let cell_value = self
-                .range
-                .get_value((row_idx, col))
-                .map(datatype_to_cellvalue)
-                .unwrap_or(CellValue::Empty);
+impl Deref for ExcelReader {
+    type Target = StreamingReader;
 
-            cells.push(cell_value);
-        }
+    fn deref(&self) -> &StreamingReader {
+        &self.inner
+    }
+}
 
-        Some(Ok(Row::new(row_idx, cells)))
+impl DerefMut for ExcelReader {
+    fn deref_mut(&mut self) -> &mut StreamingReader {
+        &mut self.inner
     }
 }
 
-/// Convert calamine Data to our CellValue
-#[inline]
-fn datatype_to_cellvalue(dt: &Data) -> CellValue {
-    match dt {
-        Data::Empty => CellValue::Empty,
-        Data::String(s) => CellValue::String(s.clone()),
-        Data::Float(f) => CellValue::Float(*f),
-        Data::Int(i) => CellValue::Int(*i),
-        Data::Bool(b) => CellValue::Bool(*b),
-        Data::DateTime(d) => CellValue::DateTime(d.as_f64()),
-        Data::Error(e) => CellValue::Error(format!("{:?}", e)),
-        Data::DateTimeIso(s) => CellValue::String(s.clone()),
-        Data::DurationIso(s) => CellValue::String(s.clone()),
+impl std::fmt::Debug for ExcelReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExcelReader")
+            .field("sheets", &self.inner.sheet_names())
+            .finish()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use calamine::Data;
+    use crate::error::ExcelError;
+    use crate::writer::ExcelWriter;
 
     #[test]
-    fn test_datatype_conversion() {
-        let dt = Data::String("test".to_string());
-        let cv = datatype_to_cellvalue(&dt);
-        assert_eq!(cv, CellValue::String("test".to_string()));
-
-        let dt = Data::Int(42);
-        let cv = datatype_to_cellvalue(&dt);
-        assert_eq!(cv, CellValue::Int(42));
+    fn test_facade_reads_cells() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        {
+            let mut writer = ExcelWriter::new(file.path()).unwrap();
+            writer.write_row(["a", "b"]).unwrap();
+            writer.write_row(["c", "d"]).unwrap();
+            writer.save().unwrap();
+        }
+
+        let mut reader = ExcelReader::open(file.path()).unwrap();
+        assert_eq!(reader.sheet_count(), 1);
+        assert_eq!(
+            reader.read_cell("Sheet1", 1, 1).unwrap(),
+            CellValue::String("d".to_string())
+        );
+        assert_eq!(reader.read_cell("Sheet1", 5, 0).unwrap(), CellValue::Empty);
+        assert!(matches!(
+            reader.read_cell("Missing", 0, 0),
+            Err(ExcelError::SheetNotFound { .. })
+        ));
+
+        // Streaming methods are reachable through the facade
+        assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 2);
     }
 }
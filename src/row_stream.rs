@@ -0,0 +1,222 @@
+//! Async row streams (feature `async`)
+//!
+//! [`StreamingReader::rows_stream`] moves the reader to a background thread
+//! that parses the sheet and hands rows over through a bounded buffer, and
+//! returns a [`RowStream`] implementing `futures::Stream`. Parsing never
+//! blocks the async executor, and when the consumer falls behind (say,
+//! waiting on a database) the reader thread pauses until there is room, so
+//! memory stays bounded by [`DEFAULT_BUFFER_ROWS`].
+//!
+//! Works with any executor; no runtime is started.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use excelstream::streaming_reader::StreamingReader;
+//! use futures::TryStreamExt;
+//!
+//! let reader = StreamingReader::open("orders.xlsx")?;
+//! let mut rows = reader.rows_stream("Orders")?.try_chunks(500);
+//! while let Some(batch) = rows.try_next().await? {
+//!     let mut insert = sqlx::QueryBuilder::new("INSERT INTO orders (id, amount) ");
+//!     insert.push_values(batch, |mut b, row| {
+//!         b.push_bind(row.get(0).map(|c| c.as_string()))
+//!             .push_bind(row.get(1).and_then(|c| c.as_f64()));
+//!     });
+//!     insert.build().execute(&pool).await?;
+//! }
+//! ```
+
+use crate::error::{ExcelError, Result};
+use crate::streaming_reader::StreamingReader;
+use crate::types::Row;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Rows parsed ahead of the consumer by [`StreamingReader::rows_stream`]
+pub const DEFAULT_BUFFER_ROWS: usize = 1024;
+
+struct State {
+    rows: VecDeque<Result<Row>>,
+    done: bool,   // Reader thread finished (or panicked)
+    closed: bool, // Stream dropped; the reader thread stops
+    waker: Option<Waker>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    space: Condvar, // Signalled when the consumer takes a row or goes away
+    capacity: usize,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // A panic while holding the lock leaves the state consistent
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Queue a row, waiting for room; false once the stream is dropped
+    fn push(&self, row: Result<Row>) -> bool {
+        let mut state = self.lock();
+        while state.rows.len() >= self.capacity && !state.closed {
+            state = self.space.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        if state.closed {
+            return false;
+        }
+        state.rows.push_back(row);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+}
+
+// Marks the stream finished when the reader thread ends, even by panic
+struct Producer(Arc<Shared>);
+
+impl Drop for Producer {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Rows of a sheet as a `futures::Stream`, see [module docs](self)
+pub struct RowStream {
+    shared: Arc<Shared>,
+}
+
+impl RowStream {
+    pub(crate) fn spawn(
+        mut reader: StreamingReader,
+        sheet_name: String,
+        capacity: usize,
+    ) -> Result<Self> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                rows: VecDeque::new(),
+                done: false,
+                closed: false,
+                waker: None,
+            }),
+            space: Condvar::new(),
+            capacity: capacity.max(1),
+        });
+        let producer = Producer(Arc::clone(&shared));
+        std::thread::Builder::new()
+            .name("excelstream-rows".to_string())
+            .spawn(move || {
+                let shared = &producer.0;
+                match reader.rows(&sheet_name) {
+                    Ok(rows) => {
+                        for row in rows {
+                            if !shared.push(row) {
+                                break;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        shared.push(Err(e));
+                    }
+                }
+            })
+            .map_err(|e| ExcelError::ReadError(format!("Failed to start reader thread: {}", e)))?;
+        Ok(RowStream { shared })
+    }
+}
+
+impl Stream for RowStream {
+    type Item = Result<Row>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.lock();
+        if let Some(row) = state.rows.pop_front() {
+            self.shared.space.notify_one();
+            return Poll::Ready(Some(row));
+        }
+        if state.done {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for RowStream {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.space.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::ExcelWriter;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Wake;
+
+    // Minimal executor: park the thread until the stream wakes it
+    struct ThreadWaker(std::thread::Thread, AtomicBool);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.1.store(true, Ordering::SeqCst);
+            self.0.unpark();
+        }
+    }
+
+    fn collect(mut stream: RowStream) -> Vec<Result<Row>> {
+        let waker = Arc::new(ThreadWaker(std::thread::current(), AtomicBool::new(false)));
+        let task_waker = Waker::from(Arc::clone(&waker));
+        let mut cx = Context::from_waker(&task_waker);
+        let mut rows = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(row)) => rows.push(row),
+                Poll::Ready(None) => return rows,
+                Poll::Pending => {
+                    while !waker.1.swap(false, Ordering::SeqCst) {
+                        std::thread::park();
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rows_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stream.xlsx");
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        for i in 0..100 {
+            writer.write_row([i.to_string()]).unwrap();
+        }
+        writer.save().unwrap();
+
+        // A buffer smaller than the sheet makes the reader wait on the consumer
+        let reader = StreamingReader::open(&path).unwrap();
+        let stream = RowStream::spawn(reader, "Sheet1".to_string(), 8).unwrap();
+        let rows: Vec<Row> = collect(stream).into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 100);
+        assert_eq!(rows[99].index, 99);
+        assert_eq!(rows[99].to_strings(), vec!["99"]);
+
+        // Dropping the stream early stops the reader thread
+        let reader = StreamingReader::open(&path).unwrap();
+        drop(reader.rows_stream("Sheet1").unwrap());
+
+        let reader = StreamingReader::open(&path).unwrap();
+        assert!(matches!(
+            reader.rows_stream("Missing"),
+            Err(ExcelError::SheetNotFound { .. })
+        ));
+    }
+}
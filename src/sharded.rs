@@ -0,0 +1,407 @@
+//! Split one large dataset across several workbooks or sheets
+//!
+//! [`ShardedWriter`] takes rows like [`ExcelWriter`] and starts a new file
+//! (`report_part01.xlsx`, `report_part02.xlsx`, ...) or a new sheet whenever
+//! the current one reaches a row or size limit. Every part starts with the
+//! same header row, and [`finish`](ShardedWriter::finish) returns a
+//! [`ShardManifest`] listing which rows ended up where.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::sharded::ShardedWriter;
+//!
+//! let mut writer = ShardedWriter::new("report.xlsx")
+//!     .with_header(["ID", "Name"])
+//!     .with_max_rows(500_000)
+//!     .with_max_bytes(100 * 1024 * 1024);
+//!
+//! for i in 0..2_000_000 {
+//!     writer.write_row([i.to_string(), format!("Item {}", i)])?;
+//! }
+//!
+//! let manifest = writer.finish()?;
+//! std::fs::write("report_manifest.csv", manifest.to_csv())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::error::Result;
+use crate::types::CellValue;
+use crate::writer::{ExcelWriter, ExcelWriterBuilder};
+use std::path::{Path, PathBuf};
+
+/// Most data rows a sheet can hold next to a one-row header
+const MAX_SHEET_ROWS: u64 = 1_048_575;
+
+/// Where [`ShardedWriter`] puts the next part
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShardTarget {
+    /// A new workbook next to the first: `report_part02.xlsx`
+    #[default]
+    Files,
+    /// A new sheet in the same workbook: `Data_part02`
+    Sheets,
+}
+
+/// One part written by [`ShardedWriter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardInfo {
+    /// Workbook the part was written to
+    pub path: PathBuf,
+    /// Sheet holding the part
+    pub sheet: String,
+    /// Position of the part's first data row in the whole dataset (1-based)
+    pub first_row: u64,
+    /// Data rows in the part, header not included
+    pub rows: u64,
+}
+
+/// Parts written by a [`ShardedWriter`], in order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardManifest {
+    pub shards: Vec<ShardInfo>,
+}
+
+impl ShardManifest {
+    /// Data rows across all parts
+    pub fn total_rows(&self) -> u64 {
+        self.shards.iter().map(|s| s.rows).sum()
+    }
+
+    /// Workbooks written, each listed once
+    pub fn files(&self) -> Vec<&Path> {
+        let mut files: Vec<&Path> = Vec::new();
+        for shard in &self.shards {
+            if !files.contains(&shard.path.as_path()) {
+                files.push(&shard.path);
+            }
+        }
+        files
+    }
+
+    /// Manifest as CSV: `file,sheet,first_row,rows`
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("file,sheet,first_row,rows\n");
+        for shard in &self.shards {
+            let file = shard
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&file),
+                csv_field(&shard.sheet),
+                shard.first_row,
+                shard.rows
+            ));
+        }
+        csv
+    }
+}
+
+/// Writer that rolls over to a new file or sheet every N rows or M bytes
+///
+/// Without limits a part holds as many rows as an Excel sheet allows. The
+/// size limit counts the cell text written to the part, before XML markup
+/// and compression: a part with 100 MB of text takes about that much memory
+/// to open, while the `.xlsx` on disk is several times smaller.
+pub struct ShardedWriter {
+    path: PathBuf,
+    sheet_name: String,
+    target: ShardTarget,
+    header: Option<Vec<String>>,
+    max_rows: u64,
+    max_bytes: Option<u64>,
+    writer: Option<ExcelWriter>,
+    shards: Vec<ShardInfo>,
+    rows_written: u64,
+    part_bytes: u64,
+}
+
+impl ShardedWriter {
+    /// Create a writer whose parts are named after `path`
+    /// (`report.xlsx` becomes `report_part01.xlsx`, ...)
+    ///
+    /// Nothing is created until the first row or [`finish`](Self::finish).
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        ShardedWriter {
+            path: path.as_ref().to_path_buf(),
+            sheet_name: "Sheet1".to_string(),
+            target: ShardTarget::Files,
+            header: None,
+            max_rows: MAX_SHEET_ROWS,
+            max_bytes: None,
+            writer: None,
+            shards: Vec::new(),
+            rows_written: 0,
+            part_bytes: 0,
+        }
+    }
+
+    /// Bold header row repeated at the top of every part
+    pub fn with_header<I, S>(mut self, header: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.header = Some(header.into_iter().map(|h| h.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Start a new part after `rows` data rows (capped at the sheet limit)
+    pub fn with_max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = rows.clamp(1, MAX_SHEET_ROWS);
+        self
+    }
+
+    /// Start a new part once the current one holds `bytes` of cell text
+    pub fn with_max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Roll over to new files (default) or new sheets of one workbook
+    pub fn with_target(mut self, target: ShardTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Name of the sheet in each file, or the prefix of the sheet names in
+    /// [`ShardTarget::Sheets`] mode (default "Sheet1")
+    pub fn with_sheet_name(mut self, name: &str) -> Self {
+        self.sheet_name = name.to_string();
+        self
+    }
+
+    /// Write a row of text cells
+    pub fn write_row<I, S>(&mut self, data: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let data: Vec<S> = data.into_iter().collect();
+        let bytes = data.iter().map(|v| v.as_ref().len() as u64).sum();
+        self.writer_for_next_row()?.write_row(data)?;
+        self.row_written(bytes);
+        Ok(())
+    }
+
+    /// Write a row of typed cells
+    pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
+        let bytes = cells.iter().map(|c| c.as_string().len() as u64).sum();
+        self.writer_for_next_row()?.write_row_typed(cells)?;
+        self.row_written(bytes);
+        Ok(())
+    }
+
+    /// Finish the last part and return the manifest
+    ///
+    /// With no rows written, a single part holding just the header is created.
+    pub fn finish(mut self) -> Result<ShardManifest> {
+        if self.writer.is_none() {
+            self.start_part()?;
+        }
+        if let Some(writer) = self.writer.take() {
+            writer.save()?;
+        }
+        Ok(ShardManifest {
+            shards: self.shards,
+        })
+    }
+
+    // Writer for the next row, starting a new part when the current is full
+    fn writer_for_next_row(&mut self) -> Result<&mut ExcelWriter> {
+        if self.writer.is_none() || self.current_part_full() {
+            self.start_part()?;
+        }
+        Ok(self.writer.as_mut().expect("part started"))
+    }
+
+    fn row_written(&mut self, bytes: u64) {
+        self.rows_written += 1;
+        self.part_bytes += bytes;
+        if let Some(shard) = self.shards.last_mut() {
+            shard.rows += 1;
+        }
+    }
+
+    fn current_part_full(&self) -> bool {
+        let rows = self.shards.last().map_or(0, |s| s.rows);
+        rows >= self.max_rows || self.max_bytes.is_some_and(|max| self.part_bytes >= max)
+    }
+
+    fn start_part(&mut self) -> Result<()> {
+        let part = self.shards.len() + 1;
+        let (path, sheet) = match self.target {
+            ShardTarget::Files => {
+                if let Some(writer) = self.writer.take() {
+                    writer.save()?;
+                }
+                let path = part_path(&self.path, part);
+                let writer = ExcelWriterBuilder::new(&path)
+                    .with_sheet_name(&self.sheet_name)
+                    .build()?;
+                self.writer = Some(writer);
+                (path, self.sheet_name.clone())
+            }
+            ShardTarget::Sheets => {
+                let sheet = format!("{}_part{:02}", self.sheet_name, part);
+                let path = part_path(&self.path, 1);
+                match self.writer.as_mut() {
+                    Some(writer) => writer.add_sheet(&sheet)?,
+                    None => {
+                        let writer = ExcelWriterBuilder::new(&path)
+                            .with_sheet_name(&sheet)
+                            .build()?;
+                        self.writer = Some(writer);
+                    }
+                }
+                (path, sheet)
+            }
+        };
+
+        if let (Some(header), Some(writer)) = (&self.header, self.writer.as_mut()) {
+            writer.write_header_bold(header)?;
+        }
+        self.part_bytes = 0;
+        self.shards.push(ShardInfo {
+            path,
+            sheet,
+            first_row: self.rows_written + 1,
+            rows: 0,
+        });
+        Ok(())
+    }
+}
+
+/// `dir/report.xlsx` -> `dir/report_part03.xlsx`
+fn part_path(path: &Path, part: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_part{:02}.{}", stem, part, ext.to_string_lossy()),
+        None => format!("{}_part{:02}", stem, part),
+    };
+    path.with_file_name(name)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExcelReader;
+
+    fn read_rows(path: &Path, sheet: &str) -> Vec<Vec<String>> {
+        let mut reader = ExcelReader::open(path).unwrap();
+        reader
+            .rows(sheet)
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect()
+    }
+
+    #[test]
+    fn test_part_path() {
+        assert_eq!(
+            part_path(Path::new("out/report.xlsx"), 1),
+            Path::new("out/report_part01.xlsx")
+        );
+        assert_eq!(
+            part_path(Path::new("report"), 120),
+            Path::new("report_part120")
+        );
+    }
+
+    #[test]
+    fn test_shard_files_by_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = ShardedWriter::new(dir.path().join("report.xlsx"))
+            .with_header(["ID", "Name"])
+            .with_max_rows(2);
+        for i in 1..=5 {
+            writer
+                .write_row_typed(&[CellValue::Int(i), CellValue::String(format!("n{}", i))])
+                .unwrap();
+        }
+        let manifest = writer.finish().unwrap();
+
+        assert_eq!(manifest.shards.len(), 3);
+        assert_eq!(manifest.total_rows(), 5);
+        assert_eq!(manifest.files().len(), 3);
+        assert_eq!(manifest.shards[2].first_row, 5);
+        assert_eq!(manifest.shards[2].rows, 1);
+        assert_eq!(
+            manifest.to_csv(),
+            "file,sheet,first_row,rows\n\
+             report_part01.xlsx,Sheet1,1,2\n\
+             report_part02.xlsx,Sheet1,3,2\n\
+             report_part03.xlsx,Sheet1,5,1\n"
+        );
+
+        let rows = read_rows(&dir.path().join("report_part02.xlsx"), "Sheet1");
+        assert_eq!(
+            rows,
+            vec![vec!["ID", "Name"], vec!["3", "n3"], vec!["4", "n4"]]
+        );
+    }
+
+    #[test]
+    fn test_shard_sheets_and_empty_dataset() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = ShardedWriter::new(dir.path().join("data.xlsx"))
+            .with_target(ShardTarget::Sheets)
+            .with_sheet_name("Data")
+            .with_header(["Value"])
+            .with_max_rows(3);
+        for i in 0..7 {
+            writer.write_row([i.to_string()]).unwrap();
+        }
+        let manifest = writer.finish().unwrap();
+
+        let sheets: Vec<_> = manifest.shards.iter().map(|s| s.sheet.as_str()).collect();
+        assert_eq!(sheets, ["Data_part01", "Data_part02", "Data_part03"]);
+        assert_eq!(manifest.files().len(), 1);
+        let path = dir.path().join("data_part01.xlsx");
+        assert_eq!(
+            read_rows(&path, "Data_part03"),
+            vec![vec!["Value"], vec!["6"]]
+        );
+
+        let manifest = ShardedWriter::new(dir.path().join("empty.xlsx"))
+            .with_header(["Value"])
+            .finish()
+            .unwrap();
+        assert_eq!(manifest.shards.len(), 1);
+        assert_eq!(manifest.total_rows(), 0);
+        assert_eq!(
+            read_rows(&manifest.shards[0].path, "Sheet1"),
+            vec![vec!["Value"]]
+        );
+    }
+
+    #[test]
+    fn test_shard_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = ShardedWriter::new(dir.path().join("big.xlsx")).with_max_bytes(100);
+        for i in 0..25 {
+            writer
+                .write_row([format!("{:>10}", i), "x".repeat(10)])
+                .unwrap();
+        }
+        let manifest = writer.finish().unwrap();
+
+        // 20 bytes a row: a part is full after 5 rows
+        let rows: Vec<_> = manifest.shards.iter().map(|s| s.rows).collect();
+        assert_eq!(rows, [5, 5, 5, 5, 5]);
+    }
+}
@@ -0,0 +1,239 @@
+//! OOXML package digital signatures
+//!
+//! Workbooks can carry XML-DSig signatures in `_xmlsignatures/` (the
+//! "Signatures" pane in Excel). Reading inspects them with
+//! [`StreamingReader::signatures`](crate::streaming_reader::StreamingReader::signatures):
+//! the signer's certificate, signing time, signature value and the parts each
+//! signature covers, with every part digest compared against the package so
+//! parts changed after signing show up in [`SignatureInfo::mismatched_parts`].
+//!
+//! Inspection is not verification. excelstream has no RSA or X.509 code of
+//! its own: checking the `SignatureValue` against the certificate, the
+//! digest of the signature's own `Object` (which needs XML canonicalization)
+//! and whether to trust the certificate is left to the caller's crypto
+//! library. Signing likewise goes through a [`Signer`] that holds the key, e.g. backed by an HSM or
+//! a KMS: set one with
+//! [`ExcelWriter::set_signer`](crate::ExcelWriter::set_signer) and the
+//! workbook parts are hashed as they stream and signed at save.
+//!
+//! Relationship parts (`*.rels`) are not covered by written signatures,
+//! since they need the OPC relationship transform. Signatures are
+//! invalidated by any later change to the file.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::streaming_reader::StreamingReader;
+//!
+//! let mut reader = StreamingReader::open("signed.xlsx")?;
+//! for signature in reader.signatures()? {
+//!     println!(
+//!         "{} signed at {:?}, changed parts: {:?}",
+//!         signature.part,
+//!         signature.signing_time,
+//!         signature.mismatched_parts().collect::<Vec<_>>()
+//!     );
+//! }
+//! # Ok::<(), excelstream::ExcelError>(())
+//! ```
+
+use crate::error::{ExcelError, Result};
+use sha2::{Digest, Sha256};
+
+/// Signature part written by [`Signer`]-enabled writers
+pub const SIGNATURE_PART: &str = "_xmlsignatures/sig1.xml";
+/// Signature origin part, the anchor all signatures hang off
+pub const ORIGIN_PART: &str = "_xmlsignatures/origin.sigs";
+
+pub(crate) const ORIGIN_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/digital-signature/origin";
+pub(crate) const SIGNATURE_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/digital-signature/signature";
+pub(crate) const ORIGIN_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-package.digital-signature-origin";
+pub(crate) const SIGNATURE_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-package.digital-signature-xmlsignature+xml";
+
+const XMLDSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const C14N: &str = "http://www.w3.org/TR/2001/REC-xml-c14n-20010315";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// Key that signs written workbooks
+///
+/// `sign` receives the canonical `SignedInfo` element and returns its
+/// RSA PKCS#1 v1.5 signature over SHA-256 (`rsa-sha256`).
+pub trait Signer: Send {
+    /// Signer's X.509 certificate, DER encoded
+    fn certificate(&self) -> Vec<u8>;
+
+    /// Sign `data` with the private key matching the certificate
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Result of comparing one signed part's digest with the package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestStatus {
+    /// The part's digest matches
+    Matches,
+    /// The part is missing or its digest differs
+    Mismatch,
+    /// Not checked: transformed reference (e.g. relationship parts), an
+    /// in-signature object or an unsupported digest algorithm
+    Unchecked,
+}
+
+/// A part covered by a signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedReference {
+    /// Reference URI, e.g. `/xl/workbook.xml?ContentType=…` or `#idPackageObject`
+    pub uri: String,
+    /// Digest algorithm URI
+    pub digest_method: String,
+    pub status: DigestStatus,
+}
+
+/// A digital signature found in a workbook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    /// Signature part, e.g. `_xmlsignatures/sig1.xml`
+    pub part: String,
+    /// Signing time as recorded by the signer (not trusted)
+    pub signing_time: Option<String>,
+    /// Signer's X.509 certificate, DER encoded
+    pub certificate: Option<Vec<u8>>,
+    /// Signature algorithm URI, e.g. `…xmldsig-more#rsa-sha256`
+    pub signature_method: String,
+    /// Raw signature value, to check against `certificate`
+    pub signature_value: Vec<u8>,
+    /// Signed parts, with their digest status
+    pub references: Vec<SignedReference>,
+}
+
+impl SignatureInfo {
+    /// URIs of signed parts that are missing or whose digest differs
+    ///
+    /// An empty list says nothing about the signature itself:
+    /// `signature_value` and the `Object` reference are not checked, see
+    /// the [module documentation](self).
+    pub fn mismatched_parts(&self) -> impl Iterator<Item = &str> {
+        self.references
+            .iter()
+            .filter(|r| r.status == DigestStatus::Mismatch)
+            .map(|r| r.uri.as_str())
+    }
+}
+
+/// Build the signature part for parts with their content type and SHA-256
+pub(crate) fn signature_xml(
+    parts: &[(String, String, [u8; 32])],
+    signing_time: &str,
+    signer: &dyn Signer,
+) -> Result<String> {
+    // Elements are written in canonical form (sorted attributes, no empty
+    // element tags) so the bytes hashed below are exactly what c14n yields
+    let mut manifest = String::new();
+    for (part, content_type, digest) in parts {
+        manifest.push_str(&format!(
+            r#"<Reference URI="/{}?ContentType={}"><DigestMethod Algorithm="{}"></DigestMethod><DigestValue>{}</DigestValue></Reference>"#,
+            part,
+            content_type,
+            SHA256,
+            base64_encode(digest)
+        ));
+    }
+    let object_body = format!(
+        r##"<Manifest>{}</Manifest><SignatureProperties><SignatureProperty Id="idSignatureTime" Target="#idPackageSignature"><mdssi:SignatureTime xmlns:mdssi="http://schemas.openxmlformats.org/package/2006/digital-signature"><mdssi:Format>YYYY-MM-DDThh:mm:ssTZD</mdssi:Format><mdssi:Value>{}</mdssi:Value></mdssi:SignatureTime></SignatureProperty></SignatureProperties></Object>"##,
+        manifest, signing_time
+    );
+    let object_digest = Sha256::digest(
+        format!(
+            r#"<Object xmlns="{}" Id="idPackageObject">{}"#,
+            XMLDSIG_NS, object_body
+        )
+        .as_bytes(),
+    );
+
+    let signed_info_body = format!(
+        r##"<CanonicalizationMethod Algorithm="{}"></CanonicalizationMethod><SignatureMethod Algorithm="{}"></SignatureMethod><Reference Type="http://www.w3.org/2000/09/xmldsig#Object" URI="#idPackageObject"><DigestMethod Algorithm="{}"></DigestMethod><DigestValue>{}</DigestValue></Reference></SignedInfo>"##,
+        C14N,
+        RSA_SHA256,
+        SHA256,
+        base64_encode(&object_digest)
+    );
+    let signature_value = signer
+        .sign(format!(r#"<SignedInfo xmlns="{}">{}"#, XMLDSIG_NS, signed_info_body).as_bytes())?;
+
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Signature xmlns="{}" Id="idPackageSignature"><SignedInfo>{}<SignatureValue>{}</SignatureValue><KeyInfo><X509Data><X509Certificate>{}</X509Certificate></X509Data></KeyInfo><Object Id="idPackageObject">{}</Signature>"#,
+        XMLDSIG_NS,
+        signed_info_body,
+        base64_encode(&signature_value),
+        base64_encode(&signer.certificate()),
+        object_body
+    ))
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode base64, ignoring whitespace (XML-DSig wraps long values)
+pub(crate) fn base64_decode(text: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'=' {
+            break;
+        }
+        let value = BASE64.iter().position(|&b| b == c).ok_or_else(|| {
+            ExcelError::InvalidFormat(format!("Invalid base64 character '{}'", c as char))
+        })?;
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_round_trip() {
+        for (data, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64_encode(data), text);
+            assert_eq!(base64_decode(text).unwrap(), data);
+        }
+        assert_eq!(base64_decode("Zm9v\n YmFy").unwrap(), b"foobar");
+        assert!(base64_decode("Zm9v!").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Outputs the writers stream into, and a fault-injecting one for tests
+//!
+//! Workbooks are written through the [`Sink`] trait, implemented for
+//! anything seekable: files, in-memory cursors or wrappers around them.
+//! [`FaultySink`] wraps another sink and misbehaves on a fixed schedule
+//! (running out of space, accepting short writes, failing like a dropped
+//! connection, or answering slowly), so error handling can be tested
+//! deterministically instead of by filling a real disk.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::sink::FaultySink;
+//! use excelstream::{ExcelError, ExcelWriterBuilder};
+//!
+//! // Pretend the disk fills up after 64 KiB
+//! let mut writer = ExcelWriterBuilder::new("report.xlsx")
+//!     .with_sink(|file| FaultySink::new(file).disk_full_after(64 * 1024))
+//!     .build()?;
+//! let mut result = Ok(());
+//! for i in 0..100_000 {
+//!     result = writer.write_row([i.to_string(), format!("row {}", i)]);
+//!     if result.is_err() {
+//!         break;
+//!     }
+//! }
+//! assert!(matches!(result, Err(ExcelError::DiskFull { .. })));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fs::File;
+use std::io::{self, ErrorKind, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+/// Seekable output a workbook is written to
+///
+/// Implemented for every `Write + Seek + Send` type.
+pub trait Sink: Write + Seek + Send {}
+
+impl<T: Write + Seek + Send> Sink for T {}
+
+/// Turns the output file a writer opened into the sink it writes through,
+/// see [`ExcelWriterBuilder::with_sink`](crate::ExcelWriterBuilder::with_sink)
+pub(crate) type WrapSink = Box<dyn FnOnce(File) -> Box<dyn Sink>>;
+
+/// A [`Sink`] that injects IO faults on a fixed schedule
+///
+/// Faults are counted in bytes accepted, so the same plan fails at the same
+/// point on every run. Without any fault configured it passes everything
+/// through unchanged.
+#[derive(Debug)]
+pub struct FaultySink<W> {
+    inner: W,
+    fail_after: Option<(u64, ErrorKind)>,
+    max_write: Option<usize>,
+    latency: Duration,
+    bytes_written: u64,
+    writes: u64,
+}
+
+impl<W: Write + Seek> FaultySink<W> {
+    /// Wrap `inner` without any faults
+    pub fn new(inner: W) -> Self {
+        FaultySink {
+            inner,
+            fail_after: None,
+            max_write: None,
+            latency: Duration::ZERO,
+            bytes_written: 0,
+            writes: 0,
+        }
+    }
+
+    /// Fail every write once `bytes` have been accepted, like ENOSPC
+    ///
+    /// The error kind is [`ErrorKind::StorageFull`], which the writers
+    /// report as [`ExcelError::DiskFull`](crate::ExcelError::DiskFull).
+    pub fn disk_full_after(self, bytes: u64) -> Self {
+        self.fail_after(bytes, ErrorKind::StorageFull)
+    }
+
+    /// Fail every write and flush with `kind` once `bytes` have been
+    /// accepted, e.g. [`ErrorKind::ConnectionReset`] for a dropped network
+    /// filesystem
+    ///
+    /// The write crossing the limit is cut short at it, so the inner sink
+    /// holds exactly `bytes` bytes.
+    pub fn fail_after(mut self, bytes: u64, kind: ErrorKind) -> Self {
+        self.fail_after = Some((bytes, kind));
+        self
+    }
+
+    /// Accept at most `max` bytes per write call (at least 1)
+    ///
+    /// Callers have to loop on partial writes; `write_all` does.
+    pub fn short_writes(mut self, max: usize) -> Self {
+        self.max_write = Some(max.max(1));
+        self
+    }
+
+    /// Sleep for `latency` before every write and flush, like a slow disk
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Bytes accepted so far
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Write calls that accepted at least one byte
+    pub fn writes(&self) -> u64 {
+        self.writes
+    }
+
+    /// The wrapped sink
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn check_failed(&self) -> io::Result<()> {
+        match self.fail_after {
+            Some((limit, kind)) if self.bytes_written >= limit => Err(io::Error::new(
+                kind,
+                format!("injected fault after {} bytes", limit),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn delay(&self) {
+        if !self.latency.is_zero() {
+            std::thread::sleep(self.latency);
+        }
+    }
+}
+
+impl<W: Write + Seek> Write for FaultySink<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.delay();
+        self.check_failed()?;
+        let mut len = buf.len();
+        if let Some(max) = self.max_write {
+            len = len.min(max);
+        }
+        if let Some((limit, _)) = self.fail_after {
+            len = len.min((limit - self.bytes_written) as usize);
+        }
+        let written = self.inner.write(&buf[..len])?;
+        if written > 0 {
+            self.bytes_written += written as u64;
+            self.writes += 1;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.delay();
+        self.check_failed()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + Seek> Seek for FaultySink<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ExcelError;
+    use crate::streaming_reader::StreamingReader;
+    use crate::ExcelWriterBuilder;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_faulty_sink() {
+        let mut sink = FaultySink::new(Cursor::new(Vec::new())).short_writes(3);
+        assert_eq!(sink.write(b"hello").unwrap(), 3);
+        sink.write_all(b"lo world").unwrap();
+        assert_eq!(sink.bytes_written(), 11);
+        assert_eq!(sink.writes(), 4);
+        assert_eq!(sink.into_inner().into_inner(), b"hello world");
+
+        let mut sink = FaultySink::new(Cursor::new(Vec::new()))
+            .fail_after(6, ErrorKind::ConnectionReset)
+            .latency(Duration::from_millis(1));
+        let err = sink.write_all(b"hello world").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+        assert_eq!(sink.flush().unwrap_err().kind(), ErrorKind::ConnectionReset);
+        assert_eq!(sink.into_inner().into_inner(), b"hello ");
+    }
+
+    #[test]
+    fn test_writer_with_faulty_sink() {
+        // Short writes everywhere still produce a valid workbook
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_sink(|file| FaultySink::new(file).short_writes(7))
+            .build()
+            .unwrap();
+        for i in 0..500 {
+            writer
+                .write_row([i.to_string(), format!("row {}", i)])
+                .unwrap();
+        }
+        writer.save().unwrap();
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader.rows("Sheet1").unwrap().collect();
+        assert_eq!(rows.len(), 500);
+        assert_eq!(rows[499].as_ref().unwrap().to_strings(), ["499", "row 499"]);
+
+        // Running out of space removes the partial file
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("full.xlsx");
+        let mut writer = ExcelWriterBuilder::new(&path)
+            .with_sink(|file| FaultySink::new(file).disk_full_after(2048))
+            .build()
+            .unwrap();
+        let mut result = Ok(());
+        for i in 0..10_000 {
+            result = writer.write_row([i.to_string(), format!("row {}", i)]);
+            if result.is_err() {
+                break;
+            }
+        }
+        let result = result.and_then(|_| writer.save().map(|_| ()));
+        assert!(matches!(result, Err(ExcelError::DiskFull { .. })));
+        assert!(!path.exists());
+
+        // Other faults come through as IO errors
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_sink(|file| FaultySink::new(file).fail_after(100, ErrorKind::TimedOut))
+            .build()
+            .unwrap();
+        writer.write_row(["a", "b"]).unwrap();
+        assert!(matches!(
+            writer.save(),
+            Err(ExcelError::IoError(e)) if e.kind() == ErrorKind::TimedOut
+        ));
+    }
+}
@@ -0,0 +1,527 @@
+//! Sort rows on write with bounded memory
+//!
+//! [`ExcelWriter::sort_by`](crate::ExcelWriter::sort_by) holds back the
+//! rows written to the current sheet and emits them ordered by
+//! [`SortOptions`] once the sheet is finished. Rows are sorted in a buffer
+//! of about [`DEFAULT_BUFFER_BYTES`]; when it fills up, the sorted buffer
+//! is spilled to a temporary file as a run, and the runs are merged when
+//! the rows are emitted. Sorting is stable: rows with equal keys keep the
+//! order they were written in.
+//!
+//! Keys compare like Excel sorts: numbers (and dates) before text, text
+//! case-insensitively, then booleans and errors. Blank cells always go
+//! last, in either direction.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::sort::SortOptions;
+//! use excelstream::types::CellValue;
+//! use excelstream::ExcelWriter;
+//!
+//! let mut writer = ExcelWriter::new("orders.xlsx")?;
+//! writer.write_header_bold(["Region", "Customer", "Amount"])?;
+//! // Rows written from here on come out by region, largest amount first
+//! writer.sort_by(SortOptions::by(0).then_by_desc(2))?;
+//! writer.write_row_typed(&["West".into(), "Acme".into(), CellValue::Int(120)])?;
+//! writer.write_row_typed(&["East".into(), "Globex".into(), CellValue::Int(80)])?;
+//! writer.save()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::error::{ExcelError, Result};
+use crate::numfmt::Currency;
+use crate::transpose::{encode, ColumnCursor, SpooledColumn};
+use crate::types::{CellStyle, CellValue, StyledCell};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Rows buffered in memory before a sorted run is spilled to disk
+pub const DEFAULT_BUFFER_BYTES: usize = 64 * 1024 * 1024;
+
+// Read buffer shared by all runs while merging
+const MERGE_BUFFER_BYTES: usize = 8 * 1024 * 1024;
+const MIN_RUN_BUFFER: usize = 4 * 1024;
+
+/// One sort key: a column (0-based) and its direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: usize,
+    pub descending: bool,
+}
+
+/// Order of the rows for
+/// [`ExcelWriter::sort_by`](crate::ExcelWriter::sort_by)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOptions {
+    /// Keys in order of precedence
+    pub keys: Vec<SortKey>,
+    /// Memory for rows before a run is spilled (default:
+    /// [`DEFAULT_BUFFER_BYTES`])
+    pub max_buffer_bytes: usize,
+    /// Directory for spilled runs (default: the system temp directory)
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl SortOptions {
+    /// Sort ascending by `column`
+    pub fn by(column: usize) -> Self {
+        SortOptions {
+            keys: vec![SortKey {
+                column,
+                descending: false,
+            }],
+            max_buffer_bytes: DEFAULT_BUFFER_BYTES,
+            temp_dir: None,
+        }
+    }
+
+    /// Sort descending by `column`
+    pub fn by_desc(column: usize) -> Self {
+        Self::by(column).descending()
+    }
+
+    /// Break ties ascending by `column`
+    pub fn then_by(mut self, column: usize) -> Self {
+        self.keys.push(SortKey {
+            column,
+            descending: false,
+        });
+        self
+    }
+
+    /// Break ties descending by `column`
+    pub fn then_by_desc(self, column: usize) -> Self {
+        self.then_by(column).descending()
+    }
+
+    /// Memory for buffered rows before spilling to disk
+    pub fn with_max_buffer_bytes(mut self, bytes: usize) -> Self {
+        self.max_buffer_bytes = bytes;
+        self
+    }
+
+    /// Directory for spilled runs
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    // Make the last key descending
+    fn descending(mut self) -> Self {
+        if let Some(key) = self.keys.last_mut() {
+            key.descending = true;
+        }
+        self
+    }
+
+    // Order of two rows by the keys
+    fn compare(&self, a: &[StyledCell], b: &[StyledCell]) -> Ordering {
+        for key in &self.keys {
+            let a = a.get(key.column).map_or(&CellValue::Empty, |c| &c.value);
+            let b = b.get(key.column).map_or(&CellValue::Empty, |c| &c.value);
+            let order = match (a.is_empty(), b.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) if key.descending => compare_values(b, a),
+                (false, false) => compare_values(a, b),
+            };
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+// Excel's sort order of two non-blank values
+fn compare_values(a: &CellValue, b: &CellValue) -> Ordering {
+    fn rank(value: &CellValue) -> u8 {
+        match value {
+            CellValue::Int(_)
+            | CellValue::Float(_)
+            | CellValue::Decimal(_)
+            | CellValue::DateTime(_)
+            | CellValue::DateTimeTz(_)
+            | CellValue::Duration(_)
+            | CellValue::Percent(_) => 0,
+            CellValue::String(_) | CellValue::RichText(_) | CellValue::Formula(_) => 1,
+            CellValue::Bool(_) => 2,
+            CellValue::Error(_) | CellValue::Empty => 3,
+        }
+    }
+    match (a, b) {
+        (CellValue::Int(x), CellValue::Int(y)) => x.cmp(y),
+        (CellValue::Bool(x), CellValue::Bool(y)) => x.cmp(y),
+        // By instant, whatever the offsets; against other numbers by local time
+        (CellValue::DateTimeTz(x), CellValue::DateTimeTz(y)) => x.cmp(y),
+        _ => match (rank(a), rank(b)) {
+            (0, 0) => {
+                let x = a.as_f64().unwrap_or(0.0);
+                x.total_cmp(&b.as_f64().unwrap_or(0.0))
+            }
+            (1, 1) | (3, 3) => {
+                let lower = |value| {
+                    text_parts(value)
+                        .flat_map(str::chars)
+                        .flat_map(char::to_lowercase)
+                };
+                lower(a).cmp(lower(b))
+            }
+            (x, y) => x.cmp(&y),
+        },
+    }
+}
+
+// Text of a text or error value, in pieces so rich text isn't concatenated
+fn text_parts(value: &CellValue) -> impl Iterator<Item = &str> {
+    let (text, runs) = match value {
+        CellValue::String(s) | CellValue::Formula(s) | CellValue::Error(s) => {
+            (Some(s.as_str()), &[][..])
+        }
+        CellValue::RichText(text) => (None, text.runs()),
+        _ => (None, &[][..]),
+    };
+    text.into_iter()
+        .chain(runs.iter().map(|run| run.text.as_str()))
+}
+
+// Style presets in `CellStyle::index` order
+const PRESETS: [CellStyle; CellStyle::BUILTIN_COUNT as usize] = [
+    CellStyle::Default,
+    CellStyle::HeaderBold,
+    CellStyle::NumberInteger,
+    CellStyle::NumberDecimal,
+    CellStyle::NumberCurrency,
+    CellStyle::NumberPercentage,
+    CellStyle::DateDefault,
+    CellStyle::DateTimestamp,
+    CellStyle::TextBold,
+    CellStyle::TextItalic,
+    CellStyle::HighlightYellow,
+    CellStyle::HighlightGreen,
+    CellStyle::HighlightRed,
+    CellStyle::BorderThin,
+    CellStyle::TextFormat,
+    CellStyle::Unlocked,
+];
+
+// Spooled form of a style: presets by index, custom formats after them,
+// currencies negative and the duration style below them
+fn style_code(style: CellStyle) -> i64 {
+    match style {
+        CellStyle::Duration => DURATION_STYLE_CODE,
+        CellStyle::Currency(currency) => {
+            -1 - Currency::ALL
+                .iter()
+                .position(|&c| c == currency)
+                .unwrap_or(0) as i64
+        }
+        CellStyle::NumberFormat(index) => CellStyle::BUILTIN_COUNT as i64 + index as i64,
+        preset => preset.index() as i64,
+    }
+}
+
+const DURATION_STYLE_CODE: i64 = -1 - Currency::ALL.len() as i64;
+
+fn style_from_code(code: i64) -> Option<CellStyle> {
+    if code == DURATION_STYLE_CODE {
+        return Some(CellStyle::Duration);
+    }
+    if code < 0 {
+        let currency = Currency::ALL.get((-1 - code) as usize)?;
+        return Some(CellStyle::Currency(*currency));
+    }
+    match code.checked_sub(CellStyle::BUILTIN_COUNT as i64) {
+        Some(index) if index >= 0 => Some(CellStyle::NumberFormat(u32::try_from(index).ok()?)),
+        _ => PRESETS.get(code as usize).copied(),
+    }
+}
+
+/// Rows held back by a sorting writer
+pub(crate) struct ExternalSorter {
+    options: SortOptions,
+    buffer: Vec<Vec<StyledCell>>,
+    buffered_bytes: usize,
+    spill: Option<BufWriter<File>>,
+    spilled: u64, // Bytes written to the spill file
+    runs: Vec<SpooledColumn>,
+}
+
+impl ExternalSorter {
+    pub(crate) fn new(options: SortOptions) -> Self {
+        ExternalSorter {
+            options,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            spill: None,
+            spilled: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Hold back a row, spilling a sorted run when the buffer is full
+    pub(crate) fn push(&mut self, row: &[StyledCell]) -> Result<()> {
+        self.buffered_bytes += row_size(row);
+        self.buffer.push(row.to_vec());
+        if self.buffered_bytes >= self.options.max_buffer_bytes {
+            self.spill_run()?;
+        }
+        Ok(())
+    }
+
+    // Sort the buffer and append it to the spill file as a run
+    fn spill_run(&mut self) -> Result<()> {
+        let options = &self.options;
+        self.buffer.sort_by(|a, b| options.compare(a, b));
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => {
+                let file = match &options.temp_dir {
+                    Some(dir) => tempfile::tempfile_in(dir)?,
+                    None => tempfile::tempfile()?,
+                };
+                self.spill.insert(BufWriter::with_capacity(64 * 1024, file))
+            }
+        };
+        let offset = self.spilled;
+        let mut values = 0u64;
+        for row in self.buffer.drain(..) {
+            let (bytes, row_values) = encode_row(spill, &row)?;
+            self.spilled += bytes;
+            values += row_values;
+        }
+        self.runs.push(SpooledColumn {
+            offset,
+            len: self.spilled - offset,
+            rows: values,
+        });
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// Hand every row to `emit`, in sorted order
+    pub(crate) fn drain<F>(mut self, mut emit: F) -> Result<()>
+    where
+        F: FnMut(&[StyledCell]) -> Result<()>,
+    {
+        if self.runs.is_empty() {
+            let options = &self.options;
+            self.buffer.sort_by(|a, b| options.compare(a, b));
+            for row in &self.buffer {
+                emit(row)?;
+            }
+            return Ok(());
+        }
+        if !self.buffer.is_empty() {
+            self.spill_run()?;
+        }
+        let mut file = match self.spill.take() {
+            Some(spill) => spill.into_inner().map_err(|e| e.into_error())?,
+            None => return Ok(()),
+        };
+
+        // Merge the runs, taking the smallest head; ties go to the earlier
+        // run, which holds the earlier rows
+        let chunk = (MERGE_BUFFER_BYTES / self.runs.len()).max(MIN_RUN_BUFFER);
+        let mut runs: Vec<(ColumnCursor, u64)> = self
+            .runs
+            .iter()
+            .map(|run| (ColumnCursor::new(run, chunk), run.rows))
+            .collect();
+        let mut heads = Vec::with_capacity(runs.len());
+        for (cursor, left) in &mut runs {
+            heads.push(next_row(cursor, left, &mut file)?);
+        }
+        loop {
+            let mut smallest: Option<usize> = None;
+            for (i, head) in heads.iter().enumerate() {
+                let Some(row) = head else { continue };
+                let is_smaller = match smallest.and_then(|s| heads[s].as_ref()) {
+                    Some(best) => self.options.compare(row, best) == Ordering::Less,
+                    None => true,
+                };
+                if is_smaller {
+                    smallest = Some(i);
+                }
+            }
+            let Some(i) = smallest else { break };
+            if let Some(row) = heads[i].take() {
+                emit(&row)?;
+            }
+            let (cursor, left) = &mut runs[i];
+            heads[i] = next_row(cursor, left, &mut file)?;
+        }
+        Ok(())
+    }
+}
+
+// Append a row to a spill file: its length, then each value and style code.
+// Returns the bytes and values written
+pub(crate) fn encode_row<W: Write>(out: &mut W, row: &[StyledCell]) -> Result<(u64, u64)> {
+    let mut bytes = encode(out, &CellValue::Int(row.len() as i64))?;
+    for cell in row {
+        bytes += encode(out, &cell.value)?;
+        bytes += encode(out, &CellValue::Int(style_code(cell.style)))?;
+    }
+    Ok((bytes, 1 + 2 * row.len() as u64))
+}
+
+// The next row of a run, None once its `left` values are used up
+pub(crate) fn next_row<R: Read + Seek>(
+    cursor: &mut ColumnCursor,
+    left: &mut u64,
+    file: &mut R,
+) -> Result<Option<Vec<StyledCell>>> {
+    if *left == 0 {
+        return Ok(None);
+    }
+    let len = match cursor.next_value(file)? {
+        CellValue::Int(len) if len >= 0 => len as usize,
+        _ => return Err(corrupt_spill()),
+    };
+    let mut row = Vec::with_capacity(len);
+    for _ in 0..len {
+        let value = cursor.next_value(file)?;
+        let style = match cursor.next_value(file)? {
+            CellValue::Int(code) => style_from_code(code).ok_or_else(corrupt_spill)?,
+            _ => return Err(corrupt_spill()),
+        };
+        row.push(StyledCell::new(value, style));
+    }
+    *left = left.saturating_sub(1 + 2 * len as u64);
+    Ok(Some(row))
+}
+
+// Rough memory held by a buffered row
+fn row_size(row: &[StyledCell]) -> usize {
+    let text = |value: &CellValue| match value {
+        CellValue::String(s) | CellValue::Formula(s) | CellValue::Error(s) => s.len(),
+        _ => 0,
+    };
+    24 + row
+        .iter()
+        .map(|cell| std::mem::size_of::<StyledCell>() + text(&cell.value))
+        .sum::<usize>()
+}
+
+fn corrupt_spill() -> ExcelError {
+    ExcelError::InvalidState("Row spill file is corrupt".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[CellValue]) -> Vec<StyledCell> {
+        values
+            .iter()
+            .map(|v| StyledCell::new(v.clone(), CellStyle::Default))
+            .collect()
+    }
+
+    #[test]
+    fn test_style_codes_round_trip() {
+        let styles = PRESETS.into_iter().chain([
+            CellStyle::Currency(Currency::VND),
+            CellStyle::Currency(Currency::USD),
+            CellStyle::Duration,
+            CellStyle::NumberFormat(3),
+            CellStyle::NumberFormat(CellStyle::BUILTIN_COUNT + 7),
+        ]);
+        for style in styles {
+            assert_eq!(style_from_code(style_code(style)), Some(style));
+        }
+    }
+
+    #[test]
+    fn test_compare_like_excel() {
+        let options = SortOptions::by(0);
+        let sorted = |mut rows: Vec<Vec<StyledCell>>, options: &SortOptions| {
+            rows.sort_by(|a, b| options.compare(a, b));
+            rows.into_iter()
+                .map(|r| r.first().map_or(CellValue::Empty, |c| c.value.clone()))
+                .collect::<Vec<_>>()
+        };
+        let rows = vec![
+            row(&[CellValue::from("b")]),
+            row(&[]),
+            row(&[CellValue::Bool(false)]),
+            row(&[CellValue::Float(2.5)]),
+            row(&[CellValue::from("A")]),
+            row(&[CellValue::Int(2)]),
+        ];
+        assert_eq!(
+            sorted(rows.clone(), &options),
+            [
+                CellValue::Int(2),
+                CellValue::Float(2.5),
+                CellValue::from("A"),
+                CellValue::from("b"),
+                CellValue::Bool(false),
+                CellValue::Empty,
+            ]
+        );
+        assert_eq!(
+            sorted(rows, &SortOptions::by_desc(0)),
+            [
+                CellValue::Bool(false),
+                CellValue::from("b"),
+                CellValue::from("A"),
+                CellValue::Float(2.5),
+                CellValue::Int(2),
+                CellValue::Empty,
+            ]
+        );
+
+        // Case-insensitive across rich text runs
+        let rich = CellValue::from(crate::types::RichText::new().bold("Ab").plain("C"));
+        assert_eq!(
+            compare_values(&rich, &CellValue::from("abc")),
+            Ordering::Equal
+        );
+        assert_eq!(
+            compare_values(&rich, &CellValue::from("ABD")),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_values(&CellValue::from("ab"), &rich),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_external_merge_is_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = SortOptions::by(0)
+            .with_max_buffer_bytes(1)
+            .with_temp_dir(dir.path());
+        let mut sorter = ExternalSorter::new(options.clone().with_max_buffer_bytes(600));
+        let mut expected = Vec::new();
+        for i in 0..100i64 {
+            let cells = [
+                CellValue::Int((i * 37) % 10),
+                CellValue::Int(i),
+                CellValue::from("x".repeat(i as usize % 7)),
+            ];
+            let mut r = row(&cells);
+            r[2].style = CellStyle::Currency(Currency::EUR);
+            sorter.push(&r).unwrap();
+            expected.push(r);
+        }
+        assert!(sorter.runs.len() > 2);
+        expected.sort_by(|a, b| options.compare(a, b));
+
+        let mut emitted = Vec::new();
+        sorter
+            .drain(|r| {
+                emitted.push(r.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(emitted, expected);
+    }
+}
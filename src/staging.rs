@@ -0,0 +1,213 @@
+//! Temp-file staging for outputs that must not be left half-written
+//!
+//! A [`StagedFile`] is written in full to a temp file and only moved over the
+//! target path by [`StagedFile::commit`]. If it is dropped first (an error, an
+//! early return or a panic unwinding through the writer), the temp file is
+//! deleted and the target is left untouched.
+//!
+//! Also home to the disk-space helpers behind [`ExcelError::DiskFull`].
+
+use crate::error::{ExcelError, Result};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+pub(crate) struct StagedFile {
+    temp: NamedTempFile,
+    target: PathBuf,
+}
+
+impl StagedFile {
+    /// Create the temp file in `temp_dir`, or next to `target` when None
+    ///
+    /// Staging next to the target keeps the final commit a plain rename.
+    pub(crate) fn new(target: &Path, temp_dir: Option<&Path>) -> Result<Self> {
+        let temp = NamedTempFile::new_in(write_dir(target, temp_dir))?;
+        Ok(StagedFile {
+            temp,
+            target: target.to_path_buf(),
+        })
+    }
+
+    /// Writable handle to the temp file
+    pub(crate) fn file(&self) -> Result<File> {
+        Ok(self.temp.as_file().try_clone()?)
+    }
+
+    /// Path of the temp file while it is being written
+    pub(crate) fn path(&self) -> &Path {
+        self.temp.path()
+    }
+
+    /// Move the finished temp file over the target
+    ///
+    /// When the rename fails, e.g. because the temp directory is on a
+    /// different filesystem, the file is copied next to the target first and
+    /// renamed from there, so the target is never left half-written.
+    pub(crate) fn commit(self) -> Result<()> {
+        let err = match self.temp.persist(&self.target) {
+            Ok(_) => return Ok(()),
+            Err(err) => err,
+        };
+
+        let mut sibling = NamedTempFile::new_in(write_dir(&self.target, None))?;
+        let copied = File::open(err.file.path())
+            .and_then(|mut source| std::io::copy(&mut source, &mut sibling));
+        if let Err(e) = copied {
+            let sibling = StagedFile {
+                temp: sibling,
+                target: self.target.clone(),
+            };
+            return Err(discard_partial(&self.target, Some(sibling), e.into()));
+        }
+        sibling.persist(&self.target).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+/// Directory an output for `target` is written in: `temp_dir` when staging,
+/// otherwise the target's own directory
+pub(crate) fn write_dir<'a>(target: &'a Path, temp_dir: Option<&'a Path>) -> &'a Path {
+    temp_dir.unwrap_or_else(|| match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    })
+}
+
+/// Whether `err` is an IO error saying the filesystem (or quota) is full
+pub(crate) fn is_disk_full(err: &ExcelError) -> bool {
+    matches!(
+        err,
+        ExcelError::IoError(e) if matches!(
+            e.kind(),
+            std::io::ErrorKind::StorageFull | std::io::ErrorKind::QuotaExceeded
+        )
+    )
+}
+
+/// Remove the partial output after the disk filled up and turn `err` into
+/// [`ExcelError::DiskFull`]
+///
+/// The partial output is the staged temp file when there is one, otherwise
+/// the target itself. Errors other than running out of space are returned
+/// unchanged (a staged temp file is still dropped).
+pub(crate) fn discard_partial(
+    target: &Path,
+    staged: Option<StagedFile>,
+    err: ExcelError,
+) -> ExcelError {
+    if !is_disk_full(&err) {
+        return err;
+    }
+
+    let partial = staged.as_ref().map_or(target, |s| s.path()).to_path_buf();
+    let needed = std::fs::metadata(&partial).map_or(0, |m| m.len());
+    match staged {
+        Some(staged) => drop(staged),
+        None => {
+            if std::fs::metadata(target).is_ok_and(|m| m.is_file()) {
+                let _ = std::fs::remove_file(target);
+            }
+        }
+    }
+
+    ExcelError::DiskFull {
+        needed,
+        available: available_space(write_dir(&partial, None)).unwrap_or(0),
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `dir`
+///
+/// None when the platform or filesystem can't tell.
+#[cfg(unix)]
+pub(crate) fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a plain
+    // C struct that statvfs fills in on success
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)] // field widths differ between platforms
+    Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Fail with [`ExcelError::DiskFull`] if `dir` has less than `needed` bytes free
+///
+/// Passes when the free space can't be determined.
+pub(crate) fn ensure_space(dir: &Path, needed: u64) -> Result<()> {
+    match available_space(dir) {
+        Some(available) if available < needed => Err(ExcelError::DiskFull { needed, available }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_commit_and_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let scratch = tempfile::tempdir().unwrap();
+        let target = dir.path().join("out.xlsx");
+
+        // Dropped without commit: nothing reaches the target, temp file removed
+        let staged = StagedFile::new(&target, Some(scratch.path())).unwrap();
+        assert!(staged.path().starts_with(scratch.path()));
+        staged.file().unwrap().write_all(b"partial").unwrap();
+        drop(staged);
+        assert!(!target.exists());
+        assert_eq!(std::fs::read_dir(scratch.path()).unwrap().count(), 0);
+
+        let staged = StagedFile::new(&target, None).unwrap();
+        assert!(staged.path().starts_with(dir.path()));
+        staged.file().unwrap().write_all(b"done").unwrap();
+        staged.commit().unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"done");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_disk_space_helpers() {
+        assert_eq!(write_dir(Path::new("out.xlsx"), None), Path::new("."));
+        assert_eq!(write_dir(Path::new("a/out.xlsx"), None), Path::new("a"));
+        assert_eq!(
+            write_dir(Path::new("a/out.xlsx"), Some(Path::new("/scratch"))),
+            Path::new("/scratch")
+        );
+
+        let full = || ExcelError::IoError(std::io::ErrorKind::StorageFull.into());
+        assert!(is_disk_full(&full()));
+        assert!(!is_disk_full(&ExcelError::IoError(
+            std::io::ErrorKind::PermissionDenied.into()
+        )));
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("out.xlsx");
+        std::fs::write(&target, b"partial").unwrap();
+        let err = discard_partial(&target, None, full());
+        assert!(matches!(err, ExcelError::DiskFull { needed: 7, .. }));
+        assert!(!target.exists());
+
+        assert!(ensure_space(dir.path(), 0).is_ok());
+        if available_space(dir.path()).is_some() {
+            assert!(matches!(
+                ensure_space(dir.path(), u64::MAX),
+                Err(ExcelError::DiskFull {
+                    needed: u64::MAX,
+                    ..
+                })
+            ));
+        }
+    }
+}
@@ -18,11 +18,31 @@
 //! - Sequential read only (can't jump to random rows)
 //! - Best for: Fast iteration, simple data extraction, no formatting needs
 
+use crate::cellref;
 use crate::error::{ExcelError, Result};
 use crate::fast_writer::StreamingZipReader;
-use crate::types::{CellValue, Row};
+#[cfg(test)]
+use crate::fast_writer::StreamingZipWriter;
+use crate::infer::TypeInference;
+use crate::middleware::{ReadMiddleware, ReadMiddlewareStack};
+use crate::numfmt::{builtin_format_code, Locale};
+use crate::ooxml;
+use crate::profile::{Profiler, SheetProfile};
+use crate::signature::{self, DigestStatus, SignatureInfo, SignedReference};
+use crate::types::{
+    serial_to_duration, BorrowedCell, BorrowedRow, CalcMode, CalcSettings, Cell, CellFormat,
+    CellRange, CellValue, Decimal, DefinedName, ExternalLink, FormattedCell, HeaderFooter,
+    HeaderFooterImage, HeaderFooterPosition, Hyperlink, Record, Row, SheetImage, SheetVisibility,
+    TableInfo, WorkbookProperties, WorkbookStats,
+};
+use sha2::Digest;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 
 /// Parse Excel date serial number to ISO date or datetime string
 /// Excel stores dates as floating point numbers representing days since 1900-01-01
@@ -34,7 +54,7 @@ use std::path::Path;
 /// This means dates from March 1, 1900 onwards are off by 1 day in Excel's count.
 ///
 /// Performance: O(1) using 400-year cycle math (no loops for distant dates)
-fn parse_excel_date(serial: f64) -> String {
+pub(crate) fn parse_excel_date(serial: f64) -> String {
     // Handle invalid dates
     if !(1.0..=2958465.999).contains(&serial) {
         // 2958465 = December 31, 9999
@@ -130,6 +150,388 @@ fn is_leap_year(year: i64) -> bool {
     (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
 }
 
+/// How invalid UTF-8 in workbook XML is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Policy {
+    /// Fail with [`ExcelError::InvalidUtf8`] on the first invalid sequence
+    Strict,
+    /// Replace invalid sequences with U+FFFD silently (default)
+    #[default]
+    Lossy,
+    /// Replace invalid sequences with U+FFFD and record each one,
+    /// see [`StreamingReader::utf8_issues`]
+    ReplaceAndReport,
+}
+
+/// How rows without any values are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlankRows {
+    /// Yield blank rows, including rows missing from the XML between two
+    /// stored rows, so row positions match what Excel shows (default)
+    #[default]
+    Fill,
+    /// Yield only rows stored in the XML, even if they hold no values
+    Keep,
+    /// Skip rows whose cells are all empty
+    Skip,
+}
+
+/// How cells missing from the XML within a row are represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingCells {
+    /// Pad gaps with [`CellValue::Empty`] so `cells[i]` is column `i` (default)
+    #[default]
+    Empty,
+    /// Only return cells stored in the XML, in column order
+    Omit,
+}
+
+/// What formula cells referencing other workbooks read as
+///
+/// Their stored value is the result Excel computed the last time the other
+/// workbook was available, which may be long out of date. See
+/// [`StreamingReader::external_links`] for the workbooks and
+/// [`cellref::external_refs`] for the references. Shared formulas are only
+/// recognized in the cell that stores their text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExternalValues {
+    /// The cached result, like any other formula cell (default)
+    #[default]
+    Cached,
+    /// [`CellValue::Empty`]
+    Empty,
+    /// [`CellValue::Formula`] with the formula text, so callers can resolve
+    /// the references themselves, e.g. with
+    /// [`ExternalLink::resolve_formula`]
+    Formula,
+}
+
+/// Safeguards against malicious or corrupt files
+///
+/// A small, highly compressed xlsx can expand to gigabytes of XML. These limits
+/// make the reader fail with [`ExcelError::LimitExceeded`] instead of
+/// exhausting memory. Defaults are far above what real workbooks need.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Max uncompressed size of any single ZIP entry (default: 4 GiB)
+    pub max_entry_size: u64,
+    /// Max number of shared strings (default: 50 million)
+    pub max_shared_strings: usize,
+    /// Max length of one cell's text in bytes (default: 128 KiB; Excel
+    /// itself caps cells at 32,767 characters)
+    pub max_cell_text_len: usize,
+    /// Max XML buffered while looking for the end of one `<row>` (default: 64 MiB)
+    ///
+    /// The row scanner is not recursive, so this bounds deeply nested or
+    /// unterminated markup as well as oversized rows.
+    pub max_row_bytes: usize,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        ReadLimits {
+            max_entry_size: 4 * 1024 * 1024 * 1024,
+            max_shared_strings: 50_000_000,
+            max_cell_text_len: 128 * 1024,
+            max_row_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl ReadLimits {
+    /// Disable all limits (only for trusted input)
+    pub fn unlimited() -> Self {
+        ReadLimits {
+            max_entry_size: u64::MAX,
+            max_shared_strings: usize::MAX,
+            max_cell_text_len: usize::MAX,
+            max_row_bytes: usize::MAX,
+        }
+    }
+
+    fn check(what: impl Into<String>, value: u64, limit: u64) -> Result<()> {
+        if value > limit {
+            return Err(ExcelError::LimitExceeded {
+                what: what.into(),
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_cell_text(&self, text: &str) -> Result<()> {
+        Self::check(
+            "cell text",
+            text.len() as u64,
+            self.max_cell_text_len as u64,
+        )
+    }
+}
+
+/// How [`StreamingReader::rows_with_header`] handles a header name that
+/// appears more than once
+///
+/// Blank header cells are never treated as duplicates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateHeaders {
+    /// Keep the names as they are; lookups by name find the first column
+    /// (default)
+    #[default]
+    KeepFirst,
+    /// Rename repeats with a column-count suffix: "Amount", "Amount_2",
+    /// "Amount_3", skipping names already in the header row
+    Suffix,
+    /// Fail with [`ExcelError::InvalidFormat`] naming both columns
+    Error,
+}
+
+/// An invalid UTF-8 sequence found (and replaced) while reading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utf8Issue {
+    /// ZIP entry the sequence was found in (e.g. "xl/worksheets/sheet1.xml")
+    pub part: String,
+    /// Byte offset of the sequence within the uncompressed entry
+    pub offset: u64,
+    /// Number of invalid bytes replaced
+    pub len: usize,
+}
+
+/// Options controlling how [`StreamingReader`] decodes worksheet data
+///
+/// # Example
+///
+/// ```no_run
+/// use excelstream::streaming_reader::{ReaderOptions, StreamingReader};
+///
+/// let options = ReaderOptions::new().with_numbers_as_decimal(true);
+/// let mut reader = StreamingReader::open_with_options("prices.xlsx", options)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReaderOptions {
+    /// Read numeric cells as [`CellValue::Decimal`] from their exact XML text
+    /// instead of going through `f64` (default: false)
+    pub numbers_as_decimal: bool,
+    /// How invalid UTF-8 is handled (default: [`Utf8Policy::Lossy`])
+    pub utf8_policy: Utf8Policy,
+    /// Keep empty cells after the last value in a row (default: false, as
+    /// Excel ignores formatted-but-empty trailing cells)
+    pub keep_trailing_empty: bool,
+    /// How blank rows are handled (default: [`BlankRows::Fill`])
+    pub blank_rows: BlankRows,
+    /// How missing cells are represented (default: [`MissingCells::Empty`])
+    pub missing_cells: MissingCells,
+    /// Size limits guarding against zip bombs
+    pub limits: ReadLimits,
+    /// Copy the top-left value of each merged range into all cells it covers
+    /// (default: false, other merged cells read as empty like in the XML)
+    pub fill_merged_cells: bool,
+    /// Normalization applied to every row before it is yielded
+    pub middleware: ReadMiddlewareStack,
+    /// Parse text cells into numbers, booleans and dates, after the
+    /// middleware has run (default: off)
+    pub infer_types: Option<TypeInference>,
+    /// Rows at the top of the sheet that are never yielded, e.g. a title
+    /// banner (default: 0)
+    pub skip_rows: u32,
+    /// 0-based row holding the column names; rows above it are skipped
+    /// (default: the first row not skipped)
+    pub header_row: Option<u32>,
+    /// What formula cells referencing other workbooks read as (default:
+    /// [`ExternalValues::Cached`])
+    pub external_values: ExternalValues,
+    /// How repeated header names are handled (default:
+    /// [`DuplicateHeaders::KeepFirst`])
+    pub duplicate_headers: DuplicateHeaders,
+    /// Look up record cells by header name ignoring case, which also makes
+    /// "Amount" and "amount" duplicates (default: false)
+    pub case_insensitive_headers: bool,
+}
+
+impl ReaderOptions {
+    /// Create options with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how invalid UTF-8 is handled
+    pub fn with_utf8_policy(mut self, policy: Utf8Policy) -> Self {
+        self.utf8_policy = policy;
+        self
+    }
+
+    /// Read numeric cells as exact decimals
+    pub fn with_numbers_as_decimal(mut self, enabled: bool) -> Self {
+        self.numbers_as_decimal = enabled;
+        self
+    }
+
+    /// Keep empty cells after the last value in a row
+    pub fn with_keep_trailing_empty(mut self, enabled: bool) -> Self {
+        self.keep_trailing_empty = enabled;
+        self
+    }
+
+    /// Set how blank rows are handled
+    pub fn with_blank_rows(mut self, blank_rows: BlankRows) -> Self {
+        self.blank_rows = blank_rows;
+        self
+    }
+
+    /// Set how missing cells are represented
+    pub fn with_missing_cells(mut self, missing_cells: MissingCells) -> Self {
+        self.missing_cells = missing_cells;
+        self
+    }
+
+    /// Set size limits
+    pub fn with_limits(mut self, limits: ReadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Copy merged ranges' top-left value into every cell they cover
+    ///
+    /// Requires an extra pass over the sheet, since Excel stores merges after
+    /// the cell data.
+    pub fn with_fill_merged_cells(mut self, enabled: bool) -> Self {
+        self.fill_merged_cells = enabled;
+        self
+    }
+
+    /// Add a normalization step, run on each row after the earlier ones
+    ///
+    /// Share one set of options across an ingestion pipeline so every file
+    /// is normalized the same way.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::middleware::{CoerceDates, NullValues, RenameHeaders, TrimWhitespace};
+    /// use excelstream::streaming_reader::{ReaderOptions, StreamingReader};
+    ///
+    /// let options = ReaderOptions::new()
+    ///     .with_middleware(TrimWhitespace)
+    ///     .with_middleware(NullValues::default())
+    ///     .with_middleware(CoerceDates::new([2]).with_formats(["%d/%m/%Y"]))
+    ///     .with_middleware(RenameHeaders::new([("Cust Name", "customer")]));
+    ///
+    /// let mut reader = StreamingReader::open_with_options("import.xlsx", options.clone())?;
+    /// for row in reader.rows("Sheet1")? {
+    ///     println!("{:?}", row?.to_strings());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_middleware<M: ReadMiddleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Parse text cells into `Int`, `Float`, `Bool` and `DateTime` values
+    /// with the default [`TypeInference`] rules (decimal point, ISO dates)
+    pub fn with_infer_types(mut self, enabled: bool) -> Self {
+        self.infer_types = enabled.then(TypeInference::new);
+        self
+    }
+
+    /// Parse text cells with custom rules, e.g. decimal commas
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::infer::TypeInference;
+    /// use excelstream::streaming_reader::{ReaderOptions, StreamingReader};
+    ///
+    /// let options = ReaderOptions::new().with_type_inference(TypeInference::decimal_comma());
+    /// let mut reader = StreamingReader::open_with_options("umsatz.xlsx", options)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_type_inference(mut self, rules: TypeInference) -> Self {
+        self.infer_types = Some(rules);
+        self
+    }
+
+    /// Parse text cells using a locale's separators and date order, see
+    /// [`Locale::type_inference`]
+    pub fn with_locale(self, locale: &Locale) -> Self {
+        self.with_type_inference(locale.type_inference())
+    }
+
+    /// Skip the first `n` rows of every sheet read
+    ///
+    /// Skipped rows don't reach the middleware. Row indices stay those of
+    /// the sheet, so the first row yielded has index `n`.
+    pub fn with_skip_rows(mut self, n: u32) -> Self {
+        self.skip_rows = n;
+        self
+    }
+
+    /// Set the 0-based row holding the column names, skipping the rows
+    /// above it
+    ///
+    /// The header row itself is still yielded by `rows()`;
+    /// [`StreamingReader::rows_with_header`] consumes it instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::{ReaderOptions, StreamingReader};
+    ///
+    /// // Report title in row 1, generation date in row 2, headers in row 4
+    /// let options = ReaderOptions::new().with_header_row(3);
+    /// let mut reader = StreamingReader::open_with_options("report.xlsx", options)?;
+    /// for record in reader.rows_with_header("Sheet1")? {
+    ///     let record = record?;
+    ///     println!("{:?}", record.get("Amount"));
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_header_row(mut self, index: u32) -> Self {
+        self.header_row = Some(index);
+        self
+    }
+
+    /// Set how repeated header names are handled
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::{DuplicateHeaders, ReaderOptions, StreamingReader};
+    ///
+    /// let options = ReaderOptions::new()
+    ///     .with_duplicate_headers(DuplicateHeaders::Suffix)
+    ///     .with_case_insensitive_headers(true);
+    /// let mut reader = StreamingReader::open_with_options("partner.xlsx", options)?;
+    /// for record in reader.rows_with_header("Sheet1")? {
+    ///     let record = record?;
+    ///     // Headers "Amount", "AMOUNT" read as "Amount", "AMOUNT_2"
+    ///     println!("{:?} {:?}", record.get("amount"), record.get("amount_2"));
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_duplicate_headers(mut self, policy: DuplicateHeaders) -> Self {
+        self.duplicate_headers = policy;
+        self
+    }
+
+    /// Look up record cells by header name ignoring case
+    pub fn with_case_insensitive_headers(mut self, enabled: bool) -> Self {
+        self.case_insensitive_headers = enabled;
+        self
+    }
+
+    /// Set what formula cells referencing other workbooks read as
+    pub fn with_external_values(mut self, external_values: ExternalValues) -> Self {
+        self.external_values = external_values;
+        self
+    }
+
+    // First row handed out by row iterators
+    fn first_row(&self) -> u32 {
+        self.skip_rows.max(self.header_row.unwrap_or(0))
+    }
+}
+
 /// Streaming reader for XLSX files
 ///
 /// **Memory Usage:**
@@ -148,9 +550,14 @@ fn is_leap_year(year: i64) -> bool {
 /// - Simple data extraction without formatting
 pub struct StreamingReader {
     archive: StreamingZipReader,
-    sst: Vec<String>,
+    path: PathBuf, // Reopened by sheet() and par_sheets workers
+    sst: SharedStrings,
     sheet_names: Vec<String>,
     sheet_paths: Vec<String>,
+    options: ReaderOptions,
+    utf8_issues: Vec<Utf8Issue>,
+    formats: Option<Vec<Arc<CellFormat>>>, // cellXfs from styles.xml, loaded on first use
+    sheet_tails: Vec<(String, String)>,    // Worksheet XML after </sheetData>, by path
 }
 
 impl StreamingReader {
@@ -179,31 +586,77 @@ impl StreamingReader {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let mut archive = StreamingZipReader::open(path)
+        Self::open_with_options(path, ReaderOptions::default())
+    }
+
+    /// Open XLSX file for streaming read with custom [`ReaderOptions`]
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: ReaderOptions) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut archive = StreamingZipReader::open(&path)
             .map_err(|e| ExcelError::ReadError(format!("Failed to open ZIP: {}", e)))?;
 
-        // Load Shared Strings Table (can't avoid this)
-        let sst = Self::load_shared_strings(&mut archive)?;
+        let mut utf8_issues = Vec::new();
 
-        println!(
-            "📊 Loaded {} shared strings (~{:.2} MB in memory)",
-            sst.len(),
-            Self::estimate_sst_size(&sst) as f64 / (1024.0 * 1024.0)
-        );
+        // Load Shared Strings Table (can't avoid this)
+        let sst = Self::load_shared_strings(&mut archive, &options, &mut utf8_issues)?;
 
         // Load sheet names and paths from workbook.xml
-        let (sheet_names, sheet_paths) = Self::load_sheet_info(&mut archive)?;
-
-        println!("📋 Found {} sheets: {:?}", sheet_names.len(), sheet_names);
+        let (sheet_names, sheet_paths) =
+            Self::load_sheet_info(&mut archive, &options, &mut utf8_issues)?;
 
         Ok(StreamingReader {
             archive,
+            path,
             sst,
             sheet_names,
             sheet_paths,
+            options,
+            utf8_issues,
+            formats: None,
+            sheet_tails: Vec::new(),
         })
     }
 
+    // Sheet names of a workbook, without loading its shared strings
+    pub(crate) fn sheet_names_of<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+        let mut archive = StreamingZipReader::open(path.as_ref())
+            .map_err(|e| ExcelError::ReadError(format!("Failed to open ZIP: {}", e)))?;
+        let (sheet_names, _) =
+            Self::load_sheet_info(&mut archive, &ReaderOptions::default(), &mut Vec::new())?;
+        Ok(sheet_names)
+    }
+
+    /// Options this reader was opened with
+    pub fn options(&self) -> &ReaderOptions {
+        &self.options
+    }
+
+    /// Number of entries in the shared strings table
+    pub fn shared_string_count(&self) -> usize {
+        self.sst.len()
+    }
+
+    /// Approximate memory held by the shared strings table, in bytes
+    ///
+    /// The table is loaded fully on open; this helps diagnose files whose
+    /// memory use is dominated by unique strings.
+    pub fn shared_strings_memory(&self) -> usize {
+        self.sst.memory()
+    }
+
+    // The shared strings table, once the reader is no longer needed
+    pub(crate) fn into_shared_strings(self) -> SharedStrings {
+        self.sst
+    }
+
+    /// Invalid UTF-8 sequences replaced so far
+    ///
+    /// Only populated with [`Utf8Policy::ReplaceAndReport`]. Worksheet issues
+    /// are added as rows are read.
+    pub fn utf8_issues(&self) -> &[Utf8Issue] {
+        &self.utf8_issues
+    }
+
     /// Get list of sheet names
     ///
     /// Returns the names of all worksheets in the workbook.
@@ -265,6 +718,164 @@ impl StreamingReader {
         Ok((row_count, max_cols))
     }
 
+    /// Per-column statistics for a sheet in one streaming pass
+    ///
+    /// When `has_header` is true the first row supplies column names and is
+    /// not counted as data. See [`crate::profile`] for the collected stats.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("import.xlsx")?;
+    /// let profile = reader.profile("Sheet1", true)?;
+    /// for column in &profile.columns {
+    ///     println!(
+    ///         "{:?}: {:?}, {} nulls, ~{} distinct",
+    ///         column.name,
+    ///         column.types.dominant(),
+    ///         column.null_count,
+    ///         column.distinct_estimate
+    ///     );
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn profile(&mut self, sheet_name: &str, has_header: bool) -> Result<SheetProfile> {
+        let mut rows = self.stream_rows(sheet_name)?;
+        let mut profiler = Profiler::new();
+        if has_header {
+            if let Some(header) = rows.next() {
+                profiler = profiler.with_header(header?.iter().map(|c| c.as_string()).collect());
+            }
+        }
+        for row in rows {
+            profiler.add_row(&row?);
+        }
+        Ok(profiler.finish())
+    }
+
+    /// Fast row count for progress estimation
+    ///
+    /// Trusts the sheet's `<dimension>` when present, otherwise scans only the
+    /// `<row r="...">` tags without parsing cells or resolving shared strings.
+    /// Returns the last used row number, i.e. the number of rows yielded with
+    /// the default [`BlankRows::Fill`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("large.xlsx")?;
+    /// let total = reader.row_count_fast("Sheet1")?;
+    /// for (i, row) in reader.rows("Sheet1")?.enumerate() {
+    ///     let _row = row?;
+    ///     if i % 100_000 == 0 {
+    ///         println!("{}/{}", i, total);
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn row_count_fast(&mut self, sheet_name: &str) -> Result<usize> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let limits = &self.options.limits;
+        check_declared_size(&self.archive, &sheet_path, limits)?;
+        let mut reader = self
+            .archive
+            .read_entry_streaming_by_name(&sheet_path)
+            .map_err(|e| ExcelError::ReadError(format!("Failed to open sheet: {}", e)))?;
+
+        let mut chunk = vec![0u8; 64 * 1024];
+        let mut window: Vec<u8> = Vec::new();
+        let mut in_sheet_data = false;
+        let mut row_tags = 0usize;
+        let mut last_row = 0usize;
+        let mut bytes_read = 0u64;
+        let mut prefixes: Option<XmlPrefixes> = None;
+        let mut row_open = b"<row".to_vec();
+
+        loop {
+            let n = reader
+                .read(&mut chunk)
+                .map_err(|e| ExcelError::ReadError(format!("Failed to read XML: {}", e)))?;
+            if n == 0 {
+                break;
+            }
+            bytes_read += n as u64;
+            ReadLimits::check(
+                format!("'{}' uncompressed size", sheet_path),
+                bytes_read,
+                limits.max_entry_size,
+            )?;
+            window.extend_from_slice(&chunk[..n]);
+
+            if !in_sheet_data {
+                let header_limit = || {
+                    ReadLimits::check(
+                        "worksheet header XML",
+                        window.len() as u64,
+                        limits.max_row_bytes as u64,
+                    )
+                };
+                if prefixes.is_none() {
+                    prefixes = XmlPrefixes::detect(&String::from_utf8_lossy(&window));
+                }
+                let Some(prefixes) = &prefixes else {
+                    header_limit()?;
+                    continue;
+                };
+                let sheet_data = format!("<{}sheetData", prefixes.main);
+                let Some(data_start) = find_bytes(&window, sheet_data.as_bytes()) else {
+                    header_limit()?;
+                    continue;
+                };
+                row_open = format!("<{}row", prefixes.main).into_bytes();
+                // A real <dimension> (not the "A1" placeholder) gives the answer
+                let header = String::from_utf8_lossy(&window[..data_start]);
+                let header = prefixes.normalize(&header);
+                let used = xml_elements(&header, "dimension")
+                    .first()
+                    .and_then(|el| xml_attr(el, "ref"))
+                    .filter(|r| r.contains(':'))
+                    .and_then(|r| r.parse::<CellRange>().ok());
+                if let Some(range) = used {
+                    return Ok(range.last_row as usize + 1);
+                }
+                window.drain(..data_start);
+                in_sheet_data = true;
+            }
+
+            // Scan complete <row ...> start tags, keep any partial tag for the next chunk
+            let mut pos = 0;
+            while let Some(start) = find_bytes(&window[pos..], &row_open) {
+                let start = pos + start;
+                let Some(end) = window[start..].iter().position(|&b| b == b'>') else {
+                    break;
+                };
+                let tag = &window[start..start + end];
+                if tag
+                    .get(row_open.len())
+                    .is_none_or(|&b| b == b'/' || b.is_ascii_whitespace())
+                {
+                    row_tags += 1;
+                    let r = std::str::from_utf8(tag)
+                        .ok()
+                        .and_then(|tag| xml_attr(tag, "r")?.parse::<usize>().ok());
+                    last_row = last_row.max(r.unwrap_or(row_tags));
+                }
+                pos = start + end + 1;
+            }
+            let keep_from = match find_bytes(&window[pos..], &row_open) {
+                Some(partial) => pos + partial,
+                None => window.len().saturating_sub(3).max(pos),
+            };
+            window.drain(..keep_from);
+        }
+
+        Ok(last_row)
+    }
+
     /// Stream rows from a worksheet
     ///
     /// # Memory Usage
@@ -296,19 +907,15 @@ impl StreamingReader {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn stream_rows(&mut self, sheet_name: &str) -> Result<RowIterator<'_>> {
-        // Find sheet path by name
-        let sheet_path = self
-            .sheet_names
-            .iter()
-            .position(|name| name == sheet_name)
-            .and_then(|idx| self.sheet_paths.get(idx))
-            .ok_or_else(|| {
-                ExcelError::ReadError(format!(
-                    "Sheet '{}' not found. Available sheets: {:?}",
-                    sheet_name, self.sheet_names
-                ))
-            })?
-            .clone();
+        let sheet_path = self.sheet_path(sheet_name)?;
+
+        let merges = if self.options.fill_merged_cells {
+            self.scan_merged_ranges(&sheet_path)?
+        } else {
+            Vec::new()
+        };
+
+        check_declared_size(&self.archive, &sheet_path, &self.options.limits)?;
 
         // Get streaming reader for worksheet XML
         let reader = self
@@ -316,444 +923,3972 @@ impl StreamingReader {
             .read_entry_streaming_by_name(&sheet_path)
             .map_err(|e| ExcelError::ReadError(format!("Failed to open sheet: {}", e)))?;
 
-        Ok(RowIterator {
-            reader: BufReader::with_capacity(64 * 1024, reader), // 64KB buffer
-            sst: &self.sst,
-            buffer: String::with_capacity(128 * 1024), // 128KB for XML parsing
-            pos: 0,
-        })
+        Ok(RowIterator::new(
+            reader,
+            &self.sst,
+            &self.options,
+            &sheet_path,
+            &mut self.utf8_issues,
+            merges,
+        ))
     }
 
-    /// Alias for `stream_rows()` for backward compatibility
+    /// Open one sheet for reading independently of the others
     ///
-    /// This method provides the same functionality as `stream_rows()` but uses
-    /// the more familiar `rows()` name that matches the old calamine-based API.
-    /// Returns an iterator of `Row` structs for full API compatibility.
+    /// The returned [`SheetReader`] reopens the file with its own ZIP entry
+    /// reader and only borrows the reader immutably, so rows of several
+    /// sheets can be consumed at the same time, e.g. to join two sorted
+    /// sheets. Opening costs one more file handle and a read of the ZIP
+    /// directory.
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use excelstream::ExcelReader;
+    /// use excelstream::streaming_reader::StreamingReader;
     ///
-    /// let mut reader = ExcelReader::open("large.xlsx")?;
-    /// for row_result in reader.rows("Sheet1")? {
-    ///     let row = row_result?;
-    ///     println!("Row {}: {:?}", row.index, row.to_strings());
+    /// let reader = StreamingReader::open("orders.xlsx")?;
+    /// let mut orders = reader.sheet("Orders")?;
+    /// let mut customers = reader.sheet("Customers")?;
+    /// let pairs = orders.rows()?.zip(customers.rows()?);
+    /// for (order, customer) in pairs {
+    ///     println!("{:?} / {:?}", order?.to_strings(), customer?.to_strings());
     /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn rows(&mut self, sheet_name: &str) -> Result<RowStructIterator<'_>> {
-        let inner = self.stream_rows(sheet_name)?;
-        Ok(RowStructIterator {
-            inner,
-            row_index: 0,
+    pub fn sheet(&self, sheet_name: &str) -> Result<SheetReader<'_>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let archive = StreamingZipReader::open(&self.path)
+            .map_err(|e| ExcelError::ReadError(format!("Failed to open ZIP: {}", e)))?;
+        check_declared_size(&archive, &sheet_path, &self.options.limits)?;
+        Ok(SheetReader {
+            archive,
+            sst: &self.sst,
+            options: &self.options,
+            name: sheet_name.to_string(),
+            sheet_path,
+            utf8_issues: Vec::new(),
         })
     }
-}
-
-// Decode XML entities (&lt; &gt; &amp; &quot; &apos;)
-fn decode_xml_entities(text: &str) -> String {
-    text.replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&amp;", "&")
-        .replace("&quot;", "\"")
-        .replace("&apos;", "'")
-}
 
-impl StreamingReader {
-    /// Load Shared Strings Table
+    /// Process all sheets in parallel (requires the `parallel` feature)
     ///
-    /// This MUST be loaded fully because cells reference strings by index.
-    /// For files with millions of unique strings, this can still be large.
-    fn load_shared_strings(archive: &mut StreamingZipReader) -> Result<Vec<String>> {
-        let mut sst = Vec::new();
+    /// Each sheet is parsed on a rayon worker thread with its own ZIP entry
+    /// reader. `f` gets the sheet name and a row iterator; results are
+    /// collected through a channel and returned in workbook order. The first
+    /// error (in workbook order) is returned if any sheet fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("consolidation.xlsx")?;
+    /// let counts = reader.par_sheets(|_name, rows| {
+    ///     let mut count = 0;
+    ///     for row in rows {
+    ///         row?;
+    ///         count += 1;
+    ///     }
+    ///     Ok(count)
+    /// })?;
+    /// for (name, count) in counts {
+    ///     println!("{}: {} rows", name, count);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn par_sheets<T, F>(&mut self, f: F) -> Result<Vec<(String, T)>>
+    where
+        T: Send,
+        F: Fn(&str, RowIterator<'_>) -> Result<T> + Sync + Send,
+    {
+        use rayon::prelude::*;
+        use std::sync::mpsc;
 
-        // Try to find sharedStrings.xml
-        let xml_data = match archive.read_entry_by_name("xl/sharedStrings.xml") {
-            Ok(data) => String::from_utf8_lossy(&data).to_string(),
-            Err(_) => return Ok(sst), // No SST = all cells are inline
-        };
+        // Merged ranges follow the cell data, so find them before fanning out
+        let mut merges = Vec::with_capacity(self.sheet_paths.len());
+        for i in 0..self.sheet_paths.len() {
+            let sheet_path = self.sheet_paths[i].clone();
+            check_declared_size(&self.archive, &sheet_path, &self.options.limits)?;
+            merges.push(if self.options.fill_merged_cells {
+                self.scan_merged_ranges(&sheet_path)?
+            } else {
+                Vec::new()
+            });
+        }
 
-        // Parse all <si> tags (multiple per line in compact XML)
-        let mut pos = 0;
-        while let Some(si_start) = xml_data[pos..].find("<si>") {
-            let si_start = pos + si_start;
-            if let Some(si_end) = xml_data[si_start..].find("</si>") {
-                let si_end = si_start + si_end + 5; // Include "</si>"
-                let si_block = &xml_data[si_start..si_end];
+        let (tx, rx) = mpsc::channel();
+        let (path, sst, options) = (&self.path, &self.sst, &self.options);
+        let (sheet_names, sheet_paths) = (&self.sheet_names, &self.sheet_paths);
+        (0..sheet_paths.len())
+            .into_par_iter()
+            .for_each_with(tx, |tx, i| {
+                let mut utf8_issues = Vec::new();
+                let result = StreamingZipReader::open(path)
+                    .map_err(|e| ExcelError::ReadError(format!("Failed to open ZIP: {}", e)))
+                    .and_then(|mut archive| {
+                        let reader = archive
+                            .read_entry_streaming_by_name(&sheet_paths[i])
+                            .map_err(|e| {
+                                ExcelError::ReadError(format!("Failed to open sheet: {}", e))
+                            })?;
+                        let rows = RowIterator::new(
+                            reader,
+                            sst,
+                            options,
+                            &sheet_paths[i],
+                            &mut utf8_issues,
+                            merges[i].clone(),
+                        );
+                        f(&sheet_names[i], rows)
+                    });
+                // The receiver outlives every worker, so sending cannot fail
+                let _ = tx.send((i, result, utf8_issues));
+            });
 
-                // Extract text from <t>text</t>
-                if let Some(t_start) = si_block.find("<t>") {
-                    if let Some(t_end) = si_block.find("</t>") {
-                        let text = &si_block[t_start + 3..t_end];
-                        // Decode XML entities in SST
-                        let decoded = decode_xml_entities(text);
-                        sst.push(decoded);
-                    }
-                }
+        let mut results: Vec<_> = rx.into_iter().collect();
+        results.sort_by_key(|(i, _, _)| *i);
 
-                pos = si_end;
-            } else {
-                break;
-            }
+        let mut output = Vec::with_capacity(results.len());
+        for (i, result, utf8_issues) in results {
+            self.utf8_issues.extend(utf8_issues);
+            output.push((self.sheet_names[i].clone(), result?));
         }
-
-        Ok(sst)
+        Ok(output)
     }
 
-    /// Load sheet names and paths from workbook.xml
+    /// Merged cell ranges of a sheet
     ///
-    /// Parses workbook.xml to get sheet names and their corresponding worksheet paths.
-    /// Supports Unicode sheet names.
-    fn load_sheet_info(archive: &mut StreamingZipReader) -> Result<(Vec<String>, Vec<String>)> {
-        let mut sheet_names = Vec::new();
-        let mut sheet_ids = Vec::new();
-
-        // Load workbook.xml
-        let xml_data = archive
-            .read_entry_by_name("xl/workbook.xml")
-            .map_err(|e| ExcelError::ReadError(format!("Failed to open workbook.xml: {}", e)))?;
-        let xml_data = String::from_utf8_lossy(&xml_data).to_string();
-
-        // Parse <sheet> tags to get names and rIds
-        // Example: <sheet name="Sheet1" sheetId="1" r:id="rId1"/>
-        let mut pos = 0;
-        while let Some(sheet_start) = xml_data[pos..].find("<sheet ") {
-            let sheet_start = pos + sheet_start;
-            if let Some(sheet_end) = xml_data[sheet_start..].find("/>") {
-                let sheet_end = sheet_start + sheet_end + 2;
-                let sheet_tag = &xml_data[sheet_start..sheet_end];
-
-                // Extract name attribute
-                if let Some(name_start) = sheet_tag.find("name=\"") {
-                    let name_start = name_start + 6;
-                    if let Some(name_end) = sheet_tag[name_start..].find("\"") {
-                        let name = &sheet_tag[name_start..name_start + name_end];
-                        sheet_names.push(name.to_string());
+    /// Merges are stored after the cell data, so this scans the whole sheet.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("report.xlsx")?;
+    /// for range in reader.merged_ranges("Sheet1")? {
+    ///     println!("merged: {}", range); // e.g. "A1:D1"
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn merged_ranges(&mut self, sheet_name: &str) -> Result<Vec<CellRange>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        self.scan_merged_ranges(&sheet_path)
+    }
+
+    // Find the worksheet XML path for a sheet name
+    pub(crate) fn sheet_path(&self, sheet_name: &str) -> Result<String> {
+        self.sheet_names
+            .iter()
+            .position(|name| name == sheet_name)
+            .and_then(|idx| self.sheet_paths.get(idx))
+            .cloned()
+            .ok_or_else(|| ExcelError::SheetNotFound {
+                sheet: sheet_name.to_string(),
+                available: self.sheet_names.join(", "),
+            })
+    }
+
+    /// Hyperlinks of a sheet with the cell ranges they cover
+    ///
+    /// External URLs are resolved from the sheet's relationships; links within
+    /// the workbook have an empty `target` and a `location`.
+    pub fn hyperlinks(&mut self, sheet_name: &str) -> Result<Vec<(CellRange, Hyperlink)>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        self.scan_hyperlinks(&sheet_path)
+    }
+
+    /// Stream rows as positioned [`Cell`]s with hyperlinks attached
+    ///
+    /// Use this when link targets matter, e.g. when the display text of a
+    /// link column differs from its URL.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("partners.xlsx")?;
+    /// for row in reader.cells("Sheet1")? {
+    ///     for cell in row? {
+    ///         if let Some(link) = &cell.hyperlink {
+    ///             println!("{} -> {}", cell.value, link.target);
+    ///         }
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn cells(&mut self, sheet_name: &str) -> Result<CellIterator<'_>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let hyperlinks = self.scan_hyperlinks(&sheet_path)?;
+
+        let mut inner = self.stream_rows(sheet_name)?;
+        inner.collect_cell_info = true;
+        Ok(CellIterator { inner, hyperlinks })
+    }
+
+    /// Defined names of the workbook (named ranges, print areas, ...)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("budget.xlsx")?;
+    /// for name in reader.defined_names()? {
+    ///     if let Some((sheet, range)) = name.range() {
+    ///         println!("{} = {}!{}", name.name, sheet, range);
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn defined_names(&mut self) -> Result<Vec<DefinedName>> {
+        let data = read_entry_limited(&mut self.archive, "xl/workbook.xml", &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&data, &mut self.utf8_issues)?;
+
+        Ok(
+            xml_elements(xml_section(&xml_data, "definedNames"), "definedName")
+                .into_iter()
+                .filter_map(|el| {
+                    let content_start = el.find('>')? + 1;
+                    let content = el.get(content_start..el.len() - "</definedName>".len())?;
+                    let scope = xml_attr(el, "localSheetId")
+                        .and_then(|id| id.parse::<usize>().ok())
+                        .and_then(|id| self.sheet_names.get(id).cloned());
+                    Some(DefinedName {
+                        name: decode_xml_entities(xml_attr(el, "name")?),
+                        refers_to: decode_xml_entities(content),
+                        scope,
+                        hidden: matches!(xml_attr(el, "hidden"), Some("1" | "true")),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Other workbooks referenced by formulas, in the order of the `[n]`
+    /// numbers formulas use
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    /// use excelstream::types::ExternalLink;
+    ///
+    /// let mut reader = StreamingReader::open("forecast.xlsx")?;
+    /// let links = reader.external_links()?;
+    /// for link in &links {
+    ///     println!("[{}] {:?}", link.index, link.target);
+    /// }
+    /// for cell in reader.cells("Sheet1")? {
+    ///     for cell in cell? {
+    ///         if let Some(formula) = &cell.formula {
+    ///             println!("{}: {}", cell.reference(), ExternalLink::resolve_formula(formula, &links));
+    ///         }
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn external_links(&mut self) -> Result<Vec<ExternalLink>> {
+        let data = read_entry_limited(&mut self.archive, "xl/workbook.xml", &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&data, &mut self.utf8_issues)?;
+        let ids: Vec<String> = xml_elements(
+            xml_section(&xml_data, "externalReferences"),
+            "externalReference",
+        )
+        .into_iter()
+        .filter_map(|el| xml_attr(el, "r:id").map(str::to_string))
+        .collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rels = self.load_relationships("xl/workbook.xml")?;
+        let mut links = Vec::new();
+        for (i, id) in ids.iter().enumerate() {
+            let Some(rel) = rels.iter().find(|r| &r.id == id) else {
+                continue;
+            };
+            let part = rel.target.clone();
+            let data = read_entry_limited(&mut self.archive, &part, &self.options.limits)?;
+            let xml_data = Utf8Decoder::new(self.options.utf8_policy, &part)
+                .decode_all(&data, &mut self.utf8_issues)?;
+            let book = xml_elements(&xml_data, "externalBook").first().copied();
+            let target = match book.and_then(|book| xml_attr(book, "r:id")) {
+                Some(book_id) => self
+                    .load_relationships(&part)?
+                    .into_iter()
+                    .find(|r| r.id == book_id)
+                    .map(|r| r.target),
+                None => None,
+            };
+            let book = book.unwrap_or_default();
+
+            let sheet_names: Vec<String> =
+                xml_elements(xml_section(book, "sheetNames"), "sheetName")
+                    .into_iter()
+                    .filter_map(|el| xml_attr(el, "val").map(decode_xml_entities))
+                    .collect();
+            let mut cached_values = Vec::new();
+            for sheet_data in xml_elements(xml_section(book, "sheetDataSet"), "sheetData") {
+                let Some(sheet) = xml_attr(sheet_data, "sheetId")
+                    .and_then(|id| id.parse::<usize>().ok())
+                    .and_then(|id| sheet_names.get(id))
+                else {
+                    continue;
+                };
+                for cell in xml_elements(sheet_data, "cell") {
+                    let Some((row, col)) = xml_attr(cell, "r").and_then(cellref::parse_a1) else {
+                        continue;
+                    };
+                    let value = external_cell_value(xml_attr(cell, "t"), xml_section(cell, "v"));
+                    cached_values.push((sheet.clone(), Cell::new(row, col, value)));
+                }
+            }
+
+            links.push(ExternalLink {
+                index: i as u32 + 1,
+                target,
+                sheet_names,
+                cached_values,
+            });
+        }
+        Ok(links)
+    }
+
+    /// Page header and footer of a sheet, with their pictures, or None if
+    /// the sheet has none
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("print.xlsx")?;
+    /// if let Some(print) = reader.header_footer("Sheet1")? {
+    ///     println!("header {:?}, {} pictures", print.header, print.images.len());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn header_footer(&mut self, sheet_name: &str) -> Result<Option<HeaderFooter>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let tail = self.read_sheet_tail(&sheet_path)?;
+        let Some(element) = xml_elements(&tail, "headerFooter").into_iter().next() else {
+            return Ok(None);
+        };
+        let text = |name: &str| {
+            let section = xml_section(element, name);
+            decode_xml_entities(section)
+        };
+        let mut header_footer = HeaderFooter {
+            header: text("oddHeader"),
+            footer: text("oddFooter"),
+            images: Vec::new(),
+        };
+
+        let Some(id) = xml_elements(&tail, "legacyDrawingHF")
+            .first()
+            .and_then(|el| xml_attr(el, "r:id"))
+            .map(str::to_string)
+        else {
+            return Ok(Some(header_footer));
+        };
+        let sheet_rels = self.load_relationships(&sheet_path)?;
+        let Some(drawing_path) = sheet_rels
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.target.clone())
+        else {
+            return Ok(Some(header_footer));
+        };
+        let data = read_entry_limited(&mut self.archive, &drawing_path, &self.options.limits)?;
+        let vml = Utf8Decoder::new(self.options.utf8_policy, &drawing_path)
+            .decode_all(&data, &mut self.utf8_issues)?;
+        let drawing_rels = self.load_relationships(&drawing_path)?;
+
+        for shape in xml_elements(&vml, "v:shape") {
+            let Some(position) =
+                vml_attr(shape, "id").and_then(HeaderFooterPosition::from_shape_id)
+            else {
+                continue;
+            };
+            let Some(media) = xml_elements(shape, "v:imagedata")
+                .first()
+                .and_then(|el| vml_attr(el, "o:relid"))
+                .and_then(|relid| drawing_rels.iter().find(|r| r.id == relid))
+            else {
+                continue;
+            };
+            let style = vml_attr(shape, "style").unwrap_or_default();
+            let size = |name: &str| {
+                style
+                    .split(';')
+                    .filter_map(|p| p.split_once(':'))
+                    .find(|(key, _)| key.trim() == name)
+                    .and_then(|(_, v)| v.trim().trim_end_matches("pt").parse::<f64>().ok())
+                    .unwrap_or(0.0)
+            };
+            let extension = media
+                .target
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_ascii_lowercase())
+                .unwrap_or_default();
+            let data = read_entry_limited(&mut self.archive, &media.target, &self.options.limits)?;
+            header_footer.images.push(HeaderFooterImage {
+                position,
+                data,
+                extension,
+                width: size("width"),
+                height: size("height"),
+            });
+        }
+        Ok(Some(header_footer))
+    }
+
+    /// Custom document properties (File > Properties > Custom) as
+    /// (name, value text), in file order
+    ///
+    /// An embedded delivery manifest is the property named
+    /// [`EMBEDDED_PROPERTY`](crate::manifest::EMBEDDED_PROPERTY).
+    pub fn custom_properties(&mut self) -> Result<Vec<(String, String)>> {
+        const PATH: &str = "docProps/custom.xml";
+        if !self.archive.entries().iter().any(|e| e.name == PATH) {
+            return Ok(Vec::new());
+        }
+        let data = read_entry_limited(&mut self.archive, PATH, &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, PATH)
+            .decode_all(&data, &mut self.utf8_issues)?;
+
+        Ok(xml_elements(&xml_data, "property")
+            .into_iter()
+            .filter_map(|el| {
+                let name = decode_xml_entities(xml_attr(el, "name")?);
+                // The single <vt:...> child holds the value
+                let inner = xml_section(el, "property");
+                let value = match (inner.find('>'), inner.rfind("</")) {
+                    (Some(start), Some(end)) if end > start => &inner[start + 1..end],
+                    _ => "",
+                };
+                Some((name, decode_xml_entities(value)))
+            })
+            .collect())
+    }
+
+    /// Document properties: title, author, dates, application, ...
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("report.xlsx")?;
+    /// let props = reader.properties()?;
+    /// println!("By {:?} on {:?}", props.creator, props.created);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn properties(&mut self) -> Result<WorkbookProperties> {
+        let core = self.read_optional_part("docProps/core.xml")?;
+        let app = self.read_optional_part("docProps/app.xml")?;
+        let text = |xml: &str, name: &str| {
+            let value = xml_section(xml, name).trim();
+            (!value.is_empty()).then(|| decode_xml_entities(value))
+        };
+        let date = |name: &str| {
+            text(&core, name).and_then(|value| chrono::DateTime::parse_from_rfc3339(&value).ok())
+        };
+        Ok(WorkbookProperties {
+            title: text(&core, "dc:title"),
+            subject: text(&core, "dc:subject"),
+            creator: text(&core, "dc:creator"),
+            keywords: text(&core, "cp:keywords"),
+            description: text(&core, "dc:description"),
+            last_modified_by: text(&core, "cp:lastModifiedBy"),
+            created: date("dcterms:created"),
+            modified: date("dcterms:modified"),
+            application: text(&app, "Application"),
+            app_version: text(&app, "AppVersion"),
+            company: text(&app, "Company"),
+        })
+    }
+
+    /// Sheet count, defined names, macros and external links, to size up a
+    /// workbook before reading it
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("upload.xlsm")?;
+    /// if reader.workbook_stats()?.has_macros {
+    ///     println!("Workbook carries macros");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn workbook_stats(&mut self) -> Result<WorkbookStats> {
+        let data = read_entry_limited(&mut self.archive, "xl/workbook.xml", &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&data, &mut self.utf8_issues)?;
+        let defined_names =
+            xml_elements(xml_section(&xml_data, "definedNames"), "definedName").len();
+        let has_external_links = !xml_elements(&xml_data, "externalReference").is_empty()
+            || self
+                .archive
+                .entries()
+                .iter()
+                .any(|e| e.name.starts_with("xl/externalLinks/"));
+        let has_macros = self
+            .archive
+            .entries()
+            .iter()
+            .any(|e| e.name.to_ascii_lowercase().ends_with("vbaproject.bin"))
+            || self.vba_project()?.is_some();
+        Ok(WorkbookStats {
+            sheet_count: self.sheet_names.len(),
+            defined_names,
+            has_macros,
+            has_external_links,
+        })
+    }
+
+    // A package part's text, or "" when the package doesn't have it
+    fn read_optional_part(&mut self, path: &str) -> Result<String> {
+        if !self.archive.entries().iter().any(|e| e.name == path) {
+            return Ok(String::new());
+        }
+        let data = read_entry_limited(&mut self.archive, path, &self.options.limits)?;
+        Utf8Decoder::new(self.options.utf8_policy, path).decode_all(&data, &mut self.utf8_issues)
+    }
+
+    /// The workbook's VBA project (`xl/vbaProject.bin` of an `.xlsm`), if it
+    /// has macros
+    ///
+    /// Attach it to a new workbook with
+    /// [`ExcelWriter::attach_vba_project`](crate::ExcelWriter::attach_vba_project)
+    /// to carry a template's macros over.
+    pub fn vba_project(&mut self) -> Result<Option<Vec<u8>>> {
+        let Some(rel) = self
+            .load_relationships("xl/workbook.xml")?
+            .into_iter()
+            .find(|rel| rel.rel_type.ends_with("/vbaProject"))
+        else {
+            return Ok(None);
+        };
+        if !self.archive.entries().iter().any(|e| e.name == rel.target) {
+            return Ok(None);
+        }
+        read_entry_limited(&mut self.archive, &rel.target, &self.options.limits).map(Some)
+    }
+
+    /// Inspect the digital signatures in the workbook, with the digest of
+    /// every signed part compared against the package
+    ///
+    /// The signatures are not verified: neither the signature value nor the
+    /// in-signature `Object` is checked, see [`signature`](crate::signature).
+    /// An unsigned workbook has none.
+    pub fn signatures(&mut self) -> Result<Vec<SignatureInfo>> {
+        let mut parts = Vec::new();
+        for origin in self.load_relationships("")? {
+            if origin.rel_type != signature::ORIGIN_RELATIONSHIP {
+                continue;
+            }
+            for rel in self.load_relationships(&origin.target)? {
+                if rel.rel_type == signature::SIGNATURE_RELATIONSHIP
+                    && self.archive.entries().iter().any(|e| e.name == rel.target)
+                {
+                    parts.push(rel.target);
+                }
+            }
+        }
+
+        let mut signatures = Vec::new();
+        for part in parts {
+            let data = read_entry_limited(&mut self.archive, &part, &self.options.limits)?;
+            let xml_data = Utf8Decoder::new(self.options.utf8_policy, &part)
+                .decode_all(&data, &mut self.utf8_issues)?;
+            // Signers write XML-DSig unprefixed (Office) or as ds:
+            let xml_data = xml_data.replace("<ds:", "<").replace("</ds:", "</");
+            let decode = |name| -> Result<Option<Vec<u8>>> {
+                match xml_elements(&xml_data, name).first() {
+                    Some(_) => Ok(Some(signature::base64_decode(xml_section(
+                        &xml_data, name,
+                    ))?)),
+                    None => Ok(None),
+                }
+            };
+            let certificate = decode("X509Certificate")?;
+            let signature_value = decode("SignatureValue")?.unwrap_or_default();
+            let signature_method = xml_elements(&xml_data, "SignatureMethod")
+                .first()
+                .and_then(|el| xml_attr(el, "Algorithm"))
+                .unwrap_or_default()
+                .to_string();
+            let signing_time = xml_elements(&xml_data, "mdssi:Value")
+                .first()
+                .map(|_| xml_section(&xml_data, "mdssi:Value").trim().to_string());
+
+            let mut references = Vec::new();
+            for el in xml_elements(&xml_data, "Reference") {
+                let uri = decode_xml_entities(xml_attr(el, "URI").unwrap_or_default());
+                let digest_method = xml_elements(el, "DigestMethod")
+                    .first()
+                    .and_then(|m| xml_attr(m, "Algorithm"))
+                    .unwrap_or_default()
+                    .to_string();
+                let status = if uri.starts_with('#')
+                    || el.contains("<Transform")
+                    || digest_method != "http://www.w3.org/2001/04/xmlenc#sha256"
+                {
+                    DigestStatus::Unchecked
+                } else {
+                    let expected = signature::base64_decode(xml_section(el, "DigestValue"))?;
+                    let target = uri.trim_start_matches('/');
+                    let target = target.split('?').next().unwrap_or_default();
+                    if self.archive.entries().iter().any(|e| e.name == target) {
+                        let digest =
+                            digest_entry_limited(&mut self.archive, target, &self.options.limits)?;
+                        if digest[..] == expected[..] {
+                            DigestStatus::Matches
+                        } else {
+                            DigestStatus::Mismatch
+                        }
+                    } else {
+                        DigestStatus::Mismatch
+                    }
+                };
+                references.push(SignedReference {
+                    uri,
+                    digest_method,
+                    status,
+                });
+            }
+
+            signatures.push(SignatureInfo {
+                part,
+                signing_time,
+                certificate,
+                signature_method,
+                signature_value,
+                references,
+            });
+        }
+        Ok(signatures)
+    }
+
+    /// Whether a sheet's tab is visible, hidden or very hidden
+    ///
+    /// [`sheet_names`](Self::sheet_names) lists hidden sheets too, in tab order.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    /// use excelstream::types::SheetVisibility;
+    ///
+    /// let mut reader = StreamingReader::open("report.xlsx")?;
+    /// for name in reader.sheet_names() {
+    ///     if reader.sheet_visibility(&name)? == SheetVisibility::Visible {
+    ///         println!("{}", name);
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn sheet_visibility(&mut self, sheet_name: &str) -> Result<SheetVisibility> {
+        self.sheet_path(sheet_name)?;
+        let data = read_entry_limited(&mut self.archive, "xl/workbook.xml", &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&data, &mut self.utf8_issues)?;
+
+        Ok(xml_elements(xml_section(&xml_data, "sheets"), "sheet")
+            .into_iter()
+            .find(|el| xml_attr(el, "name").map(decode_xml_entities).as_deref() == Some(sheet_name))
+            .and_then(|el| xml_attr(el, "state"))
+            .and_then(SheetVisibility::parse)
+            .unwrap_or_default())
+    }
+
+    /// Name of the tab shown when the file is opened, or None for a
+    /// workbook without sheets
+    pub fn active_sheet(&mut self) -> Result<Option<String>> {
+        let data = read_entry_limited(&mut self.archive, "xl/workbook.xml", &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&data, &mut self.utf8_issues)?;
+
+        let active_tab = xml_elements(&xml_data, "workbookView")
+            .first()
+            .and_then(|el| xml_attr(el, "activeTab"))
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+        Ok(self
+            .sheet_names
+            .get(active_tab)
+            .or_else(|| self.sheet_names.first())
+            .cloned())
+    }
+
+    /// Calculation settings of the workbook, or None if it has no `<calcPr>`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("model.xlsx")?;
+    /// if let Some(calc) = reader.calc_settings()? {
+    ///     println!("mode {:?}, full calc on load: {}", calc.mode, calc.full_calc_on_load);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn calc_settings(&mut self) -> Result<Option<CalcSettings>> {
+        let data = read_entry_limited(&mut self.archive, "xl/workbook.xml", &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&data, &mut self.utf8_issues)?;
+
+        let Some(el) = xml_elements(&xml_data, "calcPr").into_iter().next() else {
+            return Ok(None);
+        };
+        let flag = |name: &str| xml_attr(el, name).map(|v| matches!(v, "1" | "true"));
+        let defaults = CalcSettings::default();
+        Ok(Some(CalcSettings {
+            mode: xml_attr(el, "calcMode")
+                .and_then(CalcMode::parse)
+                .unwrap_or(defaults.mode),
+            full_calc_on_load: flag("fullCalcOnLoad").unwrap_or(defaults.full_calc_on_load),
+            iterate: flag("iterate").unwrap_or(defaults.iterate),
+            iterate_count: xml_attr(el, "iterateCount")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.iterate_count),
+            iterate_delta: xml_attr(el, "iterateDelta")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.iterate_delta),
+        }))
+    }
+
+    /// Excel Tables defined on a sheet, with their ranges and column names
+    ///
+    /// Lets ingestion target a named table instead of guessing where the
+    /// header row starts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("orders.xlsx")?;
+    /// if let Some(table) = reader.tables("Sheet1")?.into_iter().find(|t| t.name == "Orders") {
+    ///     println!("columns {:?}, data in {}", table.columns, table.data_range());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tables(&mut self, sheet_name: &str) -> Result<Vec<TableInfo>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let tail = self.read_sheet_tail(&sheet_path)?;
+        let table_ids: Vec<String> = xml_elements(xml_section(&tail, "tableParts"), "tablePart")
+            .into_iter()
+            .filter_map(|el| xml_attr(el, "r:id").map(str::to_string))
+            .collect();
+        if table_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rels = self.load_relationships(&sheet_path)?;
+        let mut tables = Vec::new();
+        for id in table_ids {
+            let Some(rel) = rels.iter().find(|r| r.id == id) else {
+                continue;
+            };
+            let data = read_entry_limited(&mut self.archive, &rel.target, &self.options.limits)?;
+            let xml_data = Utf8Decoder::new(self.options.utf8_policy, &rel.target)
+                .decode_all(&data, &mut self.utf8_issues)?;
+            let Some(table) = xml_elements(&xml_data, "table").first().copied() else {
+                continue;
+            };
+            let Some(range) = xml_attr(table, "ref").and_then(|r| r.parse().ok()) else {
+                continue;
+            };
+            let count = |name| xml_attr(table, name).and_then(|v| v.parse().ok());
+
+            tables.push(TableInfo {
+                name: xml_attr(table, "displayName")
+                    .or_else(|| xml_attr(table, "name"))
+                    .map(decode_xml_entities)
+                    .unwrap_or_default(),
+                range,
+                columns: xml_elements(xml_section(table, "tableColumns"), "tableColumn")
+                    .into_iter()
+                    .filter_map(|col| xml_attr(col, "name").map(decode_xml_entities))
+                    .collect(),
+                header_row_count: count("headerRowCount").unwrap_or(1),
+                totals_row_count: count("totalsRowCount").unwrap_or(0),
+            });
+        }
+        Ok(tables)
+    }
+
+    /// Images embedded in a sheet, with the cells they are anchored to
+    ///
+    /// Reads the sheet's drawing part and the referenced media. Images placed
+    /// at absolute positions (not anchored to cells) are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("catalog.xlsx")?;
+    /// for image in reader.images("Products")? {
+    ///     let file = format!("row{}.{}", image.anchor.first_row + 1, image.extension().unwrap_or_default());
+    ///     std::fs::write(file, &image.data)?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn images(&mut self, sheet_name: &str) -> Result<Vec<SheetImage>> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let tail = self.read_sheet_tail(&sheet_path)?;
+        let drawing_ids: Vec<String> = xml_elements(&tail, "drawing")
+            .into_iter()
+            .filter_map(|el| xml_attr(el, "r:id").map(str::to_string))
+            .collect();
+        if drawing_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let sheet_rels = self.load_relationships(&sheet_path)?;
+        let mut images = Vec::new();
+        for id in drawing_ids {
+            let Some(rel) = sheet_rels.iter().find(|r| r.id == id) else {
+                continue;
+            };
+            let drawing_path = rel.target.clone();
+            let data = read_entry_limited(&mut self.archive, &drawing_path, &self.options.limits)?;
+            let drawing_xml = Utf8Decoder::new(self.options.utf8_policy, &drawing_path)
+                .decode_all(&data, &mut self.utf8_issues)?;
+            let drawing_rels = self.load_relationships(&drawing_path)?;
+
+            for (anchor, pic) in parse_drawing_pictures(&drawing_xml) {
+                let Some(media) = xml_elements(pic, "a:blip")
+                    .first()
+                    .and_then(|blip| xml_attr(blip, "r:embed"))
+                    .and_then(|embed| drawing_rels.iter().find(|r| r.id == embed))
+                else {
+                    continue;
+                };
+                let props = xml_elements(pic, "xdr:cNvPr");
+                let props = props.first().copied().unwrap_or_default();
+                let data =
+                    read_entry_limited(&mut self.archive, &media.target, &self.options.limits)?;
+                images.push(SheetImage {
+                    name: xml_attr(props, "name")
+                        .map(decode_xml_entities)
+                        .unwrap_or_default(),
+                    description: xml_attr(props, "descr").map(decode_xml_entities),
+                    path: media.target.clone(),
+                    anchor,
+                    data,
+                });
+            }
+        }
+        Ok(images)
+    }
+
+    // Parse the <hyperlinks> section of a worksheet and resolve its r:ids
+    fn scan_hyperlinks(&mut self, sheet_path: &str) -> Result<Vec<(CellRange, Hyperlink)>> {
+        let tail = self.read_sheet_tail(sheet_path)?;
+        let elements = xml_elements(xml_section(&tail, "hyperlinks"), "hyperlink");
+        if elements.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rels = self.load_relationships(sheet_path)?;
+
+        let attr = |el: &str, name: &str| xml_attr(el, name).map(decode_xml_entities);
+        Ok(elements
+            .into_iter()
+            .filter_map(|el| {
+                let range = xml_attr(el, "ref")?.parse().ok()?;
+                let target = xml_attr(el, "r:id")
+                    .and_then(|id| rels.iter().find(|r| r.id == id))
+                    .map(|r| r.target.clone())
+                    .unwrap_or_default();
+                let link = Hyperlink {
+                    target,
+                    location: attr(el, "location"),
+                    tooltip: attr(el, "tooltip"),
+                    display: attr(el, "display"),
+                };
+                Some((range, link))
+            })
+            .collect())
+    }
+
+    // Relationships of a part (e.g. "xl/worksheets/sheet1.xml" reads
+    // "xl/worksheets/_rels/sheet1.xml.rels", "" reads the package's
+    // "_rels/.rels"); internal targets are resolved to full ZIP paths
+    fn load_relationships(&mut self, part_path: &str) -> Result<Vec<Relationship>> {
+        let (dir, file) = part_path.rsplit_once('/').unwrap_or(("", part_path));
+        let rels_path = if dir.is_empty() {
+            format!("_rels/{}.rels", file)
+        } else {
+            format!("{}/_rels/{}.rels", dir, file)
+        };
+        if !self.archive.entries().iter().any(|e| e.name == rels_path) {
+            return Ok(Vec::new());
+        }
+        let data = read_entry_limited(&mut self.archive, &rels_path, &self.options.limits)?;
+        let xml_data = Utf8Decoder::new(self.options.utf8_policy, &rels_path)
+            .decode_all(&data, &mut self.utf8_issues)?;
+
+        Ok(xml_elements(&xml_data, "Relationship")
+            .into_iter()
+            .filter_map(|el| {
+                let target = decode_xml_entities(xml_attr(el, "Target")?);
+                let external = xml_attr(el, "TargetMode") == Some("External");
+                Some(Relationship {
+                    id: xml_attr(el, "Id")?.to_string(),
+                    rel_type: xml_attr(el, "Type").unwrap_or_default().to_string(),
+                    target: if external {
+                        target
+                    } else {
+                        resolve_part_path(dir, &target)
+                    },
+                })
+            })
+            .collect())
+    }
+
+    // Parse the <mergeCells> section of a worksheet
+    fn scan_merged_ranges(&mut self, sheet_path: &str) -> Result<Vec<CellRange>> {
+        let tail = self.read_sheet_tail(sheet_path)?;
+        Ok(parse_merged_ranges(&tail))
+    }
+
+    // Everything after </sheetData> in a worksheet (merges, hyperlinks,
+    // drawings, tables...), cached per sheet
+    fn read_sheet_tail(&mut self, sheet_path: &str) -> Result<String> {
+        if let Some((_, tail)) = self.sheet_tails.iter().find(|(p, _)| p == sheet_path) {
+            return Ok(tail.clone());
+        }
+        let tail = scan_sheet_tail(
+            &mut self.archive,
+            sheet_path,
+            &self.options,
+            &mut self.utf8_issues,
+        )?;
+        self.sheet_tails
+            .push((sheet_path.to_string(), tail.clone()));
+        Ok(tail)
+    }
+
+    /// Stream rows with each cell's resolved formatting
+    ///
+    /// Parses styles.xml on first use, so import logic can honor formatting
+    /// cues such as number formats, bold text or highlight colors. Numbers
+    /// with an elapsed time format (`[h]:mm:ss`) read as
+    /// [`CellValue::Duration`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("review.xlsx")?;
+    /// for row in reader.rows_with_style("Sheet1")? {
+    ///     for cell in row? {
+    ///         if cell.format.fill_color.as_deref() == Some("FFFF0000") {
+    ///             println!("rejected: {}", cell.value);
+    ///         }
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rows_with_style(&mut self, sheet_name: &str) -> Result<FormattedRowIterator<'_>> {
+        if self.formats.is_none() {
+            let formats =
+                Self::load_formats(&mut self.archive, &self.options, &mut self.utf8_issues)?;
+            self.formats = Some(formats.into_iter().map(Arc::new).collect());
+        }
+        let formats = self.formats.clone().unwrap_or_default();
+
+        let mut inner = self.stream_rows(sheet_name)?;
+        inner.collect_cell_info = true;
+        Ok(FormattedRowIterator {
+            inner,
+            formats,
+            default_format: Arc::new(CellFormat::default()),
+        })
+    }
+
+    /// Alias for `stream_rows()` for backward compatibility
+    ///
+    /// This method provides the same functionality as `stream_rows()` but uses
+    /// the more familiar `rows()` name that matches the old calamine-based API.
+    /// Returns an iterator of `Row` structs for full API compatibility.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::ExcelReader;
+    ///
+    /// let mut reader = ExcelReader::open("large.xlsx")?;
+    /// for row_result in reader.rows("Sheet1")? {
+    ///     let row = row_result?;
+    ///     println!("Row {}: {:?}", row.index, row.to_strings());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rows(&mut self, sheet_name: &str) -> Result<RowStructIterator<'_>> {
+        let inner = self.stream_rows(sheet_name)?;
+        Ok(RowStructIterator { inner })
+    }
+
+    /// Stream rows whose shared strings are borrowed from the reader
+    ///
+    /// Like [`rows`](Self::rows), but text cells from the shared strings
+    /// table are [`BorrowedCell::Shared`] slices of the table instead of
+    /// copies, saving an allocation per string cell. Skipped rows, header
+    /// row and blank row handling apply as usual; read middleware, type
+    /// inference and merged cell filling work on owned values and fail with
+    /// [`ExcelError::NotSupported`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    ///
+    /// let mut reader = StreamingReader::open("customers.xlsx")?;
+    /// let mut vip = 0;
+    /// for row in reader.rows_borrowed("Sheet1")? {
+    ///     let row = row?;
+    ///     if row.get(3).and_then(|c| c.as_str()) == Some("VIP") {
+    ///         vip += 1;
+    ///     }
+    /// }
+    /// println!("{} VIP customers", vip);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rows_borrowed(&mut self, sheet_name: &str) -> Result<BorrowedRowIterator<'_>> {
+        let unsupported = if !self.options.middleware.is_empty() {
+            Some("read middleware")
+        } else if self.options.infer_types.is_some() {
+            Some("type inference")
+        } else if self.options.fill_merged_cells {
+            Some("merged cell filling")
+        } else {
+            None
+        };
+        if let Some(option) = unsupported {
+            return Err(ExcelError::NotSupported(format!(
+                "{} needs owned cells, read with rows() instead of rows_borrowed()",
+                option
+            )));
+        }
+        let inner = self.stream_rows(sheet_name)?;
+        Ok(BorrowedRowIterator {
+            inner,
+            pending: None,
+        })
+    }
+
+    /// Stream rows keyed by the header row
+    ///
+    /// The header is the row set with [`ReaderOptions::with_header_row`],
+    /// or else the first row yielded (after
+    /// [`ReaderOptions::with_skip_rows`]). It goes through the middleware
+    /// like any other row, so renamed headers apply.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::ExcelReader;
+    ///
+    /// let mut reader = ExcelReader::open("customers.xlsx")?;
+    /// for record in reader.rows_with_header("Sheet1")? {
+    ///     let record = record?;
+    ///     for (header, value) in record.iter() {
+    ///         println!("{}: {}", header, value);
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rows_with_header(&mut self, sheet_name: &str) -> Result<RecordIterator<'_>> {
+        let inner = self.stream_rows(sheet_name)?;
+        Ok(RecordIterator {
+            inner,
+            headers: None,
+            failed: false,
+        })
+    }
+
+    /// Stream the rows of a sheet as a `futures::Stream` (feature `async`)
+    ///
+    /// The reader moves to a background thread that parses ahead by up to
+    /// [`DEFAULT_BUFFER_ROWS`](crate::row_stream::DEFAULT_BUFFER_ROWS) rows
+    /// and then waits for the consumer, see [`row_stream`](crate::row_stream).
+    #[cfg(feature = "async")]
+    pub fn rows_stream(self, sheet_name: &str) -> Result<crate::row_stream::RowStream> {
+        self.sheet_path(sheet_name)?;
+        crate::row_stream::RowStream::spawn(
+            self,
+            sheet_name.to_string(),
+            crate::row_stream::DEFAULT_BUFFER_ROWS,
+        )
+    }
+
+    /// Stream rows in batches of up to `batch_size`, e.g. for bulk inserts
+    ///
+    /// The last batch may be shorter. If a row fails to parse, the rows
+    /// before it are yielded as a batch first and the error follows.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::ExcelReader;
+    ///
+    /// let mut reader = ExcelReader::open("large.xlsx")?;
+    /// for batch in reader.row_batches("Sheet1", 1000)? {
+    ///     let batch = batch?;
+    ///     println!("Inserting {} rows", batch.len());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn row_batches(
+        &mut self,
+        sheet_name: &str,
+        batch_size: usize,
+    ) -> Result<RowBatchIterator<'_>> {
+        if batch_size == 0 {
+            return Err(ExcelError::InvalidState(
+                "batch_size must be greater than 0".to_string(),
+            ));
+        }
+        let rows = self.rows(sheet_name)?;
+        Ok(RowBatchIterator {
+            rows,
+            batch_size,
+            pending_error: None,
+        })
+    }
+}
+
+// Decode XML entities (&lt; &gt; &amp; &quot; &apos;) and character
+// references (&#10; &#x1F4CA;) in one pass, so "&amp;lt;" stays "&lt;"
+pub(crate) fn decode_xml_entities(text: &str) -> String {
+    if memchr::memchr(b'&', text.as_bytes()).is_none() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = memchr::memchr(b'&', rest.as_bytes()) {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        // The longest reference is "&#x10FFFF;"
+        let decoded = rest
+            .bytes()
+            .take(11)
+            .position(|b| b == b';')
+            .and_then(|semi| {
+                let c = match &rest[1..semi] {
+                    "lt" => '<',
+                    "gt" => '>',
+                    "amp" => '&',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    reference => {
+                        let number = reference.strip_prefix('#')?;
+                        let code = match number.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => number.parse().ok()?,
+                        };
+                        char::from_u32(code)?
+                    }
+                };
+                Some((c, semi + 1))
+            });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                // Not a reference: keep the '&' as text
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Text of a string item (`<si>` or `<is>`): every `<t>` run joined, phonetic
+// runs (`<rPh>`) left out, entities decoded. None if there is no `<t>` at all.
+fn string_item_text(item: &str) -> Option<String> {
+    let mut text = None::<String>;
+    let mut pos = 0;
+    while let Some(start) = find_start_tag(item, pos, "<t") {
+        if let Some(phonetic) = find_start_tag(item, pos, "<rPh") {
+            if phonetic < start {
+                pos = find_from(item, phonetic, "</rPh>").map_or(item.len(), |end| end + 6);
+                continue;
+            }
+        }
+        let Some(tag_end) = find_from(item, start, ">") else {
+            break;
+        };
+        let text = text.get_or_insert_with(String::new);
+        if item.as_bytes()[tag_end - 1] == b'/' {
+            pos = tag_end + 1; // <t/>
+            continue;
+        }
+        let Some(end) = find_from(item, tag_end, "</t>") else {
+            break;
+        };
+        text.push_str(&decode_xml_entities(&item[tag_end + 1..end]));
+        pos = end + 4;
+    }
+    text
+}
+
+impl StreamingReader {
+    /// Load Shared Strings Table
+    ///
+    /// This MUST be loaded fully because cells reference strings by index.
+    /// For files with millions of unique strings, this can still be large.
+    fn load_shared_strings(
+        archive: &mut StreamingZipReader,
+        options: &ReaderOptions,
+        utf8_issues: &mut Vec<Utf8Issue>,
+    ) -> Result<SharedStrings> {
+        let mut sst = SharedStrings::default();
+
+        // Try to find sharedStrings.xml
+        if !archive
+            .entries()
+            .iter()
+            .any(|e| e.name == "xl/sharedStrings.xml")
+        {
+            return Ok(sst); // No SST = all cells are inline
+        }
+        let data = read_entry_limited(archive, "xl/sharedStrings.xml", &options.limits)?;
+        let xml_data = Utf8Decoder::new(options.utf8_policy, "xl/sharedStrings.xml")
+            .decode_all(&data, utf8_issues)?;
+
+        // Parse all <si> tags (multiple per line in compact XML); some
+        // producers write an empty item as <si/>
+        let mut pos = 0;
+        while let Some(si_start) = find_start_tag(&xml_data, pos, "<si") {
+            let Some(tag_end) = find_from(&xml_data, si_start, ">") else {
+                break;
+            };
+            let si_end = if xml_data.as_bytes()[tag_end - 1] == b'/' {
+                Some(tag_end + 1)
+            } else {
+                find_from(&xml_data, tag_end, "</si>").map(|end| end + 5) // Include "</si>"
+            };
+            if let Some(si_end) = si_end {
+                let si_block = &xml_data[si_start..si_end];
+
+                // Every item takes an index, even an empty one, so later
+                // indices stay aligned; rich text runs are joined
+                let text = string_item_text(si_block).unwrap_or_default();
+                options.limits.check_cell_text(&text)?;
+                ReadLimits::check(
+                    "shared string count",
+                    sst.len() as u64 + 1,
+                    options.limits.max_shared_strings as u64,
+                )?;
+                sst.push(&text);
+
+                pos = si_end;
+            } else {
+                break;
+            }
+        }
+
+        sst.shrink_to_fit();
+        Ok(sst)
+    }
+
+    /// Load sheet names and paths from workbook.xml
+    ///
+    /// Parses workbook.xml to get sheet names and their corresponding worksheet paths.
+    /// Supports Unicode sheet names.
+    fn load_sheet_info(
+        archive: &mut StreamingZipReader,
+        options: &ReaderOptions,
+        utf8_issues: &mut Vec<Utf8Issue>,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        // Load workbook.xml
+        let xml_data = read_entry_limited(archive, "xl/workbook.xml", &options.limits)?;
+        let xml_data = Utf8Decoder::new(options.utf8_policy, "xl/workbook.xml")
+            .decode_all(&xml_data, utf8_issues)?;
+
+        // Load workbook.xml.rels to map rIds to worksheet paths
+        let rels_data = read_entry_limited(archive, "xl/_rels/workbook.xml.rels", &options.limits)?;
+        let rels_data = Utf8Decoder::new(options.utf8_policy, "xl/_rels/workbook.xml.rels")
+            .decode_all(&rels_data, utf8_issues)?;
+        let rels = xml_elements(&rels_data, "Relationship");
+
+        // Parse <sheet> tags for names and rIds
+        // Example: <sheet name="Sheet1" sheetId="1" r:id="rId1"/>
+        let mut sheet_names = Vec::new();
+        let mut sheet_paths = Vec::new();
+        for sheet in xml_elements(&xml_data, "sheet") {
+            if let Some(name) = xml_attr(sheet, "name") {
+                sheet_names.push(decode_xml_entities(name));
+            }
+            let target = xml_attr(sheet, "r:id").and_then(|rid| {
+                rels.iter()
+                    .find(|rel| xml_attr(rel, "Id") == Some(rid))
+                    .and_then(|rel| xml_attr(rel, "Target"))
+            });
+            if let Some(target) = target {
+                // Usually relative to xl/ ("worksheets/sheet1.xml"); some
+                // producers write absolute part names ("/xl/worksheets/...")
+                sheet_paths.push(resolve_part_path("xl", &decode_xml_entities(target)));
+            }
+        }
+
+        if sheet_names.len() != sheet_paths.len() {
+            return Err(ExcelError::ReadError(format!(
+                "Mismatch between sheet names ({}) and paths ({})",
+                sheet_names.len(),
+                sheet_paths.len()
+            )));
+        }
+
+        Ok((sheet_names, sheet_paths))
+    }
+
+    /// Load cell formats (cellXfs) from styles.xml, indexed by style id
+    fn load_formats(
+        archive: &mut StreamingZipReader,
+        options: &ReaderOptions,
+        utf8_issues: &mut Vec<Utf8Issue>,
+    ) -> Result<Vec<CellFormat>> {
+        if !archive.entries().iter().any(|e| e.name == "xl/styles.xml") {
+            return Ok(Vec::new());
+        }
+        let data = read_entry_limited(archive, "xl/styles.xml", &options.limits)?;
+        let xml_data = Utf8Decoder::new(options.utf8_policy, "xl/styles.xml")
+            .decode_all(&data, utf8_issues)?;
+        Ok(parse_cell_formats(&xml_data))
+    }
+}
+
+/// Shared strings table, stored as one text buffer and the offsets where
+/// each string starts
+///
+/// Saves the header and allocation of a `String` per entry, and lets
+/// [`BorrowedRowIterator`] hand out slices of it.
+#[derive(Debug, Default)]
+pub(crate) struct SharedStrings {
+    text: String,
+    ends: Vec<usize>, // End offset of each string in `text`
+}
+
+impl SharedStrings {
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.ends.push(self.text.len());
+    }
+
+    fn get(&self, index: usize) -> Option<&str> {
+        let end = *self.ends.get(index)?;
+        let start = index.checked_sub(1).map_or(0, |i| self.ends[i]);
+        Some(&self.text[start..end])
+    }
+
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.text.shrink_to_fit();
+        self.ends.shrink_to_fit();
+    }
+
+    // Bytes held, including spare capacity
+    fn memory(&self) -> usize {
+        self.text.capacity() + self.ends.capacity() * std::mem::size_of::<usize>()
+    }
+}
+
+// Everything after </sheetData> in a worksheet. Excel stores merges,
+// hyperlinks, drawings and tables after the cell data, so the whole sheet is
+// streamed through once.
+fn scan_sheet_tail(
+    archive: &mut StreamingZipReader,
+    sheet_path: &str,
+    options: &ReaderOptions,
+    utf8_issues: &mut Vec<Utf8Issue>,
+) -> Result<String> {
+    let limits = &options.limits;
+    check_declared_size(archive, sheet_path, limits)?;
+    let mut reader = archive
+        .read_entry_streaming_by_name(sheet_path)
+        .map_err(|e| ExcelError::ReadError(format!("Failed to open sheet: {}", e)))?;
+
+    let mut chunk = vec![0u8; 32 * 1024];
+    let mut window: Vec<u8> = Vec::new(); // Unmatched tail, or the sheet tail once found
+    let mut found = false;
+    let mut bytes_read = 0u64;
+    // Taken from the first chunk, which holds the root tag
+    let mut prefixes: Option<XmlPrefixes> = None;
+    let (mut end_tag, mut empty_tag) = (Vec::new(), Vec::new());
+
+    loop {
+        let n = reader
+            .read(&mut chunk)
+            .map_err(|e| ExcelError::ReadError(format!("Failed to read XML: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        bytes_read += n as u64;
+        ReadLimits::check(
+            format!("'{}' uncompressed size", sheet_path),
+            bytes_read,
+            limits.max_entry_size,
+        )?;
+
+        window.extend_from_slice(&chunk[..n]);
+        if prefixes.is_none() {
+            let detected =
+                XmlPrefixes::detect(&String::from_utf8_lossy(&window)).unwrap_or_default();
+            end_tag = format!("</{}sheetData>", detected.main).into_bytes();
+            empty_tag = format!("<{}sheetData/>", detected.main).into_bytes();
+            prefixes = Some(detected);
+        }
+        if found {
+            ReadLimits::check(
+                "worksheet trailing XML",
+                window.len() as u64,
+                limits.max_row_bytes as u64,
+            )?;
+            continue;
+        }
+        match find_bytes(&window, &end_tag).or_else(|| find_bytes(&window, &empty_tag)) {
+            Some(end) => {
+                // Both tags have the same length
+                window.drain(..end + end_tag.len());
+                found = true;
+            }
+            None => {
+                // Keep just enough to match a tag split across chunks
+                let keep = window.len().saturating_sub(end_tag.len() - 1);
+                window.drain(..keep);
+            }
+        }
+    }
+
+    if !found {
+        window.clear();
+    }
+    let tail =
+        Utf8Decoder::new(options.utf8_policy, sheet_path).decode_all(&window, utf8_issues)?;
+    Ok(prefixes.unwrap_or_default().normalize(&tail).into_owned())
+}
+
+// Merged ranges from the <mergeCells> section of a worksheet tail
+fn parse_merged_ranges(tail: &str) -> Vec<CellRange> {
+    xml_elements(xml_section(tail, "mergeCells"), "mergeCell")
+        .into_iter()
+        .filter_map(|el| xml_attr(el, "ref")?.parse().ok())
+        .collect()
+}
+
+// Reject entries whose size in the ZIP directory is already over the limit
+fn check_declared_size(
+    archive: &StreamingZipReader,
+    name: &str,
+    limits: &ReadLimits,
+) -> Result<()> {
+    if let Some(entry) = archive.entries().iter().find(|e| e.name == name) {
+        ReadLimits::check(
+            format!("'{}' uncompressed size", name),
+            entry.uncompressed_size,
+            limits.max_entry_size,
+        )?;
+    }
+    Ok(())
+}
+
+// Read a whole entry, stopping once it decompresses past the limit
+// (the declared size can't be trusted)
+fn read_entry_limited(
+    archive: &mut StreamingZipReader,
+    name: &str,
+    limits: &ReadLimits,
+) -> Result<Vec<u8>> {
+    check_declared_size(archive, name, limits)?;
+    let reader = archive
+        .read_entry_streaming_by_name(name)
+        .map_err(|e| ExcelError::ReadError(format!("Failed to open {}: {}", name, e)))?;
+
+    let mut data = Vec::new();
+    reader
+        .take(limits.max_entry_size.saturating_add(1))
+        .read_to_end(&mut data)
+        .map_err(|e| ExcelError::ReadError(format!("Failed to read {}: {}", name, e)))?;
+    ReadLimits::check(
+        format!("'{}' uncompressed size", name),
+        data.len() as u64,
+        limits.max_entry_size,
+    )?;
+    Ok(data)
+}
+
+// SHA-256 of an entry, hashed as it streams, stopping once it decompresses
+// past the limit
+fn digest_entry_limited(
+    archive: &mut StreamingZipReader,
+    name: &str,
+    limits: &ReadLimits,
+) -> Result<[u8; 32]> {
+    check_declared_size(archive, name, limits)?;
+    let reader = archive
+        .read_entry_streaming_by_name(name)
+        .map_err(|e| ExcelError::ReadError(format!("Failed to open {}: {}", name, e)))?;
+
+    let mut hasher = sha2::Sha256::new();
+    let size = std::io::copy(
+        &mut reader.take(limits.max_entry_size.saturating_add(1)),
+        &mut hasher,
+    )
+    .map_err(|e| ExcelError::ReadError(format!("Failed to read {}: {}", name, e)))?;
+    ReadLimits::check(
+        format!("'{}' uncompressed size", name),
+        size,
+        limits.max_entry_size,
+    )?;
+    Ok(hasher.finalize().into())
+}
+
+/// Iterator over rows in a worksheet
+/// Streams XML data from ZIP without loading entire worksheet into memory
+pub struct RowIterator<'a> {
+    reader: BufReader<Box<dyn Read + 'a>>,
+    sst: &'a SharedStrings,
+    options: &'a ReaderOptions,
+    decoder: Utf8Decoder,
+    utf8_issues: &'a mut Vec<Utf8Issue>,
+    buffer: String,                       // Buffer for reading XML chunks
+    pos: usize,                           // Current scan position in buffer
+    buffer_start: u64,                    // Offset of `buffer` in the decoded sheet XML
+    row_start: u64,                       // Offset of the row last parsed, likewise
+    pending: Option<StoredRow>,           // Parsed row waiting behind blank fill rows
+    next_index: u32,                      // 0-based index of the next row in the sheet grid
+    row_index: u32,                       // 0-based index of the row last returned
+    bytes_read: u64,                      // Uncompressed worksheet bytes read so far
+    scan_from: usize,                     // Where the search for an incomplete row's </row> resumes
+    chunk: Vec<u8>,                       // Reused read buffer for decompressed bytes
+    collect_cell_info: bool,              // Record each cell's column and style
+    row_cell_info: Vec<CellInfo>,         // Cell info of the row last returned
+    merges: Vec<CellRange>,               // Merged ranges to fill (fill_merged_cells)
+    merge_values: Vec<Option<CellValue>>, // Top-left value of each merge once seen
+    prefixes: Option<XmlPrefixes>,        // Namespace prefixes, once the root tag is read
+    row_open: String,                     // "<row", with the main namespace prefix
+    row_close: String,                    // "</row>", likewise
+}
+
+/// Position of a [`RowIterator`] in its sheet, see
+/// [`RowIterator::position`]
+///
+/// Opaque; its text form can be stored and parsed back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowPosition {
+    offset: u64,     // Decoded sheet XML offset of the next row
+    next_index: u32, // Parser state at that offset
+    row_index: u32,
+    part: String, // Worksheet XML path
+}
+
+impl fmt::Display for RowPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.offset, self.next_index, self.row_index, self.part
+        )
+    }
+}
+
+impl FromStr for RowPosition {
+    type Err = ExcelError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || ExcelError::InvalidFormat(format!("Invalid row position: '{}'", s));
+        let mut fields = s.splitn(4, ':');
+        let mut number = || fields.next().and_then(|f| f.parse::<u64>().ok());
+        let offset = number().ok_or_else(invalid)?;
+        let next_index = number().and_then(|n| u32::try_from(n).ok());
+        let row_index = number().and_then(|n| u32::try_from(n).ok());
+        let part = fields.next().filter(|p| !p.is_empty());
+        match (next_index, row_index, part) {
+            (Some(next_index), Some(row_index), Some(part)) => Ok(RowPosition {
+                offset,
+                next_index,
+                row_index,
+                part: part.to_string(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// A `<row>` as stored in the XML
+struct StoredRow<C = CellValue> {
+    index: u32,
+    cells: Vec<C>,
+    cell_info: Vec<CellInfo>,
+}
+
+// A parsed cell: an owned value, or one borrowing shared strings
+trait ParsedCell<'s>: From<CellValue> {
+    fn shared(text: &'s str) -> Self;
+    fn is_empty(&self) -> bool;
+}
+
+impl<'s> ParsedCell<'s> for CellValue {
+    fn shared(text: &'s str) -> Self {
+        CellValue::String(text.to_string())
+    }
+
+    fn is_empty(&self) -> bool {
+        CellValue::is_empty(self)
+    }
+}
+
+impl<'s> ParsedCell<'s> for BorrowedCell<'s> {
+    fn shared(text: &'s str) -> Self {
+        BorrowedCell::Shared(text)
+    }
+
+    fn is_empty(&self) -> bool {
+        BorrowedCell::is_empty(self)
+    }
+}
+
+// Position, style and formula text of a parsed cell
+#[derive(Debug, Clone, PartialEq)]
+struct CellInfo {
+    col: u32,
+    style: u32,
+    formula: Option<String>,
+    number: Option<f64>, // Stored value of a numeric cell, before date guessing
+}
+
+impl<'a> Iterator for RowIterator<'a> {
+    type Item = Result<Vec<CellValue>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let row = match self.pending.take() {
+                Some(row) => row,
+                None => match self.next_stored_row()? {
+                    Ok(row) => row,
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+
+            if self.options.blank_rows == BlankRows::Fill && row.index > self.next_index {
+                // Rows absent from the XML are blank in Excel
+                let mut blank = StoredRow {
+                    index: self.next_index,
+                    cells: Vec::new(),
+                    cell_info: Vec::new(),
+                };
+                self.fill_merged(&mut blank);
+                self.pending = Some(row);
+                match self.emit(blank) {
+                    Some(cells) => return Some(Ok(cells)),
+                    None => continue,
+                }
+            }
+
+            let mut row = row;
+            self.fill_merged(&mut row);
+            match self.options.blank_rows {
+                BlankRows::Skip if row.cells.iter().all(|c| c.is_empty()) => {
+                    self.next_index = row.index.saturating_add(1);
+                    continue;
+                }
+                _ => {
+                    if let Some(cells) = self.emit(row) {
+                        return Some(Ok(cells));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a> RowIterator<'a> {
+    pub(crate) fn new(
+        reader: Box<dyn Read + 'a>,
+        sst: &'a SharedStrings,
+        options: &'a ReaderOptions,
+        sheet_path: &str,
+        utf8_issues: &'a mut Vec<Utf8Issue>,
+        merges: Vec<CellRange>,
+    ) -> Self {
+        RowIterator {
+            reader: BufReader::with_capacity(64 * 1024, reader), // 64KB buffer
+            sst,
+            options,
+            decoder: Utf8Decoder::new(options.utf8_policy, sheet_path),
+            utf8_issues,
+            buffer: String::with_capacity(128 * 1024), // 128KB for XML parsing
+            pos: 0,
+            buffer_start: 0,
+            row_start: 0,
+            pending: None,
+            next_index: 0,
+            row_index: 0,
+            bytes_read: 0,
+            scan_from: 0,
+            chunk: vec![0u8; 32 * 1024],
+            collect_cell_info: false,
+            row_cell_info: Vec::new(),
+            merge_values: vec![None; merges.len()],
+            merges,
+            prefixes: None,
+            row_open: "<row".to_string(),
+            row_close: "</row>".to_string(),
+        }
+    }
+
+    /// 0-based index of the row most recently returned by `next()`
+    pub fn row_index(&self) -> u32 {
+        self.row_index
+    }
+
+    /// Where the iterator stands, to continue from the next row later with
+    /// [`resume_at`](Self::resume_at)
+    ///
+    /// The position survives a restart through its text form
+    /// (`to_string()` and `parse()`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::{RowPosition, StreamingReader};
+    ///
+    /// let mut reader = StreamingReader::open("import.xlsx")?;
+    /// let mut rows = reader.stream_rows("Sheet1")?;
+    /// for row in rows.by_ref().take(1000) {
+    ///     row?;
+    /// }
+    /// let checkpoint = rows.position().to_string(); // store it somewhere
+    /// drop(rows);
+    ///
+    /// // After a restart
+    /// let position: RowPosition = checkpoint.parse()?;
+    /// let mut rows = reader.stream_rows("Sheet1")?;
+    /// rows.resume_at(&position)?;
+    /// let next = rows.next(); // row 1001
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn position(&self) -> RowPosition {
+        RowPosition {
+            // A row held back behind blank fill rows is read again
+            offset: match self.pending {
+                Some(_) => self.row_start,
+                None => self.buffer_start + self.pos as u64,
+            },
+            next_index: self.next_index,
+            row_index: self.row_index,
+            part: self.decoder.part.clone(),
+        }
+    }
+
+    /// Continue from a [`position`](Self::position) of an earlier iterator
+    /// over the same sheet, before reading any row
+    ///
+    /// Rows before the position are not parsed, but the compressed sheet
+    /// XML up to it is still decompressed, since a deflate stream can only
+    /// be read from its start. UTF-8 issues in the skipped part are not
+    /// reported again. Fails with [`ExcelError::NotSupported`] when merged
+    /// cells are filled, as their values may come from skipped rows.
+    pub fn resume_at(&mut self, position: &RowPosition) -> Result<()> {
+        if self.buffer_start + self.pos as u64 > 0 || self.pending.is_some() {
+            return Err(ExcelError::InvalidState(
+                "resume_at() must come before the first row is read".to_string(),
+            ));
+        }
+        if position.part != self.decoder.part {
+            return Err(ExcelError::InvalidState(format!(
+                "Position is in '{}', not '{}'",
+                position.part, self.decoder.part
+            )));
+        }
+        if !self.merges.is_empty() {
+            return Err(ExcelError::NotSupported(
+                "Resuming while filling merged cells".to_string(),
+            ));
+        }
+
+        let mut skipped_issues = Vec::new();
+        loop {
+            self.detect_prefixes();
+            if self.prefixes.is_some() {
+                let skip = position
+                    .offset
+                    .saturating_sub(self.buffer_start)
+                    .min(self.buffer.len() as u64) as usize;
+                if !self.buffer.is_char_boundary(skip) {
+                    return Err(ExcelError::InvalidState(
+                        "Position is not at a row of this sheet".to_string(),
+                    ));
+                }
+                self.buffer.drain(..skip);
+                self.buffer_start += skip;
+                if self.buffer_start == position.offset {
+                    break;
+                }
+            }
+            let n = self
+                .reader
+                .read(&mut self.chunk)
+                .map_err(|e| ExcelError::ReadError(format!("Failed to read XML: {}", e)))?;
+            if n == 0 {
+                return Err(ExcelError::InvalidState(
+                    "Position is past the end of the sheet".to_string(),
+                ));
+            }
+            self.bytes_read += n as u64;
+            ReadLimits::check(
+                format!("'{}' uncompressed size", self.decoder.part),
+                self.bytes_read,
+                self.options.limits.max_entry_size,
+            )?;
+            self.decoder
+                .decode(&self.chunk[..n], &mut self.buffer, &mut skipped_issues)?;
+        }
+        self.next_index = position.next_index;
+        self.row_index = position.row_index;
+        Ok(())
+    }
+
+    // Continue the sheet grid at `index`, for XML that starts mid-sheet
+    pub(crate) fn set_next_index(&mut self, index: u32) {
+        self.next_index = index;
+    }
+
+    // Take namespace prefixes from the root tag once it is in the buffer
+    fn detect_prefixes(&mut self) {
+        if self.prefixes.is_none() {
+            self.prefixes = XmlPrefixes::detect(&self.buffer);
+            if let Some(prefixes) = &self.prefixes {
+                self.row_open = format!("<{}row", prefixes.main);
+                self.row_close = format!("</{}row>", prefixes.main);
+            }
+        }
+    }
+
+    // Copy merged ranges' top-left values into the cells they cover
+    fn fill_merged(&mut self, row: &mut StoredRow) {
+        for (range, value) in self.merges.iter().zip(self.merge_values.iter_mut()) {
+            if !(range.first_row..=range.last_row).contains(&row.index) {
+                continue;
+            }
+            if row.index == range.first_row {
+                *value = row.cells.get(range.first_col as usize).cloned();
+            }
+            let Some(value) = value.as_ref() else {
+                continue;
+            };
+            let last_col = range.last_col as usize;
+            if row.cells.len() <= last_col {
+                row.cells.resize(last_col + 1, CellValue::Empty);
+            }
+            for cell in &mut row.cells[range.first_col as usize..=last_col] {
+                *cell = value.clone();
+            }
+        }
+    }
+
+    // Hand out a row, or None when read middleware skips it
+    fn emit(&mut self, row: StoredRow) -> Option<Vec<CellValue>> {
+        self.row_index = row.index;
+        self.next_index = row.index.saturating_add(1);
+        self.row_cell_info = row.cell_info;
+        if row.index < self.options.first_row() {
+            return None;
+        }
+        let mut cells = row.cells;
+        self.options.middleware.apply(row.index, &mut cells)?;
+        if let Some(rules) = &self.options.infer_types {
+            rules.apply(&mut cells);
+        }
+        Some(cells)
+    }
+
+    /// Read the next `<row>` element and its 0-based index
+    fn next_stored_row<C: ParsedCell<'a>>(&mut self) -> Option<Result<StoredRow<C>>> {
+        loop {
+            self.detect_prefixes();
+
+            // Try to find row in current buffer
+            // (nothing to find while the root tag is incomplete)
+            let row_start = self
+                .prefixes
+                .as_ref()
+                .and_then(|_| find_start_tag(&self.buffer, self.pos, &self.row_open));
+            if let (Some(prefixes), Some(row_start)) = (&self.prefixes, row_start) {
+                // Check if we have the end of the row (`<row .../>` has no cells)
+                let tag_end = find_from(&self.buffer, row_start, ">");
+                let self_closing = tag_end.is_some_and(|t| self.buffer.as_bytes()[t - 1] == b'/');
+                let row_end = if self_closing {
+                    tag_end.map(|t| t + 1)
+                } else {
+                    // Don't rescan what the previous attempt already searched
+                    let from = self.scan_from.max(row_start);
+                    let close = self.row_close.len();
+                    let end = find_from(&self.buffer, from, &self.row_close);
+                    if end.is_none() {
+                        self.scan_from = self.buffer.len().saturating_sub(close - 1).max(row_start);
+                    }
+                    end.map(|end_idx| end_idx + close)
+                };
+                if let Some(row_end) = row_end {
+                    let row_xml = prefixes.normalize(&self.buffer[row_start..row_end]);
+                    let index = parse_row_number(&row_xml)
+                        .map(|r| r.saturating_sub(1))
+                        .unwrap_or(self.next_index);
+                    let mut cell_info = Vec::new();
+                    let result = Self::parse_row(
+                        &row_xml,
+                        self.sst,
+                        self.options,
+                        self.collect_cell_info.then_some(&mut cell_info),
+                    );
+
+                    // Advance position
+                    self.row_start = self.buffer_start + row_start as u64;
+                    self.pos = row_end;
+                    return Some(result.map(|cells| StoredRow {
+                        index,
+                        cells,
+                        cell_info,
+                    }));
+                }
+            }
+
+            // If we are here, either no row found, or incomplete row at end
+            // We need to read more data.
+            // First, compact the buffer if needed (move valid tail to front)
+            if self.pos > 0 {
+                self.buffer_start += self.pos as u64;
+                // If we consumed everything, just clear
+                if self.pos >= self.buffer.len() {
+                    self.buffer.clear();
+                } else {
+                    // We have some data left (incomplete row), move it to front
+                    self.buffer.drain(..self.pos);
+                }
+                self.scan_from = self.scan_from.saturating_sub(self.pos);
+                self.pos = 0;
+            }
+
+            // Read next chunk
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => {
+                    // EOF - flush any dangling partial sequence
+                    if let Err(e) = self.decoder.finish(self.utf8_issues) {
+                        return Some(Err(e));
+                    }
+                    if !self.buffer.is_empty() {
+                        self.buffer.clear();
+                    }
+                    return None;
+                }
+                Ok(n) => {
+                    let limits = &self.options.limits;
+                    self.bytes_read += n as u64;
+                    if let Err(e) = ReadLimits::check(
+                        format!("'{}' uncompressed size", self.decoder.part),
+                        self.bytes_read,
+                        limits.max_entry_size,
+                    )
+                    .and_then(|_| {
+                        ReadLimits::check(
+                            "row XML",
+                            self.buffer.len() as u64,
+                            limits.max_row_bytes as u64,
+                        )
+                    }) {
+                        return Some(Err(e));
+                    }
+
+                    // Append data. Sequences split across chunks are carried over
+                    if let Err(e) =
+                        self.decoder
+                            .decode(&self.chunk[..n], &mut self.buffer, self.utf8_issues)
+                    {
+                        return Some(Err(e));
+                    }
+                }
+                Err(e) => {
+                    return Some(Err(ExcelError::ReadError(format!(
+                        "Failed to read XML: {}",
+                        e
+                    ))))
+                }
+            }
+        }
+    }
+}
+
+impl<'a> RowIterator<'a> {
+    // Parse a row, optionally recording each cell's style index (`s` attribute)
+    // in `cell_info`, aligned with the returned cells
+    fn parse_row<'s, C: ParsedCell<'s>>(
+        row_xml: &str,
+        sst: &'s SharedStrings,
+        options: &ReaderOptions,
+        mut cell_info: Option<&mut Vec<CellInfo>>,
+    ) -> Result<Vec<C>> {
+        let mut row_data: Vec<C> = Vec::new();
+        let mut pos = 0;
+        let mut next_col = 0; // Column of a cell without an `r` attribute
+
+        while let Some(cell_start) = find_start_tag(row_xml, pos, "<c") {
+            // Handle both self-closing <c ... /> and <c ...></c>
+            // (only the opening tag decides - a later cell may be self-closing)
+            let Some(tag_end) = find_from(row_xml, cell_start, ">") else {
+                break; // Incomplete cell tag
+            };
+            let cell_end = if row_xml.as_bytes()[tag_end - 1] == b'/' {
+                tag_end + 1
+            } else if let Some(close_tag_pos) = find_from(row_xml, tag_end, "</c>") {
+                close_tag_pos + 4
+            } else {
+                break; // Incomplete cell tag
+            };
+            let cell_xml = &row_xml[cell_start..cell_end];
+
+            // Extract cell reference (e.g., "A1", "B1", "AA1")
+            let col_idx = xml_attr(cell_xml, "r")
+                .and_then(cellref::col_of)
+                .map_or(next_col, |col| col as usize);
+            next_col = col_idx + 1;
+
+            // Fill empty cells between last column and current column
+            if options.missing_cells == MissingCells::Empty {
+                while row_data.len() < col_idx {
+                    if let Some(cell_info) = cell_info.as_mut() {
+                        cell_info.push(CellInfo {
+                            col: row_data.len() as u32,
+                            style: 0,
+                            formula: None,
+                            number: None,
+                        });
+                    }
+                    row_data.push(C::from(CellValue::Empty));
+                }
+            }
+
+            if let Some(cell_info) = cell_info.as_mut() {
+                cell_info.push(CellInfo {
+                    col: col_idx.max(row_data.len()) as u32,
+                    style: xml_attr(cell_xml, "s")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0),
+                    formula: parse_formula(cell_xml),
+                    number: match xml_attr(cell_xml, "t") {
+                        None | Some("n") => cell_number(cell_xml),
+                        _ => None,
+                    },
+                });
+            }
+
+            // Determine cell type (no type means numeric)
+            let cell_type = xml_attr(cell_xml, "t").unwrap_or("");
+
+            let is_shared_string = cell_type == "s";
+            let is_inline_str = cell_type == "inlineStr";
+            let is_boolean = cell_type == "b";
+            let is_error = cell_type == "e";
+            let is_formula_string = cell_type == "str";
+            let is_iso_date = cell_type == "d"; // Strict OOXML dates
+                                                // Empty type means numeric or date
+
+            // Extract value (shared strings are looked up separately)
+            let mut shared = None;
+            let cell_value = if is_inline_str {
+                // Inline string - <is><t>...</t></is>, or rich text runs
+                match string_item_text(cell_xml) {
+                    Some(value) => {
+                        options.limits.check_cell_text(&value)?;
+                        CellValue::String(value)
+                    }
+                    None => CellValue::Empty,
+                }
+            } else if let Some(v_start) = find_from(cell_xml, 0, "<v>") {
+                if let Some(v_end) = find_from(cell_xml, v_start, "</v>") {
+                    let val_str = &cell_xml[v_start + 3..v_end];
+                    options.limits.check_cell_text(val_str)?;
+
+                    if is_shared_string {
+                        // Lookup in SST, already decoded when the table was loaded
+                        shared = val_str
+                            .parse::<usize>()
+                            .ok()
+                            .map(|idx| sst.get(idx).unwrap_or_default());
+                        CellValue::Empty
+                    } else if is_boolean {
+                        // Boolean: 0 = false, 1 = true
+                        CellValue::Bool(val_str == "1")
+                    } else if is_error {
+                        // Error cell
+                        CellValue::Error(val_str.to_string())
+                    } else if is_formula_string {
+                        // Cached string result of a formula, even if it looks numeric
+                        CellValue::String(decode_xml_entities(val_str))
+                    } else if is_iso_date {
+                        iso_date_value(val_str)
+                    } else if val_str.is_empty() {
+                        CellValue::Empty
+                    } else {
+                        // Numeric value (could be number or date)
+                        // Try to parse as number first
+                        if let Ok(num) = val_str.parse::<f64>() {
+                            // Check if this might be a date
+                            // Dates in Excel are typically between 1 (1900-01-01) and 2958465 (9999-12-31)
+                            // Also check for style attribute 's' which indicates formatting
+                            let has_style = xml_attr(cell_xml, "s").is_some();
+
+                            // If it looks like a date serial number and has a style, try parsing as date
+                            if has_style && (1.0..=2958465.0).contains(&num) && num.fract() < 0.0001
+                            {
+                                // Likely a date - return as string in ISO format
+                                CellValue::String(parse_excel_date(num))
+                            } else if options.numbers_as_decimal {
+                                // Keep the exact digits stored in the XML
+                                val_str
+                                    .parse::<Decimal>()
+                                    .map(CellValue::Decimal)
+                                    .unwrap_or(CellValue::Float(num))
+                            } else if num.fract() == 0.0
+                                && (i64::MIN as f64..=i64::MAX as f64).contains(&num)
+                            {
+                                // Integer
+                                CellValue::Int(num as i64)
+                            } else {
+                                // Float
+                                CellValue::Float(num)
+                            }
+                        } else {
+                            // Can't parse as number, treat as string
+                            CellValue::String(decode_xml_entities(val_str))
+                        }
+                    }
+                } else {
+                    CellValue::Empty
+                }
+            } else {
+                CellValue::Empty
+            };
+
+            let external = match options.external_values {
+                ExternalValues::Cached => None,
+                external_values => match parse_formula(cell_xml) {
+                    Some(f) if !cellref::external_refs(&f).is_empty() => match external_values {
+                        // Formula values carry the leading '=' like on write
+                        ExternalValues::Formula if f.starts_with('=') => {
+                            Some(CellValue::Formula(f))
+                        }
+                        ExternalValues::Formula => Some(CellValue::Formula(format!("={}", f))),
+                        _ => Some(CellValue::Empty),
+                    },
+                    _ => None,
+                },
+            };
+
+            row_data.push(match (external, shared) {
+                (Some(value), _) => C::from(value),
+                (None, Some(text)) => C::shared(text),
+                (None, None) => C::from(cell_value),
+            });
+            pos = cell_end;
+        }
+
+        if !options.keep_trailing_empty {
+            while row_data.last().is_some_and(|c| c.is_empty()) {
+                row_data.pop();
+            }
+            if let Some(cell_info) = cell_info {
+                cell_info.truncate(row_data.len());
+            }
+        }
+
+        Ok(row_data)
+    }
+}
+
+/// Incremental UTF-8 decoder applying a [`Utf8Policy`]
+///
+/// Keeps incomplete trailing sequences between chunks so multi-byte
+/// characters split across reads are decoded correctly.
+struct Utf8Decoder {
+    policy: Utf8Policy,
+    part: String,
+    offset: u64,
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    fn new(policy: Utf8Policy, part: &str) -> Self {
+        Utf8Decoder {
+            policy,
+            part: part.to_string(),
+            offset: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decode a complete part in one go, with namespace prefixes
+    /// normalized (see `XmlPrefixes`)
+    fn decode_all(mut self, input: &[u8], issues: &mut Vec<Utf8Issue>) -> Result<String> {
+        let mut out = String::with_capacity(input.len());
+        self.decode(input, &mut out, issues)?;
+        self.finish(issues)?;
+        match XmlPrefixes::detect(&out) {
+            Some(prefixes) if prefixes != XmlPrefixes::default() => {
+                Ok(prefixes.normalize(&out).into_owned())
+            }
+            _ => Ok(out),
+        }
+    }
+
+    /// Decode a chunk, appending to `out`
+    fn decode(
+        &mut self,
+        input: &[u8],
+        out: &mut String,
+        issues: &mut Vec<Utf8Issue>,
+    ) -> Result<()> {
+        // Complete a sequence split across chunks a few bytes at a time,
+        // rather than copying the whole chunk behind it
+        let mut input = input;
+        while !self.pending.is_empty() && !input.is_empty() {
+            let take = input.len().min(4);
+            let mut joined = [0u8; 8];
+            let carried = self.pending.len();
+            joined[..carried].copy_from_slice(&self.pending);
+            joined[carried..carried + take].copy_from_slice(&input[..take]);
+            self.pending.clear();
+            self.decode_slice(&joined[..carried + take], out, issues)?;
+            input = &input[take..];
+        }
+
+        if input.is_empty() {
+            return Ok(());
+        }
+        self.decode_slice(input, out, issues)
+    }
+
+    // Decode bytes with nothing pending, keeping an incomplete trailing
+    // sequence in `pending`
+    fn decode_slice(
+        &mut self,
+        mut rest: &[u8],
+        out: &mut String,
+        issues: &mut Vec<Utf8Issue>,
+    ) -> Result<()> {
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    out.push_str(valid);
+                    self.offset += rest.len() as u64;
+                    return Ok(());
+                }
+                Err(e) => {
+                    let valid_len = e.valid_up_to();
+                    // Safe: from_utf8 validated this prefix
+                    out.push_str(std::str::from_utf8(&rest[..valid_len]).unwrap_or_default());
+                    self.offset += valid_len as u64;
+
+                    match e.error_len() {
+                        Some(bad_len) => {
+                            self.invalid(bad_len, out, issues)?;
+                            rest = &rest[valid_len + bad_len..];
+                        }
+                        None => {
+                            // Incomplete sequence at end of chunk - wait for more data
+                            self.pending.extend_from_slice(&rest[valid_len..]);
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handle bytes left over at end of input (a truncated sequence)
+    fn finish(&mut self, issues: &mut Vec<Utf8Issue>) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let len = self.pending.len();
+        self.pending.clear();
+        let mut ignored = String::new();
+        self.invalid(len, &mut ignored, issues)
+    }
+
+    fn invalid(&mut self, len: usize, out: &mut String, issues: &mut Vec<Utf8Issue>) -> Result<()> {
+        match self.policy {
+            Utf8Policy::Strict => {
+                return Err(ExcelError::InvalidUtf8 {
+                    part: self.part.clone(),
+                    offset: self.offset,
+                })
+            }
+            Utf8Policy::Lossy => {}
+            Utf8Policy::ReplaceAndReport => issues.push(Utf8Issue {
+                part: self.part.clone(),
+                offset: self.offset,
+                len,
+            }),
+        }
+        out.push(char::REPLACEMENT_CHARACTER);
+        self.offset += len as u64;
+        Ok(())
+    }
+}
+
+// Attribute of a VML start tag, which Excel writes with single or double
+// quotes and line breaks between attributes
+fn vml_attr<'t>(tag: &'t str, name: &str) -> Option<&'t str> {
+    let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+    let mut pos = 0;
+    loop {
+        let at = pos + tag[pos..].find(name)?;
+        let after = at + name.len();
+        let boundary = at > 0 && tag.as_bytes()[at - 1].is_ascii_whitespace();
+        let rest = &tag[after..];
+        if boundary && rest.starts_with('=') {
+            let quote = rest[1..].chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &rest[2..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+        pos = after;
+    }
+}
+
+// Value of attribute `name` in an XML start tag (children are ignored)
+pub(crate) fn xml_attr<'t>(tag: &'t str, name: &str) -> Option<&'t str> {
+    let tag = &tag[..find_from(tag, 0, ">").unwrap_or(tag.len())];
+    let bytes = tag.as_bytes();
+    let mut pos = 0;
+    loop {
+        let at = find_from(tag, pos, name)?;
+        let after = at + name.len();
+        if at > 0 && bytes[at - 1].is_ascii_whitespace() && bytes[after..].starts_with(b"=\"") {
+            let end = find_from(tag, after + 2, "\"")?;
+            return Some(&tag[after + 2..end]);
+        }
+        pos = after;
+    }
+}
+
+// Namespace prefixes a part binds on its root element. Parsing matches
+// unprefixed SpreadsheetML names and `r:` attributes, so documents that
+// prefix the main namespace (`<x:row>`) or name the relationships prefix
+// differently are rewritten to that form. Transitional and strict OOXML
+// namespaces are both recognized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XmlPrefixes {
+    main: String,          // "" or e.g. "x:"
+    relationships: String, // "r:" unless declared otherwise
+}
+
+impl Default for XmlPrefixes {
+    fn default() -> Self {
+        XmlPrefixes {
+            main: String::new(),
+            relationships: "r:".to_string(),
+        }
+    }
+}
+
+impl XmlPrefixes {
+    // Prefixes declared on the root element, None while its start tag is
+    // incomplete
+    fn detect(xml: &str) -> Option<Self> {
+        // Skip the XML declaration, processing instructions and comments
+        let mut pos = 0;
+        let root = loop {
+            let start = find_from(xml, pos, "<")?;
+            match xml.as_bytes().get(start + 1)? {
+                b'?' | b'!' => pos = find_from(xml, start, ">")? + 1,
+                _ => break start,
+            }
+        };
+        let tag = &xml[root..find_from(xml, root, ">")?];
+
+        let mut prefixes = XmlPrefixes::default();
+        for declaration in tag.split_whitespace() {
+            let Some((name, uri)) = declaration
+                .strip_prefix("xmlns")
+                .and_then(|d| d.split_once('='))
+            else {
+                continue;
+            };
+            let uri = uri.trim_end_matches('/').trim_matches(['"', '\'']);
+            let prefix = name.strip_prefix(':').map(|p| format!("{}:", p));
+            if uri == ooxml::MAIN_NS || uri == ooxml::STRICT_MAIN_NS {
+                prefixes.main = prefix.unwrap_or_default();
+            } else if uri == ooxml::RELATIONSHIPS_NS || uri == ooxml::STRICT_RELATIONSHIPS_NS {
+                if let Some(prefix) = prefix {
+                    prefixes.relationships = prefix;
+                }
+            }
+        }
+        Some(prefixes)
+    }
+
+    // `xml` with SpreadsheetML elements unprefixed and relationship
+    // attributes as `r:`
+    fn normalize<'t>(&self, xml: &'t str) -> Cow<'t, str> {
+        let mut xml = Cow::Borrowed(xml);
+        if !self.main.is_empty() {
+            xml = Cow::Owned(
+                xml.replace(&format!("</{}", self.main), "</")
+                    .replace(&format!("<{}", self.main), "<"),
+            );
+        }
+        if self.relationships != "r:" {
+            xml = Cow::Owned(xml.replace(&format!(" {}", self.relationships), " r:"));
+        }
+        xml
+    }
+}
+
+// An entry of a part's .rels file
+#[derive(Debug, Clone)]
+struct Relationship {
+    id: String,
+    rel_type: String,
+    target: String,
+}
+
+// Resolve a relationship target relative to the directory of its source part
+// ("../media/image1.png" from "xl/drawings" -> "xl/media/image1.png")
+pub(crate) fn resolve_part_path(base_dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            _ => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+// Cell-anchored pictures in a drawing part, as (anchor, <xdr:pic> element)
+fn parse_drawing_pictures(xml: &str) -> Vec<(CellRange, &str)> {
+    // 0-based (row, col) of an <xdr:from>/<xdr:to> marker
+    fn marker(anchor: &str, name: &str) -> Option<(u32, u32)> {
+        let marker = *xml_elements(anchor, name).first()?;
+        let value = |tag| xml_section(marker, tag).trim().parse().ok();
+        Some((value("xdr:row")?, value("xdr:col")?))
+    }
+
+    let mut pictures = Vec::new();
+    for tag in ["xdr:twoCellAnchor", "xdr:oneCellAnchor"] {
+        for anchor in xml_elements(xml, tag) {
+            let Some((first_row, first_col)) = marker(anchor, "xdr:from") else {
+                continue;
+            };
+            let (last_row, last_col) = marker(anchor, "xdr:to").unwrap_or((first_row, first_col));
+            for pic in xml_elements(anchor, "xdr:pic") {
+                let range = CellRange::new(first_row, first_col, last_row, last_col);
+                pictures.push((range, pic));
+            }
+        }
+    }
+    pictures
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    memchr::memmem::find(haystack, needle)
+}
+
+// Offset of `needle` in `text` at or after byte `from` (SIMD-accelerated)
+fn find_from(text: &str, from: usize, needle: &str) -> Option<usize> {
+    let haystack = &text.as_bytes()[from..];
+    match needle.as_bytes() {
+        [byte] => memchr::memchr(*byte, haystack),
+        bytes => memchr::memmem::find(haystack, bytes),
+    }
+    .map(|i| from + i)
+}
+
+// Next `open` start tag (e.g. "<c") at or after `from`, skipping longer tag
+// names sharing the prefix (<c> vs <col>). A tag cut off at the end of the
+// text still matches so callers wait for more data.
+fn find_start_tag(xml: &str, from: usize, open: &str) -> Option<usize> {
+    let mut pos = from;
+    loop {
+        let start = find_from(xml, pos, open)?;
+        let after = start + open.len();
+        if matches!(xml.as_bytes().get(after), None | Some(b'>' | b'/'))
+            || xml.as_bytes()[after].is_ascii_whitespace()
+        {
+            return Some(start);
+        }
+        pos = after;
+    }
+}
+
+// Content between `<name ...>` and `</name>`, or "" if absent
+// Cached value of an external link `<cell>`
+fn external_cell_value(cell_type: Option<&str>, value: &str) -> CellValue {
+    match cell_type {
+        _ if value.is_empty() => CellValue::Empty,
+        Some("b") => CellValue::Bool(value == "1"),
+        Some("e") => CellValue::Error(value.to_string()),
+        Some("str" | "s" | "inlineStr") => CellValue::String(decode_xml_entities(value)),
+        _ => match value.parse::<f64>() {
+            Ok(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => CellValue::Int(n as i64),
+            Ok(n) => CellValue::Float(n),
+            Err(_) => CellValue::String(decode_xml_entities(value)),
+        },
+    }
+}
+
+fn xml_section<'t>(xml: &'t str, name: &str) -> &'t str {
+    xml_elements(xml, name)
+        .into_iter()
+        .next()
+        .and_then(|el| {
+            let start = el.find('>')? + 1;
+            Some(&el[start..el.len().saturating_sub(name.len() + 3).max(start)])
+        })
+        .unwrap_or("")
+}
+
+// All `<name>` elements (self-closing or not) in document order
+pub(crate) fn xml_elements<'t>(xml: &'t str, name: &str) -> Vec<&'t str> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut elements = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = xml[pos..].find(&open) {
+        let start = pos + start;
+        let after = start + open.len();
+        // Skip longer tag names sharing the prefix (<font> vs <fonts>)
+        if !xml
+            .as_bytes()
+            .get(after)
+            .is_some_and(|&b| b == b'>' || b == b'/' || b.is_ascii_whitespace())
+        {
+            pos = after;
+            continue;
+        }
+        let Some(tag_end) = xml[start..].find('>').map(|t| start + t + 1) else {
+            break;
+        };
+        let end = if xml[..tag_end].ends_with("/>") {
+            tag_end
+        } else if let Some(close_pos) = xml[tag_end..].find(&close) {
+            tag_end + close_pos + close.len()
+        } else {
+            break;
+        };
+        elements.push(&xml[start..end]);
+        pos = end;
+    }
+
+    elements
+}
+
+// Whether a font flag element (<b/>, <i/>) is present and not switched off
+fn xml_flag(xml: &str, name: &str) -> bool {
+    xml_elements(xml, name)
+        .first()
+        .is_some_and(|el| !matches!(xml_attr(el, "val"), Some("0" | "false")))
+}
+
+// Resolve styles.xml cellXfs into cell formats
+fn parse_cell_formats(xml: &str) -> Vec<CellFormat> {
+    let num_fmts: Vec<(u32, String)> = xml_elements(xml_section(xml, "numFmts"), "numFmt")
+        .into_iter()
+        .filter_map(|el| {
+            let id = xml_attr(el, "numFmtId")?.parse().ok()?;
+            Some((id, decode_xml_entities(xml_attr(el, "formatCode")?)))
+        })
+        .collect();
+
+    let fonts: Vec<(bool, bool, Option<String>)> = xml_elements(xml_section(xml, "fonts"), "font")
+        .into_iter()
+        .map(|el| {
+            let color = xml_elements(el, "color")
+                .first()
+                .and_then(|c| xml_attr(c, "rgb"))
+                .map(str::to_string);
+            (xml_flag(el, "b"), xml_flag(el, "i"), color)
+        })
+        .collect();
+
+    let fills: Vec<Option<String>> = xml_elements(xml_section(xml, "fills"), "fill")
+        .into_iter()
+        .map(|el| {
+            xml_elements(el, "fgColor")
+                .first()
+                .and_then(|c| xml_attr(c, "rgb"))
+                .map(str::to_string)
+        })
+        .collect();
+
+    xml_elements(xml_section(xml, "cellXfs"), "xf")
+        .into_iter()
+        .enumerate()
+        .map(|(index, xf)| {
+            let id = |name| {
+                xml_attr(xf, name)
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0)
+            };
+            let num_fmt_id = id("numFmtId") as u32;
+            let num_fmt = num_fmts
+                .iter()
+                .find(|(fmt_id, _)| *fmt_id == num_fmt_id)
+                .map(|(_, code)| code.clone())
+                .or_else(|| builtin_format_code(num_fmt_id).map(str::to_string));
+            let (bold, italic, font_color) = fonts.get(id("fontId")).cloned().unwrap_or_default();
+
+            CellFormat {
+                style_index: index as u32,
+                num_fmt_id,
+                num_fmt,
+                bold,
+                italic,
+                font_color,
+                fill_color: fills.get(id("fillId")).cloned().flatten(),
+            }
+        })
+        .collect()
+}
+
+// Formula text of a cell (`<f>`), None for plain values and for cells that
+// only reference a shared formula defined elsewhere
+fn parse_formula(cell_xml: &str) -> Option<String> {
+    let start = find_start_tag(cell_xml, 0, "<f")?;
+    let tag_end = find_from(cell_xml, start, ">")?;
+    if cell_xml.as_bytes()[tag_end - 1] == b'/' {
+        return None;
+    }
+    let end = find_from(cell_xml, tag_end, "</f>")?;
+    Some(decode_xml_entities(&cell_xml[tag_end + 1..end])).filter(|f| !f.is_empty())
+}
+
+// ISO 8601 date cell value: with an offset other than UTC it is kept as
+// `DateTimeTz`, otherwise text in the format of `parse_excel_date`
+fn iso_date_value(value: &str) -> CellValue {
+    match chrono::DateTime::parse_from_rfc3339(value) {
+        Ok(dt) if !value.ends_with('Z') && dt.offset().local_minus_utc() != 0 => {
+            CellValue::DateTimeTz(dt)
+        }
+        _ => CellValue::String(iso_date_text(value)),
+    }
+}
+
+// ("2023-10-18T12:00:00Z" -> "2023-10-18 12:00:00", midnight -> date only)
+fn iso_date_text(value: &str) -> String {
+    let value = value.trim_end_matches('Z');
+    match value.split_once('T') {
+        Some((date, "00:00:00" | "00:00:00.000")) => date.to_string(),
+        Some((date, time)) => {
+            let time = time.split('.').next().unwrap_or(time);
+            format!("{} {}", date, time)
+        }
+        None => value.to_string(),
+    }
+}
+
+// Number in a cell's `<v>`
+fn cell_number(cell_xml: &str) -> Option<f64> {
+    let start = find_from(cell_xml, 0, "<v>")? + 3;
+    let end = find_from(cell_xml, start, "</v>")?;
+    cell_xml[start..end].parse().ok()
+}
+
+// Parse the 1-based `r` attribute from a `<row ...>` element; None when
+// missing or outside the sheet grid (a crafted `r="4294967295"` would
+// otherwise overflow the row counters)
+fn parse_row_number(row_xml: &str) -> Option<u32> {
+    xml_attr(row_xml, "r")?
+        .parse()
+        .ok()
+        .filter(|r| (1..=cellref::MAX_ROWS).contains(r))
+}
+
+/// Iterator over rows with resolved cell formatting, see
+/// [`StreamingReader::rows_with_style`]
+pub struct FormattedRowIterator<'a> {
+    inner: RowIterator<'a>,
+    formats: Vec<Arc<CellFormat>>,
+    default_format: Arc<CellFormat>,
+}
+
+impl<'a> FormattedRowIterator<'a> {
+    /// 0-based index of the row most recently returned by `next()`
+    pub fn row_index(&self) -> u32 {
+        self.inner.row_index()
+    }
+}
+
+impl<'a> Iterator for FormattedRowIterator<'a> {
+    type Item = Result<Vec<FormattedCell>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cells = match self.inner.next()? {
+            Ok(cells) => cells,
+            Err(e) => return Some(Err(e)),
+        };
+        let cell_info = &self.inner.row_cell_info;
+        let row = cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let info = cell_info.get(i);
+                let format = info
+                    .and_then(|info| self.formats.get(info.style as usize))
+                    .unwrap_or(&self.default_format);
+                // Elapsed times read as durations, not as dates or numbers
+                let value = match info.and_then(|info| info.number) {
+                    Some(days) if format.is_elapsed_time() => {
+                        serial_to_duration(days).map_or(value, CellValue::Duration)
+                    }
+                    _ => value,
+                };
+                FormattedCell {
+                    value,
+                    format: Arc::clone(format),
+                }
+            })
+            .collect();
+        Some(Ok(row))
+    }
+}
+
+/// Iterator over rows as positioned cells, see [`StreamingReader::cells`]
+pub struct CellIterator<'a> {
+    inner: RowIterator<'a>,
+    hyperlinks: Vec<(CellRange, Hyperlink)>,
+}
+
+impl<'a> Iterator for CellIterator<'a> {
+    type Item = Result<Vec<Cell>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let values = match self.inner.next()? {
+            Ok(values) => values,
+            Err(e) => return Some(Err(e)),
+        };
+        let row = self.inner.row_index();
+        let mut cell_info = std::mem::take(&mut self.inner.row_cell_info).into_iter();
+        let cells = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let info = cell_info.next();
+                let col = info.as_ref().map_or(i as u32, |info| info.col);
+                let mut cell = Cell::new(row, col, value);
+                cell.formula = info.and_then(|info| info.formula);
+                cell.hyperlink = self
+                    .hyperlinks
+                    .iter()
+                    .find(|(range, _)| range.contains(row, col))
+                    .map(|(_, link)| link.clone());
+                cell
+            })
+            .collect();
+        Some(Ok(cells))
+    }
+}
+
+/// A sheet opened with its own ZIP entry reader, see
+/// [`StreamingReader::sheet`]
+///
+/// Reads the shared strings and options of the reader it came from.
+pub struct SheetReader<'r> {
+    archive: StreamingZipReader,
+    sst: &'r SharedStrings,
+    options: &'r ReaderOptions,
+    name: String,
+    sheet_path: String,
+    utf8_issues: Vec<Utf8Issue>,
+}
+
+impl<'r> SheetReader<'r> {
+    /// Name of the sheet
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Stream the rows of the sheet as plain cell value vectors
+    ///
+    /// Each call starts again from the first row.
+    pub fn stream_rows(&mut self) -> Result<RowIterator<'_>> {
+        let merges = if self.options.fill_merged_cells {
+            let tail = scan_sheet_tail(
+                &mut self.archive,
+                &self.sheet_path,
+                self.options,
+                &mut self.utf8_issues,
+            )?;
+            parse_merged_ranges(&tail)
+        } else {
+            Vec::new()
+        };
+        let reader = self
+            .archive
+            .read_entry_streaming_by_name(&self.sheet_path)
+            .map_err(|e| ExcelError::ReadError(format!("Failed to open sheet: {}", e)))?;
+        Ok(RowIterator::new(
+            reader,
+            self.sst,
+            self.options,
+            &self.sheet_path,
+            &mut self.utf8_issues,
+            merges,
+        ))
+    }
+
+    /// Stream the rows of the sheet as [`Row`]s
+    ///
+    /// Each call starts again from the first row.
+    pub fn rows(&mut self) -> Result<RowStructIterator<'_>> {
+        let inner = self.stream_rows()?;
+        Ok(RowStructIterator { inner })
+    }
+
+    /// Invalid UTF-8 sequences replaced while reading this sheet, see
+    /// [`StreamingReader::utf8_issues`]
+    pub fn utf8_issues(&self) -> &[Utf8Issue] {
+        &self.utf8_issues
+    }
+}
+
+/// Iterator wrapper that returns Row structs instead of Vec<CellValue>
+/// for backward compatibility with the old calamine-based API
+pub struct RowStructIterator<'a> {
+    inner: RowIterator<'a>,
+}
+
+impl<'a> RowStructIterator<'a> {
+    /// Where the iterator stands, see [`RowIterator::position`]
+    pub fn position(&self) -> RowPosition {
+        self.inner.position()
+    }
+
+    /// Continue from an earlier position, see [`RowIterator::resume_at`]
+    pub fn resume_at(&mut self, position: &RowPosition) -> Result<()> {
+        self.inner.resume_at(position)
+    }
+}
+
+impl<'a> Iterator for RowStructIterator<'a> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.inner.next()? {
+            Ok(cells) => Some(Ok(Row::new(self.inner.row_index(), cells))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Iterator over rows borrowing shared strings from the reader, see
+/// [`StreamingReader::rows_borrowed`]
+pub struct BorrowedRowIterator<'a> {
+    inner: RowIterator<'a>,
+    pending: Option<BorrowedRow<'a>>, // Parsed row waiting behind blank fill rows
+}
+
+impl<'a> Iterator for BorrowedRowIterator<'a> {
+    type Item = Result<BorrowedRow<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let options = self.inner.options;
+        loop {
+            let mut row = match self.pending.take() {
+                Some(row) => row,
+                None => match self.inner.next_stored_row()? {
+                    Ok(StoredRow { index, cells, .. }) => BorrowedRow { index, cells },
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+
+            if options.blank_rows == BlankRows::Fill && row.index > self.inner.next_index {
+                // Rows absent from the XML are blank in Excel
+                let blank = BorrowedRow {
+                    index: self.inner.next_index,
+                    cells: Vec::new(),
+                };
+                self.pending = Some(std::mem::replace(&mut row, blank));
+            }
+
+            self.inner.row_index = row.index;
+            self.inner.next_index = row.index.saturating_add(1);
+            if row.index < options.first_row()
+                || (options.blank_rows == BlankRows::Skip && row.is_empty())
+            {
+                continue;
+            }
+            return Some(Ok(row));
+        }
+    }
+}
+
+/// Iterator over header-keyed rows, see [`StreamingReader::rows_with_header`]
+pub struct RecordIterator<'a> {
+    inner: RowIterator<'a>,
+    headers: Option<Arc<[String]>>,
+    failed: bool, // The header row was rejected; nothing follows
+}
+
+impl RecordIterator<'_> {
+    /// Header names, once the header row has been read
+    pub fn headers(&self) -> Option<&[String]> {
+        self.headers.as_deref()
+    }
+}
+
+impl<'a> Iterator for RecordIterator<'a> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let options = self.inner.options;
+        loop {
+            let cells = match self.inner.next()? {
+                Ok(cells) => cells,
+                Err(e) => return Some(Err(e)),
+            };
+            match &self.headers {
+                Some(headers) => {
+                    return Some(Ok(Record {
+                        index: self.inner.row_index(),
+                        headers: Arc::clone(headers),
+                        cells,
+                        case_insensitive: options.case_insensitive_headers,
+                    }))
+                }
+                // Rows above the header never get here, so this is it
+                None => {
+                    let names = cells.iter().map(|c| c.as_string()).collect();
+                    match resolve_headers(names, options) {
+                        Ok(names) => self.headers = Some(names.into()),
+                        Err(e) => {
+                            self.failed = true;
+                            return Some(Err(e));
+                        }
                     }
                 }
+            }
+        }
+    }
+}
+
+// Apply the duplicate header policy to a header row
+fn resolve_headers(mut names: Vec<String>, options: &ReaderOptions) -> Result<Vec<String>> {
+    if options.duplicate_headers == DuplicateHeaders::KeepFirst {
+        return Ok(names);
+    }
+    let key = |name: &str| {
+        if options.case_insensitive_headers {
+            name.to_lowercase()
+        } else {
+            name.to_string()
+        }
+    };
+    let mut taken: HashSet<String> = names.iter().map(|name| key(name)).collect();
+    let mut first_col: HashMap<String, usize> = HashMap::new();
+    for (col, name) in names.iter_mut().enumerate() {
+        if name.is_empty() {
+            continue;
+        }
+        let first = *first_col.entry(key(name)).or_insert(col);
+        if first == col {
+            continue;
+        }
+        if options.duplicate_headers == DuplicateHeaders::Error {
+            return Err(ExcelError::InvalidFormat(format!(
+                "Duplicate header '{}' in columns {} and {}",
+                name,
+                cellref::col_to_name(first as u32),
+                cellref::col_to_name(col as u32)
+            )));
+        }
+        let renamed = (2..)
+            .map(|n| format!("{}_{}", name, n))
+            .find(|candidate| !taken.contains(&key(candidate)))
+            .unwrap_or_default();
+        taken.insert(key(&renamed));
+        *name = renamed;
+    }
+    Ok(names)
+}
+
+/// Iterator over batches of `Row`s, see [`StreamingReader::row_batches`]
+pub struct RowBatchIterator<'a> {
+    rows: RowStructIterator<'a>,
+    batch_size: usize,
+    pending_error: Option<ExcelError>,
+}
+
+impl<'a> Iterator for RowBatchIterator<'a> {
+    type Item = Result<Vec<Row>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending_error.take() {
+            return Some(Err(e));
+        }
+
+        let mut batch = Vec::with_capacity(self.batch_size);
+        while batch.len() < self.batch_size {
+            match self.rows.next() {
+                Some(Ok(row)) => batch.push(row),
+                Some(Err(e)) if batch.is_empty() => return Some(Err(e)),
+                Some(Err(e)) => {
+                    self.pending_error = Some(e);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        if batch.is_empty() {
+            None
+        } else {
+            Some(Ok(batch))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_sst_size() {
+        let mut sst = SharedStrings::default();
+        sst.push("hello");
+        sst.push("world");
+        let size = sst.memory();
+        assert!(size > 10); // At least the string bytes
+        assert_eq!(
+            (sst.len(), sst.get(0), sst.get(1)),
+            (2, Some("hello"), Some("world"))
+        );
+        assert_eq!(sst.get(2), None);
+    }
+
+    #[test]
+    fn test_xml_scanning_helpers() {
+        let xml = r#"<cols/><c xr="Z9" r="B1" t="s"><v>0</v></c><c>"#;
+        assert_eq!(find_start_tag(xml, 0, "<c"), Some(7));
+        assert_eq!(find_start_tag(xml, 8, "<c"), Some(43));
+        assert_eq!(xml_attr(&xml[7..], "r"), Some("B1"));
+        assert_eq!(xml_attr(&xml[7..], "t"), Some("s"));
+        assert_eq!(xml_attr(&xml[7..], "s"), None);
+        assert_eq!(find_from(xml, 7, "</c>"), Some(39));
+
+        // Cells without attributes or with attribute names ending in "r"
+        let row_xml = r#"<row r="1"><c><v>1</v></c><c xr="A1" r="C1"><v>2</v></c></row>"#;
+        let cells = RowIterator::parse_row::<CellValue>(
+            row_xml,
+            &SharedStrings::default(),
+            &ReaderOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            cells,
+            vec![CellValue::Int(1), CellValue::Empty, CellValue::Int(2)]
+        );
+    }
+
+    #[test]
+    fn test_string_items_and_entities() {
+        assert_eq!(decode_xml_entities("a &amp;lt; b"), "a &lt; b");
+        assert_eq!(decode_xml_entities("&#65;&#x1F4CA;&quot;"), "A📊\"");
+        assert_eq!(decode_xml_entities("AT&T & co;"), "AT&T & co;");
+
+        let rich = r#"<si><r><rPr><b/></rPr><t>Bold</t></r><r><t xml:space="preserve"> plain</t></r></si>"#;
+        assert_eq!(string_item_text(rich).as_deref(), Some("Bold plain"));
+        let phonetic = r#"<si><t>東京</t><rPh sb="0" eb="2"><t>トウキョウ</t></rPh><phoneticPr fontId="1"/></si>"#;
+        assert_eq!(string_item_text(phonetic).as_deref(), Some("東京"));
+        assert_eq!(string_item_text("<si><t/></si>").as_deref(), Some(""));
+        assert_eq!(string_item_text(r#"<c r="A1" t="inlineStr"/>"#), None);
+    }
+
+    #[test]
+    fn test_parse_row_numbers_as_decimal() {
+        let row_xml =
+            r#"<row r="1"><c r="A1"><v>0.30000000000000004</v></c><c r="B1"><v>12</v></c></row>"#;
+
+        let cells = RowIterator::parse_row::<CellValue>(
+            row_xml,
+            &SharedStrings::default(),
+            &ReaderOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(cells[0], CellValue::Float(0.30000000000000004));
+        assert_eq!(cells[1], CellValue::Int(12));
+
+        let options = ReaderOptions::new().with_numbers_as_decimal(true);
+        let cells =
+            RowIterator::parse_row::<CellValue>(row_xml, &SharedStrings::default(), &options, None)
+                .unwrap();
+        assert_eq!(
+            cells[0],
+            CellValue::Decimal(Decimal::new(30000000000000004, 17))
+        );
+        assert_eq!(cells[1], CellValue::Decimal(Decimal::new(12, 0)));
+    }
+
+    #[test]
+    fn test_parse_row_empty_cells() {
+        let row_xml = r#"<row r="1"><c r="B1"><v>1</v></c><c r="D1" s="1"/></row>"#;
+
+        let cells = RowIterator::parse_row::<CellValue>(
+            row_xml,
+            &SharedStrings::default(),
+            &ReaderOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(cells, vec![CellValue::Empty, CellValue::Int(1)]);
+
+        let options = ReaderOptions::new()
+            .with_keep_trailing_empty(true)
+            .with_missing_cells(MissingCells::Omit);
+        let cells =
+            RowIterator::parse_row::<CellValue>(row_xml, &SharedStrings::default(), &options, None)
+                .unwrap();
+        assert_eq!(cells, vec![CellValue::Int(1), CellValue::Empty]);
+    }
+
+    fn collect_rows(xml: &'static str, options: &ReaderOptions) -> Vec<(u32, Vec<CellValue>)> {
+        let mut issues = Vec::new();
+        let merges: Vec<CellRange> = xml_elements(xml, "mergeCell")
+            .into_iter()
+            .filter_map(|el| xml_attr(el, "ref")?.parse().ok())
+            .filter(|_| options.fill_merged_cells)
+            .collect();
+        let reader = Box::new(std::io::Cursor::new(xml.as_bytes()));
+        let sst = SharedStrings::default();
+        let mut iter = RowIterator::new(reader, &sst, options, "sheet.xml", &mut issues, merges);
+        let mut rows = Vec::new();
+        while let Some(cells) = iter.next() {
+            rows.push((iter.row_index(), cells.unwrap()));
+        }
+        rows
+    }
+
+    // Build a minimal single-sheet xlsx around the given worksheet XML,
+    // plus any extra parts (which replace default parts of the same name)
+    fn write_test_xlsx(sheet_xml: &str, extra: &[(&str, &str)]) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut zip = StreamingZipWriter::new(file.path()).unwrap();
+        let entries = [
+            (
+                "xl/workbook.xml",
+                r#"<workbook><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            ),
+            ("xl/worksheets/sheet1.xml", sheet_xml),
+        ];
+        let defaults = entries
+            .iter()
+            .filter(|(name, _)| !extra.iter().any(|(extra_name, _)| extra_name == name));
+        for (name, data) in defaults.chain(extra) {
+            zip.start_entry(name).unwrap();
+            zip.write_data(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_prefixed_strict_namespaces() {
+        // Strict namespaces bound to non-default prefixes throughout
+        let ns = r#"xmlns:x="http://purl.oclc.org/ooxml/spreadsheetml/main" xmlns:rel="http://purl.oclc.org/ooxml/officeDocument/relationships""#;
+        let workbook = format!(
+            r#"<?xml version="1.0"?><x:workbook {} conformance="strict"><x:sheets><x:sheet name="Data" sheetId="1" rel:id="rId1"/></x:sheets></x:workbook>"#,
+            ns
+        );
+        let sheet = format!(
+            r#"<?xml version="1.0"?><x:worksheet {}><x:dimension ref="A1:B3"/><x:sheetData><x:row r="1"><x:c r="A1" t="inlineStr"><x:is><x:t>When</x:t></x:is></x:c><x:c r="B1"><x:v>2</x:v></x:c></x:row><x:row r="2"><x:c r="A2" t="d"><x:v>2024-03-01T00:00:00Z</x:v></x:c><x:c r="B2" t="d"><x:v>2024-03-01T12:30:00.000</x:v></x:c></x:row></x:sheetData><x:mergeCells count="1"><x:mergeCell ref="A3:B3"/></x:mergeCells><x:hyperlinks><x:hyperlink ref="B1" rel:id="rId1"/></x:hyperlinks></x:worksheet>"#,
+            ns
+        );
+        let rels = r#"<Relationships><Relationship Id="rId1" Type="http://purl.oclc.org/ooxml/officeDocument/relationships/hyperlink" Target="https://example.com/" TargetMode="External"/></Relationships>"#;
+        let file = write_test_xlsx(
+            &sheet,
+            &[
+                ("xl/workbook.xml", &workbook),
+                ("xl/worksheets/_rels/sheet1.xml.rels", rels),
+            ],
+        );
+
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        assert_eq!(reader.sheet_names(), vec!["Data"]);
+        let rows: Vec<Vec<CellValue>> = reader
+            .stream_rows("Data")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![CellValue::String("When".into()), CellValue::Int(2)],
+                vec![
+                    CellValue::String("2024-03-01".into()),
+                    CellValue::String("2024-03-01 12:30:00".into())
+                ],
+            ]
+        );
+        assert_eq!(
+            reader.merged_ranges("Data").unwrap(),
+            vec![CellRange::new(2, 0, 2, 1)]
+        );
+        assert_eq!(
+            reader.hyperlinks("Data").unwrap()[0].1.target,
+            "https://example.com/"
+        );
+        assert_eq!(reader.row_count_fast("Data").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_merged_cells() {
+        let xml = r#"<worksheet><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>Region</t></is></c><c r="C1"><v>7</v></c></row><row r="2"><c r="C2"><v>8</v></c></row></sheetData><mergeCells count="2"><mergeCell ref="A1:B2"/><mergeCell ref="D1:D1"/></mergeCells></worksheet>"#;
+
+        let region = CellValue::String("Region".to_string());
+        let rows = collect_rows(xml, &ReaderOptions::new().with_fill_merged_cells(true));
+        assert_eq!(
+            rows[0].1,
+            vec![region.clone(), region.clone(), CellValue::Int(7)]
+        );
+        assert_eq!(rows[1].1, vec![region.clone(), region, CellValue::Int(8)]);
+
+        let rows = collect_rows(xml, &ReaderOptions::default());
+        assert_eq!(
+            rows[1].1,
+            vec![CellValue::Empty, CellValue::Empty, CellValue::Int(8)]
+        );
+
+        let file = write_test_xlsx(xml, &[]);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        assert_eq!(
+            reader.merged_ranges("Sheet1").unwrap(),
+            vec![CellRange::new(0, 0, 1, 1), CellRange::new(0, 3, 0, 3)]
+        );
+    }
+
+    #[test]
+    fn test_hyperlinks() {
+        let sheet = r#"<worksheet><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>Partner site</t></is></c><c r="B1" t="inlineStr"><is><t>Jump</t></is></c></row></sheetData><hyperlinks><hyperlink ref="A1" r:id="rId1" tooltip="Open &amp; go"/><hyperlink ref="B1" location="Sheet2!A1"/></hyperlinks></worksheet>"#;
+        let rels = r#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/?a=1&amp;b=2" TargetMode="External"/></Relationships>"#;
+        let file = write_test_xlsx(sheet, &[("xl/worksheets/_rels/sheet1.xml.rels", rels)]);
+
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let links = reader.hyperlinks("Sheet1").unwrap();
+        assert_eq!(links.len(), 2);
+
+        let rows: Vec<Vec<Cell>> = reader
+            .cells("Sheet1")
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        let link = rows[0][0].hyperlink.as_ref().unwrap();
+        assert_eq!(link.target, "https://example.com/?a=1&b=2");
+        assert_eq!(link.tooltip.as_deref(), Some("Open & go"));
+        let link = rows[0][1].hyperlink.as_ref().unwrap();
+        assert_eq!(link.target, "");
+        assert_eq!(link.location.as_deref(), Some("Sheet2!A1"));
+    }
+
+    #[test]
+    fn test_formula_string_and_error_cells() {
+        let sheet = r#"<worksheet><sheetData><row r="1"><c r="A1" t="str"><f>TEXT(B1,"000")</f><v>007</v></c><c r="B1"><f>3+4</f><v>7</v></c><c r="C1" t="e"><f>1/0</f><v>#DIV/0!</v></c><c r="D1"><f t="shared" ref="D1:D2" si="0">B1&amp;"x"</f><v>1</v></c></row><row r="2"><c r="D2"><f t="shared" si="0"/><v>2</v></c></row></sheetData></worksheet>"#;
+        let file = write_test_xlsx(sheet, &[]);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+
+        let rows: Vec<Vec<Cell>> = reader
+            .cells("Sheet1")
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(rows[0][0].value, CellValue::String("007".to_string()));
+        assert_eq!(rows[0][0].formula.as_deref(), Some(r#"TEXT(B1,"000")"#));
+        assert_eq!(rows[0][1].value, CellValue::Int(7));
+        assert_eq!(rows[0][1].formula.as_deref(), Some("3+4"));
+        assert_eq!(rows[0][2].value, CellValue::Error("#DIV/0!".to_string()));
+        assert_eq!(rows[0][3].formula.as_deref(), Some(r#"B1&"x""#));
+        assert_eq!(rows[1][3].formula, None);
+        assert_eq!(rows[1][0].formula, None);
+    }
+
+    #[test]
+    fn test_images() {
+        let sheet = r#"<worksheet><sheetData/><drawing r:id="rId1"/></worksheet>"#;
+        let sheet_rels = r#"<Relationships><Relationship Id="rId1" Target="../drawings/drawing1.xml"/></Relationships>"#;
+        let drawing = r#"<xdr:wsDr><xdr:twoCellAnchor><xdr:from><xdr:col>1</xdr:col><xdr:colOff>0</xdr:colOff><xdr:row>4</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:from><xdr:to><xdr:col>3</xdr:col><xdr:colOff>0</xdr:colOff><xdr:row>9</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:to><xdr:pic><xdr:nvPicPr><xdr:cNvPr id="2" name="Picture 1" descr="Red chair"/></xdr:nvPicPr><xdr:blipFill><a:blip r:embed="rId7"/></xdr:blipFill></xdr:pic><xdr:clientData/></xdr:twoCellAnchor>
+<xdr:absoluteAnchor><xdr:pic><xdr:blipFill><a:blip r:embed="rId7"/></xdr:blipFill></xdr:pic></xdr:absoluteAnchor></xdr:wsDr>"#;
+        let drawing_rels = r#"<Relationships><Relationship Id="rId7" Target="../media/image1.PNG"/></Relationships>"#;
+        let file = write_test_xlsx(
+            sheet,
+            &[
+                ("xl/worksheets/_rels/sheet1.xml.rels", sheet_rels),
+                ("xl/drawings/drawing1.xml", drawing),
+                ("xl/drawings/_rels/drawing1.xml.rels", drawing_rels),
+                ("xl/media/image1.PNG", "not really a png"),
+            ],
+        );
+
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let images = reader.images("Sheet1").unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].name, "Picture 1");
+        assert_eq!(images[0].description.as_deref(), Some("Red chair"));
+        assert_eq!(images[0].path, "xl/media/image1.PNG");
+        assert_eq!(images[0].extension().as_deref(), Some("png"));
+        assert_eq!(images[0].anchor, CellRange::new(4, 1, 9, 3));
+        assert_eq!(images[0].data, b"not really a png");
+    }
+
+    #[test]
+    fn test_vml_attr_quotes() {
+        let shape = "<v:shape id=\"LH\" o:spid=\"_x0000_s1025\"\n style='width:42pt;height:21pt'><v:imagedata o:relid=\"rId1\"/></v:shape>";
+        assert_eq!(vml_attr(shape, "id"), Some("LH"));
+        assert_eq!(vml_attr(shape, "style"), Some("width:42pt;height:21pt"));
+        assert_eq!(vml_attr(shape, "o:relid"), None);
+        assert_eq!(vml_attr(shape, "spid"), None);
+    }
+
+    #[test]
+    fn test_defined_names_and_tables() {
+        let sheet = r#"<worksheet><sheetData><row r="3"><c r="B3" t="inlineStr"><is><t>Id</t></is></c></row></sheetData><tableParts count="1"><tablePart r:id="rId2"/></tableParts></worksheet>"#;
+        let rels = r#"<Relationships><Relationship Id="rId2" Target="../tables/table1.xml"/></Relationships>"#;
+        let table = r#"<table id="1" name="Table1" displayName="Orders" ref="B3:D20" totalsRowCount="1"><autoFilter ref="B3:D19"/><tableColumns count="3"><tableColumn id="1" name="Id"/><tableColumn id="2" name="Customer"/><tableColumn id="3" name="Amount &amp; Tax"/></tableColumns></table>"#;
+        let workbook = r#"<workbook><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets><definedNames><definedName name="_xlnm.Print_Area" localSheetId="0" hidden="1">Sheet1!$A$1:$D$20</definedName><definedName name="Rate">0.07</definedName></definedNames></workbook>"#;
+        let file = write_test_xlsx(
+            sheet,
+            &[
+                ("xl/workbook.xml", workbook),
+                ("xl/worksheets/_rels/sheet1.xml.rels", rels),
+                ("xl/tables/table1.xml", table),
+            ],
+        );
+
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let tables = reader.tables("Sheet1").unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "Orders");
+        assert_eq!(tables[0].columns, vec!["Id", "Customer", "Amount & Tax"]);
+        assert_eq!(tables[0].data_range(), CellRange::new(3, 1, 18, 3));
+
+        let names = reader.defined_names().unwrap();
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0].scope.as_deref(), Some("Sheet1"));
+        assert!(names[0].hidden);
+        assert_eq!(
+            names[0].range(),
+            Some(("Sheet1".to_string(), CellRange::new(0, 0, 19, 3)))
+        );
+        assert_eq!(names[1].refers_to, "0.07");
+        assert_eq!(names[1].range(), None);
+    }
+
+    #[test]
+    fn test_row_count_fast() {
+        let sheet = r#"<worksheet><dimension ref="A1:C250"/><sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData></worksheet>"#;
+        let file = write_test_xlsx(sheet, &[]);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 250);
+
+        let sheet = r#"<worksheet><dimension ref="A1"/><sheetData><row r="1" spans="1:2"><c r="A1"><v>1</v></c></row><row r="7"/><rows/></sheetData></worksheet>"#;
+        let file = write_test_xlsx(sheet, &[]);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 7);
+
+        let file = write_test_xlsx("<worksheet><sheetData/></worksheet>", &[]);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_resolve_part_path() {
+        assert_eq!(
+            resolve_part_path("xl/drawings", "../media/image1.png"),
+            "xl/media/image1.png"
+        );
+        assert_eq!(
+            resolve_part_path("xl/worksheets", "/xl/tables/table1.xml"),
+            "xl/tables/table1.xml"
+        );
+    }
+
+    #[test]
+    fn test_blank_rows() {
+        let xml = r#"<sheetData><row r="1"><c r="A1"><v>1</v></c></row><row r="2"/><row r="3"></row><row r="5"><c r="A5"><v>5</v></c></row></sheetData>"#;
+
+        let rows = collect_rows(xml, &ReaderOptions::default());
+        let indices: Vec<u32> = rows.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+        assert!(rows[3].1.is_empty());
+        assert_eq!(rows[4].1, vec![CellValue::Int(5)]);
+
+        let rows = collect_rows(xml, &ReaderOptions::new().with_blank_rows(BlankRows::Keep));
+        let indices: Vec<u32> = rows.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![0, 1, 2, 4]);
+
+        let rows = collect_rows(xml, &ReaderOptions::new().with_blank_rows(BlankRows::Skip));
+        let indices: Vec<u32> = rows.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![0, 4]);
+    }
+
+    #[test]
+    fn test_parse_cell_formats() {
+        let xml = r#"<styleSheet><numFmts count="1"><numFmt numFmtId="164" formatCode="0.0 &quot;kg&quot;"/></numFmts>
+<fonts count="2"><font><sz val="11"/></font><font><b/><color rgb="FF0000FF"/></font></fonts>
+<fills count="3"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill>
+<fill><patternFill patternType="solid"><fgColor rgb="FFFF0000"/></patternFill></fill></fills>
+<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0"/></cellStyleXfs>
+<cellXfs count="3"><xf numFmtId="0" fontId="0" fillId="0"/><xf numFmtId="10" fontId="1" fillId="2"/>
+<xf numFmtId="164" fontId="0" fillId="0"></xf></cellXfs></styleSheet>"#;
 
-                // Extract r:id attribute
-                if let Some(rid_start) = sheet_tag.find("r:id=\"") {
-                    let rid_start = rid_start + 6;
-                    if let Some(rid_end) = sheet_tag[rid_start..].find("\"") {
-                        let rid = &sheet_tag[rid_start..rid_start + rid_end];
-                        sheet_ids.push(rid.to_string());
-                    }
+        let formats = parse_cell_formats(xml);
+        assert_eq!(formats.len(), 3);
+        assert_eq!(formats[0].num_fmt.as_deref(), Some("General"));
+        assert!(!formats[0].bold);
+        assert_eq!(formats[1].num_fmt.as_deref(), Some("0.00%"));
+        assert!(formats[1].bold);
+        assert_eq!(formats[1].font_color.as_deref(), Some("FF0000FF"));
+        assert_eq!(formats[1].fill_color.as_deref(), Some("FFFF0000"));
+        assert_eq!(formats[2].num_fmt.as_deref(), Some("0.0 \"kg\""));
+        assert_eq!(formats[2].style_index, 2);
+    }
+
+    #[test]
+    fn test_parse_row_cell_info() {
+        let row_xml =
+            r#"<row r="1"><c r="B1" s="2"><v>1</v></c><c r="D1" s="1"><v>2</v></c></row>"#;
+        let mut cell_info = Vec::new();
+        let options = ReaderOptions::new().with_missing_cells(MissingCells::Omit);
+        let cells = RowIterator::parse_row::<CellValue>(
+            row_xml,
+            &SharedStrings::default(),
+            &options,
+            Some(&mut cell_info),
+        )
+        .unwrap();
+        assert_eq!(cells.len(), 2);
+        assert_eq!(
+            cell_info,
+            vec![
+                CellInfo {
+                    col: 1,
+                    style: 2,
+                    formula: None,
+                    number: Some(1.0)
+                },
+                CellInfo {
+                    col: 3,
+                    style: 1,
+                    formula: None,
+                    number: Some(2.0)
                 }
+            ]
+        );
+    }
 
-                pos = sheet_end;
-            } else {
-                break;
-            }
+    #[test]
+    fn test_parse_row_implicit_positions() {
+        // Cells without `r` follow the previous cell; empty untyped cells
+        let row_xml = "<row>\n<c r=\"C1\"><v>1</v></c><c\tt=\"n\"><v>2</v></c><c/><c><v></v></c><c><v>5</v></c></row>";
+        let cells = RowIterator::parse_row::<CellValue>(
+            row_xml,
+            &SharedStrings::default(),
+            &ReaderOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            cells,
+            vec![
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::Int(1),
+                CellValue::Int(2),
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::Int(5)
+            ]
+        );
+
+        let mut cell_info = Vec::new();
+        let options = ReaderOptions::new().with_missing_cells(MissingCells::Omit);
+        RowIterator::parse_row::<CellValue>(
+            row_xml,
+            &SharedStrings::default(),
+            &options,
+            Some(&mut cell_info),
+        )
+        .unwrap();
+        let cols: Vec<u32> = cell_info.iter().map(|info| info.col).collect();
+        assert_eq!(cols, vec![2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_sheet_readers_interleave() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(file.path()).unwrap();
+        for i in 0..3 {
+            writer.write_row([format!("order {}", i)]).unwrap();
         }
-        // Now load workbook.xml.rels to map rIds to worksheet paths
-        let mut sheet_paths = Vec::new();
+        writer.add_sheet("Customers").unwrap();
+        for i in 0..3 {
+            writer.write_row([format!("customer {}", i)]).unwrap();
+        }
+        writer.save().unwrap();
 
-        let rels_data = archive
-            .read_entry_by_name("xl/_rels/workbook.xml.rels")
-            .map_err(|e| {
-                ExcelError::ReadError(format!("Failed to open workbook.xml.rels: {}", e))
-            })?;
-        let rels_data = String::from_utf8_lossy(&rels_data).to_string();
-
-        // Map rIds to worksheet paths
-        for rid in &sheet_ids {
-            // Find <Relationship Id="rId1" Target="worksheets/sheet1.xml"/>
-            if let Some(rel_start) = rels_data.find(&format!("Id=\"{}\"", rid)) {
-                // Find the start of this Relationship tag
-                let tag_start = rels_data[..rel_start]
-                    .rfind("<Relationship")
-                    .unwrap_or(rel_start.saturating_sub(100));
-
-                // Find the end of this Relationship tag
-                let tag_end = if let Some(end_pos) = rels_data[rel_start..].find("/>") {
-                    rel_start + end_pos + 2
-                } else {
-                    rels_data.len()
-                };
+        let reader = StreamingReader::open(file.path()).unwrap();
+        let mut orders = reader.sheet("Sheet1").unwrap();
+        let mut customers = reader.sheet("Customers").unwrap();
+        assert_eq!(customers.name(), "Customers");
+        let mut a = orders.rows().unwrap();
+        let mut b = customers.stream_rows().unwrap();
+        let mut joined = Vec::new();
+        while let (Some(order), Some(customer)) = (a.next(), b.next()) {
+            joined.push((order.unwrap().to_strings()[0].clone(), customer.unwrap()));
+        }
+        assert_eq!(joined.len(), 3);
+        assert_eq!(joined[2].0, "order 2");
+        assert_eq!(joined[2].1, [CellValue::from("customer 2")]);
 
-                let rel_tag = &rels_data[tag_start..tag_end];
+        // Reading again starts over
+        drop(a);
+        assert_eq!(orders.rows().unwrap().count(), 3);
+        assert!(matches!(
+            reader.sheet("Missing"),
+            Err(ExcelError::SheetNotFound { .. })
+        ));
+    }
 
-                // Extract Target from this specific tag
-                if let Some(target_start) = rel_tag.find("Target=\"") {
-                    let target_start = target_start + 8;
-                    if let Some(target_end) = rel_tag[target_start..].find("\"") {
-                        let target = &rel_tag[target_start..target_start + target_end];
-                        // Target is relative to xl/, e.g., "worksheets/sheet1.xml"
-                        let full_path = format!("xl/{}", target);
-                        sheet_paths.push(full_path);
-                    }
-                }
-            }
+    #[test]
+    fn test_resume_at_position() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = crate::ExcelWriter::new(file.path()).unwrap();
+        for i in 0..5000 {
+            writer
+                .write_row([i.to_string(), format!("row {}", i)])
+                .unwrap();
         }
+        writer.add_sheet("Other").unwrap();
+        writer.save().unwrap();
 
-        if sheet_names.len() != sheet_paths.len() {
-            return Err(ExcelError::ReadError(format!(
-                "Mismatch between sheet names ({}) and paths ({})",
-                sheet_names.len(),
-                sheet_paths.len()
-            )));
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let all: Vec<Row> = reader.rows("Sheet1").unwrap().map(|r| r.unwrap()).collect();
+        let mut rows = reader.rows("Sheet1").unwrap();
+        let fresh = rows.position();
+        for row in rows.by_ref().take(3210) {
+            row.unwrap();
         }
+        let checkpoint = rows.position().to_string();
+        drop(rows);
 
-        Ok((sheet_names, sheet_paths))
-    }
+        let position: RowPosition = checkpoint.parse().unwrap();
+        let mut rows = reader.rows("Sheet1").unwrap();
+        rows.resume_at(&position).unwrap();
+        assert_eq!(rows.position(), position);
+        let rest: Vec<Row> = rows.map(|r| r.unwrap()).collect();
+        assert_eq!(rest, all[3210..]);
 
-    fn estimate_sst_size(sst: &[String]) -> usize {
-        sst.iter().map(|s| s.len() + 24).sum() // 24 bytes per String overhead
-    }
-}
+        // The start position resumes at the first row
+        let mut rows = reader.rows("Sheet1").unwrap();
+        rows.resume_at(&fresh).unwrap();
+        assert_eq!(rows.count(), 5000);
 
-/// Iterator over rows in a worksheet
-/// Streams XML data from ZIP without loading entire worksheet into memory
-pub struct RowIterator<'a> {
-    reader: BufReader<Box<dyn Read + 'a>>,
-    sst: &'a [String],
-    buffer: String, // Buffer for reading XML chunks
-    pos: usize,     // Current scan position in buffer
-}
+        let mut rows = reader.rows("Other").unwrap();
+        assert!(matches!(
+            rows.resume_at(&position),
+            Err(ExcelError::InvalidState(_))
+        ));
+        assert!("12:x".parse::<RowPosition>().is_err());
 
-impl<'a> Iterator for RowIterator<'a> {
-    type Item = Result<Vec<CellValue>>;
+        // A row waiting behind blank rows is read again
+        let file = write_test_xlsx(
+            r#"<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c></row><row r="4"><c r="A4"><v>4</v></c></row></sheetData></worksheet>"#,
+            &[],
+        );
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let mut rows = reader.stream_rows("Sheet1").unwrap();
+        rows.next().unwrap().unwrap();
+        assert!(rows.next().unwrap().unwrap().is_empty());
+        let position = rows.position();
+        drop(rows);
+        let mut rows = reader.stream_rows("Sheet1").unwrap();
+        rows.resume_at(&position).unwrap();
+        let rest: Vec<_> = rows.map(|r| r.unwrap()).collect();
+        assert_eq!(rest, [vec![], vec![CellValue::Int(4)]]);
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            // Try to find row in current buffer
-            let search_slice = &self.buffer[self.pos..];
-            if let Some(start_idx) = search_slice.find("<row") {
-                let row_start = self.pos + start_idx;
-                // Check if we have the end of the row
-                if let Some(end_idx) = self.buffer[row_start..].find("</row>") {
-                    let row_end = row_start + end_idx + 6; // + length of </row>
+    #[test]
+    fn test_rows_borrowed() {
+        let file = write_test_xlsx(
+            r#"<worksheet><sheetData><row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row><row r="3"><c r="A3" t="s"><v>1</v></c><c r="B3"><v>42</v></c><c r="C3" t="s"><v>7</v></c></row><row r="4"><c r="A4" t="inlineStr"><is><t>inline</t></is></c></row></sheetData></worksheet>"#,
+            &[(
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Name</t></si><si><t>Alice &amp; Bob</t></si></sst>"#,
+            )],
+        );
 
-                    let row_xml = &self.buffer[row_start..row_end];
-                    let result = Self::parse_row(row_xml, self.sst);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let owned: Vec<Row> = reader.rows("Sheet1").unwrap().map(|r| r.unwrap()).collect();
+        let rows: Vec<BorrowedRow> = reader
+            .rows_borrowed("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].get(0), Some(&BorrowedCell::Shared("Name")));
+        assert!(rows[1].is_empty());
+        assert_eq!(
+            rows[2].cells,
+            [
+                BorrowedCell::Shared("Alice & Bob"),
+                BorrowedCell::Value(CellValue::Int(42)),
+                BorrowedCell::Shared(""),
+            ]
+        );
+        assert_eq!(rows[3].get(0).and_then(|c| c.as_str()), Some("inline"));
 
-                    // Advance position
-                    self.pos = row_end;
-                    return Some(result);
-                }
-            }
+        // Same values as the owned rows
+        let copied: Vec<Row> = rows.iter().map(|r| r.to_row()).collect();
+        assert_eq!(copied, owned);
 
-            // If we are here, either no row found, or incomplete row at end
-            // We need to read more data.
-            // First, compact the buffer if needed (move valid tail to front)
-            if self.pos > 0 {
-                // If we consumed everything, just clear
-                if self.pos >= self.buffer.len() {
-                    self.buffer.clear();
-                } else {
-                    // We have some data left (incomplete row), move it to front
-                    self.buffer.drain(..self.pos);
-                }
-                self.pos = 0;
-            }
+        let options = ReaderOptions::new()
+            .with_skip_rows(1)
+            .with_blank_rows(BlankRows::Skip);
+        let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+        let indices: Vec<u32> = reader
+            .rows_borrowed("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().index)
+            .collect();
+        assert_eq!(indices, [2, 3]);
 
-            // Read next chunk
-            let mut chunk = vec![0u8; 32 * 1024];
-            match self.reader.read(&mut chunk) {
-                Ok(0) => {
-                    // EOF
-                    if !self.buffer.is_empty() {
-                        self.buffer.clear();
-                    }
-                    return None;
-                }
-                Ok(n) => {
-                    // Append data. Use lossy utf8 conversion to be safe
-                    let s = String::from_utf8_lossy(&chunk[..n]);
-                    self.buffer.push_str(&s);
-                }
-                Err(e) => {
-                    return Some(Err(ExcelError::ReadError(format!(
-                        "Failed to read XML: {}",
-                        e
-                    ))))
-                }
-            }
-        }
+        let options = ReaderOptions::new().with_fill_merged_cells(true);
+        let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+        assert!(matches!(
+            reader.rows_borrowed("Sheet1"),
+            Err(ExcelError::NotSupported(_))
+        ));
     }
-}
 
-impl<'a> RowIterator<'a> {
-    fn parse_row(row_xml: &str, sst: &[String]) -> Result<Vec<CellValue>> {
-        let mut row_data = Vec::new();
-        let mut pos = 0;
+    #[test]
+    fn test_header_row_and_skip_rows() {
+        // Title banner, blank spacer, then the header and data rows
+        let file = write_test_xlsx(
+            r#"<worksheet><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>Q3 Report</t></is></c></row><row r="3"><c r="A3" t="inlineStr"><is><t>Name</t></is></c><c r="B3" t="inlineStr"><is><t>Amount</t></is></c></row><row r="4"><c r="A4" t="inlineStr"><is><t>Ann</t></is></c><c r="B4"><v>10</v></c></row><row r="5"><c r="A5" t="inlineStr"><is><t>Bob</t></is></c></row></sheetData></worksheet>"#,
+            &[],
+        );
 
-        while let Some(cell_start) = row_xml[pos..]
-            .find("<c ")
-            .or_else(|| row_xml[pos..].find("<c>"))
-        {
-            let cell_start = pos + cell_start;
+        let options = ReaderOptions::new().with_skip_rows(2);
+        let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+        let rows: Vec<Row> = reader.rows("Sheet1").unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].index, 2);
+        assert_eq!(rows[0].to_strings(), vec!["Name", "Amount"]);
 
-            // Handle both self-closing <c ... /> and <c ...></c>
-            let (cell_end, cell_xml) =
-                if let Some(self_close_pos) = row_xml[cell_start..].find("/>") {
-                    let end = cell_start + self_close_pos + 2;
-                    let xml = &row_xml[cell_start..end];
-                    (end, xml)
-                } else if let Some(close_tag_pos) = row_xml[cell_start..].find("</c>") {
-                    let end = cell_start + close_tag_pos + 4;
-                    let xml = &row_xml[cell_start..end];
-                    (end, xml)
-                } else {
-                    break; // Incomplete cell tag
-                };
+        let records: Vec<Record> = reader
+            .rows_with_header("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].index, 3);
+        assert_eq!(records[0].get("Amount"), Some(&CellValue::Int(10)));
+        assert_eq!(records[1].get("Amount"), Some(&CellValue::Empty));
+        assert_eq!(records[1].get("Missing"), None);
+        let pairs: Vec<(&str, String)> =
+            records[0].iter().map(|(h, v)| (h, v.as_string())).collect();
+        assert_eq!(
+            pairs,
+            vec![("Name", "Ann".to_string()), ("Amount", "10".to_string())]
+        );
 
-            // Extract cell reference (e.g., "A1", "B1", "AA1")
-            let col_idx = if let Some(r_start) = cell_xml.find("r=\"") {
-                let r_start = r_start + 3;
-                if let Some(r_end) = cell_xml[r_start..].find("\"") {
-                    let cell_ref = &cell_xml[r_start..r_start + r_end];
-                    parse_column_index(cell_ref)
-                } else {
-                    row_data.len()
-                }
-            } else {
-                row_data.len()
-            };
+        // The header row can be named directly, and middleware sees it
+        let options = ReaderOptions::new().with_header_row(2).with_middleware(
+            crate::middleware::RenameHeaders::new([("Name", "name")]).header_row(2),
+        );
+        let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+        let mut records = reader.rows_with_header("Sheet1").unwrap();
+        let first = records.next().unwrap().unwrap();
+        assert_eq!(records.headers().unwrap(), ["name", "Amount"]);
+        assert_eq!(
+            first.get("name"),
+            Some(&CellValue::String("Ann".to_string()))
+        );
+        assert_eq!(records.count(), 1);
+    }
 
-            // Fill empty cells between last column and current column
-            while row_data.len() < col_idx {
-                row_data.push(CellValue::Empty);
-            }
+    #[test]
+    fn test_duplicate_headers() {
+        let header: String = ["Amount", "Name", "amount", "Amount", "Amount_2", "", ""]
+            .iter()
+            .map(|h| format!(r#"<c t="inlineStr"><is><t>{}</t></is></c>"#, h))
+            .collect();
+        let values: String = (1..=5).map(|v| format!("<c><v>{}</v></c>", v)).collect();
+        let file = write_test_xlsx(
+            &format!(
+                r#"<worksheet><sheetData><row r="1">{}</row><row r="2">{}</row></sheetData></worksheet>"#,
+                header, values
+            ),
+            &[],
+        );
+        let read = |options: ReaderOptions| {
+            let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+            let mut records = reader.rows_with_header("Sheet1").unwrap();
+            let record = records.next().unwrap().unwrap();
+            assert!(records.next().is_none());
+            record
+        };
 
-            // Determine cell type
-            let cell_type = if let Some(t_start) = cell_xml.find("t=\"") {
-                let t_start = t_start + 3;
-                if let Some(t_end) = cell_xml[t_start..].find("\"") {
-                    &cell_xml[t_start..t_start + t_end]
-                } else {
-                    ""
-                }
-            } else {
-                "" // No type means numeric
-            };
+        let record = read(ReaderOptions::new());
+        assert_eq!(record.headers[3], "Amount");
+        assert_eq!(record.get("Amount"), Some(&CellValue::Int(1)));
+        assert_eq!(record.get("AMOUNT"), None);
 
-            let is_shared_string = cell_type == "s";
-            let is_inline_str = cell_type == "inlineStr";
-            let is_boolean = cell_type == "b";
-            let is_error = cell_type == "e";
-            // Empty type means numeric or date
+        let record = read(ReaderOptions::new().with_duplicate_headers(DuplicateHeaders::Suffix));
+        assert_eq!(
+            &record.headers[..],
+            ["Amount", "Name", "amount", "Amount_3", "Amount_2", "", ""]
+        );
+        assert_eq!(record.get("Amount_3"), Some(&CellValue::Int(4)));
 
-            // Extract value
-            let cell_value = if is_inline_str {
-                // Inline string - look for <is><t>...</t></is>
-                if let Some(t_start) = cell_xml.find("<t>") {
-                    if let Some(t_end) = cell_xml[t_start..].find("</t>") {
-                        let value = cell_xml[t_start + 3..t_start + t_end].to_string();
-                        CellValue::String(decode_xml_entities(&value))
-                    } else {
-                        CellValue::Empty
-                    }
-                } else {
-                    CellValue::Empty
-                }
-            } else if let Some(v_start) = cell_xml.find("<v>") {
-                if let Some(v_end) = cell_xml[v_start..].find("</v>") {
-                    let val_str = &cell_xml[v_start + 3..v_start + v_end];
+        let record = read(
+            ReaderOptions::new()
+                .with_duplicate_headers(DuplicateHeaders::Suffix)
+                .with_case_insensitive_headers(true),
+        );
+        assert_eq!(
+            &record.headers[..],
+            ["Amount", "Name", "amount_3", "Amount_4", "Amount_2", "", ""]
+        );
+        assert_eq!(record.get("AMOUNT_4"), Some(&CellValue::Int(4)));
+        assert_eq!(record.get("name"), Some(&CellValue::Int(2)));
 
-                    if is_shared_string {
-                        // Lookup in SST
-                        if let Ok(idx) = val_str.parse::<usize>() {
-                            let value = sst.get(idx).cloned().unwrap_or_default();
-                            CellValue::String(decode_xml_entities(&value))
-                        } else {
-                            CellValue::Empty
-                        }
-                    } else if is_boolean {
-                        // Boolean: 0 = false, 1 = true
-                        CellValue::Bool(val_str == "1")
-                    } else if is_error {
-                        // Error cell
-                        CellValue::Error(val_str.to_string())
-                    } else {
-                        // Numeric value (could be number or date)
-                        // Try to parse as number first
-                        if let Ok(num) = val_str.parse::<f64>() {
-                            // Check if this might be a date
-                            // Dates in Excel are typically between 1 (1900-01-01) and 2958465 (9999-12-31)
-                            // Also check for style attribute 's' which indicates formatting
-                            let has_style = cell_xml.contains("s=\"");
+        let options = ReaderOptions::new().with_duplicate_headers(DuplicateHeaders::Error);
+        let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+        let mut records = reader.rows_with_header("Sheet1").unwrap();
+        let err = records.next().unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            ExcelError::InvalidFormat(ref msg) if msg == "Duplicate header 'Amount' in columns A and D"
+        ));
+        assert!(records.next().is_none());
+    }
 
-                            // If it looks like a date serial number and has a style, try parsing as date
-                            if has_style && (1.0..=2958465.0).contains(&num) && num.fract() < 0.0001
-                            {
-                                // Likely a date - return as string in ISO format
-                                CellValue::String(parse_excel_date(num))
-                            } else if num.fract() == 0.0
-                                && (i64::MIN as f64..=i64::MAX as f64).contains(&num)
-                            {
-                                // Integer
-                                CellValue::Int(num as i64)
-                            } else {
-                                // Float
-                                CellValue::Float(num)
-                            }
-                        } else {
-                            // Can't parse as number, treat as string
-                            CellValue::String(decode_xml_entities(val_str))
-                        }
-                    }
-                } else {
-                    CellValue::Empty
-                }
-            } else {
-                CellValue::Empty
-            };
+    #[test]
+    fn test_external_links() {
+        let workbook = r#"<workbook><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets><externalReferences><externalReference r:id="rId2"/></externalReferences></workbook>"#;
+        let workbook_rels = r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="externalLinks/externalLink1.xml"/></Relationships>"#;
+        let link = r#"<externalLink><externalBook r:id="rId1"><sheetNames><sheetName val="Rates"/><sheetName val="Notes"/></sheetNames><sheetDataSet><sheetData sheetId="0"><row r="2"><cell r="B2"><v>1.5</v></cell><cell r="C2" t="str"><v>EUR</v></cell></row></sheetData></sheetDataSet></externalBook></externalLink>"#;
+        let link_rels = r#"<Relationships><Relationship Id="rId1" Target="file:///C:/data/fx%20rates.xlsx" TargetMode="External"/></Relationships>"#;
+        let sheet = r#"<worksheet><sheetData><row r="1"><c r="A1"><f>[1]Rates!B2*2</f><v>3</v></c><c r="B1"><f>A1+1</f><v>4</v></c><c r="C1" t="str"><f>'[1]Rates'!C2</f><v>EUR</v></c></row></sheetData></worksheet>"#;
+        let file = write_test_xlsx(
+            sheet,
+            &[
+                ("xl/workbook.xml", workbook),
+                ("xl/_rels/workbook.xml.rels", workbook_rels),
+                ("xl/externalLinks/externalLink1.xml", link),
+                ("xl/externalLinks/_rels/externalLink1.xml.rels", link_rels),
+            ],
+        );
 
-            row_data.push(cell_value);
-            pos = cell_end;
-        }
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let links = reader.external_links().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].index, 1);
+        assert_eq!(links[0].file_name(), Some("fx%20rates.xlsx"));
+        assert_eq!(links[0].sheet_names, vec!["Rates", "Notes"]);
+        assert_eq!(
+            links[0].cached_value("Rates", 1, 1),
+            Some(&CellValue::Float(1.5))
+        );
+        assert_eq!(
+            links[0].cached_value("Rates", 1, 2),
+            Some(&CellValue::String("EUR".to_string()))
+        );
+        assert_eq!(
+            ExternalLink::resolve_formula("[1]Rates!B2*2", &links),
+            "'[fx rates.xlsx]Rates'!B2*2"
+        );
+        assert_eq!(
+            ExternalLink::resolve_formula("'[1]Rates'!C2&[7]X!A1", &links),
+            "'[fx rates.xlsx]Rates'!C2&[7]X!A1"
+        );
 
-        Ok(row_data)
+        // Cached by default; stripped or exposed on request
+        let values = |external_values| {
+            let options = ReaderOptions::new().with_external_values(external_values);
+            let mut reader = StreamingReader::open_with_options(file.path(), options).unwrap();
+            let row = reader.rows("Sheet1").unwrap().next().unwrap().unwrap();
+            row.cells
+        };
+        assert_eq!(
+            values(ExternalValues::Cached),
+            vec![
+                CellValue::Int(3),
+                CellValue::Int(4),
+                CellValue::String("EUR".to_string())
+            ]
+        );
+        assert_eq!(
+            values(ExternalValues::Empty),
+            vec![CellValue::Empty, CellValue::Int(4)]
+        );
+        assert_eq!(
+            values(ExternalValues::Formula),
+            vec![
+                CellValue::Formula("=[1]Rates!B2*2".to_string()),
+                CellValue::Int(4),
+                CellValue::Formula("='[1]Rates'!C2".to_string())
+            ]
+        );
+
+        let mut plain = StreamingReader::open(write_test_xlsx(sheet, &[]).path()).unwrap();
+        assert!(plain.external_links().unwrap().is_empty());
     }
-}
 
-// Parse column index from cell reference (e.g., "A1" -> 0, "B1" -> 1, "AA1" -> 26)
-fn parse_column_index(cell_ref: &str) -> usize {
-    let mut col_idx = 0usize;
-    for ch in cell_ref.chars() {
-        if ch.is_ascii_alphabetic() {
-            col_idx = col_idx * 26 + (ch.to_ascii_uppercase() as usize - 'A' as usize + 1);
-        } else {
-            break;
-        }
+    #[test]
+    fn test_cell_text_limit() {
+        let row_xml = r#"<row r="1"><c r="A1" t="inlineStr"><is><t>too long</t></is></c></row>"#;
+        let limits = ReadLimits {
+            max_cell_text_len: 4,
+            ..ReadLimits::default()
+        };
+        let options = ReaderOptions::new().with_limits(limits);
+        let err =
+            RowIterator::parse_row::<CellValue>(row_xml, &SharedStrings::default(), &options, None)
+                .unwrap_err();
+        assert!(matches!(err, ExcelError::LimitExceeded { limit: 4, .. }));
     }
-    col_idx.saturating_sub(1) // Convert to 0-based index
-}
 
-/// Iterator wrapper that returns Row structs instead of Vec<CellValue>
-/// for backward compatibility with the old calamine-based API
-pub struct RowStructIterator<'a> {
-    inner: RowIterator<'a>,
-    row_index: u32,
-}
+    #[test]
+    fn test_read_limits_unlimited() {
+        let limits = ReadLimits::unlimited();
+        assert!(limits.check_cell_text(&"x".repeat(1 << 20)).is_ok());
+        assert!(ReadLimits::check("entry", 11, 10).is_err());
+    }
 
-impl<'a> Iterator for RowStructIterator<'a> {
-    type Item = Result<Row>;
+    #[test]
+    fn test_utf8_decoder_policies() {
+        let bytes = b"ok\xFFok";
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.inner.next()? {
-            Ok(cells) => {
-                let row = Row::new(self.row_index, cells);
-                self.row_index += 1;
-                Some(Ok(row))
-            }
-            Err(e) => Some(Err(e)),
-        }
+        let mut issues = Vec::new();
+        let text = Utf8Decoder::new(Utf8Policy::Lossy, "part.xml")
+            .decode_all(bytes, &mut issues)
+            .unwrap();
+        assert_eq!(text, "ok\u{FFFD}ok");
+        assert!(issues.is_empty());
+
+        let text = Utf8Decoder::new(Utf8Policy::ReplaceAndReport, "part.xml")
+            .decode_all(bytes, &mut issues)
+            .unwrap();
+        assert_eq!(text, "ok\u{FFFD}ok");
+        assert_eq!(
+            issues,
+            vec![Utf8Issue {
+                part: "part.xml".to_string(),
+                offset: 2,
+                len: 1
+            }]
+        );
+
+        let err = Utf8Decoder::new(Utf8Policy::Strict, "part.xml")
+            .decode_all(bytes, &mut issues)
+            .unwrap_err();
+        assert!(matches!(err, ExcelError::InvalidUtf8 { offset: 2, .. }));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_utf8_decoder_split_sequence() {
+        // "é" = C3 A9 split across two chunks must not be replaced
+        let mut decoder = Utf8Decoder::new(Utf8Policy::Strict, "sheet.xml");
+        let mut issues = Vec::new();
+        let mut out = String::new();
+        decoder.decode(b"caf\xC3", &mut out, &mut issues).unwrap();
+        decoder.decode(b"\xA9!", &mut out, &mut issues).unwrap();
+        decoder.finish(&mut issues).unwrap();
+        assert_eq!(out, "café!");
+    }
 
     #[test]
-    fn test_estimate_sst_size() {
-        let sst = vec!["hello".to_string(), "world".to_string()];
-        let size = StreamingReader::estimate_sst_size(&sst);
-        assert!(size > 10); // At least the string bytes
+    fn test_utf8_decoder_byte_at_a_time() {
+        let text = "a😀é\u{FFFD}z".as_bytes();
+        let mut decoder = Utf8Decoder::new(Utf8Policy::ReplaceAndReport, "sheet.xml");
+        let mut issues = Vec::new();
+        let mut out = String::new();
+        for byte in text.iter().chain(b"\xF0\x9F!") {
+            decoder.decode(&[*byte], &mut out, &mut issues).unwrap();
+        }
+        decoder.finish(&mut issues).unwrap();
+        assert_eq!(out, "a😀é\u{FFFD}z\u{FFFD}!");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].offset, text.len() as u64);
+    }
+
+    #[test]
+    fn test_row_iterator_reuses_buffers() {
+        let mut xml = String::from("<worksheet><sheetData>");
+        for i in 1..=20_000 {
+            xml.push_str(&format!(
+                r#"<row r="{i}"><c r="A{i}"><v>{i}</v></c><c r="B{i}" t="inlineStr"><is><t>row {i}</t></is></c></row>"#
+            ));
+        }
+        xml.push_str("</sheetData></worksheet>");
+
+        let mut issues = Vec::new();
+        let options = ReaderOptions::default();
+        let reader = Box::new(std::io::Cursor::new(xml.into_bytes()));
+        let sst = SharedStrings::default();
+        let mut iter =
+            RowIterator::new(reader, &sst, &options, "sheet.xml", &mut issues, Vec::new());
+
+        iter.next().unwrap().unwrap();
+        let capacities = (iter.buffer.capacity(), iter.chunk.capacity());
+        for row in iter.by_ref() {
+            row.unwrap();
+        }
+        assert_eq!(iter.row_index(), 19_999);
+        // Steady state reading never grows or replaces the buffers
+        assert_eq!((iter.buffer.capacity(), iter.chunk.capacity()), capacities);
     }
 
     #[test]
@@ -853,4 +4988,58 @@ mod tests {
         assert_eq!(feb28_1900, "1900-02-28", "Feb 28, 1900");
         assert_eq!(mar1_1900, "1900-03-01", "Mar 1, 1900");
     }
+
+    #[test]
+    fn test_workbook_properties_and_stats() {
+        let file = write_test_xlsx(
+            "<worksheet><sheetData/></worksheet>",
+            &[
+                (
+                    "xl/workbook.xml",
+                    r#"<workbook><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets><definedNames><definedName name="A">Sheet1!$A$1</definedName><definedName name="B">Sheet1!$B$1</definedName></definedNames><externalReferences><externalReference r:id="rId9"/></externalReferences></workbook>"#,
+                ),
+                (
+                    "docProps/core.xml",
+                    r#"<cp:coreProperties><dc:title>Q3 &amp; Q4</dc:title><dc:creator>Ada</dc:creator><dcterms:created xsi:type="dcterms:W3CDTF">2024-03-01T12:00:00+02:00</dcterms:created><dcterms:modified>bad</dcterms:modified></cp:coreProperties>"#,
+                ),
+                (
+                    "docProps/app.xml",
+                    "<Properties><Application>Microsoft Excel</Application><Company/></Properties>",
+                ),
+                ("xl/vbaProject.bin", "macros"),
+            ],
+        );
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        let props = reader.properties().unwrap();
+        assert_eq!(props.title.as_deref(), Some("Q3 & Q4"));
+        assert_eq!(props.creator.as_deref(), Some("Ada"));
+        assert_eq!(
+            props.created.map(|d| d.to_rfc3339()).as_deref(),
+            Some("2024-03-01T12:00:00+02:00")
+        );
+        assert_eq!(props.modified, None);
+        assert_eq!(props.application.as_deref(), Some("Microsoft Excel"));
+        assert_eq!(props.company, None);
+        assert_eq!(
+            reader.workbook_stats().unwrap(),
+            WorkbookStats {
+                sheet_count: 1,
+                defined_names: 2,
+                has_macros: true,
+                has_external_links: true,
+            }
+        );
+
+        // No docProps at all
+        let file = write_test_xlsx("<worksheet><sheetData/></worksheet>", &[]);
+        let mut reader = StreamingReader::open(file.path()).unwrap();
+        assert_eq!(reader.properties().unwrap(), WorkbookProperties::default());
+        assert_eq!(
+            reader.workbook_stats().unwrap(),
+            WorkbookStats {
+                sheet_count: 1,
+                ..WorkbookStats::default()
+            }
+        );
+    }
 }
@@ -0,0 +1,375 @@
+//! Group-by summaries collected while rows are written
+//!
+//! [`SummaryWriter`] wraps an [`ExcelWriter`]: rows go to the data sheet as
+//! usual while sums, counts, minimums and maximums are kept per group of key
+//! columns. [`finish`](SummaryWriter::finish) adds a "Summary" sheet with one
+//! row per group and, optionally, a row of `SUBTOTAL` formulas under the data.
+//! Only the aggregates are held in memory, one set per group.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::summary::{Aggregate, SummaryWriter};
+//! use excelstream::types::CellValue;
+//! use excelstream::ExcelWriter;
+//!
+//! let mut writer = SummaryWriter::new(ExcelWriter::new("sales.xlsx")?)
+//!     .group_by([0])
+//!     .aggregate(2, Aggregate::Sum)
+//!     .aggregate(2, Aggregate::Count)
+//!     .with_subtotals(true);
+//!
+//! writer.write_header(["Region", "Product", "Amount"])?;
+//! writer.write_row_typed(&[
+//!     CellValue::String("North".to_string()),
+//!     CellValue::String("Widget".to_string()),
+//!     CellValue::Float(120.0),
+//! ])?;
+//! writer.finish()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::cellref;
+use crate::error::Result;
+use crate::types::{CellValue, WriteReport};
+use crate::writer::ExcelWriter;
+use indexmap::IndexMap;
+
+/// Aggregate computed per group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Sum of the numeric values
+    Sum,
+    /// Number of non-empty values
+    Count,
+    /// Smallest numeric value
+    Min,
+    /// Largest numeric value
+    Max,
+}
+
+impl Aggregate {
+    fn label(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "Sum",
+            Aggregate::Count => "Count",
+            Aggregate::Min => "Min",
+            Aggregate::Max => "Max",
+        }
+    }
+
+    /// `SUBTOTAL` function number (3 = COUNTA, 4 = MAX, 5 = MIN, 9 = SUM)
+    fn subtotal_function(self) -> u32 {
+        match self {
+            Aggregate::Sum => 9,
+            Aggregate::Count => 3,
+            Aggregate::Min => 5,
+            Aggregate::Max => 4,
+        }
+    }
+}
+
+/// Running value of one aggregate within one group
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    sum: f64,
+    count: u64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: &CellValue) {
+        if value.is_empty() {
+            return;
+        }
+        self.count += 1;
+        if let Some(v) = value.as_f64() {
+            self.sum += v;
+            self.min = Some(self.min.map_or(v, |m| m.min(v)));
+            self.max = Some(self.max.map_or(v, |m| m.max(v)));
+        }
+    }
+
+    fn value(&self, aggregate: Aggregate) -> CellValue {
+        let number = |v: Option<f64>| v.map_or(CellValue::Empty, CellValue::Float);
+        match aggregate {
+            Aggregate::Sum => CellValue::Float(self.sum),
+            Aggregate::Count => CellValue::Int(self.count as i64),
+            Aggregate::Min => number(self.min),
+            Aggregate::Max => number(self.max),
+        }
+    }
+}
+
+struct Group {
+    keys: Vec<CellValue>,
+    accumulators: Vec<Accumulator>,
+}
+
+/// Writer that keeps group aggregates of the rows it writes
+///
+/// Groups appear on the summary sheet in the order they were first seen.
+/// Keys compare by their text, so `Int(1)` and `String("1")` are one group.
+pub struct SummaryWriter {
+    writer: ExcelWriter,
+    key_columns: Vec<usize>,
+    aggregates: Vec<(usize, Aggregate)>,
+    header: Option<Vec<String>>,
+    groups: IndexMap<Vec<String>, Group>,
+    data_rows: u32,
+    subtotals: bool,
+    summary_sheet: String,
+}
+
+impl SummaryWriter {
+    /// Wrap a writer; rows go to its current sheet
+    pub fn new(writer: ExcelWriter) -> Self {
+        SummaryWriter {
+            writer,
+            key_columns: Vec::new(),
+            aggregates: Vec::new(),
+            header: None,
+            groups: IndexMap::new(),
+            data_rows: 0,
+            subtotals: false,
+            summary_sheet: "Summary".to_string(),
+        }
+    }
+
+    /// Group by these columns (0-based); without any, one group covers all rows
+    pub fn group_by<I: IntoIterator<Item = usize>>(mut self, columns: I) -> Self {
+        self.key_columns = columns.into_iter().collect();
+        self
+    }
+
+    /// Add an aggregate of a column (0-based) to the summary
+    pub fn aggregate(mut self, column: usize, aggregate: Aggregate) -> Self {
+        self.aggregates.push((column, aggregate));
+        self
+    }
+
+    /// Write a row of `SUBTOTAL` formulas under the data, one per aggregated
+    /// column (the first aggregate of a column decides the function)
+    pub fn with_subtotals(mut self, subtotals: bool) -> Self {
+        self.subtotals = subtotals;
+        self
+    }
+
+    /// Name of the summary sheet (default "Summary")
+    pub fn with_summary_sheet_name(mut self, name: &str) -> Self {
+        self.summary_sheet = name.to_string();
+        self
+    }
+
+    /// Write the bold header row; its names label the summary columns
+    pub fn write_header<I, S>(&mut self, headers: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let headers: Vec<String> = headers
+            .into_iter()
+            .map(|h| h.as_ref().to_string())
+            .collect();
+        self.writer.write_header_bold(&headers)?;
+        self.header = Some(headers);
+        Ok(())
+    }
+
+    /// Write a row of text cells; numeric text counts toward sums
+    pub fn write_row<I, S>(&mut self, data: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let values: Vec<String> = data.into_iter().map(|v| v.as_ref().to_string()).collect();
+        self.writer.write_row(&values)?;
+        let cells: Vec<CellValue> = values.into_iter().map(CellValue::String).collect();
+        self.add_to_groups(&cells);
+        Ok(())
+    }
+
+    /// Write a row of typed cells
+    pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
+        self.writer.write_row_typed(cells)?;
+        self.add_to_groups(cells);
+        Ok(())
+    }
+
+    /// Write the subtotal row and the summary sheet, then save
+    pub fn finish(mut self) -> Result<WriteReport> {
+        if self.subtotals && self.data_rows > 0 {
+            self.write_subtotals()?;
+        }
+
+        self.writer.add_sheet(&self.summary_sheet)?;
+        let mut header: Vec<String> = self
+            .key_columns
+            .iter()
+            .map(|&col| self.column_name(col))
+            .collect();
+        header.extend(self.aggregates.iter().map(|&(col, aggregate)| {
+            format!("{} of {}", aggregate.label(), self.column_name(col))
+        }));
+        self.writer.write_header_bold(&header)?;
+
+        for group in self.groups.values() {
+            let mut row = group.keys.clone();
+            row.extend(
+                group
+                    .accumulators
+                    .iter()
+                    .zip(&self.aggregates)
+                    .map(|(acc, &(_, aggregate))| acc.value(aggregate)),
+            );
+            self.writer.write_row_typed(&row)?;
+        }
+
+        self.writer.save()
+    }
+
+    fn add_to_groups(&mut self, cells: &[CellValue]) {
+        self.data_rows += 1;
+        let cell = |col: usize| cells.get(col).cloned().unwrap_or(CellValue::Empty);
+        let key: Vec<String> = self
+            .key_columns
+            .iter()
+            .map(|&c| cell(c).as_string())
+            .collect();
+        let aggregates = self.aggregates.len();
+        let group = self.groups.entry(key).or_insert_with(|| Group {
+            keys: self.key_columns.iter().map(|&c| cell(c)).collect(),
+            accumulators: vec![Accumulator::default(); aggregates],
+        });
+        for (acc, &(col, _)) in group.accumulators.iter_mut().zip(&self.aggregates) {
+            acc.add(&cell(col));
+        }
+    }
+
+    // "Total" in the first column, SUBTOTAL over the data rows below the header
+    fn write_subtotals(&mut self) -> Result<()> {
+        let first_row = u32::from(self.header.is_some()); // 0-based
+        let last_row = first_row + self.data_rows - 1;
+        let width = self
+            .aggregates
+            .iter()
+            .map(|&(c, _)| c + 1)
+            .max()
+            .unwrap_or(1);
+
+        let mut row = vec![CellValue::Empty; width];
+        row[0] = CellValue::String("Total".to_string());
+        for (col, aggregate) in &self.aggregates {
+            if matches!(row[*col], CellValue::Formula(_)) {
+                continue;
+            }
+            let first = cellref::to_a1(first_row, *col as u32);
+            let last = cellref::to_a1(last_row, *col as u32);
+            row[*col] = CellValue::Formula(format!(
+                "SUBTOTAL({},{}:{})",
+                aggregate.subtotal_function(),
+                first,
+                last
+            ));
+        }
+        self.writer.write_row_typed(&row)
+    }
+
+    fn column_name(&self, col: usize) -> String {
+        self.header
+            .as_ref()
+            .and_then(|h| h.get(col))
+            .cloned()
+            .unwrap_or_else(|| cellref::col_to_name(col as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming_reader::StreamingReader;
+    use crate::{ExcelReader, ReaderOptions};
+
+    #[test]
+    fn test_accumulator() {
+        let mut acc = Accumulator::default();
+        for value in [
+            CellValue::Int(4),
+            CellValue::Empty,
+            CellValue::Float(-1.5),
+            CellValue::String("n/a".to_string()),
+        ] {
+            acc.add(&value);
+        }
+        assert_eq!(acc.value(Aggregate::Sum), CellValue::Float(2.5));
+        assert_eq!(acc.value(Aggregate::Count), CellValue::Int(3));
+        assert_eq!(acc.value(Aggregate::Min), CellValue::Float(-1.5));
+        assert_eq!(acc.value(Aggregate::Max), CellValue::Float(4.0));
+        assert_eq!(
+            Accumulator::default().value(Aggregate::Min),
+            CellValue::Empty
+        );
+    }
+
+    #[test]
+    fn test_summary_sheet_and_subtotals() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = SummaryWriter::new(ExcelWriter::new(temp.path()).unwrap())
+            .group_by([0])
+            .aggregate(2, Aggregate::Sum)
+            .aggregate(2, Aggregate::Max)
+            .aggregate(1, Aggregate::Count)
+            .with_subtotals(true);
+        writer
+            .write_header(["Region", "Product", "Amount"])
+            .unwrap();
+        for (region, product, amount) in [("North", "A", 10), ("South", "B", 5), ("North", "C", 7)]
+        {
+            writer
+                .write_row_typed(&[
+                    CellValue::String(region.to_string()),
+                    CellValue::String(product.to_string()),
+                    CellValue::Int(amount),
+                ])
+                .unwrap();
+        }
+        let report = writer.finish().unwrap();
+        assert_eq!(
+            report.per_sheet_rows,
+            vec![("Sheet1".to_string(), 5), ("Summary".to_string(), 3)]
+        );
+
+        let mut reader = ExcelReader::open(temp.path()).unwrap();
+        let summary: Vec<_> = reader
+            .rows("Summary")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                vec![
+                    "Region",
+                    "Sum of Amount",
+                    "Max of Amount",
+                    "Count of Product"
+                ],
+                vec!["North", "17", "10", "2"],
+                vec!["South", "5", "5", "1"],
+            ]
+        );
+
+        // SUBTOTAL cells have no cached value yet, so keep them
+        let options = ReaderOptions::new().with_keep_trailing_empty(true);
+        let mut reader = StreamingReader::open_with_options(temp.path(), options).unwrap();
+        let cells: Vec<_> = reader
+            .cells("Sheet1")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(cells[4][0].value, CellValue::String("Total".to_string()));
+        assert_eq!(cells[4][1].formula.as_deref(), Some("SUBTOTAL(3,B2:B4)"));
+        assert_eq!(cells[4][2].formula.as_deref(), Some("SUBTOTAL(9,C2:C4)"));
+    }
+}
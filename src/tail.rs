@@ -0,0 +1,392 @@
+//! Follow a growing workbook like `tail -f`
+//!
+//! [`watch`] yields the rows appended to a sheet after watching started, as
+//! [`AppendableExcelWriter`](crate::append::AppendableExcelWriter) saves
+//! them. An append keeps the compressed sheet up to the DEFLATE block
+//! holding `</sheetData>` byte-for-byte, so the watcher remembers where that
+//! block starts, and the 32 KiB of output before it that the block may refer
+//! back to, and decodes only from there on each change. A sheet rewritten
+//! some other way is read again in full, skipping rows already seen.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::tail;
+//! use std::time::Duration;
+//!
+//! let rows = tail::watch("log.xlsx", "Log")?.with_interval(Duration::from_secs(2));
+//! for row in rows {
+//!     println!("{:?}", row?.to_strings());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::append::inflate::{inflate_from, InflateSink, WINDOW};
+use crate::append::splice::{entry_data, MARKERS, MARKER_LEN};
+use crate::error::Result;
+use crate::streaming_reader::{ReaderOptions, RowIterator, SharedStrings, StreamingReader};
+use crate::types::Row;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Default time between checks for new rows
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Start following `sheet` of the workbook at `path`
+///
+/// Rows already in the sheet are skipped, which takes one read of the
+/// sheet; the watcher yields the rows appended afterwards.
+pub fn watch<P: AsRef<Path>>(path: P, sheet: &str) -> Result<TailWatcher> {
+    let mut watcher = TailWatcher {
+        path: path.as_ref().to_path_buf(),
+        sheet: sheet.to_string(),
+        sheet_path: String::new(),
+        options: ReaderOptions::default(),
+        sst: SharedStrings::default(),
+        head: Vec::new(),
+        cut: None,
+        next_index: 0,
+        version: None,
+        interval: DEFAULT_INTERVAL,
+        queue: VecDeque::new(),
+    };
+    let version = watcher.file_version()?;
+    watcher.read_full(true)?;
+    watcher.version = Some(version);
+    Ok(watcher)
+}
+
+/// Rows appended to a sheet, see [`watch`]
+///
+/// [`poll`](Self::poll) checks once. As an iterator it waits for new rows
+/// and never ends; errors are returned and the next call checks again.
+pub struct TailWatcher {
+    path: PathBuf,
+    sheet: String,
+    sheet_path: String,
+    options: ReaderOptions,
+    sst: SharedStrings,
+    head: Vec<u8>,    // Sheet XML before <sheetData>, for the root tag
+    cut: Option<Cut>, // Where decoding resumes
+    next_index: u32,  // 0-based index of the next new row
+    version: Option<(u64, SystemTime)>, // Size and mtime of the file last read
+    interval: Duration,
+    queue: VecDeque<Row>,
+}
+
+impl TailWatcher {
+    /// Wait `interval` between checks when iterating (default
+    /// [`DEFAULT_INTERVAL`])
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// 0-based index the next appended row will have
+    pub fn next_row_index(&self) -> u32 {
+        self.next_index
+    }
+
+    /// Rows appended since the last check, without waiting
+    pub fn poll(&mut self) -> Result<Vec<Row>> {
+        let version = self.file_version()?;
+        if self.version == Some(version) {
+            return Ok(Vec::new());
+        }
+        let rows = match self.read_appended()? {
+            Some(rows) => rows,
+            None => self.read_full(false)?,
+        };
+        self.version = Some(version);
+        Ok(rows)
+    }
+
+    fn file_version(&self) -> Result<(u64, SystemTime)> {
+        let metadata = std::fs::metadata(&self.path)?;
+        Ok((metadata.len(), metadata.modified()?))
+    }
+
+    // Decode the sheet from the remembered block on, or None when the
+    // sheet no longer continues from it
+    fn read_appended(&mut self) -> Result<Option<Vec<Row>>> {
+        let Some(cut) = &self.cut else {
+            return Ok(None);
+        };
+        let mut file = File::open(&self.path)?;
+        let entry = entry_data(&mut file, &self.sheet_path)?;
+        let byte = cut.bit_offset / 8;
+        if !entry.deflated || byte >= entry.compressed_size {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::Start(entry.offset + byte))?;
+        let mut scan = Scan::new(byte * 8, cut.offset, cut.window.clone(), true);
+        let input = file.take(entry.compressed_size - byte);
+        let decoded = inflate_from(input, (cut.bit_offset % 8) as u32, &cut.window, &mut scan);
+        let new_xml = match (decoded, scan.marker, &scan.collected) {
+            (Ok(_), Some(_), Some(xml)) if xml.starts_with(&cut.data) => &xml[cut.data.len()..],
+            _ => return Ok(None),
+        };
+        let mut xml = self.head.clone();
+        xml.extend_from_slice(new_xml);
+        let rows = self.parse_rows(xml)?;
+        self.cut = scan.cut;
+        Ok(Some(rows))
+    }
+
+    // Read the whole sheet, returning rows not seen yet (none when
+    // `skip`) and remembering where its data ends
+    fn read_full(&mut self, skip: bool) -> Result<Vec<Row>> {
+        // Find the cut before reading rows, so rows never come from an
+        // older file than the cut; rows read twice are dropped by index
+        self.sheet_path = StreamingReader::open(&self.path)?.sheet_path(&self.sheet)?;
+        let mut file = File::open(&self.path)?;
+        let entry = entry_data(&mut file, &self.sheet_path)?;
+        self.cut = None;
+        if entry.deflated {
+            file.seek(SeekFrom::Start(entry.offset))?;
+            let mut scan = Scan::new(0, 0, Vec::new(), false);
+            if inflate_from(file.take(entry.compressed_size), 0, &[], &mut scan).is_ok() {
+                self.head = scan.head;
+                self.cut = scan.cut;
+            }
+        }
+
+        let mut reader = StreamingReader::open(&self.path)?;
+        let mut rows = Vec::new();
+        let mut next_index = self.next_index;
+        for row in reader.rows(&self.sheet)? {
+            let row = row?;
+            if row.index >= self.next_index {
+                next_index = row.index + 1;
+                if !skip {
+                    rows.push(row);
+                }
+            }
+        }
+        self.next_index = next_index;
+        self.sst = reader.into_shared_strings();
+        Ok(rows)
+    }
+
+    // Parse rows from the head followed by new row XML
+    fn parse_rows(&mut self, xml: Vec<u8>) -> Result<Vec<Row>> {
+        let mut utf8_issues = Vec::new();
+        let mut iter = RowIterator::new(
+            Box::new(Cursor::new(xml)),
+            &self.sst,
+            &self.options,
+            &self.sheet_path,
+            &mut utf8_issues,
+            Vec::new(),
+        );
+        iter.set_next_index(self.next_index);
+        let mut rows = Vec::new();
+        while let Some(cells) = iter.next() {
+            let index = iter.row_index();
+            if index >= self.next_index {
+                rows.push(Row::new(index, cells?));
+            }
+        }
+        if let Some(last) = rows.last() {
+            self.next_index = last.index + 1;
+        }
+        Ok(rows)
+    }
+}
+
+impl Iterator for TailWatcher {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.queue.pop_front() {
+                return Some(Ok(row));
+            }
+            match self.poll() {
+                Ok(rows) if rows.is_empty() => std::thread::sleep(self.interval),
+                Ok(rows) => self.queue.extend(rows),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+// A DEFLATE block of the sheet and the output around it
+#[derive(Debug, Clone)]
+struct Cut {
+    bit_offset: u64, // From the start of the entry data
+    offset: u64,     // Decoded offset where the block starts
+    window: Vec<u8>, // Output before the block
+    data: Vec<u8>,   // Output of the block, up to the end of <sheetData> once found
+}
+
+// Follows decoded sheet XML to the end of <sheetData> and the block it is in
+struct Scan {
+    bit_base: u64,
+    offset: u64,                // Decoded offset of the next output
+    recent: Vec<u8>,            // Last WINDOW bytes of output
+    prev: Option<Cut>,          // Block before `cur`, for markers across blocks
+    cur: Option<Cut>,           // Block being decoded
+    carry: Vec<u8>,             // Output end a marker may continue from
+    start: u64,                 // Decoded offset the scan started at
+    collected: Option<Vec<u8>>, // Output up to the marker, when collecting
+    head: Vec<u8>,              // Output before <sheetData>
+    head_done: bool,
+    marker: Option<u64>, // Decoded offset of the end of <sheetData>
+    cut: Option<Cut>,    // Block the marker starts in
+}
+
+impl Scan {
+    fn new(bit_base: u64, offset: u64, window: Vec<u8>, collect: bool) -> Self {
+        Scan {
+            bit_base,
+            offset,
+            recent: window,
+            prev: None,
+            cur: None,
+            carry: Vec::new(),
+            start: offset,
+            collected: collect.then(Vec::new),
+            head: Vec::new(),
+            head_done: offset > 0,
+            marker: None,
+            cut: None,
+        }
+    }
+
+    fn found_marker(&mut self, marker: u64) {
+        self.marker = Some(marker);
+        if let Some(collected) = &mut self.collected {
+            collected.truncate((marker - self.start) as usize);
+        }
+        let cur = self.cur.take();
+        self.cut = match (self.prev.take(), cur) {
+            (_, Some(cur)) if cur.offset <= marker => Some(cur),
+            (Some(mut prev), Some(cur)) if prev.offset <= marker => {
+                prev.data.extend_from_slice(&cur.data);
+                Some(prev)
+            }
+            _ => None,
+        };
+        if let Some(cut) = &mut self.cut {
+            cut.data.truncate((marker - cut.offset) as usize);
+        }
+    }
+}
+
+impl InflateSink for Scan {
+    fn block_start(&mut self, bit_offset: u64) -> Result<()> {
+        if self.marker.is_none() {
+            self.prev = self.cur.take();
+            self.cur = Some(Cut {
+                bit_offset: self.bit_base + bit_offset,
+                offset: self.offset,
+                window: self.recent.clone(),
+                data: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    fn data(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.marker.is_some() {
+            return Ok(());
+        }
+        if !self.head_done {
+            self.head.extend_from_slice(bytes);
+            if let Some(pos) = memchr::memmem::find(&self.head, b"sheetData") {
+                let tag = memchr::memrchr(b'<', &self.head[..pos]).unwrap_or(pos);
+                self.head.truncate(tag);
+                self.head_done = true;
+            }
+        }
+        if let Some(cur) = &mut self.cur {
+            cur.data.extend_from_slice(bytes);
+        }
+        if let Some(collected) = &mut self.collected {
+            collected.extend_from_slice(bytes);
+        }
+        self.recent.extend_from_slice(bytes);
+        if self.recent.len() > 2 * WINDOW {
+            self.recent.drain(..self.recent.len() - WINDOW);
+        }
+
+        let carried = self.carry.len() as u64;
+        self.carry.extend_from_slice(bytes);
+        let found = MARKERS
+            .iter()
+            .filter_map(|m| memchr::memmem::find(&self.carry, m))
+            .min();
+        let carry_start = self.offset - carried;
+        self.offset += bytes.len() as u64;
+        match found {
+            Some(pos) => self.found_marker(carry_start + pos as u64),
+            None => {
+                let keep = self.carry.len().saturating_sub(MARKER_LEN - 1);
+                self.carry.drain(..keep);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::append::AppendableExcelWriter;
+    use crate::types::CellValue;
+    use crate::ExcelWriter;
+
+    fn append(path: &Path, rows: std::ops::Range<u32>) {
+        let mut writer = AppendableExcelWriter::open(path).unwrap();
+        writer.select_sheet("Sheet1").unwrap();
+        for i in rows {
+            writer.append_row([format!("entry {}", i)]).unwrap();
+        }
+        writer.save().unwrap();
+    }
+
+    #[test]
+    fn test_watch_appended_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.xlsx");
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        for i in 0..2000 {
+            writer.write_row([format!("entry {}", i)]).unwrap();
+        }
+        writer.save().unwrap();
+
+        let mut watcher = watch(&path, "Sheet1").unwrap();
+        assert_eq!(watcher.next_row_index(), 2000);
+        assert!(watcher.poll().unwrap().is_empty());
+
+        // Appends are read from the last block on
+        append(&path, 2000..2003);
+        let rows = watcher.read_appended().unwrap().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].index, 2000);
+        assert_eq!(rows[2].cells, [CellValue::from("entry 2002")]);
+
+        append(&path, 2003..2005);
+        let rows = watcher.poll().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].index, 2004);
+        append(&path, 2005..2006);
+        let row = watcher.next().unwrap().unwrap();
+        assert_eq!(row.to_strings(), ["entry 2005"]);
+
+        // A rewritten workbook is read again, skipping rows seen
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        for i in 0..2008 {
+            writer.write_row([format!("entry {}", i)]).unwrap();
+        }
+        writer.save().unwrap();
+        let rows = watcher.poll().unwrap();
+        let indices: Vec<u32> = rows.iter().map(|r| r.index).collect();
+        assert_eq!(indices, [2006, 2007]);
+        assert!(watch(&path, "Missing").is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Rate limits for writers
+//!
+//! A [`Throttle`] caps how fast a writer produces rows, so a service
+//! exporting for many tenants can keep one large export from starving the
+//! others. The writer paces itself: once it is ahead of the rate, the row
+//! call that got it there waits (the calling thread sleeps for the local
+//! writers, the task awaits for the cloud writers) until it is back on
+//! schedule.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::throttle::Throttle;
+//! use excelstream::ExcelWriterBuilder;
+//!
+//! // At most 5 MB of sheet XML per second
+//! let mut writer = ExcelWriterBuilder::new("tenant-42.xlsx")
+//!     .with_throttle(Throttle::BytesPerSec(5_000_000))
+//!     .build()?;
+//! for i in 0..1_000_000 {
+//!     writer.write_row([i.to_string(), format!("row {}", i)])?;
+//! }
+//! writer.save()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::time::{Duration, Instant};
+
+/// Maximum rate a writer produces rows at
+///
+/// Bytes are counted as the uncompressed worksheet XML of each row, which
+/// tracks the CPU and upload work a row costs more closely than the row
+/// count when rows vary in width. A rate of 0 turns throttling off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    /// Rows per second
+    RowsPerSec(u64),
+    /// Bytes of worksheet XML per second
+    BytesPerSec(u64),
+}
+
+/// Tracks rows written against a [`Throttle`] and says how long to wait
+#[derive(Debug)]
+pub(crate) struct Throttler {
+    throttle: Throttle,
+    started: Option<Instant>, // Set by the first row
+    used: u64,                // Rows or bytes since `started`
+}
+
+impl Throttler {
+    pub(crate) fn new(throttle: Throttle) -> Self {
+        Throttler {
+            throttle,
+            started: None,
+            used: 0,
+        }
+    }
+
+    /// Record a row of `bytes` bytes and return how long to wait before the
+    /// next one, if the writer is ahead of the rate
+    pub(crate) fn record(&mut self, bytes: usize) -> Option<Duration> {
+        let (rate, units) = match self.throttle {
+            Throttle::RowsPerSec(rate) => (rate, 1),
+            Throttle::BytesPerSec(rate) => (rate, bytes as u64),
+        };
+        if rate == 0 {
+            return None;
+        }
+        let started = *self.started.get_or_insert_with(Instant::now);
+        self.used += units;
+        let due = Duration::from_secs_f64(self.used as f64 / rate as f64);
+        due.checked_sub(started.elapsed())
+            .filter(|wait| !wait.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttler_delay() {
+        let mut throttler = Throttler::new(Throttle::RowsPerSec(10));
+        let wait = throttler.record(1000).unwrap();
+        assert!(wait > Duration::from_millis(50) && wait <= Duration::from_millis(100));
+        for _ in 0..9 {
+            throttler.record(1000);
+        }
+        assert!(throttler.record(0).unwrap() > Duration::from_millis(1000));
+
+        let mut throttler = Throttler::new(Throttle::BytesPerSec(1000));
+        let wait = throttler.record(500).unwrap();
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+
+        let mut throttler = Throttler::new(Throttle::RowsPerSec(0));
+        assert_eq!(throttler.record(1000), None);
+    }
+}
@@ -0,0 +1,471 @@
+//! Write column-major data as a normal row-major sheet
+//!
+//! [`TransposedWriter`] wraps an [`ExcelWriter`] and takes data one column
+//! at a time, e.g. one simulation variable or sensor channel after another.
+//! Each column streams into a temporary spool file as it arrives;
+//! [`finish`](TransposedWriter::finish) then reads all columns back side by
+//! side to write the rows. Memory stays bounded by the read buffers (about
+//! [`READ_BUFFER_BYTES`] in total) instead of the whole matrix.
+//!
+//! Columns may have different lengths: missing cells at the bottom of the
+//! shorter ones are left empty.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::transpose::TransposedWriter;
+//! use excelstream::types::CellValue;
+//! use excelstream::ExcelWriter;
+//!
+//! let mut writer = TransposedWriter::new(ExcelWriter::new("run42.xlsx")?);
+//! for channel in 0..64 {
+//!     let name = CellValue::from(format!("ch{}", channel));
+//!     let samples = (0..100_000).map(|i| CellValue::from((i * channel) as f64 * 0.001));
+//!     writer.write_column(std::iter::once(name).chain(samples))?;
+//! }
+//! writer.finish()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::cellref::{MAX_COLS, MAX_ROWS};
+use crate::error::{ExcelError, Result};
+use crate::types::{CellValue, Decimal, RichText, TextRun, WriteReport};
+use crate::writer::ExcelWriter;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Read buffer shared by all columns while [`TransposedWriter::finish`]
+/// writes the rows
+pub const READ_BUFFER_BYTES: usize = 8 * 1024 * 1024;
+
+// Per-column read buffer bounds
+const MIN_COLUMN_BUFFER: usize = 512;
+const MAX_COLUMN_BUFFER: usize = 64 * 1024;
+
+// Spool value tags
+const TAG_EMPTY: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_INT: u8 = 2;
+const TAG_FLOAT: u8 = 3;
+const TAG_BOOL: u8 = 4;
+const TAG_DATETIME: u8 = 5;
+const TAG_ERROR: u8 = 6;
+const TAG_FORMULA: u8 = 7;
+const TAG_DECIMAL: u8 = 8;
+const TAG_DATETIME_TZ: u8 = 9;
+const TAG_DURATION: u8 = 10;
+const TAG_PERCENT: u8 = 11;
+const TAG_RICH_TEXT: u8 = 12;
+
+// Run flags of a spooled rich text
+const RUN_BOLD: u8 = 1;
+const RUN_ITALIC: u8 = 2;
+const RUN_UNDERLINE: u8 = 4;
+const RUN_STRIKE: u8 = 8;
+
+/// A spooled column: where its values start and how many there are
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SpooledColumn {
+    pub(crate) offset: u64,
+    pub(crate) len: u64,
+    pub(crate) rows: u64,
+}
+
+/// Writer taking data column by column, see [module docs](self)
+pub struct TransposedWriter {
+    writer: ExcelWriter,
+    temp_dir: Option<PathBuf>,
+    spool: Option<BufWriter<File>>,
+    spooled: u64, // Bytes written to the spool
+    columns: Vec<SpooledColumn>,
+}
+
+impl TransposedWriter {
+    /// Wrap a writer; rows go to its current sheet on
+    /// [`finish`](Self::finish)
+    pub fn new(writer: ExcelWriter) -> Self {
+        TransposedWriter {
+            writer,
+            temp_dir: None,
+            spool: None,
+            spooled: 0,
+            columns: Vec::new(),
+        }
+    }
+
+    /// Directory for the spool file (default: the system temp directory)
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Number of columns written so far
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Append the next column, top to bottom
+    ///
+    /// Fails with [`ExcelError::LimitExceeded`] past 16,384 columns or
+    /// 1,048,576 values; the column is then dropped and the writer stays
+    /// usable.
+    pub fn write_column<I, V>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<CellValue>,
+    {
+        if self.columns.len() == MAX_COLS as usize {
+            return Err(ExcelError::LimitExceeded {
+                what: "Column count".to_string(),
+                limit: MAX_COLS as u64,
+            });
+        }
+        let spool = match &mut self.spool {
+            Some(spool) => spool,
+            None => {
+                let file = match &self.temp_dir {
+                    Some(dir) => tempfile::tempfile_in(dir)?,
+                    None => tempfile::tempfile()?,
+                };
+                self.spool.insert(BufWriter::with_capacity(64 * 1024, file))
+            }
+        };
+
+        let offset = self.spooled;
+        let mut rows = 0u32;
+        for value in values {
+            if rows == MAX_ROWS {
+                return Err(ExcelError::LimitExceeded {
+                    what: format!("Column {} length", self.columns.len() + 1),
+                    limit: MAX_ROWS as u64,
+                });
+            }
+            self.spooled += encode(spool, &value.into())?;
+            rows += 1;
+        }
+        self.columns.push(SpooledColumn {
+            offset,
+            len: self.spooled - offset,
+            rows: rows as u64,
+        });
+        Ok(())
+    }
+
+    /// Write the rows and save the workbook
+    pub fn finish(mut self) -> Result<WriteReport> {
+        if let Some(spool) = self.spool.take() {
+            let mut file = spool.into_inner().map_err(|e| e.into_error())?;
+            let chunk = (READ_BUFFER_BYTES / self.columns.len().max(1))
+                .clamp(MIN_COLUMN_BUFFER, MAX_COLUMN_BUFFER);
+            let mut cursors: Vec<ColumnCursor> = self
+                .columns
+                .iter()
+                .map(|column| ColumnCursor::new(column, chunk))
+                .collect();
+            let rows = self.columns.iter().map(|c| c.rows).max().unwrap_or(0);
+
+            let mut row = Vec::with_capacity(cursors.len());
+            for _ in 0..rows {
+                row.clear();
+                for cursor in &mut cursors {
+                    row.push(cursor.next_value(&mut file)?);
+                }
+                self.writer.write_row_typed(&row)?;
+            }
+        }
+        self.writer.save()
+    }
+}
+
+// Append a value to the spool, returning the bytes written
+pub(crate) fn encode<W: Write>(out: &mut W, value: &CellValue) -> Result<u64> {
+    let text = |out: &mut W, tag: u8, s: &str| -> Result<u64> {
+        out.write_all(&[tag])?;
+        out.write_all(&(s.len() as u32).to_le_bytes())?;
+        out.write_all(s.as_bytes())?;
+        Ok(5 + s.len() as u64)
+    };
+    let fixed = |out: &mut W, tag: u8, bytes: &[u8]| -> Result<u64> {
+        out.write_all(&[tag])?;
+        out.write_all(bytes)?;
+        Ok(1 + bytes.len() as u64)
+    };
+    match value {
+        CellValue::Empty => fixed(out, TAG_EMPTY, &[]),
+        CellValue::String(s) => text(out, TAG_STRING, s),
+        CellValue::Int(i) => fixed(out, TAG_INT, &i.to_le_bytes()),
+        CellValue::Float(f) => fixed(out, TAG_FLOAT, &f.to_le_bytes()),
+        CellValue::Bool(b) => fixed(out, TAG_BOOL, &[*b as u8]),
+        CellValue::DateTime(d) => fixed(out, TAG_DATETIME, &d.to_le_bytes()),
+        CellValue::Percent(p) => fixed(out, TAG_PERCENT, &p.to_le_bytes()),
+        CellValue::Error(e) => text(out, TAG_ERROR, e),
+        CellValue::Formula(f) => text(out, TAG_FORMULA, f),
+        CellValue::Decimal(d) => {
+            let mut bytes = [0u8; 20];
+            bytes[..16].copy_from_slice(&d.mantissa().to_le_bytes());
+            bytes[16..].copy_from_slice(&d.scale().to_le_bytes());
+            fixed(out, TAG_DECIMAL, &bytes)
+        }
+        CellValue::DateTimeTz(dt) => {
+            let mut bytes = [0u8; 16];
+            bytes[..8].copy_from_slice(&dt.timestamp().to_le_bytes());
+            bytes[8..12].copy_from_slice(&dt.timestamp_subsec_nanos().to_le_bytes());
+            bytes[12..].copy_from_slice(&dt.offset().local_minus_utc().to_le_bytes());
+            fixed(out, TAG_DATETIME_TZ, &bytes)
+        }
+        CellValue::Duration(d) => {
+            let mut bytes = [0u8; 12];
+            bytes[..8].copy_from_slice(&d.as_secs().to_le_bytes());
+            bytes[8..].copy_from_slice(&d.subsec_nanos().to_le_bytes());
+            fixed(out, TAG_DURATION, &bytes)
+        }
+        // Run count, then each run's flags, color ("" for none) and text
+        CellValue::RichText(rich) => {
+            let mut bytes = fixed(
+                out,
+                TAG_RICH_TEXT,
+                &(rich.runs().len() as u32).to_le_bytes(),
+            )?;
+            for run in rich.runs() {
+                let flags = [
+                    (run.bold, RUN_BOLD),
+                    (run.italic, RUN_ITALIC),
+                    (run.underline, RUN_UNDERLINE),
+                    (run.strike, RUN_STRIKE),
+                ]
+                .iter()
+                .filter(|(set, _)| *set)
+                .fold(0u8, |flags, (_, flag)| flags | flag);
+                out.write_all(&[flags])?;
+                bytes += 1;
+                for s in [run.color.as_deref().unwrap_or(""), run.text.as_str()] {
+                    out.write_all(&(s.len() as u32).to_le_bytes())?;
+                    out.write_all(s.as_bytes())?;
+                    bytes += 4 + s.len() as u64;
+                }
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// Reads one spooled column back in chunks
+pub(crate) struct ColumnCursor {
+    pos: u64, // Next spool byte to load
+    end: u64,
+    rows_left: u64,
+    chunk: usize,
+    buf: Vec<u8>,
+    start: usize, // Next unread byte in `buf`
+}
+
+impl ColumnCursor {
+    pub(crate) fn new(column: &SpooledColumn, chunk: usize) -> Self {
+        ColumnCursor {
+            pos: column.offset,
+            end: column.offset + column.len,
+            rows_left: column.rows,
+            chunk,
+            buf: Vec::new(),
+            start: 0,
+        }
+    }
+
+    pub(crate) fn next_value<R: Read + Seek>(&mut self, file: &mut R) -> Result<CellValue> {
+        if self.rows_left == 0 {
+            return Ok(CellValue::Empty);
+        }
+        self.rows_left -= 1;
+        let tag = self.take(file, 1)?[0];
+        let value = match tag {
+            TAG_EMPTY => CellValue::Empty,
+            TAG_STRING => CellValue::String(self.take_text(file)?),
+            TAG_INT => CellValue::Int(i64::from_le_bytes(self.take_array(file)?)),
+            TAG_FLOAT => CellValue::Float(f64::from_le_bytes(self.take_array(file)?)),
+            TAG_BOOL => CellValue::Bool(self.take(file, 1)?[0] != 0),
+            TAG_DATETIME => CellValue::DateTime(f64::from_le_bytes(self.take_array(file)?)),
+            TAG_PERCENT => CellValue::Percent(f64::from_le_bytes(self.take_array(file)?)),
+            TAG_ERROR => CellValue::Error(self.take_text(file)?),
+            TAG_FORMULA => CellValue::Formula(self.take_text(file)?),
+            TAG_DECIMAL => {
+                let mantissa = i128::from_le_bytes(self.take_array(file)?);
+                let scale = u32::from_le_bytes(self.take_array(file)?);
+                CellValue::Decimal(Decimal::new(mantissa, scale))
+            }
+            TAG_DATETIME_TZ => {
+                let secs = i64::from_le_bytes(self.take_array(file)?);
+                let nanos = u32::from_le_bytes(self.take_array(file)?);
+                let offset = i32::from_le_bytes(self.take_array(file)?);
+                let offset = chrono::FixedOffset::east_opt(offset).ok_or_else(corrupt_spool)?;
+                let dt = chrono::DateTime::from_timestamp(secs, nanos).ok_or_else(corrupt_spool)?;
+                CellValue::DateTimeTz(dt.with_timezone(&offset))
+            }
+            TAG_DURATION => {
+                let secs = u64::from_le_bytes(self.take_array(file)?);
+                let nanos = u32::from_le_bytes(self.take_array(file)?);
+                if nanos >= 1_000_000_000 {
+                    return Err(corrupt_spool());
+                }
+                CellValue::Duration(std::time::Duration::new(secs, nanos))
+            }
+            TAG_RICH_TEXT => {
+                let count = u32::from_le_bytes(self.take_array(file)?);
+                let mut rich = RichText::new();
+                for _ in 0..count {
+                    let flags = self.take(file, 1)?[0];
+                    let color = self.take_text(file)?;
+                    rich = rich.run(TextRun {
+                        text: self.take_text(file)?,
+                        bold: flags & RUN_BOLD != 0,
+                        italic: flags & RUN_ITALIC != 0,
+                        underline: flags & RUN_UNDERLINE != 0,
+                        strike: flags & RUN_STRIKE != 0,
+                        color: (!color.is_empty()).then_some(color),
+                    });
+                }
+                CellValue::RichText(rich)
+            }
+            _ => return Err(corrupt_spool()),
+        };
+        Ok(value)
+    }
+
+    fn take_array<const N: usize, R: Read + Seek>(&mut self, file: &mut R) -> Result<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(file, N)?);
+        Ok(array)
+    }
+
+    fn take_text<R: Read + Seek>(&mut self, file: &mut R) -> Result<String> {
+        let len = u32::from_le_bytes(self.take_array(file)?) as usize;
+        String::from_utf8(self.take(file, len)?.to_vec()).map_err(|_| corrupt_spool())
+    }
+
+    // Next `n` bytes of the column, loading another chunk when needed
+    fn take<R: Read + Seek>(&mut self, file: &mut R, n: usize) -> Result<&[u8]> {
+        let available = self.buf.len() - self.start;
+        if available < n {
+            self.buf.drain(..self.start);
+            self.start = 0;
+            let missing = (n - available) as u64;
+            let load = missing.max(self.chunk as u64).min(self.end - self.pos);
+            if load < missing {
+                return Err(corrupt_spool());
+            }
+            let filled = self.buf.len();
+            self.buf.resize(filled + load as usize, 0);
+            file.seek(SeekFrom::Start(self.pos))?;
+            file.read_exact(&mut self.buf[filled..])?;
+            self.pos += load;
+        }
+        let bytes = &self.buf[self.start..self.start + n];
+        self.start += n;
+        Ok(bytes)
+    }
+}
+
+fn corrupt_spool() -> ExcelError {
+    ExcelError::InvalidState("Transpose spool file is corrupt".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming_reader::StreamingReader;
+
+    #[test]
+    fn test_transposed_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transposed.xlsx");
+        let mut writer =
+            TransposedWriter::new(ExcelWriter::new(&path).unwrap()).with_temp_dir(dir.path());
+        writer
+            .write_column(["id".into(), CellValue::Int(1), CellValue::Int(2)])
+            .unwrap();
+        writer
+            .write_column([
+                CellValue::from("mixed"),
+                CellValue::Decimal(Decimal::new(1999, 2)),
+                CellValue::Bool(true),
+                CellValue::Error("#N/A".to_string()),
+            ])
+            .unwrap();
+        // Long text spans several read chunks while staying within Excel's
+        // cell limit
+        let long = "\u{20ac}".repeat(crate::types::MAX_TEXT_LEN);
+        assert!(long.len() > MAX_COLUMN_BUFFER);
+        writer
+            .write_column(vec!["text".to_string(), long.clone()])
+            .unwrap();
+        writer.write_column(Vec::<CellValue>::new()).unwrap();
+        assert_eq!(writer.column_count(), 4);
+        writer.finish().unwrap();
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        let rows: Vec<Vec<CellValue>> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            vec![
+                CellValue::from("id"),
+                CellValue::from("mixed"),
+                CellValue::from("text")
+            ]
+        );
+        assert_eq!(rows[1][0], CellValue::Int(1));
+        assert_eq!(rows[1][1].as_f64(), Some(19.99));
+        assert_eq!(rows[1][2], CellValue::String(long));
+        assert_eq!(rows[2][1], CellValue::Bool(true));
+        assert_eq!(rows[2].len(), 2);
+        assert_eq!(
+            rows[3],
+            vec![CellValue::Empty, CellValue::Error("#N/A".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_spool_round_trip() {
+        let values = [
+            CellValue::Empty,
+            CellValue::from("héllo"),
+            CellValue::Int(-7),
+            CellValue::Float(2.5),
+            CellValue::Bool(false),
+            CellValue::DateTime(45322.5),
+            CellValue::Error("#REF!".to_string()),
+            CellValue::Formula("=A1+1".to_string()),
+            CellValue::Decimal(Decimal::new(-12345, 3)),
+            CellValue::DateTimeTz(
+                chrono::DateTime::parse_from_rfc3339("2024-01-31T09:00:00.25-05:30").unwrap(),
+            ),
+            CellValue::Duration(std::time::Duration::new(129_600, 5)),
+            CellValue::Percent(12.5),
+            CellValue::RichText(
+                RichText::new()
+                    .bold("Error: ")
+                    .plain("")
+                    .colored("disk full", "ff0000"),
+            ),
+        ];
+        let mut file = tempfile::tempfile().unwrap();
+        let mut len = 0;
+        for value in &values {
+            len += encode(&mut file, value).unwrap();
+        }
+        let column = SpooledColumn {
+            offset: 0,
+            len,
+            rows: values.len() as u64,
+        };
+        let mut cursor = ColumnCursor::new(&column, 4);
+        for value in &values {
+            assert_eq!(&cursor.next_value(&mut file).unwrap(), value);
+        }
+        assert_eq!(cursor.next_value(&mut file).unwrap(), CellValue::Empty);
+    }
+}
@@ -1,261 +1,2384 @@
 //! Type definitions for Excel data
 
+use crate::error::ExcelError;
+use crate::numfmt::Currency;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Cell style presets for formatting
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CellStyle {
     /// Default style - no formatting
-    Default = 0,
+    Default,
     /// Bold text for headers
-    HeaderBold = 1,
+    HeaderBold,
     /// Integer format with thousand separator (#,##0)
-    NumberInteger = 2,
+    NumberInteger,
     /// Decimal format with 2 places (#,##0.00)
-    NumberDecimal = 3,
+    NumberDecimal,
     /// Currency format ($#,##0.00)
-    NumberCurrency = 4,
+    NumberCurrency,
     /// Percentage format (0.00%)
-    NumberPercentage = 5,
+    NumberPercentage,
     /// Date format (MM/DD/YYYY)
-    DateDefault = 6,
+    DateDefault,
     /// DateTime format (MM/DD/YYYY HH:MM:SS)
-    DateTimestamp = 7,
+    DateTimestamp,
     /// Bold text for emphasis
-    TextBold = 8,
+    TextBold,
     /// Italic text for notes
-    TextItalic = 9,
+    TextItalic,
     /// Yellow background highlight
-    HighlightYellow = 10,
+    HighlightYellow,
     /// Green background highlight
-    HighlightGreen = 11,
+    HighlightGreen,
     /// Red background highlight
-    HighlightRed = 12,
+    HighlightRed,
     /// Thin borders on all sides
-    BorderThin = 13,
+    BorderThin,
+    /// Text format (`@`, numFmtId 49) with the value stored as a string
+    ///
+    /// Use it for codes like "0012345" or phone numbers: Excel shows the text
+    /// as written and doesn't turn it into a number when the cell is edited.
+    /// Numbers written with this style are stored as their text.
+    TextFormat,
+    /// Unlocked cell: stays editable when its sheet is protected
+    ///
+    /// For input cells of templates. To unlock a cell with another style,
+    /// use [`ExcelWriter::unlocked_style`](crate::writer::ExcelWriter::unlocked_style).
+    Unlocked,
+    /// Elapsed time format (`[h]:mm:ss`): hours keep counting past 24
+    ///
+    /// Used for [`CellValue::Duration`] cells written with the default style.
+    /// Registered in the workbook's style table on first use.
+    Duration,
+    /// Currency format for a specific currency (e.g. VND, EUR)
+    ///
+    /// Registered in the workbook's style table on first use.
+    Currency(Currency),
+    /// Custom style returned by
+    /// [`ExcelWriter::register_number_format`](crate::writer::ExcelWriter::register_number_format)
+    /// or [`ExcelWriter::unlocked_style`](crate::writer::ExcelWriter::unlocked_style)
+    NumberFormat(u32),
 }
 
 impl CellStyle {
+    /// Number of preset styles built into every workbook
+    pub const BUILTIN_COUNT: u32 = 16;
+
     /// Get the style index for XML
+    ///
+    /// `Currency` and `Duration` styles are assigned an index by the writer's
+    /// style registry; writers without one fall back to the generic
+    /// `NumberCurrency` style and to no format.
     pub fn index(&self) -> u32 {
-        *self as u32
+        match self {
+            CellStyle::Default => 0,
+            CellStyle::HeaderBold => 1,
+            CellStyle::NumberInteger => 2,
+            CellStyle::NumberDecimal => 3,
+            CellStyle::NumberCurrency => 4,
+            CellStyle::NumberPercentage => 5,
+            CellStyle::DateDefault => 6,
+            CellStyle::DateTimestamp => 7,
+            CellStyle::TextBold => 8,
+            CellStyle::TextItalic => 9,
+            CellStyle::HighlightYellow => 10,
+            CellStyle::HighlightGreen => 11,
+            CellStyle::HighlightRed => 12,
+            CellStyle::BorderThin => 13,
+            CellStyle::TextFormat => 14,
+            CellStyle::Unlocked => 15,
+            CellStyle::Currency(_) => 4,
+            CellStyle::Duration => 0,
+            CellStyle::NumberFormat(index) => *index,
+        }
+    }
+}
+
+/// Styled cell value (combines value with formatting)
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyledCell {
+    /// The cell value
+    pub value: CellValue,
+    /// The cell style
+    pub style: CellStyle,
+}
+
+impl StyledCell {
+    /// Create a new styled cell
+    pub fn new(value: CellValue, style: CellStyle) -> Self {
+        StyledCell { value, style }
+    }
+
+    /// Create a cell with default style
+    pub fn default_style(value: CellValue) -> Self {
+        StyledCell {
+            value,
+            style: CellStyle::Default,
+        }
+    }
+
+    /// The value as it is stored: numbers in [`CellStyle::TextFormat`] cells
+    /// become their text
+    pub(crate) fn stored<'v>(
+        value: &'v CellValue,
+        style: &CellStyle,
+    ) -> std::borrow::Cow<'v, CellValue> {
+        match (style, value) {
+            (
+                CellStyle::TextFormat,
+                CellValue::Int(_)
+                | CellValue::Float(_)
+                | CellValue::Decimal(_)
+                | CellValue::Duration(_)
+                | CellValue::Percent(_),
+            ) => std::borrow::Cow::Owned(CellValue::String(value.as_string())),
+            _ => std::borrow::Cow::Borrowed(value),
+        }
+    }
+}
+
+impl From<CellValue> for StyledCell {
+    fn from(value: CellValue) -> Self {
+        StyledCell::default_style(value)
+    }
+}
+
+/// Represents a single cell value in an Excel worksheet
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    /// Empty cell
+    Empty,
+    /// String value
+    String(String),
+    /// Integer value
+    Int(i64),
+    /// Float value
+    Float(f64),
+    /// Boolean value
+    Bool(bool),
+    /// DateTime value (Excel serial date number)
+    DateTime(f64),
+    /// Date and time with a UTC offset; spreadsheets have no time zones, so
+    /// writers store it per their [`TimeZonePolicy`]
+    DateTimeTz(chrono::DateTime<chrono::FixedOffset>),
+    /// Elapsed time, stored as a fraction of days with the
+    /// [`CellStyle::Duration`] format unless styled otherwise
+    Duration(Duration),
+    /// Percentage in points: `Percent(15.0)` is 15%, stored as 0.15 with
+    /// the [`CellStyle::NumberPercentage`] format unless styled otherwise
+    ///
+    /// A `Float(15.0)` styled as a percentage shows as 1500%.
+    Percent(f64),
+    /// Error value (e.g. "#N/A"), see [`CellValue::error_code`]
+    Error(String),
+    /// Formula value (e.g., "=SUM(A1:A10)")
+    /// The formula should start with '=' and use Excel formula syntax
+    Formula(String),
+    /// Exact decimal value, written as its decimal text instead of an f64
+    Decimal(Decimal),
+    /// Text mixing formats within the cell, see [`RichText`]
+    RichText(RichText),
+}
+
+/// Error values a cell can hold
+pub const ERROR_CODES: [&str; 7] = [
+    "#NULL!", "#DIV/0!", "#VALUE!", "#REF!", "#NAME?", "#NUM!", "#N/A",
+];
+
+impl CellValue {
+    /// The cell error literal `text` stands for, if any
+    ///
+    /// Matches [`ERROR_CODES`] ignoring case, with or without the leading
+    /// `#` ("n/a" gives "#N/A"). Writers emit [`CellValue::Error`] values that
+    /// match as error cells and anything else as plain text, since an
+    /// unknown error literal makes the file unreadable.
+    pub fn error_code(text: &str) -> Option<&'static str> {
+        let text = text.trim();
+        let text = text.strip_prefix('#').unwrap_or(text);
+        ERROR_CODES
+            .iter()
+            .find(|code| code[1..].eq_ignore_ascii_case(text))
+            .copied()
+    }
+
+    /// Convert cell value to string
+    pub fn as_string(&self) -> String {
+        match self {
+            CellValue::Empty => String::new(),
+            CellValue::String(s) => s.clone(),
+            CellValue::Int(i) => i.to_string(),
+            CellValue::Float(f) => f.to_string(),
+            CellValue::Bool(b) => b.to_string(),
+            CellValue::DateTime(d) => d.to_string(),
+            CellValue::DateTimeTz(dt) => dt.to_rfc3339(),
+            CellValue::Duration(d) => {
+                let secs = d.as_secs();
+                format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            }
+            CellValue::Percent(p) => format!("{}%", p),
+            CellValue::Error(e) => format!("ERROR: {}", e),
+            CellValue::Formula(f) => f.clone(),
+            CellValue::Decimal(d) => d.to_string(),
+            CellValue::RichText(text) => text.text(),
+        }
+    }
+
+    /// Check if cell is empty
+    pub fn is_empty(&self) -> bool {
+        matches!(self, CellValue::Empty)
+    }
+
+    /// Try to convert to integer
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            CellValue::Int(i) => Some(*i),
+            CellValue::Float(f) => Some(*f as i64),
+            CellValue::Decimal(d) => d.trunc_i64(),
+            CellValue::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Try to convert to float
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            CellValue::Float(f) => Some(*f),
+            CellValue::Int(i) => Some(*i as f64),
+            CellValue::DateTime(d) => Some(*d),
+            CellValue::DateTimeTz(dt) => CellValue::from(dt.naive_local()).as_f64(),
+            CellValue::Duration(d) => Some(duration_serial(d)),
+            CellValue::Percent(p) => Some(p / 100.0),
+            CellValue::Decimal(d) => Some(d.to_f64()),
+            CellValue::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Try to convert to boolean
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            CellValue::Bool(b) => Some(*b),
+            CellValue::Int(i) => Some(*i != 0),
+            CellValue::String(s) => match s.to_lowercase().as_str() {
+                "true" | "yes" | "1" => Some(true),
+                "false" | "no" | "0" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Borrow the text of a string cell
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CellValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Try to convert to a date and time
+    ///
+    /// Numbers are read as Excel serial dates (sheets often store dates as
+    /// plain numbers) and text as ISO 8601, with or without a time. A
+    /// [`CellValue::DateTimeTz`] gives its local time.
+    pub fn as_date(&self) -> Option<chrono::NaiveDateTime> {
+        match self {
+            CellValue::DateTime(d) | CellValue::Float(d) => serial_to_datetime(*d),
+            CellValue::DateTimeTz(dt) => Some(dt.naive_local()),
+            CellValue::Int(i) => serial_to_datetime(*i as f64),
+            CellValue::String(s) => {
+                let s = s.trim();
+                ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                    .iter()
+                    .find_map(|format| chrono::NaiveDateTime::parse_from_str(s, format).ok())
+                    .or_else(|| {
+                        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                            .ok()
+                            .map(|d| d.and_time(chrono::NaiveTime::MIN))
+                    })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Day zero of Excel serial dates from March 1900 on (the phantom
+// 1900-02-29 shifts earlier ones by a day)
+fn excel_epoch() -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(1899, 12, 30)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .expect("valid epoch")
+}
+
+/// Days in an elapsed time, as Excel stores it
+pub(crate) fn duration_serial(duration: &Duration) -> f64 {
+    duration.as_secs_f64() / 86_400.0
+}
+
+/// Elapsed time of a number of days, to the millisecond; None if negative
+pub(crate) fn serial_to_duration(days: f64) -> Option<Duration> {
+    let millis = (days * 86_400_000.0).round();
+    (millis.is_finite() && (0.0..=1e15).contains(&millis))
+        .then(|| Duration::from_millis(millis as u64))
+}
+
+fn serial_to_datetime(serial: f64) -> Option<chrono::NaiveDateTime> {
+    let millis = (serial * 86_400_000.0).round();
+    if !millis.is_finite() || millis.abs() > 1e15 {
+        return None;
+    }
+    excel_epoch().checked_add_signed(chrono::Duration::milliseconds(millis as i64))
+}
+
+impl PartialEq<str> for CellValue {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for CellValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+
+impl PartialEq<i64> for CellValue {
+    fn eq(&self, other: &i64) -> bool {
+        matches!(self, CellValue::Int(i) if i == other)
+    }
+}
+
+impl PartialEq<f64> for CellValue {
+    fn eq(&self, other: &f64) -> bool {
+        matches!(self, CellValue::Float(f) if f == other)
+    }
+}
+
+impl PartialEq<bool> for CellValue {
+    fn eq(&self, other: &bool) -> bool {
+        matches!(self, CellValue::Bool(b) if b == other)
+    }
+}
+
+impl fmt::Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_string())
+    }
+}
+
+impl From<&str> for CellValue {
+    fn from(s: &str) -> Self {
+        CellValue::String(s.to_string())
+    }
+}
+
+impl From<String> for CellValue {
+    fn from(s: String) -> Self {
+        CellValue::String(s)
+    }
+}
+
+impl From<i64> for CellValue {
+    fn from(i: i64) -> Self {
+        CellValue::Int(i)
+    }
+}
+
+impl From<i32> for CellValue {
+    fn from(i: i32) -> Self {
+        CellValue::Int(i as i64)
+    }
+}
+
+impl From<u32> for CellValue {
+    fn from(i: u32) -> Self {
+        CellValue::Int(i as i64)
+    }
+}
+
+impl From<f64> for CellValue {
+    fn from(f: f64) -> Self {
+        CellValue::Float(f)
+    }
+}
+
+impl From<bool> for CellValue {
+    fn from(b: bool) -> Self {
+        CellValue::Bool(b)
+    }
+}
+
+impl From<Decimal> for CellValue {
+    fn from(d: Decimal) -> Self {
+        CellValue::Decimal(d)
+    }
+}
+
+/// `None` becomes an empty cell
+impl<T: Into<CellValue>> From<Option<T>> for CellValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(CellValue::Empty, Into::into)
+    }
+}
+
+impl From<chrono::NaiveDate> for CellValue {
+    fn from(date: chrono::NaiveDate) -> Self {
+        date.and_time(chrono::NaiveTime::MIN).into()
+    }
+}
+
+/// Stored as an Excel serial date, to the millisecond
+impl From<Duration> for CellValue {
+    fn from(duration: Duration) -> Self {
+        CellValue::Duration(duration)
+    }
+}
+
+impl From<RichText> for CellValue {
+    fn from(text: RichText) -> Self {
+        CellValue::RichText(text)
+    }
+}
+
+impl From<chrono::DateTime<chrono::FixedOffset>> for CellValue {
+    fn from(datetime: chrono::DateTime<chrono::FixedOffset>) -> Self {
+        CellValue::DateTimeTz(datetime)
+    }
+}
+
+impl From<chrono::NaiveDateTime> for CellValue {
+    fn from(datetime: chrono::NaiveDateTime) -> Self {
+        let millis = (datetime - excel_epoch()).num_milliseconds();
+        CellValue::DateTime(millis as f64 / 86_400_000.0)
+    }
+}
+
+/// Build a `Vec<CellValue>` from values of mixed types
+///
+/// Each value goes through `CellValue::from`, so anything with a `From`
+/// conversion works: strings, integers, floats, booleans, [`Decimal`],
+/// chrono dates and `Option`s of those (`None` is an empty cell).
+///
+/// # Examples
+///
+/// ```
+/// use excelstream::row;
+/// use excelstream::types::CellValue;
+///
+/// let cells = row!["Alice", 30, 1234.56, true, None::<i64>];
+/// assert_eq!(cells[1], CellValue::Int(30));
+/// assert_eq!(cells[4], CellValue::Empty);
+/// ```
+#[macro_export]
+macro_rules! row {
+    () => {
+        Vec::<$crate::types::CellValue>::new()
+    };
+    ($($value:expr),+ $(,)?) => {
+        vec![$($crate::types::CellValue::from($value)),+]
+    };
+}
+
+/// Exact decimal number stored as a scaled integer (`mantissa * 10^-scale`)
+///
+/// Unlike `f64`, a `Decimal` keeps the digits you give it, so `0.1 + 0.2`
+/// style artifacts never end up in financial exports.
+///
+/// # Examples
+///
+/// ```
+/// use excelstream::types::Decimal;
+///
+/// let price = Decimal::new(1999, 2);
+/// assert_eq!(price.to_string(), "19.99");
+///
+/// let parsed: Decimal = "0.30".parse().unwrap();
+/// assert_eq!(parsed.to_string(), "0.30");
+///
+/// // Equal amounts are equal whatever their scale
+/// assert_eq!(parsed, "0.3".parse().unwrap());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Maximum number of fractional digits
+    pub const MAX_SCALE: u32 = 28;
+
+    /// Create a decimal from a scaled integer (`Decimal::new(1234, 2)` = 12.34)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is greater than [`Decimal::MAX_SCALE`].
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        assert!(
+            scale <= Self::MAX_SCALE,
+            "Decimal scale {} exceeds maximum of {}",
+            scale,
+            Self::MAX_SCALE
+        );
+        Decimal { mantissa, scale }
+    }
+
+    /// Get the unscaled integer value
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// Get the number of fractional digits
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Convert to the nearest f64 (may lose precision)
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Integer part of the value, if it fits in an i64
+    pub fn trunc_i64(&self) -> Option<i64> {
+        i64::try_from(self.mantissa / 10i128.pow(self.scale)).ok()
+    }
+
+    // Mantissa and scale without trailing fractional zeros
+    fn normalized(&self) -> (i128, u32) {
+        let (mut mantissa, mut scale) = (self.mantissa, self.scale);
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        (mantissa, scale)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for Decimal {}
+
+impl std::hash::Hash for Decimal {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        // Left-pad with zeros so there is at least one integer digit
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+        write!(f, "{}{}.{}", sign, int_part, frac_part)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ExcelError;
+
+    /// Parse plain (`-12.50`) or scientific (`1.25E-3`) decimal text exactly
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ExcelError::InvalidFormat(format!("Invalid decimal: '{}'", s));
+        let text = s.trim();
+
+        let (number, exponent) = match text.find(['e', 'E']) {
+            Some(pos) => (
+                &text[..pos],
+                text[pos + 1..].parse::<i32>().map_err(|_| invalid())?,
+            ),
+            None => (text, 0),
+        };
+
+        let (negative, unsigned) = match number.as_bytes().first() {
+            Some(b'-') => (true, &number[1..]),
+            Some(b'+') => (false, &number[1..]),
+            _ => (false, number),
+        };
+
+        let (int_digits, frac_digits) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if int_digits.is_empty() && frac_digits.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut mantissa: i128 = 0;
+        for ch in int_digits.chars().chain(frac_digits.chars()) {
+            let digit = ch.to_digit(10).ok_or_else(invalid)?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit as i128))
+                .ok_or_else(invalid)?;
+        }
+
+        let mut scale = frac_digits.len() as i64 - exponent as i64;
+        if mantissa == 0 {
+            // Zero at any exponent; no need to scale it
+            scale = scale.clamp(0, Self::MAX_SCALE as i64);
+        } else if scale < -(i128::MAX.ilog10() as i64) {
+            // More integer digits than an i128 holds
+            return Err(invalid());
+        }
+        while scale < 0 {
+            mantissa = mantissa.checked_mul(10).ok_or_else(invalid)?;
+            scale += 1;
+        }
+        if scale > Self::MAX_SCALE as i64 {
+            return Err(invalid());
+        }
+
+        if negative {
+            mantissa = -mantissa;
+        }
+        Ok(Decimal {
+            mantissa,
+            scale: scale as u32,
+        })
+    }
+}
+
+/// Text made of runs with their own formatting, for a cell such as
+/// "**Error:** disk full"
+///
+/// Written as an inline string with one run per part; the cell's style
+/// still sets the font the runs start from. Readers give back the plain
+/// text.
+///
+/// # Examples
+///
+/// ```
+/// use excelstream::types::{CellValue, RichText};
+///
+/// let status = RichText::new().bold("Error: ").plain("disk full");
+/// assert_eq!(status.text(), "Error: disk full");
+/// let cell = CellValue::from(status);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RichText {
+    runs: Vec<TextRun>,
+}
+
+/// A part of a [`RichText`] with its formatting
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TextRun {
+    /// The text
+    pub text: String,
+    /// Bold
+    pub bold: bool,
+    /// Italic
+    pub italic: bool,
+    /// Single underline
+    pub underline: bool,
+    /// Struck through
+    pub strike: bool,
+    /// Font color as ARGB hex ("FFFF0000" is red), None for the cell's;
+    /// anything but 8 hex digits is written as the cell's color
+    pub color: Option<String>,
+}
+
+impl TextRun {
+    /// Unformatted text
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        TextRun {
+            text: text.into(),
+            ..TextRun::default()
+        }
+    }
+
+    /// Append the run's `<rPr>`, if it has any formatting
+    pub(crate) fn push_properties(&self, out: &mut Vec<u8>) {
+        // Checked here since the field is public and lands in an attribute
+        let color = self
+            .color
+            .as_deref()
+            .filter(|c| c.len() == 8 && c.bytes().all(|b| b.is_ascii_hexdigit()));
+        if !(self.bold || self.italic || self.underline || self.strike || color.is_some()) {
+            return;
+        }
+        out.extend_from_slice(b"<rPr>");
+        if self.bold {
+            out.extend_from_slice(b"<b/>");
+        }
+        if self.italic {
+            out.extend_from_slice(b"<i/>");
+        }
+        if self.strike {
+            out.extend_from_slice(b"<strike/>");
+        }
+        if let Some(color) = color {
+            out.extend_from_slice(b"<color rgb=\"");
+            out.extend_from_slice(color.as_bytes());
+            out.extend_from_slice(b"\"/>");
+        }
+        if self.underline {
+            out.extend_from_slice(b"<u/>");
+        }
+        out.extend_from_slice(b"</rPr>");
+    }
+}
+
+impl RichText {
+    /// Empty text
+    pub fn new() -> Self {
+        RichText::default()
+    }
+
+    /// Append a run
+    pub fn run(mut self, run: TextRun) -> Self {
+        self.runs.push(run);
+        self
+    }
+
+    /// Append unformatted text
+    pub fn plain<S: Into<String>>(self, text: S) -> Self {
+        self.run(TextRun::new(text))
+    }
+
+    /// Append bold text
+    pub fn bold<S: Into<String>>(self, text: S) -> Self {
+        self.run(TextRun {
+            bold: true,
+            ..TextRun::new(text)
+        })
+    }
+
+    /// Append italic text
+    pub fn italic<S: Into<String>>(self, text: S) -> Self {
+        self.run(TextRun {
+            italic: true,
+            ..TextRun::new(text)
+        })
+    }
+
+    /// Append underlined text
+    pub fn underline<S: Into<String>>(self, text: S) -> Self {
+        self.run(TextRun {
+            underline: true,
+            ..TextRun::new(text)
+        })
+    }
+
+    /// Append text in a color given as RGB or ARGB hex ("FF0000" or
+    /// "FFFF0000"); any other color leaves the text in the cell's color
+    pub fn colored<S: Into<String>>(self, text: S, color: &str) -> Self {
+        let color = color.trim_start_matches('#');
+        let color = match color.len() {
+            6 => Some(format!("FF{}", color)),
+            8 => Some(color.to_string()),
+            _ => None,
+        }
+        .filter(|c| c.bytes().all(|b| b.is_ascii_hexdigit()))
+        .map(|c| c.to_ascii_uppercase());
+        self.run(TextRun {
+            color,
+            ..TextRun::new(text)
+        })
+    }
+
+    /// The runs, in order
+    pub fn runs(&self) -> &[TextRun] {
+        &self.runs
+    }
+
+    /// The text without formatting
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+}
+
+/// How `CellValue::Float` values are written into cell XML
+///
+/// The default writes the shortest text that reads back as the same `f64`
+/// (`0.1 + 0.2` stays `0.30000000000000004`), in plain notation for
+/// magnitudes from 1e-7 up to 1e21 and in scientific notation (`1E+21`)
+/// outside that range, so huge or tiny values don't turn into hundreds of
+/// digits.
+///
+/// # Examples
+///
+/// ```
+/// use excelstream::types::FloatFormat;
+///
+/// let default = FloatFormat::default();
+/// assert_eq!(default.format(0.1 + 0.2), "0.30000000000000004");
+/// assert_eq!(default.format(1e21), "1E+21");
+///
+/// // What Excel itself keeps: 15 significant digits
+/// let excel = FloatFormat::EXCEL;
+/// assert_eq!(excel.format(0.1 + 0.2), "0.3");
+///
+/// let plain = FloatFormat::default().scientific_outside(-20, 30);
+/// assert_eq!(plain.format(1e21), "1000000000000000000000");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatFormat {
+    significant_digits: Option<u32>,
+    min_plain_exponent: i32,
+    max_plain_exponent: i32,
+    non_finite: NonFinite,
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        FloatFormat {
+            significant_digits: None,
+            min_plain_exponent: -7,
+            max_plain_exponent: 20,
+            non_finite: NonFinite::Error,
+        }
+    }
+}
+
+/// How writers store NaN and infinite floats, which a spreadsheet has no
+/// number for
+///
+/// Writing them as numbers would produce a file Excel refuses to open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinite {
+    /// Error cell: `#NUM!` for NaN, `#DIV/0!` for infinities (default)
+    #[default]
+    Error,
+    /// Blank cell
+    Empty,
+    /// Text cell: "NaN", "inf" or "-inf"
+    String,
+}
+
+/// Most characters a cell's text can hold in Excel
+pub const MAX_TEXT_LEN: usize = 32_767;
+
+/// Most characters a formula can have in Excel
+pub const MAX_FORMULA_LEN: usize = 8_192;
+
+/// Most hyperlinks a worksheet can have in Excel
+pub const MAX_HYPERLINKS: usize = 65_530;
+
+/// Most cell formats a workbook can have in Excel
+pub const MAX_CELL_STYLES: u32 = 64_000;
+
+/// Widest a column can be in Excel, in characters
+pub const MAX_COLUMN_WIDTH: f64 = 255.0;
+
+/// Check a column width is a number from 0 to [`MAX_COLUMN_WIDTH`]
+pub(crate) fn check_column_width(col: u32, width: f64) -> crate::error::Result<()> {
+    if !(0.0..=MAX_COLUMN_WIDTH).contains(&width) {
+        return Err(crate::error::ExcelError::InvalidState(format!(
+            "Column {} width {} is not between 0 and {}",
+            crate::cellref::col_to_name(col),
+            width,
+            MAX_COLUMN_WIDTH
+        )));
+    }
+    Ok(())
+}
+
+/// What writers do with content over Excel's hard limits
+///
+/// Excel opens such files only after "repairing" them, which deletes the
+/// offending content. The limits are [`MAX_TEXT_LEN`], [`MAX_FORMULA_LEN`],
+/// [`MAX_HYPERLINKS`] per sheet and [`MAX_CELL_STYLES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitPolicy {
+    /// Fail with [`ExcelError::LimitExceeded`](crate::ExcelError::LimitExceeded)
+    /// naming the cell, and drop its row (default)
+    #[default]
+    Error,
+    /// Cut text at the limit, drop hyperlinks past it and write cells with
+    /// styles past it unstyled; formulas that are too long still fail,
+    /// since a cut formula is not a formula
+    Truncate,
+}
+
+impl FloatFormat {
+    /// Round to 15 significant digits, the precision Excel stores
+    pub const EXCEL: FloatFormat = FloatFormat {
+        significant_digits: Some(15),
+        min_plain_exponent: -7,
+        max_plain_exponent: 20,
+        non_finite: NonFinite::Error,
+    };
+
+    /// Round to at most `digits` significant digits (1 to 17)
+    pub fn significant_digits(mut self, digits: u32) -> Self {
+        self.significant_digits = Some(digits.clamp(1, 17));
+        self
+    }
+
+    /// Use plain notation for decimal exponents from `min` to `max` (a value
+    /// `x` with `10^e <= |x| < 10^(e+1)` has exponent `e`), scientific outside
+    pub fn scientific_outside(mut self, min: i32, max: i32) -> Self {
+        self.min_plain_exponent = min;
+        self.max_plain_exponent = max;
+        self
+    }
+
+    /// How NaN and infinite floats are written (default [`NonFinite::Error`])
+    pub fn non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// What a writer stores instead of `value` (a float, date or
+    /// percentage), if it is NaN or infinite
+    pub(crate) fn replace_non_finite(&self, value: &CellValue) -> Option<CellValue> {
+        let (CellValue::Float(f) | CellValue::DateTime(f) | CellValue::Percent(f)) = value else {
+            return None;
+        };
+        if f.is_finite() {
+            return None;
+        }
+        Some(match self.non_finite {
+            NonFinite::Error if f.is_nan() => CellValue::Error("#NUM!".to_string()),
+            NonFinite::Error => CellValue::Error("#DIV/0!".to_string()),
+            NonFinite::Empty => CellValue::Empty,
+            NonFinite::String => CellValue::String(f.to_string()),
+        })
+    }
+
+    /// Format a value
+    ///
+    /// NaN and infinities come out as `NaN`, `inf` and `-inf`; writers
+    /// store those cells according to [`FloatFormat::non_finite`] instead.
+    pub fn format(&self, value: f64) -> String {
+        let mut out = Vec::new();
+        self.write(value, &mut out);
+        String::from_utf8(out).unwrap_or_default()
+    }
+
+    /// Append a formatted value to an XML buffer
+    ///
+    /// The text is formatted in place at the end of `out`, without
+    /// allocating; writing to a `Vec` cannot fail.
+    pub(crate) fn write(&self, value: f64, out: &mut Vec<u8>) {
+        use std::io::Write;
+
+        if !value.is_finite() || value == 0.0 {
+            let _ = write!(out, "{}", value);
+            return;
+        }
+
+        // Rounding through the decimal text, then formatting the result
+        // shortest-first, drops the trailing zeros and noise digits
+        let start = out.len();
+        let value = match self.significant_digits {
+            Some(digits) => {
+                let _ = write!(out, "{:.*e}", digits as usize - 1, value);
+                let rounded = std::str::from_utf8(&out[start..])
+                    .ok()
+                    .and_then(|text| text.parse().ok())
+                    .unwrap_or(value);
+                out.truncate(start);
+                rounded
+            }
+            None => value,
+        };
+
+        // LowerExp is shortest round-trip too: "1.2345e21"
+        let _ = write!(out, "{:e}", value);
+        let e = out[start..]
+            .iter()
+            .position(|&b| b == b'e')
+            .map_or(out.len(), |i| start + i);
+        let exponent: i32 = std::str::from_utf8(out.get(e + 1..).unwrap_or_default())
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .unwrap_or(0);
+
+        if (self.min_plain_exponent..=self.max_plain_exponent).contains(&exponent) {
+            out.truncate(start);
+            let _ = write!(out, "{}", value);
+        } else {
+            // Excel's own spelling: 1.5E+21, 2E-8
+            out.truncate(e);
+            out.extend_from_slice(if exponent < 0 { b"E-" } else { b"E+" });
+            out.extend_from_slice(
+                itoa::Buffer::new()
+                    .format(exponent.unsigned_abs())
+                    .as_bytes(),
+            );
+        }
+    }
+}
+
+/// How writers store [`CellValue::DateTimeTz`] values
+///
+/// A spreadsheet date is a plain local time with no offset, so an offset
+/// date either becomes the local time of one chosen zone or stays text.
+///
+/// # Example
+///
+/// ```
+/// use chrono::DateTime;
+/// use excelstream::types::{CellValue, TimeZonePolicy};
+///
+/// let dt = DateTime::parse_from_rfc3339("2024-01-31T09:00:00+07:00").unwrap();
+/// let utc = TimeZonePolicy::default().resolve(&CellValue::DateTimeTz(dt));
+/// assert_eq!(utc.as_date().unwrap().to_string(), "2024-01-31 02:00:00");
+///
+/// let iso = TimeZonePolicy::IsoString.resolve(&CellValue::DateTimeTz(dt));
+/// assert_eq!(iso, CellValue::String("2024-01-31T09:00:00+07:00".to_string()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZonePolicy {
+    /// Convert to the local time at this offset and store a date serial
+    /// (default: UTC); the offset is lost
+    Convert(chrono::FixedOffset),
+    /// Store RFC 3339 text with the offset (`2024-01-31T09:00:00+07:00`),
+    /// which reads back as [`CellValue::DateTimeTz`] with type inference
+    IsoString,
+}
+
+impl Default for TimeZonePolicy {
+    fn default() -> Self {
+        TimeZonePolicy::Convert(chrono::FixedOffset::east_opt(0).expect("valid offset"))
+    }
+}
+
+impl TimeZonePolicy {
+    /// What a writer stores for `value`; values other than
+    /// [`CellValue::DateTimeTz`] are returned as they are
+    pub fn resolve(&self, value: &CellValue) -> CellValue {
+        self.replace(value).unwrap_or_else(|| value.clone())
+    }
+
+    /// What a writer stores instead of `value`, if it has an offset
+    pub(crate) fn replace(&self, value: &CellValue) -> Option<CellValue> {
+        let CellValue::DateTimeTz(dt) = value else {
+            return None;
+        };
+        Some(match self {
+            TimeZonePolicy::Convert(offset) => {
+                CellValue::from(dt.with_timezone(offset).naive_local())
+            }
+            TimeZonePolicy::IsoString => CellValue::String(dt.to_rfc3339()),
+        })
+    }
+}
+
+/// Hyperlink attached to a cell
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Hyperlink {
+    /// Link target URL (empty for links within the workbook)
+    pub target: String,
+    /// Location within the workbook or target document (e.g. "Sheet2!A1")
+    pub location: Option<String>,
+    /// Tooltip shown on hover
+    pub tooltip: Option<String>,
+    /// Display text stored with the link, if different from the cell value
+    pub display: Option<String>,
+}
+
+/// Another workbook referenced by formulas, from `xl/externalLinks`
+///
+/// Formulas refer to it by number: `[1]Sheet1!A1` reads cell A1 of the
+/// external link with `index` 1. The values Excel last saw in the
+/// referenced cells are kept in `cached_values`; they are what formula
+/// cells hold until the workbook is recalculated with the other file
+/// available.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalLink {
+    /// 1-based number formulas use for the workbook
+    pub index: u32,
+    /// Path or URL of the workbook as stored (e.g. `file:///C:/data/rates.xlsx`
+    /// or `rates.xlsx`), None for DDE and OLE links
+    pub target: Option<String>,
+    /// Sheets of the referenced workbook
+    pub sheet_names: Vec<String>,
+    /// Cached values of referenced cells, with the sheet they are on
+    pub cached_values: Vec<(String, Cell)>,
+}
+
+impl ExternalLink {
+    /// File name of the target, e.g. `rates.xlsx`
+    pub fn file_name(&self) -> Option<&str> {
+        let target = self.target.as_deref()?;
+        target
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Cached value of a cell of the referenced workbook (0-based)
+    pub fn cached_value(&self, sheet: &str, row: u32, col: u32) -> Option<&CellValue> {
+        self.cached_values
+            .iter()
+            .find(|(s, cell)| s == sheet && cell.row == row && cell.col == col)
+            .map(|(_, cell)| &cell.value)
+    }
+
+    /// Replace the `[n]` markers in a formula with the file names of the
+    /// links, the way Excel displays them (`[rates.xlsx]Sheet1!A1`)
+    ///
+    /// Markers of unknown links or links without a target are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use excelstream::types::ExternalLink;
+    ///
+    /// let links = [ExternalLink {
+    ///     index: 1,
+    ///     target: Some("file:///C:/data/fx%20rates.xlsx".to_string()),
+    ///     sheet_names: vec!["EUR".to_string()],
+    ///     cached_values: Vec::new(),
+    /// }];
+    /// assert_eq!(
+    ///     ExternalLink::resolve_formula("=B2*[1]EUR!A1", &links),
+    ///     "=B2*'[fx rates.xlsx]EUR'!A1"
+    /// );
+    /// ```
+    pub fn resolve_formula(formula: &str, links: &[ExternalLink]) -> String {
+        let mut out = String::with_capacity(formula.len());
+        let mut pos = 0;
+        for (span, index) in crate::cellref::external_refs(formula) {
+            let Some(name) = links
+                .iter()
+                .find(|link| link.index == index)
+                .and_then(|link| link.file_name())
+            else {
+                continue;
+            };
+            let name = percent_decode(name);
+            out.push_str(&formula[pos..span.start]);
+            pos = span.end;
+
+            let quoted = formula[..span.start].ends_with('\'');
+            let sheet_end = formula[pos..].find('!').map(|i| pos + i);
+            let needs_quotes = !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+            match sheet_end {
+                // Quote the workbook and sheet together: '[my file.xlsx]Sheet1'!A1
+                Some(end) if !quoted && needs_quotes => {
+                    out.push_str(&format!(
+                        "'[{}]{}'",
+                        name.replace('\'', "''"),
+                        formula[pos..end].replace('\'', "''")
+                    ));
+                    pos = end;
+                }
+                _ if quoted => out.push_str(&format!("[{}]", name.replace('\'', "''"))),
+                _ => out.push_str(&format!("[{}]", name)),
+            }
+        }
+        out.push_str(&formula[pos..]);
+        out
+    }
+}
+
+// Decode %XX escapes of a file URL
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = text
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).unwrap_or_else(|_| text.to_string())
+}
+
+/// Document properties of a workbook, from `docProps/core.xml` and
+/// `docProps/app.xml`; fields the file doesn't set are None
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WorkbookProperties {
+    /// Title
+    pub title: Option<String>,
+    /// Subject
+    pub subject: Option<String>,
+    /// Author (`dc:creator`)
+    pub creator: Option<String>,
+    /// Keywords
+    pub keywords: Option<String>,
+    /// Comments (`dc:description`)
+    pub description: Option<String>,
+    /// Who saved the file last
+    pub last_modified_by: Option<String>,
+    /// Creation time
+    pub created: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Time of the last save
+    pub modified: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Application that wrote the file (e.g. "Microsoft Excel")
+    pub application: Option<String>,
+    /// Version of that application (e.g. "16.0300")
+    pub app_version: Option<String>,
+    /// Company
+    pub company: Option<String>,
+}
+
+/// What a workbook contains beyond its cells, see
+/// [`StreamingReader::workbook_stats`](crate::streaming_reader::StreamingReader::workbook_stats)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WorkbookStats {
+    /// Number of worksheets
+    pub sheet_count: usize,
+    /// Number of defined names (named ranges, print areas, ...)
+    pub defined_names: usize,
+    /// The package holds a VBA project
+    pub has_macros: bool,
+    /// Formulas may reference other workbooks
+    pub has_external_links: bool,
+}
+
+/// Workbook defined name (named range, print area, ...)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinedName {
+    /// Name (e.g. "SalesData", "_xlnm.Print_Area")
+    pub name: String,
+    /// Formula the name refers to (e.g. "Sheet1!$A$1:$C$10")
+    pub refers_to: String,
+    /// Sheet the name is scoped to, None for workbook scope
+    pub scope: Option<String>,
+    /// Hidden from the Name Manager
+    pub hidden: bool,
+}
+
+impl DefinedName {
+    /// Sheet and range the name refers to, if it is a plain single range
+    ///
+    /// Returns None for formulas, constants and multi-area references.
+    pub fn range(&self) -> Option<(String, CellRange)> {
+        let (sheet, range) = self.refers_to.rsplit_once('!')?;
+        let sheet = match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+            Some(quoted) => quoted.replace("''", "'"),
+            // Unquoted names can't contain ',' - this is a multi-area reference
+            None if sheet.contains(',') => return None,
+            None => sheet.to_string(),
+        };
+        Some((sheet, range.parse().ok()?))
+    }
+}
+
+/// Excel Table (ListObject) defined on a worksheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInfo {
+    /// Table name as shown in Excel (e.g. "Orders")
+    pub name: String,
+    /// Full table range, including header and totals rows
+    pub range: CellRange,
+    /// Column names, left to right
+    pub columns: Vec<String>,
+    /// Number of header rows (0 or 1)
+    pub header_row_count: u32,
+    /// Number of totals rows (0 or 1)
+    pub totals_row_count: u32,
+}
+
+impl TableInfo {
+    /// Range of the data rows only (without header and totals rows)
+    pub fn data_range(&self) -> CellRange {
+        CellRange::new(
+            self.range.first_row + self.header_row_count,
+            self.range.first_col,
+            self.range.last_row.saturating_sub(self.totals_row_count),
+            self.range.last_col,
+        )
+    }
+}
+
+/// Image embedded in a worksheet, see
+/// [`StreamingReader::images`](crate::streaming_reader::StreamingReader::images)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetImage {
+    /// Shape name (e.g. "Picture 1")
+    pub name: String,
+    /// Alt text, if any
+    pub description: Option<String>,
+    /// Path of the media part in the package (e.g. "xl/media/image1.png")
+    pub path: String,
+    /// Cells the image is anchored to (a single cell for one-cell anchors)
+    pub anchor: CellRange,
+    /// Raw image bytes
+    pub data: Vec<u8>,
+}
+
+impl SheetImage {
+    /// File extension of the media part, lowercase (e.g. "png")
+    pub fn extension(&self) -> Option<String> {
+        let file = self.path.rsplit('/').next()?;
+        let (_, ext) = file.rsplit_once('.')?;
+        Some(ext.to_ascii_lowercase())
+    }
+}
+
+/// Section of the page header or footer a picture is printed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFooterPosition {
+    LeftHeader,
+    CenterHeader,
+    RightHeader,
+    LeftFooter,
+    CenterFooter,
+    RightFooter,
+}
+
+impl HeaderFooterPosition {
+    /// Shape id of the picture in the VML drawing ("LH", "CF", ...)
+    pub fn shape_id(&self) -> &'static str {
+        match self {
+            HeaderFooterPosition::LeftHeader => "LH",
+            HeaderFooterPosition::CenterHeader => "CH",
+            HeaderFooterPosition::RightHeader => "RH",
+            HeaderFooterPosition::LeftFooter => "LF",
+            HeaderFooterPosition::CenterFooter => "CF",
+            HeaderFooterPosition::RightFooter => "RF",
+        }
+    }
+
+    /// Position for a VML shape id
+    pub fn from_shape_id(id: &str) -> Option<Self> {
+        match id {
+            "LH" => Some(HeaderFooterPosition::LeftHeader),
+            "CH" => Some(HeaderFooterPosition::CenterHeader),
+            "RH" => Some(HeaderFooterPosition::RightHeader),
+            "LF" => Some(HeaderFooterPosition::LeftFooter),
+            "CF" => Some(HeaderFooterPosition::CenterFooter),
+            "RF" => Some(HeaderFooterPosition::RightFooter),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a header (not a footer) section
+    pub fn is_header(&self) -> bool {
+        self.shape_id().ends_with('H')
+    }
+
+    // Section code in header/footer text: 'L', 'C' or 'R'
+    fn section_code(&self) -> char {
+        self.shape_id().chars().next().unwrap_or('C')
+    }
+}
+
+/// Picture printed in a page header or footer, placed by a `&G` code
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderFooterImage {
+    /// Section the picture belongs to
+    pub position: HeaderFooterPosition,
+    /// Raw image bytes
+    pub data: Vec<u8>,
+    /// Image type as a file extension, lowercase ("png", "jpeg", ...)
+    pub extension: String,
+    /// Printed width in points
+    pub width: f64,
+    /// Printed height in points
+    pub height: f64,
+}
+
+impl HeaderFooterImage {
+    /// Picture of `width` x `height` points
+    pub fn new(
+        position: HeaderFooterPosition,
+        data: Vec<u8>,
+        extension: &str,
+        width: f64,
+        height: f64,
+    ) -> Self {
+        HeaderFooterImage {
+            position,
+            data,
+            extension: extension.to_ascii_lowercase(),
+            width,
+            height,
+        }
+    }
+
+    /// MIME type of the image
+    pub fn content_type(&self) -> &'static str {
+        match self.extension.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "bmp" => "image/bmp",
+            "emf" => "image/x-emf",
+            "wmf" => "image/x-wmf",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+/// Page header and footer printed on every page of a sheet
+///
+/// Text uses Excel's codes: `&L`, `&C` and `&R` start the left, center and
+/// right section (text before any of them is centered), `&P` is the page
+/// number, `&N` the page count, `&D` the date, `&T` the time, `&A` the sheet
+/// name, `&G` the section's picture and `&&` a literal ampersand.
+///
+/// # Example
+///
+/// ```
+/// use excelstream::types::{HeaderFooter, HeaderFooterImage, HeaderFooterPosition};
+///
+/// # let logo_png: Vec<u8> = Vec::new();
+/// let print = HeaderFooter::new()
+///     .with_header("&L&G&RConfidential")
+///     .with_footer("&CPage &P of &N")
+///     .with_image(HeaderFooterImage::new(
+///         HeaderFooterPosition::LeftHeader,
+///         logo_png,
+///         "png",
+///         96.0,
+///         32.0,
+///     ));
+/// assert!(print.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderFooter {
+    /// Header text with formatting codes (empty for none)
+    pub header: String,
+    /// Footer text with formatting codes (empty for none)
+    pub footer: String,
+    /// Pictures, at most one per section
+    pub images: Vec<HeaderFooterImage>,
+}
+
+impl HeaderFooter {
+    /// Empty header and footer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the header text
+    pub fn with_header(mut self, text: &str) -> Self {
+        self.header = text.to_string();
+        self
+    }
+
+    /// Set the footer text
+    pub fn with_footer(mut self, text: &str) -> Self {
+        self.footer = text.to_string();
+        self
+    }
+
+    /// Add a picture, replacing any other in the same section
+    pub fn with_image(mut self, image: HeaderFooterImage) -> Self {
+        self.images.retain(|i| i.position != image.position);
+        self.images.push(image);
+        self
+    }
+
+    /// Picture of a section, if any
+    pub fn image(&self, position: HeaderFooterPosition) -> Option<&HeaderFooterImage> {
+        self.images.iter().find(|i| i.position == position)
+    }
+
+    /// Check that the text fits Excel's 255-character limit and that every
+    /// picture's section has a `&G` code to print it
+    pub fn validate(&self) -> crate::error::Result<()> {
+        for (label, text) in [("Header", &self.header), ("Footer", &self.footer)] {
+            if text.chars().count() > 255 {
+                return Err(crate::error::ExcelError::InvalidFormat(format!(
+                    "{} is longer than 255 characters",
+                    label
+                )));
+            }
+        }
+        for image in &self.images {
+            let text = if image.position.is_header() {
+                &self.header
+            } else {
+                &self.footer
+            };
+            if !header_footer_section(text, image.position.section_code()).contains("&G") {
+                return Err(crate::error::ExcelError::InvalidFormat(format!(
+                    "No &G code for the {:?} picture",
+                    image.position
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Codes of one section ('L', 'C' or 'R') of header/footer text, with `&&`
+// left out so a literal "&&G" is not read as a picture
+fn header_footer_section(text: &str, code: char) -> String {
+    let mut section = String::new();
+    let mut current = 'C';
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            match chars.next() {
+                Some(next @ ('L' | 'C' | 'R')) => current = next,
+                Some('&') => {}
+                Some(next) if current == code => {
+                    section.push('&');
+                    section.push(next);
+                }
+                _ => {}
+            }
+        } else if current == code {
+            section.push(c);
+        }
+    }
+    section
+}
+
+/// Represents a cell with its position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    /// Row index (0-based)
+    pub row: u32,
+    /// Column index (0-based)
+    pub col: u32,
+    /// Cell value
+    pub value: CellValue,
+    /// Hyperlink on this cell, if any
+    pub hyperlink: Option<Hyperlink>,
+    /// Formula text (without the leading `=`) when the cell stores one
+    pub formula: Option<String>,
+}
+
+impl Cell {
+    /// Create a new cell
+    pub fn new(row: u32, col: u32, value: CellValue) -> Self {
+        Cell {
+            row,
+            col,
+            value,
+            hyperlink: None,
+            formula: None,
+        }
+    }
+
+    /// Attach a hyperlink
+    pub fn with_hyperlink(mut self, hyperlink: Hyperlink) -> Self {
+        self.hyperlink = Some(hyperlink);
+        self
+    }
+
+    /// Get Excel-style cell reference (e.g., "A1", "B2")
+    pub fn reference(&self) -> String {
+        crate::cellref::to_a1(self.row, self.col)
+    }
+}
+
+/// Represents a row of cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// Row index (0-based)
+    pub index: u32,
+    /// Cells in this row
+    pub cells: Vec<CellValue>,
+}
+
+impl Row {
+    /// Create a new row
+    pub fn new(index: u32, cells: Vec<CellValue>) -> Self {
+        Row { index, cells }
+    }
+
+    /// Get cell at column index
+    pub fn get(&self, col: usize) -> Option<&CellValue> {
+        self.cells.get(col)
+    }
+
+    /// Get the cell under the first header named `name`
+    ///
+    /// `headers` is typically the first row's
+    /// [`to_strings`](Self::to_strings). Cells missing at the end of the row
+    /// read as [`CellValue::Empty`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use excelstream::types::{CellValue, Row};
+    ///
+    /// let headers = ["Name", "Age"];
+    /// let row = Row::new(1, vec![CellValue::from("Alice"), CellValue::from(30)]);
+    /// assert_eq!(row.get_by_header(&headers, "Age"), Some(&CellValue::Int(30)));
+    /// assert_eq!(row.get_by_header(&headers, "Email"), None);
+    /// ```
+    pub fn get_by_header<S: AsRef<str>>(&self, headers: &[S], name: &str) -> Option<&CellValue> {
+        let col = headers.iter().position(|h| h.as_ref() == name)?;
+        Some(self.cells.get(col).unwrap_or(&CellValue::Empty))
+    }
+
+    /// Get number of cells
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Check if row is empty
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() || self.cells.iter().all(|c| c.is_empty())
+    }
+
+    /// Convert row to vector of strings
+    pub fn to_strings(&self) -> Vec<String> {
+        self.cells.iter().map(|c| c.as_string()).collect()
+    }
+}
+
+/// A data row keyed by the sheet's header row, see
+/// [`StreamingReader::rows_with_header`](crate::streaming_reader::StreamingReader::rows_with_header)
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// Row index (0-based)
+    pub index: u32,
+    /// Header names, shared by all records of a sheet
+    pub headers: Arc<[String]>,
+    /// Cells in this row, in column order
+    pub cells: Vec<CellValue>,
+    /// Whether [`get`](Self::get) ignores case, see
+    /// [`ReaderOptions::case_insensitive_headers`](crate::ReaderOptions::case_insensitive_headers)
+    pub case_insensitive: bool,
+}
+
+impl Record {
+    /// Get the cell under the first header named `name`
+    ///
+    /// Cells missing at the end of the row read as [`CellValue::Empty`].
+    pub fn get(&self, name: &str) -> Option<&CellValue> {
+        let col = self.headers.iter().position(|h| {
+            if self.case_insensitive {
+                h.chars()
+                    .flat_map(char::to_lowercase)
+                    .eq(name.chars().flat_map(char::to_lowercase))
+            } else {
+                h == name
+            }
+        })?;
+        Some(self.cells.get(col).unwrap_or(&CellValue::Empty))
+    }
+
+    /// Header and cell pairs, one per header
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CellValue)> {
+        self.headers.iter().enumerate().map(|(col, header)| {
+            (
+                header.as_str(),
+                self.cells.get(col).unwrap_or(&CellValue::Empty),
+            )
+        })
+    }
+
+    /// Convert to a plain [`Row`], dropping the headers
+    pub fn into_row(self) -> Row {
+        Row::new(self.index, self.cells)
+    }
+}
+
+/// A cell read by
+/// [`StreamingReader::rows_borrowed`](crate::streaming_reader::StreamingReader::rows_borrowed)
+///
+/// Shared strings are borrowed from the reader's table instead of copied
+/// into each cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedCell<'a> {
+    /// Text from the shared strings table
+    Shared(&'a str),
+    /// Any other value
+    Value(CellValue),
+}
+
+impl BorrowedCell<'_> {
+    /// Text of a string cell
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            BorrowedCell::Shared(text) => Some(text),
+            BorrowedCell::Value(value) => value.as_str(),
+        }
+    }
+
+    /// Check if cell is empty
+    pub fn is_empty(&self) -> bool {
+        matches!(self, BorrowedCell::Value(CellValue::Empty))
+    }
+
+    /// Copy into an owned [`CellValue`]
+    pub fn to_value(&self) -> CellValue {
+        match self {
+            BorrowedCell::Shared(text) => CellValue::String(text.to_string()),
+            BorrowedCell::Value(value) => value.clone(),
+        }
+    }
+
+    /// Convert into an owned [`CellValue`]
+    pub fn into_value(self) -> CellValue {
+        match self {
+            BorrowedCell::Shared(text) => CellValue::String(text.to_string()),
+            BorrowedCell::Value(value) => value,
+        }
+    }
+}
+
+impl From<CellValue> for BorrowedCell<'_> {
+    fn from(value: CellValue) -> Self {
+        BorrowedCell::Value(value)
+    }
+}
+
+impl fmt::Display for BorrowedCell<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowedCell::Shared(text) => f.write_str(text),
+            BorrowedCell::Value(value) => value.fmt(f),
+        }
+    }
+}
+
+/// A row of [`BorrowedCell`]s
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorrowedRow<'a> {
+    /// Row index (0-based)
+    pub index: u32,
+    /// Cells in this row
+    pub cells: Vec<BorrowedCell<'a>>,
+}
+
+impl<'a> BorrowedRow<'a> {
+    /// Get cell at column index
+    pub fn get(&self, col: usize) -> Option<&BorrowedCell<'a>> {
+        self.cells.get(col)
+    }
+
+    /// Get number of cells
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Check if row is empty
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|c| c.is_empty())
+    }
+
+    /// Copy into an owned [`Row`]
+    pub fn to_row(&self) -> Row {
+        Row::new(
+            self.index,
+            self.cells.iter().map(|c| c.to_value()).collect(),
+        )
+    }
+}
+
+/// A rectangular cell range such as `A1:C3` (0-based, inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellRange {
+    /// First row (0-based)
+    pub first_row: u32,
+    /// First column (0-based)
+    pub first_col: u32,
+    /// Last row (0-based, inclusive)
+    pub last_row: u32,
+    /// Last column (0-based, inclusive)
+    pub last_col: u32,
+}
+
+impl CellRange {
+    /// Create a range from 0-based corner positions
+    pub fn new(first_row: u32, first_col: u32, last_row: u32, last_col: u32) -> Self {
+        CellRange {
+            first_row,
+            first_col,
+            last_row,
+            last_col,
+        }
+    }
+
+    /// Whether the range covers the given cell
+    pub fn contains(&self, row: u32, col: u32) -> bool {
+        (self.first_row..=self.last_row).contains(&row)
+            && (self.first_col..=self.last_col).contains(&col)
+    }
+
+    /// Parse an A1-style cell reference ("B3", "$B$3") into 0-based (row, col)
+    pub fn parse_cell_ref(cell_ref: &str) -> Option<(u32, u32)> {
+        crate::cellref::parse_a1(cell_ref)
+    }
+
+    /// Number of rows covered
+    pub fn rows(&self) -> u32 {
+        self.last_row - self.first_row + 1
+    }
+
+    /// Number of columns covered
+    pub fn cols(&self) -> u32 {
+        self.last_col - self.first_col + 1
+    }
+
+    /// Number of cells covered
+    pub fn cell_count(&self) -> u64 {
+        self.rows() as u64 * self.cols() as u64
+    }
+
+    /// The same-sized range moved by `rows` and `cols`, or None if it would
+    /// leave the worksheet
+    pub fn offset(&self, rows: i64, cols: i64) -> Option<CellRange> {
+        let shift = |index: u32, by: i64, max: u32| {
+            let moved = index as i64 + by;
+            (0..max as i64).contains(&moved).then_some(moved as u32)
+        };
+        use crate::cellref::{MAX_COLS, MAX_ROWS};
+        Some(CellRange::new(
+            shift(self.first_row, rows, MAX_ROWS)?,
+            shift(self.first_col, cols, MAX_COLS)?,
+            shift(self.last_row, rows, MAX_ROWS)?,
+            shift(self.last_col, cols, MAX_COLS)?,
+        ))
+    }
+
+    /// Cells covered by both ranges
+    pub fn intersection(&self, other: &CellRange) -> Option<CellRange> {
+        let range = CellRange::new(
+            self.first_row.max(other.first_row),
+            self.first_col.max(other.first_col),
+            self.last_row.min(other.last_row),
+            self.last_col.min(other.last_col),
+        );
+        (range.first_row <= range.last_row && range.first_col <= range.last_col).then_some(range)
+    }
+
+    /// Smallest range covering both ranges
+    pub fn union(&self, other: &CellRange) -> CellRange {
+        CellRange::new(
+            self.first_row.min(other.first_row),
+            self.first_col.min(other.first_col),
+            self.last_row.max(other.last_row),
+            self.last_col.max(other.last_col),
+        )
+    }
+
+    /// Absolute form for formulas, e.g. "$A$1:$C$3"
+    pub fn to_absolute(&self) -> String {
+        use crate::cellref::to_a1_absolute;
+        format!(
+            "{}:{}",
+            to_a1_absolute(self.first_row, self.first_col),
+            to_a1_absolute(self.last_row, self.last_col)
+        )
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::cellref::to_a1;
+        write!(
+            f,
+            "{}:{}",
+            to_a1(self.first_row, self.first_col),
+            to_a1(self.last_row, self.last_col)
+        )
+    }
+}
+
+impl FromStr for CellRange {
+    type Err = ExcelError;
+
+    /// Parse "A1:C3" (a single reference like "B2" is a one-cell range)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::cellref::parse_range(s).ok_or_else(|| ExcelError::InvalidCell(s.to_string()))
+    }
+}
+
+/// Formatting of a cell as read from a workbook's styles.xml
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CellFormat {
+    /// Index of the cell format in the workbook's styles.xml (0 = default)
+    pub style_index: u32,
+    /// Number format id (0 = General)
+    pub num_fmt_id: u32,
+    /// Number format code (e.g. "0.00%"), None for unknown ids
+    pub num_fmt: Option<String>,
+    /// Bold font
+    pub bold: bool,
+    /// Italic font
+    pub italic: bool,
+    /// Font color as ARGB hex (e.g. "FFFF0000"), if set explicitly
+    pub font_color: Option<String>,
+    /// Solid fill color as ARGB hex, if set explicitly
+    pub fill_color: Option<String>,
+}
+
+impl CellFormat {
+    /// Whether the number format shows elapsed time (`[h]:mm:ss`,
+    /// `[mm]:ss`), which doesn't wrap at 24 hours
+    pub fn is_elapsed_time(&self) -> bool {
+        self.num_fmt
+            .as_deref()
+            .is_some_and(crate::numfmt::is_elapsed_time)
+    }
+}
+
+/// Cell value with its resolved formatting, yielded by
+/// [`StreamingReader::rows_with_style`](crate::streaming_reader::StreamingReader::rows_with_style)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormattedCell {
+    /// The cell value
+    pub value: CellValue,
+    /// The cell formatting (shared between cells with the same style)
+    pub format: Arc<CellFormat>,
+}
+
+impl FormattedCell {
+    /// The value as a number with the unit its number format gives it, see
+    /// [`numfmt::normalize`](crate::numfmt::normalize)
+    ///
+    /// A cell showing "12%" gives 0.12 and [`NumberUnit::Percent`], one
+    /// showing "$1,234.50" gives 1234.5 with currency "USD". None for cells
+    /// that aren't numbers or number text.
+    ///
+    /// [`NumberUnit::Percent`]: crate::numfmt::NumberUnit::Percent
+    pub fn normalized(&self) -> Option<crate::numfmt::NormalizedValue> {
+        crate::numfmt::normalize(&self.value, self.format.num_fmt.as_deref())
+    }
+}
+
+/// Summary of a finished workbook, returned by `save()`/`close()`
+///
+/// Lets export jobs log and assert on output size without re-opening the file.
+#[derive(Debug, Clone, Default)]
+pub struct WriteReport {
+    /// Rows written to each sheet, in sheet order
+    pub per_sheet_rows: Vec<(String, u32)>,
+    /// Total number of cells written across all sheets
+    pub total_cells: u64,
+    /// Size of the finished file (compressed ZIP)
+    pub compressed_bytes: u64,
+    /// Total XML bytes fed into the compressor
+    pub uncompressed_bytes: u64,
+    /// Time from writer creation until the file was finalized
+    pub duration: Duration,
+    /// Cells masked per column (0-based), for columns set with
+    /// [`ExcelWriter::mask_column`](crate::ExcelWriter::mask_column)
+    pub masked_cells: Vec<(u32, u64)>,
+    /// Delivery manifest, when enabled with
+    /// [`ExcelWriterBuilder::with_manifest`](crate::ExcelWriterBuilder::with_manifest)
+    pub manifest: Option<crate::manifest::Manifest>,
+    /// Rows dropped as duplicates, see
+    /// [`ExcelWriter::dedupe_by`](crate::ExcelWriter::dedupe_by)
+    pub duplicates_dropped: u64,
+    /// Buffer high-water marks, when enabled with
+    /// [`ExcelWriter::set_memory_instrumentation`](crate::ExcelWriter::set_memory_instrumentation)
+    pub memory: Option<MemoryReport>,
+}
+
+impl WriteReport {
+    /// Total rows written across all sheets
+    pub fn total_rows(&self) -> u64 {
+        self.per_sheet_rows
+            .iter()
+            .map(|(_, rows)| *rows as u64)
+            .sum()
+    }
+
+    /// Total cells masked across all columns
+    pub fn total_masked_cells(&self) -> u64 {
+        self.masked_cells.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Compression ratio (uncompressed / compressed), or 0.0 for an empty file
+    pub fn compression_ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            0.0
+        } else {
+            self.uncompressed_bytes as f64 / self.compressed_bytes as f64
+        }
     }
 }
 
-/// Styled cell value (combines value with formatting)
-#[derive(Debug, Clone)]
-pub struct StyledCell {
-    /// The cell value
-    pub value: CellValue,
-    /// The cell style
-    pub style: CellStyle,
+/// Peak memory held by a writer, in [`WriteReport::memory`]
+///
+/// Buffer sizes are allocated capacities, in bytes. They cover the
+/// writer's own buffers; the compressor state inside the ZIP library and
+/// the caller's allocations are only seen in the process-wide
+/// `peak_rss_bytes`. Only the local writers are instrumented; the cloud
+/// writers have no report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryReport {
+    /// Largest row XML buffer
+    pub peak_row_buffer: usize,
+    /// Largest batch of rows waiting for the compressor, see
+    /// [`FlushPolicy`](crate::fast_writer::FlushPolicy)
+    pub peak_batch_buffer: usize,
+    /// Peak of the row buffer and batch together
+    pub peak_buffers: usize,
+    /// Shared string table at close
+    pub shared_strings_bytes: usize,
+    /// Cell formats registered
+    pub styles: u32,
+    /// ZIP entries written
+    pub zip_entries: usize,
+    /// Estimated size of the central directory the ZIP writer holds until
+    /// close, one record per entry
+    pub zip_directory_bytes: usize,
+    /// Largest entry, uncompressed; entries are streamed, so this is
+    /// output size rather than memory
+    pub largest_entry_bytes: u64,
+    /// Peak resident set size of the whole process at close (`VmHWM`, on
+    /// Linux; None elsewhere)
+    pub peak_rss_bytes: Option<u64>,
 }
 
-impl StyledCell {
-    /// Create a new styled cell
-    pub fn new(value: CellValue, style: CellStyle) -> Self {
-        StyledCell { value, style }
-    }
+/// Options for the table-of-contents sheet written by
+/// [`ExcelWriter::generate_index_sheet`](crate::ExcelWriter::generate_index_sheet)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSheetOptions {
+    /// Name of the index sheet (default: "Index"); made unique if a data
+    /// sheet already uses it
+    pub sheet_name: String,
+    /// Add a column with each sheet's row count (default: true)
+    pub row_counts: bool,
+    /// Rows at the top of every sheet left out of the count, e.g. 1 for a
+    /// header row (default: 0)
+    pub header_rows: u32,
+}
 
-    /// Create a cell with default style
-    pub fn default_style(value: CellValue) -> Self {
-        StyledCell {
-            value,
-            style: CellStyle::Default,
+impl Default for IndexSheetOptions {
+    fn default() -> Self {
+        IndexSheetOptions {
+            sheet_name: "Index".to_string(),
+            row_counts: true,
+            header_rows: 0,
         }
     }
 }
 
-impl From<CellValue> for StyledCell {
-    fn from(value: CellValue) -> Self {
-        StyledCell::default_style(value)
+impl IndexSheetOptions {
+    /// Create options with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the index sheet's name
+    pub fn with_sheet_name(mut self, name: &str) -> Self {
+        self.sheet_name = name.to_string();
+        self
+    }
+
+    /// Include or leave out the row count column
+    pub fn with_row_counts(mut self, enabled: bool) -> Self {
+        self.row_counts = enabled;
+        self
+    }
+
+    /// Leave header rows out of the row counts
+    pub fn with_header_rows(mut self, rows: u32) -> Self {
+        self.header_rows = rows;
+        self
     }
 }
 
-/// Represents a single cell value in an Excel worksheet
+/// A column declared by
+/// [`ExcelWriter::write_table_header`](crate::ExcelWriter::write_table_header):
+/// its header text, width, header style and the default style of its cells
 #[derive(Debug, Clone, PartialEq)]
-pub enum CellValue {
-    /// Empty cell
-    Empty,
-    /// String value
-    String(String),
-    /// Integer value
-    Int(i64),
-    /// Float value
-    Float(f64),
-    /// Boolean value
-    Bool(bool),
-    /// DateTime value (Excel serial date number)
-    DateTime(f64),
-    /// Error value
-    Error(String),
-    /// Formula value (e.g., "=SUM(A1:A10)")
-    /// The formula should start with '=' and use Excel formula syntax
-    Formula(String),
+pub struct Column {
+    /// Header text
+    pub name: String,
+    /// Width in Excel units; None keeps the default width
+    pub width: Option<f64>,
+    /// Style of the header cell (default: [`CellStyle::HeaderBold`])
+    pub header_style: CellStyle,
+    /// Style of cells written in the column without an explicit style
+    /// (default: [`CellStyle::Default`])
+    pub style: CellStyle,
+    /// Number format code of those cells, replacing the format of `style`
+    pub format: Option<String>,
 }
 
-impl CellValue {
-    /// Convert cell value to string
-    pub fn as_string(&self) -> String {
-        match self {
-            CellValue::Empty => String::new(),
-            CellValue::String(s) => s.clone(),
-            CellValue::Int(i) => i.to_string(),
-            CellValue::Float(f) => f.to_string(),
-            CellValue::Bool(b) => b.to_string(),
-            CellValue::DateTime(d) => d.to_string(),
-            CellValue::Error(e) => format!("ERROR: {}", e),
-            CellValue::Formula(f) => f.clone(),
+impl Column {
+    /// Create a column with a bold header and default cells
+    pub fn new(name: &str) -> Self {
+        Column {
+            name: name.to_string(),
+            width: None,
+            header_style: CellStyle::HeaderBold,
+            style: CellStyle::Default,
+            format: None,
         }
     }
 
-    /// Check if cell is empty
-    pub fn is_empty(&self) -> bool {
-        matches!(self, CellValue::Empty)
+    /// Set the column width
+    pub fn width(mut self, width: f64) -> Self {
+        self.width = Some(width);
+        self
     }
 
-    /// Try to convert to integer
-    pub fn as_i64(&self) -> Option<i64> {
-        match self {
-            CellValue::Int(i) => Some(*i),
-            CellValue::Float(f) => Some(*f as i64),
-            CellValue::String(s) => s.parse().ok(),
-            _ => None,
-        }
+    /// Set the header cell's style
+    pub fn header_style(mut self, style: CellStyle) -> Self {
+        self.header_style = style;
+        self
     }
 
-    /// Try to convert to float
-    pub fn as_f64(&self) -> Option<f64> {
-        match self {
-            CellValue::Float(f) => Some(*f),
-            CellValue::Int(i) => Some(*i as f64),
-            CellValue::DateTime(d) => Some(*d),
-            CellValue::String(s) => s.parse().ok(),
-            _ => None,
-        }
+    /// Set the default style of the column's cells
+    pub fn style(mut self, style: CellStyle) -> Self {
+        self.style = style;
+        self
     }
 
-    /// Try to convert to boolean
-    pub fn as_bool(&self) -> Option<bool> {
-        match self {
-            CellValue::Bool(b) => Some(*b),
-            CellValue::Int(i) => Some(*i != 0),
-            CellValue::String(s) => match s.to_lowercase().as_str() {
-                "true" | "yes" | "1" => Some(true),
-                "false" | "no" | "0" => Some(false),
-                _ => None,
-            },
-            _ => None,
-        }
+    /// Set the number format code of the column's cells, e.g. `"#,##0.00"`
+    pub fn fmt(mut self, code: &str) -> Self {
+        self.format = Some(code.to_string());
+        self
     }
 }
 
-impl fmt::Display for CellValue {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_string())
-    }
+/// Order and alignment of a saved workbook's ZIP entries, see
+/// [`ExcelWriter::set_zip_layout`](crate::ExcelWriter::set_zip_layout)
+///
+/// Worksheets stream out as they are written, so by default the package
+/// metadata (`[Content_Types].xml`, `xl/workbook.xml` and the rest) comes
+/// after them. Readers that fetch the file with range requests or parse it
+/// while it downloads need the metadata first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ZipLayout {
+    /// Move the content types, relationships, workbook, styles and shared
+    /// strings in front of the worksheets (default: false)
+    pub metadata_first: bool,
+    /// Start each entry's data at a multiple of this many bytes, e.g. 4096
+    /// for page-aligned ranged reads (default: 0, unaligned)
+    pub alignment: u16,
 }
 
-impl From<&str> for CellValue {
-    fn from(s: &str) -> Self {
-        CellValue::String(s.to_string())
+impl ZipLayout {
+    /// Layout as written: worksheets first, no alignment
+    pub fn new() -> Self {
+        Self::default()
     }
-}
 
-impl From<String> for CellValue {
-    fn from(s: String) -> Self {
-        CellValue::String(s)
+    /// Put the package metadata first or not
+    pub fn with_metadata_first(mut self, enabled: bool) -> Self {
+        self.metadata_first = enabled;
+        self
     }
-}
 
-impl From<i64> for CellValue {
-    fn from(i: i64) -> Self {
-        CellValue::Int(i)
+    /// Align entry data to `bytes` (0 or 1 = unaligned)
+    pub fn with_alignment(mut self, bytes: u16) -> Self {
+        self.alignment = bytes;
+        self
+    }
+
+    /// Whether the archive is written as it streams out, without a rewrite
+    pub fn is_streamed(&self) -> bool {
+        !self.metadata_first && self.alignment <= 1
     }
 }
 
-impl From<f64> for CellValue {
-    fn from(f: f64) -> Self {
-        CellValue::Float(f)
+/// Options for the provenance sheet written by
+/// [`ExcelWriter::set_audit`](crate::ExcelWriter::set_audit)
+///
+/// The sheet lists each data sheet's row and cell counts and schema hash
+/// (SHA-256 of its first row as written, so files with the same header
+/// share it), then when and where the file was generated, how long it took
+/// and the library version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditOptions {
+    /// Name of the audit sheet (default: "_audit"); made unique if a data
+    /// sheet already uses it
+    pub sheet_name: String,
+    /// Tab visibility of the audit sheet (default: hidden)
+    pub visibility: SheetVisibility,
+    /// Producing host (default: the `HOSTNAME` or `COMPUTERNAME`
+    /// environment variable, or `/etc/hostname`)
+    pub host: Option<String>,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        AuditOptions {
+            sheet_name: "_audit".to_string(),
+            visibility: SheetVisibility::Hidden,
+            host: None,
+        }
     }
 }
 
-impl From<bool> for CellValue {
-    fn from(b: bool) -> Self {
-        CellValue::Bool(b)
+impl AuditOptions {
+    /// Create options with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the audit sheet's name
+    pub fn with_sheet_name(mut self, name: &str) -> Self {
+        self.sheet_name = name.to_string();
+        self
+    }
+
+    /// Show, hide or very-hide the audit sheet
+    pub fn with_visibility(mut self, visibility: SheetVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Record `host` as the producing host instead of detecting it
+    pub fn with_host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_string());
+        self
     }
 }
 
-/// Represents a cell with its position
-#[derive(Debug, Clone)]
-pub struct Cell {
-    /// Row index (0-based)
-    pub row: u32,
-    /// Column index (0-based)
-    pub col: u32,
-    /// Cell value
-    pub value: CellValue,
+/// Whether a sheet's tab is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SheetVisibility {
+    /// Tab shown (default)
+    #[default]
+    Visible,
+    /// Tab hidden, the user can unhide it
+    Hidden,
+    /// Tab hidden and not listed in Excel's Unhide dialog
+    VeryHidden,
 }
 
-impl Cell {
-    /// Create a new cell
-    pub fn new(row: u32, col: u32, value: CellValue) -> Self {
-        Cell { row, col, value }
+impl SheetVisibility {
+    /// `state` attribute value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SheetVisibility::Visible => "visible",
+            SheetVisibility::Hidden => "hidden",
+            SheetVisibility::VeryHidden => "veryHidden",
+        }
     }
 
-    /// Get Excel-style cell reference (e.g., "A1", "B2")
-    pub fn reference(&self) -> String {
-        format!("{}{}", Self::col_to_letter(self.col), self.row + 1)
+    /// Parse a `state` attribute value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "visible" => Some(SheetVisibility::Visible),
+            "hidden" => Some(SheetVisibility::Hidden),
+            "veryHidden" => Some(SheetVisibility::VeryHidden),
+            _ => None,
+        }
     }
+}
 
-    /// Convert column index to Excel letter (0 -> A, 25 -> Z, 26 -> AA)
-    fn col_to_letter(col: u32) -> String {
-        let mut result = String::new();
-        let mut col = col + 1;
+/// When Excel recalculates formulas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CalcMode {
+    /// Recalculate on every change (Excel's default)
+    #[default]
+    Automatic,
+    /// Like automatic, but data tables only on request
+    AutomaticExceptTables,
+    /// Only when the user asks (F9)
+    Manual,
+}
 
-        while col > 0 {
-            col -= 1;
-            result.insert(0, (b'A' + (col % 26) as u8) as char);
-            col /= 26;
+impl CalcMode {
+    /// `calcMode` attribute value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalcMode::Automatic => "auto",
+            CalcMode::AutomaticExceptTables => "autoNoTable",
+            CalcMode::Manual => "manual",
         }
+    }
 
-        result
+    /// Parse a `calcMode` attribute value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(CalcMode::Automatic),
+            "autoNoTable" => Some(CalcMode::AutomaticExceptTables),
+            "manual" => Some(CalcMode::Manual),
+            _ => None,
+        }
     }
 }
 
-/// Represents a row of cells
-#[derive(Debug, Clone)]
-pub struct Row {
-    /// Row index (0-based)
-    pub index: u32,
-    /// Cells in this row
-    pub cells: Vec<CellValue>,
+/// Workbook calculation settings (`<calcPr>` in workbook.xml)
+///
+/// Defaults match what Excel assumes when the element is missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalcSettings {
+    /// Calculation mode (default: automatic)
+    pub mode: CalcMode,
+    /// Recalculate every formula when the file is opened (default: false).
+    /// Turn off for workbooks with many formulas whose cached values are
+    /// already right, as recalculation can take minutes.
+    pub full_calc_on_load: bool,
+    /// Allow circular references, resolved by iteration (default: false)
+    pub iterate: bool,
+    /// Maximum iterations (default: 100)
+    pub iterate_count: u32,
+    /// Stop iterating once values change by less than this (default: 0.001)
+    pub iterate_delta: f64,
 }
 
-impl Row {
-    /// Create a new row
-    pub fn new(index: u32, cells: Vec<CellValue>) -> Self {
-        Row { index, cells }
+impl Default for CalcSettings {
+    fn default() -> Self {
+        CalcSettings {
+            mode: CalcMode::Automatic,
+            full_calc_on_load: false,
+            iterate: false,
+            iterate_count: 100,
+            iterate_delta: 0.001,
+        }
     }
+}
 
-    /// Get cell at column index
-    pub fn get(&self, col: usize) -> Option<&CellValue> {
-        self.cells.get(col)
+impl CalcSettings {
+    /// Create settings with Excel's defaults
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Get number of cells
-    pub fn len(&self) -> usize {
-        self.cells.len()
+    /// Set the calculation mode
+    pub fn with_mode(mut self, mode: CalcMode) -> Self {
+        self.mode = mode;
+        self
     }
 
-    /// Check if row is empty
-    pub fn is_empty(&self) -> bool {
-        self.cells.is_empty() || self.cells.iter().all(|c| c.is_empty())
+    /// Recalculate all formulas when the file is opened
+    pub fn with_full_calc_on_load(mut self, enabled: bool) -> Self {
+        self.full_calc_on_load = enabled;
+        self
     }
 
-    /// Convert row to vector of strings
-    pub fn to_strings(&self) -> Vec<String> {
-        self.cells.iter().map(|c| c.as_string()).collect()
+    /// Enable iterative calculation with a maximum number of iterations and
+    /// the change below which iteration stops
+    pub fn with_iteration(mut self, count: u32, delta: f64) -> Self {
+        self.iterate = true;
+        self.iterate_count = count;
+        self.iterate_delta = delta;
+        self
+    }
+
+    /// `<calcPr>` element, with only the attributes that differ from the
+    /// defaults
+    pub(crate) fn to_xml(&self) -> String {
+        let defaults = CalcSettings::default();
+        let mut xml = String::from("<calcPr");
+        if self.mode != defaults.mode {
+            xml.push_str(&format!(" calcMode=\"{}\"", self.mode.as_str()));
+        }
+        if self.full_calc_on_load {
+            xml.push_str(" fullCalcOnLoad=\"1\"");
+        }
+        if self.iterate {
+            xml.push_str(" iterate=\"1\"");
+        }
+        if self.iterate_count != defaults.iterate_count {
+            xml.push_str(&format!(" iterateCount=\"{}\"", self.iterate_count));
+        }
+        if self.iterate_delta != defaults.iterate_delta {
+            xml.push_str(&format!(" iterateDelta=\"{}\"", self.iterate_delta));
+        }
+        xml.push_str("/>");
+        xml
     }
 }
 
@@ -402,6 +2525,63 @@ impl ProtectionOptions {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_header_footer_validation() {
+        let image = |position| HeaderFooterImage::new(position, vec![1], "PNG", 10.0, 10.0);
+        let print = HeaderFooter::new()
+            .with_header("&LTitle&R&G")
+            .with_footer("Page &P &&G")
+            .with_image(image(HeaderFooterPosition::RightHeader));
+        assert!(print.validate().is_ok());
+        assert_eq!(print.images[0].content_type(), "image/png");
+        assert_eq!(header_footer_section("Page &P &&G", 'C'), "Page &P G");
+
+        // "&&G" is a literal ampersand, not a picture
+        let print = print.with_image(image(HeaderFooterPosition::CenterFooter));
+        assert!(print.validate().is_err());
+        let long = HeaderFooter::new().with_header(&"x".repeat(256));
+        assert!(long.validate().is_err());
+    }
+
+    #[test]
+    fn test_text_run_color_is_checked() {
+        let mut out = Vec::new();
+        let run = TextRun {
+            color: Some("FF0000\"/><b/><x a=\"".to_string()),
+            ..TextRun::new("red")
+        };
+        run.push_properties(&mut out);
+        assert!(out.is_empty());
+
+        let run = TextRun {
+            italic: true,
+            color: Some("FFC00000".to_string()),
+            ..TextRun::new("red")
+        };
+        run.push_properties(&mut out);
+        assert_eq!(out, b"<rPr><i/><color rgb=\"FFC00000\"/></rPr>");
+    }
+
+    #[test]
+    fn test_float_format() {
+        let default = FloatFormat::default();
+        assert_eq!(default.format(1234.56), "1234.56");
+        assert_eq!(default.format(-0.000001), "-0.000001");
+        assert_eq!(default.format(1.5e-8), "1.5E-8");
+        assert_eq!(default.format(-2.5e300), "-2.5E+300");
+        assert_eq!(default.format(1e20), "100000000000000000000");
+        assert_eq!(default.format(0.0), "0");
+
+        let rounded = FloatFormat::default().significant_digits(3);
+        assert_eq!(rounded.format(1234.56), "1230");
+        assert_eq!(rounded.format(0.012345), "0.0123");
+        assert_eq!(FloatFormat::EXCEL.format(1.0 / 3.0), "0.333333333333333");
+        assert_eq!(
+            FloatFormat::EXCEL.format(123456789.12345678),
+            "123456789.123457"
+        );
+    }
+
     #[test]
     fn test_cell_reference() {
         let cell = Cell::new(0, 0, CellValue::Empty);
@@ -414,6 +2594,48 @@ mod tests {
         assert_eq!(cell.reference(), "AA1");
     }
 
+    #[test]
+    fn test_cell_range() {
+        let range: CellRange = "B2:D4".parse().unwrap();
+        assert_eq!(range, CellRange::new(1, 1, 3, 3));
+        assert!(range.contains(2, 3));
+        assert!(!range.contains(0, 1));
+        assert_eq!(range.to_string(), "B2:D4");
+
+        assert_eq!("$AA$10".parse::<CellRange>().unwrap().first_col, 26);
+        assert!("A0:B1".parse::<CellRange>().is_err());
+        assert!("1A".parse::<CellRange>().is_err());
+
+        assert_eq!((range.rows(), range.cols(), range.cell_count()), (3, 3, 9));
+        assert_eq!(range.offset(-1, 2), Some(CellRange::new(0, 3, 2, 5)));
+        assert_eq!(range.offset(-2, 0), None);
+        let other: CellRange = "C3:F10".parse().unwrap();
+        assert_eq!(range.intersection(&other), Some(CellRange::new(2, 2, 3, 3)));
+        assert_eq!(range.intersection(&"A10".parse().unwrap()), None);
+        assert_eq!(range.union(&other).to_string(), "B2:F10");
+        assert_eq!(range.to_absolute(), "$B$2:$D$4");
+    }
+
+    #[test]
+    fn test_defined_name_range() {
+        let name = DefinedName {
+            name: "Prices".to_string(),
+            refers_to: "'Q1 ''24'!$B$2:$D$9".to_string(),
+            scope: None,
+            hidden: false,
+        };
+        assert_eq!(
+            name.range(),
+            Some(("Q1 '24".to_string(), CellRange::new(1, 1, 8, 3)))
+        );
+
+        let formula = DefinedName {
+            refers_to: "Sheet1!$A$1,Sheet1!$C$1".to_string(),
+            ..name
+        };
+        assert_eq!(formula.range(), None);
+    }
+
     #[test]
     fn test_cell_value_conversions() {
         let val = CellValue::Int(42);
@@ -423,4 +2645,115 @@ mod tests {
         let val = CellValue::String("true".to_string());
         assert_eq!(val.as_bool(), Some(true));
     }
+
+    #[test]
+    fn test_from_rust_types() {
+        use chrono::NaiveDate;
+
+        assert_eq!(CellValue::from(7i32), CellValue::Int(7));
+        assert_eq!(CellValue::from(7u32), CellValue::Int(7));
+        assert_eq!(CellValue::from(Some("x")), CellValue::from("x"));
+        assert_eq!(CellValue::from(None::<f64>), CellValue::Empty);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(CellValue::from(date), CellValue::DateTime(45322.0));
+        let noon = date.and_hms_opt(12, 0, 0).unwrap();
+        assert_eq!(CellValue::from(noon), CellValue::DateTime(45322.5));
+
+        let cells = crate::row!["a", String::from("b"), 1, 2.5, false, Some(date)];
+        assert_eq!(
+            cells,
+            vec![
+                CellValue::String("a".to_string()),
+                CellValue::String("b".to_string()),
+                CellValue::Int(1),
+                CellValue::Float(2.5),
+                CellValue::Bool(false),
+                CellValue::DateTime(45322.0),
+            ]
+        );
+        assert!(crate::row![].is_empty());
+    }
+
+    #[test]
+    fn test_accessors_and_comparisons() {
+        use chrono::NaiveDate;
+
+        let name = CellValue::from("Alice");
+        assert_eq!(name.as_str(), Some("Alice"));
+        assert_eq!(CellValue::Int(1).as_str(), None);
+        assert!(name == "Alice");
+        assert!(CellValue::Int(3) == 3i64);
+        assert!(CellValue::Float(3.0) != 3i64);
+        assert!(CellValue::Bool(true) == true);
+
+        let noon = NaiveDate::from_ymd_opt(2024, 1, 31)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(CellValue::DateTime(45322.5).as_date(), Some(noon));
+        assert_eq!(CellValue::Float(45322.5).as_date(), Some(noon));
+        assert_eq!(CellValue::from("2024-01-31T12:00:00").as_date(), Some(noon));
+        assert_eq!(
+            CellValue::from("2024-01-31").as_date(),
+            Some(noon - chrono::Duration::hours(12))
+        );
+        assert_eq!(CellValue::from("soon").as_date(), None);
+        assert_eq!(CellValue::Float(f64::NAN).as_date(), None);
+
+        let row = Row::new(0, vec![name.clone()]);
+        assert_eq!(row, Row::new(0, vec![CellValue::from("Alice")]));
+        assert_eq!(
+            row.get_by_header(&["Name", "Age"], "Age"),
+            Some(&CellValue::Empty)
+        );
+    }
+
+    #[test]
+    fn test_decimal_display_and_parse() {
+        assert_eq!(Decimal::new(1999, 2).to_string(), "19.99");
+        assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Decimal::new(42, 0).to_string(), "42");
+
+        assert_eq!("0.30".parse::<Decimal>().unwrap(), Decimal::new(30, 2));
+        assert_eq!("-12.5".parse::<Decimal>().unwrap(), Decimal::new(-125, 1));
+        assert_eq!("1.25E-3".parse::<Decimal>().unwrap(), Decimal::new(125, 5));
+        assert_eq!("1.5E2".parse::<Decimal>().unwrap(), Decimal::new(150, 0));
+        assert!("12a".parse::<Decimal>().is_err());
+        assert!("".parse::<Decimal>().is_err());
+
+        assert_eq!(
+            "0E2147483647".parse::<Decimal>().unwrap(),
+            Decimal::new(0, 0)
+        );
+        assert_eq!("0.00E-2147483648".parse::<Decimal>().unwrap().scale(), 28);
+        assert!("1E2147483647".parse::<Decimal>().is_err());
+        assert!("1E39".parse::<Decimal>().is_err());
+        assert_eq!(
+            "1E38".parse::<Decimal>().unwrap().mantissa(),
+            10i128.pow(38)
+        );
+    }
+
+    #[test]
+    fn test_decimal_equality_ignores_scale() {
+        use std::collections::HashSet;
+
+        let a: Decimal = "0.30".parse().unwrap();
+        let b: Decimal = "0.3".parse().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), "0.30");
+        assert_eq!(Decimal::new(150, 0), Decimal::new(15000, 2));
+        assert_ne!(Decimal::new(3, 1), Decimal::new(3, 2));
+        assert_eq!(Decimal::new(0, 5), Decimal::new(0, 0));
+        assert_eq!(HashSet::from([a, b, Decimal::new(300, 3)]).len(), 1);
+    }
+
+    #[test]
+    fn test_decimal_cell_value() {
+        let val = CellValue::Decimal(Decimal::new(30, 2));
+        assert_eq!(val.as_string(), "0.30");
+        assert_eq!(val.as_f64(), Some(0.3));
+        assert_eq!(val.as_i64(), Some(0));
+    }
 }
@@ -0,0 +1,195 @@
+//! Read untrusted files, such as user uploads, without panicking
+//!
+//! [`parse_untrusted`] reads every sheet of a workbook into memory under
+//! [`UntrustedLimits`], and reports anything wrong with the file as an
+//! [`ExcelError`] rather than a panic. The reader's own parsing is
+//! written not to panic on malformed XML; a panic from a dependency (the
+//! ZIP layer) is caught as well and turned into
+//! [`ExcelError::InvalidFormat`]. The process-wide panic hook still runs
+//! for such a panic, and nothing can be caught when the crate is built with
+//! `panic = "abort"`.
+//!
+//! Values are counted against a budget, since a few bytes of XML
+//! can expand into many values (a cell at column XFD pads its row with
+//! 16,383 empty cells, a row numbered 1,048,576 is preceded by as many
+//! blank rows).
+//!
+//! The `fuzz` directory holds a `cargo fuzz` target running this entry
+//! point on arbitrary bytes.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::untrusted::{parse_untrusted, UntrustedLimits};
+//!
+//! match parse_untrusted("upload.xlsx", UntrustedLimits::default()) {
+//!     Ok(sheets) => {
+//!         for sheet in &sheets {
+//!             println!("{}: {} rows", sheet.name, sheet.rows.len());
+//!         }
+//!     }
+//!     Err(e) => eprintln!("Rejected upload: {}", e),
+//! }
+//! ```
+
+use crate::error::{ExcelError, Result};
+use crate::streaming_reader::{ReadLimits, ReaderOptions, StreamingReader};
+use crate::types::Row;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// Limits for [`parse_untrusted`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntrustedLimits {
+    /// Limits of the underlying reader (entry sizes, shared strings, cell
+    /// text and row sizes)
+    pub read: ReadLimits,
+    /// Max number of worksheets (default: 1,024)
+    pub max_sheets: usize,
+    /// Max values read across all sheets, padding included; a blank row
+    /// counts as one (default: 10 million)
+    pub max_cells: u64,
+}
+
+impl Default for UntrustedLimits {
+    fn default() -> Self {
+        UntrustedLimits {
+            read: ReadLimits::default(),
+            max_sheets: 1024,
+            max_cells: 10_000_000,
+        }
+    }
+}
+
+/// A worksheet read by [`parse_untrusted`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedSheet {
+    /// Sheet name
+    pub name: String,
+    /// Rows in sheet order, blank rows included
+    pub rows: Vec<Row>,
+}
+
+/// Read every sheet of an untrusted file, see [module docs](self)
+pub fn parse_untrusted<P: AsRef<Path>>(
+    path: P,
+    limits: UntrustedLimits,
+) -> Result<Vec<ParsedSheet>> {
+    let path = path.as_ref();
+    panic::catch_unwind(AssertUnwindSafe(|| parse(path, &limits))).unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_string());
+        Err(ExcelError::InvalidFormat(format!(
+            "Malformed workbook '{}': {}",
+            path.display(),
+            reason
+        )))
+    })
+}
+
+fn parse(path: &Path, limits: &UntrustedLimits) -> Result<Vec<ParsedSheet>> {
+    let options = ReaderOptions::new().with_limits(limits.read.clone());
+    let mut reader = StreamingReader::open_with_options(path, options)?;
+    let names = reader.sheet_names();
+    if names.len() > limits.max_sheets {
+        return Err(ExcelError::LimitExceeded {
+            what: "Sheet count".to_string(),
+            limit: limits.max_sheets as u64,
+        });
+    }
+
+    let mut cells = 0u64;
+    let mut sheets = Vec::with_capacity(names.len());
+    for name in names {
+        let mut rows = Vec::new();
+        for row in reader.rows(&name)? {
+            let row = row?;
+            cells += row.cells.len().max(1) as u64;
+            if cells > limits.max_cells {
+                return Err(ExcelError::LimitExceeded {
+                    what: "Cell count".to_string(),
+                    limit: limits.max_cells,
+                });
+            }
+            rows.push(row);
+        }
+        sheets.push(ParsedSheet { name, rows });
+    }
+    Ok(sheets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fast_writer::StreamingZipWriter;
+    use crate::types::CellValue;
+    use std::io::Write;
+
+    // Minimal workbook around one worksheet's XML
+    fn write_xlsx(path: &Path, sheet_xml: &str) {
+        let mut zip = StreamingZipWriter::new(path).unwrap();
+        let parts = [
+            (
+                "xl/workbook.xml",
+                r#"<workbook><sheets><sheet name="Data" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            ),
+            ("xl/worksheets/sheet1.xml", sheet_xml),
+        ];
+        for (name, xml) in parts {
+            zip.start_entry(name).unwrap();
+            zip.write_data(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_parse_untrusted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload.xlsx");
+        write_xlsx(
+            &path,
+            r#"<worksheet><sheetData><row r="4294967295"><c r="A1"><v>1</v></c></row><row><c><v>2</v></c></row><row r="2"/></sheetData></worksheet>"#,
+        );
+        let sheets = parse_untrusted(&path, UntrustedLimits::default()).unwrap();
+        assert_eq!(sheets[0].name, "Data");
+        let rows: Vec<(u32, Vec<CellValue>)> = sheets[0]
+            .rows
+            .iter()
+            .map(|r| (r.index, r.cells.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (0, vec![CellValue::Int(1)]),
+                (1, vec![CellValue::Int(2)]),
+                (1, vec![])
+            ]
+        );
+
+        write_xlsx(
+            &path,
+            r#"<worksheet><sheetData><row r="1"><c r="XFD1"><v>1</v></c></row></sheetData></worksheet>"#,
+        );
+        let limits = UntrustedLimits {
+            max_cells: 1000,
+            ..UntrustedLimits::default()
+        };
+        assert!(matches!(
+            parse_untrusted(&path, limits),
+            Err(ExcelError::LimitExceeded { limit: 1000, .. })
+        ));
+
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(b"PK\x03\x04 not a zip")
+            .unwrap();
+        assert!(parse_untrusted(&path, UntrustedLimits::default()).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Write several sheets at once, in any order
+//!
+//! An [`ExcelWriter`] fills one sheet after the other: once a sheet is
+//! left, no more rows can go to it. [`Workbook`] lays the sheets out up
+//! front and takes rows for any of them at any time, for applications
+//! that produce, say, a summary, a details and an errors sheet from the
+//! same pass over their data.
+//!
+//! The first sheet streams straight into the file like an
+//! [`ExcelWriter`]'s. Rows for the other sheets are held in memory until
+//! they take up more than the
+//! [memory threshold](Workbook::with_memory_threshold) together; the sheet
+//! crossing it then moves its rows to a temporary spool file of its own
+//! and keeps writing there. [`finish`](Workbook::finish) copies the held
+//! rows in after the first sheet, in the order the sheets were added. Small
+//! side sheets never touch the disk, and memory stays bounded however many
+//! rows each sheet gets.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use excelstream::types::CellValue;
+//! use excelstream::workbook::Workbook;
+//!
+//! let mut book = Workbook::new("orders.xlsx");
+//! book.add_sheet("Orders")?;
+//! book.add_sheet("Errors")?;
+//! book.sheet("Orders")?.write_row(["Order", "Amount"])?;
+//! book.sheet("Errors")?.write_row(["Order", "Problem"])?;
+//!
+//! for (id, amount) in [(1, 25.0), (2, -3.0), (3, 12.5)] {
+//!     if amount < 0.0 {
+//!         book.sheet("Errors")?
+//!             .write_row_typed(&[CellValue::Int(id), "negative amount".into()])?;
+//!     } else {
+//!         book.sheet("Orders")?
+//!             .write_row_typed(&[CellValue::Int(id), CellValue::Float(amount)])?;
+//!     }
+//! }
+//! book.finish()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::cellref::MAX_ROWS;
+use crate::error::{ExcelError, Result};
+use crate::sort::{encode_row, next_row};
+use crate::transpose::{ColumnCursor, SpooledColumn};
+use crate::types::{CellStyle, CellValue, StyledCell, WriteReport};
+use crate::writer::{ExcelWriter, ExcelWriterBuilder};
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Write};
+use std::path::{Path, PathBuf};
+
+// Read buffer for a spool while `finish` copies it in
+const READ_BUFFER: usize = 64 * 1024;
+
+// Rows held in memory across the sheets before one is spooled to disk
+const MEMORY_THRESHOLD: usize = 4 * 1024 * 1024;
+
+/// Workbook taking rows for its sheets in any order, see
+/// [module docs](self)
+pub struct Workbook {
+    builder: Option<ExcelWriterBuilder>, // Until the first sheet is added
+    writer: Option<ExcelWriter>,
+    temp_dir: Option<PathBuf>,
+    memory_threshold: usize,
+    held: usize, // Bytes of rows held in memory across the sheets
+    sheets: Vec<SheetSpool>,
+}
+
+// A sheet of the workbook; the first one's spool stays empty
+struct SheetSpool {
+    name: String,
+    spool: Spool,
+    spooled: u64, // Bytes written to the spool
+    values: u64,  // Values in the spool
+    rows: u32,
+}
+
+// Where a sheet's rows wait for `finish`
+enum Spool {
+    Memory(Vec<u8>),
+    File(BufWriter<File>),
+}
+
+impl Workbook {
+    /// Create a workbook at `path`; nothing is written until the first
+    /// sheet is added
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self::from_builder(ExcelWriterBuilder::new(path))
+    }
+
+    /// Create a workbook with the settings of `builder`; its sheet name is
+    /// replaced by the first sheet added
+    pub fn from_builder(builder: ExcelWriterBuilder) -> Self {
+        Workbook {
+            builder: Some(builder),
+            writer: None,
+            temp_dir: None,
+            memory_threshold: MEMORY_THRESHOLD,
+            held: 0,
+            sheets: Vec::new(),
+        }
+    }
+
+    /// Directory for the spool files (default: the system temp directory)
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Bytes of rows the sheets after the first may hold in memory
+    /// together before spooling to disk (default: 4 MiB; 0 spools every
+    /// row)
+    ///
+    /// Rows take about their text length plus a few bytes per cell. Once
+    /// a sheet is spooled, its rows go through a 64 KiB file buffer.
+    pub fn with_memory_threshold(mut self, bytes: usize) -> Self {
+        self.memory_threshold = bytes;
+        self
+    }
+
+    /// Add a sheet after the ones added so far
+    ///
+    /// Sheet names are compared case-insensitively, as in Excel; adding a
+    /// name twice fails with [`ExcelError::InvalidState`].
+    pub fn add_sheet(&mut self, name: &str) -> Result<()> {
+        if self.index_of(name).is_some() {
+            return Err(ExcelError::InvalidState(format!(
+                "Sheet '{}' already exists",
+                name
+            )));
+        }
+        if let Some(builder) = self.builder.take() {
+            self.writer = Some(builder.with_sheet_name(name).build()?);
+        }
+        self.sheets.push(SheetSpool {
+            name: name.to_string(),
+            spool: Spool::Memory(Vec::new()),
+            spooled: 0,
+            values: 0,
+            rows: 0,
+        });
+        Ok(())
+    }
+
+    /// Names of the sheets, in workbook order
+    pub fn sheet_names(&self) -> Vec<&str> {
+        self.sheets.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    /// The sheet called `name`, to write rows to
+    ///
+    /// Fails with [`ExcelError::SheetNotFound`] if no sheet has that name.
+    pub fn sheet(&mut self, name: &str) -> Result<SheetWriter<'_>> {
+        match self.index_of(name) {
+            Some(index) => Ok(SheetWriter { book: self, index }),
+            None => Err(crate::formula::sheet_not_found(name, &self.sheet_names())),
+        }
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.sheets
+            .iter()
+            .position(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    // Write a row to the first sheet, or spool it for a later one
+    fn write(&mut self, index: usize, cells: &[(CellValue, CellStyle)]) -> Result<()> {
+        if index == 0 {
+            return self
+                .writer
+                .as_mut()
+                .ok_or_else(unusable)?
+                .write_row_styled(cells);
+        }
+        let sheet = &mut self.sheets[index];
+        if sheet.rows == MAX_ROWS {
+            return Err(ExcelError::LimitExceeded {
+                what: format!("Row count of sheet '{}'", sheet.name),
+                limit: MAX_ROWS as u64,
+            });
+        }
+        let row: Vec<StyledCell> = cells
+            .iter()
+            .map(|(value, style)| StyledCell::new(value.clone(), *style))
+            .collect();
+        let (bytes, values) = match &mut sheet.spool {
+            Spool::Memory(buf) => {
+                let encoded = encode_row(buf, &row)?;
+                self.held += encoded.0 as usize;
+                encoded
+            }
+            Spool::File(file) => encode_row(file, &row)?,
+        };
+        sheet.spooled += bytes;
+        sheet.values += values;
+        sheet.rows += 1;
+        if self.held > self.memory_threshold {
+            self.spill(index)?;
+        }
+        Ok(())
+    }
+
+    // Move a sheet's rows held in memory to a spool file
+    fn spill(&mut self, index: usize) -> Result<()> {
+        let sheet = &mut self.sheets[index];
+        let Spool::Memory(buf) = &sheet.spool else {
+            return Ok(());
+        };
+        let file = match &self.temp_dir {
+            Some(dir) => tempfile::tempfile_in(dir)?,
+            None => tempfile::tempfile()?,
+        };
+        let mut file = BufWriter::with_capacity(64 * 1024, file);
+        file.write_all(buf)?;
+        self.held -= buf.len();
+        sheet.spool = Spool::File(file);
+        Ok(())
+    }
+
+    /// Copy the spooled sheets in after the first and save the workbook
+    ///
+    /// A workbook without sheets is saved with the builder's default sheet.
+    pub fn finish(mut self) -> Result<WriteReport> {
+        let Some(mut writer) = self.writer.take() else {
+            return self.builder.take().ok_or_else(unusable)?.build()?.save();
+        };
+        for sheet in self.sheets.iter_mut().skip(1) {
+            writer.add_sheet(&sheet.name)?;
+            let spool = std::mem::replace(&mut sheet.spool, Spool::Memory(Vec::new()));
+            match spool {
+                Spool::Memory(buf) => copy_rows(&mut writer, sheet, &mut Cursor::new(buf))?,
+                Spool::File(file) => {
+                    let mut file = file.into_inner().map_err(|e| e.into_error())?;
+                    copy_rows(&mut writer, sheet, &mut file)?
+                }
+            }
+        }
+        writer.save()
+    }
+}
+
+// Write a sheet's held rows, read back from its spool
+fn copy_rows<R: std::io::Read + std::io::Seek>(
+    writer: &mut ExcelWriter,
+    sheet: &SheetSpool,
+    spool: &mut R,
+) -> Result<()> {
+    let mut cursor = ColumnCursor::new(
+        &SpooledColumn {
+            offset: 0,
+            len: sheet.spooled,
+            rows: sheet.values,
+        },
+        READ_BUFFER,
+    );
+    let mut left = sheet.values;
+    while let Some(row) = next_row(&mut cursor, &mut left, spool)? {
+        let cells: Vec<(CellValue, CellStyle)> =
+            row.into_iter().map(|c| (c.value, c.style)).collect();
+        writer.write_row_styled(&cells)?;
+    }
+    Ok(())
+}
+
+// The writer failed to build when the first sheet was added
+fn unusable() -> ExcelError {
+    ExcelError::InvalidState("Workbook could not be created".to_string())
+}
+
+/// A sheet of a [`Workbook`], returned by [`Workbook::sheet`]
+pub struct SheetWriter<'a> {
+    book: &'a mut Workbook,
+    index: usize,
+}
+
+impl SheetWriter<'_> {
+    /// Name of the sheet
+    pub fn name(&self) -> &str {
+        &self.book.sheets[self.index].name
+    }
+
+    /// Append a row of text; empty strings are left blank
+    pub fn write_row<I, S>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let cells: Vec<(CellValue, CellStyle)> = values
+            .into_iter()
+            .map(|v| match v.as_ref() {
+                "" => (CellValue::Empty, CellStyle::Default),
+                v => (CellValue::String(v.to_string()), CellStyle::Default),
+            })
+            .collect();
+        self.book.write(self.index, &cells)
+    }
+
+    /// Append a row of typed values
+    pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
+        let cells: Vec<(CellValue, CellStyle)> = cells
+            .iter()
+            .map(|value| (value.clone(), CellStyle::Default))
+            .collect();
+        self.book.write(self.index, &cells)
+    }
+
+    /// Append a row of styled cells
+    pub fn write_row_styled(&mut self, cells: &[(CellValue, CellStyle)]) -> Result<()> {
+        self.book.write(self.index, cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming_reader::StreamingReader;
+
+    #[test]
+    fn test_interleaved_sheets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.xlsx");
+        let mut book = Workbook::new(&path).with_temp_dir(dir.path());
+        for name in ["Summary", "Details", "Errors"] {
+            book.add_sheet(name).unwrap();
+        }
+        assert!(matches!(
+            book.add_sheet("details"),
+            Err(ExcelError::InvalidState(_))
+        ));
+        assert!(matches!(
+            book.sheet("Missing"),
+            Err(ExcelError::SheetNotFound { .. })
+        ));
+
+        book.sheet("Details")
+            .unwrap()
+            .write_row(["id", "", "note"])
+            .unwrap();
+        book.sheet("Summary").unwrap().write_row(["total"]).unwrap();
+        for i in 1..=3 {
+            book.sheet("details")
+                .unwrap()
+                .write_row_typed(&[CellValue::Int(i)])
+                .unwrap();
+        }
+        book.sheet("Summary")
+            .unwrap()
+            .write_row_styled(&[(CellValue::Float(2.5), CellStyle::NumberDecimal)])
+            .unwrap();
+        assert_eq!(book.sheet("ERRORS").unwrap().name(), "Errors");
+        book.finish().unwrap();
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        assert_eq!(reader.sheet_names(), ["Summary", "Details", "Errors"]);
+        let mut rows = |sheet: &str| -> Vec<Vec<CellValue>> {
+            reader
+                .rows(sheet)
+                .unwrap()
+                .map(|r| r.unwrap().cells)
+                .collect()
+        };
+        assert_eq!(
+            rows("Summary"),
+            [vec![CellValue::from("total")], vec![CellValue::Float(2.5)]]
+        );
+        assert_eq!(
+            rows("Details"),
+            [
+                vec!["id".into(), CellValue::Empty, "note".into()],
+                vec![CellValue::Int(1)],
+                vec![CellValue::Int(2)],
+                vec![CellValue::Int(3)],
+            ]
+        );
+        assert!(rows("Errors").is_empty());
+    }
+
+    #[test]
+    fn test_memory_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.xlsx");
+        let mut book = Workbook::new(&path)
+            .with_temp_dir(dir.path())
+            .with_memory_threshold(1000);
+        for name in ["Main", "Small", "Large"] {
+            book.add_sheet(name).unwrap();
+        }
+        book.sheet("Small").unwrap().write_row(["note"]).unwrap();
+        for i in 0..100 {
+            book.sheet("Large")
+                .unwrap()
+                .write_row_typed(&[CellValue::Int(i), "row".into()])
+                .unwrap();
+        }
+        assert!(matches!(book.sheets[1].spool, Spool::Memory(_)));
+        assert!(matches!(book.sheets[2].spool, Spool::File(_)));
+        assert!(book.held <= 1000);
+        book.finish().unwrap();
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        assert_eq!(reader.rows("Small").unwrap().count(), 1);
+        let large: Vec<Vec<CellValue>> = reader
+            .rows("Large")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(large.len(), 100);
+        assert_eq!(large[99], [CellValue::Int(99), CellValue::from("row")]);
+    }
+}
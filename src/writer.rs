@@ -3,10 +3,68 @@
 //! **Breaking Change in v0.2.0:** ExcelWriter now uses streaming with constant memory usage.
 //! Data is written directly to disk as you call write_row(), not kept in memory.
 
+use crate::dedupe::{Deduper, Keep, Verdict};
 use crate::error::Result;
-use crate::fast_writer::UltraLowMemoryWorkbook;
-use crate::types::{CellStyle, CellValue};
-use std::path::Path;
+use crate::fast_writer::{FlushPolicy, MemoryProfile, UltraLowMemoryWorkbook};
+use crate::formula::DeferredRef;
+use crate::manifest::{Manifest, ManifestOptions};
+use crate::middleware::{Masker, RowMiddleware};
+use crate::numfmt::Locale;
+use crate::signature::Signer;
+use crate::sink::{Sink, WrapSink};
+use crate::sort::{ExternalSorter, SortOptions};
+use crate::staging;
+use crate::throttle::Throttle;
+use crate::types::{
+    AuditOptions, CalcSettings, CellStyle, CellValue, Column, FloatFormat, IndexSheetOptions,
+    LimitPolicy, SheetVisibility, StyledCell, TimeZonePolicy, WriteReport, ZipLayout,
+};
+use std::path::{Path, PathBuf};
+
+/// Common interface of the XLSX writer backends
+///
+/// Implemented by [`ExcelWriter`], [`UltraLowMemoryWorkbook`] and
+/// [`ZeroTempWorkbook`](crate::fast_writer::ZeroTempWorkbook), so application
+/// code and tests can be generic over the backend. `ExcelWriter` starts with a
+/// "Sheet1" worksheet; the `fast_writer` workbooks need `add_sheet` before the
+/// first row.
+///
+/// # Examples
+///
+/// ```no_run
+/// use excelstream::writer::{ExcelWriter, SpreadsheetWriter};
+/// use excelstream::types::CellValue;
+///
+/// fn export<W: SpreadsheetWriter>(mut writer: W) -> excelstream::Result<()> {
+///     writer.add_sheet("Report")?;
+///     writer.write_row(["Name", "Score"])?;
+///     writer.write_row_typed(&[CellValue::String("Alice".into()), CellValue::Int(95)])?;
+///     writer.close()?;
+///     Ok(())
+/// }
+///
+/// export(ExcelWriter::new("report.xlsx")?)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait SpreadsheetWriter {
+    /// Start a new worksheet; following rows are written to it
+    fn add_sheet(&mut self, name: &str) -> Result<()>;
+
+    /// Write a row of text cells
+    fn write_row<I, S>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>;
+
+    /// Write a row of typed cells with the default style
+    fn write_row_typed(&mut self, values: &[CellValue]) -> Result<()>;
+
+    /// Write a row of styled cells
+    fn write_row_styled(&mut self, cells: &[StyledCell]) -> Result<()>;
+
+    /// Finish the file and report what was written
+    fn close(self) -> Result<WriteReport>;
+}
 
 /// Excel file writer with streaming capabilities
 ///
@@ -34,6 +92,74 @@ pub struct ExcelWriter {
     inner: UltraLowMemoryWorkbook,
     current_sheet_name: String,
     current_row: u32,
+    middleware: Vec<Box<dyn RowMiddleware>>,
+    masks: Vec<(usize, Masker, u64)>, // (column, masker, cells masked)
+    manifest: Option<ManifestOptions>,
+    // Styles replacing DateDefault and DateTimestamp, set by `set_locale`
+    date_styles: Option<(CellStyle, CellStyle)>,
+    deferred_refs: Vec<DeferredRef>, // Sheets formulas expect to be added
+    sheet_hooks: Vec<SheetHook>,
+    sorter: Option<ExternalSorter>, // Rows of the current sheet held back for sorting
+    deduper: Option<Deduper>,
+    duplicates_dropped: u64,
+}
+
+type SheetHook = Box<dyn FnMut(&mut SheetContext<'_>) -> Result<()> + Send>;
+
+/// The sheet being finished, handed to
+/// [`ExcelWriter::on_sheet_finish`] hooks
+///
+/// Counts include rows written by earlier hooks. Rows written here skip the
+/// middleware and column masks.
+pub struct SheetContext<'a> {
+    writer: &'a mut ExcelWriter,
+}
+
+impl SheetContext<'_> {
+    /// Name of the sheet
+    pub fn name(&self) -> &str {
+        &self.writer.current_sheet_name
+    }
+
+    /// Rows written to the sheet so far
+    pub fn rows(&self) -> u32 {
+        self.writer.current_row
+    }
+
+    /// Cell count of the sheet's widest row
+    pub fn columns(&self) -> u32 {
+        self.writer.inner.sheet_extent().1
+    }
+
+    /// A1 range from A1 to the last written cell, or None for an empty sheet
+    pub fn used_range(&self) -> Option<String> {
+        let (rows, cols) = (self.rows(), self.columns());
+        (rows > 0 && cols > 0).then(|| format!("A1:{}", crate::cellref::to_a1(rows - 1, cols - 1)))
+    }
+
+    /// Append a row of typed cells with the default style
+    pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
+        let cells: Vec<StyledCell> = cells
+            .iter()
+            .map(|cell| StyledCell::new(cell.clone(), CellStyle::Default))
+            .collect();
+        self.writer.write_styled_cells(&cells)
+    }
+
+    /// Append a row of styled cells
+    pub fn write_row_styled(&mut self, cells: &[(CellValue, CellStyle)]) -> Result<()> {
+        let cells: Vec<StyledCell> = cells
+            .iter()
+            .map(|(value, style)| StyledCell::new(value.clone(), *style))
+            .collect();
+        self.writer.write_styled_cells(&cells)
+    }
+
+    /// Put an autofilter on `range` of the sheet, see
+    /// [`ExcelWriter::set_auto_filter`]
+    pub fn set_auto_filter(&mut self, range: &str) -> Result<()> {
+        self.writer.set_auto_filter(range)
+    }
 }
 
 impl ExcelWriter {
@@ -56,6 +182,15 @@ impl ExcelWriter {
             inner,
             current_sheet_name: "Sheet1".to_string(),
             current_row: 0,
+            middleware: Vec::new(),
+            masks: Vec::new(),
+            manifest: None,
+            date_styles: None,
+            deferred_refs: Vec::new(),
+            sheet_hooks: Vec::new(),
+            sorter: None,
+            deduper: None,
+            duplicates_dropped: 0,
         })
     }
 
@@ -87,6 +222,15 @@ impl ExcelWriter {
             inner,
             current_sheet_name: "Sheet1".to_string(),
             current_row: 0,
+            middleware: Vec::new(),
+            masks: Vec::new(),
+            manifest: None,
+            date_styles: None,
+            deferred_refs: Vec::new(),
+            sheet_hooks: Vec::new(),
+            sorter: None,
+            deduper: None,
+            duplicates_dropped: 0,
         })
     }
 
@@ -143,6 +287,16 @@ impl ExcelWriter {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
+        if self.has_row_pipeline() {
+            let cells: Vec<CellValue> = data
+                .into_iter()
+                .map(|v| match v.as_ref() {
+                    "" => CellValue::Empty,
+                    v => CellValue::String(v.to_string()),
+                })
+                .collect();
+            return self.write_row_typed(&cells);
+        }
         self.inner.write_row(data)?;
         self.current_row += 1;
         Ok(())
@@ -205,17 +359,17 @@ impl ExcelWriter {
     /// writer.save().unwrap();
     /// ```
     pub fn write_row_typed(&mut self, cells: &[CellValue]) -> Result<()> {
-        use crate::types::StyledCell;
+        let Some(cells) = self.apply_middleware(cells.to_vec()) else {
+            return Ok(());
+        };
 
         // Convert CellValue to StyledCell with default style to preserve types
         let styled_cells: Vec<StyledCell> = cells
-            .iter()
-            .map(|cell| StyledCell::new(cell.clone(), CellStyle::Default))
+            .into_iter()
+            .map(|cell| StyledCell::new(cell, CellStyle::Default))
             .collect();
 
-        self.inner.write_row_styled(&styled_cells)?;
-        self.current_row += 1;
-        Ok(())
+        self.write_styled_cells(&styled_cells)
     }
 
     /// Write a row with styled cells
@@ -235,18 +389,305 @@ impl ExcelWriter {
     /// writer.save().unwrap();
     /// ```
     pub fn write_row_styled(&mut self, cells: &[(CellValue, CellStyle)]) -> Result<()> {
-        use crate::types::StyledCell;
+        let values = cells.iter().map(|(value, _)| value.clone()).collect();
+        let Some(values) = self.apply_middleware(values) else {
+            return Ok(());
+        };
 
-        let styled_cells: Vec<StyledCell> = cells
-            .iter()
-            .map(|(value, style)| StyledCell::new(value.clone(), *style))
+        // Cells added by middleware get the default style
+        let styled_cells: Vec<StyledCell> = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let style = cells.get(i).map_or(CellStyle::Default, |(_, s)| *s);
+                StyledCell::new(value, style)
+            })
             .collect();
 
-        self.inner.write_row_styled(&styled_cells)?;
+        self.write_styled_cells(&styled_cells)
+    }
+
+    /// Write a row of borrowed values with their styles
+    ///
+    /// Same as [`write_row_styled`](Self::write_row_styled) without cloning
+    /// each value, for rows built from data the caller keeps anyway. Values
+    /// are only copied when middleware has to rewrite the row.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    /// use excelstream::types::{CellValue, CellStyle};
+    ///
+    /// let rows = vec![
+    ///     vec![CellValue::String("Widget".to_string()), CellValue::Float(9.99)],
+    ///     vec![CellValue::String("Gadget".to_string()), CellValue::Float(24.5)],
+    /// ];
+    /// let styles = [CellStyle::Default, CellStyle::NumberCurrency];
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// for row in &rows {
+    ///     writer.write_row_styled_ref(row.iter().zip(styles)).unwrap();
+    /// }
+    /// writer.save().unwrap();
+    /// ```
+    pub fn write_row_styled_ref<'a, I>(&mut self, cells: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (&'a CellValue, CellStyle)>,
+    {
+        if self.has_row_pipeline() {
+            let cells: Vec<(CellValue, CellStyle)> = cells
+                .into_iter()
+                .map(|(value, style)| (value.clone(), style))
+                .collect();
+            return self.write_row_styled(&cells);
+        }
+        let date_styles = self.date_styles;
+        self.inner.write_row_styled_ref(
+            cells
+                .into_iter()
+                .map(|(value, style)| (value, Self::localized_style(date_styles, style))),
+        )?;
+        self.current_row += 1;
+        Ok(())
+    }
+
+    /// Add a row transformation, run on every data row before it is written
+    ///
+    /// Middleware runs in the order added; header rows from
+    /// [`write_header_bold`](Self::write_header_bold) skip it. See
+    /// [`middleware`](crate::middleware) for the built-in ones.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::middleware::{MaskColumns, TrimWhitespace};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.add_middleware(TrimWhitespace);
+    /// writer.add_middleware(MaskColumns::new([1]).keep_last(4));
+    /// writer.write_row(&[" Alice ", "0912345678"]).unwrap(); // "Alice", "******5678"
+    /// writer.save().unwrap();
+    /// ```
+    pub fn add_middleware<M: RowMiddleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    /// Produce a delivery manifest (SHA-256, rows per sheet, timestamp,
+    /// library version) on save, see [`manifest`](crate::manifest)
+    ///
+    /// The manifest is returned in [`WriteReport::manifest`](crate::WriteReport::manifest)
+    /// and, depending on `options`, written as a JSON sidecar and embedded
+    /// in the workbook.
+    pub fn set_manifest(&mut self, options: ManifestOptions) {
+        self.inner.set_embedded_manifest(options.embedded);
+        self.manifest = Some(options);
+    }
+
+    /// Report peak buffer sizes, the ZIP writer's bookkeeping and the
+    /// process's peak RSS on save, in [`WriteReport::memory`](crate::WriteReport::memory)
+    ///
+    /// Lets a production job log and alert on the memory a write actually
+    /// took. Row buffers are only counted once rows reach the workbook, so
+    /// rows held back by [`sort_by`](Self::sort_by) or
+    /// [`dedupe_by`](Self::dedupe_by) are not included.
+    ///
+    /// Off by default, and nothing is tracked while off; enable it before
+    /// the first row.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx")?;
+    /// writer.set_memory_instrumentation(true);
+    /// writer.write_row(["Data"])?;
+    /// let memory = writer.save()?.memory.unwrap();
+    /// println!("peak buffers {} bytes, peak RSS {:?}", memory.peak_buffers, memory.peak_rss_bytes);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_memory_instrumentation(&mut self, enabled: bool) {
+        self.inner.set_memory_instrumentation(enabled);
+    }
+
+    /// Sign the workbook on save with an OOXML package signature made by
+    /// `signer`, see [`signature`](crate::signature)
+    ///
+    /// Call before writing rows: parts are hashed as they stream out, so
+    /// this fails with [`ExcelError::InvalidState`](crate::ExcelError::InvalidState)
+    /// afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::signature::Signer;
+    /// use excelstream::ExcelWriter;
+    ///
+    /// struct Hsm;
+    /// impl Signer for Hsm {
+    ///     fn certificate(&self) -> Vec<u8> {
+    ///         std::fs::read("signer.der").unwrap()
+    ///     }
+    ///     fn sign(&self, data: &[u8]) -> excelstream::Result<Vec<u8>> {
+    ///         // RSA-SHA256 signature of `data` from the HSM
+    ///         # let _ = data;
+    ///         # unimplemented!()
+    ///     }
+    /// }
+    ///
+    /// let mut writer = ExcelWriter::new("signed.xlsx")?;
+    /// writer.set_signer(Hsm)?;
+    /// writer.write_row(["id", "amount"])?;
+    /// writer.save()?;
+    /// # Ok::<(), excelstream::ExcelError>(())
+    /// ```
+    pub fn set_signer<S: Signer + 'static>(&mut self, signer: S) -> Result<()> {
+        self.inner.set_signer(Some(Box::new(signer)))
+    }
+
+    /// Attach a VBA project, making the workbook a macro-enabled `.xlsm`
+    ///
+    /// `project` is the `vbaProject.bin` of an existing macro workbook, e.g.
+    /// a template read with
+    /// [`StreamingReader::vba_project`](crate::streaming_reader::StreamingReader::vba_project).
+    /// Fails with [`ExcelError::InvalidState`](crate::ExcelError::InvalidState)
+    /// when the file is named `.xlsx`, which Excel won't open with macros,
+    /// and with [`ExcelError::InvalidFormat`](crate::ExcelError::InvalidFormat)
+    /// when `project` isn't a VBA project.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use excelstream::streaming_reader::StreamingReader;
+    /// use excelstream::ExcelWriter;
+    ///
+    /// let macros = StreamingReader::open("template.xlsm")?.vba_project()?;
+    /// let mut writer = ExcelWriter::new("report.xlsm")?;
+    /// if let Some(macros) = macros {
+    ///     writer.attach_vba_project(macros)?;
+    /// }
+    /// writer.write_row(["id", "amount"])?;
+    /// writer.save()?;
+    /// # Ok::<(), excelstream::ExcelError>(())
+    /// ```
+    pub fn attach_vba_project(&mut self, project: impl Into<Vec<u8>>) -> Result<()> {
+        let is_xlsx = self
+            .inner
+            .path()
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+        if is_xlsx {
+            return Err(crate::ExcelError::InvalidState(format!(
+                "Macro-enabled workbooks must be saved as .xlsm, not {}",
+                self.inner.path().display()
+            )));
+        }
+        self.inner.set_vba_project(Some(project.into()))
+    }
+
+    /// Mask a column's values with a built-in [`Masker`] (email, phone,
+    /// tax ID, card number) on every sheet
+    ///
+    /// Masking runs after all middleware, so nothing later can see the
+    /// original value; header rows are left as they are. Masked cells become
+    /// text and are counted per column in
+    /// [`WriteReport::masked_cells`](crate::WriteReport::masked_cells).
+    /// Setting a column again replaces its masker.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::middleware::Masker;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("customers.xlsx").unwrap();
+    /// writer.mask_column(1, Masker::Email);
+    /// writer.mask_column(2, Masker::CreditCard);
+    /// writer.write_header_bold(["Name", "Email", "Card"]).unwrap();
+    /// writer
+    ///     .write_row(["Alice", "alice@example.com", "4111-1111-1111-1111"])
+    ///     .unwrap(); // "Alice", "a****@example.com", "****-****-****-1111"
+    /// let report = writer.save().unwrap();
+    /// assert_eq!(report.total_masked_cells(), 2);
+    /// ```
+    pub fn mask_column(&mut self, col: usize, masker: Masker) {
+        match self.masks.iter_mut().find(|(c, _, _)| *c == col) {
+            Some(mask) => mask.1 = masker,
+            None => self.masks.push((col, masker, 0)),
+        }
+    }
+
+    // Run the middleware stack, then the column masks; None when a
+    // middleware drops the row
+    fn apply_middleware(&mut self, mut row: Vec<CellValue>) -> Option<Vec<CellValue>> {
+        for middleware in &mut self.middleware {
+            middleware.transform(&mut row)?;
+        }
+        for (col, masker, count) in &mut self.masks {
+            if let Some(cell) = row.get_mut(*col) {
+                if !cell.is_empty() {
+                    *cell = CellValue::String(masker.mask(&cell.as_string()));
+                    *count += 1;
+                }
+            }
+        }
+        Some(row)
+    }
+
+    // Whether rows must go through `write_styled_cells` rather than
+    // straight to the workbook
+    fn has_row_pipeline(&self) -> bool {
+        !self.middleware.is_empty()
+            || !self.masks.is_empty()
+            || self.sorter.is_some()
+            || self.deduper.is_some()
+    }
+
+    fn write_styled_cells(&mut self, cells: &[StyledCell]) -> Result<()> {
+        let verdict = match self.deduper.as_mut() {
+            Some(deduper) => deduper.push(cells)?,
+            None => Verdict::Write,
+        };
+        match verdict {
+            Verdict::Write => self.sort_or_emit(cells)?,
+            Verdict::Hold => {}
+            Verdict::Drop => {
+                self.duplicates_dropped += 1;
+                return Ok(());
+            }
+        }
         self.current_row += 1;
         Ok(())
     }
 
+    fn sort_or_emit(&mut self, cells: &[StyledCell]) -> Result<()> {
+        match self.sorter.as_mut() {
+            Some(sorter) => sorter.push(cells),
+            None => self.emit_styled_cells(cells),
+        }
+    }
+
+    // Hand a row to the workbook, past any sorting
+    fn emit_styled_cells(&mut self, cells: &[StyledCell]) -> Result<()> {
+        let date_styles = self.date_styles;
+        self.inner.write_row_styled_ref(
+            cells
+                .iter()
+                .map(|cell| (&cell.value, Self::localized_style(date_styles, cell.style))),
+        )
+    }
+
+    // Swap the built-in date styles for the locale's formats, if set
+    fn localized_style(date_styles: Option<(CellStyle, CellStyle)>, style: CellStyle) -> CellStyle {
+        match (date_styles, style) {
+            (Some((date, _)), CellStyle::DateDefault) => date,
+            (Some((_, datetime)), CellStyle::DateTimestamp) => datetime,
+            _ => style,
+        }
+    }
+
     /// Write a row with all cells using the same style
     ///
     /// # Examples
@@ -285,18 +726,16 @@ impl ExcelWriter {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        use crate::types::CellStyle;
-
         let cells: Vec<_> = headers
             .into_iter()
             .map(|h| {
-                (
+                StyledCell::new(
                     CellValue::String(h.as_ref().to_string()),
                     CellStyle::HeaderBold,
                 )
             })
             .collect();
-        self.write_row_styled(&cells)
+        self.write_styled_cells(&cells)
     }
 
     /// Write header row (without bold - for backward compatibility)
@@ -318,7 +757,11 @@ impl ExcelWriter {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.write_row(headers)
+        // Header text is not PII
+        let masks = std::mem::take(&mut self.masks);
+        let result = self.write_row(headers);
+        self.masks = masks;
+        result
     }
 
     /// Add a new sheet and switch to it
@@ -337,9 +780,14 @@ impl ExcelWriter {
     /// writer.save().unwrap();
     /// ```
     pub fn add_sheet(&mut self, name: &str) -> Result<()> {
+        self.finish_dedupe()?;
+        self.finish_sort()?;
+        self.run_sheet_hooks()?;
         self.inner.add_worksheet(name)?;
         self.current_sheet_name = name.to_string();
         self.current_row = 0;
+        self.deferred_refs
+            .retain(|r| !r.sheet.eq_ignore_ascii_case(name));
         Ok(())
     }
 
@@ -353,7 +801,8 @@ impl ExcelWriter {
     ///
     /// # Arguments
     /// * `col` - Column index (0-based: 0=A, 1=B, 2=C, etc.)
-    /// * `width` - Column width in Excel units (typically 8-50)
+    /// * `width` - Column width in Excel units (typically 8-50), from 0 to
+    ///   [`MAX_COLUMN_WIDTH`](crate::types::MAX_COLUMN_WIDTH)
     ///
     /// # Examples
     ///
@@ -376,6 +825,104 @@ impl ExcelWriter {
         self.inner.set_column_width(col, width)
     }
 
+    /// Write a column (0-based) of the current sheet as text
+    ///
+    /// Cells in the column without an explicit style get
+    /// [`CellStyle::TextFormat`]: Excel keeps values like "0012345" or
+    /// "+84 912 345 678" as written instead of converting them to numbers, and
+    /// numbers written there are stored as text. Call it again after
+    /// [`add_sheet`](Self::add_sheet) for other sheets.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_text_column(1);
+    /// writer.write_row(&["Alice", "0912345678"]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_text_column(&mut self, col: u32) {
+        self.inner.set_text_column(col);
+    }
+
+    /// Style cells of a column (0-based) of the current sheet that are
+    /// written without an explicit style
+    ///
+    /// Text columns ([`set_text_column`](Self::set_text_column)) and the
+    /// default styles of durations and percentages take precedence. Call it
+    /// again after [`add_sheet`](Self::add_sheet) for other sheets.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::{CellStyle, CellValue};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_column_style(1, CellStyle::NumberCurrency);
+    /// writer.write_row_typed(&["Widget".into(), CellValue::Float(9.5)]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_column_style(&mut self, col: u32, style: CellStyle) {
+        self.inner
+            .set_column_style(col, Self::localized_style(self.date_styles, style));
+    }
+
+    /// Write the header row of a table and set up its columns in one go
+    ///
+    /// Sets each [`Column`]'s width, registers its number format and makes
+    /// its style the default of the cells below, then writes the header row
+    /// with the header styles. Must come before any other row of the sheet,
+    /// like [`set_column_width`](Self::set_column_width).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::{CellStyle, CellValue, Column};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.write_table_header(&[
+    ///     Column::new("Item").width(30.0),
+    ///     Column::new("Amount")
+    ///         .width(14.0)
+    ///         .style(CellStyle::NumberCurrency)
+    ///         .fmt("#,##0.00"),
+    /// ]).unwrap();
+    /// writer.write_row_typed(&["Widget".into(), CellValue::Float(1234.5)]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn write_table_header(&mut self, columns: &[Column]) -> Result<()> {
+        if self.current_row > 0 {
+            return Err(crate::error::ExcelError::InvalidState(
+                "The table header must be the first row of the sheet".to_string(),
+            ));
+        }
+        for (col, column) in columns.iter().enumerate() {
+            let col = col as u32;
+            if let Some(width) = column.width {
+                self.set_column_width(col, width)?;
+            }
+            let style = Self::localized_style(self.date_styles, column.style);
+            let style = match &column.format {
+                Some(code) => {
+                    CellStyle::NumberFormat(self.inner.register_style_format(&style, code))
+                }
+                None => style,
+            };
+            self.inner.set_column_style(col, style);
+        }
+        let cells: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                StyledCell::new(CellValue::String(column.name.clone()), column.header_style)
+            })
+            .collect();
+        self.write_styled_cells(&cells)
+    }
+
     /// Set height for the next row to be written
     ///
     /// Height is in points (1 point = 1/72 inch).
@@ -439,33 +986,82 @@ impl ExcelWriter {
         self.inner.protect_sheet(options)
     }
 
-    /// Set flush interval (rows between disk flushes)
+    /// Protect the current worksheet with a password
+    ///
+    /// Shorthand for [`protect_sheet`](Self::protect_sheet) with
+    /// `options.with_password(password)`; an empty password protects without
+    /// one. Cells written with [`CellStyle::Unlocked`] or an
+    /// [`unlocked_style`](Self::unlocked_style) stay editable.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use excelstream::types::{CellStyle, CellValue};
+    /// use excelstream::{ExcelWriter, ProtectionOptions};
+    ///
+    /// let mut writer = ExcelWriter::new("template.xlsx").unwrap();
+    /// writer
+    ///     .protect_current_sheet("secret123", ProtectionOptions::new())
+    ///     .unwrap();
+    /// let input = writer.unlocked_style(CellStyle::NumberDecimal);
+    /// writer
+    ///     .write_row_styled(&[
+    ///         (CellValue::String("Quantity".to_string()), CellStyle::HeaderBold),
+    ///         (CellValue::Empty, input),
+    ///     ])
+    ///     .unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn protect_current_sheet(
+        &mut self,
+        password: &str,
+        options: crate::types::ProtectionOptions,
+    ) -> Result<()> {
+        let options = if password.is_empty() {
+            options
+        } else {
+            options.with_password(password)
+        };
+        self.inner.protect_sheet(options)
+    }
+
+    /// A copy of `style` whose cells stay editable on protected sheets
+    ///
+    /// Calling it again for the same style returns the same copy.
+    pub fn unlocked_style(&mut self, style: CellStyle) -> CellStyle {
+        match self.inner.register_unlocked_style(&style) {
+            index if index == CellStyle::Unlocked.index() => CellStyle::Unlocked,
+            index => CellStyle::NumberFormat(index),
+        }
+    }
+
+    /// Register a custom number format and get a style that applies it
     ///
-    /// Default is 1000 rows. Lower values use less memory but slower.
-    /// Higher values are faster but use more memory.
+    /// Use [`crate::numfmt`] to build correct format codes instead of
+    /// hand-writing them. Registering the same code twice returns the same style.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use excelstream::writer::ExcelWriter;
+    /// use excelstream::numfmt::{NegativeStyle, NumberFormat};
+    /// use excelstream::types::CellValue;
     ///
     /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
-    /// writer.set_flush_interval(500); // Flush every 500 rows
+    /// let code = NumberFormat::number(2)
+    ///     .thousands_separator(true)
+    ///     .negative_style(NegativeStyle::Parentheses)
+    ///     .build();
+    /// let style = writer.register_number_format(&code);
+    /// writer.write_row_styled(&[(CellValue::Float(-1234.5), style)]).unwrap();
+    /// writer.save().unwrap();
     /// ```
-    pub fn set_flush_interval(&mut self, interval: u32) {
-        self.inner.set_flush_interval(interval);
-    }
-
-    /// Set maximum buffer size before forcing a flush
-    ///
-    /// Default is 1MB. This ensures memory usage stays bounded.
-    pub fn set_max_buffer_size(&mut self, size: usize) {
-        self.inner.set_max_buffer_size(size);
+    pub fn register_number_format(&mut self, code: &str) -> CellStyle {
+        CellStyle::NumberFormat(self.inner.register_number_format(code))
     }
 
-    /// Save and finalize the workbook
+    /// Make a cell of the current sheet link to a cell of another sheet
     ///
-    /// This closes the ZIP file and ensures all data is written to disk.
+    /// The link can be added before or after the cell's row is written.
     ///
     /// # Examples
     ///
@@ -473,231 +1069,2614 @@ impl ExcelWriter {
     /// use excelstream::writer::ExcelWriter;
     ///
     /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
-    /// writer.write_row(&["Data"]).unwrap();
+    /// writer.write_row(["See details"]).unwrap();
+    /// writer.add_internal_link("A1", "Details", "A1").unwrap();
+    /// writer.add_sheet("Details").unwrap();
     /// writer.save().unwrap();
     /// ```
-    pub fn save(self) -> Result<()> {
-        self.inner.close()
+    pub fn add_internal_link(&mut self, cell: &str, sheet: &str, target: &str) -> Result<()> {
+        let (row, col) = crate::cellref::parse_a1(cell)
+            .ok_or_else(|| crate::ExcelError::InvalidCell(cell.to_string()))?;
+        if crate::cellref::parse_a1(target).is_none() {
+            return Err(crate::ExcelError::InvalidCell(target.to_string()));
+        }
+        let location = format!("'{}'!{}", sheet.replace('\'', "''"), target);
+        self.inner.add_internal_hyperlink(row, col, &location)
     }
 
-    /// Get current row number (0-based)
-    pub fn current_row(&self) -> u32 {
-        self.current_row
+    /// Put an autofilter on `range` (like "A1:D120") of the current sheet
+    ///
+    /// The filter is written when the sheet is finished, so the range can
+    /// depend on rows written after this call. Calling it again replaces
+    /// the range.
+    pub fn set_auto_filter(&mut self, range: &str) -> Result<()> {
+        let cells = crate::cellref::parse_range(range)
+            .ok_or_else(|| crate::ExcelError::InvalidCell(range.to_string()))?;
+        let range = format!(
+            "{}:{}",
+            crate::cellref::to_a1(cells.first_row, cells.first_col),
+            crate::cellref::to_a1(cells.last_row, cells.last_col)
+        );
+        self.inner.set_auto_filter(Some(&range));
+        Ok(())
     }
-}
 
-/// Builder for creating configured Excel writers
-pub struct ExcelWriterBuilder {
-    path: String,
-    default_sheet_name: Option<String>,
-    flush_interval: Option<u32>,
-    max_buffer_size: Option<usize>,
-}
+    /// Emit the rest of the current sheet's rows sorted, see
+    /// [`sort`](crate::sort)
+    ///
+    /// Rows written from now until the sheet is finished (by `add_sheet` or
+    /// `save`) are held back and written in the order of `options`; rows
+    /// written before, such as the header, stay on top. Memory is bounded
+    /// by [`SortOptions::max_buffer_bytes`], beyond which sorted runs are
+    /// spilled to temp files. Calling it again first emits the rows held
+    /// so far. Sheets added later are not sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::sort::SortOptions;
+    /// use excelstream::types::CellValue;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("ranking.xlsx").unwrap();
+    /// writer.write_header_bold(["Player", "Score"]).unwrap();
+    /// writer.sort_by(SortOptions::by_desc(1)).unwrap();
+    /// writer.write_row_typed(&["Bob".into(), CellValue::Int(72)]).unwrap();
+    /// writer.write_row_typed(&["Alice".into(), CellValue::Int(95)]).unwrap();
+    /// writer.save().unwrap(); // Alice, then Bob
+    /// ```
+    pub fn sort_by(&mut self, options: SortOptions) -> Result<()> {
+        self.finish_sort()?;
+        self.sorter = Some(ExternalSorter::new(options));
+        Ok(())
+    }
 
-impl ExcelWriterBuilder {
-    /// Create a new builder
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        ExcelWriterBuilder {
-            path: path.as_ref().to_string_lossy().to_string(),
-            default_sheet_name: None,
-            flush_interval: None,
-            max_buffer_size: None,
+    // Write the rows held back by `sort_by`, in order
+    fn finish_sort(&mut self) -> Result<()> {
+        match self.sorter.take() {
+            Some(sorter) => sorter.drain(|cells| self.emit_styled_cells(cells)),
+            None => Ok(()),
         }
     }
 
-    /// Set the default sheet name
-    pub fn with_sheet_name(mut self, name: &str) -> Self {
-        self.default_sheet_name = Some(name.to_string());
+    /// Drop rows repeating the values in `columns` (0-based), see
+    /// [`dedupe`](crate::dedupe)
+    ///
+    /// Applies to rows written from now until the sheet is finished, like
+    /// [`sort_by`](Self::sort_by); rows written before, such as the header,
+    /// are not checked. With [`Keep::Last`] the rows are held back until the
+    /// sheet is finished. Dropped rows are counted in
+    /// [`WriteReport::duplicates_dropped`], not in [`current_row`](Self::current_row).
+    /// Combined with `sort_by`, duplicates are dropped before sorting.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::dedupe::Keep;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("customers.xlsx").unwrap();
+    /// writer.write_header_bold(["Id", "Name"]).unwrap();
+    /// writer.dedupe_by([0], Keep::First).unwrap();
+    /// writer.write_row(["1", "Alice"]).unwrap();
+    /// writer.write_row(["1", "Alice"]).unwrap(); // Dropped
+    /// writer.save().unwrap();
+    /// ```
+    pub fn dedupe_by<I>(&mut self, columns: I, keep: Keep) -> Result<()>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        self.finish_dedupe()?;
+        self.deduper = Some(Deduper::new(columns.into_iter().collect(), keep));
+        Ok(())
+    }
+
+    // Write the rows held back by `dedupe_by`, dropping repeated keys
+    fn finish_dedupe(&mut self) -> Result<()> {
+        if let Some(deduper) = self.deduper.take() {
+            let dropped = deduper.drain(|cells| self.sort_or_emit(cells))?;
+            self.current_row -= dropped as u32;
+            self.duplicates_dropped += dropped;
+        }
+        Ok(())
+    }
+
+    /// Run `hook` as each sheet is finished, see [`SheetContext`]
+    ///
+    /// A sheet is finished when the next one is added or the workbook is
+    /// saved; only then are its final row and column counts known. Hooks
+    /// run in the order they were added and can append rows, such as totals
+    /// or footer formulas, or set an autofilter over the written range. An
+    /// error from a hook is returned by `add_sheet` or `save`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::CellValue;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("sales.xlsx").unwrap();
+    /// writer.on_sheet_finish(|sheet| {
+    ///     if let Some(range) = sheet.used_range() {
+    ///         sheet.set_auto_filter(&range)?;
+    ///     }
+    ///     let last = sheet.rows();
+    ///     sheet.write_row_typed(&[
+    ///         CellValue::String("Total".to_string()),
+    ///         CellValue::Formula(format!("SUM(B2:B{})", last)),
+    ///     ])
+    /// });
+    /// writer.write_header_bold(["Region", "Amount"]).unwrap();
+    /// writer.write_row(["North", "120"]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn on_sheet_finish<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut SheetContext<'_>) -> Result<()> + Send + 'static,
+    {
+        self.sheet_hooks.push(Box::new(hook));
+    }
+
+    // Hand the current sheet to the `on_sheet_finish` hooks
+    fn run_sheet_hooks(&mut self) -> Result<()> {
+        let mut hooks = std::mem::take(&mut self.sheet_hooks);
+        let result = hooks
+            .iter_mut()
+            .try_for_each(|hook| hook(&mut SheetContext { writer: self }));
+        self.sheet_hooks = hooks;
+        result
+    }
+
+    /// Add a table-of-contents sheet when the workbook is saved
+    ///
+    /// The sheet comes first and lists every other sheet with a link to it
+    /// and, by default, its row count. It is written at
+    /// [`save`](Self::save), once all sheets are known.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::IndexSheetOptions;
+    /// use excelstream::writer::ExcelWriterBuilder;
+    ///
+    /// let mut writer = ExcelWriterBuilder::new("report.xlsx")
+    ///     .with_sheet_name("Sales")
+    ///     .build()
+    ///     .unwrap();
+    /// writer.generate_index_sheet(IndexSheetOptions::new().with_header_rows(1));
+    /// writer.write_row(["Region", "Amount"]).unwrap();
+    /// writer.add_sheet("Costs").unwrap();
+    /// writer.write_row(["Item", "Amount"]).unwrap();
+    /// writer.save().unwrap(); // sheets: Index, Sales, Costs
+    /// ```
+    pub fn generate_index_sheet(&mut self, options: IndexSheetOptions) {
+        self.inner.set_index_sheet(Some(options));
+    }
+
+    /// Add a hidden provenance sheet on save, see [`AuditOptions`]
+    ///
+    /// The sheet goes after the data sheets and is left out of the
+    /// [index sheet](Self::generate_index_sheet). Its rows count in the
+    /// [`WriteReport`] like any other sheet's.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::AuditOptions;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("report.xlsx").unwrap();
+    /// writer.set_audit(AuditOptions::new());
+    /// writer.write_row(["Region", "Amount"]).unwrap();
+    /// writer.save().unwrap(); // sheets: Sheet1, _audit (hidden)
+    /// ```
+    pub fn set_audit(&mut self, options: AuditOptions) {
+        self.inner.set_audit(Some(options));
+    }
+
+    /// Reorder or align the file's ZIP entries on save, see [`ZipLayout`]
+    ///
+    /// Readers that fetch the file with range requests, or parse it while it
+    /// downloads, can start on the workbook before the worksheets arrive.
+    /// The saved file is rewritten once, copying compressed data as is.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::ZipLayout;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("report.xlsx").unwrap();
+    /// writer.set_zip_layout(ZipLayout::new().with_metadata_first(true).with_alignment(4096));
+    /// writer.write_row(["Region", "Amount"]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_zip_layout(&mut self, layout: ZipLayout) {
+        self.inner.set_zip_layout(layout);
+    }
+
+    /// Check a built formula against this workbook and turn it into a cell
+    ///
+    /// Fails with [`ExcelError::SheetNotFound`](crate::ExcelError::SheetNotFound)
+    /// when it refers to a sheet not added yet, and with
+    /// [`ExcelError::InvalidCell`](crate::ExcelError::InvalidCell) for
+    /// references outside the grid, instead of leaving `#REF!` in the file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::formula::{Formula, Range};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.write_row(["Amount"]).unwrap();
+    /// writer.write_row(["10"]).unwrap();
+    /// writer.add_sheet("Summary").unwrap();
+    /// let total = writer
+    ///     .formula(&Formula::sum(Range::col("A", 2, 2).on_sheet("Sheet1")))
+    ///     .unwrap();
+    /// writer.write_row_typed(&[total]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn formula(&self, formula: &crate::formula::Formula) -> Result<CellValue> {
+        formula.validate(self.inner.sheet_names())?;
+        Ok(formula.to_cell())
+    }
+
+    /// Like [`formula`](Self::formula), but references to sheets not added
+    /// yet are accepted as [`DeferredRef`]s
+    ///
+    /// Each deferred sheet must be added before [`save`](Self::save), which
+    /// otherwise fails with [`ExcelError::SheetNotFound`](crate::ExcelError::SheetNotFound).
+    /// Ranges are still checked right away.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::formula::{Formula, Range};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// // A summary first, pointing at the detail sheet streamed after it
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.write_row(["Total"]).unwrap();
+    /// let total = writer
+    ///     .formula_deferred(&Formula::sum(Range::col("B", 2, 100_001).on_sheet("Q1 Detail")))
+    ///     .unwrap();
+    /// writer.write_row_typed(&[total]).unwrap();
+    ///
+    /// writer.add_sheet("Q1 Detail").unwrap();
+    /// // ... detail rows ...
+    /// writer.save().unwrap();
+    /// ```
+    pub fn formula_deferred(&mut self, formula: &crate::formula::Formula) -> Result<CellValue> {
+        for sheet in formula.missing_sheets(self.inner.sheet_names())? {
+            if !self
+                .deferred_refs
+                .iter()
+                .any(|r| r.sheet.eq_ignore_ascii_case(sheet))
+            {
+                self.deferred_refs.push(DeferredRef {
+                    sheet: sheet.to_string(),
+                    from_sheet: self.current_sheet_name.clone(),
+                    row: self.current_row,
+                });
+            }
+        }
+        Ok(formula.to_cell())
+    }
+
+    /// References to sheets not added yet, from
+    /// [`formula_deferred`](Self::formula_deferred)
+    pub fn deferred_refs(&self) -> &[DeferredRef] {
+        &self.deferred_refs
+    }
+
+    /// Neutralize formula-like text in exported cells
+    ///
+    /// Text values starting with `=`, `+`, `-`, `@`, tab or carriage return are
+    /// prefixed with `'` so a spreadsheet app (or a CSV export of the file)
+    /// won't evaluate them. Use this when writing user-supplied data.
+    /// Explicit [`CellValue::Formula`] cells and plain numbers like "-5" are
+    /// left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.enable_formula_sanitization();
+    /// writer.write_row(&["=HYPERLINK(\"http://evil\")"]).unwrap(); // written as '=HYPERLINK(...)
+    /// writer.save().unwrap();
+    /// ```
+    pub fn enable_formula_sanitization(&mut self) {
+        self.inner.set_formula_sanitization(Some('\''));
+    }
+
+    /// Like [`enable_formula_sanitization`](Self::enable_formula_sanitization)
+    /// but with a custom prefix character (e.g. a space)
+    pub fn enable_formula_sanitization_with(&mut self, prefix: char) {
+        self.inner.set_formula_sanitization(Some(prefix));
+    }
+
+    /// Turn formula sanitization off again
+    pub fn disable_formula_sanitization(&mut self) {
+        self.inner.set_formula_sanitization(None);
+    }
+
+    /// Control how float cells are written
+    ///
+    /// By default floats keep full round-trip precision and switch to
+    /// scientific notation outside 1e-7..1e21. [`FloatFormat::EXCEL`] rounds
+    /// to the 15 significant digits Excel keeps, so `0.1 + 0.2` is written
+    /// as `0.3`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::{CellValue, FloatFormat};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_float_format(FloatFormat::default().significant_digits(6));
+    /// writer.write_row_typed(&[CellValue::Float(3.14159265)]).unwrap(); // 3.14159
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_float_format(&mut self, format: FloatFormat) {
+        self.inner.set_float_format(format);
+    }
+
+    /// Control how [`CellValue::DateTimeTz`] cells are written
+    ///
+    /// By default they are converted to UTC and stored as dates. Convert to
+    /// the zone the workbook is read in with [`TimeZonePolicy::Convert`], or
+    /// keep the offset as ISO 8601 text with [`TimeZonePolicy::IsoString`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chrono::{DateTime, FixedOffset};
+    /// use excelstream::types::TimeZonePolicy;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// let berlin = FixedOffset::east_opt(3600).unwrap();
+    /// writer.set_time_zone_policy(TimeZonePolicy::Convert(berlin));
+    /// let dt = DateTime::parse_from_rfc3339("2024-01-31T09:00:00+07:00").unwrap();
+    /// writer.write_row_typed(&[dt.into()]).unwrap(); // 2024-01-31 03:00
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_time_zone_policy(&mut self, policy: TimeZonePolicy) {
+        self.inner.set_time_zone_policy(policy);
+    }
+
+    /// Choose what happens to cells over Excel's hard limits
+    ///
+    /// Text over [`MAX_TEXT_LEN`](crate::types::MAX_TEXT_LEN) characters,
+    /// formulas over [`MAX_FORMULA_LEN`](crate::types::MAX_FORMULA_LEN),
+    /// links past [`MAX_HYPERLINKS`](crate::types::MAX_HYPERLINKS) in a
+    /// sheet and styles past [`MAX_CELL_STYLES`](crate::types::MAX_CELL_STYLES)
+    /// would make Excel delete content when it opens the file. By default
+    /// the write fails with [`ExcelError::LimitExceeded`](crate::ExcelError::LimitExceeded)
+    /// naming the cell, and the row is not written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::LimitPolicy;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_limit_policy(LimitPolicy::Truncate);
+    /// writer.write_row(["x".repeat(40_000)]).unwrap(); // cut to 32,767
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_limit_policy(&mut self, policy: LimitPolicy) {
+        self.inner.set_limit_policy(policy);
+    }
+
+    /// Cap the rate rows are written at, see [`throttle`](crate::throttle)
+    ///
+    /// Once the writer is ahead of the rate, the row call that got it there
+    /// sleeps until it is back on schedule. `None` removes the cap.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    /// use excelstream::Throttle;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_throttle(Some(Throttle::RowsPerSec(10_000)));
+    /// ```
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.inner.set_throttle(throttle);
+    }
+
+    /// Show [`CellStyle::DateDefault`] and [`CellStyle::DateTimestamp`]
+    /// cells in a locale's date order, e.g. `31.01.2024` for
+    /// [`Locale::DE_DE`]
+    ///
+    /// The built-in date styles otherwise use US month-first formats.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::numfmt::Locale;
+    /// use excelstream::types::{CellStyle, CellValue};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_locale(&Locale::VI_VN);
+    /// writer
+    ///     .write_row_styled(&[(CellValue::DateTime(45322.0), CellStyle::DateDefault)])
+    ///     .unwrap(); // 31/01/2024
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_locale(&mut self, locale: &Locale) {
+        let date = self.register_number_format(&locale.date_format_code());
+        let datetime = self.register_number_format(&locale.datetime_format_code());
+        self.date_styles = Some((date, datetime));
+    }
+
+    /// Print a page header and footer on the current sheet
+    ///
+    /// Pictures (a logo, a signature) go in a section through its `&G`
+    /// code; see [`HeaderFooter`](crate::types::HeaderFooter) for the text
+    /// codes. Fails with [`ExcelError::InvalidFormat`](crate::ExcelError::InvalidFormat)
+    /// if a picture's section has no `&G` or the text is over 255 characters.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::{HeaderFooter, HeaderFooterImage, HeaderFooterPosition};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let logo = std::fs::read("logo.png").unwrap();
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer
+    ///     .set_header_footer(
+    ///         HeaderFooter::new()
+    ///             .with_header("&L&G&R&A")
+    ///             .with_footer("&CPage &P of &N")
+    ///             .with_image(HeaderFooterImage::new(
+    ///                 HeaderFooterPosition::LeftHeader,
+    ///                 logo,
+    ///                 "png",
+    ///                 120.0,
+    ///                 40.0,
+    ///             )),
+    ///     )
+    ///     .unwrap();
+    /// writer.write_row(["Data"]).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_header_footer(&mut self, header_footer: crate::types::HeaderFooter) -> Result<()> {
+        self.inner.set_header_footer(header_footer)
+    }
+
+    /// Set the workbook's calculation settings
+    ///
+    /// Without this no `<calcPr>` is written and Excel uses its defaults.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::{CalcMode, CalcSettings};
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_calc_settings(
+    ///     CalcSettings::new()
+    ///         .with_mode(CalcMode::Manual)
+    ///         .with_full_calc_on_load(false),
+    /// );
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_calc_settings(&mut self, settings: CalcSettings) {
+        self.inner.set_calc_settings(Some(settings));
+    }
+
+    /// Show or hide a sheet's tab
+    ///
+    /// Hidden sheets still hold their data, e.g. lookup tables behind
+    /// formulas. Fails with [`ExcelError::SheetNotFound`](crate::ExcelError::SheetNotFound)
+    /// for an unknown sheet and [`ExcelError::InvalidState`](crate::ExcelError::InvalidState)
+    /// when hiding the active sheet or the last visible one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::types::SheetVisibility;
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.write_row(["Total"]).unwrap();
+    /// writer.add_sheet("Lookup").unwrap();
+    /// writer.write_row(["Code", "Label"]).unwrap();
+    /// writer.set_sheet_visibility("Lookup", SheetVisibility::Hidden).unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_sheet_visibility(&mut self, name: &str, visibility: SheetVisibility) -> Result<()> {
+        self.inner.set_sheet_visibility(name, visibility)
+    }
+
+    /// Select the tab shown when the file is opened (default: the first
+    /// visible sheet)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.write_row(["Detail"]).unwrap();
+    /// writer.add_sheet("Summary").unwrap();
+    /// writer.write_row(["Total"]).unwrap();
+    /// writer.set_active_sheet("Summary").unwrap();
+    /// writer.save().unwrap();
+    /// ```
+    pub fn set_active_sheet(&mut self, name: &str) -> Result<()> {
+        self.inner.set_active_sheet(name)
+    }
+
+    /// Order the tabs: `names` first, in this order, then the sheets not
+    /// listed in the order they were added
+    ///
+    /// Lets a summary written last appear first. Only the tabs move; sheet
+    /// data is written once, in the order it was added.
+    pub fn set_sheet_order(&mut self, names: &[&str]) -> Result<()> {
+        self.inner.set_sheet_order(names)
+    }
+
+    /// Batch rows before they are compressed, see [`FlushPolicy`]
+    ///
+    /// By default every row goes to the compressor as it is written.
+    /// Batching means fewer, larger writes but keeps the batch in memory;
+    /// batches are sized in bytes, so wide rows don't make them grow.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    /// use excelstream::FlushPolicy;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.set_flush_policy(FlushPolicy::new(512 * 1024)); // Flush every 512 KiB
+    /// ```
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.inner.set_flush_policy(policy);
+    }
+
+    /// Turn row batching on (`interval` above 1) or off
+    ///
+    /// Batches are sized in bytes, not rows: turning batching on uses
+    /// [`FlushPolicy::BATCHED`] unless a batch size is already set. Prefer
+    /// [`set_flush_policy`](Self::set_flush_policy).
+    pub fn set_flush_interval(&mut self, interval: u32) {
+        self.inner.set_flush_interval(interval);
+    }
+
+    /// Batch rows up to `size` bytes (0 = stream every row), short for
+    /// `set_flush_policy(FlushPolicy::new(size))`
+    pub fn set_max_buffer_size(&mut self, size: usize) {
+        self.inner.set_max_buffer_size(size);
+    }
+
+    /// Save and finalize the workbook
+    ///
+    /// This closes the ZIP file and ensures all data is written to disk.
+    /// Returns a [`WriteReport`] with row/cell counts, sizes and duration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use excelstream::writer::ExcelWriter;
+    ///
+    /// let mut writer = ExcelWriter::new("output.xlsx").unwrap();
+    /// writer.write_row(&["Data"]).unwrap();
+    /// let report = writer.save().unwrap();
+    /// println!(
+    ///     "{} rows, {} bytes in {:?}",
+    ///     report.total_rows(),
+    ///     report.compressed_bytes,
+    ///     report.duration
+    /// );
+    /// ```
+    pub fn save(mut self) -> Result<WriteReport> {
+        if let Some(deferred) = self.deferred_refs.first() {
+            return Err(crate::formula::sheet_not_found(
+                &deferred.sheet,
+                self.inner.sheet_names(),
+            ));
+        }
+        self.finish_dedupe()?;
+        self.finish_sort()?;
+        self.run_sheet_hooks()?;
+        let path = self.inner.path().to_path_buf();
+        let mut report = self.inner.close()?;
+        if let Some(options) = &self.manifest {
+            let manifest = Manifest::for_file(&path, report.per_sheet_rows.clone())?;
+            if options.sidecar {
+                std::fs::write(options.sidecar_path_for(&path), manifest.to_json())?;
+            }
+            report.manifest = Some(manifest);
+        }
+        report.masked_cells = self
+            .masks
+            .iter()
+            .map(|(col, _, count)| (*col as u32, *count))
+            .collect();
+        report.masked_cells.sort_unstable();
+        report.duplicates_dropped = self.duplicates_dropped;
+        Ok(report)
+    }
+
+    /// Get current row number (0-based)
+    pub fn current_row(&self) -> u32 {
+        self.current_row
+    }
+
+    /// Name of the sheet rows are written to
+    pub fn current_sheet_name(&self) -> &str {
+        &self.current_sheet_name
+    }
+}
+
+/// Builder for creating configured Excel writers
+pub struct ExcelWriterBuilder {
+    path: String,
+    default_sheet_name: Option<String>,
+    flush_interval: Option<u32>,
+    max_buffer_size: Option<usize>,
+    flush_policy: Option<FlushPolicy>,
+    compression_level: u32,
+    memory_profile: Option<MemoryProfile>,
+    temp_dir: Option<PathBuf>,
+    required_space: Option<u64>,
+    float_format: Option<FloatFormat>,
+    time_zone_policy: Option<TimeZonePolicy>,
+    limit_policy: Option<LimitPolicy>,
+    throttle: Option<Throttle>,
+    locale: Option<Locale>,
+    calc_settings: Option<CalcSettings>,
+    exact_dimensions: bool,
+    strict: bool,
+    manifest: Option<ManifestOptions>,
+    memory_instrumentation: bool,
+    audit: Option<AuditOptions>,
+    zip_layout: Option<ZipLayout>,
+    sink: Option<WrapSink>,
+}
+
+impl ExcelWriterBuilder {
+    /// Create a new builder
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        ExcelWriterBuilder {
+            path: path.as_ref().to_string_lossy().to_string(),
+            default_sheet_name: None,
+            flush_interval: None,
+            max_buffer_size: None,
+            flush_policy: None,
+            compression_level: 6,
+            memory_profile: None,
+            temp_dir: None,
+            required_space: None,
+            float_format: None,
+            time_zone_policy: None,
+            limit_policy: None,
+            throttle: None,
+            locale: None,
+            calc_settings: None,
+            exact_dimensions: false,
+            strict: false,
+            manifest: None,
+            memory_instrumentation: false,
+            audit: None,
+            zip_layout: None,
+            sink: None,
+        }
+    }
+
+    /// Set the default sheet name
+    pub fn with_sheet_name(mut self, name: &str) -> Self {
+        self.default_sheet_name = Some(name.to_string());
+        self
+    }
+
+    /// Turn row batching on or off, see [`ExcelWriter::set_flush_interval`]
+    pub fn with_flush_interval(mut self, interval: u32) -> Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    /// Batch rows up to `size` bytes, see [`ExcelWriter::set_max_buffer_size`]
+    pub fn with_max_buffer_size(mut self, size: usize) -> Self {
+        self.max_buffer_size = Some(size);
+        self
+    }
+
+    /// Batch rows by size, see [`ExcelWriter::set_flush_policy`]
+    ///
+    /// Takes precedence over `with_flush_interval`, `with_max_buffer_size`
+    /// and the memory profile.
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = Some(policy);
+        self
+    }
+
+    /// Set the compression level from 0 to 9 (default 6), see
+    /// [`ExcelWriter::set_compression_level`]
+    ///
+    /// 1 writes fastest with larger files, 9 the smallest files.
+    pub fn with_compression_level(mut self, level: u32) -> Self {
+        self.compression_level = level.min(9);
+        self
+    }
+
+    /// Batch rows according to a memory profile, e.g.
+    /// `MemoryProfile::from_env()` in containers
+    ///
+    /// Explicit `with_flush_policy` / `with_max_buffer_size` values take
+    /// precedence.
+    pub fn with_memory_profile(mut self, profile: MemoryProfile) -> Self {
+        self.memory_profile = Some(profile);
+        self
+    }
+
+    /// Write into a temp file in `dir` and move it into place on save
+    ///
+    /// Nothing appears at the target path until `save()` succeeds; if the
+    /// writer is dropped first (error or panic), the temp file is deleted.
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Check before creating the file that at least `bytes` are free where
+    /// it is written (the temp dir, if set)
+    ///
+    /// `build()` fails with [`ExcelError::DiskFull`](crate::ExcelError::DiskFull) when they aren't. The
+    /// check is skipped on platforms that can't report free space. Running
+    /// out of space later still surfaces as `DiskFull`, with the partial file
+    /// removed.
+    pub fn with_required_space(mut self, bytes: u64) -> Self {
+        self.required_space = Some(bytes);
+        self
+    }
+
+    /// Control how float cells are written, see
+    /// [`ExcelWriter::set_float_format`]
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = Some(format);
+        self
+    }
+
+    /// Control how cells with a UTC offset are written, see
+    /// [`ExcelWriter::set_time_zone_policy`]
+    pub fn with_time_zone_policy(mut self, policy: TimeZonePolicy) -> Self {
+        self.time_zone_policy = Some(policy);
         self
     }
 
-    /// Set flush interval (rows between disk flushes)
-    pub fn with_flush_interval(mut self, interval: u32) -> Self {
-        self.flush_interval = Some(interval);
-        self
+    /// Handle cells over Excel's limits, see
+    /// [`ExcelWriter::set_limit_policy`]
+    pub fn with_limit_policy(mut self, policy: LimitPolicy) -> Self {
+        self.limit_policy = Some(policy);
+        self
+    }
+
+    /// Cap the rate rows are written at, see [`ExcelWriter::set_throttle`]
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Write dates in a locale's order, see [`ExcelWriter::set_locale`]
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+
+    /// Set the calculation settings, see [`ExcelWriter::set_calc_settings`]
+    pub fn with_calc_settings(mut self, settings: CalcSettings) -> Self {
+        self.calc_settings = Some(settings);
+        self
+    }
+
+    /// Write each sheet's `<dimension>` with its exact used range
+    ///
+    /// Some strict consumers reject sheets without one, and readers use it
+    /// to size a sheet without scanning it. Each sheet's rows are spooled to
+    /// a temp file (in the temp dir, if set) until the sheet is finished, so
+    /// writing takes extra disk I/O; memory use is unchanged.
+    ///
+    /// Off by default, which writes sheets without a `<dimension>`.
+    pub fn with_exact_dimensions(mut self, enabled: bool) -> Self {
+        self.exact_dimensions = enabled;
+        self
+    }
+
+    /// Write "Strict Open XML Spreadsheet" markup
+    ///
+    /// Parts use the strict (`purl.oclc.org`) namespaces instead of the
+    /// transitional ones, for consumers that require ISO/IEC 29500 strict
+    /// conformance. Excel opens both. Header/footer pictures are written
+    /// as VML, which strict conformance excludes.
+    pub fn with_strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Produce a delivery manifest on save, see [`ExcelWriter::set_manifest`]
+    pub fn with_manifest(mut self, options: ManifestOptions) -> Self {
+        self.manifest = Some(options);
+        self
+    }
+
+    /// Report memory high-water marks on save, see
+    /// [`ExcelWriter::set_memory_instrumentation`]
+    pub fn with_memory_instrumentation(mut self, enabled: bool) -> Self {
+        self.memory_instrumentation = enabled;
+        self
+    }
+
+    /// Add a provenance sheet on save, see [`ExcelWriter::set_audit`]
+    pub fn with_audit(mut self, options: AuditOptions) -> Self {
+        self.audit = Some(options);
+        self
+    }
+
+    /// Reorder or align the ZIP entries on save, see
+    /// [`ExcelWriter::set_zip_layout`]
+    pub fn with_zip_layout(mut self, layout: ZipLayout) -> Self {
+        self.zip_layout = Some(layout);
+        self
+    }
+
+    /// Write through a sink wrapping the output file
+    ///
+    /// `wrap` gets the file once it's created (the temp file, if a temp dir
+    /// is set) and returns what the workbook is written to, e.g. a
+    /// [`FaultySink`](crate::sink::FaultySink) to test how an application
+    /// handles a full disk or a failing network share. Errors from the sink
+    /// are handled like errors from the file.
+    pub fn with_sink<F, S>(mut self, wrap: F) -> Self
+    where
+        F: FnOnce(std::fs::File) -> S + 'static,
+        S: Sink + 'static,
+    {
+        self.sink = Some(Box::new(move |file| Box::new(wrap(file)) as Box<dyn Sink>));
+        self
+    }
+
+    /// Build the writer
+    pub fn build(self) -> Result<ExcelWriter> {
+        if let Some(needed) = self.required_space {
+            let target = Path::new(&self.path);
+            staging::ensure_space(staging::write_dir(target, self.temp_dir.as_deref()), needed)?;
+        }
+
+        let mut inner = UltraLowMemoryWorkbook::create(
+            Path::new(&self.path),
+            self.compression_level,
+            self.temp_dir.as_deref(),
+            self.sink,
+        )?;
+        if let Some(profile) = self.memory_profile {
+            profile.apply(&mut inner);
+        }
+
+        if self.exact_dimensions {
+            inner.set_exact_dimensions(true, self.temp_dir.as_deref());
+        }
+        if self.strict {
+            inner.set_strict(true);
+        }
+
+        let sheet_name = self
+            .default_sheet_name
+            .unwrap_or_else(|| "Sheet1".to_string());
+        inner.add_worksheet(&sheet_name)?;
+
+        let mut writer = ExcelWriter {
+            inner,
+            current_row: 0,
+            current_sheet_name: sheet_name,
+            middleware: Vec::new(),
+            masks: Vec::new(),
+            manifest: None,
+            date_styles: None,
+            deferred_refs: Vec::new(),
+            sheet_hooks: Vec::new(),
+            sorter: None,
+            deduper: None,
+            duplicates_dropped: 0,
+        };
+
+        if let Some(interval) = self.flush_interval {
+            writer.set_flush_interval(interval);
+        }
+
+        if let Some(size) = self.max_buffer_size {
+            writer.set_max_buffer_size(size);
+        }
+
+        if let Some(policy) = self.flush_policy {
+            writer.set_flush_policy(policy);
+        }
+
+        if let Some(format) = self.float_format {
+            writer.set_float_format(format);
+        }
+
+        if let Some(policy) = self.time_zone_policy {
+            writer.set_time_zone_policy(policy);
+        }
+
+        if let Some(policy) = self.limit_policy {
+            writer.set_limit_policy(policy);
+        }
+
+        if self.throttle.is_some() {
+            writer.set_throttle(self.throttle);
+        }
+
+        if let Some(locale) = self.locale {
+            writer.set_locale(&locale);
+        }
+
+        if let Some(settings) = self.calc_settings {
+            writer.set_calc_settings(settings);
+        }
+
+        if let Some(options) = self.manifest {
+            writer.set_manifest(options);
+        }
+
+        if self.memory_instrumentation {
+            writer.set_memory_instrumentation(true);
+        }
+
+        if let Some(options) = self.audit {
+            writer.set_audit(options);
+        }
+
+        if let Some(layout) = self.zip_layout {
+            writer.set_zip_layout(layout);
+        }
+
+        Ok(writer)
+    }
+}
+
+impl SpreadsheetWriter for ExcelWriter {
+    fn add_sheet(&mut self, name: &str) -> Result<()> {
+        ExcelWriter::add_sheet(self, name)
+    }
+
+    fn write_row<I, S>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        ExcelWriter::write_row(self, values)
+    }
+
+    fn write_row_typed(&mut self, values: &[CellValue]) -> Result<()> {
+        ExcelWriter::write_row_typed(self, values)
+    }
+
+    fn write_row_styled(&mut self, cells: &[StyledCell]) -> Result<()> {
+        self.write_styled_cells(cells)
+    }
+
+    fn close(self) -> Result<WriteReport> {
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    fn write_sample<W: SpreadsheetWriter>(mut writer: W) -> WriteReport {
+        writer.add_sheet("Data").unwrap();
+        writer.write_row(["id", "name"]).unwrap();
+        writer
+            .write_row_typed(&[CellValue::Int(1), CellValue::String("a".to_string())])
+            .unwrap();
+        writer
+            .write_row_styled(&[StyledCell::new(
+                CellValue::Float(2.5),
+                CellStyle::NumberDecimal,
+            )])
+            .unwrap();
+        writer.close().unwrap()
+    }
+
+    #[test]
+    fn test_spreadsheet_writer_backends() {
+        use crate::fast_writer::{UltraLowMemoryWorkbook, ZeroTempWorkbook};
+        use crate::ExcelReader;
+
+        let files: Vec<NamedTempFile> = (0..3).map(|_| NamedTempFile::new().unwrap()).collect();
+        let path = |i: usize| files[i].path().to_str().unwrap().to_string();
+        write_sample(ExcelWriter::new(path(0)).unwrap());
+        write_sample(UltraLowMemoryWorkbook::new(path(1)).unwrap());
+        write_sample(ZeroTempWorkbook::new(&path(2), 6).unwrap());
+
+        for i in 0..3 {
+            let mut reader = ExcelReader::open(path(i)).unwrap();
+            let rows: Vec<_> = reader
+                .rows("Data")
+                .unwrap()
+                .map(|r| r.unwrap().cells)
+                .collect();
+            assert_eq!(rows.len(), 3, "backend {}", i);
+            assert_eq!(rows[1][0], CellValue::Int(1));
+            assert_eq!(rows[2][0], CellValue::Float(2.5));
+        }
+    }
+
+    #[test]
+    fn test_writer_creation() {
+        let temp = NamedTempFile::new().unwrap();
+        let writer = ExcelWriter::new(temp.path());
+        assert!(writer.is_ok());
+
+        // Should be able to save immediately
+        let writer = writer.unwrap();
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_write_row() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        assert!(writer.write_row(["A", "B", "C"]).is_ok());
+        assert!(writer.write_row(["1", "2", "3"]).is_ok());
+        assert_eq!(writer.current_row(), 2);
+
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_write_row_typed() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        use crate::types::CellValue;
+        let row = vec![
+            CellValue::String("Text".to_string()),
+            CellValue::Int(42),
+            CellValue::Float(1234.56),
+            CellValue::Bool(true),
+        ];
+
+        assert!(writer.write_row_typed(&row).is_ok());
+        assert_eq!(writer.current_row(), 1);
+
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_write_row_styled_ref() {
+        use crate::middleware::TrimWhitespace;
+        use crate::streaming_reader::StreamingReader;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_locale(Locale::DE_DE)
+            .build()
+            .unwrap();
+        let row = [
+            CellValue::String(" Total ".to_string()),
+            CellValue::DateTime(45322.0),
+        ];
+        let styles = [CellStyle::HeaderBold, CellStyle::DateDefault];
+        writer.write_row_styled_ref(row.iter().zip(styles)).unwrap();
+        // Middleware still applies to borrowed rows
+        writer.add_middleware(TrimWhitespace);
+        writer.write_row_styled_ref(row.iter().zip(styles)).unwrap();
+        assert_eq!(writer.current_row(), 2);
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let styled: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(styled[0][0].value, CellValue::String(" Total ".to_string()));
+        assert!(styled[0][0].format.bold);
+        assert_eq!(styled[0][1].format.num_fmt.as_deref(), Some("dd.mm.yyyy"));
+        assert_eq!(styled[1][0].value, CellValue::String("Total".to_string()));
+        assert!(styled[1][0].format.bold);
+    }
+
+    #[test]
+    fn test_builder() {
+        let temp = NamedTempFile::new().unwrap();
+        let writer = ExcelWriterBuilder::new(temp.path())
+            .with_sheet_name("CustomSheet")
+            .with_flush_interval(500)
+            .with_max_buffer_size(512 * 1024)
+            .build();
+
+        assert!(writer.is_ok());
+        let writer = writer.unwrap();
+        assert_eq!(writer.current_sheet_name, "CustomSheet");
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        use crate::streaming_reader::StreamingReader;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_flush_policy(FlushPolicy::new(4096).with_low_watermark(2048))
+            .build()
+            .unwrap();
+        // Narrow rows batch up; 300-column rows are past the high watermark
+        for i in 0..200 {
+            let width = if i % 50 == 0 { 300 } else { 3 };
+            writer
+                .write_row((0..width).map(|col| format!("{}-{}", i, col)))
+                .unwrap();
+        }
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader.rows("Sheet1").unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 200);
+        assert_eq!(rows[50].len(), 300);
+        assert_eq!(rows[50].cells[299], CellValue::String("50-299".to_string()));
+        assert_eq!(rows[199].to_strings(), ["199-0", "199-1", "199-2"]);
+    }
+
+    #[test]
+    fn test_builder_compression_and_memory_profile() {
+        for level in [0, 1, 6, 9] {
+            let temp = NamedTempFile::new().unwrap();
+            let mut writer = ExcelWriterBuilder::new(temp.path())
+                .with_compression_level(level)
+                .with_memory_profile(MemoryProfile::Low)
+                .build()
+                .unwrap();
+            assert_eq!(writer.compression_level(), level);
+            for i in 0..250 {
+                writer.write_row([i.to_string()]).unwrap();
+            }
+            let report = writer.save().unwrap();
+            assert_eq!(report.total_rows(), 250, "level {}", level);
+
+            let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+            let last = reader.rows("Sheet1").unwrap().last().unwrap().unwrap();
+            assert_eq!(last.cells[0], CellValue::String("249".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_add_sheet() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        writer.write_row(["Sheet1 Data"]).unwrap();
+        assert_eq!(writer.current_row(), 1);
+
+        writer.add_sheet("Sheet2").unwrap();
+        assert_eq!(writer.current_row(), 0);
+        assert_eq!(writer.current_sheet_name, "Sheet2");
+
+        writer.write_row(["Sheet2 Data"]).unwrap();
+        assert_eq!(writer.current_row(), 1);
+
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_write_header() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        writer.write_header(["ID", "Name", "Email"]).unwrap();
+        writer
+            .write_row(["1", "Alice", "alice@example.com"])
+            .unwrap();
+
+        assert_eq!(writer.current_row(), 2);
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_batch_write() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        let data = vec![
+            vec!["A1", "B1", "C1"],
+            vec!["A2", "B2", "C2"],
+            vec!["A3", "B3", "C3"],
+        ];
+
+        writer.write_rows_batch(&data).unwrap();
+        assert_eq!(writer.current_row(), 3);
+
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_formula_support() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        use crate::types::CellValue;
+
+        // Write header
+        writer.write_header(["Value 1", "Value 2", "Sum"]).unwrap();
+
+        // Write data with formula
+        writer
+            .write_row_typed(&[
+                CellValue::Int(10),
+                CellValue::Int(20),
+                CellValue::Formula("=A2+B2".to_string()),
+            ])
+            .unwrap();
+
+        writer
+            .write_row_typed(&[
+                CellValue::Int(15),
+                CellValue::Int(25),
+                CellValue::Formula("=A3+B3".to_string()),
+            ])
+            .unwrap();
+
+        // Add a summary row with SUM formula
+        writer
+            .write_row_typed(&[
+                CellValue::String("Total".to_string()),
+                CellValue::Empty,
+                CellValue::Formula("=SUM(C2:C3)".to_string()),
+            ])
+            .unwrap();
+
+        assert_eq!(writer.current_row(), 4);
+        assert!(writer.save().is_ok());
+    }
+
+    #[test]
+    fn test_save_report() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+        writer.write_row(["A", "B", "C"]).unwrap();
+        writer.write_row(["1", "2", "3"]).unwrap();
+        writer.add_sheet("Sheet2").unwrap();
+        writer
+            .write_row_typed(&[CellValue::Int(1), CellValue::Empty])
+            .unwrap();
+
+        let report = writer.save().unwrap();
+        assert_eq!(
+            report.per_sheet_rows,
+            vec![("Sheet1".to_string(), 2), ("Sheet2".to_string(), 1)]
+        );
+        assert_eq!(report.total_rows(), 3);
+        assert_eq!(report.total_cells, 8);
+        assert_eq!(
+            report.compressed_bytes,
+            std::fs::metadata(temp.path()).unwrap().len()
+        );
+        assert!(report.uncompressed_bytes > 0);
+    }
+
+    #[test]
+    fn test_float_format() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_float_format(FloatFormat::EXCEL)
+            .build()
+            .unwrap();
+        writer
+            .write_row_typed(&[
+                CellValue::Float(0.1 + 0.2),
+                CellValue::Float(1e21),
+                CellValue::Float(-2.5e-9),
+            ])
+            .unwrap();
+        writer.set_float_format(FloatFormat::default());
+        writer
+            .write_row_typed(&[CellValue::Float(0.1 + 0.2)])
+            .unwrap();
+        writer.save().unwrap();
+
+        let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows_by_index(0)
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(rows[0][0], CellValue::Float(0.3));
+        assert_eq!(rows[0][1], CellValue::Float(1e21));
+        assert_eq!(rows[0][2], CellValue::Float(-2.5e-9));
+        assert_eq!(rows[1][0], CellValue::Float(0.1 + 0.2));
+    }
+
+    #[test]
+    fn test_time_zone_policy() {
+        let temp = NamedTempFile::new().unwrap();
+        let dt = chrono::DateTime::parse_from_rfc3339("2024-01-31T09:00:00+07:00").unwrap();
+        let berlin = chrono::FixedOffset::east_opt(3600).unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_time_zone_policy(TimeZonePolicy::Convert(berlin))
+            .build()
+            .unwrap();
+        writer.write_row_typed(&[dt.into()]).unwrap();
+        writer.set_time_zone_policy(TimeZonePolicy::IsoString);
+        writer.write_row_typed(&[dt.into()]).unwrap();
+        writer.save().unwrap();
+
+        let options =
+            crate::ReaderOptions::new().with_type_inference(crate::infer::TypeInference::new());
+        let mut reader =
+            crate::streaming_reader::StreamingReader::open_with_options(temp.path(), options)
+                .unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(
+            rows[0][0].as_date().unwrap().to_string(),
+            "2024-01-31 03:00:00"
+        );
+        assert!(matches!(rows[1][0], CellValue::DateTimeTz(_)));
+        assert_eq!(rows[1][0].as_string(), "2024-01-31T09:00:00+07:00");
+    }
+
+    #[test]
+    fn test_duration_cells() {
+        use crate::streaming_reader::StreamingReader;
+        use std::time::Duration;
+
+        let temp = NamedTempFile::new().unwrap();
+        let shift = Duration::from_secs(36 * 3600 + 90);
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer
+            .write_row_typed(&[shift.into(), Duration::from_secs(48 * 3600).into()])
+            .unwrap();
+        writer
+            .write_row_styled(&[(shift.into(), CellStyle::NumberDecimal)])
+            .unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows[0][0].format.num_fmt.as_deref(), Some("[h]:mm:ss"));
+        assert_eq!(rows[0][0].value, CellValue::Duration(shift));
+        assert_eq!(rows[0][0].value.as_string(), "36:01:30");
+        assert_eq!(
+            rows[0][1].value,
+            CellValue::Duration(Duration::from_secs(48 * 3600))
+        );
+        assert_eq!(rows[1][0].value, CellValue::Float(1.5010416666666666));
+    }
+
+    #[test]
+    fn test_percent_cells() {
+        use crate::streaming_reader::StreamingReader;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer
+            .write_row_typed(&[CellValue::Percent(15.0), CellValue::Percent(f64::NAN)])
+            .unwrap();
+        writer
+            .write_row_styled(&[(CellValue::Percent(12.5), CellStyle::NumberDecimal)])
+            .unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows[0][0].value, CellValue::Float(0.15));
+        assert_eq!(
+            rows[0][0].format.num_fmt.as_deref(),
+            crate::numfmt::builtin_format_code(9)
+        );
+        assert_eq!(rows[0][1].value, CellValue::Error("#NUM!".to_string()));
+        assert_eq!(rows[1][0].value, CellValue::Float(0.125));
+        assert_eq!(rows[1][0].format.num_fmt.as_deref(), Some("#,##0.00"));
+        assert_eq!(CellValue::Percent(15.0).as_string(), "15%");
+    }
+
+    #[test]
+    fn test_memory_instrumentation() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_flush_policy(FlushPolicy::new(4096))
+            .with_memory_instrumentation(true)
+            .build()
+            .unwrap();
+        for i in 0..200 {
+            writer.write_row([i.to_string(), "x".repeat(50)]).unwrap();
+        }
+        let report = writer.save().unwrap();
+        let memory = report.memory.unwrap();
+        assert!(memory.peak_batch_buffer >= 4096 / 4 * 3);
+        assert!(memory.peak_buffers >= memory.peak_batch_buffer);
+        assert!(memory.zip_entries >= 8);
+        assert!(memory.largest_entry_bytes > 200 * 50);
+        assert!(memory.largest_entry_bytes < report.uncompressed_bytes);
+        assert!(memory.styles > 0);
+
+        let temp = NamedTempFile::new().unwrap();
+        let writer = ExcelWriter::new(temp.path()).unwrap();
+        assert_eq!(writer.save().unwrap().memory, None);
+    }
+
+    #[test]
+    fn test_rich_text_cells() {
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
+        use crate::types::RichText;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        let status = RichText::new()
+            .bold("Error: ")
+            .colored("disk <full>", "#c00000");
+        writer
+            .write_row_typed(&[CellValue::Int(1), status.clone().into()])
+            .unwrap();
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(
+            r#"<is><r><rPr><b/></rPr><t xml:space="preserve">Error: </t></r><r><rPr><color rgb="FFC00000"/></rPr><t xml:space="preserve">disk &lt;full&gt;</t></r></is>"#
+        ));
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let row = reader.rows("Sheet1").unwrap().next().unwrap().unwrap();
+        assert_eq!(row.cells[1], CellValue::from("Error: disk <full>"));
+        assert_eq!(CellValue::from(status).as_string(), "Error: disk <full>");
+    }
+
+    #[test]
+    fn test_write_table_header() {
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
+        use crate::types::Column;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer
+            .write_table_header(&[
+                Column::new("Item").width(30.0),
+                Column::new("Amount")
+                    .width(14.0)
+                    .style(CellStyle::NumberCurrency)
+                    .fmt("#,##0.00"),
+                Column::new("Note").header_style(CellStyle::TextItalic),
+            ])
+            .unwrap();
+        writer
+            .write_row_typed(&["Widget".into(), CellValue::Float(1234.5)])
+            .unwrap();
+        writer
+            .write_row_styled(&[
+                ("Refund".into(), CellStyle::Default),
+                (CellValue::Float(-3.0), CellStyle::NumberInteger),
+            ])
+            .unwrap();
+        assert!(matches!(
+            writer.write_table_header(&[Column::new("Late")]),
+            Err(crate::error::ExcelError::InvalidState(_))
+        ));
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(
+            r#"<cols><col min="1" max="1" width="30" customWidth="1"/><col min="2" max="2" width="14" customWidth="1"/></cols><sheetData>"#
+        ));
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert!(rows[0][1].format.bold);
+        assert!(rows[0][2].format.italic);
+        assert_eq!(rows[1][0].format.style_index, 0);
+        assert_eq!(rows[1][1].value, CellValue::Float(1234.5));
+        assert_eq!(rows[1][1].format.num_fmt.as_deref(), Some("#,##0.00"));
+        assert_eq!(rows[2][1].format.num_fmt_id, 3);
+    }
+
+    #[test]
+    fn test_throttle() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_throttle(Throttle::RowsPerSec(200))
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+        for i in 0..20 {
+            writer.write_row([i.to_string()]).unwrap();
+        }
+        assert!(started.elapsed() >= std::time::Duration::from_millis(90));
+
+        // 1000 rows would take 5 seconds at the old rate
+        writer.set_throttle(None);
+        let started = std::time::Instant::now();
+        for i in 0..1000 {
+            writer.write_row([i.to_string()]).unwrap();
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        writer.save().unwrap();
+
+        let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+        assert_eq!(reader.rows_by_index(0).unwrap().count(), 1020);
     }
 
-    /// Set maximum buffer size
-    pub fn with_max_buffer_size(mut self, size: usize) -> Self {
-        self.max_buffer_size = Some(size);
-        self
+    #[test]
+    fn test_limit_policy() {
+        use crate::types::{MAX_FORMULA_LEN, MAX_TEXT_LEN};
+        use crate::ExcelError;
+
+        let long = "x".repeat(MAX_TEXT_LEN + 1);
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer.write_row(["ok"]).unwrap();
+        let err = writer.write_row(["a", long.as_str()]).unwrap_err();
+        assert!(matches!(
+            err,
+            ExcelError::LimitExceeded { ref what, limit: 32_767 } if what == "Cell B2 text length"
+        ));
+        let formula = format!("=LEN(\"{}\")", "y".repeat(MAX_FORMULA_LEN));
+        let err = writer
+            .write_row_typed(&[CellValue::Formula(formula.clone())])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExcelError::LimitExceeded { limit: 8_192, .. }
+        ));
+        writer.write_row(["next"]).unwrap();
+
+        // Truncating cuts text but still refuses broken formulas
+        writer.set_limit_policy(LimitPolicy::Truncate);
+        writer.write_row(["b", long.as_str()]).unwrap();
+        assert!(writer
+            .write_row_typed(&[CellValue::Formula(formula)])
+            .is_err());
+        writer.save().unwrap();
+
+        let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows_by_index(0)
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], ["next"]);
+        assert_eq!(rows[2][1].chars().count(), MAX_TEXT_LEN);
     }
 
-    /// Build the writer
-    pub fn build(self) -> Result<ExcelWriter> {
-        let mut inner = UltraLowMemoryWorkbook::new(&self.path)?;
+    #[test]
+    fn test_locale_round_trip() {
+        use crate::streaming_reader::StreamingReader;
 
-        let sheet_name = self
-            .default_sheet_name
-            .unwrap_or_else(|| "Sheet1".to_string());
-        inner.add_worksheet(&sheet_name)?;
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_locale(Locale::DE_DE)
+            .build()
+            .unwrap();
+        writer
+            .write_row_styled(&[
+                (CellValue::DateTime(45322.0), CellStyle::DateDefault),
+                (CellValue::DateTime(45322.5), CellStyle::DateTimestamp),
+            ])
+            .unwrap();
+        writer.write_row(["1.234,5", "31.01.2024"]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let styled: Vec<_> = reader
+            .rows_with_style("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(styled[0][0].format.num_fmt.as_deref(), Some("dd.mm.yyyy"));
+        assert_eq!(
+            styled[0][1].format.num_fmt.as_deref(),
+            Some("dd.mm.yyyy hh:mm:ss")
+        );
+
+        let options = crate::ReaderOptions::new().with_locale(&Locale::DE_DE);
+        let mut reader = StreamingReader::open_with_options(temp.path(), options).unwrap();
+        let rows: Vec<_> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().cells)
+            .collect();
+        assert_eq!(
+            rows[1],
+            vec![CellValue::Float(1234.5), CellValue::DateTime(45322.0)]
+        );
+    }
 
-        let mut writer = ExcelWriter {
-            inner,
-            current_row: 0,
-            current_sheet_name: sheet_name,
+    #[test]
+    fn test_header_footer_pictures_round_trip() {
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
+        use crate::types::{HeaderFooter, HeaderFooterImage, HeaderFooterPosition};
+
+        let logo = HeaderFooterImage::new(
+            HeaderFooterPosition::LeftHeader,
+            b"\x89PNG fake".to_vec(),
+            "png",
+            120.0,
+            40.5,
+        );
+        let stamp = HeaderFooterImage::new(
+            HeaderFooterPosition::RightFooter,
+            b"\xff\xd8 fake".to_vec(),
+            "jpeg",
+            60.0,
+            60.0,
+        );
+        let print = HeaderFooter::new()
+            .with_header("&L&G&CQ&&A Report")
+            .with_footer("&CPage &P of &N&R&G")
+            .with_image(logo)
+            .with_image(stamp);
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        assert!(writer
+            .set_header_footer(HeaderFooter::new().with_image(print.images[0].clone()))
+            .is_err());
+        writer.set_header_footer(print.clone()).unwrap();
+        writer.write_row(["Data"]).unwrap();
+        writer.add_sheet("Plain").unwrap();
+        writer
+            .set_header_footer(HeaderFooter::new().with_footer("&A"))
+            .unwrap();
+        writer.add_sheet("None").unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.header_footer("Sheet1").unwrap(), Some(print));
+        assert_eq!(
+            reader.header_footer("Plain").unwrap(),
+            Some(HeaderFooter::new().with_footer("&A"))
+        );
+        assert_eq!(reader.header_footer("None").unwrap(), None);
+        assert_eq!(
+            reader
+                .rows("Sheet1")
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap()
+                .cells,
+            vec![CellValue::String("Data".to_string())]
+        );
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let types =
+            String::from_utf8(zip.read_entry_by_name("[Content_Types].xml").unwrap()).unwrap();
+        assert!(types.contains(r#"<Default Extension="vml""#));
+        assert!(types.contains(r#"<Default Extension="jpeg" ContentType="image/jpeg"/>"#));
+    }
+
+    #[test]
+    fn test_calc_settings_round_trip() {
+        use crate::streaming_reader::StreamingReader;
+        use crate::types::CalcMode;
+
+        let temp = NamedTempFile::new().unwrap();
+        let settings = CalcSettings::new()
+            .with_mode(CalcMode::Manual)
+            .with_full_calc_on_load(true)
+            .with_iteration(50, 0.01);
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_calc_settings(settings.clone())
+            .build()
+            .unwrap();
+        writer.write_row(["1"]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.calc_settings().unwrap(), Some(settings));
+
+        let plain = NamedTempFile::new().unwrap();
+        ExcelWriter::new(plain.path()).unwrap().save().unwrap();
+        let mut reader = StreamingReader::open(plain.path()).unwrap();
+        assert_eq!(reader.calc_settings().unwrap(), None);
+        assert_eq!(
+            CalcSettings::new().with_full_calc_on_load(true).to_xml(),
+            r#"<calcPr fullCalcOnLoad="1"/>"#
+        );
+    }
+
+    #[test]
+    fn test_error_cells_and_non_finite_floats() {
+        use crate::streaming_reader::StreamingReader;
+        use crate::types::NonFinite;
+
+        assert_eq!(CellValue::error_code("n/a"), Some("#N/A"));
+        assert_eq!(CellValue::error_code(" #div/0! "), Some("#DIV/0!"));
+        assert_eq!(CellValue::error_code("#OOPS"), None);
+
+        let row = [
+            CellValue::Float(f64::NAN),
+            CellValue::Float(f64::NEG_INFINITY),
+            CellValue::Error("n/a".to_string()),
+            CellValue::Error("oops".to_string()),
+            CellValue::Int(1),
+        ];
+        let read_back = |non_finite: Option<NonFinite>| {
+            let temp = NamedTempFile::new().unwrap();
+            let mut builder = ExcelWriterBuilder::new(temp.path());
+            if let Some(policy) = non_finite {
+                builder = builder.with_float_format(FloatFormat::default().non_finite(policy));
+            }
+            let mut writer = builder.build().unwrap();
+            writer.write_row_typed(&row).unwrap();
+            writer.save().unwrap();
+            let mut reader = StreamingReader::open(temp.path()).unwrap();
+            let cells = reader
+                .rows("Sheet1")
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap()
+                .cells;
+            cells
         };
 
-        if let Some(interval) = self.flush_interval {
-            writer.set_flush_interval(interval);
-        }
+        assert_eq!(
+            read_back(None),
+            vec![
+                CellValue::Error("#NUM!".to_string()),
+                CellValue::Error("#DIV/0!".to_string()),
+                CellValue::Error("#N/A".to_string()),
+                CellValue::String("oops".to_string()),
+                CellValue::Int(1),
+            ]
+        );
+        assert_eq!(
+            read_back(Some(NonFinite::Empty))[..2],
+            [CellValue::Empty, CellValue::Empty]
+        );
+        assert_eq!(
+            read_back(Some(NonFinite::String))[..2],
+            [
+                CellValue::String("NaN".to_string()),
+                CellValue::String("-inf".to_string())
+            ]
+        );
+    }
 
-        if let Some(size) = self.max_buffer_size {
-            writer.set_max_buffer_size(size);
+    #[test]
+    fn test_signed_workbook_round_trip() {
+        use crate::signature::{DigestStatus, Signer};
+        use crate::streaming_reader::StreamingReader;
+
+        struct TestSigner;
+        impl Signer for TestSigner {
+            fn certificate(&self) -> Vec<u8> {
+                b"certificate".to_vec()
+            }
+            fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+                assert!(data.starts_with(b"<SignedInfo xmlns="));
+                Ok(vec![1, 2, 3])
+            }
         }
 
-        Ok(writer)
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed.xlsx");
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        writer.set_signer(TestSigner).unwrap();
+        writer.write_row(["id", "name"]).unwrap();
+        writer.write_row(["1", "Alice"]).unwrap();
+        assert!(writer.set_signer(TestSigner).is_err());
+        writer.add_sheet("Second").unwrap();
+        writer.write_row(["x"]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        let signatures = reader.signatures().unwrap();
+        assert_eq!(signatures.len(), 1);
+        let signature = &signatures[0];
+        assert_eq!(signature.part, "_xmlsignatures/sig1.xml");
+        assert_eq!(signature.certificate.as_deref(), Some(&b"certificate"[..]));
+        assert_eq!(signature.signature_value, vec![1, 2, 3]);
+        assert!(signature.signature_method.ends_with("#rsa-sha256"));
+        assert!(signature.signing_time.is_some());
+        assert_eq!(signature.mismatched_parts().count(), 0);
+        let checked: Vec<&str> = signature
+            .references
+            .iter()
+            .filter(|r| r.status == DigestStatus::Matches)
+            .map(|r| r.uri.split('?').next().unwrap())
+            .collect();
+        for part in [
+            "/xl/workbook.xml",
+            "/xl/worksheets/sheet1.xml",
+            "/xl/worksheets/sheet2.xml",
+        ] {
+            assert!(checked.contains(&part), "{} not signed", part);
+        }
+        assert_eq!(reader.rows("Sheet1").unwrap().count(), 2);
+
+        let plain = dir.path().join("plain.xlsx");
+        ExcelWriter::new(&plain).unwrap().save().unwrap();
+        assert!(StreamingReader::open(&plain)
+            .unwrap()
+            .signatures()
+            .unwrap()
+            .is_empty());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::NamedTempFile;
 
     #[test]
-    fn test_writer_creation() {
-        let temp = NamedTempFile::new().unwrap();
-        let writer = ExcelWriter::new(temp.path());
-        assert!(writer.is_ok());
+    fn test_vba_project_round_trip() {
+        use crate::append::AppendableExcelWriter;
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
+
+        let mut project = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        project.extend_from_slice(b"ThisWorkbook module");
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut writer = ExcelWriter::new(dir.path().join("plain.xlsx")).unwrap();
+        assert!(matches!(
+            writer.attach_vba_project(project.clone()),
+            Err(crate::ExcelError::InvalidState(_))
+        ));
+
+        let path = dir.path().join("macros.xlsm");
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        assert!(matches!(
+            writer.attach_vba_project(b"not a project".to_vec()),
+            Err(crate::ExcelError::InvalidFormat(_))
+        ));
+        writer.attach_vba_project(project.clone()).unwrap();
+        writer.write_row(["id"]).unwrap();
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(&path).unwrap();
+        let content_types =
+            String::from_utf8(zip.read_entry_by_name("[Content_Types].xml").unwrap()).unwrap();
+        assert!(content_types.contains(
+            r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.ms-excel.sheet.macroEnabled.main+xml"/>"#
+        ));
+        assert!(content_types.contains(
+            r#"<Default Extension="bin" ContentType="application/vnd.ms-office.vbaProject"/>"#
+        ));
+        let mut reader = StreamingReader::open(&path).unwrap();
+        assert_eq!(reader.vba_project().unwrap(), Some(project.clone()));
+
+        // Appending rows keeps the macros
+        let mut appender = AppendableExcelWriter::open(&path).unwrap();
+        appender.select_sheet("Sheet1").unwrap();
+        appender.append_row(["2"]).unwrap();
+        appender.save().unwrap();
+        let mut reader = StreamingReader::open(&path).unwrap();
+        assert_eq!(reader.vba_project().unwrap(), Some(project));
+        assert_eq!(reader.rows("Sheet1").unwrap().count(), 2);
+
+        let plain = dir.path().join("plain.xlsx");
+        ExcelWriter::new(&plain).unwrap().save().unwrap();
+        assert_eq!(
+            StreamingReader::open(&plain)
+                .unwrap()
+                .vba_project()
+                .unwrap(),
+            None
+        );
+    }
 
-        // Should be able to save immediately
-        let writer = writer.unwrap();
-        assert!(writer.save().is_ok());
+    #[test]
+    fn test_manifest_sidecar_and_embedded() {
+        use crate::manifest::EMBEDDED_PROPERTY;
+        use crate::streaming_reader::StreamingReader;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("delivery.xlsx");
+        let mut writer = ExcelWriterBuilder::new(&path)
+            .with_sheet_name("Orders")
+            .with_manifest(ManifestOptions::new().with_embedded(true))
+            .build()
+            .unwrap();
+        writer.write_row(["id"]).unwrap();
+        writer.write_row(["1"]).unwrap();
+        writer.add_sheet("Notes & more").unwrap();
+        writer.write_row(["n"]).unwrap();
+        let report = writer.save().unwrap();
+
+        let manifest = report.manifest.unwrap();
+        assert_eq!(manifest.file.as_deref(), Some("delivery.xlsx"));
+        assert_eq!(manifest.size, Some(report.compressed_bytes));
+        assert_eq!(manifest.total_rows(), 3);
+        manifest.verify(&path).unwrap();
+        let sidecar =
+            std::fs::read_to_string(dir.path().join("delivery.xlsx.manifest.json")).unwrap();
+        assert_eq!(sidecar, manifest.to_json());
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        let properties = reader.custom_properties().unwrap();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].0, EMBEDDED_PROPERTY);
+        assert!(properties[0].1.starts_with(
+            r#"{"sheets":[{"name":"Orders","rows":2},{"name":"Notes & more","rows":1}],"total_rows":3,"#
+        ));
+
+        let plain = dir.path().join("plain.xlsx");
+        let report = ExcelWriter::new(&plain).unwrap().save().unwrap();
+        assert!(report.manifest.is_none());
+        assert!(StreamingReader::open(&plain)
+            .unwrap()
+            .custom_properties()
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn test_write_row() {
+    fn test_sheet_visibility_order_and_active_tab() {
+        use crate::streaming_reader::StreamingReader;
+
         let temp = NamedTempFile::new().unwrap();
         let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer.write_row(["detail"]).unwrap();
+        writer.add_sheet("Lookup").unwrap();
+        writer.write_row(["code"]).unwrap();
+        writer.add_sheet("Summary").unwrap();
+        writer.write_row(["total"]).unwrap();
 
-        assert!(writer.write_row(["A", "B", "C"]).is_ok());
-        assert!(writer.write_row(["1", "2", "3"]).is_ok());
-        assert_eq!(writer.current_row(), 2);
-
-        assert!(writer.save().is_ok());
+        writer
+            .set_sheet_visibility("Lookup", SheetVisibility::VeryHidden)
+            .unwrap();
+        assert!(writer.set_active_sheet("Lookup").is_err());
+        assert!(writer.set_active_sheet("Missing").is_err());
+        writer.set_active_sheet("Summary").unwrap();
+        assert!(writer
+            .set_sheet_visibility("Summary", SheetVisibility::Hidden)
+            .is_err());
+        assert!(writer.set_sheet_order(&["Summary", "Summary"]).is_err());
+        writer.set_sheet_order(&["Summary"]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.sheet_names(), vec!["Summary", "Sheet1", "Lookup"]);
+        assert_eq!(reader.active_sheet().unwrap().as_deref(), Some("Summary"));
+        assert_eq!(
+            reader.sheet_visibility("Lookup").unwrap(),
+            SheetVisibility::VeryHidden
+        );
+        assert_eq!(
+            reader.sheet_visibility("Sheet1").unwrap(),
+            SheetVisibility::Visible
+        );
+        let rows: Vec<_> = reader.rows("Lookup").unwrap().collect();
+        assert_eq!(rows.len(), 1);
+
+        // A hidden first tab moves the default active tab to the next one
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer.add_sheet("Shown").unwrap();
+        writer
+            .set_sheet_visibility("Sheet1", SheetVisibility::Hidden)
+            .unwrap();
+        assert!(writer
+            .set_sheet_visibility("Shown", SheetVisibility::Hidden)
+            .is_err());
+        writer.save().unwrap();
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.active_sheet().unwrap().as_deref(), Some("Shown"));
     }
 
     #[test]
-    fn test_write_row_typed() {
+    fn test_exact_dimensions() {
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
+
         let temp = NamedTempFile::new().unwrap();
-        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_exact_dimensions(true)
+            .with_flush_interval(3)
+            .build()
+            .unwrap();
+        writer.write_row(["a", "b"]).unwrap();
+        for i in 0..10 {
+            writer
+                .write_row_typed(&[CellValue::Int(i), CellValue::Empty, CellValue::Bool(true)])
+                .unwrap();
+        }
+        writer.write_row(Vec::<String>::new()).unwrap();
+        writer.add_sheet("Empty").unwrap();
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(r#"<dimension ref="A1:C12"/><sheetData><row r="1" spans="1:2">"#));
+        assert!(sheet.contains(r#"<row r="2" spans="1:3">"#));
+        assert!(sheet.contains(r#"<row r="12"></row>"#));
+        let empty =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet2.xml").unwrap()).unwrap();
+        assert!(empty.contains(r#"<dimension ref="A1"/><sheetData></sheetData>"#));
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 12);
+        assert_eq!(reader.rows("Sheet1").unwrap().count(), 12);
+    }
 
-        use crate::types::CellValue;
-        let row = vec![
-            CellValue::String("Text".to_string()),
-            CellValue::Int(42),
-            CellValue::Float(1234.56),
-            CellValue::Bool(true),
-        ];
+    #[test]
+    fn test_full_width_rows() {
+        use crate::cellref::MAX_COLS;
+        use crate::error::ExcelError;
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
 
-        assert!(writer.write_row_typed(&row).is_ok());
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_exact_dimensions(true)
+            .build()
+            .unwrap();
+        let wide: Vec<CellValue> = (0..MAX_COLS as i64).map(CellValue::Int).collect();
+        writer.write_row_typed(&wide).unwrap();
+
+        // One column too many is refused and leaves no trace
+        let too_wide = vec!["x"; MAX_COLS as usize + 1];
+        assert!(matches!(
+            writer.write_row(&too_wide),
+            Err(ExcelError::LimitExceeded { limit: 16_384, .. })
+        ));
+        let mut too_wide_typed = wide.clone();
+        too_wide_typed.push(CellValue::Empty);
+        assert!(writer.write_row_typed(&too_wide_typed).is_err());
         assert_eq!(writer.current_row(), 1);
-
-        assert!(writer.save().is_ok());
+        writer.write_row(["next"]).unwrap();
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let sheet =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(r#"<dimension ref="A1:XFD2"/>"#));
+        assert!(sheet.contains(r#"<row r="1" spans="1:16384">"#));
+        assert!(sheet.contains(r#"<c r="XFD1" t="n"><v>16383</v></c></row>"#));
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader.rows("Sheet1").unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(rows[0].cells, wide);
+        assert_eq!(rows[1].to_strings(), vec!["next"]);
     }
 
     #[test]
-    fn test_builder() {
+    fn test_strict_conformance() {
+        use crate::fast_writer::StreamingZipReader;
+        use crate::streaming_reader::StreamingReader;
+
         let temp = NamedTempFile::new().unwrap();
-        let writer = ExcelWriterBuilder::new(temp.path())
-            .with_sheet_name("CustomSheet")
-            .with_flush_interval(500)
-            .with_max_buffer_size(512 * 1024)
-            .build();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_strict(true)
+            .build()
+            .unwrap();
+        writer.write_row(["name", "qty"]).unwrap();
+        writer
+            .write_row_typed(&[CellValue::String("pear".into()), CellValue::Int(4)])
+            .unwrap();
+        writer.add_sheet("Second").unwrap();
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let mut part = |name| String::from_utf8(zip.read_entry_by_name(name).unwrap()).unwrap();
+        let workbook = part("xl/workbook.xml");
+        assert!(workbook.contains(
+            r#"<workbook conformance="strict" xmlns="http://purl.oclc.org/ooxml/spreadsheetml/main" xmlns:r="http://purl.oclc.org/ooxml/officeDocument/relationships">"#
+        ));
+        assert!(part("xl/_rels/workbook.xml.rels").contains(
+            r#"Type="http://purl.oclc.org/ooxml/officeDocument/relationships/worksheet""#
+        ));
+        for name in [
+            "_rels/.rels",
+            "xl/worksheets/sheet1.xml",
+            "xl/worksheets/sheet2.xml",
+            "xl/styles.xml",
+            "docProps/app.xml",
+        ] {
+            assert!(
+                !part(name).contains("schemas.openxmlformats.org/spreadsheetml")
+                    && !part(name).contains("schemas.openxmlformats.org/officeDocument"),
+                "{} has transitional markup",
+                name
+            );
+        }
 
-        assert!(writer.is_ok());
-        let writer = writer.unwrap();
-        assert_eq!(writer.current_sheet_name, "CustomSheet");
-        assert!(writer.save().is_ok());
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.sheet_names(), vec!["Sheet1", "Second"]);
+        let rows: Vec<Vec<String>> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(rows, vec![vec!["name", "qty"], vec!["pear", "4"]]);
     }
 
     #[test]
-    fn test_add_sheet() {
+    fn test_index_sheet() {
+        use crate::streaming_reader::StreamingReader;
+        use crate::types::CellRange;
+
         let temp = NamedTempFile::new().unwrap();
-        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_sheet_name("Index")
+            .build()
+            .unwrap();
+        writer.generate_index_sheet(IndexSheetOptions::new().with_header_rows(1));
+        writer.write_row(["Region"]).unwrap();
+        writer.write_row(["North"]).unwrap();
+        writer.write_row(["South"]).unwrap();
+        writer.add_sheet("Bob's Costs").unwrap();
+        writer.write_row(["Item"]).unwrap();
+        let report = writer.save().unwrap();
+        assert_eq!(report.per_sheet_rows[0], ("Index (2)".to_string(), 3));
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(reader.sheet_names(), ["Index (2)", "Index", "Bob's Costs"]);
+        let rows: Vec<_> = reader
+            .rows("Index (2)")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Sheet", "Rows"],
+                vec!["Index", "2"],
+                vec!["Bob's Costs", "0"]
+            ]
+        );
+        let links = reader.hyperlinks("Index (2)").unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].0, CellRange::new(2, 0, 2, 0));
+        assert_eq!(links[1].1.location.as_deref(), Some("'Bob''s Costs'!A1"));
+    }
 
-        writer.write_row(["Sheet1 Data"]).unwrap();
-        assert_eq!(writer.current_row(), 1);
+    #[test]
+    fn test_audit_sheet() {
+        use crate::streaming_reader::StreamingReader;
 
-        writer.add_sheet("Sheet2").unwrap();
-        assert_eq!(writer.current_row(), 0);
-        assert_eq!(writer.current_sheet_name, "Sheet2");
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriterBuilder::new(temp.path())
+            .with_audit(AuditOptions::new().with_host("export-7"))
+            .build()
+            .unwrap();
+        writer.generate_index_sheet(IndexSheetOptions::new());
+        writer.write_row(["Region", "Amount"]).unwrap();
+        writer.write_row(["North", "10"]).unwrap();
+        writer.add_sheet("Costs").unwrap();
+        writer.write_row(["Region", "Amount"]).unwrap();
+        writer.add_sheet("Empty").unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        assert_eq!(
+            reader.sheet_names(),
+            ["Index", "Sheet1", "Costs", "Empty", "_audit"]
+        );
+        assert_eq!(
+            reader.sheet_visibility("_audit").unwrap(),
+            SheetVisibility::Hidden
+        );
+        let index: Vec<_> = reader
+            .rows("Index")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings()[0].clone())
+            .collect();
+        assert_eq!(index, ["Sheet", "Sheet1", "Costs", "Empty"]);
 
-        writer.write_row(["Sheet2 Data"]).unwrap();
-        assert_eq!(writer.current_row(), 1);
+        let rows: Vec<_> = reader
+            .rows("_audit")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(rows[0], ["Sheet", "Rows", "Cells", "Schema hash"]);
+        assert_eq!(rows[1][..3], ["Sheet1", "2", "4"]);
+        assert_eq!(rows[2][..3], ["Costs", "1", "2"]);
+        assert_eq!(rows[1][3].len(), 64);
+        assert_eq!(rows[1][3], rows[2][3]); // Same header row
+        assert_eq!(rows[3], ["Empty", "0", "0"]);
+        let labels: Vec<_> = rows[rows.len() - 4..]
+            .iter()
+            .map(|r| r[0].as_str())
+            .collect();
+        assert_eq!(
+            labels,
+            ["Generated at", "Duration (ms)", "Host", "Library version"]
+        );
+        assert_eq!(rows[rows.len() - 2][1], "export-7");
+    }
 
-        assert!(writer.save().is_ok());
+    #[test]
+    fn test_zip_layout() {
+        use crate::append::splice::entry_data;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ranged.xlsx");
+        let mut writer = ExcelWriterBuilder::new(&path)
+            .with_zip_layout(
+                ZipLayout::new()
+                    .with_metadata_first(true)
+                    .with_alignment(64),
+            )
+            .with_temp_dir(dir.path())
+            .with_manifest(ManifestOptions::new())
+            .build()
+            .unwrap();
+        for i in 0..500 {
+            writer
+                .write_row([i.to_string(), format!("row {}", i)])
+                .unwrap();
+        }
+        let report = writer.save().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(report.compressed_bytes, bytes.len() as u64);
+        assert_eq!(&bytes[30..49], b"[Content_Types].xml");
+        let mut file = std::fs::File::open(&path).unwrap();
+        let content_types = entry_data(&mut file, "[Content_Types].xml").unwrap();
+        let workbook = entry_data(&mut file, "xl/workbook.xml").unwrap();
+        let sheet = entry_data(&mut file, "xl/worksheets/sheet1.xml").unwrap();
+        assert!(content_types.offset < workbook.offset && workbook.offset < sheet.offset);
+        for data in [content_types, workbook, sheet] {
+            assert_eq!(data.offset % 64, 0);
+        }
+        report.manifest.unwrap().verify(&path).unwrap();
+
+        let mut reader = crate::ExcelReader::open(&path).unwrap();
+        let rows: Vec<_> = reader.rows("Sheet1").unwrap().collect();
+        assert_eq!(rows.len(), 500);
+        assert_eq!(rows[499].as_ref().unwrap().to_strings(), ["499", "row 499"]);
+        // Only the final file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
     }
 
     #[test]
-    fn test_write_header() {
+    fn test_sheet_finish_hooks() {
+        use crate::fast_writer::StreamingZipReader;
+
         let temp = NamedTempFile::new().unwrap();
         let mut writer = ExcelWriter::new(temp.path()).unwrap();
-
-        writer.write_header(["ID", "Name", "Email"]).unwrap();
+        writer.add_middleware(|_: &mut Vec<CellValue>| None); // Drops data rows only
+        writer.on_sheet_finish(|sheet| {
+            if let Some(range) = sheet.used_range() {
+                sheet.set_auto_filter(&range)?;
+            }
+            Ok(())
+        });
+        writer.on_sheet_finish(|sheet| {
+            let rows = sheet.rows();
+            sheet.write_row_typed(&[
+                CellValue::String(format!("{} rows", rows)),
+                CellValue::Formula(format!("SUM(B2:B{})", rows)),
+            ])
+        });
         writer
-            .write_row(["1", "Alice", "alice@example.com"])
+            .write_header_bold(["Region", "Amount", "Note"])
             .unwrap();
+        writer.write_row(["ignored"]).unwrap();
+        writer.add_sheet("Empty").unwrap();
+        writer.save().unwrap();
+
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let sheet1 =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet1.contains("</sheetData><autoFilter ref=\"A1:C1\"/>"));
+        assert!(sheet1.contains("<c r=\"A2\" t=\"inlineStr\"><is><t>1 rows</t>"));
+        assert!(sheet1.contains("<f>SUM(B2:B1)</f>"));
+        let sheet2 =
+            String::from_utf8(zip.read_entry_by_name("xl/worksheets/sheet2.xml").unwrap()).unwrap();
+        assert!(!sheet2.contains("autoFilter"));
+        assert!(sheet2.contains("<t>0 rows</t>"));
 
-        assert_eq!(writer.current_row(), 2);
-        assert!(writer.save().is_ok());
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        assert!(writer.set_auto_filter("not a range").is_err());
+        writer.on_sheet_finish(|_| Err(crate::ExcelError::WriteError("hook".to_string())));
+        assert!(writer.add_sheet("Next").is_err());
     }
 
     #[test]
-    fn test_batch_write() {
-        let temp = NamedTempFile::new().unwrap();
-        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+    fn test_sort_by() {
+        use crate::sort::SortOptions;
 
-        let data = vec![
-            vec!["A1", "B1", "C1"],
-            vec!["A2", "B2", "C2"],
-            vec!["A3", "B3", "C3"],
-        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sorted.xlsx");
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        writer.write_header_bold(["Region", "Amount"]).unwrap();
+        writer
+            .sort_by(
+                SortOptions::by(0)
+                    .then_by_desc(1)
+                    .with_max_buffer_bytes(64)
+                    .with_temp_dir(dir.path()),
+            )
+            .unwrap();
+        for (region, amount) in [("west", 5), ("East", 1), ("", 9), ("east", 7), ("West", 5)] {
+            let region = match region {
+                "" => CellValue::Empty,
+                region => CellValue::from(region),
+            };
+            writer
+                .write_row_typed(&[region, CellValue::Int(amount)])
+                .unwrap();
+        }
+        assert_eq!(writer.current_row, 6);
+        writer.add_sheet("Plain").unwrap();
+        writer.write_row(["b"]).unwrap();
+        writer.write_row(["a"]).unwrap();
+        writer.save().unwrap();
+
+        let mut reader = StreamingReader::open(&path).unwrap();
+        let rows: Vec<String> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings().join(","))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "Region,Amount",
+                "east,7",
+                "East,1",
+                "west,5",
+                "West,5",
+                ",9"
+            ]
+        );
+        let rows: Vec<String> = reader
+            .rows("Plain")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings().join(","))
+            .collect();
+        assert_eq!(rows, ["b", "a"]);
+    }
 
-        writer.write_rows_batch(&data).unwrap();
-        assert_eq!(writer.current_row(), 3);
+    #[test]
+    fn test_dedupe_by() {
+        use crate::dedupe::Keep;
+        use crate::sort::SortOptions;
 
-        assert!(writer.save().is_ok());
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer.write_header_bold(["Id", "Status"]).unwrap();
+        writer.dedupe_by([0], Keep::Last).unwrap();
+        writer.sort_by(SortOptions::by(0)).unwrap();
+        for (id, status) in [("7", "new"), ("3", "new"), ("7", "paid"), ("3", "new")] {
+            writer.write_row([id, status]).unwrap();
+        }
+        writer.add_sheet("First").unwrap();
+        writer.dedupe_by([0, 1], Keep::First).unwrap();
+        for (id, status) in [("7", "new"), ("7", "paid"), ("7", "new")] {
+            writer.write_row([id, status]).unwrap();
+        }
+        assert_eq!(writer.current_row(), 2);
+        let report = writer.save().unwrap();
+        assert_eq!(report.duplicates_dropped, 3);
+        assert_eq!(report.per_sheet_rows[0].1, 3);
+
+        let mut reader = StreamingReader::open(temp.path()).unwrap();
+        let rows: Vec<String> = reader
+            .rows("Sheet1")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings().join(","))
+            .collect();
+        assert_eq!(rows, ["Id,Status", "3,new", "7,paid"]);
+        let rows: Vec<String> = reader
+            .rows("First")
+            .unwrap()
+            .map(|r| r.unwrap().to_strings().join(","))
+            .collect();
+        assert_eq!(rows, ["7,new", "7,paid"]);
     }
 
     #[test]
-    fn test_formula_support() {
+    fn test_protected_sheet_with_unlocked_cells() {
+        use crate::fast_writer::StreamingZipReader;
+        use crate::types::ProtectionOptions;
+
         let temp = NamedTempFile::new().unwrap();
         let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer
+            .protect_current_sheet("secret", ProtectionOptions::new())
+            .unwrap();
+        let input = writer.unlocked_style(CellStyle::NumberDecimal);
+        assert_eq!(writer.unlocked_style(CellStyle::NumberDecimal), input);
+        assert_eq!(
+            writer.unlocked_style(CellStyle::Default),
+            CellStyle::Unlocked
+        );
+        writer
+            .write_row_styled(&[
+                (CellValue::String("Qty".to_string()), CellStyle::HeaderBold),
+                (CellValue::Float(1.5), input),
+                (CellValue::Empty, CellStyle::Unlocked),
+            ])
+            .unwrap();
+        writer.add_sheet("Open").unwrap();
+        writer.save().unwrap();
 
-        use crate::types::CellValue;
+        let mut zip = StreamingZipReader::open(temp.path()).unwrap();
+        let read = |zip: &mut StreamingZipReader, name: &str| {
+            String::from_utf8(zip.read_entry_by_name(name).unwrap()).unwrap()
+        };
+        let sheet1 = read(&mut zip, "xl/worksheets/sheet1.xml");
+        assert!(sheet1.contains("<sheetProtection sheet=\"1\" password=\""));
+        assert!(sheet1.contains("<c r=\"B1\" s=\"16\""));
+        assert!(sheet1.contains("<c r=\"C1\" s=\"15\"/>"));
+        assert!(!read(&mut zip, "xl/worksheets/sheet2.xml").contains("sheetProtection"));
+        let styles = read(&mut zip, "xl/styles.xml");
+        assert!(styles.contains(
+            "<xf numFmtId=\"4\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\" applyProtection=\"1\"><protection locked=\"0\"/></xf>"
+        ));
+    }
 
-        // Write header
-        writer.write_header(["Value 1", "Value 2", "Sum"]).unwrap();
+    #[test]
+    fn test_formula_sanitization() {
+        use crate::fast_writer::cell_xml::is_formula_like;
+
+        assert!(is_formula_like("=1+1"));
+        assert!(is_formula_like("@SUM(A1)"));
+        assert!(is_formula_like("+cmd|' /C calc'!A0"));
+        assert!(is_formula_like("-2+3"));
+        assert!(!is_formula_like("-5"));
+        assert!(!is_formula_like("+1.5"));
+        assert!(!is_formula_like("a=b"));
+        assert!(!is_formula_like(""));
 
-        // Write data with formula
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
+        writer.enable_formula_sanitization();
+        writer.write_row(["=1+1", "-5"]).unwrap();
         writer
             .write_row_typed(&[
-                CellValue::Int(10),
-                CellValue::Int(20),
-                CellValue::Formula("=A2+B2".to_string()),
+                CellValue::String("@cmd".to_string()),
+                CellValue::Formula("=1+1".to_string()),
             ])
             .unwrap();
+        writer.save().unwrap();
+
+        let mut reader = crate::ExcelReader::open(temp.path()).unwrap();
+        let rows: Vec<_> = reader
+            .rows_by_index(0)
+            .unwrap()
+            .map(|r| r.unwrap().to_strings())
+            .collect();
+        assert_eq!(rows[0], vec!["'=1+1", "-5"]);
+        assert_eq!(rows[1][0], "'@cmd");
+    }
+
+    #[test]
+    fn test_currency_styles() {
+        use crate::numfmt::Currency;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut writer = ExcelWriter::new(temp.path()).unwrap();
 
         writer
-            .write_row_typed(&[
-                CellValue::Int(15),
-                CellValue::Int(25),
-                CellValue::Formula("=A3+B3".to_string()),
+            .write_row_styled(&[
+                (CellValue::Int(150000), CellStyle::Currency(Currency::VND)),
+                (CellValue::Float(12.5), CellStyle::Currency(Currency::EUR)),
             ])
             .unwrap();
 
-        // Add a summary row with SUM formula
+        let style = writer.register_number_format("0.000");
+        assert_eq!(style, CellStyle::NumberFormat(CellStyle::BUILTIN_COUNT + 2));
         writer
-            .write_row_typed(&[
-                CellValue::String("Total".to_string()),
-                CellValue::Empty,
-                CellValue::Formula("=SUM(C2:C3)".to_string()),
-            ])
+            .write_row_styled(&[(CellValue::Float(1.5), style)])
             .unwrap();
 
-        assert_eq!(writer.current_row(), 4);
         assert!(writer.save().is_ok());
     }
+
+    #[test]
+    fn test_builder_temp_dir() {
+        let out = tempfile::tempdir().unwrap();
+        let scratch = tempfile::tempdir().unwrap();
+        let target = out.path().join("report.xlsx");
+
+        // Dropped mid-write: no partial output anywhere
+        let mut writer = ExcelWriterBuilder::new(&target)
+            .with_temp_dir(scratch.path())
+            .build()
+            .unwrap();
+        writer.write_row(["a", "b"]).unwrap();
+        drop(writer);
+        assert!(!target.exists());
+        assert_eq!(std::fs::read_dir(scratch.path()).unwrap().count(), 0);
+
+        let mut writer = ExcelWriterBuilder::new(&target)
+            .with_temp_dir(scratch.path())
+            .build()
+            .unwrap();
+        writer.write_row(["a", "b"]).unwrap();
+        assert!(!target.exists());
+        writer.save().unwrap();
+        assert_eq!(std::fs::read_dir(scratch.path()).unwrap().count(), 0);
+
+        let mut reader = crate::ExcelReader::open(&target).unwrap();
+        assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_builder_required_space() {
+        let out = tempfile::tempdir().unwrap();
+        let target = out.path().join("big.xlsx");
+
+        let writer = ExcelWriterBuilder::new(&target)
+            .with_required_space(1024)
+            .build()
+            .unwrap();
+        writer.save().unwrap();
+
+        if staging::available_space(out.path()).is_some() {
+            let result = ExcelWriterBuilder::new(out.path().join("huge.xlsx"))
+                .with_required_space(u64::MAX)
+                .build();
+            assert!(matches!(
+                result,
+                Err(crate::ExcelError::DiskFull {
+                    needed: u64::MAX,
+                    ..
+                })
+            ));
+            assert!(!out.path().join("huge.xlsx").exists());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_disk_full_mid_write() {
+        // Every write to /dev/full fails with ENOSPC
+        let mut writer = match ExcelWriter::new("/dev/full") {
+            Ok(writer) => writer,
+            Err(_) => return,
+        };
+        let mut result = Ok(());
+        for _ in 0..10_000 {
+            result = writer.write_row(["some text", "more text", "12345"]);
+            if result.is_err() {
+                break;
+            }
+        }
+        let err = match result {
+            Err(err) => err,
+            Ok(()) => writer.save().unwrap_err(),
+        };
+        assert!(
+            matches!(err, crate::ExcelError::DiskFull { .. }),
+            "{:?}",
+            err
+        );
+        assert!(Path::new("/dev/full").exists());
+    }
 }
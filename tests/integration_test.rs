@@ -1,7 +1,7 @@
 //! Integration tests for rust-excelize
 
 use excelstream::types::CellValue;
-use excelstream::{ExcelReader, ExcelWriter};
+use excelstream::{ExcelError, ExcelReader, ExcelWriter};
 use tempfile::NamedTempFile;
 
 #[test]
@@ -106,6 +106,40 @@ fn test_multi_sheet() {
     }
 }
 
+#[cfg(feature = "parallel")]
+#[test]
+fn test_par_sheets() {
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+
+    {
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        for sheet in 1..=4 {
+            if sheet > 1 {
+                writer.add_sheet(&format!("Sheet{}", sheet)).unwrap();
+            }
+            for i in 0..sheet * 10 {
+                writer.write_row([i.to_string()]).unwrap();
+            }
+        }
+        writer.save().unwrap();
+    }
+
+    let mut reader = ExcelReader::open(&path).unwrap();
+    let counts = reader
+        .par_sheets(|_, rows| Ok(rows.collect::<Result<Vec<_>, _>>()?.len()))
+        .unwrap();
+    assert_eq!(
+        counts,
+        vec![
+            ("Sheet1".to_string(), 10),
+            ("Sheet2".to_string(), 20),
+            ("Sheet3".to_string(), 30),
+            ("Sheet4".to_string(), 40),
+        ]
+    );
+}
+
 #[test]
 fn test_large_dataset_streaming() {
     let temp = NamedTempFile::new().unwrap();
@@ -171,6 +205,202 @@ fn test_empty_cells() {
     }
 }
 
+#[test]
+fn test_decimal_roundtrip() {
+    use excelstream::{Decimal, ReaderOptions};
+
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+
+    {
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        writer
+            .write_row_typed(&[
+                CellValue::Decimal(Decimal::new(30, 2)),
+                CellValue::Decimal(Decimal::new(-123456789012345678, 6)),
+            ])
+            .unwrap();
+        writer.save().unwrap();
+    }
+
+    {
+        let options = ReaderOptions::new().with_numbers_as_decimal(true);
+        let mut reader = ExcelReader::open_with_options(&path, options).unwrap();
+        let rows: Vec<_> = reader
+            .rows_by_index(0)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            rows[0].get(0),
+            Some(&CellValue::Decimal(Decimal::new(30, 2)))
+        );
+        assert_eq!(rows[0].get(1).unwrap().as_string(), "-123456789012.345678");
+    }
+}
+
+// Write 1000 rows of ("row", index, padding) to `path`
+fn write_padded_rows(path: &str) {
+    let mut writer = ExcelWriter::new(path).unwrap();
+    for i in 0..1000 {
+        writer
+            .write_row(["row", &i.to_string(), "some padding text"])
+            .unwrap();
+    }
+    writer.save().unwrap();
+}
+
+#[test]
+fn test_read_limits() {
+    use excelstream::{ExcelError, ReadLimits, ReaderOptions};
+
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+    write_padded_rows(&path);
+
+    // Workbook parts are tiny, the worksheet is not
+    let limits = ReadLimits {
+        max_entry_size: 8 * 1024,
+        ..ReadLimits::default()
+    };
+    let mut reader =
+        ExcelReader::open_with_options(&path, ReaderOptions::new().with_limits(limits)).unwrap();
+    // Rejected up front from the ZIP directory, or while decompressing
+    let result = reader
+        .rows_by_index(0)
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>());
+    assert!(matches!(
+        result,
+        Err(ExcelError::LimitExceeded { limit: 8192, .. })
+    ));
+
+    // Defaults accept normal files
+    let mut reader = ExcelReader::open(&path).unwrap();
+    assert_eq!(reader.rows_by_index(0).unwrap().count(), 1000);
+}
+
+#[test]
+fn test_row_count_fast() {
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+    write_padded_rows(&path);
+
+    let mut reader = ExcelReader::open(&path).unwrap();
+    assert_eq!(reader.row_count_fast("Sheet1").unwrap(), 1000);
+}
+
+#[test]
+fn test_profile() {
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+    write_padded_rows(&path);
+
+    let mut reader = ExcelReader::open(&path).unwrap();
+    let profile = reader.profile("Sheet1", false).unwrap();
+    assert_eq!(profile.rows, 1000);
+    assert_eq!(profile.columns[0].distinct_estimate, 1);
+    assert_eq!(profile.columns[1].types.dominant(), Some("string"));
+}
+
+#[test]
+fn test_row_batches() {
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+    write_padded_rows(&path);
+
+    let mut reader = ExcelReader::open(&path).unwrap();
+    let sizes: Vec<usize> = reader
+        .row_batches("Sheet1", 300)
+        .unwrap()
+        .map(|batch| batch.unwrap().len())
+        .collect();
+    assert_eq!(sizes, vec![300, 300, 300, 100]);
+    let last = reader.row_batches("Sheet1", 300).unwrap().last().unwrap();
+    assert_eq!(last.unwrap()[99].index, 999);
+    assert!(reader.row_batches("Sheet1", 0).is_err());
+}
+
+#[test]
+fn test_rows_with_style() {
+    use excelstream::types::CellStyle;
+
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+
+    {
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        writer.write_header_bold(["Item", "Status"]).unwrap();
+        writer
+            .write_row_styled(&[
+                (CellValue::String("Widget".to_string()), CellStyle::Default),
+                (
+                    CellValue::String("rejected".to_string()),
+                    CellStyle::HighlightRed,
+                ),
+            ])
+            .unwrap();
+        writer
+            .write_row_styled(&[(CellValue::Float(0.25), CellStyle::NumberPercentage)])
+            .unwrap();
+        writer.save().unwrap();
+    }
+
+    let mut reader = ExcelReader::open(&path).unwrap();
+    let rows: Vec<_> = reader
+        .rows_with_style("Sheet1")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert!(rows[0][0].format.bold);
+    assert!(!rows[1][0].format.bold);
+    assert_eq!(rows[1][1].format.fill_color.as_deref(), Some("FFFF0000"));
+    assert_eq!(rows[1][1].value, CellValue::String("rejected".to_string()));
+    assert_eq!(rows[2][0].format.num_fmt.as_deref(), Some("0%"));
+}
+
+#[test]
+fn test_text_columns_keep_leading_zeros() {
+    use excelstream::types::CellStyle;
+
+    let temp = NamedTempFile::new().unwrap();
+    let path = temp.path().to_string_lossy().to_string();
+
+    {
+        let mut writer = ExcelWriter::new(&path).unwrap();
+        writer.set_text_column(1);
+        writer.write_row(["Alice", "0012345"]).unwrap();
+        writer
+            .write_row_typed(&[
+                CellValue::String("Bob".to_string()),
+                CellValue::Int(84912345678),
+            ])
+            .unwrap();
+        writer
+            .write_row_styled(&[(CellValue::Float(1.5), CellStyle::TextFormat)])
+            .unwrap();
+        writer.save().unwrap();
+    }
+
+    let mut reader = ExcelReader::open(&path).unwrap();
+    let rows: Vec<_> = reader
+        .rows_with_style("Sheet1")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(rows[0][1].value, CellValue::String("0012345".to_string()));
+    assert_eq!(rows[0][1].format.num_fmt_id, 49);
+    assert_eq!(rows[0][0].format.num_fmt_id, 0);
+    assert_eq!(
+        rows[1][1].value,
+        CellValue::String("84912345678".to_string())
+    );
+    assert_eq!(rows[2][0].value, CellValue::String("1.5".to_string()));
+    assert_eq!(rows[2][0].format.num_fmt.as_deref(), Some("@"));
+}
+
 #[test]
 fn test_column_width() {
     let temp = NamedTempFile::new().unwrap();
@@ -435,19 +665,29 @@ fn test_column_width_must_be_set_before_rows() {
         // Write a row first
         writer.write_row(["Data"]).unwrap();
 
-        // Try to set column width after writing rows
-        // In v0.5.0 (UltraLowMemory), set_column_width is a no-op (not supported)
-        // So it returns Ok(()) instead of error
+        // Widths go into the sheet header, which is already written
         let result = writer.set_column_width(0, 20.0);
-
-        // v0.5.0: No-op, returns Ok
-        // v0.4.x: Would return Err
-        assert!(result.is_ok() || result.is_err());
+        assert!(matches!(result, Err(ExcelError::InvalidState(_))));
 
         writer.save().unwrap();
     }
 }
 
+#[test]
+fn test_column_width_must_be_in_range() {
+    let temp = NamedTempFile::new().unwrap();
+    let mut writer = ExcelWriter::new(temp.path()).unwrap();
+
+    for width in [-1.0, 255.5, f64::NAN, f64::INFINITY] {
+        let result = writer.set_column_width(0, width);
+        assert!(matches!(result, Err(ExcelError::InvalidState(_))));
+    }
+    writer.set_column_width(0, 0.0).unwrap();
+    writer.set_column_width(1, 255.0).unwrap();
+    writer.write_row(["Data"]).unwrap();
+    writer.save().unwrap();
+}
+
 #[test]
 fn test_multiple_sheets_with_column_widths() {
     let temp = NamedTempFile::new().unwrap();
@@ -477,3 +717,28 @@ fn test_multiple_sheets_with_column_widths() {
         assert!(sheets.len() >= 2);
     }
 }
+
+#[test]
+fn test_fixture_corpus_round_trip() {
+    use excelstream::fixtures;
+
+    for fixture in fixtures::corpus() {
+        let original = fixture.write_temp().unwrap();
+        let read = fixtures::read_workbook(original.path()).unwrap();
+        assert!(
+            read == fixture.expected(),
+            "reading fixture '{}' gave different values",
+            fixture.name()
+        );
+
+        // reader -> writer -> reader keeps sheet names and values
+        let copy = NamedTempFile::new().unwrap();
+        fixtures::rewrite(original.path(), copy.path()).unwrap();
+        let reread = fixtures::read_workbook(copy.path()).unwrap();
+        assert!(
+            reread == read,
+            "rewriting fixture '{}' changed its values",
+            fixture.name()
+        );
+    }
+}